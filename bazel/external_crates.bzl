@@ -439,6 +439,9 @@ def external_crates_repository(name, cargo_lockfile, lockfile, sanitizers_enable
                     "custom",
                 ],
             ),
+            "gimli": crate.spec(
+                version = "^0.28.0",
+            ),
             "glob": crate.spec(
                 version = "^0.3.0",
             ),
@@ -1,41 +1,68 @@
 use clap::{arg, Arg, Command};
 use ic_artifact_pool::{
     certification_pool::CertificationPoolImpl,
-    consensus_pool::{PoolSectionOps, UncachedConsensusPoolImpl},
+    consensus_pool::{PoolSectionOp, PoolSectionOps, UncachedConsensusPoolImpl},
+    replay_bundle::ReplayBundle,
 };
 use ic_config::artifact_pool::ArtifactPoolConfig;
 use ic_interfaces::consensus_pool::*;
 use ic_logger::{LoggerImpl, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
+use ic_protobuf::types::v1 as pb;
+use ic_registry_local_store::{changelog_to_compact_delta, LocalStoreImpl, LocalStoreReader};
 use ic_types::{
-    consensus::{certification::CertificationMessage, CatchUpPackage, ConsensusMessageHashable},
+    consensus::{
+        certification::CertificationMessage, CatchUpPackage, ConsensusMessage,
+        ConsensusMessageHashable, HasHeight,
+    },
     time::current_time,
-    NodeId, PrincipalId,
+    Height, NodeId, PrincipalId, RegistryVersion,
 };
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use serde_bytes_repr::{ByteFmtDeserializer, ByteFmtSerializer};
 use serde_json::{Deserializer, Serializer};
+use std::collections::BTreeMap;
 use std::convert::TryFrom;
 use std::io::BufRead;
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-fn main() {
-    let mut app = Command::new("ic-consensus-pool-util")
+fn height_range_args() -> [Arg<'static>; 2] {
+    [
+        Arg::new("min-height")
+            .long("min-height")
+            .value_name("HEIGHT")
+            .help("Only consider artifacts at or above this height")
+            .takes_value(true),
+        Arg::new("max-height")
+            .long("max-height")
+            .value_name("HEIGHT")
+            .help("Only consider artifacts at or below this height")
+            .takes_value(true),
+    ]
+}
+
+fn artifact_arg() -> Arg<'static> {
+    Arg::new("artifact")
+        .short('a')
+        .long("artifact")
+        .value_name("NAME")
+        .help("Artifact name")
+        .multiple_occurrences(true)
+        .multiple_values(true)
+        .takes_value(true)
+}
+
+fn app() -> Command<'static> {
+    Command::new("ic-consensus-pool-util")
         .version("0.1")
         .about("IC Consensus Pool Unitity")
         .subcommand(
-            Command::new("export").about("Export data to stdout").arg(
-                Arg::new("artifact")
-                    .short('a')
-                    .long("artifact")
-                    .value_name("NAME")
-                    .help("Artifact name")
-                    .multiple_occurrences(true)
-                    .multiple_values(true)
-                    .takes_value(true),
-            ),
+            Command::new("export")
+                .about("Export data to stdout")
+                .arg(artifact_arg())
+                .args(height_range_args()),
         )
         .subcommand(Command::new("import").about("Import data from stdin"))
         .subcommand(
@@ -51,7 +78,68 @@ fn main() {
                         .takes_value(true),
                 ),
         )
-        .arg(arg!(<PATH>       "PATH to the consensus pool directory"));
+        .subcommand(
+            Command::new("list")
+                .about("List validated consensus artifacts by height and type")
+                .arg(artifact_arg())
+                .args(height_range_args()),
+        )
+        .subcommand(
+            Command::new("usage")
+                .about("Report the number of artifacts and bytes used per height")
+                .args(height_range_args()),
+        )
+        .subcommand(
+            Command::new("prune")
+                .about("Remove all validated consensus artifacts below the given height. The replica must be stopped.")
+                .arg(
+                    Arg::new("below")
+                        .long("below")
+                        .value_name("HEIGHT")
+                        .help("Height below which all artifacts are removed")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("dry-run")
+                        .long("dry-run")
+                        .help("Only report what would be removed"),
+                ),
+        )
+        .subcommand(
+            Command::new("export-bundle")
+                .about("Export consensus artifacts and registry content into a bundle that can be replayed with ic-replay")
+                .args(height_range_args())
+                .arg(
+                    Arg::new("registry-local-store")
+                        .long("registry-local-store")
+                        .value_name("DIR")
+                        .help("Path to the registry local store of the node")
+                        .required(true)
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("unvalidated")
+                        .long("unvalidated")
+                        .value_name("FILE")
+                        .help("JSON file of unvalidated artifacts, in the format produced by export")
+                        .takes_value(true),
+                )
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Output filename")
+                        .required(true)
+                        .takes_value(true),
+                ),
+        )
+        .arg(arg!(<PATH>       "PATH to the consensus pool directory"))
+}
+
+fn main() {
+    let mut app = app();
     let mut help = Vec::new();
     app.write_help(&mut help)
         .expect("Unable to output help message");
@@ -65,6 +153,20 @@ fn main() {
         import(path)
     } else if let Some(matches) = matches.subcommand_matches("export-cup-proto") {
         export_cup_proto(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("list") {
+        list(path, matches)
+    } else if let Some(matches) = matches.subcommand_matches("usage") {
+        print!("{}", usage(path, matches))
+    } else if let Some(matches) = matches.subcommand_matches("prune") {
+        match prune(path, matches) {
+            Ok(report) => println!("{}", report),
+            Err(err) => {
+                eprintln!("{}", err);
+                std::process::exit(1);
+            }
+        }
+    } else if let Some(matches) = matches.subcommand_matches("export-bundle") {
+        export_bundle(path, matches)
     } else {
         eprintln!(
             "{}",
@@ -140,81 +242,85 @@ fn to_string<T: Serialize>(msg: &T) -> String {
     String::from_utf8(out).expect("UTF8 conversion error")
 }
 
-fn export(path: &str, matches: &clap::ArgMatches) {
-    let artifacts = match matches.values_of("artifact") {
+fn parse_height(matches: &clap::ArgMatches, name: &str) -> Option<Height> {
+    matches.value_of(name).map(|h| {
+        Height::from(
+            h.parse::<u64>()
+                .unwrap_or_else(|err| panic!("Invalid {} '{}': {}", name, h, err)),
+        )
+    })
+}
+
+fn parse_height_range(matches: &clap::ArgMatches) -> HeightRange {
+    HeightRange::new(
+        parse_height(matches, "min-height").unwrap_or_else(|| Height::from(0)),
+        parse_height(matches, "max-height").unwrap_or_else(|| Height::from(u64::MAX)),
+    )
+}
+
+fn parse_artifacts(matches: &clap::ArgMatches) -> Vec<&'static str> {
+    match matches.values_of("artifact") {
         Some(names) => parse_artifact_names(&names.collect::<Vec<&str>>()),
         None => ALL_ARTIFACT_NAMES.to_vec(),
-    };
+    }
+}
+
+/// Returns the validated consensus artifacts of the given type within the height range.
+/// Returns `None` for artifacts that are not stored in the consensus pool.
+fn consensus_artifacts(
+    pool: &dyn PoolSection<ValidatedConsensusArtifact>,
+    artifact: &str,
+    range: HeightRange,
+) -> Option<Box<dyn Iterator<Item = ConsensusMessage>>> {
+    fn messages<T: ConsensusMessageHashable + 'static>(
+        pool: &dyn HeightIndexedPool<T>,
+        range: HeightRange,
+    ) -> Box<dyn Iterator<Item = ConsensusMessage>> {
+        Box::new(pool.get_by_height_range(range).map(|x| x.into_message()))
+    }
+
+    Some(match artifact {
+        "RandomBeacon" => messages(pool.random_beacon(), range),
+        "Finalization" => messages(pool.finalization(), range),
+        "Notarization" => messages(pool.notarization(), range),
+        "BlockProposal" => messages(pool.block_proposal(), range),
+        "RandomBeaconShare" => messages(pool.random_beacon_share(), range),
+        "NotarizationShare" => messages(pool.notarization_share(), range),
+        "FinalizationShare" => messages(pool.finalization_share(), range),
+        "RandomTape" => messages(pool.random_tape(), range),
+        "RandomTapeShare" => messages(pool.random_tape_share(), range),
+        "CatchUpPackage" => messages(pool.catch_up_package(), range),
+        "CatchUpPackageShare" => messages(pool.catch_up_package_share(), range),
+        _ => return None,
+    })
+}
+
+/// Size of the artifact as stored in the persistent pool.
+fn encoded_size(msg: ConsensusMessage) -> usize {
+    pb::ConsensusMessage::from(msg).encoded_len()
+}
+
+fn export(path: &str, matches: &clap::ArgMatches) {
+    let artifacts = parse_artifacts(matches);
+    let range = parse_height_range(matches);
 
     let consensus_pool = open_consensus_pool(path, true);
     let certification_pool = open_certification_pool(path, true);
 
     for artifact in artifacts {
-        match artifact {
-            "RandomBeacon" => {
-                for x in consensus_pool.validated().random_beacon().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "Finalization" => {
-                for x in consensus_pool.validated().finalization().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "Notarization" => {
-                for x in consensus_pool.validated().notarization().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "BlockProposal" => {
-                for x in consensus_pool.validated().block_proposal().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "RandomBeaconShare" => {
-                for x in consensus_pool.validated().random_beacon_share().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "NotarizationShare" => {
-                for x in consensus_pool.validated().notarization_share().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "FinalizationShare" => {
-                for x in consensus_pool.validated().finalization_share().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "RandomTape" => {
-                for x in consensus_pool.validated().random_tape().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "RandomTapeShare" => {
-                for x in consensus_pool.validated().random_tape_share().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "CatchUpPackage" => {
-                for x in consensus_pool.validated().catch_up_package().get_all() {
-                    println!("{}", to_string(&x.into_message()));
-                }
-            }
-            "CatchUpPackageShare" => {
-                for x in consensus_pool
-                    .validated()
-                    .catch_up_package_share()
-                    .get_all()
-                {
-                    println!("{}", to_string(&x.into_message()));
-                }
+        if let Some(msgs) = consensus_artifacts(consensus_pool.validated(), artifact, range.clone())
+        {
+            for msg in msgs {
+                println!("{}", to_string(&msg));
             }
+            continue;
+        }
+        match artifact {
             "Certification" => {
                 for x in certification_pool
                     .persistent_pool
                     .certifications()
-                    .get_all()
+                    .get_by_height_range(range.clone())
                 {
                     println!("{}", to_string(&CertificationMessage::Certification(x)));
                 }
@@ -223,7 +329,7 @@ fn export(path: &str, matches: &clap::ArgMatches) {
                 for x in certification_pool
                     .persistent_pool
                     .certification_shares()
-                    .get_all()
+                    .get_by_height_range(range.clone())
                 {
                     println!(
                         "{}",
@@ -236,6 +342,129 @@ fn export(path: &str, matches: &clap::ArgMatches) {
     }
 }
 
+fn list(path: &str, matches: &clap::ArgMatches) {
+    let artifacts = parse_artifacts(matches);
+    let range = parse_height_range(matches);
+    let consensus_pool = open_consensus_pool(path, true);
+
+    let mut entries = Vec::new();
+    for artifact in artifacts {
+        if let Some(msgs) = consensus_artifacts(consensus_pool.validated(), artifact, range.clone())
+        {
+            for msg in msgs {
+                let id = msg.get_id();
+                entries.push((id.height, artifact, id.hash, encoded_size(msg)));
+            }
+        }
+    }
+    entries.sort_by_key(|(height, artifact, _, _)| (*height, *artifact));
+
+    println!("height\ttype\thash\tbytes");
+    for (height, artifact, hash, size) in entries {
+        println!("{}\t{}\t{:?}\t{}", height, artifact, hash.digest(), size);
+    }
+}
+
+/// Returns a tab separated report of the number of artifacts and bytes per height.
+fn usage(path: &str, matches: &clap::ArgMatches) -> String {
+    let range = parse_height_range(matches);
+    let consensus_pool = open_consensus_pool(path, true);
+
+    // Height -> artifact type -> (count, bytes)
+    let mut usage: BTreeMap<Height, BTreeMap<&str, (usize, usize)>> = BTreeMap::new();
+    for artifact in ALL_ARTIFACT_NAMES {
+        if let Some(msgs) = consensus_artifacts(consensus_pool.validated(), artifact, range.clone())
+        {
+            for msg in msgs {
+                let height = msg.height();
+                let entry = usage
+                    .entry(height)
+                    .or_default()
+                    .entry(artifact)
+                    .or_default();
+                entry.0 += 1;
+                entry.1 += encoded_size(msg);
+            }
+        }
+    }
+
+    let (mut total_count, mut total_bytes) = (0, 0);
+    let mut report = String::from("height\tcount\tbytes\tper_type\n");
+    for (height, per_type) in usage {
+        let count: usize = per_type.values().map(|(count, _)| count).sum();
+        let bytes: usize = per_type.values().map(|(_, bytes)| bytes).sum();
+        let breakdown = per_type
+            .iter()
+            .map(|(artifact, (_, bytes))| format!("{}={}", artifact, bytes))
+            .collect::<Vec<_>>()
+            .join(",");
+        report.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            height, count, bytes, breakdown
+        ));
+        total_count += count;
+        total_bytes += bytes;
+    }
+    report.push_str(&format!("total\t{}\t{}\n", total_count, total_bytes));
+    report
+}
+
+/// Removes the validated artifacts below the given height and returns a report of
+/// what was (or, for a dry run, would be) removed.
+fn prune(path: &str, matches: &clap::ArgMatches) -> Result<String, String> {
+    let below = parse_height(matches, "below").expect("Missing height to prune below");
+    let dry_run = matches.is_present("dry-run");
+
+    let mut consensus_pool = open_consensus_pool(path, dry_run);
+    // The highest CUP is required to restart the replica. Never remove it.
+    let cup_height = consensus_pool
+        .validated()
+        .catch_up_package()
+        .max_height()
+        .ok_or_else(|| {
+            format!(
+                "Refusing to prune the consensus pool at {} which does not contain a CatchUpPackage",
+                path
+            )
+        })?;
+    if below > cup_height {
+        return Err(format!(
+            "Refusing to prune below height {} which is above the highest CatchUpPackage at height {}",
+            below, cup_height
+        ));
+    }
+
+    let range = HeightRange::new(Height::from(0), below.decrement());
+    let (mut count, mut bytes) = (0, 0);
+    if below > Height::from(0) {
+        for artifact in ALL_ARTIFACT_NAMES {
+            if let Some(msgs) =
+                consensus_artifacts(consensus_pool.validated(), artifact, range.clone())
+            {
+                for msg in msgs {
+                    count += 1;
+                    bytes += encoded_size(msg);
+                }
+            }
+        }
+    }
+
+    if dry_run {
+        return Ok(format!(
+            "Would remove {} artifacts ({} bytes) below height {}",
+            count, bytes, below
+        ));
+    }
+
+    consensus_pool.validated.mutate(PoolSectionOps {
+        ops: vec![PoolSectionOp::PurgeBelow(below)],
+    });
+    Ok(format!(
+        "Removed {} artifacts ({} bytes) below height {}",
+        count, bytes, below
+    ))
+}
+
 fn import(path: &str) {
     let mut consensus_pool = open_consensus_pool(path, false);
     let certification_pool = open_certification_pool(path, false);
@@ -274,3 +503,240 @@ fn export_cup_proto(path: &str, matches: &clap::ArgMatches) {
     file.write_all(&buf)
         .unwrap_or_else(|err| panic!("Cannot write to file {}: {:?}", filename, err));
 }
+
+fn export_bundle(path: &str, matches: &clap::ArgMatches) {
+    let range = parse_height_range(matches);
+    let filename = matches
+        .value_of("output")
+        .expect("Expect an output filename");
+    let local_store_path = matches
+        .value_of("registry-local-store")
+        .expect("Missing path to the registry local store");
+
+    let consensus_pool = open_consensus_pool(path, true);
+    let validated = consensus_pool.validated();
+
+    // The replay has to start from a CUP, so we start the export from the highest
+    // CUP at or below the requested minimum height.
+    let cup = validated
+        .catch_up_package()
+        .get_by_height_range(HeightRange::new(Height::from(0), range.min))
+        .last()
+        .unwrap_or_else(|| {
+            eprintln!("No CatchUpPackage found at or below height {}", range.min);
+            std::process::exit(1);
+        });
+    // Use the protobuf as stored in the pool, because re-encoding the CUP might
+    // invalidate its signature.
+    let cup_proto = validated
+        .catch_up_package_proto_at(cup.height())
+        .expect("The CUP was just found in the pool");
+    if cup.height() < range.min {
+        println!(
+            "Starting export at the CatchUpPackage height {} instead of {}",
+            cup.height(),
+            range.min
+        );
+    }
+    let range = HeightRange::new(cup.height(), range.max);
+
+    // Other CUPs are left out, since the bundle only carries the original bytes of
+    // the CUP the replay starts from.
+    let mut validated_msgs = Vec::new();
+    for artifact in ALL_ARTIFACT_NAMES {
+        if artifact == "CatchUpPackage" {
+            continue;
+        }
+        if let Some(msgs) = consensus_artifacts(validated, artifact, range.clone()) {
+            validated_msgs.extend(msgs);
+        }
+    }
+
+    let mut unvalidated_msgs = Vec::new();
+    if let Some(file) = matches.value_of("unvalidated") {
+        let file = std::fs::File::open(file)
+            .unwrap_or_else(|err| panic!("Cannot open file {} for read: {:?}", file, err));
+        for line in std::io::BufReader::new(file).lines() {
+            let s = line.expect("Cannot read input");
+            let msg: ConsensusMessage =
+                from_str(&s).unwrap_or_else(|err| panic!("Failed to parse JSON {}: {:?}", s, err));
+            let height = msg.height();
+            let is_cup = matches!(msg, ConsensusMessage::CatchUpPackage(_));
+            if !is_cup && range.min <= height && height <= range.max {
+                unvalidated_msgs.push(msg);
+            }
+        }
+    }
+
+    let max_height = validated_msgs
+        .iter()
+        .chain(unvalidated_msgs.iter())
+        .map(|msg| msg.height())
+        .max()
+        .unwrap_or(range.min);
+
+    // Include the registry up to the highest version any of the exported blocks refers to.
+    let registry_version = validated_msgs
+        .iter()
+        .chain(unvalidated_msgs.iter())
+        .filter_map(|msg| match msg {
+            ConsensusMessage::BlockProposal(proposal) => {
+                Some(proposal.content.as_ref().context.registry_version)
+            }
+            _ => None,
+        })
+        .chain(std::iter::once(
+            cup.content.block.as_ref().context.registry_version,
+        ))
+        .max()
+        .expect("At least the CUP refers to a registry version");
+    let mut changelog = LocalStoreImpl::new(local_store_path)
+        .get_changelog_since_version(RegistryVersion::from(0))
+        .unwrap_or_else(|err| panic!("Cannot read the registry local store: {:?}", err));
+    if (changelog.len() as u64) < registry_version.get() {
+        eprintln!(
+            "The registry local store only contains {} versions, but version {} is required",
+            changelog.len(),
+            registry_version
+        );
+        std::process::exit(1);
+    }
+    changelog.truncate(registry_version.get() as usize);
+    let registry_delta = changelog_to_compact_delta(RegistryVersion::from(0), changelog)
+        .unwrap_or_else(|err| panic!("Cannot encode the registry changelog: {:?}", err));
+
+    println!(
+        "Exporting {} validated and {} unvalidated artifacts from height {} to {} and registry version {}",
+        validated_msgs.len(),
+        unvalidated_msgs.len(),
+        range.min,
+        max_height,
+        registry_version
+    );
+    ReplayBundle::new(
+        range.min,
+        max_height,
+        &cup_proto,
+        validated_msgs,
+        unvalidated_msgs,
+        registry_delta,
+    )
+    .write_to_file(Path::new(filename))
+    .unwrap_or_else(|err| panic!("Cannot write to file {}: {:?}", filename, err));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_consensus::{fake::*, make_genesis};
+    use ic_types::{
+        consensus::{dkg::Summary, RandomBeacon, RandomBeaconContent},
+        crypto::{CryptoHash, CryptoHashOf},
+        time::UNIX_EPOCH,
+    };
+    use tempfile::TempDir;
+
+    /// Creates a consensus pool with a random beacon at every height up to `max_height`
+    /// and, if given, a CatchUpPackage at `cup_height`.
+    fn make_pool(max_height: u64, cup_height: Option<u64>) -> TempDir {
+        let dir = tempfile::Builder::new()
+            .prefix("consensus_pool_util")
+            .tempdir()
+            .unwrap();
+        let mut pool = open_consensus_pool(dir.path().to_str().unwrap(), false);
+        let mut ops = PoolSectionOps::new();
+        for height in 1..=max_height {
+            let random_beacon = RandomBeacon::fake(RandomBeaconContent::new(
+                Height::from(height),
+                CryptoHashOf::from(CryptoHash(Vec::new())),
+            ));
+            ops.insert(ValidatedConsensusArtifact {
+                msg: random_beacon.into_message(),
+                timestamp: UNIX_EPOCH,
+            });
+        }
+        if let Some(cup_height) = cup_height {
+            let mut summary = Summary::fake();
+            summary.height = Height::from(cup_height);
+            ops.insert(ValidatedConsensusArtifact {
+                msg: make_genesis(summary).into_message(),
+                timestamp: UNIX_EPOCH,
+            });
+        }
+        pool.validated.mutate(ops);
+        dir
+    }
+
+    fn run_prune(dir: &TempDir, args: &[&str]) -> Result<String, String> {
+        let path = dir.path().to_str().unwrap();
+        let args = [&["ic-consensus-pool-util", path, "prune"][..], args].concat();
+        let matches = app().get_matches_from(args);
+        prune(path, matches.subcommand_matches("prune").unwrap())
+    }
+
+    fn run_usage(dir: &TempDir) -> String {
+        let path = dir.path().to_str().unwrap();
+        let matches = app().get_matches_from(["ic-consensus-pool-util", path, "usage"]);
+        usage(path, matches.subcommand_matches("usage").unwrap())
+    }
+
+    #[test]
+    fn prune_rejects_empty_pool() {
+        let dir = make_pool(0, None);
+        for args in [&["--below", "1"][..], &["--below", "1", "--dry-run"]] {
+            let err = run_prune(&dir, args).unwrap_err();
+            assert!(err.contains("does not contain a CatchUpPackage"), "{}", err);
+        }
+    }
+
+    #[test]
+    fn prune_rejects_height_above_highest_catch_up_package() {
+        let dir = make_pool(10, Some(5));
+        let err = run_prune(&dir, &["--below", "6"]).unwrap_err();
+        assert!(err.contains("above the highest CatchUpPackage"), "{}", err);
+        assert!(run_usage(&dir).contains("\ntotal\t11\t"));
+    }
+
+    #[test]
+    fn prune_removes_artifacts_below_height() {
+        let dir = make_pool(10, Some(5));
+
+        let report = run_prune(&dir, &["--below", "5", "--dry-run"]).unwrap();
+        assert!(report.starts_with("Would remove 4 artifacts"), "{}", report);
+        assert!(run_usage(&dir).contains("\ntotal\t11\t"));
+
+        let report = run_prune(&dir, &["--below", "5"]).unwrap();
+        assert!(report.starts_with("Removed 4 artifacts"), "{}", report);
+        let report = run_usage(&dir);
+        let lines: Vec<_> = report.lines().collect();
+        assert!(lines[1].starts_with("5\t2\t"), "{}", report);
+        assert!(
+            lines.last().unwrap().starts_with("total\t7\t"),
+            "{}",
+            report
+        );
+    }
+
+    #[test]
+    fn usage_reports_artifacts_per_height() {
+        let dir = make_pool(3, Some(3));
+        let report = run_usage(&dir);
+        let lines: Vec<_> = report.lines().collect();
+        assert_eq!(lines.len(), 5, "{}", report);
+        assert_eq!(lines[0], "height\tcount\tbytes\tper_type");
+        assert!(lines[1].starts_with("1\t1\t"), "{}", report);
+        assert!(lines[2].starts_with("2\t1\t"), "{}", report);
+        assert!(lines[3].starts_with("3\t2\t"), "{}", report);
+        assert!(lines[3].contains("CatchUpPackage="), "{}", report);
+        assert!(lines[4].starts_with("total\t4\t"), "{}", report);
+    }
+
+    #[test]
+    fn usage_of_empty_pool() {
+        let dir = make_pool(0, None);
+        assert_eq!(
+            run_usage(&dir),
+            "height\tcount\tbytes\tper_type\ntotal\t0\t0\n"
+        );
+    }
+}
@@ -1332,6 +1332,15 @@ impl PoolSection<ValidatedConsensusArtifact> for PersistentHeightIndexedPool<Con
             .catch_up_package()
             .max_height()
             .expect("There should always be a CUP in the pool.");
+        self.catch_up_package_proto_at(h).unwrap_or_else(|| {
+            panic!(
+                "This should be impossible since we found a max height at {:?}",
+                h
+            )
+        })
+    }
+
+    fn catch_up_package_proto_at(&self, h: Height) -> Option<pb::CatchUpPackage> {
         let key = HeightKey::from(h);
         let index_db = self.get_index_db(&CatchUpPackage::type_key());
         let log = self.log.clone();
@@ -1360,12 +1369,6 @@ impl PoolSection<ValidatedConsensusArtifact> for PersistentHeightIndexedPool<Con
             self.log.clone(),
         )
         .next()
-        .unwrap_or_else(|| {
-            panic!(
-                "This should be impossible since we found a max height at {:?}",
-                h
-            )
-        })
     }
 
     /// Number of artifacts in the DB.
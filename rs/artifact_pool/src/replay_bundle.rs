@@ -0,0 +1,179 @@
+//! A replay bundle captures what a single node saw in a range of heights, so
+//! that its consensus decisions can be reproduced offline.
+//!
+//! The bundle contains the CatchUpPackage the replay starts from, the
+//! validated and unvalidated consensus artifacts in the exported height range
+//! and the registry changelog up to the highest registry version referenced by
+//! these artifacts. It carries no replicated state: The finalized blocks are
+//! executed on top of the checkpoint at the height of the CUP, which has to be
+//! restored separately. It is written by `ic-consensus-pool-util export-bundle`
+//! and consumed by `ic-replay replay-bundle`.
+//!
+//! All artifacts are stored as encoded protobufs. The CUP is stored exactly as
+//! it was found in the pool, since re-encoding it might invalidate its
+//! signature. For the same reason, no other CUPs are part of the bundle. The
+//! registry changelog is stored as a compact delta, see
+//! `ic_registry_local_store::changelog_to_compact_delta`.
+
+use ic_protobuf::types::v1 as pb;
+use ic_types::{consensus::ConsensusMessage, Height};
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind, Result},
+    path::Path,
+};
+
+/// Version of the bundle format. Bundles of other versions are rejected.
+const BUNDLE_FORMAT_VERSION: u32 = 1;
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
+pub struct ReplayBundle {
+    format_version: u32,
+    /// Lowest height of the exported artifacts.
+    pub min_height: Height,
+    /// Highest height of the exported artifacts.
+    pub max_height: Height,
+    cup: Vec<u8>,
+    validated: Vec<Vec<u8>>,
+    unvalidated: Vec<Vec<u8>>,
+    /// Registry changelog as compact delta.
+    pub registry_delta: Vec<u8>,
+}
+
+impl ReplayBundle {
+    /// Creates a bundle starting from the given CUP. Any other CUPs among the
+    /// given artifacts are dropped.
+    pub fn new(
+        min_height: Height,
+        max_height: Height,
+        cup: &pb::CatchUpPackage,
+        validated: Vec<ConsensusMessage>,
+        unvalidated: Vec<ConsensusMessage>,
+        registry_delta: Vec<u8>,
+    ) -> Self {
+        Self {
+            format_version: BUNDLE_FORMAT_VERSION,
+            min_height,
+            max_height,
+            cup: cup.encode_to_vec(),
+            validated: encode_messages(validated),
+            unvalidated: encode_messages(unvalidated),
+            registry_delta,
+        }
+    }
+
+    /// The original protobuf of the CUP the replay starts from.
+    pub fn cup_proto(&self) -> Result<pb::CatchUpPackage> {
+        pb::CatchUpPackage::decode(self.cup.as_slice()).map_err(invalid_data)
+    }
+
+    /// Artifacts that were in the validated section of the pool.
+    pub fn validated(&self) -> Result<Vec<ConsensusMessage>> {
+        self.validated.iter().map(|b| decode_message(b)).collect()
+    }
+
+    /// Artifacts that were not (yet) validated by the node.
+    pub fn unvalidated(&self) -> Result<Vec<ConsensusMessage>> {
+        self.unvalidated.iter().map(|b| decode_message(b)).collect()
+    }
+
+    pub fn write_to_file(&self, path: &Path) -> Result<()> {
+        let bytes = bincode::serialize(self).map_err(invalid_data)?;
+        std::fs::write(path, bytes)
+    }
+
+    pub fn read_from_file(path: &Path) -> Result<Self> {
+        let bytes = std::fs::read(path)?;
+        let bundle: Self = bincode::deserialize(&bytes).map_err(invalid_data)?;
+        if bundle.format_version != BUNDLE_FORMAT_VERSION {
+            return Err(invalid_data(format!(
+                "Unsupported bundle format version {}, expected {}",
+                bundle.format_version, BUNDLE_FORMAT_VERSION
+            )));
+        }
+        Ok(bundle)
+    }
+}
+
+fn encode_messages(msgs: Vec<ConsensusMessage>) -> Vec<Vec<u8>> {
+    msgs.into_iter()
+        .filter(|msg| !matches!(msg, ConsensusMessage::CatchUpPackage(_)))
+        .map(|msg| pb::ConsensusMessage::from(msg).encode_to_vec())
+        .collect()
+}
+
+fn decode_message(bytes: &[u8]) -> Result<ConsensusMessage> {
+    let proto = pb::ConsensusMessage::decode(bytes).map_err(invalid_data)?;
+    ConsensusMessage::try_from(proto).map_err(invalid_data)
+}
+
+fn invalid_data<E: std::fmt::Debug>(err: E) -> Error {
+    Error::new(ErrorKind::InvalidData, format!("{:?}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::make_summary;
+    use ic_test_utilities_consensus::{fake::*, make_genesis};
+    use ic_types::{
+        consensus::*,
+        crypto::{CryptoHash, CryptoHashOf},
+    };
+
+    #[test]
+    fn test_bundle_round_trip() {
+        let finalization = Finalization::fake(FinalizationContent::new(
+            Height::from(22),
+            CryptoHashOf::from(CryptoHash(vec![1, 2, 3])),
+        ))
+        .into_message();
+        let notarization = Notarization::fake(NotarizationContent::new(
+            Height::from(23),
+            CryptoHashOf::from(CryptoHash(vec![4, 5, 6])),
+        ))
+        .into_message();
+        let cup = pb::CatchUpPackage {
+            content: vec![7, 8, 9],
+            ..Default::default()
+        };
+        let bundle = ReplayBundle::new(
+            Height::from(20),
+            Height::from(23),
+            &cup,
+            vec![finalization.clone()],
+            vec![notarization.clone()],
+            vec![10, 11],
+        );
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("bundle.bin");
+        bundle.write_to_file(&path).unwrap();
+        let read = ReplayBundle::read_from_file(&path).unwrap();
+
+        assert_eq!(read, bundle);
+        assert_eq!(read.cup_proto().unwrap(), cup);
+        assert_eq!(read.validated().unwrap(), vec![finalization]);
+        assert_eq!(read.unvalidated().unwrap(), vec![notarization]);
+        assert_eq!(read.registry_delta, vec![10, 11]);
+    }
+
+    #[test]
+    fn test_bundle_drops_other_cups() {
+        let start_cup = make_genesis(make_summary(Height::from(10)));
+        let later_cup = make_genesis(make_summary(Height::from(20))).into_message();
+        let bundle = ReplayBundle::new(
+            Height::from(10),
+            Height::from(20),
+            &pb::CatchUpPackage::from(&start_cup),
+            vec![later_cup.clone()],
+            vec![later_cup],
+            vec![],
+        );
+
+        assert!(bundle.validated().unwrap().is_empty());
+        assert!(bundle.unvalidated().unwrap().is_empty());
+    }
+}
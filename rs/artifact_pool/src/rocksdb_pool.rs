@@ -651,8 +651,13 @@ impl PoolSection<ValidatedConsensusArtifact> for PersistentHeightIndexedPool<Con
 
     fn highest_catch_up_package_proto(&self) -> pb::CatchUpPackage {
         let height_opt = self.max_height::<CatchUpPackage>().unwrap();
-        let min_height_key = make_min_key(height_opt.get());
-        let max_height_key = make_max_key(height_opt.get());
+        self.catch_up_package_proto_at(height_opt)
+            .expect("There must be a catch up package in the pool")
+    }
+
+    fn catch_up_package_proto_at(&self, height: Height) -> Option<pb::CatchUpPackage> {
+        let min_height_key = make_min_key(height.get());
+        let max_height_key = make_max_key(height.get());
         let mut iter = check_ok_uw!(StandaloneIterator::new(
             self.db.clone(),
             CatchUpPackage::info().name,
@@ -660,9 +665,7 @@ impl PoolSection<ValidatedConsensusArtifact> for PersistentHeightIndexedPool<Con
             &max_height_key,
             deserialize_catch_up_package_fn
         ));
-        iter.next()
-            .expect("There must be a catch up package in the pool")
-            .msg
+        iter.next().map(|artifact| artifact.msg)
     }
 
     // TODO(CON-308): Implement size()
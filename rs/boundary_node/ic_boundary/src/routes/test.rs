@@ -518,6 +518,8 @@ async fn test_all_call_types() -> Result<(), Error> {
             sender: Blob(sender.as_slice().to_vec()),
             nonce: None,
             ingress_expiry: 1234,
+            tip: None,
+            trace: None,
         },
     };
 
@@ -206,6 +206,8 @@ pub fn prepare_update<S: ToString>(
             nonce: Some(Blob(nonce)),
             sender: sender_field,
             ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
+            tip: None,
+            trace: None,
         },
     };
 
@@ -383,6 +385,8 @@ mod tests {
                     .into_vec(),
                 ),
                 ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                tip: None,
+                trace: None,
             },
         };
         let sender = Sender::from_keypair(&keypair);
@@ -425,6 +429,8 @@ mod tests {
                 nonce: None,
                 sender: Blob(sender_id.get().into_vec()),
                 ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                tip: None,
+                trace: None,
             },
         };
         let sender =
@@ -462,6 +468,8 @@ mod tests {
                 nonce: None,
                 sender: Blob(UserId::from(PrincipalId::new_anonymous()).get().into_vec()),
                 ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                tip: None,
+                trace: None,
             },
         };
         let (submit, id) = sign_submit(content.clone(), &Sender::Anonymous).unwrap();
@@ -143,10 +143,13 @@ impl LauncherServer {
                             .map(|x| x.panic_on_failure)
                             .unwrap_or(true);
                         if should_panic {
-                            // If we have a canister id, tell the replica process to print its history.
-                            if let Some(canister_id) = process_info.and_then(|x| x.canister_id) {
+                            // Tell the replica process to print the history of
+                            // the canister that the sandbox was assigned to.
+                            if process_info.is_some() {
                                 controller
-                                    .sandbox_exited(SandboxExitedRequest { canister_id })
+                                    .sandbox_exited(SandboxExitedRequest {
+                                        pid: pid.as_raw() as u32,
+                                    })
                                     .sync()
                                     .unwrap();
                             }
@@ -192,7 +195,7 @@ impl LauncherService for LauncherServer {
                 info_map.insert(
                     Pid::from_raw(pid as i32),
                     ProcessInfo {
-                        canister_id: Some(canister_id),
+                        canister_id,
                         panic_on_failure: true,
                     },
                 );
@@ -13,7 +13,8 @@ use crate::fdenum::EnumerateInnerFileDescriptors;
 pub struct LaunchSandboxRequest {
     pub sandbox_exec_path: String,
     pub argv: Vec<String>,
-    pub canister_id: CanisterId,
+    /// `None` for pre-forked sandboxes that are not yet assigned to a canister.
+    pub canister_id: Option<CanisterId>,
     pub socket: RawFd,
 }
 
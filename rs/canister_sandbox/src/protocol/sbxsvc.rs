@@ -73,6 +73,9 @@ pub struct OpenWasmSerializedRequest {
     #[serde(serialize_with = "ic_utils::serde_arc::serialize_arc")]
     #[serde(deserialize_with = "ic_utils::serde_arc::deserialize_arc")]
     pub serialized_module: Arc<SerializedModuleBytes>,
+
+    /// Whether the module has passive data segments.
+    pub has_passive_data_segments: bool,
 }
 
 /// Reply to an `OpenWasmRequest`.
@@ -77,7 +77,7 @@ pub fn spawn_launcher_process(
 pub fn spawn_canister_sandbox_process(
     exec_path: &str,
     argv: &[String],
-    canister_id: CanisterId,
+    canister_id: Option<CanisterId>,
     controller_service: Arc<super::controller_service_impl::ControllerServiceImpl>,
     launcher: &dyn LauncherService,
 ) -> std::io::Result<(Arc<dyn SandboxService>, u32, std::thread::JoinHandle<()>)> {
@@ -131,15 +131,18 @@ pub fn spawn_canister_sandbox_process(
     Ok((svc, pid, thread_handle))
 }
 
-/// Spawns a sandbox process for the given canister.
+/// Spawns a sandbox process for the given canister, or a pre-forked sandbox
+/// process that is not yet assigned to any canister if `canister_id` is `None`.
 pub fn create_sandbox_process(
     controller_service: Arc<super::controller_service_impl::ControllerServiceImpl>,
     launcher_service: &dyn LauncherService,
-    canister_id: CanisterId,
+    canister_id: Option<CanisterId>,
     mut argv: Vec<String>,
 ) -> std::io::Result<(Arc<dyn SandboxService>, u32)> {
     assert!(!argv.is_empty());
-    argv.push(canister_id.to_string());
+    if let Some(canister_id) = canister_id {
+        argv.push(canister_id.to_string());
+    }
 
     let (sandbox_handle, pid, _recv_thread_handle) = spawn_canister_sandbox_process(
         &argv[0],
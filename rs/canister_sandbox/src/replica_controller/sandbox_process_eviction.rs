@@ -6,6 +6,8 @@ use ic_types::CanisterId;
 pub(crate) struct EvictionCandidate {
     pub id: CanisterId,
     pub last_used: Instant,
+    /// The recent usage of the candidate, see `SandboxProcessStats`.
+    pub activity: u64,
 }
 
 /// Evicts the least recently used candidates in order to bring the number of
@@ -15,8 +17,14 @@ pub(crate) struct EvictionCandidate {
 /// time (`last_used_threshold`) while keeping the number of the remaining
 /// candidates at or above `min_count_threshold`.
 ///
+/// Among the candidates that are not idle, the least active ones are evicted
+/// first, so that a frequently used candidate survives a burst of candidates
+/// that were used only once.
+///
 /// More formally:
-/// 1. Sort the candidates in the order of increasing `last_used` field.
+/// 1. Sort the candidates such that the idle candidates come first in the
+///    order of increasing `last_used` field, followed by the other candidates
+///    in the order of increasing `activity` and then `last_used` fields.
 /// 2. Let `N` be the total number of candidates.
 /// 3. Evict the first `K` candidates such that the number of remaining
 ///    candidates `N-K` is between the given thresholds:
@@ -30,7 +38,11 @@ pub(crate) fn evict(
     max_count_threshold: usize,
     last_used_threshold: Instant,
 ) -> Vec<EvictionCandidate> {
-    candidates.sort_by_key(|x| x.last_used);
+    candidates.sort_by_key(|x| {
+        let idle = x.last_used < last_used_threshold;
+        let activity = if idle { 0 } else { x.activity };
+        (!idle, activity, x.last_used)
+    });
 
     let evict_at_least = candidates.len().saturating_sub(max_count_threshold);
     let evict_at_most = candidates.len().saturating_sub(min_count_threshold);
@@ -76,6 +88,7 @@ mod tests {
             candidates.push(EvictionCandidate {
                 id: canister_test_id(i),
                 last_used: now,
+                activity: 0,
             });
         }
         assert_eq!(evict(candidates, 0, 10, now,), vec![],);
@@ -89,6 +102,7 @@ mod tests {
             candidates.push(EvictionCandidate {
                 id: canister_test_id(i),
                 last_used: now + Duration::from_secs(100 - i),
+                activity: 0,
             });
         }
         assert_eq!(
@@ -105,6 +119,7 @@ mod tests {
             candidates.push(EvictionCandidate {
                 id: canister_test_id(i),
                 last_used: now - Duration::from_secs(i),
+                activity: 0,
             });
         }
         assert_eq!(
@@ -121,6 +136,7 @@ mod tests {
             candidates.push(EvictionCandidate {
                 id: canister_test_id(i),
                 last_used: now - Duration::from_secs(i + 1),
+                activity: 0,
             });
         }
         assert_eq!(
@@ -137,8 +153,54 @@ mod tests {
             candidates.push(EvictionCandidate {
                 id: canister_test_id(i),
                 last_used: now - Duration::from_secs(i + 1),
+                activity: 0,
             });
         }
         assert_eq!(evict(candidates.clone(), 0, 100, now).len(), 100);
     }
+
+    #[test]
+    fn evict_least_active_first() {
+        let now = Instant::now();
+        let frequently_used = EvictionCandidate {
+            id: canister_test_id(0),
+            last_used: now,
+            activity: 100,
+        };
+        let mut candidates = vec![frequently_used.clone()];
+        for i in 1..10 {
+            candidates.push(EvictionCandidate {
+                id: canister_test_id(i),
+                last_used: now + Duration::from_secs(i),
+                activity: 1,
+            });
+        }
+        let evicted = evict(candidates.clone(), 0, 5, now);
+        assert_eq!(evicted, candidates[1..6].to_vec());
+        assert!(!evicted.contains(&frequently_used));
+    }
+
+    #[test]
+    fn evict_idle_before_active() {
+        let now = Instant::now();
+        let idle = EvictionCandidate {
+            id: canister_test_id(0),
+            last_used: now - Duration::from_secs(100),
+            activity: 100,
+        };
+        let active = EvictionCandidate {
+            id: canister_test_id(1),
+            last_used: now,
+            activity: 1,
+        };
+        assert_eq!(
+            evict(
+                vec![active, idle.clone()],
+                0,
+                1,
+                now - Duration::from_secs(10)
+            ),
+            vec![idle]
+        );
+    }
 }
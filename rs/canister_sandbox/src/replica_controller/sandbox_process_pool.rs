@@ -0,0 +1,152 @@
+use std::sync::{Condvar, Mutex};
+
+struct PoolState<P> {
+    idle: Vec<P>,
+    shutdown: bool,
+}
+
+/// A pool of idle sandbox processes that are spawned ahead of time and are
+/// not yet assigned to any canister.
+///
+/// Taking a process out of the pool wakes up the refill loop (see
+/// `run_refill()`), which spawns processes until the pool is back at its
+/// target size. Spawning happens without holding the lock, so that taking a
+/// process never waits for a process to be spawned.
+pub(crate) struct SandboxProcessPool<P> {
+    state: Mutex<PoolState<P>>,
+    refill: Condvar,
+    target_size: usize,
+}
+
+impl<P> SandboxProcessPool<P> {
+    pub(crate) fn new(target_size: usize) -> Self {
+        Self {
+            state: Mutex::new(PoolState {
+                idle: Vec::with_capacity(target_size),
+                shutdown: false,
+            }),
+            refill: Condvar::new(),
+            target_size,
+        }
+    }
+
+    /// Takes an idle process out of the pool, if there is one.
+    pub(crate) fn take(&self) -> Option<P> {
+        let process = self.state.lock().unwrap().idle.pop();
+        if process.is_some() {
+            self.refill.notify_one();
+        }
+        process
+    }
+
+    /// Returns the number of idle processes in the pool.
+    pub(crate) fn len(&self) -> usize {
+        self.state.lock().unwrap().idle.len()
+    }
+
+    /// Keeps the pool at its target size by spawning processes with the given
+    /// function, blocking until `shutdown()` is called. Returns the first error
+    /// of the spawn function, unless the pool has been shut down meanwhile.
+    pub(crate) fn run_refill<E>(&self, mut spawn: impl FnMut() -> Result<P, E>) -> Result<(), E> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                while !state.shutdown && state.idle.len() >= self.target_size {
+                    state = self.refill.wait(state).unwrap();
+                }
+                if state.shutdown {
+                    return Ok(());
+                }
+            }
+
+            let process = match spawn() {
+                Ok(process) => process,
+                Err(_) if self.state.lock().unwrap().shutdown => return Ok(()),
+                Err(err) => return Err(err),
+            };
+
+            let mut state = self.state.lock().unwrap();
+            if state.shutdown {
+                // Drop the process outside of the lock.
+                drop(state);
+                drop(process);
+                return Ok(());
+            }
+            state.idle.push(process);
+        }
+    }
+
+    /// Drops all idle processes and stops the refill loop.
+    pub(crate) fn shutdown(&self) {
+        let idle = {
+            let mut state = self.state.lock().unwrap();
+            state.shutdown = true;
+            std::mem::take(&mut state.idle)
+        };
+        self.refill.notify_all();
+        drop(idle);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use super::SandboxProcessPool;
+
+    fn wait_for_len(pool: &SandboxProcessPool<u32>, len: usize) {
+        while pool.len() != len {
+            thread::sleep(Duration::from_millis(1));
+        }
+    }
+
+    #[test]
+    fn take_from_empty_pool() {
+        let pool = SandboxProcessPool::<u32>::new(2);
+        assert_eq!(pool.take(), None);
+    }
+
+    #[test]
+    fn pool_is_refilled_after_take() {
+        let pool = Arc::new(SandboxProcessPool::new(2));
+        let pool_copy = Arc::clone(&pool);
+        let refill = thread::spawn(move || {
+            let mut next = 0;
+            pool_copy.run_refill(|| -> Result<u32, ()> {
+                next += 1;
+                Ok(next)
+            })
+        });
+
+        wait_for_len(&pool, 2);
+        assert_eq!(pool.take(), Some(2));
+        wait_for_len(&pool, 2);
+        assert_eq!(pool.take(), Some(3));
+        assert_eq!(pool.take(), Some(1));
+        wait_for_len(&pool, 2);
+
+        pool.shutdown();
+        assert_eq!(refill.join().unwrap(), Ok(()));
+        assert_eq!(pool.len(), 0);
+        assert_eq!(pool.take(), None);
+    }
+
+    #[test]
+    fn refill_stops_on_spawn_error() {
+        let pool = SandboxProcessPool::<u32>::new(2);
+        assert_eq!(pool.run_refill(|| Err("spawn failed")), Err("spawn failed"));
+        assert_eq!(pool.len(), 0);
+    }
+
+    #[test]
+    fn refill_ignores_spawn_error_after_shutdown() {
+        let pool = SandboxProcessPool::<u32>::new(2);
+        let result = pool.run_refill(|| {
+            pool.shutdown();
+            Err("launcher terminated")
+        });
+        assert_eq!(result, Ok(()));
+    }
+}
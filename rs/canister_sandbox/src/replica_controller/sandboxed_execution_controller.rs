@@ -29,9 +29,7 @@ use ic_types::ingress::WasmResult;
 use ic_types::methods::{FuncRef, WasmMethod};
 use ic_types::{CanisterId, NumInstructions};
 use ic_wasm_types::CanisterModule;
-#[cfg(target_os = "linux")]
-use prometheus::IntGauge;
-use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec};
+use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge};
 use std::collections::{HashMap, VecDeque};
 #[cfg(target_os = "linux")]
 use std::convert::TryInto;
@@ -51,6 +49,7 @@ use super::process_exe_and_args::{
 #[cfg(target_os = "linux")]
 use super::process_os_metrics;
 use super::sandbox_process_eviction::{self, EvictionCandidate};
+use super::sandbox_process_pool::SandboxProcessPool;
 use ic_replicated_state::page_map::PageAllocatorFileDescriptor;
 
 const SANDBOX_PROCESS_UPDATE_INTERVAL: Duration = Duration::from_secs(10);
@@ -71,6 +70,12 @@ const COMPILATION_CACHE_HIT: &str = "compilation_cache_hit";
 const COMPILATION_CACHE_HIT_COMPILATION_ERROR: &str = "compilation_cache_hit_compilation_error";
 const CACHE_MISS: &str = "cache_miss";
 
+// Metric labels for whether a canister without a sandbox process got one from
+// the pool of pre-forked sandbox processes. Stored in the metric
+// [`SandboxedExecutionMetrics::sandboxed_execution_sandbox_pool_lookups`].
+const SANDBOX_POOL_HIT: &str = "hit";
+const SANDBOX_POOL_MISS: &str = "miss";
+
 struct SandboxedExecutionMetrics {
     sandboxed_execution_replica_execute_duration: HistogramVec,
     sandboxed_execution_replica_execute_prepare_duration: HistogramVec,
@@ -79,6 +84,9 @@ struct SandboxedExecutionMetrics {
     sandboxed_execution_sandbox_execute_duration: HistogramVec,
     sandboxed_execution_sandbox_execute_run_duration: HistogramVec,
     sandboxed_execution_spawn_process: Histogram,
+    sandboxed_execution_cold_start_duration: Histogram,
+    sandboxed_execution_sandbox_pool_lookups: IntCounterVec,
+    sandboxed_execution_sandbox_pool_size: IntGauge,
     #[cfg(target_os = "linux")]
     sandboxed_execution_subprocess_anon_rss_total: IntGauge,
     #[cfg(target_os = "linux")]
@@ -158,6 +166,20 @@ impl SandboxedExecutionMetrics {
                 "The time to spawn a sandbox process",
                 decimal_buckets_with_zero(-4, 1),
             ),
+            sandboxed_execution_cold_start_duration: metrics_registry.histogram(
+                "sandboxed_execution_cold_start_duration_seconds",
+                "The time to provide a sandbox process to a canister that has none",
+                decimal_buckets_with_zero(-4, 1),
+            ),
+            sandboxed_execution_sandbox_pool_lookups: metrics_registry.int_counter_vec(
+                "sandboxed_execution_sandbox_pool_lookups_total",
+                "Whether a canister without a sandbox process got a pre-forked one from the pool",
+                &["result"],
+            ),
+            sandboxed_execution_sandbox_pool_size: metrics_registry.int_gauge(
+                "sandboxed_execution_sandbox_pool_size",
+                "The number of idle pre-forked sandbox processes in the pool",
+            ),
             #[cfg(target_os = "linux")]
             sandboxed_execution_subprocess_anon_rss_total: metrics_registry.int_gauge(
                 "sandboxed_execution_subprocess_anon_rss_total_kib",
@@ -271,6 +293,12 @@ impl SandboxedExecutionMetrics {
             .inc();
     }
 
+    fn inc_sandbox_pool_lookup(&self, label: &str) {
+        self.sandboxed_execution_sandbox_pool_lookups
+            .with_label_values(&[label])
+            .inc();
+    }
+
     /// Helper function to observe executed message slices.
     fn observe_executed_message_slice(&self, api_type_label: &str, execution_status: &str) {
         self.sandboxed_execution_executed_message_slices
@@ -456,6 +484,9 @@ enum Backend {
 #[derive(Clone)]
 struct SandboxProcessStats {
     last_used: std::time::Instant,
+    /// The number of times the sandbox process was used, halved on every
+    /// `SANDBOX_PROCESS_UPDATE_INTERVAL` so that recent activity counts most.
+    activity: u64,
 }
 
 enum SandboxProcessStatus {
@@ -642,7 +673,9 @@ pub struct SandboxedExecutionController {
     /// the same for all canisters.
     sandbox_exec_argv: Vec<String>,
     metrics: Arc<SandboxedExecutionMetrics>,
-    launcher_service: Box<dyn LauncherService>,
+    launcher_service: Arc<dyn LauncherService>,
+    /// Idle sandbox processes that are not yet assigned to any canister.
+    sandbox_pool: Arc<SandboxProcessPool<Arc<SandboxProcess>>>,
     fd_factory: Arc<dyn PageAllocatorFileDescriptor>,
 }
 
@@ -651,6 +684,7 @@ impl Drop for SandboxedExecutionController {
         // Evict all the sandbox processes.
         let mut guard = self.backends.lock().unwrap();
         evict_sandbox_processes(&mut guard, 0, 0, Duration::default());
+        self.sandbox_pool.shutdown();
 
         // Terminate the Sandbox Launcher process.
         self.launcher_service
@@ -1023,9 +1057,11 @@ impl SandboxedExecutionController {
             create_sandbox_argv(embedder_config).expect("No canister_sandbox binary found");
         let backends = Arc::new(Mutex::new(HashMap::new()));
         let metrics = Arc::new(SandboxedExecutionMetrics::new(metrics_registry));
+        let sandbox_pool = Arc::new(SandboxProcessPool::new(embedder_config.sandbox_pool_size));
 
         let backends_copy = Arc::clone(&backends);
         let metrics_copy = Arc::clone(&metrics);
+        let sandbox_pool_copy = Arc::clone(&sandbox_pool);
         let logger_copy = logger.clone();
 
         std::thread::spawn(move || {
@@ -1033,6 +1069,7 @@ impl SandboxedExecutionController {
                 logger_copy,
                 backends_copy,
                 metrics_copy,
+                sandbox_pool_copy,
                 min_sandbox_count,
                 max_sandbox_count,
                 max_sandbox_idle_time,
@@ -1059,6 +1096,34 @@ impl SandboxedExecutionController {
             panic_due_to_exit(output, pid);
         });
 
+        let launcher_service: Arc<dyn LauncherService> = Arc::from(launcher_service);
+        if embedder_config.sandbox_pool_size > 0 {
+            let sandbox_pool = Arc::clone(&sandbox_pool);
+            let launcher_service = Arc::clone(&launcher_service);
+            let metrics = Arc::clone(&metrics);
+            let logger = logger.clone();
+            let sandbox_exec_argv = sandbox_exec_argv.clone();
+            // Keep the pool filled in the background, so that spawning a
+            // process is off the critical path of message execution.
+            thread::spawn(move || {
+                let result = sandbox_pool.run_refill(|| {
+                    let _timer = metrics.sandboxed_execution_spawn_process.start_timer();
+                    spawn_sandbox_process(
+                        &logger,
+                        &*launcher_service,
+                        None,
+                        sandbox_exec_argv.clone(),
+                    )
+                });
+                if let Err(err) = result {
+                    error!(
+                        logger,
+                        "Stopped pre-forking sandbox processes due to error: {}", err
+                    );
+                }
+            });
+        }
+
         Ok(Self {
             backends,
             min_sandbox_count,
@@ -1069,6 +1134,7 @@ impl SandboxedExecutionController {
             sandbox_exec_argv,
             metrics,
             launcher_service,
+            sandbox_pool,
             fd_factory: Arc::clone(&fd_factory),
         })
     }
@@ -1081,6 +1147,7 @@ impl SandboxedExecutionController {
         #[allow(unused_variables)] logger: ReplicaLogger,
         backends: Arc<Mutex<HashMap<CanisterId, Backend>>>,
         metrics: Arc<SandboxedExecutionMetrics>,
+        sandbox_pool: Arc<SandboxProcessPool<Arc<SandboxProcess>>>,
         min_sandbox_count: usize,
         max_sandbox_count: usize,
         max_sandbox_idle_time: Duration,
@@ -1173,8 +1240,13 @@ impl SandboxedExecutionController {
                 }
             }
 
+            metrics
+                .sandboxed_execution_sandbox_pool_size
+                .set(sandbox_pool.len() as i64);
+
             {
                 let mut guard = backends.lock().unwrap();
+                decay_sandbox_process_activity(&mut guard);
                 evict_sandbox_processes(
                     &mut guard,
                     min_sandbox_count,
@@ -1208,24 +1280,31 @@ impl SandboxedExecutionController {
                 } => sandbox_process.upgrade().map(|p| (p, stats)),
                 Backend::Empty => None,
             };
-            if let Some((sandbox_process, _stats)) = sandbox_process_and_stats {
-                let now = std::time::Instant::now();
+            if let Some((sandbox_process, stats)) = sandbox_process_and_stats {
+                let stats = SandboxProcessStats {
+                    last_used: std::time::Instant::now(),
+                    activity: stats.activity.saturating_add(1),
+                };
                 if self.max_sandbox_count > 0 {
                     *backend = Backend::Active {
                         sandbox_process: Arc::clone(&sandbox_process),
-                        stats: SandboxProcessStats { last_used: now },
+                        stats,
                     };
                 } else {
                     *backend = Backend::Evicted {
                         sandbox_process: Arc::downgrade(&sandbox_process),
-                        stats: SandboxProcessStats { last_used: now },
+                        stats,
                     };
                 }
                 return sandbox_process;
             }
         }
 
-        let _timer = self.metrics.sandboxed_execution_spawn_process.start_timer();
+        // No live sandbox process found for this canister: a cold start.
+        let _timer = self
+            .metrics
+            .sandboxed_execution_cold_start_duration
+            .start_timer();
         if guard.len() > self.max_sandbox_count {
             let to_evict = self.max_sandbox_count * SANDBOX_PROCESS_EVICTION_PERCENT / 100;
             let max_active_sandboxes = self.max_sandbox_count.saturating_sub(to_evict);
@@ -1237,29 +1316,36 @@ impl SandboxedExecutionController {
             );
         }
 
-        // No sandbox process found for this canister. Start a new one and register it.
-        let reg = Arc::new(ActiveExecutionStateRegistry::new());
-        let controller_service = ControllerServiceImpl::new(Arc::clone(&reg), self.logger.clone());
-
-        let (sandbox_service, pid) = create_sandbox_process(
-            controller_service,
-            &*self.launcher_service,
-            canister_id,
-            self.sandbox_exec_argv.clone(),
-        )
-        .unwrap();
-
-        let sandbox_process = Arc::new(SandboxProcess {
-            execution_states: reg,
-            sandbox_service,
-            pid,
-            history: SandboxProcessRequestHistory::new(),
-        });
+        // Take a pre-forked sandbox process from the pool, or start a new one
+        // if the pool is empty, and register it.
+        let sandbox_process = match self.sandbox_pool.take() {
+            Some(sandbox_process) => {
+                self.metrics.inc_sandbox_pool_lookup(SANDBOX_POOL_HIT);
+                self.metrics
+                    .sandboxed_execution_sandbox_pool_size
+                    .set(self.sandbox_pool.len() as i64);
+                sandbox_process
+            }
+            None => {
+                self.metrics.inc_sandbox_pool_lookup(SANDBOX_POOL_MISS);
+                let _timer = self.metrics.sandboxed_execution_spawn_process.start_timer();
+                spawn_sandbox_process(
+                    &self.logger,
+                    &*self.launcher_service,
+                    Some(canister_id),
+                    self.sandbox_exec_argv.clone(),
+                )
+                .unwrap()
+            }
+        };
 
         let now = std::time::Instant::now();
         let backend = Backend::Active {
             sandbox_process: Arc::clone(&sandbox_process),
-            stats: SandboxProcessStats { last_used: now },
+            stats: SandboxProcessStats {
+                last_used: now,
+                activity: 1,
+            },
         };
         (*guard).insert(canister_id, backend);
 
@@ -1707,6 +1793,7 @@ fn open_wasm(
                 .open_wasm_serialized(protocol::sbxsvc::OpenWasmSerializedRequest {
                     wasm_id,
                     serialized_module: Arc::clone(&serialized_module.bytes),
+                    has_passive_data_segments: serialized_module.has_passive_data_segments,
                 })
                 .on_completion(|_| ());
             cache_opened_wasm(&mut embedder_cache, sandbox_process, wasm_id);
@@ -1779,6 +1866,7 @@ fn open_wasm_with_compiler_sandbox(
                         .open_wasm_serialized(protocol::sbxsvc::OpenWasmSerializedRequest {
                             wasm_id,
                             serialized_module: Arc::clone(&serialized_module.bytes),
+                            has_passive_data_segments: serialized_module.has_passive_data_segments,
                         })
                         .on_completion(|_| ());
                     cache_opened_wasm(&mut embedder_cache, sandbox_process, wasm_id);
@@ -1809,6 +1897,7 @@ fn open_wasm_with_compiler_sandbox(
                 .open_wasm_serialized(protocol::sbxsvc::OpenWasmSerializedRequest {
                     wasm_id,
                     serialized_module: Arc::clone(&serialized_module.bytes),
+                    has_passive_data_segments: serialized_module.has_passive_data_segments,
                 })
                 .on_completion(|_| ());
             cache_opened_wasm(&mut embedder_cache, sandbox_process, wasm_id);
@@ -1868,6 +1957,45 @@ fn wrap_remote_memory(
     SandboxMemoryHandle::new(Arc::new(opened_memory))
 }
 
+// Spawns a sandbox process. The canister id is only passed to the sandbox
+// process for visibility, it is `None` for pre-forked sandbox processes.
+fn spawn_sandbox_process(
+    logger: &ReplicaLogger,
+    launcher_service: &dyn LauncherService,
+    canister_id: Option<CanisterId>,
+    sandbox_exec_argv: Vec<String>,
+) -> std::io::Result<Arc<SandboxProcess>> {
+    let reg = Arc::new(ActiveExecutionStateRegistry::new());
+    let controller_service = ControllerServiceImpl::new(Arc::clone(&reg), logger.clone());
+
+    let (sandbox_service, pid) = create_sandbox_process(
+        controller_service,
+        launcher_service,
+        canister_id,
+        sandbox_exec_argv,
+    )?;
+
+    Ok(Arc::new(SandboxProcess {
+        execution_states: reg,
+        sandbox_service,
+        pid,
+        history: SandboxProcessRequestHistory::new(),
+    }))
+}
+
+// Halves the activity of all sandbox processes, so that the activity reflects
+// the recent usage of a sandbox process rather than its total usage.
+fn decay_sandbox_process_activity(backends: &mut HashMap<CanisterId, Backend>) {
+    for backend in backends.values_mut() {
+        match backend {
+            Backend::Active { stats, .. } | Backend::Evicted { stats, .. } => {
+                stats.activity /= 2;
+            }
+            Backend::Empty => {}
+        }
+    }
+}
+
 // Evicts some sandbox process backends according to the heuristics of the
 // `sandbox_process_eviction::evict()` function. See the comments of that
 // function for the explanation of the threshold parameters.
@@ -1897,6 +2025,7 @@ fn evict_sandbox_processes(
             Backend::Active { stats, .. } => Some(EvictionCandidate {
                 id: *id,
                 last_used: stats.last_used,
+                activity: stats.activity,
             }),
             Backend::Evicted { .. } | Backend::Empty => None,
         })
@@ -2009,22 +2138,21 @@ impl ControllerLauncherService for ExitWatcher {
         req: protocol::ctllaunchersvc::SandboxExitedRequest,
     ) -> crate::rpc::Call<protocol::ctllaunchersvc::SandboxExitedReply> {
         let guard = self.backends.lock().unwrap();
-        let sandbox_process = match guard.get(&req.canister_id).unwrap_or_else(|| {
-            panic!(
-                "Sandbox exited for unrecognized canister id {}",
-                req.canister_id,
-            )
-        }) {
-            Backend::Active {
-                sandbox_process, ..
-            } => sandbox_process,
-            Backend::Evicted { .. } | Backend::Empty => {
-                return rpc::Call::new_resolved(Ok(protocol::ctllaunchersvc::SandboxExitedReply));
-            }
-        };
-        sandbox_process
-            .history
-            .replay(&self.logger, req.canister_id, sandbox_process.pid);
+        // Evicted sandbox processes and pre-forked sandbox processes that are
+        // not yet assigned to a canister have no history worth replaying.
+        let active = guard
+            .iter()
+            .find_map(|(canister_id, backend)| match backend {
+                Backend::Active {
+                    sandbox_process, ..
+                } if sandbox_process.pid == req.pid => Some((canister_id, sandbox_process)),
+                Backend::Active { .. } | Backend::Evicted { .. } | Backend::Empty => None,
+            });
+        if let Some((canister_id, sandbox_process)) = active {
+            sandbox_process
+                .history
+                .replay(&self.logger, *canister_id, sandbox_process.pid);
+        }
         rpc::Call::new_resolved(Ok(protocol::ctllaunchersvc::SandboxExitedReply))
     }
 }
@@ -2127,4 +2255,50 @@ mod tests {
             canister_id, sandbox_pid
         )));
     }
+
+    #[test]
+    fn cold_start_takes_pre_forked_sandbox() {
+        use ic_replicated_state::page_map::TestPageAllocatorFileDescriptorImpl;
+        let embedder_config = EmbeddersConfig {
+            sandbox_pool_size: 1,
+            ..EmbeddersConfig::default()
+        };
+        let controller = SandboxedExecutionController::new(
+            no_op_logger(),
+            &MetricsRegistry::new(),
+            &embedder_config,
+            Arc::new(TestPageAllocatorFileDescriptorImpl::new()),
+        )
+        .unwrap();
+        while controller.sandbox_pool.len() == 0 {
+            thread::sleep(Duration::from_millis(10));
+        }
+
+        let canister_module = CanisterModule::new(wat::parse_str("(module)").unwrap());
+        controller
+            .create_execution_state(
+                canister_module,
+                PathBuf::new(),
+                canister_test_id(0),
+                Arc::new(CompilationCache::new(MAX_COMPILATION_CACHE_SIZE)),
+            )
+            .unwrap();
+
+        let lookups = |label| {
+            controller
+                .metrics
+                .sandboxed_execution_sandbox_pool_lookups
+                .with_label_values(&[label])
+                .get()
+        };
+        assert_eq!(lookups(SANDBOX_POOL_HIT), 1);
+        assert_eq!(lookups(SANDBOX_POOL_MISS), 0);
+        assert_eq!(
+            controller
+                .metrics
+                .sandboxed_execution_cold_start_duration
+                .get_sample_count(),
+            1
+        );
+    }
 }
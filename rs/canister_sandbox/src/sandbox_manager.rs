@@ -11,7 +11,8 @@
 //!
 //! All of the above objects as well as the functionality provided
 //! towards the controller are found in this module.
-use std::collections::HashMap;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
 use std::rc::Rc;
 use std::sync::{Arc, Mutex};
@@ -29,6 +30,7 @@ use ic_config::embedders::Config as EmbeddersConfig;
 use ic_embedders::{
     wasm_executor::WasmStateChanges,
     wasm_utils::{compile, decoding::decode_wasm, Segments},
+    wasmtime_embedder::WasmtimeInstance,
     CompilationResult, SerializedModule, SerializedModuleBytes, WasmtimeEmbedder,
 };
 use ic_interfaces::execution_environment::{
@@ -36,11 +38,43 @@ use ic_interfaces::execution_environment::{
 };
 use ic_logger::ReplicaLogger;
 use ic_replicated_state::page_map::{PageAllocatorRegistry, PageMapSerialization};
-use ic_replicated_state::{EmbedderCache, Global, Memory, PageMap};
+use ic_replicated_state::{EmbedderCache, Global, Memory, NumWasmPages, PageMap};
+use ic_system_api::ModificationTracking;
 use ic_types::CanisterId;
 
 use crate::dts::{DeterministicTimeSlicingHandler, PausedExecution};
 
+/// Identifies the code and state a query instance was created for.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct QueryInstanceKey {
+    wasm_id: WasmId,
+    wasm_memory_id: MemoryId,
+    stable_memory_id: MemoryId,
+}
+
+thread_local! {
+    /// The instance of the last query executed on this worker thread, reset
+    /// to the state it was created for. The next query against the same
+    /// state reuses it instead of instantiating the module again, so each
+    /// thread running the concurrent queries of a hot canister keeps a warm
+    /// instance of the shared state.
+    static QUERY_INSTANCE: RefCell<Option<(QueryInstanceKey, WasmtimeInstance)>> =
+        const { RefCell::new(None) };
+}
+
+// Takes the instance cached on this thread if it was created for `key`.
+fn take_query_instance(key: QueryInstanceKey) -> Option<WasmtimeInstance> {
+    QUERY_INSTANCE.with(|cached| {
+        let mut cached = cached.borrow_mut();
+        match cached.as_ref() {
+            Some((cached_key, _)) if *cached_key == key => {
+                cached.take().map(|(_, instance)| instance)
+            }
+            _ => None,
+        }
+    })
+}
+
 /// A canister execution currently in progress.
 struct Execution {
     /// Id of the execution. This is used in communicating back to
@@ -74,6 +108,7 @@ impl Execution {
         sandbox_manager: Arc<SandboxManager>,
         workers: &mut threadpool::ThreadPool,
         exec_input: SandboxExecInput,
+        query_instance_key: Option<QueryInstanceKey>,
         total_timer: std::time::Instant,
     ) {
         let wasm_memory = (*wasm_memory).clone();
@@ -86,7 +121,14 @@ impl Execution {
         });
 
         workers.execute(move || {
-            execution.run(exec_id, exec_input, wasm_memory, stable_memory, total_timer)
+            execution.run(
+                exec_id,
+                exec_input,
+                wasm_memory,
+                stable_memory,
+                query_instance_key,
+                total_timer,
+            )
         });
     }
 
@@ -98,9 +140,12 @@ impl Execution {
         exec_input: SandboxExecInput,
         mut wasm_memory: Memory,
         mut stable_memory: Memory,
+        query_instance_key: Option<QueryInstanceKey>,
         total_timer: std::time::Instant,
     ) {
         let run_timer = std::time::Instant::now();
+        let memory_sizes = (wasm_memory.size, stable_memory.size);
+        let reusable_instance = query_instance_key.and_then(take_query_instance);
 
         let message_instruction_limit =
             exec_input.execution_parameters.instruction_limits.message();
@@ -154,16 +199,17 @@ impl Execution {
             self.sandbox_manager.log.clone(),
             exec_input.wasm_reserved_pages,
             Rc::new(out_of_instructions_handler),
+            reusable_instance,
         );
 
         match wasm_result {
             Ok(_) => {
-                let state_modifications = deltas.map(
-                    |WasmStateChanges {
-                         dirty_page_indices,
-                         globals,
-                     }| {
-                        let system_state_changes = match instance_or_system_api {
+                let (state_modifications, instance) = match deltas {
+                    Some(WasmStateChanges {
+                        dirty_page_indices,
+                        globals,
+                    }) => {
+                        let (system_state_changes, instance) = match instance_or_system_api {
                             // Here we use `store_data_mut` instead of
                             // `into_store_data` because the later will drop the
                             // wasmtime Instance which can be an expensive
@@ -171,23 +217,28 @@ impl Execution {
                             // to delay the drop until after the execution
                             // completed message is sent back to the main
                             // process.
-                            Ok(mut instance) => instance
-                                .store_data_mut()
-                                .system_api_mut()
-                                .expect("System api not present in the wasmtime instance")
-                                .take_system_state_changes(),
-                            Err(system_api) => system_api.into_system_state_changes(),
+                            Ok(mut instance) => (
+                                instance
+                                    .store_data_mut()
+                                    .system_api_mut()
+                                    .expect("System api not present in the wasmtime instance")
+                                    .take_system_state_changes(),
+                                Some(instance),
+                            ),
+                            Err(system_api) => (system_api.into_system_state_changes(), None),
                         };
-                        StateModifications::new(
+                        let state_modifications = StateModifications::new(
                             globals,
                             &wasm_memory,
                             &stable_memory,
                             &dirty_page_indices.wasm_memory_delta,
                             &dirty_page_indices.stable_memory_delta,
                             system_state_changes,
-                        )
-                    },
-                );
+                        );
+                        (Some(state_modifications), instance)
+                    }
+                    None => (None, instance_or_system_api.ok()),
+                };
                 if state_modifications.is_some() {
                     self.sandbox_manager
                         .add_memory(exec_input.next_wasm_memory_id, wasm_memory);
@@ -215,6 +266,12 @@ impl Execution {
                         },
                     },
                 );
+                self.keep_query_instance(
+                    query_instance_key,
+                    instance,
+                    &exec_input.globals,
+                    memory_sizes,
+                );
             }
             Err(HypervisorError::Aborted) => {
                 // Do not send any reply to the controller because the execution
@@ -243,9 +300,39 @@ impl Execution {
                         },
                     },
                 );
+                self.keep_query_instance(
+                    query_instance_key,
+                    instance_or_system_api.ok(),
+                    &exec_input.globals,
+                    memory_sizes,
+                );
             }
         }
     }
+
+    // Resets the instance of a finished query and caches it on this thread for
+    // the next query against the same state. This happens after the result is
+    // sent to the replica to keep the reset off the critical path.
+    fn keep_query_instance(
+        &self,
+        query_instance_key: Option<QueryInstanceKey>,
+        instance: Option<WasmtimeInstance>,
+        globals: &[Global],
+        (heap_size, stable_memory_size): (NumWasmPages, NumWasmPages),
+    ) {
+        let (Some(key), Some(instance)) = (query_instance_key, instance) else {
+            return;
+        };
+        let instance = self.sandbox_manager.embedder.reset_instance(
+            instance,
+            globals,
+            heap_size,
+            stable_memory_size,
+        );
+        if let Some(instance) = instance {
+            QUERY_INSTANCE.with(|cached| *cached.borrow_mut() = Some((key, instance)));
+        }
+    }
 }
 
 /// Manages the entirety of the sandbox process. It provides the methods
@@ -260,6 +347,8 @@ pub struct SandboxManager {
 }
 struct SandboxManagerInt {
     caches: HashMap<WasmId, Arc<EmbedderCache>>,
+    // Instances of these modules are not reused across queries.
+    wasms_with_passive_data_segments: HashSet<WasmId>,
     memories: HashMap<MemoryId, Arc<Memory>>,
     paused_executions: HashMap<ExecId, PausedExecution>,
     workers_for_replicated_execution: threadpool::ThreadPool,
@@ -280,9 +369,12 @@ impl SandboxManager {
         SandboxManager {
             repr: Mutex::new(SandboxManagerInt {
                 caches: HashMap::new(),
+                wasms_with_passive_data_segments: HashSet::new(),
                 memories: HashMap::new(),
                 paused_executions: HashMap::new(),
                 workers_for_replicated_execution: threadpool::ThreadPool::new(1),
+                // Grows up to `query_execution_threads_per_hot_canister` if
+                // the canister turns hot, see `start_execution`.
                 workers_for_non_replicated_execution: threadpool::ThreadPool::new(
                     config.query_execution_threads_per_canister,
                 ),
@@ -312,6 +404,11 @@ impl SandboxManager {
         let (cache, result) = compile(&self.embedder, &wasm);
         let embedder_cache = Arc::new(cache);
         guard.caches.insert(wasm_id, Arc::clone(&embedder_cache));
+        if let Ok((_, serialized_module)) = &result {
+            if serialized_module.has_passive_data_segments {
+                guard.wasms_with_passive_data_segments.insert(wasm_id);
+            }
+        }
         // Return as much memory as possible because compiling seems to use up
         // some extra memory that can be returned.
         //
@@ -328,6 +425,7 @@ impl SandboxManager {
         &self,
         wasm_id: WasmId,
         serialized_module: &SerializedModuleBytes,
+        has_passive_data_segments: bool,
     ) -> HypervisorResult<(Arc<EmbedderCache>, Duration)> {
         let mut guard = self.repr.lock().unwrap();
         assert!(
@@ -342,6 +440,9 @@ impl SandboxManager {
         let cache = Arc::new(EmbedderCache::new(instance_pre.clone()));
         let deserialization_time = deserialization_timer.elapsed();
         guard.caches.insert(wasm_id, Arc::clone(&cache));
+        if has_passive_data_segments {
+            guard.wasms_with_passive_data_segments.insert(wasm_id);
+        }
         match instance_pre {
             Ok(_) => Ok((cache, deserialization_time)),
             Err(err) => Err(err),
@@ -352,6 +453,7 @@ impl SandboxManager {
     pub fn close_wasm(&self, wasm_id: WasmId) {
         let mut guard = self.repr.lock().unwrap();
         let removed = guard.caches.remove(&wasm_id);
+        guard.wasms_with_passive_data_segments.remove(&wasm_id);
         assert!(
             removed.is_some(),
             "Failed to close wasm session {}: id not found",
@@ -407,39 +509,74 @@ impl SandboxManager {
                 exec_id, wasm_id
             )
         });
+        let wasm_runner = Arc::clone(wasm_runner);
         let wasm_memory = guard.memories.get(&wasm_memory_id).unwrap_or_else(|| {
             unreachable!(
                 "Failed to open exec session {}: wasm memory {} not found",
                 exec_id, wasm_memory_id,
             )
         });
+        let wasm_memory = Arc::clone(wasm_memory);
         let stable_memory = guard.memories.get(&stable_memory_id).unwrap_or_else(|| {
             unreachable!(
                 "Failed to open exec session {}: stable memory {} not found",
                 exec_id, stable_memory_id,
             )
         });
+        let stable_memory = Arc::clone(stable_memory);
         match exec_input.execution_parameters.execution_mode {
             ExecutionMode::Replicated => Execution::start_on_worker_thread(
                 exec_id,
-                Arc::clone(wasm_runner),
-                Arc::clone(wasm_memory),
-                Arc::clone(stable_memory),
+                wasm_runner,
+                wasm_memory,
+                stable_memory,
                 Arc::clone(sandbox_manager),
                 &mut guard.workers_for_replicated_execution,
                 exec_input,
+                None,
                 total_timer,
             ),
-            ExecutionMode::NonReplicated => Execution::start_on_worker_thread(
-                exec_id,
-                Arc::clone(wasm_runner),
-                Arc::clone(wasm_memory),
-                Arc::clone(stable_memory),
-                Arc::clone(sandbox_manager),
-                &mut guard.workers_for_non_replicated_execution,
-                exec_input,
-                total_timer,
-            ),
+            ExecutionMode::NonReplicated => {
+                // Queries that do not modify the state reuse the instance of
+                // the previous query against the same state on their thread.
+                // `data.drop` cannot be undone, so modules with passive data
+                // segments always get a new instance.
+                let query_instance_key = (exec_input.api_type.modification_tracking()
+                    == ModificationTracking::Ignore
+                    && !guard.wasms_with_passive_data_segments.contains(&wasm_id))
+                .then_some(QueryInstanceKey {
+                    wasm_id,
+                    wasm_memory_id,
+                    stable_memory_id,
+                });
+
+                // The replica executes more concurrent queries of a canister
+                // than `query_execution_threads_per_canister` only if the
+                // canister is hot, so the pool grows only for hot canisters.
+                let max_threads = sandbox_manager
+                    .embedder
+                    .config()
+                    .query_execution_threads_per_hot_canister;
+                let workers = &mut guard.workers_for_non_replicated_execution;
+                let num_threads = workers.max_count();
+                if workers.active_count() + workers.queued_count() >= num_threads
+                    && num_threads < max_threads
+                {
+                    workers.set_num_threads(num_threads + 1);
+                }
+
+                Execution::start_on_worker_thread(
+                    exec_id,
+                    wasm_runner,
+                    wasm_memory,
+                    stable_memory,
+                    Arc::clone(sandbox_manager),
+                    workers,
+                    exec_input,
+                    query_instance_key,
+                    total_timer,
+                )
+            }
         };
     }
 
@@ -508,8 +645,11 @@ impl SandboxManager {
         stable_memory_page_map: PageMapSerialization,
     ) -> HypervisorResult<CreateExecutionStateSerializedSuccessReply> {
         let timer = Instant::now();
-        let (embedder_cache, deserialization_time) =
-            self.open_wasm_serialized(wasm_id, &serialized_module.bytes)?;
+        let (embedder_cache, deserialization_time) = self.open_wasm_serialized(
+            wasm_id,
+            &serialized_module.bytes,
+            serialized_module.has_passive_data_segments,
+        )?;
         let (wasm_memory_modifications, exported_globals) = self
             .create_initial_memory_and_globals(
                 &embedder_cache,
@@ -46,7 +46,11 @@ impl SandboxService for SandboxServer {
     ) -> rpc::Call<OpenWasmSerializedReply> {
         let result = self
             .manager
-            .open_wasm_serialized(req.wasm_id, &req.serialized_module)
+            .open_wasm_serialized(
+                req.wasm_id,
+                &req.serialized_module,
+                req.has_passive_data_segments,
+            )
             .map(|_| ());
         rpc::Call::new_resolved(Ok(OpenWasmSerializedReply(result)))
     }
@@ -156,7 +160,7 @@ mod tests {
     use ic_replicated_state::{Global, NumWasmPages, PageIndex, PageMap};
     use ic_system_api::{
         sandbox_safe_system_state::{CanisterStatusView, SandboxSafeSystemState},
-        ApiType, ExecutionParameters, InstructionLimits,
+        ApiType, ExecutionParameters, InstructionLimits, NonReplicatedQueryKind,
     };
     use ic_test_utilities_types::ids::{canister_test_id, subnet_test_id, user_test_id};
     use ic_types::{
@@ -224,6 +228,8 @@ mod tests {
             CanisterTimer::Inactive,
             0,
             BTreeSet::from([controller]),
+            Default::default(),
+            Default::default(),
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
             caller,
             0,
@@ -921,6 +927,70 @@ mod tests {
         assert_eq!(WasmResult::Reply([2, 0, 0, 0].to_vec()), wasm_result);
     }
 
+    /// Verifies that queries reusing the instance of a previous query against
+    /// the same state do not observe the changes made by that query.
+    #[test]
+    fn test_non_replicated_query_instance_reuse() {
+        let exec_finished_sync =
+            Arc::new(SyncCell::<protocol::ctlsvc::ExecutionFinishedRequest>::new());
+
+        // Run all queries on the same thread.
+        let srv = SandboxServer::new(SandboxManager::new(
+            setup_mock_controller(exec_finished_sync.clone()),
+            EmbeddersConfig {
+                query_execution_threads_per_canister: 1,
+                query_execution_threads_per_hot_canister: 1,
+                ..EmbeddersConfig::default()
+            },
+            no_op_logger(),
+        ));
+
+        let wasm_id = WasmId::new();
+        let rep = srv
+            .open_wasm(OpenWasmRequest {
+                wasm_id,
+                wasm_src: make_counter_canister_wasm(),
+            })
+            .sync()
+            .unwrap();
+        assert!(rep.0.is_ok());
+
+        let wasm_memory = PageMap::new_for_testing();
+        let wasm_memory_id = open_memory(&srv, &wasm_memory, 1);
+        let stable_memory = PageMap::new_for_testing();
+        let stable_memory_id = open_memory(&srv, &stable_memory, 0);
+
+        for _ in 0..3 {
+            let mut exec_input =
+                exec_input_for_query("inc_read", &[], vec![Global::I32(0), Global::I64(0)]);
+            exec_input.api_type = ApiType::non_replicated_query(
+                Time::from_nanos_since_unix_epoch(0),
+                user_test_id(0).get(),
+                subnet_test_id(0),
+                vec![],
+                None,
+                NonReplicatedQueryKind::Pure,
+            );
+            exec_input.execution_parameters.execution_mode = ExecutionMode::NonReplicated;
+            let rep = srv
+                .start_execution(protocol::sbxsvc::StartExecutionRequest {
+                    exec_id: ExecId::new(),
+                    wasm_id,
+                    wasm_memory_id,
+                    stable_memory_id,
+                    exec_input,
+                })
+                .sync()
+                .unwrap();
+            assert!(rep.success);
+
+            let result = exec_finished_sync.get();
+            assert!(result.exec_output.state.is_none());
+            let wasm_result = result.exec_output.wasm.wasm_result.unwrap().unwrap();
+            assert_eq!(WasmResult::Reply([1, 0, 0, 0].to_vec()), wasm_result);
+        }
+    }
+
     /// Verify that stable memory writes result in correct page being marked
     /// dirty and passed back.
     #[test]
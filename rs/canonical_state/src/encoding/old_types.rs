@@ -108,7 +108,8 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for RequestV17 {
             method_payload: request.method_payload.clone(),
             cycles_payment: None,
             metadata: request.metadata.as_ref().and_then(|metadata| {
-                (certification_version >= CertificationVersion::V14).then_some(metadata.into())
+                (certification_version >= CertificationVersion::V14)
+                    .then(|| (metadata, certification_version).into())
             }),
         }
     }
@@ -135,7 +136,7 @@ impl TryFrom<RequestV17> for ic_types::messages::Request {
             payment,
             method_name: request.method_name,
             method_payload: request.method_payload,
-            metadata: request.metadata.map(From::from),
+            metadata: request.metadata.map(TryFrom::try_from).transpose()?,
             deadline: NO_DEADLINE,
         })
     }
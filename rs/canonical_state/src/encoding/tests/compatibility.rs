@@ -21,7 +21,7 @@ use ic_types::{
     crypto::CryptoHash,
     messages::{
         CallbackId, Payload, RejectContext, Request, RequestMetadata, RequestOrResponse, Response,
-        NO_DEADLINE,
+        TraceId, NO_DEADLINE,
     },
     nominal_cycles::NominalCycles,
     time::CoarseTime,
@@ -498,6 +498,93 @@ fn canonical_encoding_request_v18_plus() {
     }
 }
 
+/// Canonical CBOR encoding (with certification versions 19 and up) of:
+///
+/// ```no_run
+/// RequestOrResponse::Request(
+///     Request {
+///         receiver: canister_test_id(1),
+///         sender: canister_test_id(2),
+///         sender_reply_callback: CallbackId::from(3),
+///         payment: Cycles::new(3),
+///         method_name: "test".to_string(),
+///         method_payload: vec![6],
+///         metadata: Some(RequestMetadata {
+///             call_tree_depth: 13,
+///             call_tree_start_time: Time::as_nanos_since_unix_epoch(101),
+///             trace_id: Some(TraceId::new([10; 16])),
+///         }),
+///         deadline: CoarseTime(8),
+///     }
+/// )
+/// ```
+///
+/// Expected:
+///
+/// ```text
+/// A1                            # map(1)
+///    00                         # field_index(RequestOrResponse::request)
+///    A8                         # map(8)
+///       00                      # field_index(Request::receiver)
+///       4A                      # bytes(10)
+///          00000000000000010101 # "\x00\x00\x00\x00\x00\x00\x00\x01\x01\x01"
+///       01                      # field_index(Request::sender)
+///       4A                      # bytes(10)
+///          00000000000000020101 # "\x00\x00\x00\x00\x00\x00\x00\x02\x01\x01"
+///       02                      # field_index(Request::sender_reply_callback)
+///       03                      # unsigned(3)
+///       03                      # field_index(Request::payment)
+///       A1                      # map(1)
+///          00                   # field_index(Funds::cycles)
+///          A1                   # map(1)
+///             00                # field_index(Cycles::raw)
+///             04                # unsigned(4)
+///       04                      # field_index(Request::method_name)
+///       64                      # text(4)
+///          74657374             # "test"
+///       05                      # field_index(Request::method_payload)
+///       41                      # bytes(1)
+///          06                   # "\x06"
+///       07                      # field_index(Request::metadata)
+///       A3                      # map(3)
+///          00                   # field_index(RequestMetadata::call_tree_depth)
+///          0D                   # unsigned(13)
+///          01                   # field_index(RequestMetadata::call_tree_start_time)
+///          18 65                # unsigned(101)
+///          03                   # field_index(RequestMetadata::trace_id)
+///          50                   # bytes(16)
+///             0A0A0A0A0A0A0A0A0A0A0A0A0A0A0A0A # "\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n\n"
+///       08                      # field_index(Request::deadline)
+///       08                      # unsigned(8)
+/// ```
+/// Used http://cbor.me/ for printing the human friendly output.
+#[test]
+fn canonical_encoding_request_v19_plus() {
+    for certification_version in
+        all_supported_versions().filter(|v| v >= &CertificationVersion::V19)
+    {
+        let request: RequestOrResponse = Request {
+            receiver: canister_test_id(1),
+            sender: canister_test_id(2),
+            sender_reply_callback: CallbackId::from(3),
+            payment: Cycles::new(4),
+            method_name: "test".to_string(),
+            method_payload: vec![6],
+            metadata: Some(
+                RequestMetadata::new(13, Time::from_nanos_since_unix_epoch(101))
+                    .with_trace_id(Some(TraceId::new([10; 16]))),
+            ),
+            deadline: CoarseTime::from_secs_since_unix_epoch(8),
+        }
+        .into();
+
+        assert_eq!(
+            "A1 00 A8 00 4A 00 00 00 00 00 00 00 01 01 01 01 4A 00 00 00 00 00 00 00 02 01 01 02 03 03 A1 00 A1 00 04 04 64 74 65 73 74 05 41 06 07 A3 00 0D 01 18 65 03 50 0A 0A 0A 0A 0A 0A 0A 0A 0A 0A 0A 0A 0A 0A 0A 0A 08 08",
+            as_hex(&encode_message(&request, certification_version))
+        );
+    }
+}
+
 /// Canonical CBOR encoding of:
 ///
 /// ```no_run
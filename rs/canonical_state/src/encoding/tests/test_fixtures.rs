@@ -4,7 +4,7 @@ use ic_test_utilities_types::ids::canister_test_id;
 use ic_types::{
     messages::{
         CallbackId, Payload, RejectContext, Request, RequestMetadata, RequestOrResponse, Response,
-        NO_DEADLINE,
+        TraceId, NO_DEADLINE,
     },
     time::CoarseTime,
     xnet::{RejectReason, RejectSignal, StreamFlags, StreamHeader},
@@ -31,8 +31,10 @@ pub fn stream_header(certification_version: CertificationVersion) -> StreamHeade
 }
 
 pub fn request(certification_version: CertificationVersion) -> RequestOrResponse {
+    let trace_id =
+        (certification_version >= CertificationVersion::V19).then_some(TraceId::new([9; 16]));
     let metadata = (certification_version >= CertificationVersion::V14).then_some(
-        RequestMetadata::new(1, Time::from_nanos_since_unix_epoch(100_000)),
+        RequestMetadata::new(1, Time::from_nanos_since_unix_epoch(100_000)).with_trace_id(trace_id),
     );
     let deadline = if certification_version >= CertificationVersion::V18 {
         CoarseTime::from_secs_since_unix_epoch(8)
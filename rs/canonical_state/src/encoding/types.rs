@@ -14,7 +14,7 @@ use crate::CertificationVersion;
 use ic_error_types::TryFromError;
 use ic_protobuf::proxy::ProxyDecodeError;
 use ic_types::{
-    messages::NO_DEADLINE,
+    messages::{TraceId, NO_DEADLINE},
     time::CoarseTime,
     xnet::{RejectReason, RejectSignal, StreamIndex},
     Time,
@@ -67,6 +67,8 @@ pub struct RequestMetadata {
     pub call_tree_start_time_u64: Option<u64>,
     #[serde(skip_serializing_if = "Option::is_none")]
     pub call_subtree_deadline_u64: Option<u64>,
+    #[serde(with = "serde_bytes", skip_serializing_if = "Option::is_none", default)]
+    pub trace_id: Option<Bytes>,
 }
 
 /// Canonical representation of `ic_types::messages::Request`.
@@ -318,24 +320,41 @@ impl TryFrom<RequestOrResponse> for ic_types::messages::RequestOrResponse {
     }
 }
 
-impl From<&ic_types::messages::RequestMetadata> for RequestMetadata {
-    fn from(metadata: &ic_types::messages::RequestMetadata) -> Self {
+impl From<(&ic_types::messages::RequestMetadata, CertificationVersion)> for RequestMetadata {
+    fn from(
+        (metadata, certification_version): (
+            &ic_types::messages::RequestMetadata,
+            CertificationVersion,
+        ),
+    ) -> Self {
         RequestMetadata {
             call_tree_depth: Some(*metadata.call_tree_depth()),
             call_tree_start_time_u64: Some(
                 metadata.call_tree_start_time().as_nanos_since_unix_epoch(),
             ),
             call_subtree_deadline_u64: None,
+            // Trace IDs are dropped from the canonical encoding before version 19.
+            trace_id: metadata
+                .trace_id()
+                .filter(|_| certification_version >= CertificationVersion::V19)
+                .map(|trace_id| trace_id.as_bytes().to_vec()),
         }
     }
 }
 
-impl From<RequestMetadata> for ic_types::messages::RequestMetadata {
-    fn from(metadata: RequestMetadata) -> Self {
-        ic_types::messages::RequestMetadata::new(
+impl TryFrom<RequestMetadata> for ic_types::messages::RequestMetadata {
+    type Error = ProxyDecodeError;
+
+    fn try_from(metadata: RequestMetadata) -> Result<Self, Self::Error> {
+        let trace_id = metadata
+            .trace_id
+            .map(|trace_id| TraceId::try_from(trace_id.as_slice()).map_err(ProxyDecodeError::Other))
+            .transpose()?;
+        Ok(ic_types::messages::RequestMetadata::new(
             metadata.call_tree_depth.unwrap_or(0),
             Time::from_nanos_since_unix_epoch(metadata.call_tree_start_time_u64.unwrap_or(0)),
         )
+        .with_trace_id(trace_id))
     }
 }
 
@@ -362,7 +381,8 @@ impl From<(&ic_types::messages::Request, CertificationVersion)> for Request {
             method_payload: request.method_payload.clone(),
             cycles_payment: None,
             metadata: request.metadata.as_ref().and_then(|metadata| {
-                (certification_version >= CertificationVersion::V14).then_some(metadata.into())
+                (certification_version >= CertificationVersion::V14)
+                    .then(|| (metadata, certification_version).into())
             }),
             deadline: request.deadline.as_secs_since_unix_epoch(),
         }
@@ -390,7 +410,7 @@ impl TryFrom<Request> for ic_types::messages::Request {
             payment,
             method_name: request.method_name,
             method_payload: request.method_payload,
-            metadata: request.metadata.map(From::from),
+            metadata: request.metadata.map(TryFrom::try_from).transpose()?,
             deadline: CoarseTime::from_secs_since_unix_epoch(request.deadline),
         })
     }
@@ -234,19 +234,24 @@ pub(crate) fn arb_valid_versioned_message(
     prop_oneof![
         (
             // No `Request::metadata` populated for versions 13 and below.
-            arbitrary::request_or_response_with_config(false, false),
+            arbitrary::request_or_response_with_config(false, false, false),
             Just(CertificationVersion::V0..=MAX_SUPPORTED_CERTIFICATION_VERSION)
         ),
         (
             // Optionally populate `Request::metadata` from version 14 on.
-            arbitrary::request_or_response_with_config(true, false),
+            arbitrary::request_or_response_with_config(true, false, false),
             Just(CertificationVersion::V14..=MAX_SUPPORTED_CERTIFICATION_VERSION)
         ),
         (
             // Optionally populate `deadline` from version 18 on.
-            arbitrary::request_or_response_with_config(true, true),
+            arbitrary::request_or_response_with_config(true, false, true),
             Just(CertificationVersion::V18..=MAX_SUPPORTED_CERTIFICATION_VERSION)
         ),
+        (
+            // Optionally populate `RequestMetadata::trace_id` from version 19 on.
+            arbitrary::request_or_response_with_config(true, true, true),
+            Just(CertificationVersion::V19..=MAX_SUPPORTED_CERTIFICATION_VERSION)
+        ),
     ]
 }
 
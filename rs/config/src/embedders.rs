@@ -29,6 +29,11 @@ pub(crate) const MAX_SUM_EXPORTED_FUNCTION_NAME_LENGTHS: usize = 20000;
 /// The number of threads to use for query execution per canister.
 /// See also `QUERY_EXECUTION_THREADS_TOTAL`.
 pub(crate) const QUERY_EXECUTION_THREADS_PER_CANISTER: usize = 2;
+/// The number of threads to use for query execution of a hot canister, i.e. a
+/// canister with more queued queries than it can execute in one time slice.
+/// It is below `QUERY_EXECUTION_THREADS_TOTAL`, so that a hot canister never
+/// occupies all query execution threads.
+pub(crate) const QUERY_EXECUTION_THREADS_PER_HOT_CANISTER: usize = 3;
 
 /// In terms of execution time, compiling 1 WASM instructions takes as much time
 /// as actually executing 6_000 instructions. Only public for use in tests.
@@ -51,6 +56,11 @@ pub(crate) const DEFAULT_MAX_SANDBOX_COUNT: usize = 2_000;
 /// duration and sandbox process eviction is activated.
 pub(crate) const DEFAULT_MAX_SANDBOX_IDLE_TIME: Duration = Duration::from_secs(30 * 60);
 
+/// The number of idle sandbox processes that are spawned ahead of time, so
+/// that a canister without a sandbox process does not have to wait for one to
+/// be spawned.
+pub(crate) const DEFAULT_SANDBOX_POOL_SIZE: usize = 4;
+
 /// The maximum number of pages that a message dirties without optimizing dirty
 /// page copying by triggering a new execution slice for copying pages.
 /// This default is 1 GiB.
@@ -81,6 +91,11 @@ const STABLE_MEMORY_ACCESSED_PAGE_LIMIT: NumOsPages = NumOsPages::new(8 * GiB /
 /// also used as the maximum size for the Wasm chunk store of each canister.
 const WASM_MAX_SIZE: NumBytes = NumBytes::new(100 * 1024 * 1024); // 100 MiB
 
+/// The default maximum size of the heap of a Wasm64 canister. Unlike Wasm32
+/// heaps, which are limited to 4 GiB by the address space, Wasm64 heaps are
+/// only limited by this configurable value.
+pub const MAX_WASM64_MEMORY_IN_BYTES: NumBytes = NumBytes::new(6 * GiB);
+
 #[derive(Copy, Clone, Debug, Deserialize, PartialEq, Eq, Serialize)]
 pub struct FeatureFlags {
     /// If this flag is enabled, then the output of the `debug_print` system-api
@@ -97,6 +112,14 @@ pub struct FeatureFlags {
     // TODO(IC-1674): remove this flag once the feature is enabled by default.
     /// Indicates whether the best-effort responses feature is enabled.
     pub best_effort_responses: FlagStatus,
+    /// Indicates whether canisters may use the Wasm tail-call proposal
+    /// (`return_call` and `return_call_indirect`).
+    pub wasm_tail_call: FlagStatus,
+    /// Indicates whether compiled modules carry the function names needed for
+    /// symbolized backtraces of the canister's Wasm stack. Whether the traps
+    /// of a canister carry a backtrace, and who may see it, is controlled by
+    /// its `backtrace_visibility` setting.
+    pub canister_backtrace: FlagStatus,
 }
 
 impl FeatureFlags {
@@ -108,6 +131,8 @@ impl FeatureFlags {
             canister_logging: FlagStatus::Enabled,
             wasm64: FlagStatus::Disabled,
             best_effort_responses: FlagStatus::Disabled,
+            wasm_tail_call: FlagStatus::Enabled,
+            canister_backtrace: FlagStatus::Disabled,
         }
     }
 }
@@ -138,6 +163,9 @@ pub struct Config {
     /// The number of threads to use for query execution per canister.
     pub query_execution_threads_per_canister: usize,
 
+    /// The number of threads to use for query execution of a hot canister.
+    pub query_execution_threads_per_hot_canister: usize,
+
     /// Maximum number of globals allowed in a Wasm module.
     pub max_globals: usize,
 
@@ -192,6 +220,10 @@ pub struct Config {
     /// duration and sandbox process eviction is activated.
     pub max_sandbox_idle_time: Duration,
 
+    /// The number of idle sandbox processes that are spawned ahead of time
+    /// and assigned to canisters on demand. Zero disables the pool.
+    pub sandbox_pool_size: usize,
+
     /// The type of the local subnet. The default value here should be replaced
     /// with the correct value at runtime when the hypervisor is created.
     pub subnet_type: SubnetType,
@@ -215,12 +247,17 @@ pub struct Config {
 
     /// The maximum allowed size for an uncompressed canister Wasm module.
     pub wasm_max_size: NumBytes,
+
+    /// The maximum size of the heap of a Wasm64 canister. Must be a multiple
+    /// of the Wasm page size.
+    pub max_wasm64_memory_size: NumBytes,
 }
 
 impl Config {
     pub const fn new() -> Self {
         Config {
             query_execution_threads_per_canister: QUERY_EXECUTION_THREADS_PER_CANISTER,
+            query_execution_threads_per_hot_canister: QUERY_EXECUTION_THREADS_PER_HOT_CANISTER,
             max_globals: MAX_GLOBALS,
             max_functions: MAX_FUNCTIONS,
             max_custom_sections: MAX_CUSTOM_SECTIONS,
@@ -239,12 +276,14 @@ impl Config {
             min_sandbox_count: DEFAULT_MIN_SANDBOX_COUNT,
             max_sandbox_count: DEFAULT_MAX_SANDBOX_COUNT,
             max_sandbox_idle_time: DEFAULT_MAX_SANDBOX_IDLE_TIME,
+            sandbox_pool_size: DEFAULT_SANDBOX_POOL_SIZE,
             subnet_type: SubnetType::Application,
             dirty_page_overhead: NumInstructions::new(0),
             trace_execution: FlagStatus::Disabled,
             max_dirty_pages_without_optimization: DEFAULT_MAX_DIRTY_PAGES_WITHOUT_OPTIMIZATION,
             dirty_page_copy_overhead: DIRTY_PAGE_COPY_OVERHEAD,
             wasm_max_size: WASM_MAX_SIZE,
+            max_wasm64_memory_size: MAX_WASM64_MEMORY_IN_BYTES,
         }
     }
 }
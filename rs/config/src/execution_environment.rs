@@ -5,7 +5,7 @@ use ic_types::{
     Cycles, NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES, MAX_WASM_MEMORY_IN_BYTES,
 };
 use serde::{Deserialize, Serialize};
-use std::{str::FromStr, time::Duration};
+use std::{path::PathBuf, str::FromStr, time::Duration};
 
 const MIB: u64 = 1024 * 1024;
 const GIB: u64 = MIB * 1024;
@@ -72,6 +72,16 @@ pub(crate) const MAX_INSTRUCTIONS_PER_COMPOSITE_QUERY_CALL: u64 = 5_000_000_000;
 /// The maximum time in seconds a query call is allowed to run.
 pub(crate) const MAX_TIME_PER_COMPOSITE_QUERY_CALL: Duration = Duration::from_secs(10);
 
+/// The maximum number of instructions a query may execute in total when query
+/// time slicing is enabled. A query that exceeds the regular per-query limit
+/// keeps executing in slices of `MAX_INSTRUCTIONS_PER_QUERY_SLICE`.
+const MAX_INSTRUCTIONS_PER_SLICED_QUERY: NumInstructions = NumInstructions::new(20_000_000_000);
+
+/// The number of instructions a query executes before it pauses to let the
+/// query scheduler run queries of other canisters on its thread. Executing
+/// this many instructions takes roughly the `QUERY_SCHEDULING_TIME_SLICE_PER_CANISTER`.
+const MAX_INSTRUCTIONS_PER_QUERY_SLICE: NumInstructions = NumInstructions::new(50_000_000);
+
 /// This would allow 100 calls with the current MAX_INSTRUCTIONS_PER_COMPOSITE_QUERY_CALL
 pub const INSTRUCTION_OVERHEAD_PER_QUERY_CALL: u64 = 50_000_000;
 
@@ -129,6 +139,9 @@ const BITCOIN_MAINNET_SOFT_LAUNCH_CANISTER_ID: &str = "gsvzx-syaaa-aaaan-aaabq-c
 /// The capacity of the Wasm compilation cache.
 pub const MAX_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(10 * GIB);
 
+/// The capacity of the persistent tier of the Wasm compilation cache.
+pub const MAX_PERSISTENT_COMPILATION_CACHE_SIZE: NumBytes = NumBytes::new(10 * GIB);
+
 /// Maximum number of controllers allowed in a request (specified in the interface spec).
 pub const MAX_ALLOWED_CONTROLLERS_COUNT: usize = 10;
 
@@ -210,6 +223,19 @@ pub struct Config {
     /// the actual call.
     pub instruction_overhead_per_query_call: NumInstructions,
 
+    /// Indicates whether non-replicated queries and `canister_inspect_message`
+    /// execute in slices. Between slices, the thread executes queries of other
+    /// canisters that are waiting. A query is aborted once it exceeds
+    /// `max_query_call_walltime`, not counting the time spent on the other
+    /// canisters. Requires deterministic time slicing.
+    pub query_time_slicing: FlagStatus,
+
+    /// The maximum number of instructions a sliced query may execute.
+    pub max_instructions_per_sliced_query: NumInstructions,
+
+    /// The maximum number of instructions in a single slice of a query.
+    pub max_instructions_per_query_slice: NumInstructions,
+
     /// If this flag is enabled, then message execution of canisters will be
     /// rate limited based on the amount of modified memory.
     pub rate_limiting_of_heap_delta: FlagStatus,
@@ -246,6 +272,14 @@ pub struct Config {
     /// The capacity of the Wasm compilation cache.
     pub max_compilation_cache_size: NumBytes,
 
+    /// Directory holding the persistent tier of the Wasm compilation cache,
+    /// which survives replica restarts. If `None`, compiled modules are only
+    /// cached in memory.
+    pub compilation_cache_dir: Option<PathBuf>,
+
+    /// The capacity of the persistent tier of the Wasm compilation cache.
+    pub max_persistent_compilation_cache_size: NumBytes,
+
     /// Indicate whether query stats should be collected or not.
     pub query_stats_aggregation: FlagStatus,
 
@@ -319,6 +353,9 @@ impl Default for Config {
             instruction_overhead_per_query_call: NumInstructions::from(
                 INSTRUCTION_OVERHEAD_PER_QUERY_CALL,
             ),
+            query_time_slicing: FlagStatus::Disabled,
+            max_instructions_per_sliced_query: MAX_INSTRUCTIONS_PER_SLICED_QUERY,
+            max_instructions_per_query_slice: MAX_INSTRUCTIONS_PER_QUERY_SLICE,
             rate_limiting_of_heap_delta: FlagStatus::Enabled,
             rate_limiting_of_instructions: FlagStatus::Enabled,
             // The allocatable compute capacity is capped at 50% to ensure that
@@ -340,6 +377,8 @@ impl Default for Config {
             query_cache_max_expiry_time: QUERY_CACHE_MAX_EXPIRY_TIME,
             query_cache_data_certificate_expiry_time: QUERY_CACHE_DATA_CERTIFICATE_EXPIRY_TIME,
             max_compilation_cache_size: MAX_COMPILATION_CACHE_SIZE,
+            compilation_cache_dir: None,
+            max_persistent_compilation_cache_size: MAX_PERSISTENT_COMPILATION_CACHE_SIZE,
             query_stats_aggregation: FlagStatus::Enabled,
             query_stats_epoch_length: QUERY_STATS_EPOCH_LENGTH,
             wasm_chunk_store: FlagStatus::Enabled,
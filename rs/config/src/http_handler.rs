@@ -63,6 +63,13 @@ pub struct Config {
     /// The maximum time the replica will wait for a message to be certified before timing out the requests and responding with `202`, for endpoint `/api/v3/call`.
     pub ingress_message_certificate_timeout_seconds: u64,
 
+    /// The maximum time the replica holds a request to `/api/v2/canister/.../read_state/await`
+    /// waiting for the requested status to become terminal, before responding with the latest certified state.
+    pub request_status_await_timeout_seconds: u64,
+
+    /// Serving at most `max_request_status_await_concurrent_requests` requests concurrently for endpoint `/api/v2/canister/.../read_state/await`.
+    pub max_request_status_await_concurrent_requests: usize,
+
     /// Serving at most `max_tracing_flamegraph_concurrent_requests` requests concurrently for all endpoints under `/_/tracing/flamegraph`.
     pub max_tracing_flamegraph_concurrent_requests: usize,
 }
@@ -88,6 +95,8 @@ impl Default for Config {
             max_query_concurrent_requests: QUERY_EXECUTION_THREADS_TOTAL * 100,
             max_pprof_concurrent_requests: 5,
             ingress_message_certificate_timeout_seconds: 10,
+            request_status_await_timeout_seconds: 30,
+            max_request_status_await_concurrent_requests: 1_000,
             max_tracing_flamegraph_concurrent_requests: 10,
         }
     }
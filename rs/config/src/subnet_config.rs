@@ -157,6 +157,10 @@ const ACCUMULATED_PRIORITY_RESET_INTERVAL: ExecutionRound = ExecutionRound::new(
 /// canister doesn't have it set in the settings.
 const DEFAULT_RESERVED_BALANCE_LIMIT: Cycles = Cycles::new(5 * T);
 
+/// The largest tip a canister pays for the induction of a single ingress
+/// message on application subnets.
+const MAX_INGRESS_TIP: Cycles = Cycles::new(10 * B as u128);
+
 /// Instructions used to upload a chunk (1MiB) to the wasm chunk store. This is
 /// 1/10th of a round.
 pub const DEFAULT_UPLOAD_CHUNK_INSTRUCTIONS: NumInstructions = NumInstructions::new(200_000_000);
@@ -436,6 +440,10 @@ pub struct CyclesAccountManagerConfig {
     /// The default value of the reserved balance limit for the case when the
     /// canister doesn't have it set in the settings.
     pub default_reserved_balance_limit: Cycles,
+
+    /// The largest tip a canister pays for the induction of a single ingress
+    /// message. Larger tips are capped at this value.
+    pub max_ingress_tip: Cycles,
 }
 
 impl CyclesAccountManagerConfig {
@@ -474,6 +482,7 @@ impl CyclesAccountManagerConfig {
             // verified application subnets.
             max_storage_reservation_period: Duration::from_secs(0),
             default_reserved_balance_limit: DEFAULT_RESERVED_BALANCE_LIMIT,
+            max_ingress_tip: MAX_INGRESS_TIP,
         }
     }
 
@@ -508,6 +517,8 @@ impl CyclesAccountManagerConfig {
             // This effectively disables the storage reservation mechanism on system subnets.
             max_storage_reservation_period: Duration::from_secs(0),
             default_reserved_balance_limit: DEFAULT_RESERVED_BALANCE_LIMIT,
+            // Tips are not charged, so they do not affect the ingress order either.
+            max_ingress_tip: Cycles::new(0),
         }
     }
 }
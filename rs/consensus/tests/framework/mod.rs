@@ -5,9 +5,11 @@ mod driver;
 mod execution;
 pub mod malicious;
 mod runner;
+mod simulation;
 mod types;
 
 pub use runner::ConsensusRunner;
+pub use simulation::SimulatedConsensus;
 pub use types::{
     ComponentModifier, ConsensusDependencies, ConsensusDriver, ConsensusInstance,
     ConsensusRunnerConfig, StopPredicate,
@@ -0,0 +1,102 @@
+use super::types::*;
+use ic_consensus_utils::pool_reader::PoolReader;
+use ic_interfaces::p2p::consensus::{MutablePool, UnvalidatedArtifact};
+use ic_p2p_test_utils::simulation::SimulatedNode;
+use ic_test_utilities_time::FastForwardTimeSource;
+use ic_types::{
+    consensus::Block,
+    crypto::{crypto_hash, CryptoHashOf},
+    time::Time,
+    Height, NodeId,
+};
+use std::sync::Arc;
+
+/// Runs a consensus instance as a node of the deterministic network simulation.
+///
+/// All instances share the given time source, which is set to the simulated time
+/// before the instance is polled.
+pub struct SimulatedConsensus<'a> {
+    instance: ConsensusInstance<'a>,
+    time_source: Arc<FastForwardTimeSource>,
+}
+
+impl<'a> SimulatedConsensus<'a> {
+    pub fn new(instance: ConsensusInstance<'a>, time_source: Arc<FastForwardTimeSource>) -> Self {
+        Self {
+            instance,
+            time_source,
+        }
+    }
+}
+
+impl SimulatedNode for SimulatedConsensus<'_> {
+    type Message = InputMessage;
+    type BlockId = CryptoHashOf<Block>;
+
+    fn on_message(&mut self, from: NodeId, message: InputMessage, now: Time) {
+        let driver = &self.instance.driver;
+        match message {
+            InputMessage::Consensus(msg) => {
+                driver
+                    .consensus_pool
+                    .write()
+                    .unwrap()
+                    .insert(UnvalidatedArtifact {
+                        message: msg,
+                        peer_id: from,
+                        timestamp: now,
+                    })
+            }
+            InputMessage::Dkg(msg) => {
+                driver
+                    .dkg_pool
+                    .write()
+                    .unwrap()
+                    .insert(UnvalidatedArtifact {
+                        message: *msg,
+                        peer_id: from,
+                        timestamp: now,
+                    })
+            }
+            InputMessage::Certification(msg) => {
+                driver
+                    .certification_pool
+                    .write()
+                    .unwrap()
+                    .insert(UnvalidatedArtifact {
+                        message: msg,
+                        peer_id: from,
+                        timestamp: now,
+                    })
+            }
+            InputMessage::Ecdsa(msg) => {
+                driver
+                    .ecdsa_pool
+                    .write()
+                    .unwrap()
+                    .insert(UnvalidatedArtifact {
+                        message: msg,
+                        peer_id: from,
+                        timestamp: now,
+                    })
+            }
+        }
+    }
+
+    fn on_tick(&mut self, now: Time) -> Vec<InputMessage> {
+        self.time_source.set_time(now).ok();
+        self.instance.driver.step()
+    }
+
+    fn finalized_height(&self) -> Height {
+        let pool = self.instance.deps.consensus_pool.read().unwrap();
+        PoolReader::new(&*pool).get_finalized_height()
+    }
+
+    fn finalized_block(&self, height: Height) -> Option<CryptoHashOf<Block>> {
+        let pool = self.instance.deps.consensus_pool.read().unwrap();
+        PoolReader::new(&*pool)
+            .get_finalized_block(height)
+            .map(|block| crypto_hash(&block))
+    }
+}
@@ -3,12 +3,14 @@ mod framework;
 
 use crate::framework::{
     malicious, setup_subnet, ComponentModifier, ConsensusDependencies, ConsensusInstance,
-    ConsensusRunner, ConsensusRunnerConfig, StopPredicate,
+    ConsensusRunner, ConsensusRunnerConfig, SimulatedConsensus, StopPredicate,
 };
 use ic_consensus_utils::{membership::Membership, pool_reader::PoolReader};
 use ic_interfaces::consensus_pool::ConsensusPool;
 use ic_interfaces::messaging::MessageRouting;
+use ic_interfaces::time_source::TimeSource;
 use ic_interfaces_registry::RegistryClient;
+use ic_p2p_test_utils::simulation::{Simulation, SimulationBuilder, SimulationError, Step};
 use ic_test_utilities_time::FastForwardTimeSource;
 use ic_test_utilities_types::ids::{node_test_id, subnet_test_id};
 use ic_types::malicious_flags::MaliciousFlags;
@@ -18,6 +20,7 @@ use rand_chacha::{rand_core::SeedableRng, ChaChaRng};
 use std::cell::RefCell;
 use std::rc::Rc;
 use std::sync::Arc;
+use std::time::Duration;
 
 #[test]
 fn multiple_nodes_are_live() -> Result<(), String> {
@@ -66,6 +69,77 @@ fn multiple_nodes_are_deterministic() {
     assert_eq!(run(), run());
 }
 
+#[test]
+fn simulation_with_same_seed_is_deterministic() {
+    let run = || {
+        with_simulation(4, 3, |simulation| {
+            simulation
+                .run_scenario([Step::AwaitFinalization {
+                    height: Height::from(10),
+                    timeout: Duration::from_secs(120),
+                }])
+                .unwrap();
+            simulation.trace().to_vec()
+        })
+    };
+    assert_eq!(run(), run());
+}
+
+#[test]
+fn simulation_partitioned_subnet_recovers_after_heal() {
+    with_simulation(4, 7, |simulation| {
+        simulation
+            .run_scenario([Step::AwaitFinalization {
+                height: Height::from(5),
+                timeout: Duration::from_secs(120),
+            }])
+            .unwrap();
+
+        // Neither half of the subnet holds a notarization quorum.
+        let result = simulation.run_scenario([
+            Step::Partition(
+                vec![node_test_id(0), node_test_id(1)],
+                vec![node_test_id(2), node_test_id(3)],
+            ),
+            Step::AwaitFinalization {
+                height: Height::from(10),
+                timeout: Duration::from_secs(10),
+            },
+        ]);
+        assert!(matches!(result, Err(SimulationError::Liveness(_))));
+
+        simulation
+            .run_scenario([
+                Step::Heal,
+                Step::AwaitFinalization {
+                    height: Height::from(15),
+                    timeout: Duration::from_secs(120),
+                },
+            ])
+            .unwrap();
+    })
+}
+
+#[test]
+fn simulation_crashed_node_catches_up() {
+    with_simulation(4, 11, |simulation| {
+        simulation
+            .run_scenario([
+                Step::Crash(node_test_id(3)),
+                Step::AwaitFinalization {
+                    height: Height::from(10),
+                    timeout: Duration::from_secs(120),
+                },
+                Step::Restart(node_test_id(3)),
+                Step::AwaitFinalization {
+                    height: Height::from(15),
+                    timeout: Duration::from_secs(120),
+                },
+            ])
+            .unwrap();
+    })
+}
+
 #[test]
 fn minority_invalid_notary_share_signature_would_pass() -> Result<(), String> {
     ConsensusRunnerConfig::new_from_env(4, 0)
@@ -233,10 +307,22 @@ fn stalled_clocks_with_f_malicious_would_pass() -> Result<(), String> {
 
 fn run_test(
     config: ConsensusRunnerConfig,
-    mut modifiers: Vec<ComponentModifier>,
+    modifiers: Vec<ComponentModifier>,
     stop_predicate: StopPredicate,
     finish: bool,
 ) {
+    with_consensus_runner(config, modifiers, |mut runner, _| {
+        assert_eq!(runner.run_until(stop_predicate), finish);
+    })
+}
+
+/// Sets up a subnet of `config.num_nodes` consensus instances and passes the runner
+/// together with its time source to `run`.
+fn with_consensus_runner<T>(
+    config: ConsensusRunnerConfig,
+    mut modifiers: Vec<ComponentModifier>,
+    run: impl FnOnce(ConsensusRunner<'_>, Arc<FastForwardTimeSource>) -> T,
+) -> T {
     let rng = &mut ChaChaRng::seed_from_u64(config.random_seed);
     let nodes = config.num_nodes;
     ic_test_utilities::artifact_pool_config::with_test_pool_configs(nodes, move |pool_configs| {
@@ -269,7 +355,7 @@ fn run_test(
             })
             .collect();
 
-        let mut runner = ConsensusRunner::new_with_config(config, time_source);
+        let mut runner = ConsensusRunner::new_with_config(config, time_source.clone());
 
         for ((pool_config, deps), crypto) in pool_configs
             .iter()
@@ -293,7 +379,40 @@ fn run_test(
                 &PoolReader::new(&*deps.consensus_pool.read().unwrap()),
             );
         }
-        assert_eq!(runner.run_until(stop_predicate), finish);
+        run(runner, time_source)
+    })
+}
+
+/// Runs `run` on a simulated subnet of `num_nodes` consensus instances. Message
+/// latencies and polling are derived from `seed`.
+fn with_simulation<T>(
+    num_nodes: usize,
+    seed: u64,
+    run: impl FnOnce(&mut Simulation<SimulatedConsensus<'_>>) -> T,
+) -> T {
+    let config = ConsensusRunnerConfig {
+        num_nodes,
+        random_seed: seed,
+        ..Default::default()
+    };
+    with_consensus_runner(config, Vec::new(), |mut runner, time_source| {
+        let nodes: Vec<_> = std::mem::take(&mut runner.instances)
+            .into_iter()
+            .map(|instance| {
+                let node_id = instance.node_id;
+                (
+                    node_id,
+                    SimulatedConsensus::new(instance, Arc::clone(&time_source)),
+                )
+            })
+            .collect();
+        let mut simulation = SimulationBuilder::new(runner.logger.clone())
+            .seed(seed)
+            .start_time(time_source.get_relative_time())
+            .message_latency(Duration::from_millis(10), Duration::from_millis(200))
+            .add_nodes(nodes)
+            .build();
+        run(&mut simulation)
     })
 }
 
@@ -1072,6 +1072,7 @@ impl EcdsaSubnetConfig {
                     idkg_key_rotation_period_ms: key_rotation_period
                         .map(|key_rotation_period| key_rotation_period.as_millis() as u64),
                 }),
+                http_outcalls_policy: None,
             },
         }
     }
@@ -26,6 +26,7 @@ use ic_replicated_state::{
 use ic_types::{
     canister_http::MAX_CANISTER_HTTP_RESPONSE_BYTES,
     messages::{Request, Response, SignedIngressContent, MAX_INTER_CANISTER_PAYLOAD_IN_BYTES},
+    methods::{SystemMethod, WasmMethod},
     CanisterId, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions, SubnetId,
 };
 use prometheus::IntCounter;
@@ -46,6 +47,10 @@ const SECONDS_PER_DAY: u128 = 24 * 60 * 60;
 /// overriding the canister's freezing threshold.
 const MAX_DELAYED_INGRESS_COST_PAYLOAD_SIZE: usize = 256;
 
+/// Name of the custom section (`icp:public` or `icp:private`) with which a
+/// canister opts into paying the tips attached to its ingress messages.
+pub const INGRESS_TIPS_SECTION: &str = "ingress-tips";
+
 /// Errors returned by the [`CyclesAccountManager`].
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum CyclesAccountManagerError {
@@ -566,7 +571,8 @@ impl CyclesAccountManager {
         self.scale_cost(cycles, subnet_size)
     }
 
-    /// Computes the cost of inducting an ingress message.
+    /// Computes the cost of inducting an ingress message. The tip attached
+    /// to the message is not included, see [`Self::ingress_tip`].
     ///
     /// Returns a tuple containing:
     ///  - ID of the canister that should pay for the cost.
@@ -615,6 +621,32 @@ impl CyclesAccountManager {
         }
     }
 
+    /// Returns the tip that `canister` pays on top of the induction cost to
+    /// have the given ingress message prioritised.
+    ///
+    /// Tips are only paid by canisters that explicitly opted in by including
+    /// the [`INGRESS_TIPS_SECTION`] custom section in their Wasm module and
+    /// that export `canister_inspect_message`, so that a tip is only ever paid
+    /// for a message the canister accepted after checking `ic0.msg_tip`. The
+    /// tip is capped at the subnet's maximum ingress tip and is zero for
+    /// messages addressed to the subnet.
+    pub fn ingress_tip(&self, ingress: &SignedIngressContent, canister: &CanisterState) -> Cycles {
+        let opted_in = canister.execution_state.as_ref().map_or(false, |state| {
+            state
+                .metadata
+                .get_custom_section(INGRESS_TIPS_SECTION)
+                .is_some()
+                && state.exports_method(&WasmMethod::System(SystemMethod::CanisterInspectMessage))
+        });
+        if !opted_in
+            || ingress.is_addressed_to_subnet(self.own_subnet_id)
+            || ingress.canister_id() != canister.canister_id()
+        {
+            return Cycles::zero();
+        }
+        min(ingress.tip(), self.config.max_ingress_tip)
+    }
+
     /// Returns the cost of an ingress message based on the message size.
     pub fn ingress_induction_cost_from_bytes(&self, bytes: NumBytes, subnet_size: usize) -> Cycles {
         self.scale_cost(
@@ -1115,6 +1147,17 @@ impl CyclesAccountManager {
             * (subnet_size as u64)
     }
 
+    /// Returns the fee for a non-replicated http request, which is made by a
+    /// single node of the subnet and hence costs as much as a request on a
+    /// subnet with a single node.
+    pub fn non_replicated_http_request_fee(
+        &self,
+        request_size: NumBytes,
+        response_size_limit: Option<NumBytes>,
+    ) -> Cycles {
+        self.http_request_fee(request_size, response_size_limit, 1)
+    }
+
     /// Returns the default value of the reserved balance limit for the case
     /// when the canister doesn't have it set in the settings.
     pub fn default_reserved_balance_limit(&self) -> Cycles {
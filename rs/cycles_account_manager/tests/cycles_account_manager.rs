@@ -1,28 +1,38 @@
 use ic_base_types::NumSeconds;
 use ic_config::subnet_config::CyclesAccountManagerConfig;
 use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
-use ic_cycles_account_manager::{IngressInductionCost, ResourceSaturation};
+use ic_cycles_account_manager::{IngressInductionCost, ResourceSaturation, INGRESS_TIPS_SECTION};
 use ic_interfaces::execution_environment::CanisterOutOfCyclesError;
 use ic_logger::replica_logger::no_op_logger;
 use ic_management_canister_types::{CanisterIdRecord, Payload, IC_00};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
-    canister_state::system_state::CyclesUseCase, testing::SystemStateTesting, SystemState,
+    canister_state::{
+        execution_state::{CustomSection, CustomSectionType, WasmMetadata},
+        system_state::CyclesUseCase,
+    },
+    testing::SystemStateTesting,
+    ExportedFunctions, SystemState,
 };
 use ic_test_utilities::cycles_account_manager::CyclesAccountManagerBuilder;
 use ic_test_utilities_logger::with_test_replica_logger;
-use ic_test_utilities_state::{new_canister_state, SystemStateBuilder};
+use ic_test_utilities_state::{new_canister_state, ExecutionStateBuilder, SystemStateBuilder};
 use ic_test_utilities_types::{
     ids::{canister_test_id, subnet_test_id, user_test_id},
     messages::SignedIngressBuilder,
 };
 use ic_types::{
     messages::{extract_effective_canister_id, SignedIngressContent},
+    methods::{SystemMethod, WasmMethod},
     nominal_cycles::NominalCycles,
     CanisterId, ComputeAllocation, Cycles, MemoryAllocation, NumBytes, NumInstructions,
 };
 use prometheus::IntCounter;
-use std::{convert::TryFrom, time::Duration};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    convert::TryFrom,
+    time::Duration,
+};
 
 #[test]
 fn test_can_charge_application_subnets() {
@@ -311,6 +321,94 @@ fn ingress_induction_cost_valid_subnet_message() {
     }
 }
 
+#[test]
+fn ingress_induction_cost_excludes_tip() {
+    let msg: SignedIngressContent = SignedIngressBuilder::new()
+        .sender(user_test_id(0))
+        .canister_id(canister_test_id(1))
+        .method_name("update")
+        .tip(1_000_000)
+        .build()
+        .into();
+    let cycles_account_manager = CyclesAccountManagerBuilder::new().build();
+    let num_bytes = msg.arg().len() + msg.method_name().len();
+
+    assert_eq!(
+        cycles_account_manager.ingress_induction_cost(&msg, None, SMALL_APP_SUBNET_MAX_SIZE),
+        IngressInductionCost::Fee {
+            payer: canister_test_id(1),
+            cost: cycles_account_manager.ingress_message_received_fee(SMALL_APP_SUBNET_MAX_SIZE)
+                + cycles_account_manager.ingress_byte_received_fee(SMALL_APP_SUBNET_MAX_SIZE)
+                    * num_bytes
+        }
+    );
+}
+
+#[test]
+fn ingress_tip_is_only_paid_by_opted_in_canisters_up_to_the_cap() {
+    let build_msg = |tip: u64| -> SignedIngressContent {
+        SignedIngressBuilder::new()
+            .sender(user_test_id(0))
+            .canister_id(canister_test_id(1))
+            .method_name("update")
+            .tip(tip)
+            .build()
+            .into()
+    };
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::Application)
+        .build();
+    let max_ingress_tip = CyclesAccountManagerConfig::application_subnet().max_ingress_tip;
+
+    let mut canister = new_canister_state(
+        canister_test_id(1),
+        canister_test_id(11).get(),
+        Cycles::new(1 << 40),
+        NumSeconds::from(0),
+    );
+    // A canister that did not opt in never pays a tip.
+    assert_eq!(
+        cycles_account_manager.ingress_tip(&build_msg(1_000_000), &canister),
+        Cycles::zero()
+    );
+
+    let mut execution_state = ExecutionStateBuilder::new()
+        .with_wasm_metadata(WasmMetadata::new(BTreeMap::from([(
+            INGRESS_TIPS_SECTION.to_string(),
+            CustomSection::new(CustomSectionType::Public, vec![]),
+        )])))
+        .build();
+    // A canister without `canister_inspect_message` cannot accept tips, so it
+    // does not pay them even if it opted in.
+    canister.execution_state = Some(execution_state.clone());
+    assert_eq!(
+        cycles_account_manager.ingress_tip(&build_msg(1_000_000), &canister),
+        Cycles::zero()
+    );
+
+    execution_state.exports = ExportedFunctions::new(BTreeSet::from([WasmMethod::System(
+        SystemMethod::CanisterInspectMessage,
+    )]));
+    canister.execution_state = Some(execution_state);
+    assert_eq!(
+        cycles_account_manager.ingress_tip(&build_msg(1_000_000), &canister),
+        Cycles::new(1_000_000)
+    );
+    assert_eq!(
+        cycles_account_manager.ingress_tip(&build_msg(u64::MAX), &canister),
+        max_ingress_tip
+    );
+
+    // Canisters on system subnets never pay tips.
+    let cycles_account_manager = CyclesAccountManagerBuilder::new()
+        .with_subnet_type(SubnetType::System)
+        .build();
+    assert_eq!(
+        cycles_account_manager.ingress_tip(&build_msg(1_000_000), &canister),
+        Cycles::zero()
+    );
+}
+
 #[test]
 fn charging_removes_canisters_with_insufficient_balance() {
     with_test_replica_logger(|log| {
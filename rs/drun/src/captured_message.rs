@@ -0,0 +1,147 @@
+//! Re-execution of a single message captured with `ic-replay capture-message`.
+//!
+//! The captured canister state is imported into a fresh state machine that
+//! runs with the subnet id, type and size of the original subnet at the
+//! captured batch time. The message is then executed with debug logging and
+//! execution tracing enabled, and its result is printed in the same format as
+//! the results of other `drun` messages.
+
+use super::print_wasm_result;
+use ic_config::{flag_status::FlagStatus, subnet_config::SubnetConfig, Config};
+use ic_registry_routing_table::{CanisterIdRange, RoutingTable};
+use ic_replay_message_capture::{CapturedMessage, MessageCapture};
+use ic_state_machine_tests::{StateMachine, StateMachineBuilder, StateMachineConfig};
+use ic_types::{
+    messages::{CallbackId, Payload, RequestOrResponse},
+    CanisterId, PrincipalId, SubnetId,
+};
+use std::path::Path;
+
+// The maximum number of rounds to wait for the response to a request.
+const MAX_TICKS: usize = 100;
+
+/// Executes the message captured in `capture_dir` on the captured state of
+/// its receiver and prints the result.
+pub fn run_captured_message(capture_dir: &Path, cfg: Config) -> Result<(), String> {
+    let capture = MessageCapture::read_from_dir(capture_dir)
+        .map_err(|err| format!("Failed to read the capture: {}", err))?;
+    let canister_id = capture.message.receiver();
+
+    // The state machine logs at the level given by `RUST_LOG`, so default
+    // to debug logs unless the user asked for a different level.
+    if std::env::var("RUST_LOG").is_err() {
+        std::env::set_var("RUST_LOG", "debug");
+    }
+    let mut hypervisor_config = cfg.hypervisor;
+    hypervisor_config.embedders_config.trace_execution = FlagStatus::Enabled;
+    hypervisor_config
+        .embedders_config
+        .feature_flags
+        .rate_limiting_of_debug_prints = FlagStatus::Disabled;
+
+    // The sender of a request is placed on another subnet, so that the
+    // response ends up in the stream to that subnet.
+    let sender_subnet_id = SubnetId::from(PrincipalId::new_subnet_test_id(1));
+    let mut routing_table = RoutingTable::new();
+    routing_table
+        .insert(
+            CanisterIdRange {
+                start: canister_id,
+                end: canister_id,
+            },
+            capture.subnet_id,
+        )
+        .map_err(|err| format!("Failed to build the routing table: {:?}", err))?;
+    if let CapturedMessage::Request(request) = &capture.message {
+        if request.sender == canister_id {
+            return Err("Requests of a canister to itself cannot be replayed in isolation.".into());
+        }
+        routing_table
+            .insert(
+                CanisterIdRange {
+                    start: request.sender,
+                    end: request.sender,
+                },
+                sender_subnet_id,
+            )
+            .map_err(|err| format!("Failed to build the routing table: {:?}", err))?;
+    }
+
+    let env = StateMachineBuilder::new()
+        .with_config(Some(StateMachineConfig::new(
+            SubnetConfig::new(capture.subnet_type),
+            hypervisor_config,
+        )))
+        .with_subnet_id(capture.subnet_id)
+        .with_subnet_type(capture.subnet_type)
+        .with_subnet_size(capture.subnet_size)
+        .with_routing_table(routing_table)
+        .with_time(capture.batch_time)
+        .build();
+    env.import_canister_state(MessageCapture::canister_state_dir(capture_dir), canister_id);
+
+    match capture.message {
+        CapturedMessage::Ingress(ingress) => {
+            let result = env.execute_ingress_as(
+                ingress.source.get(),
+                canister_id,
+                ingress.method_name,
+                ingress.method_payload,
+            );
+            print!("ingress ");
+            match result {
+                Ok(wasm_result) => {
+                    print!("Completed: ");
+                    print_wasm_result(wasm_result);
+                }
+                Err(err) => println!("Err: {}", err),
+            }
+        }
+        CapturedMessage::Request(request) => {
+            let (sender, callback_id) = (request.sender, request.sender_reply_callback);
+            env.push_input_request(request)
+                .map_err(|err| format!("Failed to enqueue the request: {}", err))?;
+            let payload = (0..MAX_TICKS)
+                .find_map(|_| {
+                    env.tick();
+                    find_response(&env, sender_subnet_id, sender, callback_id)
+                })
+                .ok_or_else(|| {
+                    format!(
+                        "The request did not produce a response within {} rounds.",
+                        MAX_TICKS
+                    )
+                })?;
+            print!("response ");
+            match payload {
+                Payload::Data(data) => println!("Reply: 0x{}", hex::encode(data)),
+                Payload::Reject(context) => println!("Reject: {}", context.message()),
+            }
+        }
+    }
+    Ok(())
+}
+
+// Returns the payload of the response to the given callback of `sender` if it
+// has been routed into the stream to `sender_subnet_id`.
+fn find_response(
+    env: &StateMachine,
+    sender_subnet_id: SubnetId,
+    sender: CanisterId,
+    callback_id: CallbackId,
+) -> Option<Payload> {
+    let state = env.get_latest_state();
+    let stream = state.get_stream(&sender_subnet_id)?;
+    stream
+        .messages()
+        .iter()
+        .find_map(|(_, message)| match message {
+            RequestOrResponse::Response(response)
+                if response.originator == sender
+                    && response.originator_reply_callback == callback_id =>
+            {
+                Some(response.response_payload.clone())
+            }
+            _ => None,
+        })
+}
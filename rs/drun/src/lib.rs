@@ -55,8 +55,11 @@ use std::sync::{Arc, Mutex};
 use std::{thread::sleep, time::Duration};
 use tower::util::ServiceExt;
 
+mod captured_message;
 mod message;
 
+pub use captured_message::run_captured_message;
+
 // drun will panic if it takes more than this many batches
 // until a response for a message is received
 const MAX_BATCHES_UNTIL_RESPONSE: u64 = 10000;
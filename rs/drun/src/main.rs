@@ -5,7 +5,7 @@ use ic_canister_sandbox_backend_lib::{
     RUN_AS_SANDBOX_LAUNCHER_FLAG,
 };
 use ic_config::{flag_status::FlagStatus, Config, ConfigSource};
-use ic_drun::{run_drun, DrunOptions};
+use ic_drun::{run_captured_message, run_drun, DrunOptions};
 use ic_registry_subnet_type::SubnetType;
 use std::path::PathBuf;
 
@@ -17,6 +17,7 @@ const ARG_MESSAGES: &str = "messages";
 const ARG_EXTRA_BATCHES: &str = "extra-batches";
 const ARG_INSTRUCTION_LIMIT: &str = "instruction-limit";
 const ARG_SUBNET_TYPE: &str = "subnet-type";
+const ARG_REPLAY_MESSAGE: &str = "replay-message";
 
 fn main() -> Result<(), String> {
     // Check if `drun` is running in the canister sandbox mode where it waits
@@ -53,6 +54,20 @@ async fn drun_main() -> Result<(), String> {
             .embedders_config
             .feature_flags
             .rate_limiting_of_debug_prints = FlagStatus::Disabled;
+        // Enable Wasm64 canisters in drun to allow local development and
+        // testing.
+        default_config
+            .hypervisor
+            .embedders_config
+            .feature_flags
+            .wasm64 = FlagStatus::Enabled;
+        // Allow canisters to opt into symbolized backtraces of their traps via
+        // the `backtrace_visibility` setting to ease debugging.
+        default_config
+            .hypervisor
+            .embedders_config
+            .feature_flags
+            .canister_backtrace = FlagStatus::Enabled;
         default_config.hypervisor.rate_limiting_of_heap_delta = FlagStatus::Disabled;
         default_config.hypervisor.rate_limiting_of_instructions = FlagStatus::Disabled;
         let cfg = Config::load_with_default(&source, default_config).unwrap_or_else(|err| {
@@ -60,6 +75,14 @@ async fn drun_main() -> Result<(), String> {
             std::process::exit(1);
         });
 
+        if let Some(capture_dir) = matches.value_of(ARG_REPLAY_MESSAGE).map(PathBuf::from) {
+            // The state machine blocks on futures internally, so it has to run
+            // outside of the async runtime.
+            return tokio::task::spawn_blocking(move || run_captured_message(&capture_dir, cfg))
+                .await
+                .map_err(|err| err.to_string())?;
+        }
+
         let log_file = matches.value_of(ARG_LOG_FILE).map(PathBuf::from);
 
         let extra_batches = matches
@@ -134,7 +157,7 @@ fn get_arg_matches() -> ArgMatches {
         )
         .arg(
             Arg::new(ARG_MESSAGES)
-                .required(true)
+                .required_unless_present(ARG_REPLAY_MESSAGE)
                 .value_name("Query/Ingress Messages")
                 .help("Text file containing one message per line."),
         )
@@ -159,5 +182,12 @@ fn get_arg_matches() -> ArgMatches {
                 .value_name("Subnet Type")
                 .takes_value(true),
         )
+        .arg(
+            Arg::new(ARG_REPLAY_MESSAGE)
+                .long(ARG_REPLAY_MESSAGE)
+                .value_name("Capture Directory")
+                .help("Re-execute a message captured with `ic-replay capture-message`.")
+                .takes_value(true),
+        )
         .get_matches()
 }
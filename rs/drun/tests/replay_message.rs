@@ -0,0 +1,79 @@
+use ic_registry_subnet_type::SubnetType;
+use ic_replay_message_capture::{CapturedMessage, MessageCapture};
+use ic_state_machine_tests::StateMachineBuilder;
+use ic_test_utilities_types::messages::IngressBuilder;
+use std::process::Command;
+
+const GREET_WAT: &str = r#"
+(module
+    (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+    (import "ic0" "msg_reply" (func $msg_reply))
+    (func $greet
+        (call $msg_reply_data_append (i32.const 0) (i32.const 5))
+        (call $msg_reply))
+    (memory 1)
+    (data (i32.const 0) "hello")
+    (export "canister_update greet" (func $greet)))
+"#;
+
+// Captures an ingress message to a canister taken from a checkpoint of a state
+// machine and re-executes it with `drun --replay-message`.
+#[test]
+fn drun_replays_captured_ingress_message() {
+    let env = StateMachineBuilder::new()
+        .with_checkpoints_enabled(true)
+        .build();
+    let canister_id = env.install_canister_wat(GREET_WAT, vec![], None);
+    env.tick();
+    env.await_state_hash();
+
+    let state_layout = env.state_manager.state_layout();
+    let checkpoint_height = *state_layout
+        .checkpoint_heights()
+        .unwrap()
+        .last()
+        .expect("no checkpoint was written");
+    let canister_layout = state_layout
+        .checkpoint(checkpoint_height)
+        .unwrap()
+        .canister(&canister_id)
+        .unwrap();
+
+    let ingress = IngressBuilder::new()
+        .receiver(canister_id)
+        .method_name("greet")
+        .build();
+    let capture = MessageCapture::new(
+        env.get_subnet_id(),
+        SubnetType::Application,
+        13,
+        checkpoint_height,
+        env.get_time(),
+        CapturedMessage::Ingress(ingress),
+    );
+    let capture_dir = tempfile::tempdir().unwrap();
+    capture
+        .write_to_dir(canister_layout.raw_path(), capture_dir.path())
+        .unwrap();
+
+    let drun_binary = std::env::var_os("DRUN_BIN").expect("missing drun binary");
+    let output = Command::new(drun_binary)
+        .arg("--replay-message")
+        .arg(capture_dir.path())
+        .output()
+        .expect("failed to run drun");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "drun failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert!(
+        stdout.contains(&format!(
+            "ingress Completed: Reply: 0x{}",
+            hex::encode("hello")
+        )),
+        "unexpected output: {}",
+        stdout
+    );
+}
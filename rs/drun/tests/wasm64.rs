@@ -0,0 +1,70 @@
+use std::process::Command;
+
+// Grows the heap to 4 GiB + 64 KiB on installation and stores the argument of
+// `write` above the 4 GiB boundary, where a Wasm32 canister cannot address it.
+const WASM64_WAT: &str = r#"
+(module
+    (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+    (import "ic0" "msg_arg_data_copy" (func $msg_arg_data_copy (param i64 i64 i64)))
+    (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i64 i64)))
+    (import "ic0" "msg_reply" (func $msg_reply))
+    (func (export "canister_init")
+        (if (i64.ne (memory.grow (i64.const 65536)) (i64.const 1))
+            (then (unreachable))))
+    (func (export "canister_update write")
+        (i64.store (i64.const 0x1_0000_0000) (call $msg_arg_data_size))
+        (call $msg_arg_data_copy
+            (i64.const 0x1_0000_0008) (i64.const 0) (call $msg_arg_data_size))
+        (call $msg_reply_data_append
+            (i64.const 0x1_0000_0008) (i64.load (i64.const 0x1_0000_0000)))
+        (call $msg_reply))
+    (func (export "canister_query read")
+        (call $msg_reply_data_append
+            (i64.const 0x1_0000_0008) (i64.load (i64.const 0x1_0000_0000)))
+        (call $msg_reply))
+    (memory i64 1))
+"#;
+
+// Installs a Wasm64 canister with drun and checks that it can use a heap above
+// 4 GiB across messages.
+#[test]
+fn drun_runs_wasm64_canister_with_heap_above_4_gib() {
+    let dir = tempfile::tempdir().unwrap();
+    let wasm_path = dir.path().join("wasm64.wasm");
+    std::fs::write(&wasm_path, wat::parse_str(WASM64_WAT).unwrap()).unwrap();
+    let messages_path = dir.path().join("messages.txt");
+    std::fs::write(
+        &messages_path,
+        format!(
+            "create\n\
+             install rwlgt-iiaaa-aaaaa-aaaaa-cai {} \"\"\n\
+             ingress rwlgt-iiaaa-aaaaa-aaaaa-cai write \"wasm64\"\n\
+             query rwlgt-iiaaa-aaaaa-aaaaa-cai read \"\"\n",
+            wasm_path.display()
+        ),
+    )
+    .unwrap();
+
+    let drun_binary = std::env::var_os("DRUN_BIN").expect("missing drun binary");
+    let output = Command::new(drun_binary)
+        .arg(&messages_path)
+        .output()
+        .expect("failed to run drun");
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    assert!(
+        output.status.success(),
+        "drun failed: {}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let reply = format!("Reply: 0x{}", hex::encode("wasm64"));
+    assert!(
+        stdout.contains(&format!("ingress Completed: {}", reply)),
+        "unexpected output: {}",
+        stdout
+    );
+    assert!(
+        stdout.contains(&format!("Ok: {}", reply)),
+        "unexpected output: {}",
+        stdout
+    );
+}
@@ -0,0 +1,125 @@
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_embedders::{
+    wasm_utils::inspection::{inspect_wasm, InspectionReport},
+    WasmtimeEmbedder,
+};
+use ic_logger::replica_logger::no_op_logger;
+
+fn usage() {
+    println!(
+        r#"
+Usage: {} [--json] [--wasm64] wasm_file
+
+  Report what the replica makes of the (possibly gzipped) wasm_file: exported
+  canister methods, imported System API functions, custom sections,
+  instrumentation overhead, compiled size and the validation error, if any.
+
+  --json    Output the report as JSON.
+  --wasm64  Validate with the Wasm64 feature flag enabled.
+
+  Exits with a non-zero status if the module would be rejected on install."#,
+        std::env::current_exe().unwrap().display()
+    );
+}
+
+fn print_report(report: &InspectionReport) {
+    println!("Module size: {} bytes", report.module_size);
+    if let Some(wasm_size) = report.wasm_size {
+        println!("Decoded size: {} bytes", wasm_size);
+    }
+
+    println!("Exported methods ({}):", report.exported_methods.len());
+    for method in &report.exported_methods {
+        println!("  {}", method);
+    }
+    println!("Imported ic0 functions ({}):", report.ic0_imports.len());
+    for import in &report.ic0_imports {
+        println!("  {}", import);
+    }
+    println!("Custom sections ({}):", report.custom_sections.len());
+    for section in &report.custom_sections {
+        println!("  {} ({} bytes)", section.name, section.size);
+    }
+
+    if let Some(count) = report.largest_function_instruction_count {
+        println!("Largest function: {} instructions", count);
+    }
+    if let Some(complexity) = report.max_function_complexity {
+        println!("Max function complexity: {}", complexity);
+    }
+    if let (Some(wasm_size), Some(overhead)) = (report.wasm_size, &report.instrumentation) {
+        println!(
+            "Instrumentation: {} -> {} instructions, {} -> {} bytes",
+            overhead.original_instructions,
+            overhead.instrumented_instructions,
+            wasm_size,
+            overhead.instrumented_size
+        );
+    }
+    if let Some(cost) = report.compilation_cost {
+        println!("Compilation cost: {} instructions", cost);
+    }
+    if let Some(compiled_size) = report.compiled_size {
+        println!("Compiled size: {} bytes", compiled_size);
+    }
+
+    if let Some(error) = &report.error {
+        let mut location = String::new();
+        if let Some(index) = error.function_index {
+            location.push_str(&format!(" in function {}", index));
+        }
+        if let Some(offset) = error.offset {
+            location.push_str(&format!(" at offset {:#x}", offset));
+        }
+        println!("Error ({:?}{}): {}", error.stage, location, error.message);
+    }
+}
+
+fn main() {
+    let args: Vec<_> = std::env::args().skip(1).collect();
+    if args.iter().any(|arg| arg == "--help" || arg == "-h") {
+        usage();
+        std::process::exit(0);
+    }
+    let mut json = false;
+    let mut wasm64 = false;
+    let mut files = vec![];
+    for arg in &args {
+        match arg.as_str() {
+            "--json" => json = true,
+            "--wasm64" => wasm64 = true,
+            _ => files.push(arg),
+        }
+    }
+    let filename = match &files[..] {
+        [filename] => filename,
+        _ => {
+            usage();
+            eprintln!("Expected a wasm file, got: {:?}", args);
+            std::process::exit(1);
+        }
+    };
+
+    let module = std::fs::read(filename).unwrap_or_else(|err| {
+        eprintln!("Failed to read {}: {}", filename, err);
+        std::process::exit(1);
+    });
+    let mut config = EmbeddersConfig::default();
+    if wasm64 {
+        config.feature_flags.wasm64 = FlagStatus::Enabled;
+    }
+    let embedder = WasmtimeEmbedder::new(config, no_op_logger());
+    let report = inspect_wasm(&embedder, module);
+
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report).expect("failed to serialize report")
+        );
+    } else {
+        print_report(&report);
+    }
+    if report.error.is_some() {
+        std::process::exit(1);
+    }
+}
@@ -1,21 +1,73 @@
-use std::sync::{Arc, Mutex};
+use std::{
+    path::Path,
+    sync::{Arc, Mutex},
+};
 
 use crate::SerializedModule;
 use ic_interfaces::execution_environment::HypervisorResult;
+use ic_logger::{warn, ReplicaLogger};
 use ic_types::NumBytes;
 use ic_utils_lru_cache::LruCache;
 use ic_wasm_types::{CanisterModule, WasmHash};
 
+use persistent::PersistentCompilationCache;
+
+mod persistent;
+
+#[cfg(test)]
+mod tests;
+
 /// Stores the serialized modules of wasm code that has already been compiled so
 /// that it can be used again without recompiling.
+///
+/// Optionally backed by a persistent tier on disk, so that successfully
+/// compiled modules survive replica restarts and upgrades (as long as the
+/// embedder configuration and Wasmtime version remain the same).
 pub struct CompilationCache {
     cache: Mutex<LruCache<WasmHash, HypervisorResult<Arc<SerializedModule>>>>,
+    persistent: Option<PersistentCompilationCache>,
 }
 
 impl CompilationCache {
     pub fn new(capacity: NumBytes) -> Self {
         Self {
             cache: Mutex::new(LruCache::new(capacity)),
+            persistent: None,
+        }
+    }
+
+    /// Creates a compilation cache with a persistent tier of `persistent_capacity`
+    /// bytes under `dir`. Only modules compiled with the same `fingerprint` (see
+    /// `WasmtimeEmbedder::compilation_cache_fingerprint()`) are reused.
+    ///
+    /// Falls back to an in-memory cache if the persistent tier cannot be opened.
+    pub fn new_with_persistent_tier(
+        capacity: NumBytes,
+        dir: &Path,
+        persistent_capacity: NumBytes,
+        fingerprint: &str,
+        log: ReplicaLogger,
+    ) -> Self {
+        let persistent = match PersistentCompilationCache::open(
+            dir,
+            fingerprint,
+            persistent_capacity,
+            log.clone(),
+        ) {
+            Ok(persistent) => Some(persistent),
+            Err(err) => {
+                warn!(
+                    log,
+                    "Failed to open persistent compilation cache at {}: {}",
+                    dir.display(),
+                    err
+                );
+                None
+            }
+        };
+        Self {
+            cache: Mutex::new(LruCache::new(capacity)),
+            persistent,
         }
     }
 
@@ -24,25 +76,46 @@ impl CompilationCache {
         canister_module: &CanisterModule,
         serialized_module: HypervisorResult<Arc<SerializedModule>>,
     ) {
+        let wasm_hash = WasmHash::from(canister_module);
+        // Only successfully compiled modules are persisted, compilation errors
+        // are cheap to reproduce.
+        if let (Some(persistent), Ok(serialized_module)) = (&self.persistent, &serialized_module) {
+            persistent.insert(wasm_hash.clone(), Arc::clone(serialized_module));
+        }
         self.cache
             .lock()
             .unwrap()
-            .push(WasmHash::from(canister_module), serialized_module);
+            .push(wasm_hash, serialized_module);
     }
 
     pub fn get(
         &self,
         canister_module: &CanisterModule,
     ) -> Option<HypervisorResult<Arc<SerializedModule>>> {
-        self.cache
+        let wasm_hash = WasmHash::from(canister_module);
+        if let Some(result) = self
+            .cache
             .lock()
             .unwrap()
-            .get(&WasmHash::from(canister_module))
+            .get(&wasm_hash)
             .map(|o| o.clone().map_err(|e| e.clone()))
+        {
+            return Some(result);
+        }
+
+        let serialized_module = Arc::new(self.persistent.as_ref()?.get(&wasm_hash)?);
+        self.cache
+            .lock()
+            .unwrap()
+            .push(wasm_hash, Ok(Arc::clone(&serialized_module)));
+        Some(Ok(serialized_module))
     }
 
     #[doc(hidden)]
     pub fn clear_for_testing(&self) {
-        self.cache.lock().unwrap().clear()
+        self.cache.lock().unwrap().clear();
+        if let Some(persistent) = &self.persistent {
+            persistent.clear();
+        }
     }
 }
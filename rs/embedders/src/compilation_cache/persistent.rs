@@ -0,0 +1,342 @@
+//! Disk-backed tier of the [`CompilationCache`](super::CompilationCache).
+//!
+//! Serialized modules are stored as individual files named after the hash of
+//! the uninstrumented Wasm, inside a versioned subdirectory named after the
+//! fingerprint of the replica version, embedder configuration and Wasmtime
+//! version that produced them. Modules compiled by a different replica version
+//! or with a different configuration are therefore never looked up; their
+//! versioned subdirectories are removed when the cache is opened. Any other
+//! entries under the cache root are left untouched.
+//!
+//! Every file starts with a magic header and the SHA-256 digest of the
+//! serialized module, which are checked before the module is deserialized.
+//! Files failing the check are removed and treated as cache misses.
+//!
+//! Modules are written (and synced) by a background thread, so persisting a
+//! module never delays execution. Reads happen outside of any lock; they only
+//! take place on a miss of the in-memory tier, i.e. instead of a compilation.
+
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{sync_channel, Receiver, SyncSender, TrySendError},
+        Arc, Mutex,
+    },
+    thread::JoinHandle,
+    time::SystemTime,
+};
+
+use ic_crypto_sha2::Sha256;
+use ic_logger::{warn, ReplicaLogger};
+use ic_types::{CountBytes, NumBytes};
+use ic_utils_lru_cache::LruCache;
+use ic_wasm_types::WasmHash;
+
+use crate::SerializedModule;
+
+/// Identifies (the version of) the file format.
+const FILE_MAGIC: &[u8; 8] = b"ICSMOD01";
+
+/// Length of the header preceding the serialized module: magic plus digest.
+const HEADER_LEN: usize = FILE_MAGIC.len() + Sha256::DIGEST_LEN;
+
+/// Prefix of the versioned subdirectories holding the modules for one
+/// fingerprint. Only directories with this prefix are ever removed from the
+/// cache root.
+const VERSIONED_DIR_PREFIX: &str = "modules_";
+
+/// Extension of complete serialized module files.
+const MODULE_EXTENSION: &str = "module";
+
+/// Extension of files that are still being written.
+const TMP_EXTENSION: &str = "tmp";
+
+/// Maximum number of modules waiting to be written. Modules compiled while the
+/// queue is full are not persisted.
+const MAX_PENDING_WRITES: usize = 16;
+
+/// The on-disk size of a cached module.
+struct FileSize(usize);
+
+impl CountBytes for FileSize {
+    fn count_bytes(&self) -> usize {
+        self.0
+    }
+}
+
+/// Requests handled by the background writer, in order.
+enum WriterRequest {
+    /// Persist the serialized module of the Wasm with the given hash.
+    Write(WasmHash, Arc<SerializedModule>),
+    /// Acknowledge once all previous requests were handled.
+    Flush(SyncSender<()>),
+}
+
+/// State shared between the cache and its background writer.
+struct Store {
+    /// The versioned subdirectory holding the modules for the current fingerprint.
+    dir: PathBuf,
+    /// Cached modules in LRU order, used for size-bounded eviction.
+    index: Mutex<LruCache<WasmHash, FileSize>>,
+    log: ReplicaLogger,
+}
+
+/// Size-bounded, disk-backed store of serialized modules.
+pub(super) struct PersistentCompilationCache {
+    store: Arc<Store>,
+    writer: Option<SyncSender<WriterRequest>>,
+    writer_handle: Option<JoinHandle<()>>,
+}
+
+impl PersistentCompilationCache {
+    /// Opens the cache under `root`, reusing the modules previously stored
+    /// with the same `fingerprint` and removing those stored with any other.
+    pub(super) fn open(
+        root: &Path,
+        fingerprint: &str,
+        capacity: NumBytes,
+        log: ReplicaLogger,
+    ) -> io::Result<Self> {
+        let dir_name = format!("{}{}", VERSIONED_DIR_PREFIX, fingerprint);
+        fs::create_dir_all(root)?;
+        for entry in fs::read_dir(root)? {
+            let entry = entry?;
+            let name = entry.file_name();
+            let is_stale_versioned_dir = name.to_str().map_or(false, |name| {
+                name.starts_with(VERSIONED_DIR_PREFIX) && name != dir_name
+            }) && entry.file_type()?.is_dir();
+            if is_stale_versioned_dir {
+                fs::remove_dir_all(entry.path())?;
+            }
+        }
+
+        let dir = root.join(dir_name);
+        fs::create_dir_all(&dir)?;
+
+        let mut modules = Vec::new();
+        for entry in fs::read_dir(&dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            match wasm_hash_from_path(&path) {
+                Some(wasm_hash) => {
+                    let metadata = entry.metadata()?;
+                    let accessed = metadata
+                        .accessed()
+                        .or_else(|_| metadata.modified())
+                        .unwrap_or(SystemTime::UNIX_EPOCH);
+                    modules.push((accessed, wasm_hash, metadata.len() as usize));
+                }
+                // Leftover temporary file or unrelated file in the versioned directory.
+                None if entry.file_type()?.is_dir() => fs::remove_dir_all(&path)?,
+                None => fs::remove_file(&path)?,
+            }
+        }
+
+        let store = Arc::new(Store {
+            dir,
+            index: Mutex::new(LruCache::new(capacity)),
+            log,
+        });
+        // Insert least recently used modules first, so they are evicted first.
+        modules.sort();
+        for (_, wasm_hash, size) in modules {
+            store.push(wasm_hash, size);
+        }
+
+        let (writer, requests) = sync_channel(MAX_PENDING_WRITES);
+        let writer_store = Arc::clone(&store);
+        let writer_handle = std::thread::Builder::new()
+            .name("CompilationCacheWriter".to_string())
+            .spawn(move || writer_store.handle_requests(requests))?;
+
+        Ok(Self {
+            store,
+            writer: Some(writer),
+            writer_handle: Some(writer_handle),
+        })
+    }
+
+    /// Looks up the serialized module of the Wasm with the given hash.
+    pub(super) fn get(&self, wasm_hash: &WasmHash) -> Option<SerializedModule> {
+        self.store.index.lock().unwrap().get(wasm_hash)?;
+        let path = self.store.path(wasm_hash);
+        match read_module(&path) {
+            Ok(serialized_module) => Some(serialized_module),
+            Err(err) => {
+                warn!(
+                    self.store.log,
+                    "Discarding persisted module {}: {}",
+                    path.display(),
+                    err
+                );
+                self.store.remove(wasm_hash);
+                None
+            }
+        }
+    }
+
+    /// Queues the serialized module of the Wasm with the given hash to be
+    /// persisted in the background. The module is dropped if the queue is full.
+    pub(super) fn insert(&self, wasm_hash: WasmHash, serialized_module: Arc<SerializedModule>) {
+        if self.store.index.lock().unwrap().get(&wasm_hash).is_some() {
+            return;
+        }
+        if let Some(writer) = &self.writer {
+            match writer.try_send(WriterRequest::Write(wasm_hash, serialized_module)) {
+                Ok(()) | Err(TrySendError::Full(_)) => {}
+                Err(TrySendError::Disconnected(_)) => {
+                    warn!(self.store.log, "Compilation cache writer is not running")
+                }
+            }
+        }
+    }
+
+    /// Blocks until all modules queued so far have been persisted.
+    pub(super) fn flush(&self) {
+        if let Some(writer) = &self.writer {
+            let (ack, acked) = sync_channel(1);
+            if writer.send(WriterRequest::Flush(ack)).is_ok() {
+                acked.recv().ok();
+            }
+        }
+    }
+
+    /// Removes all persisted modules.
+    pub(super) fn clear(&self) {
+        self.flush();
+        let mut index = self.store.index.lock().unwrap();
+        index.clear();
+        let dir = &self.store.dir;
+        if let Err(err) = fs::remove_dir_all(dir).and_then(|_| fs::create_dir(dir)) {
+            warn!(
+                self.store.log,
+                "Failed to clear compilation cache directory {}: {}",
+                dir.display(),
+                err
+            );
+        }
+    }
+}
+
+impl Drop for PersistentCompilationCache {
+    /// Persists all queued modules before returning.
+    fn drop(&mut self) {
+        // Dropping the sender stops the writer once it handled all queued requests.
+        self.writer.take();
+        if let Some(writer_handle) = self.writer_handle.take() {
+            writer_handle.join().ok();
+        }
+    }
+}
+
+impl Store {
+    /// Handles writer requests until the sending side is dropped.
+    fn handle_requests(&self, requests: Receiver<WriterRequest>) {
+        for request in requests {
+            match request {
+                WriterRequest::Write(wasm_hash, serialized_module) => {
+                    self.write(wasm_hash, &serialized_module)
+                }
+                WriterRequest::Flush(ack) => {
+                    ack.send(()).ok();
+                }
+            }
+        }
+    }
+
+    /// Writes the serialized module to disk, without holding the index lock,
+    /// then adds it to the index.
+    fn write(&self, wasm_hash: WasmHash, serialized_module: &SerializedModule) {
+        if self.index.lock().unwrap().get(&wasm_hash).is_some() {
+            return;
+        }
+        match write_module(&self.path(&wasm_hash), serialized_module) {
+            Ok(size) => self.push(wasm_hash, size),
+            Err(err) => warn!(
+                self.log,
+                "Failed to persist compiled module {}: {}",
+                hex::encode(wasm_hash.to_slice()),
+                err
+            ),
+        }
+    }
+
+    /// Adds a module that is not yet indexed to the index, deleting the files
+    /// of any evicted modules.
+    fn push(&self, wasm_hash: WasmHash, size: usize) {
+        let evicted = self.index.lock().unwrap().push(wasm_hash, FileSize(size));
+        for (evicted_hash, _) in evicted {
+            self.remove_file(&evicted_hash);
+        }
+    }
+
+    fn remove(&self, wasm_hash: &WasmHash) {
+        self.index.lock().unwrap().pop(wasm_hash);
+        self.remove_file(wasm_hash);
+    }
+
+    fn remove_file(&self, wasm_hash: &WasmHash) {
+        let path = self.path(wasm_hash);
+        if let Err(err) = fs::remove_file(&path) {
+            if err.kind() != io::ErrorKind::NotFound {
+                warn!(self.log, "Failed to remove {}: {}", path.display(), err);
+            }
+        }
+    }
+
+    fn path(&self, wasm_hash: &WasmHash) -> PathBuf {
+        self.dir
+            .join(hex::encode(wasm_hash.to_slice()))
+            .with_extension(MODULE_EXTENSION)
+    }
+}
+
+/// Parses the Wasm hash out of a module file path.
+fn wasm_hash_from_path(path: &Path) -> Option<WasmHash> {
+    if path.extension()? != MODULE_EXTENSION {
+        return None;
+    }
+    let bytes = hex::decode(path.file_stem()?.to_str()?).ok()?;
+    WasmHash::try_from(bytes).ok()
+}
+
+/// Writes the serialized module to a temporary file, then atomically renames
+/// it to `path`. Returns the size of the file.
+fn write_module(path: &Path, serialized_module: &SerializedModule) -> io::Result<usize> {
+    let payload = bincode::serialize(serialized_module)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+
+    let tmp_path = path.with_extension(TMP_EXTENSION);
+    let mut file = File::create(&tmp_path)?;
+    file.write_all(FILE_MAGIC)?;
+    file.write_all(&Sha256::hash(&payload))?;
+    file.write_all(&payload)?;
+    file.sync_all()?;
+    fs::rename(&tmp_path, path)?;
+
+    Ok(HEADER_LEN + payload.len())
+}
+
+/// Reads a serialized module, checking its header and digest before
+/// deserializing it.
+fn read_module(path: &Path) -> io::Result<SerializedModule> {
+    let invalid_data = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+
+    let mut contents = Vec::new();
+    File::open(path)?.read_to_end(&mut contents)?;
+    if contents.len() < HEADER_LEN {
+        return Err(invalid_data("truncated header"));
+    }
+    let (magic, rest) = contents.split_at(FILE_MAGIC.len());
+    let (digest, payload) = rest.split_at(Sha256::DIGEST_LEN);
+    if magic != FILE_MAGIC.as_slice() {
+        return Err(invalid_data("unknown file format"));
+    }
+    if digest != Sha256::hash(payload).as_slice() {
+        return Err(invalid_data("checksum mismatch"));
+    }
+
+    bincode::deserialize(payload).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))
+}
@@ -0,0 +1,159 @@
+use super::*;
+use crate::{wasm_utils::compile, WasmtimeEmbedder};
+use ic_config::embedders::Config as EmbeddersConfig;
+use ic_interfaces::execution_environment::HypervisorError;
+use ic_logger::replica_logger::no_op_logger;
+use ic_wasm_types::{BinaryEncodedWasm, WasmEngineError};
+use std::path::PathBuf;
+
+const FINGERPRINT: &str = "fingerprint";
+
+fn compile_module(wat: &str) -> (CanisterModule, Arc<SerializedModule>) {
+    let wasm = wat::parse_str(wat).unwrap();
+    let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+    let (_, result) = compile(&embedder, &BinaryEncodedWasm::new(wasm.clone()));
+    (CanisterModule::new(wasm), Arc::new(result.unwrap().1))
+}
+
+fn open(dir: &Path, fingerprint: &str, persistent_capacity: u64) -> CompilationCache {
+    CompilationCache::new_with_persistent_tier(
+        NumBytes::new(1 << 30),
+        dir,
+        NumBytes::new(persistent_capacity),
+        fingerprint,
+        no_op_logger(),
+    )
+}
+
+fn versioned_dir(dir: &Path, fingerprint: &str) -> PathBuf {
+    dir.join(format!("modules_{}", fingerprint))
+}
+
+fn module_files(dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<_> = std::fs::read_dir(versioned_dir(dir, FINGERPRINT))
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .collect();
+    files.sort();
+    files
+}
+
+fn assert_same_module(expected: &SerializedModule, actual: &SerializedModule) {
+    assert_eq!(expected.bytes.as_slice(), actual.bytes.as_slice());
+    assert_eq!(expected.exported_functions, actual.exported_functions);
+    assert_eq!(expected.compilation_cost, actual.compilation_cost);
+}
+
+#[test]
+fn persisted_module_is_reused_after_restart() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (canister_module, serialized_module) =
+        compile_module(r#"(module (func (export "canister_query go")))"#);
+
+    let cache = open(tmp.path(), FINGERPRINT, 1 << 30);
+    cache.insert(&canister_module, Ok(Arc::clone(&serialized_module)));
+    drop(cache);
+
+    let cache = open(tmp.path(), FINGERPRINT, 1 << 30);
+    let cached = cache.get(&canister_module).unwrap().unwrap();
+    assert_same_module(&serialized_module, &cached);
+}
+
+#[test]
+fn compilation_errors_are_not_persisted() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (canister_module, _) = compile_module("(module)");
+
+    let cache = open(tmp.path(), FINGERPRINT, 1 << 30);
+    cache.insert(
+        &canister_module,
+        Err(HypervisorError::WasmEngineError(
+            WasmEngineError::FailedToInitializeEngine,
+        )),
+    );
+    assert!(cache.get(&canister_module).unwrap().is_err());
+    assert!(module_files(tmp.path()).is_empty());
+}
+
+#[test]
+fn modules_with_different_fingerprint_are_discarded() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (canister_module, serialized_module) = compile_module("(module)");
+
+    let cache = open(tmp.path(), "old", 1 << 30);
+    cache.insert(&canister_module, Ok(serialized_module));
+    drop(cache);
+
+    let cache = open(tmp.path(), FINGERPRINT, 1 << 30);
+    assert!(cache.get(&canister_module).is_none());
+    assert!(!versioned_dir(tmp.path(), "old").exists());
+}
+
+#[test]
+fn unrelated_entries_in_cache_root_are_kept() {
+    let tmp = tempfile::tempdir().unwrap();
+    std::fs::create_dir(tmp.path().join("unrelated_dir")).unwrap();
+    std::fs::write(tmp.path().join("unrelated_file"), b"data").unwrap();
+
+    let cache = open(tmp.path(), "old", 1 << 30);
+    drop(cache);
+    let _cache = open(tmp.path(), FINGERPRINT, 1 << 30);
+
+    assert!(tmp.path().join("unrelated_dir").is_dir());
+    assert!(tmp.path().join("unrelated_file").is_file());
+    assert!(!versioned_dir(tmp.path(), "old").exists());
+}
+
+#[test]
+fn corrupted_module_is_discarded() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (canister_module, serialized_module) = compile_module("(module)");
+
+    let cache = open(tmp.path(), FINGERPRINT, 1 << 30);
+    cache.insert(&canister_module, Ok(serialized_module));
+    drop(cache);
+
+    let files = module_files(tmp.path());
+    assert_eq!(1, files.len());
+    let mut contents = std::fs::read(&files[0]).unwrap();
+    *contents.last_mut().unwrap() ^= 0xff;
+    std::fs::write(&files[0], contents).unwrap();
+
+    let cache = open(tmp.path(), FINGERPRINT, 1 << 30);
+    assert!(cache.get(&canister_module).is_none());
+    assert!(module_files(tmp.path()).is_empty());
+}
+
+#[test]
+fn least_recently_used_modules_are_evicted() {
+    let tmp = tempfile::tempdir().unwrap();
+    let (module_1, serialized_module_1) = compile_module("(module)");
+    let (module_2, serialized_module_2) =
+        compile_module(r#"(module (func (export "canister_query go")))"#);
+
+    // Enough capacity for the largest of the two modules, but not for both.
+    let cache = open(tmp.path(), FINGERPRINT, 1 << 30);
+    cache.insert(&module_1, Ok(Arc::clone(&serialized_module_1)));
+    cache.insert(&module_2, Ok(Arc::clone(&serialized_module_2)));
+    // Modules are persisted in the background.
+    cache.persistent.as_ref().unwrap().flush();
+    let capacity = module_files(tmp.path())
+        .iter()
+        .map(|file| std::fs::metadata(file).unwrap().len())
+        .max()
+        .unwrap();
+    cache.clear_for_testing();
+    drop(cache);
+
+    let cache = open(tmp.path(), FINGERPRINT, capacity + 32);
+    cache.insert(&module_1, Ok(serialized_module_1));
+    cache.insert(&module_2, Ok(Arc::clone(&serialized_module_2)));
+    drop(cache);
+
+    let cache = open(tmp.path(), FINGERPRINT, capacity + 32);
+    assert!(cache.get(&module_1).is_none());
+    assert_same_module(
+        &serialized_module_2,
+        &cache.get(&module_2).unwrap().unwrap(),
+    );
+}
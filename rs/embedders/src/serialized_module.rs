@@ -69,6 +69,9 @@ pub struct SerializedModule {
     pub compilation_cost: NumInstructions,
     /// Imported System API functions that are deprecated, should become deprecated, or should only be used by NNS canisters.
     pub imports_details: WasmImportsDetails,
+    /// Whether the module has passive data segments. Instances of such modules
+    /// cannot be reused across executions because `data.drop` is permanent.
+    pub has_passive_data_segments: bool,
 }
 
 impl CountBytes for SerializedModule {
@@ -91,6 +94,7 @@ impl SerializedModule {
             wasm_metadata: validation_details.wasm_metadata,
             compilation_cost: instrumentation_output.compilation_cost,
             imports_details: validation_details.imports_details,
+            has_passive_data_segments: instrumentation_output.has_passive_data_segments,
         })
     }
 
@@ -230,6 +230,7 @@ impl WasmExecutor for WasmExecutorImpl {
             self.log.clone(),
             wasm_reserved_pages,
             Rc::new(DefaultOutOfInstructionsHandler::default()),
+            None,
         );
 
         // Collect logs only when the flag is enabled to avoid producing too much data.
@@ -557,6 +558,10 @@ pub fn get_wasm_reserved_pages(execution_state: &ExecutionState) -> NumWasmPages
     NumWasmPages::from(0)
 }
 
+/// Executes the given function. If `reusable_instance` is given, then it must
+/// be an instance of the same canister state prepared with
+/// `WasmtimeEmbedder::reset_instance`, and it is used instead of creating a
+/// new instance.
 #[allow(clippy::too_many_arguments)]
 #[allow(clippy::type_complexity)]
 pub fn process(
@@ -575,6 +580,7 @@ pub fn process(
     logger: ReplicaLogger,
     wasm_reserved_pages: NumWasmPages,
     out_of_instructions_handler: Rc<dyn OutOfInstructionsHandler>,
+    reusable_instance: Option<WasmtimeInstance>,
 ) -> (
     SliceExecutionOutput,
     WasmExecutionOutput,
@@ -601,15 +607,22 @@ pub fn process(
     let first_slice_instruction_limit = system_api.slice_instruction_limit();
     let message_instruction_limit = system_api.message_instruction_limit();
 
-    let mut instance = match embedder.new_instance(
-        canister_id,
-        embedder_cache,
-        Some(globals),
-        wasm_memory,
-        stable_memory,
-        modification_tracking,
-        Some(system_api),
-    ) {
+    let instance = match reusable_instance {
+        Some(mut instance) if instance.modification_tracking() == modification_tracking => {
+            instance.store_data_mut().system_api = Some(system_api);
+            Ok(instance)
+        }
+        _ => embedder.new_instance(
+            canister_id,
+            embedder_cache,
+            Some(globals),
+            wasm_memory,
+            stable_memory,
+            modification_tracking,
+            Some(system_api),
+        ),
+    };
+    let mut instance = match instance {
         Ok(instance) => instance,
         Err((err, system_api)) => {
             return (
@@ -706,6 +719,13 @@ pub fn process(
     // returning cycles from a request that wasn't sent.
     let mut wasm_result = system_api.take_execution_result(run_result.as_ref().err());
 
+    // Backtraces reveal the internals of the canister, so they are only
+    // returned to callers permitted by the canister's backtrace visibility.
+    if !system_api.caller_can_view_backtrace() {
+        wasm_result = wasm_result.map_err(|err| err.with_backtrace(None));
+    }
+    let log_backtrace = system_api.backtrace_can_be_logged();
+
     let wasm_heap_size_after = instance.heap_size(CanisterMemoryType::Heap);
     let wasm_heap_limit =
         NumWasmPages::from(wasmtime_environ::WASM32_MAX_PAGES as usize) - wasm_reserved_pages;
@@ -764,14 +784,19 @@ pub fn process(
             }
         }
         Err(err) => {
-            if let Some(log_message) = match err {
-                HypervisorError::Trapped(trap_code) => Some(format!("[TRAP]: {}", trap_code)),
-                HypervisorError::CalledTrap(text) if text.is_empty() => {
+            if let Some(mut log_message) = match &err {
+                HypervisorError::Trapped { trap_code, .. } => {
+                    Some(format!("[TRAP]: {}", trap_code))
+                }
+                HypervisorError::CalledTrap { message, .. } if message.is_empty() => {
                     Some("[TRAP]: (no message)".to_string())
                 }
-                HypervisorError::CalledTrap(text) => Some(format!("[TRAP]: {}", text)),
+                HypervisorError::CalledTrap { message, .. } => Some(format!("[TRAP]: {}", message)),
                 _ => None,
             } {
+                if let Some(backtrace) = err.backtrace().filter(|_| log_backtrace) {
+                    log_message.push_str(&format!("\n{}", backtrace));
+                }
                 canister_log.add_record(
                     embedder.config().feature_flags.canister_logging == FlagStatus::Enabled,
                     timestamp_nanos,
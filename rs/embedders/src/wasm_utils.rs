@@ -3,7 +3,7 @@ use std::{
     time::Instant,
 };
 
-use ic_config::embedders::Config as EmbeddersConfig;
+use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
 use ic_interfaces::execution_environment::HypervisorResult;
 use ic_replicated_state::{
     canister_state::{execution_state::WasmMetadata, WASM_PAGE_SIZE_IN_BYTES},
@@ -20,7 +20,9 @@ use crate::{serialized_module::SerializedModule, CompilationResult, WasmtimeEmbe
 use wasmtime::InstancePre;
 
 pub mod decoding;
+pub mod inspection;
 pub mod instrumentation;
+mod symbolization;
 mod system_api_replacements;
 pub mod validation;
 
@@ -195,6 +197,10 @@ pub struct InstrumentationOutput {
     /// The time it takes to compile this module is comparable to executing this
     /// many instructions.
     pub compilation_cost: NumInstructions,
+
+    /// Whether the module has passive data segments, which `data.drop` can
+    /// discard during an execution.
+    pub has_passive_data_segments: bool,
 }
 
 fn validate_and_instrument(
@@ -202,11 +208,17 @@ fn validate_and_instrument(
     config: &EmbeddersConfig,
 ) -> HypervisorResult<(WasmValidationDetails, InstrumentationOutput)> {
     let (wasm_validation_details, module) = validate_wasm_binary(wasm, config)?;
+    let function_names = match config.feature_flags.canister_backtrace {
+        FlagStatus::Enabled => Some(symbolization::function_names(wasm.as_slice())),
+        FlagStatus::Disabled => None,
+    };
     let instrumentation_output = instrument(
         module,
+        function_names,
         config.cost_to_compile_wasm_instruction,
         config.feature_flags.write_barrier,
         config.feature_flags.wasm_native_stable_memory,
+        config.max_wasm64_memory_size,
         config.metering_type,
         config.subnet_type,
         config.dirty_page_overhead,
@@ -0,0 +1,315 @@
+//! Static analysis of canister modules, reporting what the replica would make
+//! of a module without installing it.
+//!
+//! The module goes through the same decoding, validation, instrumentation and
+//! compilation steps as on installation. The first step that fails is reported
+//! as an [`InspectionError`], together with its location in the module where it
+//! is known; the remaining steps are skipped.
+
+use std::sync::Arc;
+
+use ic_interfaces::execution_environment::HypervisorError;
+use ic_wasm_transform::Module;
+use ic_wasm_types::WasmValidationError;
+use serde::Serialize;
+use wasmparser::TypeRef;
+
+use super::{decoding::decode_wasm, validate_and_instrument_for_testing};
+use crate::{SerializedModuleBytes, WasmtimeEmbedder};
+
+/// Name of the module from which the System API is imported.
+const IC0_MODULE: &str = "ic0";
+
+/// The outcome of inspecting a canister module.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct InspectionReport {
+    /// Size of the module as given, possibly gzip-compressed.
+    pub module_size: usize,
+    /// Size of the module after decompression.
+    pub wasm_size: Option<usize>,
+    /// Canister methods exported by the module, e.g. `canister_update foo`.
+    pub exported_methods: Vec<String>,
+    /// Names of the System API functions imported from `ic0`.
+    pub ic0_imports: Vec<String>,
+    /// Custom sections of the module, in the order they appear.
+    pub custom_sections: Vec<CustomSection>,
+    /// Number of instructions in the largest function.
+    pub largest_function_instruction_count: Option<u64>,
+    /// Complexity of the most complex function.
+    pub max_function_complexity: Option<u64>,
+    /// Size increase caused by instrumentation.
+    pub instrumentation: Option<InstrumentationOverhead>,
+    /// Compiling the module is charged as this many instructions.
+    pub compilation_cost: Option<u64>,
+    /// Size of the compiled and serialized module.
+    pub compiled_size: Option<usize>,
+    /// The error that would prevent the module from being installed.
+    pub error: Option<InspectionError>,
+}
+
+/// A custom section of the module.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct CustomSection {
+    pub name: String,
+    pub size: usize,
+}
+
+/// Comparison of the module before and after instrumentation.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InstrumentationOverhead {
+    /// Number of instructions in all function bodies, before instrumentation.
+    pub original_instructions: usize,
+    /// Number of instructions in all function bodies, after instrumentation.
+    pub instrumented_instructions: usize,
+    /// Size of the Wasm binary after instrumentation.
+    pub instrumented_size: usize,
+}
+
+/// The step at which inspection failed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum InspectionStage {
+    Decoding,
+    Validation,
+    Instrumentation,
+    Compilation,
+}
+
+/// An error preventing the module from being installed.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct InspectionError {
+    pub stage: InspectionStage,
+    pub message: String,
+    /// Index of the offending function, imported functions included.
+    pub function_index: Option<u32>,
+    /// Byte offset in the Wasm binary reported by the Wasm validator.
+    pub offset: Option<usize>,
+}
+
+impl InspectionError {
+    fn new(stage: InspectionStage, message: String) -> Self {
+        Self {
+            stage,
+            message,
+            function_index: None,
+            offset: None,
+        }
+    }
+}
+
+/// Inspects the given (possibly gzip-compressed) canister module, using the
+/// configuration of the given embedder.
+pub fn inspect_wasm(embedder: &WasmtimeEmbedder, module: Vec<u8>) -> InspectionReport {
+    let mut report = InspectionReport {
+        module_size: module.len(),
+        ..Default::default()
+    };
+
+    let wasm = match decode_wasm(embedder.config().wasm_max_size, Arc::new(module)) {
+        Ok(wasm) => wasm,
+        Err(err) => {
+            report.error = Some(InspectionError::new(
+                InspectionStage::Decoding,
+                err.to_string(),
+            ));
+            return report;
+        }
+    };
+    report.wasm_size = Some(wasm.len());
+
+    // The imports and custom sections are reported even if validation fails.
+    let mut num_imported_functions = 0;
+    let mut original_instructions = 0;
+    if let Ok(module) = Module::parse(wasm.as_slice(), false) {
+        for import in &module.imports {
+            if let TypeRef::Func(_) = import.ty {
+                num_imported_functions += 1;
+                if import.module == IC0_MODULE {
+                    report.ic0_imports.push(import.name.to_string());
+                }
+            }
+        }
+        report.custom_sections = module
+            .custom_sections
+            .iter()
+            .map(|(name, data)| CustomSection {
+                name: name.to_string(),
+                size: data.len(),
+            })
+            .collect();
+        original_instructions = count_instructions(&module);
+    }
+
+    let (validation_details, output) = match validate_and_instrument_for_testing(embedder, &wasm) {
+        Ok(result) => result,
+        Err(err) => {
+            report.error = Some(validation_error(err, num_imported_functions));
+            return report;
+        }
+    };
+    report.exported_methods = output
+        .exported_functions
+        .iter()
+        .map(|method| method.to_string())
+        .collect();
+    report.largest_function_instruction_count =
+        Some(validation_details.largest_function_instruction_count.get());
+    report.max_function_complexity = Some(validation_details.max_complexity.0);
+    report.compilation_cost = Some(output.compilation_cost.get());
+    // The instrumented module uses multiple memories.
+    if let Ok(module) = Module::parse(output.binary.as_slice(), true) {
+        report.instrumentation = Some(InstrumentationOverhead {
+            original_instructions,
+            instrumented_instructions: count_instructions(&module),
+            instrumented_size: output.binary.len(),
+        });
+    }
+
+    match embedder
+        .compile(&output.binary)
+        .and_then(|module| SerializedModuleBytes::try_from(&module))
+    {
+        Ok(bytes) => report.compiled_size = Some(bytes.as_slice().len()),
+        Err(err) => {
+            report.error = Some(InspectionError::new(
+                InspectionStage::Compilation,
+                err.to_string(),
+            ))
+        }
+    }
+    report
+}
+
+fn count_instructions(module: &Module) -> usize {
+    module
+        .code_sections
+        .iter()
+        .map(|body| body.instructions.len())
+        .sum()
+}
+
+/// Converts an error returned by validation or instrumentation, locating the
+/// offending function or byte offset where possible.
+fn validation_error(err: HypervisorError, num_imported_functions: u32) -> InspectionError {
+    let validation_err = match &err {
+        HypervisorError::InvalidWasm(validation_err) => validation_err,
+        HypervisorError::InstrumentationFailed(_) => {
+            return InspectionError::new(InspectionStage::Instrumentation, err.to_string());
+        }
+        _ => return InspectionError::new(InspectionStage::Validation, err.to_string()),
+    };
+
+    let mut error = InspectionError::new(InspectionStage::Validation, err.to_string());
+    match validation_err {
+        // Function indices in these errors only count the functions defined
+        // in the code section.
+        WasmValidationError::FunctionComplexityTooHigh { index, .. }
+        | WasmValidationError::UnsupportedWasmInstruction { index, .. }
+        | WasmValidationError::FunctionTooLarge { index, .. } => {
+            error.function_index = Some(num_imported_functions + *index as u32);
+        }
+        WasmValidationError::WasmtimeValidation(message) => {
+            error.offset = parse_offset(message);
+        }
+        _ => {}
+    }
+    error
+}
+
+/// Extracts the offset from a Wasm validator message ending in
+/// `(at offset 0x...)`.
+fn parse_offset(message: &str) -> Option<usize> {
+    let (_, rest) = message.rsplit_once("at offset 0x")?;
+    let hex_digits = rest
+        .find(|c: char| !c.is_ascii_hexdigit())
+        .unwrap_or(rest.len());
+    usize::from_str_radix(&rest[..hex_digits], 16).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_config::embedders::Config as EmbeddersConfig;
+    use ic_logger::replica_logger::no_op_logger;
+
+    fn inspect(wat: &str) -> InspectionReport {
+        let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+        inspect_wasm(&embedder, wat::parse_str(wat).unwrap())
+    }
+
+    #[test]
+    fn reports_valid_module() {
+        let report = inspect(
+            r#"
+            (module
+                (import "ic0" "msg_reply" (func $msg_reply))
+                (func $f (export "canister_update f") (call $msg_reply))
+                (func (export "canister_query g"))
+                (func (export "private"))
+                (memory 1)
+                (@custom "icp:public candid:service" "service : {}")
+            )"#,
+        );
+        assert_eq!(report.error, None);
+        assert_eq!(
+            report.exported_methods,
+            vec!["canister_update f", "canister_query g"]
+        );
+        assert_eq!(report.ic0_imports, vec!["msg_reply"]);
+        assert_eq!(
+            report.custom_sections,
+            vec![CustomSection {
+                name: "icp:public candid:service".to_string(),
+                size: 12,
+            }]
+        );
+        let instrumentation = report.instrumentation.unwrap();
+        assert_eq!(instrumentation.original_instructions, 4);
+        assert!(instrumentation.instrumented_instructions > 4);
+        assert!(report.compiled_size.unwrap() > 0);
+    }
+
+    #[test]
+    fn locates_function_errors() {
+        let report = inspect(
+            r#"
+            (module
+                (import "ic0" "msg_reply" (func $msg_reply))
+                (table 1 funcref)
+                (func)
+                (func (table.set (i32.const 0) (ref.null func)))
+            )"#,
+        );
+        let error = report.error.unwrap();
+        assert_eq!(error.stage, InspectionStage::Validation);
+        assert!(error.message.contains("table.set"), "{}", error.message);
+        assert_eq!(error.function_index, Some(2));
+        assert_eq!(report.ic0_imports, vec!["msg_reply"]);
+        assert_eq!(report.compiled_size, None);
+    }
+
+    #[test]
+    fn locates_wasmtime_validation_errors() {
+        let report = inspect("(module (func (result i32) (i64.const 0)))");
+        let error = report.error.unwrap();
+        assert_eq!(error.stage, InspectionStage::Validation);
+        assert!(error.offset.is_some(), "{}", error.message);
+    }
+
+    #[test]
+    fn reports_decoding_errors() {
+        let embedder = WasmtimeEmbedder::new(EmbeddersConfig::default(), no_op_logger());
+        let report = inspect_wasm(&embedder, b"not wasm".to_vec());
+        assert_eq!(report.error.unwrap().stage, InspectionStage::Decoding);
+        assert_eq!(report.wasm_size, None);
+    }
+
+    #[test]
+    fn parses_validator_offsets() {
+        assert_eq!(
+            parse_offset("type mismatch: expected i32, found i64 (at offset 0x2a)"),
+            Some(0x2a)
+        );
+        assert_eq!(parse_offset("no offset"), None);
+    }
+}
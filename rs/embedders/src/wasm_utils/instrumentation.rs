@@ -98,6 +98,15 @@
 //! bound by the length of the longest execution path consisting of
 //! non-reentrant basic blocks.
 //!
+//! # Function names
+//!
+//! If canister backtraces are enabled, the `name` section of the module is
+//! replaced by one that refers to the instrumented function indices, so that
+//! Wasmtime can name the frames of a trap backtrace. See the
+//! `symbolization` module for how the names are derived. Every function name
+//! and DWARF line row processed to derive the names is charged like a Wasm
+//! instruction in the compilation cost.
+//!
 //! # Wasm-native stable memory
 //!
 //! Two additional memories are inserted for stable memory. One is the actual
@@ -110,6 +119,7 @@
 //! ```
 //!
 
+use super::symbolization;
 use super::system_api_replacements::replacement_functions;
 use super::validation::API_VERSION_IC0;
 use super::{InstrumentationOutput, Segments, SystemApiFunc};
@@ -119,8 +129,9 @@ use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::NumWasmPages;
 use ic_sys::PAGE_SIZE;
 use ic_types::{methods::WasmMethod, MAX_WASM_MEMORY_IN_BYTES};
-use ic_types::{NumInstructions, MAX_STABLE_MEMORY_IN_BYTES};
+use ic_types::{NumBytes, NumInstructions, MAX_STABLE_MEMORY_IN_BYTES};
 use ic_wasm_types::{BinaryEncodedWasm, WasmError, WasmInstrumentationError};
+use wasm_encoder::Encode;
 use wasmtime_environ::WASM_PAGE_SIZE;
 
 use crate::wasmtime_embedder::{
@@ -162,7 +173,7 @@ pub(crate) enum InjectedImports {
 }
 
 impl InjectedImports {
-    fn count(wasm_native_stable_memory: FlagStatus) -> usize {
+    pub(crate) fn count(wasm_native_stable_memory: FlagStatus) -> usize {
         if wasm_native_stable_memory == FlagStatus::Enabled {
             5
         } else {
@@ -712,7 +723,6 @@ const BYTEMAP_SIZE_IN_WASM_PAGES: u64 =
     MAX_WASM_MEMORY_IN_BYTES / (PAGE_SIZE as u64) / (WASM_PAGE_SIZE as u64);
 
 const MAX_STABLE_MEMORY_IN_WASM_PAGES: u64 = MAX_STABLE_MEMORY_IN_BYTES / (WASM_PAGE_SIZE as u64);
-const MAX_WASM_MEMORY_IN_WASM_PAGES: u64 = MAX_WASM_MEMORY_IN_BYTES / (WASM_PAGE_SIZE as u64);
 /// There is one byte for each OS page in the stable memory.
 const STABLE_BYTEMAP_SIZE_IN_WASM_PAGES: u64 = MAX_STABLE_MEMORY_IN_WASM_PAGES / (PAGE_SIZE as u64);
 
@@ -909,11 +919,14 @@ pub(super) struct SpecialIndices {
 ///
 /// Returns an [`InstrumentationOutput`] or an error if the input binary could
 /// not be instrumented.
+#[allow(clippy::too_many_arguments)]
 pub(super) fn instrument(
     module: Module<'_>,
+    function_names: Option<symbolization::FunctionNames>,
     cost_to_compile_wasm_instruction: NumInstructions,
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
+    max_wasm64_memory_size: NumBytes,
     metering_type: MeteringType,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
@@ -922,8 +935,12 @@ pub(super) fn instrument(
     let stable_memory_index;
     let mut module = inject_helper_functions(module, wasm_native_stable_memory, main_memory_type);
     module = export_table(module);
-    (module, stable_memory_index) =
-        update_memories(module, write_barrier, wasm_native_stable_memory);
+    (module, stable_memory_index) = update_memories(
+        module,
+        write_barrier,
+        wasm_native_stable_memory,
+        max_wasm64_memory_size,
+    );
 
     let mut extra_strs: Vec<String> = Vec::new();
     module = export_mutable_globals(module, &mut extra_strs);
@@ -1009,22 +1026,48 @@ pub(super) fn instrument(
         for (func_ix, func_type) in func_types.into_iter() {
             inject_try_grow_wasm_memory(&mut func_bodies[func_ix], &func_type, main_memory_type);
             if write_barrier == FlagStatus::Enabled {
-                inject_mem_barrier(&mut func_bodies[func_ix], &func_type);
+                inject_mem_barrier(&mut func_bodies[func_ix], &func_type, main_memory_type);
             }
         }
     }
 
     module = export_additional_symbols(module, &special_indices, wasm_native_stable_memory);
 
-    if wasm_native_stable_memory == FlagStatus::Enabled {
-        replace_system_api_functions(
+    let func_index_replacements = match wasm_native_stable_memory {
+        FlagStatus::Enabled => replace_system_api_functions(
             &mut module,
             special_indices,
             subnet_type,
             dirty_page_overhead,
             main_memory_type,
-        )
-    }
+        ),
+        FlagStatus::Disabled => BTreeMap::new(),
+    };
+
+    // The original `name` section refers to the function indices before
+    // instrumentation, so it is replaced by one with the shifted indices.
+    // System API functions replaced by Wasm code are named after the import.
+    let mut symbolization_entries = 0;
+    let name_section = function_names.map(|function_names| {
+        symbolization_entries = function_names.processed_entries;
+        let injected_imports = InjectedImports::count(wasm_native_stable_memory) as u32;
+        let mut function_names: BTreeMap<u32, String> = function_names
+            .names
+            .into_iter()
+            .map(|(index, name)| (index + injected_imports, name))
+            .collect();
+        let imported_functions = module
+            .imports
+            .iter()
+            .filter(|import| matches!(import.ty, TypeRef::Func(_)));
+        for (old_index, import) in imported_functions.enumerate() {
+            if let Some(new_index) = func_index_replacements.get(&(old_index as u32)) {
+                function_names.insert(*new_index, format!("{}.{}", import.module, import.name));
+            }
+        }
+        module.custom_sections.retain(|(name, _)| *name != "name");
+        symbolization::name_section(&function_names)
+    });
 
     let exported_functions = module
         .exports
@@ -1057,6 +1100,10 @@ pub(super) fn instrument(
     // pull out the data from the data section
     let data = get_data(&mut module.data)?;
     data.validate(NumWasmPages::from(initial_limit as usize))?;
+    let has_passive_data_segments = module
+        .data
+        .iter()
+        .any(|segment| matches!(segment.kind, ic_wasm_transform::DataSegmentKind::Passive));
 
     let mut wasm_instruction_count: u64 = 0;
     for body in &module.code_sections {
@@ -1071,15 +1118,23 @@ pub(super) fn instrument(
         wasm_instruction_count += 2;
     }
 
-    let result = module.encode().map_err(|err| {
+    let mut result = module.encode().map_err(|err| {
         WasmInstrumentationError::WasmSerializeError(WasmError::new(err.to_string()))
     })?;
+    // Custom sections may appear anywhere, but the `name` section is expected
+    // to follow the data section, so it is appended to the encoded module.
+    if let Some(name_section) = name_section {
+        result.push(wasm_encoder::SectionId::Custom as u8);
+        name_section.encode(&mut result);
+    }
 
     Ok(InstrumentationOutput {
         exported_functions,
         data,
         binary: BinaryEncodedWasm::new(result),
-        compilation_cost: cost_to_compile_wasm_instruction * wasm_instruction_count,
+        compilation_cost: cost_to_compile_wasm_instruction
+            * (wasm_instruction_count + symbolization_entries),
+        has_passive_data_segments,
     })
 }
 
@@ -1101,13 +1156,15 @@ fn calculate_api_indexes(module: &Module<'_>) -> BTreeMap<SystemApiFunc, u32> {
         .collect()
 }
 
+/// Replaces calls to imported System API functions with calls to equivalent
+/// Wasm functions and returns the mapping from old to new function indices.
 fn replace_system_api_functions(
     module: &mut Module<'_>,
     special_indices: SpecialIndices,
     subnet_type: SubnetType,
     dirty_page_overhead: NumInstructions,
     main_memory_type: WasmMemoryType,
-) {
+) -> BTreeMap<u32, u32> {
     let api_indexes = calculate_api_indexes(module);
     let number_of_func_imports = module
         .imports
@@ -1137,6 +1194,7 @@ fn replace_system_api_functions(
     mutate_function_indices(module, |idx| {
         *func_index_replacements.get(&idx).unwrap_or(&idx)
     });
+    func_index_replacements
 }
 
 // Helper function used by instrumentation to export additional symbols.
@@ -1515,76 +1573,84 @@ fn write_barrier_instructions<'a>(
     offset: u64,
     val_arg_idx: u32,
     addr_arg_idx: u32,
+    mem_type: WasmMemoryType,
 ) -> Vec<Operator<'a>> {
     use Operator::*;
     let page_size_shift = PAGE_SIZE.trailing_zeros() as i32;
     let tracking_mem_idx = 1;
-    if offset % PAGE_SIZE as u64 == 0 {
-        vec![
-            LocalSet {
-                local_index: val_arg_idx,
-            }, // value
-            LocalTee {
-                local_index: addr_arg_idx,
-            }, // address
-            I32Const {
-                value: page_size_shift,
-            },
-            I32ShrU,
-            I32Const { value: 1 },
-            I32Store8 {
-                memarg: wasmparser::MemArg {
-                    align: 0,
-                    max_align: 0,
-                    offset: offset >> page_size_shift,
-                    memory: tracking_mem_idx,
-                },
-            },
-            // Put original params on the stack
-            LocalGet {
-                local_index: addr_arg_idx,
-            },
-            LocalGet {
-                local_index: val_arg_idx,
-            },
-        ]
-    } else {
-        vec![
-            LocalSet {
-                local_index: val_arg_idx,
-            }, // value
-            LocalTee {
-                local_index: addr_arg_idx,
-            }, // address
-            I32Const {
-                value: offset as i32,
-            },
-            I32Add,
+    let mut instructions = vec![
+        LocalSet {
+            local_index: val_arg_idx,
+        }, // value
+        LocalTee {
+            local_index: addr_arg_idx,
+        }, // address
+    ];
+    // Page aligned offsets are applied to the (32-bit) bytemap directly, all
+    // others are added to the address.
+    let bytemap_offset =
+        if offset % PAGE_SIZE as u64 == 0 && offset >> page_size_shift <= u32::MAX as u64 {
+            offset >> page_size_shift
+        } else {
+            match mem_type {
+                WasmMemoryType::Wasm32 => instructions.extend([
+                    I32Const {
+                        value: offset as i32,
+                    },
+                    I32Add,
+                ]),
+                WasmMemoryType::Wasm64 => instructions.extend([
+                    I64Const {
+                        value: offset as i64,
+                    },
+                    I64Add,
+                ]),
+            }
+            0
+        };
+    match mem_type {
+        WasmMemoryType::Wasm32 => instructions.extend([
             I32Const {
                 value: page_size_shift,
             },
             I32ShrU,
-            I32Const { value: 1 },
-            I32Store8 {
-                memarg: wasmparser::MemArg {
-                    align: 0,
-                    max_align: 0,
-                    offset: 0,
-                    memory: tracking_mem_idx,
-                },
+        ]),
+        // The bytemap is a 32-bit memory, so the page index is wrapped. Page
+        // indices of valid addresses always fit into 32 bits.
+        WasmMemoryType::Wasm64 => instructions.extend([
+            I64Const {
+                value: page_size_shift as i64,
             },
-            // Put original params on the stack
-            LocalGet {
-                local_index: addr_arg_idx,
-            },
-            LocalGet {
-                local_index: val_arg_idx,
-            },
-        ]
+            I64ShrU,
+            I32WrapI64,
+        ]),
     }
+    instructions.extend([
+        I32Const { value: 1 },
+        I32Store8 {
+            memarg: wasmparser::MemArg {
+                align: 0,
+                max_align: 0,
+                offset: bytemap_offset,
+                memory: tracking_mem_idx,
+            },
+        },
+        // Put original params on the stack
+        LocalGet {
+            local_index: addr_arg_idx,
+        },
+        LocalGet {
+            local_index: val_arg_idx,
+        },
+    ]);
+    instructions
 }
 
-fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncType) {
+fn inject_mem_barrier(
+    func_body: &mut ic_wasm_transform::Body,
+    func_type: &FuncType,
+    mem_type: WasmMemoryType,
+) {
     use Operator::*;
     let mut val_i32_needed = false;
     let mut val_i64_needed = false;
@@ -1623,7 +1689,7 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
         // the total number of locals.
         let n_locals: u32 = func_body.locals.iter().map(|x| x.0).sum();
         let mut next_local = func_type.params().len() as u32 + n_locals;
-        let arg_i32_addr_idx = next_local;
+        let arg_addr_idx = next_local;
         next_local += 1;
 
         // conditionally add following locals
@@ -1632,13 +1698,26 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
         let arg_f32_val_idx;
         let arg_f64_val_idx;
 
-        if val_i32_needed {
-            arg_i32_val_idx = next_local;
-            next_local += 1;
-            func_body.locals.push((2, ValType::I32)); // addr and val locals
-        } else {
-            arg_i32_val_idx = u32::MAX; // not used
-            func_body.locals.push((1, ValType::I32)); // only addr local
+        match (mem_type, val_i32_needed) {
+            (WasmMemoryType::Wasm32, true) => {
+                arg_i32_val_idx = next_local;
+                next_local += 1;
+                func_body.locals.push((2, ValType::I32)); // addr and val locals
+            }
+            (WasmMemoryType::Wasm32, false) => {
+                arg_i32_val_idx = u32::MAX; // not used
+                func_body.locals.push((1, ValType::I32)); // only addr local
+            }
+            (WasmMemoryType::Wasm64, true) => {
+                arg_i32_val_idx = next_local;
+                next_local += 1;
+                func_body.locals.push((1, ValType::I64)); // addr local
+                func_body.locals.push((1, ValType::I32)); // val local
+            }
+            (WasmMemoryType::Wasm64, false) => {
+                arg_i32_val_idx = u32::MAX; // not used
+                func_body.locals.push((1, ValType::I64)); // only addr local
+            }
         }
 
         if val_i64_needed {
@@ -1677,7 +1756,8 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_i32_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        mem_type,
                     ));
                 }
                 I64Store { memarg }
@@ -1687,21 +1767,24 @@ fn inject_mem_barrier(func_body: &mut ic_wasm_transform::Body, func_type: &FuncT
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_i64_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        mem_type,
                     ));
                 }
                 F32Store { memarg } => {
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_f32_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        mem_type,
                     ));
                 }
                 F64Store { memarg } => {
                     elems.extend_from_slice(&write_barrier_instructions(
                         memarg.offset,
                         arg_f64_val_idx,
-                        arg_i32_addr_idx,
+                        arg_addr_idx,
+                        mem_type,
                     ));
                 }
                 _ => {}
@@ -1910,16 +1993,32 @@ fn export_table(mut module: Module) -> Module {
 /// Exports existing memories and injects new memories. Returns the index of an
 /// injected stable memory when using wasm-native stable memory. The bytemap for
 /// the stable memory will always be inserted directly after the stable memory.
+///
+/// The maximum size of a Wasm64 heap is capped at `max_wasm64_memory_size`
+/// and its bytemap is sized accordingly.
 fn update_memories(
     mut module: Module,
     write_barrier: FlagStatus,
     wasm_native_stable_memory: FlagStatus,
+    max_wasm64_memory_size: NumBytes,
 ) -> (Module, u32) {
     let mut stable_index = 0;
+    let mut bytemap_size_in_wasm_pages = BYTEMAP_SIZE_IN_WASM_PAGES;
 
     if let Some(mem) = module.memories.first_mut() {
-        if mem.memory64 && mem.maximum.is_none() {
-            mem.maximum = Some(MAX_WASM_MEMORY_IN_WASM_PAGES);
+        if mem.memory64 {
+            let max_wasm64_memory_in_wasm_pages =
+                max_wasm64_memory_size.get() / (WASM_PAGE_SIZE as u64);
+            mem.maximum = Some(
+                mem.maximum
+                    .map_or(max_wasm64_memory_in_wasm_pages, |maximum| {
+                        maximum.min(max_wasm64_memory_in_wasm_pages)
+                    }),
+            );
+            bytemap_size_in_wasm_pages = max_wasm64_memory_size
+                .get()
+                .div_ceil(PAGE_SIZE as u64)
+                .div_ceil(WASM_PAGE_SIZE as u64);
         }
     }
 
@@ -1944,8 +2043,8 @@ fn update_memories(
         module.memories.push(MemoryType {
             memory64: false,
             shared: false,
-            initial: BYTEMAP_SIZE_IN_WASM_PAGES,
-            maximum: Some(BYTEMAP_SIZE_IN_WASM_PAGES),
+            initial: bytemap_size_in_wasm_pages,
+            maximum: Some(bytemap_size_in_wasm_pages),
         });
 
         module.exports.push(Export {
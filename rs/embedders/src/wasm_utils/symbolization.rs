@@ -0,0 +1,431 @@
+//! Symbolization of canister functions for trap backtraces.
+//!
+//! Wasmtime names the frames of a backtrace using the `name` custom section of
+//! the module it compiles. Instrumentation shifts the function indices of the
+//! canister's module, so the original `name` section is replaced by one that
+//! is keyed by the instrumented indices (see [`function_names`]).
+//!
+//! The names are extended with the source location of the function if the
+//! module carries debug information:
+//!  * DWARF line number programs are used to append the file and line at
+//!    which the function starts, e.g. `foo at src/lib.rs:42`.
+//!  * Otherwise, if the module references a source map via the
+//!    `sourceMappingURL` section, the offset of the function body in the
+//!    original binary is appended, e.g. `foo at wasm offset 0x1a2`, which can
+//!    be resolved with the source map.
+//!
+//! Locations are only resolved per function and not per instruction, because
+//! instrumentation rewrites the code and the instruction offsets of the
+//! compiled module no longer match the ones of the original binary.
+//!
+//! Symbolization is best effort: malformed or unsupported debug information is
+//! ignored. It is also bounded: DWARF sections larger than
+//! [`MAX_DEBUG_SECTIONS_SIZE`] are ignored and at most [`MAX_LINE_ROWS`] line
+//! rows are processed. The work done is reported in
+//! [`FunctionNames::processed_entries`] so that it can be accounted for in the
+//! compilation cost.
+
+use std::collections::BTreeMap;
+
+use gimli::{EndianSlice, LittleEndian};
+use wasmparser::{Name, NameSectionReader, Parser, Payload, TypeRef};
+
+/// The maximum length of a function name in bytes. Longer names are
+/// truncated.
+const MAX_FUNCTION_NAME_LEN: usize = 256;
+
+/// The maximum total size of the DWARF sections of a module. Modules with more
+/// debug information are symbolized as if they had none.
+const MAX_DEBUG_SECTIONS_SIZE: usize = 64 * 1024 * 1024;
+
+/// The maximum number of DWARF line rows processed per module. Functions
+/// whose rows come after this limit are not annotated with a location.
+const MAX_LINE_ROWS: u64 = 1_000_000;
+
+const NAME_SECTION: &str = "name";
+const SOURCE_MAPPING_URL_SECTION: &str = "sourceMappingURL";
+const DEBUG_SECTION_PREFIX: &str = ".debug_";
+
+type Reader<'a> = EndianSlice<'a, LittleEndian>;
+
+/// The names of the functions of a Wasm binary.
+pub(super) struct FunctionNames {
+    /// The function names keyed by their index in the function index space
+    /// of the binary.
+    pub names: BTreeMap<u32, String>,
+    /// The number of function names and DWARF line rows that were processed
+    /// to derive the names.
+    pub processed_entries: u64,
+}
+
+/// Returns the names of the functions of the given Wasm binary.
+pub(super) fn function_names(wasm: &[u8]) -> FunctionNames {
+    let mut num_imported_functions = 0;
+    let mut code_section_start = 0;
+    let mut body_ranges = vec![];
+    let mut names = BTreeMap::new();
+    let mut has_source_map = false;
+    let mut debug_sections = BTreeMap::new();
+
+    for payload in Parser::new(0).parse_all(wasm) {
+        let Ok(payload) = payload else {
+            break;
+        };
+        match payload {
+            Payload::ImportSection(reader) => {
+                for import in reader.into_iter().flatten() {
+                    if let TypeRef::Func(_) = import.ty {
+                        num_imported_functions += 1;
+                    }
+                }
+            }
+            Payload::CodeSectionStart { range, .. } => code_section_start = range.start,
+            Payload::CodeSectionEntry(body) => body_ranges.push(body.range()),
+            Payload::CustomSection(reader) => match reader.name() {
+                NAME_SECTION => {
+                    let reader = NameSectionReader::new(reader.data(), reader.data_offset());
+                    for name in reader.into_iter().flatten() {
+                        if let Name::Function(map) = name {
+                            for naming in map.into_iter().flatten() {
+                                names.insert(naming.index, naming.name.to_string());
+                            }
+                        }
+                    }
+                }
+                SOURCE_MAPPING_URL_SECTION => has_source_map = true,
+                name if name.starts_with(DEBUG_SECTION_PREFIX) => {
+                    debug_sections.insert(name, reader.data());
+                }
+                _ => {}
+            },
+            _ => {}
+        }
+    }
+    let mut processed_entries = names.len() as u64;
+
+    let debug_sections_size: usize = debug_sections.values().map(|data| data.len()).sum();
+    let line_rows = if debug_sections_size <= MAX_DEBUG_SECTIONS_SIZE {
+        line_rows(&debug_sections)
+    } else {
+        None
+    };
+    let locations = if let Some(LineRows {
+        mut rows,
+        paths,
+        processed_rows,
+    }) = line_rows
+    {
+        processed_entries += processed_rows;
+        // DWARF addresses are offsets into the contents of the code section.
+        // A function is attributed all rows after the end of the previous
+        // function, so that rows pointing to its size field are included.
+        let mut start = 0;
+        let ranges = body_ranges.iter().map(|range| {
+            let end = range.end - code_section_start;
+            let code_range = (start, end);
+            start = end;
+            code_range
+        });
+        // The sort is stable, so the first row of an address is kept first.
+        rows.sort_by_key(|row| row.address);
+        ranges
+            .map(|(start, end)| {
+                let (start, end) = (start as u64, end as u64);
+                let first = rows.partition_point(|row| row.address < start);
+                rows.get(first).filter(|row| row.address < end).map(|row| {
+                    let path = row
+                        .path
+                        .and_then(|path| paths[path].as_deref())
+                        .unwrap_or("<unknown>");
+                    format!("{}:{}", path, row.line)
+                })
+            })
+            .collect()
+    } else if has_source_map {
+        body_ranges
+            .iter()
+            .map(|range| Some(format!("wasm offset {:#x}", range.start)))
+            .collect()
+    } else {
+        vec![]
+    };
+
+    for (i, location) in locations.into_iter().enumerate() {
+        if let Some(location) = location {
+            let index = num_imported_functions + i as u32;
+            let name = names
+                .remove(&index)
+                .unwrap_or_else(|| format!("<wasm function {}>", index));
+            names.insert(index, format!("{} at {}", name, location));
+        }
+    }
+
+    for name in names.values_mut() {
+        truncate(name, MAX_FUNCTION_NAME_LEN);
+    }
+    FunctionNames {
+        names,
+        processed_entries,
+    }
+}
+
+/// Encodes the given function names as a `name` custom section.
+pub(super) fn name_section(names: &BTreeMap<u32, String>) -> wasm_encoder::NameSection {
+    let mut function_names = wasm_encoder::NameMap::new();
+    for (index, name) in names {
+        function_names.append(*index, name);
+    }
+    let mut section = wasm_encoder::NameSection::new();
+    section.functions(&function_names);
+    section
+}
+
+fn truncate(name: &mut String, max_len: usize) {
+    if name.len() > max_len {
+        let mut len = max_len;
+        while !name.is_char_boundary(len) {
+            len -= 1;
+        }
+        name.truncate(len);
+    }
+}
+
+/// A row of a DWARF line number table with a non-zero line that does not end
+/// a sequence.
+struct LineRow {
+    address: u64,
+    /// The index of the file path in [`LineRows::paths`].
+    path: Option<usize>,
+    line: u64,
+}
+
+/// The rows of the line number programs of all compilation units.
+struct LineRows {
+    rows: Vec<LineRow>,
+    /// The file paths of all line number programs.
+    paths: Vec<Option<String>>,
+    /// The number of rows processed, including rows without a line.
+    processed_rows: u64,
+}
+
+/// Returns the rows of the line number programs of all compilation units in
+/// the given DWARF sections, or `None` if there are none. Parsing stops at
+/// the first malformed unit or after [`MAX_LINE_ROWS`] rows.
+fn line_rows(debug_sections: &BTreeMap<&str, &[u8]>) -> Option<LineRows> {
+    let dwarf = gimli::Dwarf::load(|id| -> gimli::Result<Reader> {
+        let data = debug_sections.get(id.name()).copied().unwrap_or_default();
+        Ok(Reader::new(data, LittleEndian))
+    })
+    .ok()?;
+
+    let mut line_rows = LineRows {
+        rows: vec![],
+        paths: vec![],
+        processed_rows: 0,
+    };
+    let mut units = dwarf.units();
+    while let Ok(Some(header)) = units.next() {
+        let Ok(unit) = dwarf.unit(header) else {
+            break;
+        };
+        let Some(program) = unit.line_program.clone() else {
+            continue;
+        };
+        if append_line_rows(&dwarf, &unit, program, &mut line_rows).is_err() {
+            break;
+        }
+    }
+    (line_rows.processed_rows > 0).then_some(line_rows)
+}
+
+/// Appends the rows and file paths of a single line number program.
+fn append_line_rows(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    program: gimli::IncompleteLineProgram<Reader>,
+    line_rows: &mut LineRows,
+) -> gimli::Result<()> {
+    let header = program.header();
+    // File indices start at 1 before DWARF 5.
+    let first_file_index = if header.version() >= 5 { 0 } else { 1 };
+    let first_path = line_rows.paths.len();
+    line_rows.paths.extend(
+        header
+            .file_names()
+            .iter()
+            .map(|file| file_path(dwarf, unit, header, file)),
+    );
+    let num_paths = line_rows.paths.len() - first_path;
+
+    let mut rows = program.rows();
+    while let Some((_, row)) = rows.next_row()? {
+        if line_rows.processed_rows >= MAX_LINE_ROWS {
+            break;
+        }
+        line_rows.processed_rows += 1;
+        // The end of a sequence is the address after its last instruction.
+        if row.end_sequence() {
+            continue;
+        }
+        let Some(line) = row.line() else {
+            continue;
+        };
+        let path = row
+            .file_index()
+            .checked_sub(first_file_index)
+            .filter(|index| *index < num_paths as u64)
+            .map(|index| first_path + index as usize);
+        line_rows.rows.push(LineRow {
+            address: row.address(),
+            path,
+            line: line.get(),
+        });
+    }
+    Ok(())
+}
+
+/// Returns the path of a file entry. Paths relative to the compilation
+/// directory are kept relative to avoid leaking the build environment.
+fn file_path(
+    dwarf: &gimli::Dwarf<Reader>,
+    unit: &gimli::Unit<Reader>,
+    header: &gimli::LineProgramHeader<Reader>,
+    file: &gimli::FileEntry<Reader>,
+) -> Option<String> {
+    let path = dwarf.attr_string(unit, file.path_name()).ok()?;
+    let path = path.to_string_lossy();
+    if file.directory_index() != 0 && !path.starts_with('/') {
+        if let Some(directory) = file.directory(header) {
+            let directory = dwarf.attr_string(unit, directory).ok()?;
+            return Some(format!(
+                "{}/{}",
+                directory.to_string_lossy().trim_end_matches('/'),
+                path
+            ));
+        }
+    }
+    Some(path.into_owned())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use gimli::write::{Address, DwarfUnit, EndianVec, LineProgram, LineString, Sections};
+    use wasm_encoder::{CustomSection, Encode};
+
+    const WAT: &str = r#"
+        (module
+            (import "ic0" "trap" (func $trap (param i32 i32)))
+            (func $foo nop)
+            (func $bar nop)
+        )"#;
+
+    fn with_custom_section(mut wasm: Vec<u8>, name: &str, data: &[u8]) -> Vec<u8> {
+        wasm.push(0);
+        CustomSection {
+            name: name.into(),
+            data: data.into(),
+        }
+        .encode(&mut wasm);
+        wasm
+    }
+
+    // Appends the DWARF sections of a compilation unit whose line number
+    // program places the function starting at address `start` at
+    // `src/lib.rs:42`.
+    fn with_debug_info(mut wasm: Vec<u8>, start: u64) -> Vec<u8> {
+        let encoding = gimli::Encoding {
+            format: gimli::Format::Dwarf32,
+            version: 4,
+            address_size: 4,
+        };
+        let mut program = LineProgram::new(
+            encoding,
+            gimli::LineEncoding::default(),
+            LineString::String(b"/build".to_vec()),
+            LineString::String(b"lib.rs".to_vec()),
+            None,
+        );
+        let directory = program.add_directory(LineString::String(b"src".to_vec()));
+        let file = program.add_file(LineString::String(b"lib.rs".to_vec()), directory, None);
+        program.begin_sequence(Some(Address::Constant(start)));
+        program.row().file = file;
+        program.row().line = 42;
+        program.generate_row();
+        // The function body is 3 bytes long.
+        program.end_sequence(3);
+
+        let mut unit = DwarfUnit::new(encoding);
+        unit.unit.line_program = program;
+        let mut sections = Sections::new(EndianVec::new(LittleEndian));
+        unit.write(&mut sections).unwrap();
+        sections
+            .for_each(|id, data| -> gimli::write::Result<()> {
+                if !data.slice().is_empty() {
+                    wasm = with_custom_section(std::mem::take(&mut wasm), id.name(), data.slice());
+                }
+                Ok(())
+            })
+            .unwrap();
+        wasm
+    }
+
+    #[test]
+    fn reads_names_from_name_section() {
+        let function_names = function_names(&wat::parse_str(WAT).unwrap());
+        assert_eq!(
+            function_names.names,
+            BTreeMap::from([
+                (0, "trap".to_string()),
+                (1, "foo".to_string()),
+                (2, "bar".to_string()),
+            ])
+        );
+        assert_eq!(function_names.processed_entries, 3);
+    }
+
+    #[test]
+    fn appends_dwarf_locations() {
+        // The code section contents start with the number of functions,
+        // followed by the size and body of each function, so the body of the
+        // second function starts at address 6.
+        let wasm = with_debug_info(wat::parse_str(WAT).unwrap(), 6);
+        let function_names = function_names(&wasm);
+        assert_eq!(function_names.names[&1], "foo");
+        assert_eq!(function_names.names[&2], "bar at src/lib.rs:42");
+        // Three names plus the row and the end of the sequence.
+        assert_eq!(function_names.processed_entries, 5);
+    }
+
+    #[test]
+    fn appends_source_map_offsets() {
+        let wasm = wat::parse_str(WAT).unwrap();
+        let wasm = with_custom_section(wasm, "sourceMappingURL", b"\x0dmain.wasm.map");
+        let names = function_names(&wasm).names;
+        assert!(
+            names[&1].starts_with("foo at wasm offset 0x"),
+            "{}",
+            names[&1]
+        );
+        assert!(
+            names[&2].starts_with("bar at wasm offset 0x"),
+            "{}",
+            names[&2]
+        );
+        assert_eq!(names[&0], "trap");
+    }
+
+    #[test]
+    fn truncates_long_names_at_char_boundary() {
+        let mut name = "ü".repeat(200);
+        truncate(&mut name, 255);
+        assert_eq!(name, "ü".repeat(127));
+    }
+
+    #[test]
+    fn ignores_malformed_debug_info() {
+        let wasm = with_custom_section(wat::parse_str(WAT).unwrap(), ".debug_info", &[1, 2, 3]);
+        let wasm = with_custom_section(wasm, ".debug_line", &[1, 2, 3]);
+        assert_eq!(function_names(&wasm).names[&2], "bar");
+    }
+}
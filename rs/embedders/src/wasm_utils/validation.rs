@@ -26,6 +26,7 @@ use crate::{
     MAX_WASM_STACK_SIZE, MIN_GUARD_REGION_SIZE,
 };
 use wasmparser::{CompositeType, ExternalKind, FuncType, Operator, TypeRef, ValType};
+use wasmtime_environ::WASM_PAGE_SIZE;
 
 /// Symbols that are reserved and cannot be exported by canisters.
 #[doc(hidden)] // pub for usage in tests
@@ -168,8 +169,13 @@ fn get_valid_system_apis_32_only() -> HashMap<String, HashMap<String, FunctionSi
         .collect()
 }
 
-// Returns system api functions available both in wasm32 and wasm64
-fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSignature>> {
+// Returns system api functions available both in wasm32 and wasm64.
+//
+// Pointers into the Wasm heap, as well as the sizes of heap regions, are of
+// type `ptr_type`: `i32` for Wasm32 and `i64` for Wasm64 modules.
+fn get_valid_system_apis_common(
+    ptr_type: ValType,
+) -> HashMap<String, HashMap<String, FunctionSignature>> {
     let valid_system_apis = vec![
         (
             // Public methods
@@ -178,7 +184,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -187,7 +193,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -198,7 +204,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -207,7 +213,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -218,7 +224,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -227,7 +233,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -242,6 +248,16 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
                 },
             )],
         ),
+        (
+            "msg_tip",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ValType::I64],
+                },
+            )],
+        ),
         (
             "msg_reject_code",
             vec![(
@@ -258,7 +274,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -267,7 +283,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -277,7 +293,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -297,7 +313,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -308,7 +324,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -317,7 +333,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -328,16 +344,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                        ValType::I32,
-                    ],
+                    param_types: vec![ptr_type; 8],
                     return_type: vec![],
                 },
             )],
@@ -347,7 +354,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -357,7 +364,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -378,7 +385,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -468,7 +475,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -478,7 +485,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -499,7 +506,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
                 API_VERSION_IC0,
                 FunctionSignature {
                     param_types: vec![],
-                    return_type: vec![ValType::I32],
+                    return_type: vec![ptr_type],
                 },
             )],
         ),
@@ -508,7 +515,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -548,7 +555,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -558,7 +565,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -568,7 +575,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32],
+                    param_types: vec![ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -578,7 +585,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I64, ValType::I64, ValType::I32],
+                    param_types: vec![ValType::I64, ValType::I64, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -588,7 +595,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I32, ValType::I32],
+                    param_types: vec![ptr_type, ptr_type],
                     return_type: vec![ValType::I32],
                 },
             )],
@@ -608,7 +615,7 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
             vec![(
                 API_VERSION_IC0,
                 FunctionSignature {
-                    param_types: vec![ValType::I64, ValType::I64, ValType::I32],
+                    param_types: vec![ValType::I64, ValType::I64, ptr_type],
                     return_type: vec![],
                 },
             )],
@@ -633,6 +640,26 @@ fn get_valid_system_apis_common() -> HashMap<String, HashMap<String, FunctionSig
                 },
             )],
         ),
+        (
+            "trace_id_size",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![],
+                    return_type: vec![ptr_type],
+                },
+            )],
+        ),
+        (
+            "trace_id_copy",
+            vec![(
+                API_VERSION_IC0,
+                FunctionSignature {
+                    param_types: vec![ptr_type, ptr_type, ptr_type],
+                    return_type: vec![],
+                },
+            )],
+        ),
     ];
 
     valid_system_apis
@@ -777,8 +804,14 @@ fn validate_import_section(module: &Module) -> Result<WasmImportsDetails, WasmVa
     let mut imports_details = WasmImportsDetails::default();
 
     if !module.imports.is_empty() {
-        let mut valid_system_apis = get_valid_system_apis_32_only();
-        valid_system_apis.extend(get_valid_system_apis_common());
+        let valid_system_apis = match main_memory_type(module) {
+            WasmMemoryType::Wasm32 => {
+                let mut valid_system_apis = get_valid_system_apis_32_only();
+                valid_system_apis.extend(get_valid_system_apis_common(ValType::I32));
+                valid_system_apis
+            }
+            WasmMemoryType::Wasm64 => get_valid_system_apis_common(ValType::I64),
+        };
         for entry in &module.imports {
             let import_module = entry.module;
             let field = entry.name;
@@ -1008,6 +1041,24 @@ fn validate_data_section(module: &Module) -> Result<(), WasmValidationError> {
     Ok(())
 }
 
+// Checks that the initial size of a Wasm64 heap does not exceed
+// `max_wasm64_memory_size`. Wasm32 heaps are limited by their address space.
+fn validate_memory_section(
+    module: &Module,
+    max_wasm64_memory_size: NumBytes,
+) -> Result<(), WasmValidationError> {
+    if let Some(memory) = module.memories.first() {
+        let max_pages = max_wasm64_memory_size.get() / WASM_PAGE_SIZE as u64;
+        if memory.memory64 && memory.initial > max_pages {
+            return Err(WasmValidationError::InvalidMemorySection(format!(
+                "Wasm64 memory of {} pages exceeds the maximum of {} pages.",
+                memory.initial, max_pages
+            )));
+        }
+    }
+    Ok(())
+}
+
 // Checks that no more than `max_globals` are defined in the module
 // and all globals have supported type.
 fn validate_global_section(module: &Module, max_globals: usize) -> Result<(), WasmValidationError> {
@@ -1204,6 +1255,8 @@ fn wasm_function_complexity(
             | BrTable { .. }
             | Call { .. }
             | CallIndirect { .. }
+            | ReturnCall { .. }
+            | ReturnCallIndirect { .. }
             | MemoryGrow { .. } => 50,
             TableGrow { .. } => {
                 return Err(WasmValidationError::UnsupportedWasmInstruction {
@@ -1397,9 +1450,20 @@ pub fn wasmtime_validation_config(embedders_config: &EmbeddersConfig) -> wasmtim
     config.generate_address_map(false);
     // The signal handler uses Posix signals, not Mach ports on MacOS.
     config.macos_use_mach_ports(false);
-    config.wasm_backtrace(false);
+    // Frames are named from the `name` section written by instrumentation.
+    // Its names carry the source location of each function, not of each trap
+    // site, so Wasmtime does not need to parse DWARF itself.
+    config.wasm_backtrace(
+        embedders_config.feature_flags.canister_backtrace
+            == ic_config::flag_status::FlagStatus::Enabled,
+    );
     config.wasm_backtrace_details(wasmtime::WasmBacktraceDetails::Disable);
     config.wasm_bulk_memory(true);
+    // There is no switch for the exception-handling proposal: it is not yet
+    // supported by Cranelift, so Wasmtime always rejects modules using it.
+    // TODO: Accept exception handling once Wasmtime supports it. This also
+    // requires costs for `try_table`, `throw` and `throw_ref` in
+    // `instruction_to_cost()` and `injections()`.
     config.wasm_function_references(false);
     config.wasm_gc(false);
     if embedders_config.feature_flags.wasm64 == ic_config::flag_status::FlagStatus::Enabled {
@@ -1414,8 +1478,12 @@ pub fn wasmtime_validation_config(embedders_config: &EmbeddersConfig) -> wasmtim
     config.wasm_reference_types(true);
     // The relaxed SIMD instructions are disable for determinism.
     config.wasm_relaxed_simd(false);
-    // Tail calls may be enabled in the future.
-    config.wasm_tail_call(false);
+    if embedders_config.feature_flags.wasm_tail_call == ic_config::flag_status::FlagStatus::Enabled
+    {
+        config.wasm_tail_call(true);
+    } else {
+        config.wasm_tail_call(false);
+    }
 
     config
         // The maximum size in bytes where a linear memory is considered
@@ -1487,6 +1555,7 @@ fn check_code_section_size(wasm: &BinaryEncodedWasm) -> Result<(), WasmValidatio
 /// * Export
 /// * Code
 /// * Data
+/// * Memory
 /// * Global
 /// * Function
 /// * CustomSections
@@ -1507,6 +1576,7 @@ pub(super) fn validate_wasm_binary<'a>(
         config.max_sum_exported_function_name_lengths,
     )?;
     validate_data_section(&module)?;
+    validate_memory_section(&module, config.max_wasm64_memory_size)?;
     let num_tables = module.tables.len();
     validate_global_section(&module, config.max_globals)?;
     validate_function_section(&module, config.max_functions)?;
@@ -7,6 +7,7 @@ use std::{
     cell::Ref,
     collections::HashMap,
     convert::TryFrom,
+    hash::Hash,
     mem::size_of,
     sync::{atomic::Ordering, Arc, Mutex},
 };
@@ -19,8 +20,10 @@ use wasmtime::{
 
 pub use host_memory::WasmtimeMemoryCreator;
 use ic_config::{embedders::Config as EmbeddersConfig, flag_status::FlagStatus};
+use ic_crypto_sha2::Sha256;
 use ic_interfaces::execution_environment::{
-    HypervisorError, HypervisorResult, InstanceStats, SystemApi, TrapCode,
+    BacktraceFrame, CanisterBacktrace, HypervisorError, HypervisorResult, InstanceStats, SystemApi,
+    TrapCode,
 };
 use ic_logger::{debug, error, fatal, ReplicaLogger};
 use ic_replicated_state::{
@@ -30,15 +33,16 @@ use ic_replicated_state::{
 use ic_sys::PAGE_SIZE;
 use ic_types::{
     methods::{FuncRef, WasmMethod},
-    CanisterId, NumInstructions, NumOsPages, MAX_STABLE_MEMORY_IN_BYTES,
+    replica_version::REPLICA_BINARY_HASH,
+    CanisterId, NumInstructions, NumOsPages, ReplicaVersion, MAX_STABLE_MEMORY_IN_BYTES,
 };
 use ic_wasm_types::{BinaryEncodedWasm, WasmEngineError};
 use memory_tracker::{DirtyPageTracking, PageBitmap, SigsegvMemoryTracker};
 use signal_stack::WasmtimeSignalStack;
 
 use crate::wasm_utils::instrumentation::{
-    WasmMemoryType, ACCESSED_PAGES_COUNTER_GLOBAL_NAME, DIRTY_PAGES_COUNTER_GLOBAL_NAME,
-    INSTRUCTIONS_COUNTER_GLOBAL_NAME,
+    InjectedImports, WasmMemoryType, ACCESSED_PAGES_COUNTER_GLOBAL_NAME,
+    DIRTY_PAGES_COUNTER_GLOBAL_NAME, INSTRUCTIONS_COUNTER_GLOBAL_NAME,
 };
 use crate::{
     serialized_module::SerializedModuleBytes, wasm_utils::validation::wasmtime_validation_config,
@@ -60,8 +64,38 @@ pub(crate) const STABLE_BYTEMAP_MEMORY_NAME: &str = "stable_bytemap_memory";
 pub(crate) const MAX_STORE_TABLES: usize = 1;
 pub(crate) const MAX_STORE_TABLE_ELEMENTS: u32 = 1_000_000;
 
-fn wasmtime_error_to_hypervisor_error(err: anyhow::Error) -> HypervisorError {
-    match err.downcast::<wasmtime::Trap>() {
+/// The maximum number of frames of a canister backtrace. Only the innermost
+/// frames are kept to bound the size of error messages and log records.
+const MAX_BACKTRACE_FRAMES: usize = 20;
+
+/// Converts the Wasmtime backtrace attached to `err`, if any, to a canister
+/// backtrace. The function indices are mapped back to the indices of the
+/// canister's module by skipping the imports injected by instrumentation.
+fn canister_backtrace(err: &anyhow::Error, injected_imports: u32) -> Option<CanisterBacktrace> {
+    let backtrace = err.downcast_ref::<wasmtime::WasmBacktrace>()?;
+    let frames = backtrace.frames();
+    if frames.is_empty() {
+        return None;
+    }
+    Some(CanisterBacktrace {
+        frames: frames
+            .iter()
+            .take(MAX_BACKTRACE_FRAMES)
+            .map(|frame| BacktraceFrame {
+                func_index: frame.func_index().saturating_sub(injected_imports),
+                func_name: frame.func_name().map(str::to_string),
+            })
+            .collect(),
+        omitted_frames: frames.len().saturating_sub(MAX_BACKTRACE_FRAMES),
+    })
+}
+
+fn wasmtime_error_to_hypervisor_error(
+    err: anyhow::Error,
+    injected_imports: u32,
+) -> HypervisorError {
+    let backtrace = canister_backtrace(&err, injected_imports);
+    let err = match err.downcast::<wasmtime::Trap>() {
         Ok(trap) => trap_code_to_hypervisor_error(trap),
         Err(err) => {
             // The error could be either a compile error or some other error.
@@ -90,28 +124,34 @@ fn wasmtime_error_to_hypervisor_error(err: anyhow::Error) -> HypervisorError {
                     error: BAD_SIGNATURE_MESSAGE.to_string(),
                 };
             }
-            HypervisorError::Trapped(TrapCode::Other)
+            HypervisorError::Trapped {
+                trap_code: TrapCode::Other,
+                backtrace: None,
+            }
         }
-    }
+    };
+    err.with_backtrace(backtrace)
 }
 
 fn trap_code_to_hypervisor_error(trap: wasmtime::Trap) -> HypervisorError {
-    match trap {
-        wasmtime::Trap::StackOverflow => HypervisorError::Trapped(TrapCode::StackOverflow),
-        wasmtime::Trap::MemoryOutOfBounds => HypervisorError::Trapped(TrapCode::HeapOutOfBounds),
-        wasmtime::Trap::TableOutOfBounds => HypervisorError::Trapped(TrapCode::TableOutOfBounds),
-        wasmtime::Trap::BadSignature => HypervisorError::ToolchainContractViolation {
-            error: BAD_SIGNATURE_MESSAGE.to_string(),
-        },
-        wasmtime::Trap::IntegerDivisionByZero => {
-            HypervisorError::Trapped(TrapCode::IntegerDivByZero)
-        }
-        wasmtime::Trap::UnreachableCodeReached => HypervisorError::Trapped(TrapCode::Unreachable),
-        _ => {
-            // The `wasmtime::TrapCode` enum is marked as #[non_exhaustive]
-            // so we have to use the wildcard matching here.
-            HypervisorError::Trapped(TrapCode::Other)
+    let trap_code = match trap {
+        wasmtime::Trap::StackOverflow => TrapCode::StackOverflow,
+        wasmtime::Trap::MemoryOutOfBounds => TrapCode::HeapOutOfBounds,
+        wasmtime::Trap::TableOutOfBounds => TrapCode::TableOutOfBounds,
+        wasmtime::Trap::BadSignature => {
+            return HypervisorError::ToolchainContractViolation {
+                error: BAD_SIGNATURE_MESSAGE.to_string(),
+            }
         }
+        wasmtime::Trap::IntegerDivisionByZero => TrapCode::IntegerDivByZero,
+        wasmtime::Trap::UnreachableCodeReached => TrapCode::Unreachable,
+        // The `wasmtime::TrapCode` enum is marked as #[non_exhaustive]
+        // so we have to use the wildcard matching here.
+        _ => TrapCode::Other,
+    };
+    HypervisorError::Trapped {
+        trap_code,
+        backtrace: None,
     }
 }
 
@@ -207,6 +247,33 @@ impl WasmtimeEmbedder {
         config
     }
 
+    /// Fingerprint of everything a serialized module depends on besides the
+    /// Wasm itself: the replica version and binary (which determine the
+    /// instrumentation and the system API), the Wasmtime version and engine
+    /// settings, plus the embedder configuration used for instrumentation.
+    /// Modules persisted by the compilation cache are only reused if their
+    /// fingerprint matches.
+    pub fn compilation_cache_fingerprint(
+        embedder_config: &EmbeddersConfig,
+    ) -> HypervisorResult<String> {
+        let engine = wasmtime::Engine::new(&Self::wasmtime_execution_config(embedder_config))
+            .map_err(|_| {
+                HypervisorError::WasmEngineError(WasmEngineError::FailedToInitializeEngine)
+            })?;
+        let mut hasher = Sha256::new();
+        // Development builds share a replica version, the binary hash tells them apart.
+        ReplicaVersion::default().hash(&mut hasher);
+        REPLICA_BINARY_HASH.get().hash(&mut hasher);
+        engine.precompile_compatibility_hash().hash(&mut hasher);
+        bincode::serialize_into(&mut hasher, embedder_config).map_err(|err| {
+            HypervisorError::WasmEngineError(WasmEngineError::Unexpected(format!(
+                "Failed to serialize embedder config: {}",
+                err
+            )))
+        })?;
+        Ok(hex::encode(hasher.finish()))
+    }
+
     fn create_engine(&self) -> HypervisorResult<Engine> {
         let mut config = Self::wasmtime_execution_config(&self.config);
         let mem_creator = Arc::new(WasmtimeMemoryCreator::new(Arc::clone(
@@ -241,13 +308,22 @@ impl WasmtimeEmbedder {
             }
         }
 
-        system_api::syscalls(
-            &mut linker,
-            self.config.feature_flags,
-            self.config.stable_memory_dirty_page_limit,
-            self.config.stable_memory_accessed_page_limit,
-            main_memory_type,
-        );
+        match main_memory_type {
+            WasmMemoryType::Wasm32 => system_api::syscalls::<u32>(
+                &mut linker,
+                self.config.feature_flags,
+                self.config.stable_memory_dirty_page_limit,
+                self.config.stable_memory_accessed_page_limit,
+                main_memory_type,
+            ),
+            WasmMemoryType::Wasm64 => system_api::syscalls::<u64>(
+                &mut linker,
+                self.config.feature_flags,
+                self.config.stable_memory_dirty_page_limit,
+                self.config.stable_memory_accessed_page_limit,
+                main_memory_type,
+            ),
+        }
 
         let instance_pre = linker.instantiate_pre(module).map_err(|e| {
             HypervisorError::WasmEngineError(WasmEngineError::FailedToInstantiateModule(format!(
@@ -401,73 +477,11 @@ impl WasmtimeEmbedder {
             instance.get_global(&mut store, INSTRUCTIONS_COUNTER_GLOBAL_NAME);
 
         if let Some(exported_globals) = exported_globals {
-            let instance_globals = get_exported_globals(
-                self.config.feature_flags.wasm_native_stable_memory,
-                &instance,
-                &mut store,
-            );
-
-            if exported_globals.len() != instance_globals.len() {
-                fatal!(
-                self.log,
-                "Given number of exported globals {} is not equal to the number of instance exported globals {}",
-                exported_globals.len(),
-                instance_globals.len()
-            );
-            }
-
-            // set the globals to persisted values
-            for ((ix, v), instance_global) in exported_globals
-                .iter()
-                .enumerate()
-                .zip(instance_globals.iter())
-            {
-                if instance_global.ty(&mut store).mutability() == Mutability::Var {
-                    instance_global
-                        .set(
-                            &mut store,
-                            match v {
-                                Global::I32(val) => Val::I32(*val),
-                                Global::I64(val) => Val::I64(*val),
-                                Global::F32(val) => Val::F32((val).to_bits()),
-                                Global::F64(val) => Val::F64((val).to_bits()),
-                                Global::V128(val) => Val::V128((*val).into()),
-                            },
-                        )
-                        .unwrap_or_else(|e| {
-                            let v = match v {
-                                Global::I32(val) => (val).to_string(),
-                                Global::I64(val) => (val).to_string(),
-                                Global::F32(val) => (val).to_string(),
-                                Global::F64(val) => (val).to_string(),
-                                Global::V128(val) => (val).to_string(),
-                            };
-                            fatal!(
-                                self.log,
-                                "error while setting exported global {} to {}: {}",
-                                ix,
-                                v,
-                                e
-                            )
-                        })
-                } else {
-                    debug!(
-                        self.log,
-                        "skipping initialization of immutable global {}", ix
-                    );
-                }
-            }
+            self.set_exported_globals(&instance, &mut store, exported_globals);
         }
 
         if self.config.feature_flags.wasm_native_stable_memory == FlagStatus::Enabled {
-            instance.get_global(&mut store, DIRTY_PAGES_COUNTER_GLOBAL_NAME)
-                .expect("Counter for dirty pages global should have been added with native stable memory enabled.")
-                .set(&mut store, Val::I64(current_dirty_page_limit.get() as i64))
-                .expect("Couldn't set dirty page counter global");
-            instance.get_global(&mut store, ACCESSED_PAGES_COUNTER_GLOBAL_NAME)
-                .expect("Counter for accessed pages global should have been added with native stable memory enabled.")
-                .set(&mut store, Val::I64(self.config.stable_memory_accessed_page_limit.get() as i64))
-                .expect("Couldn't set dirty page counter global");
+            self.set_stable_memory_counters(&instance, &mut store, current_dirty_page_limit);
         }
 
         let mut memories = HashMap::new();
@@ -499,6 +513,175 @@ impl WasmtimeEmbedder {
         })
     }
 
+    fn set_exported_globals(
+        &self,
+        instance: &Instance,
+        mut store: &mut Store<StoreData>,
+        exported_globals: &[Global],
+    ) {
+        let instance_globals = get_exported_globals(
+            self.config.feature_flags.wasm_native_stable_memory,
+            instance,
+            &mut store,
+        );
+
+        if exported_globals.len() != instance_globals.len() {
+            fatal!(
+                self.log,
+                "Given number of exported globals {} is not equal to the number of instance exported globals {}",
+                exported_globals.len(),
+                instance_globals.len()
+            );
+        }
+
+        // set the globals to persisted values
+        for ((ix, v), instance_global) in exported_globals
+            .iter()
+            .enumerate()
+            .zip(instance_globals.iter())
+        {
+            if instance_global.ty(&mut store).mutability() == Mutability::Var {
+                instance_global
+                    .set(
+                        &mut store,
+                        match v {
+                            Global::I32(val) => Val::I32(*val),
+                            Global::I64(val) => Val::I64(*val),
+                            Global::F32(val) => Val::F32((val).to_bits()),
+                            Global::F64(val) => Val::F64((val).to_bits()),
+                            Global::V128(val) => Val::V128((*val).into()),
+                        },
+                    )
+                    .unwrap_or_else(|e| {
+                        let v = match v {
+                            Global::I32(val) => (val).to_string(),
+                            Global::I64(val) => (val).to_string(),
+                            Global::F32(val) => (val).to_string(),
+                            Global::F64(val) => (val).to_string(),
+                            Global::V128(val) => (val).to_string(),
+                        };
+                        fatal!(
+                            self.log,
+                            "error while setting exported global {} to {}: {}",
+                            ix,
+                            v,
+                            e
+                        )
+                    })
+            } else {
+                debug!(
+                    self.log,
+                    "skipping initialization of immutable global {}", ix
+                );
+            }
+        }
+    }
+
+    fn set_stable_memory_counters(
+        &self,
+        instance: &Instance,
+        mut store: &mut Store<StoreData>,
+        dirty_page_limit: NumOsPages,
+    ) {
+        instance.get_global(&mut store, DIRTY_PAGES_COUNTER_GLOBAL_NAME)
+            .expect("Counter for dirty pages global should have been added with native stable memory enabled.")
+            .set(&mut store, Val::I64(dirty_page_limit.get() as i64))
+            .expect("Couldn't set dirty page counter global");
+        instance.get_global(&mut store, ACCESSED_PAGES_COUNTER_GLOBAL_NAME)
+            .expect("Counter for accessed pages global should have been added with native stable memory enabled.")
+            .set(&mut store, Val::I64(self.config.stable_memory_accessed_page_limit.get() as i64))
+            .expect("Couldn't set dirty page counter global");
+    }
+
+    /// Prepares an instance that finished executing a query for the next
+    /// query against the same canister state: the changes to the memories are
+    /// discarded and the globals are restored to `exported_globals`. The
+    /// globals and memory sizes must be the ones of that state. Returns `None`
+    /// if the instance cannot be reused, e.g. because the query grew one of
+    /// its memories.
+    pub fn reset_instance(
+        &self,
+        mut instance: WasmtimeInstance,
+        exported_globals: &[Global],
+        heap_size: NumWasmPages,
+        stable_memory_size: NumWasmPages,
+    ) -> Option<WasmtimeInstance> {
+        let wasm_native_stable_memory = self.config.feature_flags.wasm_native_stable_memory;
+        if instance.heap_size(CanisterMemoryType::Heap) != heap_size
+            || (wasm_native_stable_memory == FlagStatus::Enabled
+                && instance.heap_size(CanisterMemoryType::Stable) != stable_memory_size)
+        {
+            return None;
+        }
+
+        for tracker in instance.memory_trackers.values() {
+            if let Err(err) = tracker.lock().unwrap().reset() {
+                error!(
+                    self.log,
+                    "Failed to reset the memory of an instance: {}", err
+                );
+                return None;
+            }
+        }
+        for (flag, bytemap_name) in [
+            (
+                self.config.feature_flags.write_barrier,
+                WASM_HEAP_BYTEMAP_MEMORY_NAME,
+            ),
+            (wasm_native_stable_memory, STABLE_BYTEMAP_MEMORY_NAME),
+        ] {
+            if flag == FlagStatus::Disabled {
+                continue;
+            }
+            let Some(bytemap) = instance
+                .instance
+                .get_memory(&mut instance.store, bytemap_name)
+            else {
+                continue;
+            };
+            let bytemap = bytemap.data_mut(&mut instance.store);
+            if bytemap.is_empty() {
+                continue;
+            }
+            use nix::sys::mman;
+            // SAFETY: The bytemap is a private anonymous mapping created by
+            // the host memory creator, so dropping its pages zeroes it.
+            if let Err(err) = unsafe {
+                mman::madvise(
+                    bytemap.as_mut_ptr() as *mut _,
+                    bytemap.len(),
+                    mman::MmapAdvise::MADV_DONTNEED,
+                )
+            } {
+                error!(
+                    self.log,
+                    "Failed to reset the bytemap of an instance: {}", err
+                );
+                return None;
+            }
+        }
+
+        // Only queries reuse instances, so the limit is the one for messages.
+        let dirty_page_limit = self.config.stable_memory_dirty_page_limit.message;
+        self.set_exported_globals(&instance.instance, &mut instance.store, exported_globals);
+        if wasm_native_stable_memory == FlagStatus::Enabled {
+            self.set_stable_memory_counters(
+                &instance.instance,
+                &mut instance.store,
+                dirty_page_limit,
+            );
+        }
+        let store_data = instance.store.data_mut();
+        store_data.system_api = None;
+        store_data.num_stable_dirty_pages_from_non_native_writes = NumOsPages::from(0);
+        instance.instance_stats = InstanceStats::default();
+        #[cfg(debug_assertions)]
+        {
+            instance.stable_memory_dirty_page_limit = dirty_page_limit;
+        }
+        Some(instance)
+    }
+
     fn instantiate_memory(
         &self,
         memory_info: WasmMemoryInfo,
@@ -746,6 +929,10 @@ pub struct WasmtimeInstance {
 }
 
 impl WasmtimeInstance {
+    pub fn modification_tracking(&self) -> ModificationTracking {
+        self.modification_tracking
+    }
+
     pub fn into_store_data(self) -> StoreData {
         self.store.into_data()
     }
@@ -759,6 +946,7 @@ impl WasmtimeInstance {
     }
 
     fn invoke_export(&mut self, export: &str, args: &[Val]) -> HypervisorResult<()> {
+        let injected_imports = self.injected_imports();
         self.instance
             .get_export(&mut self.store, export)
             .ok_or_else(|| {
@@ -769,7 +957,12 @@ impl WasmtimeInstance {
                 error: "export is not a function".to_string(),
             })?
             .call(&mut self.store, args, &mut [])
-            .map_err(wasmtime_error_to_hypervisor_error)
+            .map_err(|err| wasmtime_error_to_hypervisor_error(err, injected_imports))
+    }
+
+    // The number of function imports injected by instrumentation.
+    fn injected_imports(&self) -> u32 {
+        InjectedImports::count(self.wasm_native_stable_memory) as u32
     }
 
     fn page_accesses(&mut self) -> HypervisorResult<PageAccessResults> {
@@ -924,6 +1117,15 @@ impl WasmtimeInstance {
         }
     }
 
+    /// Returns the type of the Wasm heap, defaulting to Wasm32 for modules
+    /// without a heap.
+    fn main_memory_type(&mut self) -> WasmMemoryType {
+        match self.get_memory(WASM_HEAP_MEMORY_NAME) {
+            Ok(memory) if memory.ty(&self.store).is_64() => WasmMemoryType::Wasm64,
+            _ => WasmMemoryType::Wasm32,
+        }
+    }
+
     fn set_instance_stats(&mut self, access_results: &PageAccessResults) {
         // Wasm stats.
         self.instance_stats.wasm_accessed_pages += access_results.wasm_num_accessed_pages;
@@ -952,15 +1154,25 @@ impl WasmtimeInstance {
     pub fn run(&mut self, func_ref: FuncRef) -> HypervisorResult<InstanceRunResult> {
         let _alt_sig_stack = unsafe { self.signal_stack.register() };
 
+        let injected_imports = self.injected_imports();
         let result = match &func_ref {
             FuncRef::Method(wasm_method) => self.invoke_export(&wasm_method.to_string(), &[]),
             FuncRef::QueryClosure(closure) | FuncRef::UpdateClosure(closure) => {
-                // As the 64-bit `env` values will be supported in `wasm64`,
-                // which is disabled for now, the conversion should always succeed.
-                let Ok(env32): Result<u32, _> = closure.env.try_into() else {
-                    return Err(HypervisorError::ToolchainContractViolation {
-                        error: format!("error converting additional value {} to u32", closure.env),
-                    });
+                // Closures of Wasm64 modules take a 64-bit `env` argument,
+                // those of Wasm32 modules a 32-bit one.
+                let env = match self.main_memory_type() {
+                    WasmMemoryType::Wasm32 => {
+                        let Ok(env32): Result<u32, _> = closure.env.try_into() else {
+                            return Err(HypervisorError::ToolchainContractViolation {
+                                error: format!(
+                                    "error converting additional value {} to u32",
+                                    closure.env
+                                ),
+                            });
+                        };
+                        Val::I32(env32 as i32)
+                    }
+                    WasmMemoryType::Wasm64 => Val::I64(closure.env as i64),
                 };
                 self.instance
                     .get_export(&mut self.store, "table")
@@ -980,8 +1192,8 @@ impl WasmtimeInstance {
                     .ok_or_else(|| HypervisorError::ToolchainContractViolation {
                         error: "unexpected null function reference".to_string(),
                     })?
-                    .call(&mut self.store, &[Val::I32(env32 as i32)], &mut [])
-                    .map_err(wasmtime_error_to_hypervisor_error)
+                    .call(&mut self.store, &[env], &mut [])
+                    .map_err(|err| wasmtime_error_to_hypervisor_error(err, injected_imports))
             }
         }
         .map_err(|e| {
@@ -997,7 +1209,9 @@ impl WasmtimeInstance {
                     error!(self.log, "[EXC-BUG] Canister {}: {}", cid, err);
                     HypervisorError::WasmEngineError(WasmEngineError::Unexpected(err))
                 }
-                Ok(Some(err)) => err,
+                // Traps reported by the System API happen in a host function,
+                // so their backtrace is the one Wasmtime recorded for `e`.
+                Ok(Some(err)) => err.with_backtrace(e.backtrace().cloned()),
                 Ok(None) => e,
                 Err(_) => e,
             }
@@ -1175,7 +1389,7 @@ impl WasmtimeInstance {
     }
 
     /// Returns the heap size.
-    /// Result is guaranteed to fit in a `u32`.
+    /// Result is guaranteed to fit in a `u32` for Wasm32 heaps.
     pub fn heap_size(&mut self, canister_memory_type: CanisterMemoryType) -> NumWasmPages {
         let name = match canister_memory_type {
             CanisterMemoryType::Heap => WASM_HEAP_MEMORY_NAME,
@@ -276,6 +276,47 @@ fn charge_direct_fee(
     Ok(())
 }
 
+/// The type of pointers into the Wasm heap, and of the sizes of heap regions,
+/// in System API calls: `u32` for Wasm32 and `u64` for Wasm64 modules.
+pub(crate) trait WasmAddress:
+    wasmtime::WasmTy
+    + TryInto<usize>
+    + TryInto<u32>
+    + Into<u64>
+    + TryFrom<usize>
+    + std::fmt::Display
+    + Copy
+    + Send
+    + Sync
+    + 'static
+{
+}
+
+impl WasmAddress for u32 {}
+impl WasmAddress for u64 {}
+
+/// Converts a Wasm address or size into a `usize`.
+fn to_usize<I: WasmAddress>(value: I) -> Result<usize, anyhow::Error> {
+    <I as TryInto<usize>>::try_into(value)
+        .map_err(|_| anyhow::Error::msg(format!("Wasm address {} does not fit into usize", value)))
+}
+
+/// Converts a Wasm table index into a `u32`.
+fn to_u32<I: WasmAddress>(value: I) -> Result<u32, anyhow::Error> {
+    <I as TryInto<u32>>::try_into(value)
+        .map_err(|_| anyhow::Error::msg(format!("Table index {} does not fit into u32", value)))
+}
+
+fn to_u64<I: WasmAddress>(value: I) -> u64 {
+    <I as Into<u64>>::into(value)
+}
+
+/// Converts the size of a System API result into the Wasm size type.
+fn to_wasm_size<I: WasmAddress>(size: usize, name: &str) -> Result<I, anyhow::Error> {
+    <I as TryFrom<usize>>::try_from(size)
+        .map_err(|_| anyhow::Error::msg(format!("{} failed: size {} is out of range", name, size)))
+}
+
 /// A helper to pass wasmtime counters to the System API
 fn ic0_performance_counter_helper(
     caller: &mut Caller<'_, StoreData>,
@@ -299,13 +340,15 @@ fn ic0_performance_counter_helper(
     }
 }
 
-pub(crate) fn syscalls(
+pub(crate) fn syscalls<I>(
     linker: &mut Linker<StoreData>,
     feature_flags: FeatureFlags,
     stable_memory_dirty_page_limit: StableMemoryDirtyPageLimit,
     stable_memory_access_page_limit: NumOsPages,
     main_memory_type: WasmMemoryType,
-) {
+) where
+    I: WasmAddress,
+{
     fn with_system_api<T>(
         mut caller: &mut Caller<'_, StoreData>,
         f: impl Fn(&mut SystemApiImpl) -> HypervisorResult<T>,
@@ -384,8 +427,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_caller_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
-                let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst)?, to_usize(offset)?, to_usize(size)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::MSG_CALLER_COPY, size)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_caller_copy(dst, offset, size, memory)
@@ -403,11 +446,8 @@ pub(crate) fn syscalls(
         .func_wrap("ic0", "msg_caller_size", {
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead::MSG_CALLER_SIZE)?;
-                with_system_api(&mut caller, |s| s.ic0_msg_caller_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
-                        anyhow::Error::msg(format!("ic0::msg_caller_size failed: {}", e))
-                    })
-                })
+                with_system_api(&mut caller, |s| s.ic0_msg_caller_size())
+                    .and_then(|s| to_wasm_size::<I>(s, "ic0::msg_caller_size"))
             }
         })
         .unwrap();
@@ -416,19 +456,16 @@ pub(crate) fn syscalls(
         .func_wrap("ic0", "msg_arg_data_size", {
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead::MSG_ARG_DATA_SIZE)?;
-                with_system_api(&mut caller, |s| s.ic0_msg_arg_data_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
-                        anyhow::Error::msg(format!("ic0::msg_arg_data_size failed: {}", e))
-                    })
-                })
+                with_system_api(&mut caller, |s| s.ic0_msg_arg_data_size())
+                    .and_then(|s| to_wasm_size::<I>(s, "ic0::msg_arg_data_size"))
             }
         })
         .unwrap();
 
     linker
         .func_wrap("ic0", "msg_arg_data_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
-                let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst)?, to_usize(offset)?, to_usize(size)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::MSG_ARG_DATA_COPY, size)?;
                 with_memory_and_system_api(&mut caller, |system_api, mem| {
                     system_api.ic0_msg_arg_data_copy(dst, offset, size, mem)
@@ -446,19 +483,16 @@ pub(crate) fn syscalls(
         .func_wrap("ic0", "msg_method_name_size", {
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead::MSG_METHOD_NAME_SIZE)?;
-                with_system_api(&mut caller, |s| s.ic0_msg_method_name_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
-                        anyhow::Error::msg(format!("ic0::msg_metohd_name_size failed: {}", e))
-                    })
-                })
+                with_system_api(&mut caller, |s| s.ic0_msg_method_name_size())
+                    .and_then(|s| to_wasm_size::<I>(s, "ic0::msg_method_name_size"))
             }
         })
         .unwrap();
 
     linker
         .func_wrap("ic0", "msg_method_name_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
-                let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst)?, to_usize(offset)?, to_usize(size)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::MSG_METHOD_NAME_COPY, size)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_method_name_copy(dst, offset, size, memory)
@@ -481,10 +515,19 @@ pub(crate) fn syscalls(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "msg_tip", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead::MSG_TIP)?;
+                with_system_api(&mut caller, |s| s.ic0_msg_tip())
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("ic0", "msg_reply_data_append", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
-                let (src, size) = (src as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src)?, to_usize(size)?);
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead::MSG_REPLY_DATA_APPEND,
@@ -517,8 +560,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_reject", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
-                let (src, size) = (src as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src)?, to_usize(size)?);
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead::MSG_REJECT,
@@ -535,19 +578,16 @@ pub(crate) fn syscalls(
         .func_wrap("ic0", "msg_reject_msg_size", {
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead::MSG_REJECT_MSG_SIZE)?;
-                with_system_api(&mut caller, |s| s.ic0_msg_reject_msg_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
-                        anyhow::Error::msg(format!("ic0_msg_reject_msg_size failed: {}", e))
-                    })
-                })
+                with_system_api(&mut caller, |s| s.ic0_msg_reject_msg_size())
+                    .and_then(|s| to_wasm_size::<I>(s, "ic0_msg_reject_msg_size"))
             }
         })
         .unwrap();
 
     linker
         .func_wrap("ic0", "msg_reject_msg_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
-                let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst)?, to_usize(offset)?, to_usize(size)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::MSG_REJECT_MSG_COPY, size)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_reject_msg_copy(dst, offset, size, memory)
@@ -565,19 +605,16 @@ pub(crate) fn syscalls(
         .func_wrap("ic0", "canister_self_size", {
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead::CANISTER_SELF_SIZE)?;
-                with_system_api(&mut caller, |s| s.ic0_canister_self_size()).and_then(|s| {
-                    i32::try_from(s).map_err(|e| {
-                        anyhow::Error::msg(format!("ic0_canister_self_size failed: {}", e))
-                    })
-                })
+                with_system_api(&mut caller, |s| s.ic0_canister_self_size())
+                    .and_then(|s| to_wasm_size::<I>(s, "ic0_canister_self_size"))
             }
         })
         .unwrap();
 
     linker
         .func_wrap("ic0", "canister_self_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
-                let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst)?, to_usize(offset)?, to_usize(size)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::CANISTER_SELF_COPY, size)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_canister_self_copy(dst, offset, size, memory)
@@ -593,7 +630,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "debug_print", {
-            move |mut caller: Caller<'_, StoreData>, offset: u32, length: u32| {
+            move |mut caller: Caller<'_, StoreData>, offset: I, length: I| {
+                let (offset, length) = (to_usize(offset)?, to_usize(length)?);
                 let mut num_bytes = 0;
                 let canister_logging_is_enabled =
                     feature_flags.canister_logging == FlagStatus::Enabled;
@@ -605,7 +643,6 @@ pub(crate) fn syscalls(
                     num_bytes += length as u64;
                 }
                 charge_for_cpu_and_mem(&mut caller, overhead::DEBUG_PRINT, num_bytes as usize)?;
-                let (offset, length) = (offset as usize, length as usize);
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.save_log_message(
                         canister_logging_is_enabled,
@@ -625,8 +662,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "trap", {
-            move |mut caller: Caller<'_, StoreData>, offset: u32, length: u32| -> Result<(), _> {
-                let (offset, length) = (offset as usize, length as usize);
+            move |mut caller: Caller<'_, StoreData>, offset: I, length: I| -> Result<(), _> {
+                let (offset, length) = (to_usize(offset)?, to_usize(length)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::TRAP, length)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_trap(offset, length, memory)
@@ -638,16 +675,18 @@ pub(crate) fn syscalls(
     linker
         .func_wrap("ic0", "call_new", {
             move |mut caller: Caller<'_, StoreData>,
-                  callee_src: u32,
-                  callee_size: u32,
-                  name_src: u32,
-                  name_len: u32,
-                  reply_fun: u32,
-                  reply_env: u32,
-                  reject_fun: u32,
-                  reject_env: u32| {
-                let (callee_src, callee_size) = (callee_src as usize, callee_size as usize);
-                let (name_src, name_len) = (name_src as usize, name_len as usize);
+                  callee_src: I,
+                  callee_size: I,
+                  name_src: I,
+                  name_len: I,
+                  reply_fun: I,
+                  reply_env: I,
+                  reject_fun: I,
+                  reject_env: I| {
+                let (callee_src, callee_size) = (to_usize(callee_src)?, to_usize(callee_size)?);
+                let (name_src, name_len) = (to_usize(name_src)?, to_usize(name_len)?);
+                let (reply_fun, reply_env) = (to_u32(reply_fun)?, to_u64(reply_env));
+                let (reject_fun, reject_env) = (to_u32(reject_fun)?, to_u64(reject_env));
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead::CALL_NEW,
@@ -672,8 +711,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "call_data_append", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
-                let (src, size) = (src as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src)?, to_usize(size)?);
                 charge_for_cpu_and_mem(
                     &mut caller,
                     overhead::CALL_DATA_APPEND,
@@ -688,7 +727,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "call_on_cleanup", {
-            move |mut caller: Caller<'_, StoreData>, fun: u32, env: u32| {
+            move |mut caller: Caller<'_, StoreData>, fun: I, env: I| {
+                let (fun, env) = (to_u32(fun)?, to_u64(env));
                 charge_for_cpu(&mut caller, overhead::CALL_ON_CLEANUP)?;
                 with_system_api(&mut caller, |s| s.ic0_call_on_cleanup(fun, env))
             }
@@ -907,8 +947,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "canister_cycle_balance128", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32| {
-                let dst = dst as usize;
+            move |mut caller: Caller<'_, StoreData>, dst: I| {
+                let dst = to_usize(dst)?;
                 charge_for_cpu(&mut caller, overhead::CANISTER_CYCLE_BALANCE128)?;
                 with_memory_and_system_api(&mut caller, |s, memory| {
                     s.ic0_canister_cycle_balance128(dst, memory)
@@ -937,8 +977,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_cycles_available128", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32| {
-                let dst = dst as usize;
+            move |mut caller: Caller<'_, StoreData>, dst: I| {
+                let dst = to_usize(dst)?;
                 charge_for_cpu(&mut caller, overhead::MSG_CYCLES_AVAILABLE128)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_cycles_available128(dst, memory)
@@ -967,8 +1007,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_cycles_refunded128", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32| {
-                let dst = dst as usize;
+            move |mut caller: Caller<'_, StoreData>, dst: I| {
+                let dst = to_usize(dst)?;
                 charge_for_cpu(&mut caller, overhead::MSG_CYCLES_REFUNDED128)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_cycles_refunded128(dst, memory)
@@ -993,8 +1033,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "msg_cycles_accept128", {
-            move |mut caller: Caller<'_, StoreData>, amount_high: u64, amount_low: u64, dst: u32| {
-                let dst = dst as usize;
+            move |mut caller: Caller<'_, StoreData>, amount_high: u64, amount_low: u64, dst: I| {
+                let dst = to_usize(dst)?;
                 charge_for_cpu(&mut caller, overhead::MSG_CYCLES_ACCEPT128)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_msg_cycles_accept128(
@@ -1075,7 +1115,10 @@ pub(crate) fn syscalls(
                         additional_pages as u64,
                         stable_memory_api
                             .try_into()
-                            .map_err(|()| HypervisorError::Trapped(TrapCode::Other))?,
+                            .map_err(|()| HypervisorError::Trapped {
+                                trap_code: TrapCode::Other,
+                                backtrace: None,
+                            })?,
                     )? {
                         StableGrowOutcome::Success => Ok(current_size),
                         StableGrowOutcome::Failure => Ok(-1),
@@ -1096,8 +1139,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "certified_data_set", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
-                let (src, size) = (src as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src)?, to_usize(size)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::CERTIFIED_DATA_SET, size)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_certified_data_set(src, size, memory)
@@ -1119,17 +1162,16 @@ pub(crate) fn syscalls(
         .func_wrap("ic0", "data_certificate_size", {
             move |mut caller: Caller<'_, StoreData>| {
                 charge_for_cpu(&mut caller, overhead::DATA_CERTIFICATE_SIZE)?;
-                with_system_api(&mut caller, |s| {
-                    s.ic0_data_certificate_size().map(|x| x as u32)
-                })
+                with_system_api(&mut caller, |s| s.ic0_data_certificate_size())
+                    .and_then(|s| to_wasm_size::<I>(s, "ic0_data_certificate_size"))
             }
         })
         .unwrap();
 
     linker
         .func_wrap("ic0", "is_controller", {
-            move |mut caller: Caller<'_, StoreData>, src: u32, size: u32| {
-                let (src, size) = (src as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, src: I, size: I| {
+                let (src, size) = (to_usize(src)?, to_usize(size)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::IS_CONTROLLER, size)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_is_controller(src, size, memory)
@@ -1149,8 +1191,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "data_certificate_copy", {
-            move |mut caller: Caller<'_, StoreData>, dst: u32, offset: u32, size: u32| {
-                let (dst, offset, size) = (dst as usize, offset as usize, size as usize);
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst)?, to_usize(offset)?, to_usize(size)?);
                 charge_for_cpu_and_mem(&mut caller, overhead::DATA_CERTIFICATE_COPY, size)?;
                 with_memory_and_system_api(&mut caller, |system_api, memory| {
                     system_api.ic0_data_certificate_copy(dst, offset, size, memory)
@@ -1177,8 +1219,8 @@ pub(crate) fn syscalls(
 
     linker
         .func_wrap("ic0", "cycles_burn128", {
-            move |mut caller: Caller<'_, StoreData>, amount_high: u64, amount_low: u64, dst: u32| {
-                let dst = dst as usize;
+            move |mut caller: Caller<'_, StoreData>, amount_high: u64, amount_low: u64, dst: I| {
+                let dst = to_usize(dst)?;
                 with_memory_and_system_api(&mut caller, |s, memory| {
                     s.ic0_cycles_burn128(Cycles::from_parts(amount_high, amount_low), dst, memory)
                 })
@@ -1225,18 +1267,54 @@ pub(crate) fn syscalls(
         })
         .unwrap();
 
+    linker
+        .func_wrap("ic0", "trace_id_size", {
+            move |mut caller: Caller<'_, StoreData>| {
+                charge_for_cpu(&mut caller, overhead::TRACE_ID_SIZE)?;
+                with_system_api(&mut caller, |s| s.ic0_trace_id_size())
+                    .and_then(|s| to_wasm_size::<I>(s, "ic0_trace_id_size"))
+            }
+        })
+        .unwrap();
+
+    linker
+        .func_wrap("ic0", "trace_id_copy", {
+            move |mut caller: Caller<'_, StoreData>, dst: I, offset: I, size: I| {
+                let (dst, offset, size) = (to_usize(dst)?, to_usize(offset)?, to_usize(size)?);
+                charge_for_cpu_and_mem(&mut caller, overhead::TRACE_ID_COPY, size)?;
+                with_memory_and_system_api(&mut caller, |system_api, memory| {
+                    system_api.ic0_trace_id_copy(dst, offset, size, memory)
+                })?;
+                if feature_flags.write_barrier == FlagStatus::Enabled {
+                    mark_writes_on_bytemap(&mut caller, dst, size)
+                } else {
+                    Ok(())
+                }
+            }
+        })
+        .unwrap();
+
     linker
         .func_wrap("__", "internal_trap", {
             move |mut caller: Caller<'_, StoreData>, err_code: i32| -> Result<(), _> {
                 let err = match InternalErrorCode::from_i32(err_code) {
                     InternalErrorCode::HeapOutOfBounds => {
-                        HypervisorError::Trapped(TrapCode::HeapOutOfBounds)
+                        HypervisorError::Trapped {
+                            trap_code: TrapCode::HeapOutOfBounds,
+                            backtrace: None,
+                        }
                     }
                     InternalErrorCode::StableMemoryOutOfBounds => {
-                        HypervisorError::Trapped(TrapCode::StableMemoryOutOfBounds)
+                        HypervisorError::Trapped {
+                            trap_code: TrapCode::StableMemoryOutOfBounds,
+                            backtrace: None,
+                        }
                     }
                     InternalErrorCode::StableMemoryTooBigFor32Bit => {
-                        HypervisorError::Trapped(TrapCode::StableMemoryTooBigFor32Bit)
+                        HypervisorError::Trapped {
+                            trap_code: TrapCode::StableMemoryTooBigFor32Bit,
+                            backtrace: None,
+                        }
                     }
                     InternalErrorCode::MemoryWriteLimitExceeded => {
                         HypervisorError::MemoryAccessLimitExceeded(
@@ -1253,12 +1331,15 @@ pub(crate) fn syscalls(
                         )
                     }
                     InternalErrorCode::StableGrowFailed => {
-                        HypervisorError::CalledTrap("Internal error: `memory.grow` instruction failed to grow stable memory".to_string())
+                        HypervisorError::CalledTrap {
+                            message: "Internal error: `memory.grow` instruction failed to grow stable memory".to_string(),
+                            backtrace: None,
+                        }
                     }
-                    InternalErrorCode::Unknown => HypervisorError::CalledTrap(format!(
-                        "Trapped with internal error code: {}",
-                        err_code
-                    )),
+                    InternalErrorCode::Unknown => HypervisorError::CalledTrap {
+                        message: format!("Trapped with internal error code: {}", err_code),
+                        backtrace: None,
+                    },
                 };
                 Err(process_err(&mut caller, err))
             }
@@ -61,6 +61,7 @@ pub mod overhead {
     pub const MSG_REJECT: NumInstructions = NumInstructions::new(500);
     pub const MSG_REPLY_DATA_APPEND: NumInstructions = NumInstructions::new(500);
     pub const MSG_REPLY: NumInstructions = NumInstructions::new(500);
+    pub const MSG_TIP: NumInstructions = NumInstructions::new(500);
     pub const PERFORMANCE_COUNTER: NumInstructions = NumInstructions::new(200);
     pub const STABLE_GROW: NumInstructions = NumInstructions::new(500);
     pub const STABLE_READ: NumInstructions = NumInstructions::new(20);
@@ -71,6 +72,8 @@ pub mod overhead {
     pub const STABLE64_SIZE: NumInstructions = NumInstructions::new(20);
     pub const STABLE64_WRITE: NumInstructions = NumInstructions::new(20);
     pub const TIME: NumInstructions = NumInstructions::new(500);
+    pub const TRACE_ID_COPY: NumInstructions = NumInstructions::new(500);
+    pub const TRACE_ID_SIZE: NumInstructions = NumInstructions::new(500);
     pub const TRAP: NumInstructions = NumInstructions::new(500);
 }
 
@@ -1,4 +1,4 @@
-use ic_config::embedders::{Config as EmbeddersConfig, MeteringType};
+use ic_config::embedders::{Config as EmbeddersConfig, FeatureFlags, MeteringType};
 use ic_config::flag_status::FlagStatus;
 use ic_config::subnet_config::SchedulerConfig;
 use ic_embedders::wasm_utils;
@@ -702,6 +702,72 @@ fn metering_loop() {
     );
 }
 
+#[test]
+fn metering_tail_calls() {
+    let instructions_used = |n: u64| {
+        let wat = format!(
+            r#"
+            (module
+                (func $countdown (param i64)
+                    local.get 0
+                    i64.eqz
+                    if
+                        return
+                    end
+                    local.get 0
+                    i64.const 1
+                    i64.sub
+                    return_call $countdown
+                )
+                (func $test (export "canister_update test")
+                    i64.const {n}
+                    call $countdown
+                )
+            )"#
+        );
+        let config = EmbeddersConfig {
+            dirty_page_overhead: SchedulerConfig::application_subnet().dirty_page_overhead,
+            feature_flags: FeatureFlags {
+                wasm_tail_call: FlagStatus::Enabled,
+                ..FeatureFlags::default()
+            },
+            ..EmbeddersConfig::default()
+        };
+        let mut instance = WasmtimeInstanceBuilder::new()
+            .with_config(config)
+            .with_wat(&wat)
+            .with_num_instructions(NumInstructions::new(1_000_000_000))
+            .build();
+        instance.run(func_ref("test")).unwrap();
+        instr_used(&mut instance)
+    };
+
+    use wasmparser::Operator::*;
+    // Every tail call is charged like a regular function entry (1) plus the
+    // instructions executed in the body.
+    let per_call = 1 + [
+        LocalGet { local_index: 0 },
+        I64Eqz,
+        If {
+            blockty: wasmparser::BlockType::Empty,
+        },
+        LocalGet { local_index: 0 },
+        I64Const { value: 1 },
+        I64Sub,
+        ReturnCall { function_index: 0 },
+    ]
+    .iter()
+    .map(instruction_to_cost)
+    .sum::<u64>();
+
+    // Far more tail calls than would fit on the Wasm stack as regular calls.
+    let n = 1_000_000;
+    assert_eq!(
+        instructions_used(2 * n) - instructions_used(n),
+        n * per_call
+    );
+}
+
 #[test]
 fn charge_for_dirty_heap() {
     let wat = r#"
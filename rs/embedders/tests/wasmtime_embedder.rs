@@ -3,7 +3,9 @@ use ic_config::{embedders::Config, flag_status::FlagStatus};
 use ic_embedders::{
     wasm_utils::instrumentation::instruction_to_cost, wasmtime_embedder::system_api_complexity,
 };
-use ic_interfaces::execution_environment::{HypervisorError, SystemApi, TrapCode};
+use ic_interfaces::execution_environment::{
+    BacktraceFrame, CanisterBacktrace, HypervisorError, SystemApi, TrapCode,
+};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{canister_state::WASM_PAGE_SIZE_IN_BYTES, Global};
 use ic_test_utilities_embedders::{WasmtimeInstanceBuilder, DEFAULT_NUM_INSTRUCTIONS};
@@ -17,6 +19,13 @@ use ic_types::{
 #[cfg(target_os = "linux")]
 use ic_types::{Cycles, PrincipalId};
 
+fn trapped(trap_code: TrapCode) -> HypervisorError {
+    HypervisorError::Trapped {
+        trap_code,
+        backtrace: None,
+    }
+}
+
 /// Ensures that attempts to execute messages on wasm modules that do not
 /// define memory fails.
 #[test]
@@ -282,14 +291,7 @@ fn stack_overflow_traps() {
                 ic_types::methods::WasmMethod::Update("f".to_string()),
             ));
 
-            assert_eq!(
-                result.err(),
-                Some(
-                    ic_interfaces::execution_environment::HypervisorError::Trapped(
-                        ic_interfaces::execution_environment::TrapCode::StackOverflow
-                    )
-                )
-            );
+            assert_eq!(result.err(), Some(trapped(TrapCode::StackOverflow)));
         })
         .unwrap();
 
@@ -527,7 +529,10 @@ fn calling_function_by_index() {
         .unwrap_err();
     assert_eq!(
         err,
-        HypervisorError::CalledTrap(std::str::from_utf8(&[0; 6]).unwrap().to_string())
+        HypervisorError::CalledTrap {
+            message: std::str::from_utf8(&[0; 6]).unwrap().to_string(),
+            backtrace: None,
+        }
     );
 }
 
@@ -548,7 +553,10 @@ fn zero_size_memory() {
         .unwrap_err();
     assert_eq!(
         err,
-        HypervisorError::CalledTrap(std::str::from_utf8(&[0; 0]).unwrap().to_string())
+        HypervisorError::CalledTrap {
+            message: std::str::from_utf8(&[0; 0]).unwrap().to_string(),
+            backtrace: None,
+        }
     );
 }
 
@@ -648,7 +656,13 @@ fn stable_write_and_read() {
     let err = instance
         .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
         .unwrap_err();
-    assert_eq!(err, HypervisorError::CalledTrap("Hello".to_string()));
+    assert_eq!(
+        err,
+        HypervisorError::CalledTrap {
+            message: "Hello".to_string(),
+            backtrace: None,
+        }
+    );
 }
 
 #[test]
@@ -689,7 +703,13 @@ fn stable64_write_and_read() {
     let err = instance
         .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
         .unwrap_err();
-    assert_eq!(err, HypervisorError::CalledTrap("Hello".to_string()));
+    assert_eq!(
+        err,
+        HypervisorError::CalledTrap {
+            message: "Hello".to_string(),
+            backtrace: None,
+        }
+    );
 }
 
 #[test]
@@ -1014,29 +1034,28 @@ fn stable_read_out_of_bounds() {
             (memory 2 2)
         )"#;
 
-    use HypervisorError::*;
     use TrapCode::*;
 
     // Host stable memory
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_src")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_dst")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_heap")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_stable")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_both")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     // native stable memory
     let mut config = Config::default();
@@ -1047,35 +1066,35 @@ fn stable_read_out_of_bounds() {
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_src")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_dst")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_heap")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_stable")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config)
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_both")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 }
 
 #[test]
@@ -1129,33 +1148,32 @@ fn stable64_read_out_of_bounds() {
             (memory 2 2)
         )"#;
 
-    use HypervisorError::*;
     use TrapCode::*;
 
     // Host stable memory
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_src")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_dst")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_heap")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_stable")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_both")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     // Native stable memory
     let mut config = Config::default();
@@ -1166,42 +1184,42 @@ fn stable64_read_out_of_bounds() {
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_src")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_dst")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_heap")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_stable")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config)
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_both")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 }
 
 #[test]
@@ -1248,29 +1266,28 @@ fn stable_write_out_of_bounds() {
             (memory 2 2)
         )"#;
 
-    use HypervisorError::*;
     use TrapCode::*;
 
     // Host stable memory
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_src")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_dst")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_heap")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_stable")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_both")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     // native stable memory
     let mut config = Config::default();
@@ -1281,35 +1298,35 @@ fn stable_write_out_of_bounds() {
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_src")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_dst")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_heap")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_stable")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config)
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_both")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 }
 
 #[test]
@@ -1363,33 +1380,32 @@ fn stable64_write_out_of_bounds() {
             (memory 2 2)
         )"#;
 
-    use HypervisorError::*;
     use TrapCode::*;
 
     // Host stable memory
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_src")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_dst")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_heap")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_stable")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
     let err = instance.run(func_ref("test_len_both")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     // native stable memory
     let mut config = Config::default();
@@ -1399,42 +1415,42 @@ fn stable64_write_out_of_bounds() {
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_src")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_dst")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_heap")).unwrap_err();
-    assert_eq!(err, Trapped(HeapOutOfBounds));
+    assert_eq!(err, trapped(HeapOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config.clone())
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_stable")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 
     let mut instance = WasmtimeInstanceBuilder::new()
         .with_config(config)
         .with_wat(wat)
         .build();
     let err = instance.run(func_ref("test_len_both")).unwrap_err();
-    assert_eq!(err, Trapped(StableMemoryOutOfBounds));
+    assert_eq!(err, trapped(StableMemoryOutOfBounds));
 }
 
 /// Test that stable memory access past the normal 32-bit range (including
@@ -1497,7 +1513,7 @@ fn wasm_heap_oob_access() {
     let err = instance
         .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
         .unwrap_err();
-    assert_eq!(err, HypervisorError::Trapped(TrapCode::HeapOutOfBounds));
+    assert_eq!(err, trapped(TrapCode::HeapOutOfBounds));
 }
 
 #[test]
@@ -1817,3 +1833,115 @@ fn wasm64_handles_memory_grow_failure_test() {
     assert_eq!(res.exported_globals[0], Global::I64(-1));
     assert_eq!(res.exported_globals[1], Global::I64(137));
 }
+
+fn canister_backtrace_config() -> Config {
+    let mut config = Config::default();
+    config.feature_flags.canister_backtrace = FlagStatus::Enabled;
+    // Injects more imports, which shifts the function indices of the module.
+    config.feature_flags.wasm_native_stable_memory = FlagStatus::Enabled;
+    config
+}
+
+fn frame(func_index: u32, func_name: &str) -> BacktraceFrame {
+    BacktraceFrame {
+        func_index,
+        func_name: Some(func_name.to_string()),
+    }
+}
+
+#[test]
+fn trap_has_symbolized_backtrace() {
+    let wat = r#"
+        (module
+            (func $inner unreachable)
+            (func $outer (export "canister_update test") (call $inner))
+        )"#;
+    let mut instance = WasmtimeInstanceBuilder::new()
+        .with_config(canister_backtrace_config())
+        .with_wat(wat)
+        .build();
+    let err = instance
+        .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        HypervisorError::Trapped {
+            trap_code: TrapCode::Unreachable,
+            backtrace: Some(CanisterBacktrace {
+                frames: vec![frame(0, "inner"), frame(1, "outer")],
+                omitted_frames: 0,
+            }),
+        }
+    );
+    assert!(err
+        .to_string()
+        .ends_with("Canister Backtrace:\n0: inner\n1: outer"));
+}
+
+#[test]
+fn called_trap_has_symbolized_backtrace() {
+    let wat = r#"
+        (module
+            (import "ic0" "trap" (func $ic0_trap (param i32 i32)))
+            (func $inner (call $ic0_trap (i32.const 0) (i32.const 2)))
+            (func $outer (export "canister_update test") (call $inner))
+            (memory 1)
+            (data (i32.const 0) "Hi")
+        )"#;
+    let mut instance = WasmtimeInstanceBuilder::new()
+        .with_config(canister_backtrace_config())
+        .with_wat(wat)
+        .build();
+    let err = instance
+        .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        HypervisorError::CalledTrap {
+            message: "Hi".to_string(),
+            backtrace: Some(CanisterBacktrace {
+                frames: vec![frame(1, "inner"), frame(2, "outer")],
+                omitted_frames: 0,
+            }),
+        }
+    );
+}
+
+#[test]
+fn backtrace_is_truncated() {
+    let wat = r#"
+        (module
+            (func $recurse (param i32)
+                (if (local.get 0)
+                    (then (call $recurse (i32.sub (local.get 0) (i32.const 1))))
+                    (else unreachable)
+                )
+            )
+            (func $main (export "canister_update test") (call $recurse (i32.const 30)))
+        )"#;
+    let mut instance = WasmtimeInstanceBuilder::new()
+        .with_config(canister_backtrace_config())
+        .with_wat(wat)
+        .build();
+    let err = instance
+        .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
+        .unwrap_err();
+    let backtrace = err.backtrace().unwrap();
+    assert_eq!(backtrace.frames, vec![frame(0, "recurse"); 20]);
+    // 31 frames of `recurse` and one of `main`.
+    assert_eq!(backtrace.omitted_frames, 12);
+}
+
+#[test]
+fn trap_has_no_backtrace_by_default() {
+    let wat = r#"
+        (module
+            (func $inner unreachable)
+            (func $outer (export "canister_update test") (call $inner))
+        )"#;
+    let mut instance = WasmtimeInstanceBuilder::new().with_wat(wat).build();
+    let err = instance
+        .run(FuncRef::Method(WasmMethod::Update("test".to_string())))
+        .unwrap_err();
+    assert_eq!(err, trapped(TrapCode::Unreachable));
+}
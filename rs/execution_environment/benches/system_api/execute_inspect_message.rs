@@ -10,6 +10,7 @@ use ic_logger::replica_logger::no_op_logger;
 use ic_metrics::MetricsRegistry;
 use ic_test_utilities_types::ids::user_test_id;
 use ic_test_utilities_types::messages::SignedIngressBuilder;
+use ic_types::Cycles;
 
 pub fn execute_inspect_message_bench(c: &mut Criterion) {
     // List of benchmarks: benchmark id (name), WAT, expected instructions.
@@ -29,6 +30,11 @@ pub fn execute_inspect_message_bench(c: &mut Criterion) {
             Module::InspectMessage.from_ic0("msg_method_name_copy", Params3(0, 0, 20), Result::No),
             539000511,
         ),
+        common::Benchmark(
+            "ic0_msg_tip()".into(),
+            Module::InspectMessage.from_ic0("msg_tip", NoParams, Result::I64),
+            517000511,
+        ),
         common::Benchmark(
             "ic0_accept_message()*".into(),
             Module::InspectMessage.from_sections(("", "")), // inspect_message accepts by default
@@ -61,6 +67,7 @@ pub fn execute_inspect_message_bench(c: &mut Criterion) {
                 time,
                 canister_state,
                 &ingress,
+                Cycles::zero(),
                 execution_parameters,
                 subnet_available_memory,
                 hypervisor,
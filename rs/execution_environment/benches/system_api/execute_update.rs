@@ -331,6 +331,16 @@ pub fn execute_update_bench(c: &mut Criterion) {
             Module::Test.from_ic0("msg_deadline", NoParams, Result::I64),
             517000006,
         ),
+        common::Benchmark(
+            "ic0_trace_id_size()".into(),
+            Module::Test.from_ic0("trace_id_size", NoParams, Result::I32),
+            517000006,
+        ),
+        common::Benchmark(
+            "ic0_trace_id_copy()/1B".into(),
+            Module::Test.from_ic0("trace_id_copy", Params3(0, 0, 1), Result::No),
+            520000006,
+        ),
     ];
     common::run_benchmarks(
         c,
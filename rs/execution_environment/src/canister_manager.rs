@@ -648,6 +648,9 @@ impl CanisterManager {
         if let Some(wasm_memory_limit) = settings.wasm_memory_limit() {
             canister.system_state.wasm_memory_limit = Some(wasm_memory_limit);
         }
+        if let Some(backtrace_visibility) = settings.backtrace_visibility() {
+            canister.system_state.backtrace_visibility = backtrace_visibility;
+        }
     }
 
     /// Tries to apply the requested settings on the canister identified by
@@ -1183,6 +1186,7 @@ impl CanisterManager {
         let freeze_threshold = canister.system_state.freeze_threshold;
         let reserved_cycles_limit = canister.system_state.reserved_balance_limit();
         let log_visibility = canister.system_state.log_visibility;
+        let backtrace_visibility = canister.system_state.backtrace_visibility;
         let wasm_memory_limit = canister.system_state.wasm_memory_limit;
 
         Ok(CanisterStatusResultV2::new(
@@ -1200,6 +1204,7 @@ impl CanisterManager {
             freeze_threshold.get(),
             reserved_cycles_limit.map(|x| x.get()),
             log_visibility,
+            backtrace_visibility,
             self.cycles_account_manager
                 .idle_cycles_burned_rate(
                     memory_allocation,
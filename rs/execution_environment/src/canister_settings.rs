@@ -2,7 +2,7 @@ use ic_base_types::{NumBytes, NumSeconds};
 use ic_cycles_account_manager::{CyclesAccountManager, ResourceSaturation};
 use ic_error_types::{ErrorCode, UserError};
 use ic_interfaces::execution_environment::SubnetAvailableMemory;
-use ic_management_canister_types::{CanisterSettingsArgs, LogVisibility};
+use ic_management_canister_types::{BacktraceVisibility, CanisterSettingsArgs, LogVisibility};
 use ic_types::{
     ComputeAllocation, Cycles, InvalidComputeAllocationError, InvalidMemoryAllocationError,
     MemoryAllocation, PrincipalId,
@@ -26,6 +26,7 @@ pub(crate) struct CanisterSettings {
     pub(crate) reserved_cycles_limit: Option<Cycles>,
     pub(crate) log_visibility: Option<LogVisibility>,
     pub(crate) wasm_memory_limit: Option<NumBytes>,
+    pub(crate) backtrace_visibility: Option<BacktraceVisibility>,
 }
 
 impl CanisterSettings {
@@ -38,6 +39,7 @@ impl CanisterSettings {
         reserved_cycles_limit: Option<Cycles>,
         log_visibility: Option<LogVisibility>,
         wasm_memory_limit: Option<NumBytes>,
+        backtrace_visibility: Option<BacktraceVisibility>,
     ) -> Self {
         Self {
             controller,
@@ -48,6 +50,7 @@ impl CanisterSettings {
             reserved_cycles_limit,
             log_visibility,
             wasm_memory_limit,
+            backtrace_visibility,
         }
     }
 
@@ -82,6 +85,10 @@ impl CanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn backtrace_visibility(&self) -> Option<BacktraceVisibility> {
+        self.backtrace_visibility
+    }
 }
 
 impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
@@ -146,6 +153,7 @@ impl TryFrom<CanisterSettingsArgs> for CanisterSettings {
             reserved_cycles_limit,
             input.log_visibility,
             wasm_memory_limit,
+            input.backtrace_visibility,
         ))
     }
 }
@@ -170,6 +178,7 @@ pub(crate) struct CanisterSettingsBuilder {
     reserved_cycles_limit: Option<Cycles>,
     log_visibility: Option<LogVisibility>,
     wasm_memory_limit: Option<NumBytes>,
+    backtrace_visibility: Option<BacktraceVisibility>,
 }
 
 #[allow(dead_code)]
@@ -184,6 +193,7 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: None,
             log_visibility: None,
             wasm_memory_limit: None,
+            backtrace_visibility: None,
         }
     }
 
@@ -197,6 +207,7 @@ impl CanisterSettingsBuilder {
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
+            backtrace_visibility: self.backtrace_visibility,
         }
     }
 
@@ -255,6 +266,13 @@ impl CanisterSettingsBuilder {
             ..self
         }
     }
+
+    pub fn with_backtrace_visibility(self, backtrace_visibility: BacktraceVisibility) -> Self {
+        Self {
+            backtrace_visibility: Some(backtrace_visibility),
+            ..self
+        }
+    }
 }
 
 pub enum UpdateSettingsError {
@@ -331,6 +349,7 @@ pub(crate) struct ValidatedCanisterSettings {
     reservation_cycles: Cycles,
     log_visibility: Option<LogVisibility>,
     wasm_memory_limit: Option<NumBytes>,
+    backtrace_visibility: Option<BacktraceVisibility>,
 }
 
 impl ValidatedCanisterSettings {
@@ -369,6 +388,10 @@ impl ValidatedCanisterSettings {
     pub fn wasm_memory_limit(&self) -> Option<NumBytes> {
         self.wasm_memory_limit
     }
+
+    pub fn backtrace_visibility(&self) -> Option<BacktraceVisibility> {
+        self.backtrace_visibility
+    }
 }
 
 /// Validates the new canisters settings:
@@ -570,5 +593,6 @@ pub(crate) fn validate_canister_settings(
         reservation_cycles,
         log_visibility: settings.log_visibility(),
         wasm_memory_limit: settings.wasm_memory_limit(),
+        backtrace_visibility: settings.backtrace_visibility(),
     })
 }
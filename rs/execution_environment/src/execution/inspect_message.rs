@@ -2,6 +2,7 @@ use crate::Hypervisor;
 use crate::{
     execution_environment::{as_round_instructions, RoundLimits},
     metrics::{CallTreeMetricsNoOp, IngressFilterMetrics},
+    query_handler::QueryScheduler,
 };
 use ic_error_types::{ErrorCode, UserError};
 use ic_interfaces::execution_environment::SubnetAvailableMemory;
@@ -10,18 +11,20 @@ use ic_replicated_state::{CanisterState, NetworkTopology};
 use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::messages::SignedIngressContent;
 use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
-use ic_types::{NumInstructions, Time};
+use ic_types::{Cycles, NumInstructions, Time};
 use prometheus::IntCounter;
 
 /// Executes the system method `canister_inspect_message`.
 ///
 /// This method is called pre-consensus to let the canister decide if it
-/// wants to accept the message or not.
+/// wants to accept the message or not. `tip` is the tip the canister pays if
+/// it accepts the message, as returned by `ic0.msg_tip`.
 #[allow(clippy::too_many_arguments)]
 pub fn execute_inspect_message(
     time: Time,
     canister: CanisterState,
     ingress: &SignedIngressContent,
+    tip: Cycles,
     execution_parameters: ExecutionParameters,
     subnet_available_memory: SubnetAvailableMemory,
     hypervisor: &Hypervisor,
@@ -61,6 +64,7 @@ pub fn execute_inspect_message(
         ingress.sender().get(),
         ingress.method_name().to_string(),
         ingress.arg().to_vec(),
+        tip,
         time,
     );
     let mut round_limits = RoundLimits {
@@ -72,7 +76,7 @@ pub fn execute_inspect_message(
     let inspect_message_timer = ingress_filter_metrics
         .inspect_message_duration_seconds
         .start_timer();
-    let (output, _output_execution_state, _system_state_accessor) = hypervisor.execute(
+    let (output, _output_execution_state, _system_state_accessor) = hypervisor.execute_sliced(
         system_api,
         time,
         system_state,
@@ -86,6 +90,10 @@ pub fn execute_inspect_message(
         state_changes_error,
         &CallTreeMetricsNoOp,
         time,
+        &mut || {
+            QueryScheduler::yield_to_other_canisters(canister_id);
+            true
+        },
     );
     drop(inspect_message_timer);
     ingress_filter_metrics.inspect_message_count.inc();
@@ -8,8 +8,8 @@
 use crate::execution::common::{validate_canister, validate_method};
 use crate::execution_environment::RoundLimits;
 use crate::{metrics::CallTreeMetricsNoOp, Hypervisor, NonReplicatedQueryKind};
-use ic_error_types::UserError;
-use ic_interfaces::execution_environment::SystemApiCallCounters;
+use ic_error_types::{ErrorCode, UserError};
+use ic_interfaces::execution_environment::{HypervisorError, SystemApiCallCounters};
 use ic_replicated_state::{CallOrigin, CanisterState, NetworkTopology};
 use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::ingress::WasmResult;
@@ -19,6 +19,10 @@ use ic_types::{Cycles, NumInstructions, Time};
 use prometheus::IntCounter;
 
 // Execute non replicated query.
+//
+// If the instruction limits allow more than one slice, then the query pauses
+// after every slice and `continue_after_slice` decides whether it resumes. The
+// query is only stopped this way when it runs out of wall-clock time.
 #[allow(clippy::too_many_arguments)]
 pub fn execute_non_replicated_query(
     query_kind: NonReplicatedQueryKind,
@@ -32,6 +36,7 @@ pub fn execute_non_replicated_query(
     hypervisor: &Hypervisor,
     round_limits: &mut RoundLimits,
     state_changes_error: &IntCounter,
+    continue_after_slice: &mut dyn FnMut() -> bool,
 ) -> (
     CanisterState,
     NumInstructions,
@@ -110,7 +115,7 @@ pub fn execute_non_replicated_query(
     // As we are executing the query in non-replicated mode, we can
     // modify the canister as the caller is not going to be able to
     // commit modifications to the canister anyway.
-    let (output, output_execution_state, output_system_state) = hypervisor.execute(
+    let (output, output_execution_state, output_system_state) = hypervisor.execute_sliced(
         api_type,
         time,
         canister.system_state,
@@ -124,15 +129,23 @@ pub fn execute_non_replicated_query(
         state_changes_error,
         &CallTreeMetricsNoOp,
         time,
+        continue_after_slice,
     );
     canister.system_state = output_system_state;
     if preserve_changes {
         canister.execution_state = Some(output_execution_state);
     }
 
-    let result = output
-        .wasm_result
-        .map_err(|err| err.into_user_error(&canister.canister_id()));
+    let result = output.wasm_result.map_err(|err| match err {
+        HypervisorError::Aborted => UserError::new(
+            ErrorCode::QueryTimeLimitExceeded,
+            format!(
+                "Query of canister {} exceeded the time limit.",
+                canister.canister_id()
+            ),
+        ),
+        err => err.into_user_error(&canister.canister_id()),
+    });
     (
         canister,
         output.num_instructions_left,
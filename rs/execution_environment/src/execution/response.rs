@@ -925,6 +925,12 @@ pub fn execute_response(
         instructions_executed: call_context.instructions_executed(),
         log_dirty_pages,
     };
+    let _response_span = ic_tracing::messages::response_span(
+        original.request_metadata.trace_id(),
+        response.originator,
+        response.respondent,
+    )
+    .entered();
 
     let mut helper =
         ResponseHelper::new(&clean_canister, &response, &original, &round, round_limits);
@@ -22,7 +22,7 @@ use ic_replicated_state::{num_bytes_try_from, CallOrigin, CanisterState};
 use ic_system_api::{ApiType, ExecutionParameters};
 use ic_types::messages::{
     CallContextId, CanisterCall, CanisterCallOrTask, CanisterMessage, CanisterMessageOrTask,
-    CanisterTask, RequestMetadata,
+    CanisterTask, RequestMetadata, TraceId,
 };
 use ic_types::methods::{FuncRef, SystemMethod, WasmMethod};
 use ic_types::{CanisterTimer, Cycles, NumBytes, NumInstructions, Time};
@@ -99,8 +99,20 @@ pub fn execute_update(
             Some(metadata) => metadata.for_downstream_call(),
             None => RequestMetadata::for_new_call_tree(time),
         },
+        // Call trees rooted in ingress messages are traced by their message ID,
+        // if the sender asked for it.
+        CanisterCallOrTask::Call(CanisterCall::Ingress(ingress)) => {
+            RequestMetadata::for_new_call_tree(time)
+                .with_trace_id(ingress.traced.then(|| TraceId::from(&ingress.message_id)))
+        }
         _ => RequestMetadata::for_new_call_tree(time),
     };
+    let _execution_span = ic_tracing::messages::execution_span(
+        request_metadata.trace_id(),
+        clean_canister.canister_id(),
+        &method,
+    )
+    .entered();
 
     let original = OriginalContext {
         call_origin: CallOrigin::from(&call_or_task),
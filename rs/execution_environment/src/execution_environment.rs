@@ -59,7 +59,7 @@ use ic_replicated_state::{
 };
 use ic_system_api::{ExecutionParameters, InstructionLimits};
 use ic_types::{
-    canister_http::CanisterHttpRequestContext,
+    canister_http::{CanisterHttpRequestContext, Replication},
     crypto::canister_threshold_sig::{ExtendedDerivationPath, MasterPublicKey, PublicKey},
     crypto::threshold_sig::ni_dkg::NiDkgTargetId,
     ingress::{IngressState, IngressStatus, WasmResult},
@@ -903,27 +903,60 @@ impl ExecutionEnvironment {
             Ok(Ic00Method::HttpRequest) => match state.metadata.own_subnet_features.http_requests {
                 true => match &msg {
                     CanisterCall::Request(request) => {
+                        let subnet_nodes = state
+                            .metadata
+                            .network_topology
+                            .subnets
+                            .get(&self.own_subnet_id)
+                            .map(|subnet_topology| subnet_topology.nodes.clone())
+                            .unwrap_or_default();
                         match CanisterHttpRequestArgs::decode(payload) {
                             Err(err) => ExecuteSubnetMessageResult::Finished {
                                 response: Err(err),
                                 refund: msg.take_cycles(),
                             },
-                            Ok(args) => match CanisterHttpRequestContext::try_from((
-                                state.time(),
-                                request.as_ref(),
-                                args,
-                            )) {
+                            Ok(args) => match Replication::choose(
+                                args.replication.as_ref(),
+                                &subnet_nodes,
+                                || rng.next_u64(),
+                            )
+                            .and_then(|replication| {
+                                CanisterHttpRequestContext::try_from((
+                                    state.time(),
+                                    request.as_ref(),
+                                    args,
+                                ))
+                                .map(|context| {
+                                    CanisterHttpRequestContext {
+                                        replication,
+                                        ..context
+                                    }
+                                })
+                            }) {
                                 Err(err) => ExecuteSubnetMessageResult::Finished {
                                     response: Err(err.into()),
                                     refund: msg.take_cycles(),
                                 },
                                 Ok(mut canister_http_request_context) => {
-                                    let http_request_fee =
-                                        self.cycles_account_manager.http_request_fee(
-                                            canister_http_request_context.variable_parts_size(),
-                                            canister_http_request_context.max_response_bytes,
-                                            registry_settings.subnet_size,
-                                        );
+                                    let http_request_fee = match canister_http_request_context
+                                        .replication
+                                    {
+                                        Replication::FullyReplicated => {
+                                            self.cycles_account_manager.http_request_fee(
+                                                canister_http_request_context.variable_parts_size(),
+                                                canister_http_request_context
+                                                    .downloaded_bytes_limit(),
+                                                registry_settings.subnet_size,
+                                            )
+                                        }
+                                        Replication::NonReplicated(_) => self
+                                            .cycles_account_manager
+                                            .non_replicated_http_request_fee(
+                                                canister_http_request_context.variable_parts_size(),
+                                                canister_http_request_context
+                                                    .downloaded_bytes_limit(),
+                                            ),
+                                    };
                                     if request.payment < http_request_fee {
                                         let err = Err(UserError::new(
                                                         ErrorCode::CanisterRejectedMessage,
@@ -2341,9 +2374,12 @@ impl ExecutionEnvironment {
                 let reveal_top_up = paying_canister
                     .controllers()
                     .contains(&ingress.sender().get());
+                let tip = self
+                    .cycles_account_manager
+                    .ingress_tip(ingress, paying_canister);
                 if let Err(err) = self.cycles_account_manager.can_withdraw_cycles(
                     &paying_canister.system_state,
-                    cost,
+                    cost + tip,
                     paying_canister.memory_usage(),
                     paying_canister.message_memory_usage(),
                     paying_canister.scheduler_state.compute_allocation,
@@ -2394,12 +2430,29 @@ impl ExecutionEnvironment {
             ));
         }
 
-        // An inspect message is expected to finish quickly, so DTS is not
-        // supported for it.
+        // An inspect message is expected to finish quickly, so it keeps the
+        // single-message instruction limit. With query time slicing, it pauses
+        // between slices to let queries of other canisters run.
+        let (inspect_message_slicing, max_instructions_per_slice) =
+            if self.config.query_time_slicing == FlagStatus::Enabled
+                && self.config.deterministic_time_slicing == FlagStatus::Enabled
+            {
+                (
+                    FlagStatus::Enabled,
+                    self.config
+                        .max_instructions_per_query_slice
+                        .min(self.config.max_instructions_for_message_acceptance_calls),
+                )
+            } else {
+                (
+                    FlagStatus::Disabled,
+                    self.config.max_instructions_for_message_acceptance_calls,
+                )
+            };
         let instruction_limits = InstructionLimits::new(
-            FlagStatus::Disabled,
-            self.config.max_instructions_for_message_acceptance_calls,
+            inspect_message_slicing,
             self.config.max_instructions_for_message_acceptance_calls,
+            max_instructions_per_slice,
         );
 
         // Letting the canister grow arbitrarily when executing the
@@ -2417,6 +2470,8 @@ impl ExecutionEnvironment {
             state.time(),
             canister_state.clone(),
             ingress,
+            self.cycles_account_manager
+                .ingress_tip(ingress, canister_state),
             execution_parameters,
             subnet_available_memory,
             &self.hypervisor,
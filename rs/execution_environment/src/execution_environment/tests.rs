@@ -4,12 +4,13 @@ use ic_base_types::{NumBytes, NumSeconds};
 use ic_config::flag_status::FlagStatus;
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_management_canister_types::{
-    self as ic00, BitcoinGetUtxosArgs, BitcoinNetwork, BoundedHttpHeaders, CanisterChange,
-    CanisterHttpRequestArgs, CanisterIdRecord, CanisterStatusResultV2, CanisterStatusType,
-    DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob, FetchCanisterLogsRequest, HttpMethod,
-    LogVisibility, MasterPublicKeyId, Method, Payload as Ic00Payload,
-    ProvisionalCreateCanisterWithCyclesArgs, ProvisionalTopUpCanisterArgs, SchnorrAlgorithm,
-    SchnorrKeyId, TransformContext, TransformFunc, IC_00,
+    self as ic00, BacktraceVisibility, BitcoinGetUtxosArgs, BitcoinNetwork, BoundedHttpHeaders,
+    CanisterChange, CanisterHttpRequestArgs, CanisterIdRecord, CanisterStatusResultV2,
+    CanisterStatusType, DerivationPath, EcdsaCurve, EcdsaKeyId, EmptyBlob,
+    FetchCanisterLogsRequest, HttpMethod, HttpOutcallReplication, LogVisibility, MasterPublicKeyId,
+    Method, Payload as Ic00Payload, ProvisionalCreateCanisterWithCyclesArgs,
+    ProvisionalTopUpCanisterArgs, SchnorrAlgorithm, SchnorrKeyId, TransformContext, TransformFunc,
+    IC_00,
 };
 use ic_registry_routing_table::{canister_id_into_u64, CanisterIdRange, RoutingTable};
 use ic_registry_subnet_type::SubnetType;
@@ -25,7 +26,7 @@ use ic_test_utilities_execution_environment::{
 };
 use ic_test_utilities_metrics::{fetch_histogram_vec_count, fetch_int_counter, metric_vec};
 use ic_types::{
-    canister_http::{CanisterHttpMethod, Transform},
+    canister_http::{CanisterHttpMethod, Replication, Transform},
     ingress::{IngressState, IngressStatus, WasmResult},
     messages::{
         CallbackId, Payload, RejectContext, RequestOrResponse, Response, MAX_RESPONSE_COUNT_BYTES,
@@ -2028,6 +2029,8 @@ fn execute_canister_http_request() {
             }),
             context: transform_context.clone(),
         }),
+        replication: None,
+        response_offset: None,
     };
 
     // Create request to HTTP_REQUEST method.
@@ -2082,6 +2085,138 @@ fn execute_canister_http_request() {
     );
 }
 
+#[test]
+fn execute_paged_canister_http_request_is_charged_for_downloaded_bytes() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    let response_size_limit = 1000u64;
+    let response_offset = 5000u64;
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: Some(response_size_limit),
+        headers: BoundedHttpHeaders::new(vec![]),
+        body: None,
+        method: HttpMethod::GET,
+        transform: None,
+        replication: None,
+        response_offset: Some(response_offset),
+    };
+
+    let payment = Cycles::new(1_000_000_000);
+    test.inject_call_to_ic00(Method::HttpRequest, args.encode(), payment);
+    test.execute_all();
+    let http_request_context = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .get(&CallbackId::from(0))
+        .unwrap();
+    assert_eq!(http_request_context.response_offset, Some(response_offset));
+    // The body up to the end of the page may have to be downloaded.
+    let fee = test.http_request_fee(
+        http_request_context.variable_parts_size(),
+        Some(NumBytes::from(response_offset + response_size_limit)),
+    );
+    assert!(
+        fee > test.http_request_fee(
+            http_request_context.variable_parts_size(),
+            Some(NumBytes::from(response_size_limit)),
+        )
+    );
+    assert_eq!(http_request_context.request.payment, payment - fee);
+}
+
+#[test]
+fn execute_non_replicated_canister_http_request() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: None,
+        headers: BoundedHttpHeaders::new(vec![]),
+        body: None,
+        method: HttpMethod::GET,
+        transform: None,
+        replication: Some(HttpOutcallReplication::NonReplicated(Some(vec![
+            node_test_id(1).get(),
+        ]))),
+        response_offset: None,
+    };
+
+    let payment = Cycles::new(1_000_000_000);
+    test.inject_call_to_ic00(Method::HttpRequest, args.encode(), payment);
+    test.execute_all();
+    let http_request_context = test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .get(&CallbackId::from(0))
+        .unwrap();
+    assert_eq!(
+        http_request_context.replication,
+        Replication::NonReplicated(node_test_id(1))
+    );
+    // The request is charged as if it was made on a single node subnet.
+    let fee = test
+        .cycles_account_manager()
+        .non_replicated_http_request_fee(http_request_context.variable_parts_size(), None);
+    assert!(fee < test.http_request_fee(http_request_context.variable_parts_size(), None));
+    assert_eq!(http_request_context.request.payment, payment - fee);
+}
+
+#[test]
+fn execute_non_replicated_canister_http_request_with_unknown_node() {
+    let own_subnet = subnet_test_id(1);
+    let caller_canister = canister_test_id(10);
+    let mut test = ExecutionTestBuilder::new()
+        .with_own_subnet_id(own_subnet)
+        .with_caller(own_subnet, caller_canister)
+        .build();
+    test.state_mut().metadata.own_subnet_features.http_requests = true;
+
+    let args = CanisterHttpRequestArgs {
+        url: "https://".to_string(),
+        max_response_bytes: None,
+        headers: BoundedHttpHeaders::new(vec![]),
+        body: None,
+        method: HttpMethod::GET,
+        transform: None,
+        replication: Some(HttpOutcallReplication::NonReplicated(Some(vec![
+            node_test_id(1000).get(),
+        ]))),
+        response_offset: None,
+    };
+
+    test.inject_call_to_ic00(
+        Method::HttpRequest,
+        args.encode(),
+        Cycles::new(1_000_000_000),
+    );
+    test.execute_all();
+    assert!(test
+        .state()
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .is_empty());
+    let response = test.xnet_messages()[0].clone();
+    assert!(get_reject_message(response).contains("is not a member of the subnet"));
+}
+
 #[test]
 fn execute_canister_http_request_disabled() {
     let own_subnet = subnet_test_id(1);
@@ -2107,6 +2242,8 @@ fn execute_canister_http_request_disabled() {
             }),
             context: vec![0, 1, 2],
         }),
+        replication: None,
+        response_offset: None,
     };
 
     // Create request to HTTP_REQUEST method.
@@ -3456,6 +3593,43 @@ fn test_canister_settings_log_visibility_set_to_public() {
     );
 }
 
+#[test]
+fn test_canister_settings_backtrace_visibility_default_disabled() {
+    // Arrange.
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.create_canister(Cycles::new(1_000_000_000));
+    // Act.
+    let result = test.canister_status(canister_id);
+    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    // Assert.
+    assert_eq!(
+        canister_status.settings().backtrace_visibility(),
+        BacktraceVisibility::Disabled
+    );
+}
+
+#[test]
+fn test_canister_settings_backtrace_visibility_create_with_settings() {
+    // Arrange.
+    let mut test = ExecutionTestBuilder::new().build();
+    // Act.
+    let canister_id = test
+        .create_canister_with_settings(
+            Cycles::new(1_000_000_000),
+            ic00::CanisterSettingsArgsBuilder::new()
+                .with_backtrace_visibility(BacktraceVisibility::Public)
+                .build(),
+        )
+        .unwrap();
+    let result = test.canister_status(canister_id);
+    let canister_status = CanisterStatusResultV2::decode(&get_reply(result)).unwrap();
+    // Assert.
+    assert_eq!(
+        canister_status.settings().backtrace_visibility(),
+        BacktraceVisibility::Public
+    );
+}
+
 #[test]
 fn test_fetch_canister_logs_should_accept_ingress_message_disabled() {
     // Arrange.
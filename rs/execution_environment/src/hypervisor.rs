@@ -2,12 +2,14 @@ use ic_canister_sandbox_backend_lib::replica_controller::sandboxed_execution_con
 use ic_config::execution_environment::{Config, MAX_COMPILATION_CACHE_SIZE};
 use ic_config::flag_status::FlagStatus;
 use ic_cycles_account_manager::CyclesAccountManager;
-use ic_embedders::wasm_executor::{WasmExecutionResult, WasmExecutor};
+use ic_embedders::wasm_executor::{wasm_execution_error, WasmExecutionResult, WasmExecutor};
 use ic_embedders::wasm_utils::decoding::decoded_wasm_size;
 use ic_embedders::{wasm_executor::WasmExecutorImpl, WasmExecutionInput, WasmtimeEmbedder};
 use ic_embedders::{CompilationCache, CompilationResult};
-use ic_interfaces::execution_environment::{HypervisorResult, WasmExecutionOutput};
-use ic_logger::ReplicaLogger;
+use ic_interfaces::execution_environment::{
+    HypervisorError, HypervisorResult, WasmExecutionOutput,
+};
+use ic_logger::{warn, ReplicaLogger};
 use ic_metrics::buckets::decimal_buckets_with_zero;
 use ic_metrics::{buckets::exponential_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
@@ -286,6 +288,27 @@ impl Hypervisor {
         embedder_config.subnet_type = own_subnet_type;
         embedder_config.dirty_page_overhead = dirty_page_overhead;
 
+        let compilation_cache = match &config.compilation_cache_dir {
+            Some(dir) => match WasmtimeEmbedder::compilation_cache_fingerprint(&embedder_config) {
+                Ok(fingerprint) => CompilationCache::new_with_persistent_tier(
+                    config.max_compilation_cache_size,
+                    dir,
+                    config.max_persistent_compilation_cache_size,
+                    &fingerprint,
+                    log.clone(),
+                ),
+                Err(err) => {
+                    warn!(
+                        log,
+                        "Persistent compilation cache disabled, failed to compute fingerprint: {}",
+                        err
+                    );
+                    CompilationCache::new(config.max_compilation_cache_size)
+                }
+            },
+            None => CompilationCache::new(config.max_compilation_cache_size),
+        };
+
         let wasm_executor: Arc<dyn WasmExecutor> = match config.canister_sandboxing_flag {
             FlagStatus::Enabled => {
                 let executor = SandboxedExecutionController::new(
@@ -315,7 +338,7 @@ impl Hypervisor {
             own_subnet_type,
             log,
             cycles_account_manager,
-            compilation_cache: Arc::new(CompilationCache::new(config.max_compilation_cache_size)),
+            compilation_cache: Arc::new(compilation_cache),
             deterministic_time_slicing: config.deterministic_time_slicing,
             cost_to_compile_wasm_instruction: config
                 .embedders_config
@@ -363,12 +386,12 @@ impl Hypervisor {
         &self,
         api_type: ApiType,
         time: Time,
-        mut system_state: SystemState,
+        system_state: SystemState,
         canister_current_memory_usage: NumBytes,
         canister_current_message_memory_usage: NumBytes,
         execution_parameters: ExecutionParameters,
         func_ref: FuncRef,
-        mut execution_state: ExecutionState,
+        execution_state: ExecutionState,
         network_topology: &NetworkTopology,
         round_limits: &mut RoundLimits,
         state_changes_error: &IntCounter,
@@ -379,7 +402,50 @@ impl Hypervisor {
             execution_parameters.instruction_limits.message(),
             execution_parameters.instruction_limits.slice()
         );
-        let execution_result = self.execute_dts(
+        self.execute_sliced(
+            api_type,
+            time,
+            system_state,
+            canister_current_memory_usage,
+            canister_current_message_memory_usage,
+            execution_parameters,
+            func_ref,
+            execution_state,
+            network_topology,
+            round_limits,
+            state_changes_error,
+            call_tree_metrics,
+            call_context_creation_time,
+            &mut || unreachable!("DTS is not supported"),
+        )
+    }
+
+    /// Same as `execute()`, but the execution pauses after every slice if the
+    /// slice instruction limit is below the message instruction limit. This is
+    /// meant for non-replicated executions, which run to completion on the
+    /// calling thread. After each slice, `continue_after_slice` decides whether
+    /// the execution resumes or is aborted with `HypervisorError::Aborted`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn execute_sliced(
+        &self,
+        api_type: ApiType,
+        time: Time,
+        mut system_state: SystemState,
+        canister_current_memory_usage: NumBytes,
+        canister_current_message_memory_usage: NumBytes,
+        execution_parameters: ExecutionParameters,
+        func_ref: FuncRef,
+        mut execution_state: ExecutionState,
+        network_topology: &NetworkTopology,
+        round_limits: &mut RoundLimits,
+        state_changes_error: &IntCounter,
+        call_tree_metrics: &dyn CallTreeMetrics,
+        call_context_creation_time: Time,
+        continue_after_slice: &mut dyn FnMut() -> bool,
+    ) -> (WasmExecutionOutput, ExecutionState, SystemState) {
+        let message_instruction_limit = execution_parameters.instruction_limits.message();
+        let mut executed_instructions = NumInstructions::from(0);
+        let mut execution_result = self.execute_dts(
             api_type,
             &execution_state,
             &system_state,
@@ -391,12 +457,26 @@ impl Hypervisor {
             round_limits,
             network_topology,
         );
-        let (slice, mut output, canister_state_changes) = match execution_result {
-            WasmExecutionResult::Finished(slice, output, system_state_changes) => {
-                (slice, output, system_state_changes)
-            }
-            WasmExecutionResult::Paused(_, _) => {
-                unreachable!("DTS is not supported");
+        let (slice, mut output, canister_state_changes) = loop {
+            match execution_result {
+                WasmExecutionResult::Finished(slice, output, canister_state_changes) => {
+                    break (slice, output, canister_state_changes);
+                }
+                WasmExecutionResult::Paused(slice, paused_execution) => {
+                    update_round_limits(round_limits, &slice);
+                    executed_instructions += slice.executed_instructions;
+                    execution_result = if continue_after_slice() {
+                        paused_execution.resume(&execution_state)
+                    } else {
+                        paused_execution.abort();
+                        let instructions_left = NumInstructions::from(
+                            message_instruction_limit
+                                .get()
+                                .saturating_sub(executed_instructions.get()),
+                        );
+                        wasm_execution_error(HypervisorError::Aborted, instructions_left)
+                    };
+                }
             }
         };
         update_round_limits(round_limits, &slice);
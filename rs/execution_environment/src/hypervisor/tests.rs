@@ -29,7 +29,7 @@ use ic_test_utilities_execution_environment::{
 };
 use ic_test_utilities_metrics::fetch_int_counter;
 use ic_test_utilities_metrics::{fetch_histogram_vec_stats, metric_vec, HistogramStats};
-use ic_types::messages::{CanisterMessage, NO_DEADLINE};
+use ic_types::messages::{CanisterMessage, MessageId, NO_DEADLINE};
 use ic_types::time::CoarseTime;
 use ic_types::Time;
 use ic_types::{
@@ -1476,6 +1476,200 @@ fn wasm64_active_data_segments() {
     assert_eq!(WasmResult::Reply(vec![]), result);
 }
 
+#[test]
+fn wasm64_system_api_uses_64_bit_pointers_and_sizes() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+            (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param i64 i64 i64)))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i64 i64)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update test")
+                (call $msg_arg_data_copy (i64.const 100) (i64.const 0) (call $msg_arg_data_size))
+                (call $msg_reply_data_append (i64.const 100) (call $msg_arg_data_size))
+                (call $msg_reply)
+            )
+            (memory i64 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![1, 2, 3]).unwrap();
+    assert_eq!(WasmResult::Reply(vec![1, 2, 3]), result);
+}
+
+#[test]
+fn wasm64_rejects_32_bit_system_api_signatures() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i32 i32)))
+            (memory i64 1)
+        )"#;
+    let err = test.canister_from_wat(wat).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidWasm, err.code());
+    assert!(err
+        .description()
+        .contains("Expected input params [I64, I64]"));
+
+    // The 32-bit stable memory API is not available to Wasm64 canisters.
+    let wat = r#"
+        (module
+            (import "ic0" "stable_size" (func $stable_size (result i32)))
+            (memory i64 1)
+        )"#;
+    let err = test.canister_from_wat(wat).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidWasm, err.code());
+}
+
+#[test]
+fn wasm64_heap_above_4_gib() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+            (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param i64 i64 i64)))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i64 i64)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_update test")
+                ;; Grow the heap to 4 GiB + 64 KiB.
+                (if (i64.ne (memory.grow (i64.const 65536)) (i64.const 1))
+                    (then (unreachable))
+                )
+                (call $msg_arg_data_copy
+                    (i64.const 0x1_0000_0010) (i64.const 0) (call $msg_arg_data_size))
+                (call $msg_reply_data_append
+                    (i64.const 0x1_0000_0010) (call $msg_arg_data_size))
+                (call $msg_reply)
+            )
+            (memory i64 1)
+        )"#;
+    // Enough cycles to cover the freezing threshold of a 4 GiB heap.
+    let canister_id = test
+        .canister_from_cycles_and_wat(Cycles::new(1u128 << 100), wat)
+        .unwrap();
+    let result = test.ingress(canister_id, "test", vec![1, 2, 3]).unwrap();
+    assert_eq!(WasmResult::Reply(vec![1, 2, 3]), result);
+}
+
+#[test]
+fn wasm64_heap_is_limited_by_config() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let wat = r#"
+        (module
+            (func (export "canister_update test")
+                ;; Try to grow the heap to 8 GiB, beyond the default limit.
+                (if (i64.ne (memory.grow (i64.const 131071)) (i64.const -1))
+                    (then (unreachable))
+                )
+            )
+            (memory i64 1)
+        )"#;
+    let canister_id = test.canister_from_wat(wat).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]).unwrap();
+    assert_eq!(WasmResult::Reply(vec![]), result);
+
+    let wat = r#"(module (memory i64 131072))"#;
+    let err = test.canister_from_wat(wat).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidWasm, err.code());
+}
+
+#[test]
+fn wasm64_callbacks_receive_64_bit_env() {
+    let mut test = ExecutionTestBuilder::new().with_wasm64().build();
+    let callee_id = test
+        .canister_from_wat(
+            r#"(module
+                (import "ic0" "msg_reply" (func $msg_reply))
+                (func (export "canister_update update") (call $msg_reply))
+                (memory 1)
+            )"#,
+        )
+        .unwrap();
+    let callee = callee_id.get().to_vec();
+    let wat = format!(
+        r#"
+        (module
+            (import "ic0" "call_new"
+                (func $ic0_call_new
+                    (param $callee_src i64)         (param $callee_size i64)
+                    (param $method_name_src i64)    (param $method_name_len i64)
+                    (param $reply_fun i64)          (param $reply_env i64)
+                    (param $reject_fun i64)         (param $reject_env i64)
+                )
+            )
+            (import "ic0" "call_perform" (func $ic0_call_perform (result i32)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func $on_reply (param $env i64)
+                (if (i64.ne (local.get $env) (i64.const 0x1_0000_0002))
+                    (then (unreachable))
+                )
+                (call $msg_reply)
+            )
+            (func $on_reject (param $env i64) (unreachable))
+            (table funcref (elem $on_reply $on_reject))
+            (func (export "canister_update test")
+                (call $ic0_call_new
+                    (i64.const 100) (i64.const {})
+                    (i64.const 0) (i64.const 6)                 ;; "update"
+                    (i64.const 0) (i64.const 0x1_0000_0002)     ;; on_reply closure
+                    (i64.const 1) (i64.const 0x1_0000_0003)     ;; on_reject closure
+                )
+                (drop (call $ic0_call_perform))
+            )
+            (memory i64 1)
+            (data (i64.const 0) "update")
+            (data (i64.const 100) "{}")
+        )"#,
+        callee.len(),
+        callee
+            .iter()
+            .map(|b| format!("\\{:02x}", b))
+            .collect::<String>()
+    );
+    let caller_id = test.canister_from_wat(wat).unwrap();
+    let (ingress_id, _) = test.ingress_raw(caller_id, "test", vec![]);
+    test.execute_all();
+    let result = check_ingress_status(test.ingress_status(&ingress_id)).unwrap();
+    assert_eq!(WasmResult::Reply(vec![]), result);
+}
+
+const TAIL_CALL_WAT: &str = r#"
+    (module
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func $countdown (param i64)
+            (if (i64.eqz (local.get 0)) (then (return)))
+            (return_call $countdown (i64.sub (local.get 0) (i64.const 1)))
+        )
+        (func (export "canister_update test")
+            ;; Deep enough to exhaust the Wasm stack without tail calls.
+            (call $countdown (i64.const 10_000_000))
+            (call $msg_reply)
+        )
+    )"#;
+
+#[test]
+fn tail_calls_are_rejected_unless_enabled() {
+    let mut test = ExecutionTestBuilder::new().without_wasm_tail_call().build();
+    let err = test.canister_from_wat(TAIL_CALL_WAT).unwrap_err();
+    assert_eq!(ErrorCode::CanisterInvalidWasm, err.code());
+    assert!(err
+        .description()
+        .contains("tail calls support is not enabled"));
+}
+
+#[test]
+fn tail_calls_do_not_grow_the_stack() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TAIL_CALL_WAT).unwrap();
+    let result = test.ingress(canister_id, "test", vec![]).unwrap();
+    assert_eq!(result, WasmResult::Reply(vec![]));
+}
+
 #[test]
 fn ic0_msg_caller_size_works_in_reply_callback() {
     let mut test = ExecutionTestBuilder::new().build();
@@ -5434,6 +5628,67 @@ fn ic0_msg_deadline_when_deadline_is_set() {
     };
 }
 
+const TRACE_ID_CANISTER_WAT: &str = r#"
+    (module
+        (import "ic0" "trace_id_size" (func $trace_id_size (result i32)))
+        (import "ic0" "trace_id_copy" (func $trace_id_copy (param i32 i32 i32)))
+        (import "ic0" "msg_reply_data_append" (func $msg_reply_data_append (param i32 i32)))
+        (import "ic0" "msg_reply" (func $msg_reply))
+        (func $reply_trace_id
+            (call $trace_id_copy (i32.const 0) (i32.const 0) (call $trace_id_size))
+            (call $msg_reply_data_append (i32.const 0) (call $trace_id_size))
+            (call $msg_reply)
+        )
+        (func (export "canister_update update") (call $reply_trace_id))
+        (func (export "canister_query query") (call $reply_trace_id))
+        (memory 1 1)
+    )"#;
+
+#[test]
+fn ic0_trace_id_copy_returns_trace_id_of_traced_ingress_call_tree() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TRACE_ID_CANISTER_WAT).unwrap();
+
+    let message_id = MessageId::from([7; 32]);
+    let ingress = ic_test_utilities_types::messages::IngressBuilder::new()
+        .message_id(message_id.clone())
+        .source(test.user_id())
+        .receiver(canister_id)
+        .method_name("update")
+        .traced(true)
+        .build();
+    test.state_mut()
+        .canister_state_mut(&canister_id)
+        .unwrap()
+        .push_ingress(ingress);
+    test.execute_all();
+    assert_eq!(
+        IngressState::Completed(WasmResult::Reply(message_id.as_bytes()[..16].to_vec())),
+        test.ingress_state(&message_id)
+    );
+}
+
+#[test]
+fn ic0_trace_id_size_is_zero_in_untraced_ingress_call_tree() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TRACE_ID_CANISTER_WAT).unwrap();
+
+    let (message_id, _) = test.ingress_raw(canister_id, "update", vec![]);
+    assert_eq!(
+        IngressState::Completed(WasmResult::Reply(vec![])),
+        test.ingress_state(&message_id)
+    );
+}
+
+#[test]
+fn ic0_trace_id_size_is_zero_in_untraced_query() {
+    let mut test = ExecutionTestBuilder::new().build();
+    let canister_id = test.canister_from_wat(TRACE_ID_CANISTER_WAT).unwrap();
+
+    let result = test.non_replicated_query(canister_id, "query", vec![]);
+    assert_eq!(Ok(WasmResult::Reply(vec![])), result);
+}
+
 fn display_page_map(page_map: PageMap, page_range: std::ops::Range<u64>) -> String {
     let mut contents = Vec::new();
     for page in page_range {
@@ -157,6 +157,9 @@ impl ExecutionServices {
         let query_scheduler = QueryScheduler::new(
             config.query_execution_threads_total,
             config.embedders_config.query_execution_threads_per_canister,
+            config
+                .embedders_config
+                .query_execution_threads_per_hot_canister,
             config.query_scheduling_time_slice_per_canister,
             metrics_registry,
             QuerySchedulerFlag::UseNewSchedulingAlgorithm,
@@ -262,6 +262,28 @@ impl InternalHttpQueryHandler {
         let subnet_available_memory = subnet_memory_capacity(&self.config);
         let max_canister_memory_size = self.config.max_canister_memory_size;
 
+        // Query time slicing relies on the sandbox support for DTS.
+        let query_time_slicing = if self.config.query_time_slicing == FlagStatus::Enabled
+            && self.config.deterministic_time_slicing == FlagStatus::Enabled
+        {
+            FlagStatus::Enabled
+        } else {
+            FlagStatus::Disabled
+        };
+        let (max_instructions_per_query, max_query_call_graph_instructions) =
+            match query_time_slicing {
+                FlagStatus::Enabled => (
+                    self.config.max_instructions_per_sliced_query,
+                    self.config
+                        .max_query_call_graph_instructions
+                        .max(self.config.max_instructions_per_sliced_query),
+                ),
+                FlagStatus::Disabled => (
+                    self.max_instructions_per_query,
+                    self.config.max_query_call_graph_instructions,
+                ),
+            };
+
         let mut context = query_context::QueryContext::new(
             &self.log,
             self.hypervisor.as_ref(),
@@ -273,9 +295,11 @@ impl InternalHttpQueryHandler {
             data_certificate,
             subnet_available_memory,
             max_canister_memory_size,
-            self.max_instructions_per_query,
+            max_instructions_per_query,
+            query_time_slicing,
+            self.config.max_instructions_per_query_slice,
             self.config.max_query_call_graph_depth,
-            self.config.max_query_call_graph_instructions,
+            max_query_call_graph_instructions,
             self.config.max_query_call_walltime,
             self.config.instruction_overhead_per_query_call,
             self.config.composite_queries,
@@ -16,6 +16,9 @@ use std::{collections::BTreeMap, mem::size_of_val, sync::Mutex, time::Duration};
 
 use crate::metrics::duration_histogram;
 
+mod policy;
+use policy::PolicySnapshot;
+
 #[cfg(test)]
 mod tests;
 
@@ -25,6 +28,7 @@ pub(crate) struct QueryCacheMetrics {
     pub hits: IntCounter,
     pub hits_with_ignored_time: IntCounter,
     pub hits_with_ignored_canister_balance: IntCounter,
+    pub hits_with_cache_policy: IntCounter,
     pub misses: IntCounter,
     pub evicted_entries: IntCounter,
     pub evicted_entries_duration: Histogram,
@@ -35,6 +39,8 @@ pub(crate) struct QueryCacheMetrics {
     pub invalidated_entries_by_canister_version: IntCounter,
     pub invalidated_entries_by_canister_balance: IntCounter,
     pub invalidated_entries_by_transient_error: IntCounter,
+    pub invalidated_entries_by_ttl: IntCounter,
+    pub invalidated_entries_by_dependency: IntCounter,
     pub invalidated_entries_duration: Histogram,
     pub count_bytes: IntGauge,
     pub len: IntGauge,
@@ -57,6 +63,10 @@ impl QueryCacheMetrics {
                 "execution_query_cache_hits_with_ignored_canister_balance_total",
                 "The total number of cache hits into entries with ignored canister balance",
             ),
+            hits_with_cache_policy: metrics_registry.int_counter(
+                "execution_query_cache_hits_with_cache_policy_total",
+                "The total number of cache hits into changed canisters allowed by their cache policy",
+            ),
             misses: metrics_registry.int_counter(
                 "execution_query_cache_misses_total",
                 "The total number of replica side query cache misses",
@@ -98,6 +108,14 @@ impl QueryCacheMetrics {
                 "execution_query_cache_invalidated_entries_by_transient_error_total",
                 "The total number of invalidated entries due to a transient error",
             ),
+            invalidated_entries_by_ttl: metrics_registry.int_counter(
+                "execution_query_cache_invalidated_entries_by_ttl_total",
+                "The total number of invalidated entries due to the canister cache policy TTL",
+            ),
+            invalidated_entries_by_dependency: metrics_registry.int_counter(
+                "execution_query_cache_invalidated_entries_by_dependency_total",
+                "The total number of invalidated entries due to the changed canister cache policy dependency",
+            ),
             invalidated_entries_duration: duration_histogram(
                 "execution_query_cache_invalidated_entries_duration_seconds",
                 "The duration of invalidated cache entries in seconds",
@@ -209,6 +227,9 @@ pub(crate) struct EntryValue {
     ignore_batch_time: bool,
     /// If set, the canister balance changes might be ignored.
     ignore_canister_balances: bool,
+    /// If set, the cache policy declared by the receiver replaces the time,
+    /// canister version and balance checks.
+    policy: Option<PolicySnapshot>,
 }
 
 impl CountBytes for EntryValue {
@@ -236,9 +257,16 @@ impl EntryValue {
             includes_data_certificate,
             ignore_batch_time,
             ignore_canister_balances,
+            policy: None,
         }
     }
 
+    /// Applies the cache policy declared by the receiver, if any.
+    fn with_policy(mut self, policy: Option<PolicySnapshot>) -> EntryValue {
+        self.policy = policy;
+        self
+    }
+
     fn is_valid(
         &self,
         state: &ReplicatedState,
@@ -272,13 +300,29 @@ impl EntryValue {
         let is_expired = self.is_expired(now, max_expiry_time);
         let is_expired_data_certificate =
             self.is_expired_data_certificate(now, data_certificate_expiry_time);
+        let (is_expired_ttl, is_changed_dependency) = match &self.policy {
+            Some(policy) => (
+                policy.is_expired(self.env.batch_time, now),
+                !policy.is_unchanged(state),
+            ),
+            None => (false, false),
+        };
+
+        // The cache policy replaces the time, canister version and balance checks.
+        let has_policy = self.policy.is_some();
+        let is_valid_time = self.env.batch_time == now || self.ignore_batch_time || has_policy;
+        let is_valid_canister_versions = all_canister_versions_are_valid || has_policy;
+        let is_valid_canister_balances =
+            all_canister_balances_are_valid || self.ignore_canister_balances || has_policy;
 
         // Check if the cache entry value is valid.
         if !is_expired
             && !is_expired_data_certificate
-            && (self.env.batch_time == now || self.ignore_batch_time)
-            && all_canister_versions_are_valid
-            && (all_canister_balances_are_valid || self.ignore_canister_balances)
+            && !is_expired_ttl
+            && !is_changed_dependency
+            && is_valid_time
+            && is_valid_canister_versions
+            && is_valid_canister_balances
         {
             // The value is still valid.
             metrics.hits.inc();
@@ -297,6 +341,13 @@ impl EntryValue {
             if !all_canister_balances_are_valid && self.ignore_canister_balances {
                 metrics.hits_with_ignored_canister_balance.inc();
             }
+            // A hit into a changed canister is only possible with a cache policy.
+            if !(self.env.batch_time == now || self.ignore_batch_time)
+                || !all_canister_versions_are_valid
+                || !(all_canister_balances_are_valid || self.ignore_canister_balances)
+            {
+                metrics.hits_with_cache_policy.inc();
+            }
             true
         } else {
             // The value is invalid.
@@ -314,13 +365,19 @@ impl EntryValue {
                     .invalidated_entries_by_data_certificate_expiry_time
                     .inc();
             }
-            if !(self.env.batch_time == now || self.ignore_batch_time) {
+            if is_expired_ttl {
+                metrics.invalidated_entries_by_ttl.inc();
+            }
+            if is_changed_dependency {
+                metrics.invalidated_entries_by_dependency.inc();
+            }
+            if !is_valid_time {
                 metrics.invalidated_entries_by_time.inc();
             }
-            if !all_canister_versions_are_valid {
+            if !is_valid_canister_versions {
                 metrics.invalidated_entries_by_canister_version.inc();
             }
-            if !(all_canister_balances_are_valid || self.ignore_canister_balances) {
+            if !is_valid_canister_balances {
                 metrics.invalidated_entries_by_canister_balance.inc();
             }
             false
@@ -458,7 +515,9 @@ impl QueryCache {
             return;
         };
 
-        let value = EntryValue::new(env, result.clone(), system_api_counters);
+        let policy =
+            PolicySnapshot::try_new(state, key.receiver, &key.method_name, evaluated_stats);
+        let value = EntryValue::new(env, result.clone(), system_api_counters).with_policy(policy);
         let mut cache = self.cache.lock().unwrap();
         let evicted_entries = cache.push(key, value);
 
@@ -0,0 +1,196 @@
+//! Canister-controlled query cache policies.
+//!
+//! By default, a cached query result is invalidated on any change of the
+//! evaluated canisters, which for most canisters means on every round.
+//! A canister may relax this for its own queries by exporting a public or
+//! private `query_cache` custom section. Each line of the section declares
+//! a policy for one query method:
+//!
+//! ```text
+//! <method name> [ttl=<seconds>] [depends_on=stable_memory|depends_on=global:<index>]
+//! ```
+//!
+//! - `ttl=<seconds>`: the result may be served for up to `seconds` after it
+//!   was computed, regardless of the time, canister version or balance.
+//! - `depends_on=stable_memory`: the result stays valid as long as the stable
+//!   memory of the canister is unchanged.
+//! - `depends_on=global:<index>`: the result stays valid as long as the value
+//!   of the exported global with the given index is unchanged. The index
+//!   counts the exported globals in the order of their exports, so a canister
+//!   may export a version counter and bump it whenever its data changes.
+//!
+//! A policy must declare a TTL, a dependency, or both. Lines that are empty or
+//! start with `#` are ignored. In all cases, a result is invalidated when the
+//! module of the canister changes, and the query cache max expiry and data
+//! certificate expiry times still apply.
+//!
+//! A policy only applies to queries that do not call other canisters,
+//! i.e. when the receiver is the only evaluated canister.
+
+use ic_base_types::CanisterId;
+use ic_replicated_state::{page_map::PageMapVersion, Global, NumWasmPages, ReplicatedState};
+use ic_types::{batch::QueryStats, Time};
+use std::{collections::BTreeMap, time::Duration};
+
+#[cfg(test)]
+mod tests;
+
+/// The name of the custom section declaring query cache policies.
+pub(crate) const QUERY_CACHE_SECTION_NAME: &str = "query_cache";
+
+/// The canister state a query result depends on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Dependency {
+    /// The stable memory of the canister.
+    StableMemory,
+    /// The exported global with the given index.
+    Global(usize),
+}
+
+/// The query cache policy declared by a canister for one of its methods.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub(crate) struct CachePolicy {
+    /// How long the result may be served regardless of the canister changes.
+    pub ttl: Option<Duration>,
+    /// The only canister state the result depends on.
+    pub depends_on: Option<Dependency>,
+}
+
+impl CachePolicy {
+    /// Returns the policy for `method_name` declared in the `query_cache`
+    /// custom section `content`, if any.
+    ///
+    /// A malformed section or line is ignored, so the default cache
+    /// semantics apply.
+    pub(crate) fn find(content: &[u8], method_name: &str) -> Option<CachePolicy> {
+        let content = std::str::from_utf8(content).ok()?;
+        content.lines().find_map(|line| {
+            if line.trim_start().starts_with('#') {
+                return None;
+            }
+            let mut tokens = line.split_whitespace();
+            match tokens.next() {
+                Some(name) if name == method_name => Self::parse(tokens),
+                _ => None,
+            }
+        })
+    }
+
+    fn parse<'a>(tokens: impl Iterator<Item = &'a str>) -> Option<CachePolicy> {
+        let mut policy = CachePolicy {
+            ttl: None,
+            depends_on: None,
+        };
+        for token in tokens {
+            match token.split_once('=')? {
+                ("ttl", secs) if policy.ttl.is_none() => {
+                    policy.ttl = Some(Duration::from_secs(secs.parse().ok()?));
+                }
+                ("depends_on", "stable_memory") if policy.depends_on.is_none() => {
+                    policy.depends_on = Some(Dependency::StableMemory);
+                }
+                ("depends_on", dependency) if policy.depends_on.is_none() => {
+                    let index = dependency.strip_prefix("global:")?.parse().ok()?;
+                    policy.depends_on = Some(Dependency::Global(index));
+                }
+                _ => return None,
+            }
+        }
+        if policy.ttl.is_none() && policy.depends_on.is_none() {
+            return None;
+        }
+        Some(policy)
+    }
+}
+
+/// The state of a policy dependency captured before the query execution.
+enum DependencySnapshot {
+    StableMemory {
+        version: PageMapVersion,
+        size: NumWasmPages,
+    },
+    Global {
+        index: usize,
+        value: Global,
+    },
+}
+
+/// A cache policy together with the canister state it was captured with.
+pub(crate) struct PolicySnapshot {
+    canister_id: CanisterId,
+    ttl: Option<Duration>,
+    module_hash: [u8; 32],
+    dependency: Option<DependencySnapshot>,
+}
+
+impl PolicySnapshot {
+    /// Captures the policy the receiver declared for `method_name`, if any.
+    ///
+    /// Returns `None` if the receiver declared no policy for the method,
+    /// if the declared dependency does not exist, or if the query evaluated
+    /// other canisters besides the receiver.
+    pub(crate) fn try_new(
+        state: &ReplicatedState,
+        receiver: CanisterId,
+        method_name: &str,
+        evaluated_stats: &BTreeMap<CanisterId, QueryStats>,
+    ) -> Option<Self> {
+        if evaluated_stats.len() != 1 || !evaluated_stats.contains_key(&receiver) {
+            return None;
+        }
+        let execution_state = state.canister_state(&receiver)?.execution_state.as_ref()?;
+        let section = execution_state
+            .metadata
+            .get_custom_section(QUERY_CACHE_SECTION_NAME)?;
+        let policy = CachePolicy::find(section.content(), method_name)?;
+        let dependency = match policy.depends_on {
+            None => None,
+            Some(Dependency::StableMemory) => Some(DependencySnapshot::StableMemory {
+                version: execution_state.stable_memory.page_map.version(),
+                size: execution_state.stable_memory.size,
+            }),
+            Some(Dependency::Global(index)) => Some(DependencySnapshot::Global {
+                index,
+                value: *execution_state.exported_globals.get(index)?,
+            }),
+        };
+        Some(Self {
+            canister_id: receiver,
+            ttl: policy.ttl,
+            module_hash: execution_state.wasm_binary.binary.module_hash(),
+            dependency,
+        })
+    }
+
+    /// Returns true if the result computed at `batch_time` outlived its TTL.
+    pub(crate) fn is_expired(&self, batch_time: Time, now: Time) -> bool {
+        match self.ttl {
+            Some(ttl) => now.saturating_duration_since(batch_time) > ttl,
+            None => false,
+        }
+    }
+
+    /// Returns true if neither the module nor the declared dependency of the
+    /// canister changed since the snapshot was taken.
+    pub(crate) fn is_unchanged(&self, state: &ReplicatedState) -> bool {
+        let Some(execution_state) = state
+            .canister_state(&self.canister_id)
+            .and_then(|canister| canister.execution_state.as_ref())
+        else {
+            return false;
+        };
+        if execution_state.wasm_binary.binary.module_hash() != self.module_hash {
+            return false;
+        }
+        match &self.dependency {
+            None => true,
+            Some(DependencySnapshot::StableMemory { version, size }) => {
+                let stable_memory = &execution_state.stable_memory;
+                stable_memory.size == *size && stable_memory.page_map.has_version(version)
+            }
+            Some(DependencySnapshot::Global { index, value }) => {
+                execution_state.exported_globals.get(*index) == Some(value)
+            }
+        }
+    }
+}
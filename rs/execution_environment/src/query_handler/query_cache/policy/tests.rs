@@ -0,0 +1,58 @@
+use super::{CachePolicy, Dependency};
+use std::time::Duration;
+
+fn find(content: &str, method_name: &str) -> Option<CachePolicy> {
+    CachePolicy::find(content.as_bytes(), method_name)
+}
+
+#[test]
+fn query_cache_policy_parses_ttl_and_dependencies() {
+    let content = "
+        # Static assets.
+        http_request ttl=60 depends_on=stable_memory
+        get_config depends_on=global:2
+        get_rate ttl=5
+    ";
+    assert_eq!(
+        find(content, "http_request"),
+        Some(CachePolicy {
+            ttl: Some(Duration::from_secs(60)),
+            depends_on: Some(Dependency::StableMemory),
+        })
+    );
+    assert_eq!(
+        find(content, "get_config"),
+        Some(CachePolicy {
+            ttl: None,
+            depends_on: Some(Dependency::Global(2)),
+        })
+    );
+    assert_eq!(
+        find(content, "get_rate"),
+        Some(CachePolicy {
+            ttl: Some(Duration::from_secs(5)),
+            depends_on: None,
+        })
+    );
+    assert_eq!(find(content, "get"), None);
+    assert_eq!(find(content, "#"), None);
+}
+
+#[test]
+fn query_cache_policy_ignores_malformed_lines() {
+    for line in [
+        "f",
+        "f ttl",
+        "f ttl=",
+        "f ttl=-1",
+        "f ttl=1 ttl=2",
+        "f depends_on=heap",
+        "f depends_on=global:",
+        "f depends_on=global:x",
+        "f depends_on=stable_memory depends_on=global:0",
+        "f ttl=1 unknown=1",
+    ] {
+        assert_eq!(find(line, "f"), None, "{}", line);
+    }
+    assert_eq!(CachePolicy::find(&[0xff, 0xfe], "f"), None);
+}
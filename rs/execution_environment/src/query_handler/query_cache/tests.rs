@@ -58,6 +58,50 @@ const QUERY_CACHE_WAT: &str = r#"
     (export "canister_query f2" (func $f))
 )"#;
 
+const QUERY_CACHE_POLICY_WAT: &str = r#"
+(module
+    (import "ic0" "msg_reply" (func $msg_reply))
+    (import "ic0" "msg_reply_data_append"
+        (func $msg_reply_data_append (param i32 i32)))
+    (import "ic0" "stable_grow" (func $stable_grow (param i32) (result i32)))
+    (import "ic0" "stable_read" (func $stable_read (param i32 i32 i32)))
+    (import "ic0" "stable_write" (func $stable_write (param i32 i32 i32)))
+
+    (memory 1)
+    (global $version (export "version") (mut i64) (i64.const 0))
+
+    (func (export "canister_init")
+        (drop (call $stable_grow (i32.const 1)))
+    )
+
+    ;; Reply with the first byte of the stable memory.
+    (func $read_stable
+        (call $stable_read (i32.const 0) (i32.const 0) (i32.const 1))
+        (call $msg_reply_data_append (i32.const 0) (i32.const 1))
+        (call $msg_reply)
+    )
+
+    (func (export "canister_update write_stable")
+        (i32.store8 (i32.const 0)
+            (i32.add (i32.load8_u (i32.const 0)) (i32.const 1)))
+        (call $stable_write (i32.const 0) (i32.const 0) (i32.const 1))
+        (call $msg_reply)
+    )
+
+    (func (export "canister_update bump_version")
+        (global.set $version (i64.add (global.get $version) (i64.const 1)))
+        (call $msg_reply)
+    )
+
+    (export "canister_query with_ttl" (func $read_stable))
+    (export "canister_query with_stable_memory" (func $read_stable))
+    (export "canister_query with_version" (func $read_stable))
+    (export "canister_query without_policy" (func $read_stable))
+
+    (@custom "icp:public query_cache"
+        "with_ttl ttl=5\nwith_stable_memory depends_on=stable_memory\nwith_version depends_on=global:0")
+)"#;
+
 fn downcast_query_handler(query_handler: &dyn std::any::Any) -> &InternalHttpQueryHandler {
     // SAFETY:
     //
@@ -1460,6 +1504,120 @@ fn query_cache_supports_query_stats() {
     });
 }
 
+#[test]
+fn query_cache_policy_ttl_ignores_canister_changes() {
+    let mut test = builder_with_query_cache_expiry_times().build();
+    let id = test.canister_from_wat(QUERY_CACHE_POLICY_WAT).unwrap();
+    let res_1 = test.non_replicated_query(id, "with_ttl", vec![]);
+    assert_eq!(res_1, Ok(WasmResult::Reply(vec![0])));
+
+    // Change the stable memory, the canister version and balance.
+    test.ingress(id, "write_stable", vec![]).unwrap();
+    test.state_mut().metadata.batch_time += Duration::from_secs(5);
+
+    // Run the same query for the second time.
+    let res_2 = test.non_replicated_query(id, "with_ttl", vec![]);
+    // Assert it's a stale hit within the TTL.
+    let m = query_cache_metrics(&test);
+    assert_eq!(1, m.misses.get());
+    assert_eq!(1, m.hits.get());
+    assert_eq!(1, m.hits_with_cache_policy.get());
+    assert_eq!(res_1, res_2);
+
+    // Run the same query after the TTL.
+    test.state_mut().metadata.batch_time += Duration::from_secs(1);
+    let res_3 = test.non_replicated_query(id, "with_ttl", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(2, m.misses.get());
+    assert_eq!(1, m.invalidated_entries.get());
+    assert_eq!(1, m.invalidated_entries_by_ttl.get());
+    assert_eq!(0, m.invalidated_entries_by_canister_version.get());
+    assert_eq!(res_3, Ok(WasmResult::Reply(vec![1])));
+}
+
+#[test]
+fn query_cache_policy_depends_on_stable_memory() {
+    let mut test = builder_with_query_cache_expiry_times().build();
+    let id = test.canister_from_wat(QUERY_CACHE_POLICY_WAT).unwrap();
+    let res_1 = test.non_replicated_query(id, "with_stable_memory", vec![]);
+    assert_eq!(res_1, Ok(WasmResult::Reply(vec![0])));
+
+    // Change the canister version, but not the stable memory.
+    test.ingress(id, "bump_version", vec![]).unwrap();
+    let res_2 = test.non_replicated_query(id, "with_stable_memory", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(1, m.misses.get());
+    assert_eq!(1, m.hits_with_cache_policy.get());
+    assert_eq!(res_1, res_2);
+
+    // Change the stable memory.
+    test.ingress(id, "write_stable", vec![]).unwrap();
+    let res_3 = test.non_replicated_query(id, "with_stable_memory", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(2, m.misses.get());
+    assert_eq!(1, m.invalidated_entries_by_dependency.get());
+    assert_eq!(0, m.invalidated_entries_by_canister_version.get());
+    assert_eq!(res_3, Ok(WasmResult::Reply(vec![1])));
+}
+
+#[test]
+fn query_cache_policy_depends_on_exported_global() {
+    let mut test = builder_with_query_cache_expiry_times().build();
+    let id = test.canister_from_wat(QUERY_CACHE_POLICY_WAT).unwrap();
+    let res_1 = test.non_replicated_query(id, "with_version", vec![]);
+    assert_eq!(res_1, Ok(WasmResult::Reply(vec![0])));
+
+    // Change the stable memory, but not the version global.
+    test.ingress(id, "write_stable", vec![]).unwrap();
+    let res_2 = test.non_replicated_query(id, "with_version", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(1, m.misses.get());
+    assert_eq!(1, m.hits_with_cache_policy.get());
+    assert_eq!(res_1, res_2);
+
+    // Bump the version global.
+    test.ingress(id, "bump_version", vec![]).unwrap();
+    let res_3 = test.non_replicated_query(id, "with_version", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(2, m.misses.get());
+    assert_eq!(1, m.invalidated_entries_by_dependency.get());
+    assert_eq!(res_3, Ok(WasmResult::Reply(vec![1])));
+}
+
+#[test]
+fn query_cache_policy_does_not_apply_to_other_methods() {
+    let mut test = builder_with_query_cache_expiry_times().build();
+    let id = test.canister_from_wat(QUERY_CACHE_POLICY_WAT).unwrap();
+    let res_1 = test.non_replicated_query(id, "without_policy", vec![]);
+    assert_eq!(res_1, Ok(WasmResult::Reply(vec![0])));
+
+    test.ingress(id, "bump_version", vec![]).unwrap();
+    let res_2 = test.non_replicated_query(id, "without_policy", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(2, m.misses.get());
+    assert_eq!(1, m.invalidated_entries_by_canister_version.get());
+    assert_eq!(0, m.hits_with_cache_policy.get());
+    assert_eq!(res_1, res_2);
+}
+
+#[test]
+fn query_cache_policy_expires_on_module_change() {
+    let mut test = builder_with_query_cache_expiry_times().build();
+    let id = test.canister_from_wat(QUERY_CACHE_POLICY_WAT).unwrap();
+    let res_1 = test.non_replicated_query(id, "with_ttl", vec![]);
+    assert_eq!(res_1, Ok(WasmResult::Reply(vec![0])));
+
+    // Reinstall the canister with a different module.
+    let wat = QUERY_CACHE_POLICY_WAT.replace("(memory 1)", "(memory 2)");
+    test.reinstall_canister(id, wat::parse_str(wat).unwrap())
+        .unwrap();
+    let res_2 = test.non_replicated_query(id, "with_ttl", vec![]);
+    let m = query_cache_metrics(&test);
+    assert_eq!(2, m.misses.get());
+    assert_eq!(1, m.invalidated_entries_by_dependency.get());
+    assert_eq!(res_1, res_2);
+}
+
 #[test]
 fn query_cache_future_proof_test() {
     match SystemApiCallId::AcceptMessage {
@@ -1506,6 +1664,7 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::MsgRejectMsgSize
         | SystemApiCallId::MsgReply
         | SystemApiCallId::MsgReplyDataAppend
+        | SystemApiCallId::MsgTip
         | SystemApiCallId::OutOfInstructions
         | SystemApiCallId::PerformanceCounter
         | SystemApiCallId::Stable64Grow
@@ -1517,6 +1676,8 @@ fn query_cache_future_proof_test() {
         | SystemApiCallId::StableSize
         | SystemApiCallId::StableWrite
         | SystemApiCallId::Time
+        | SystemApiCallId::TraceIdCopy
+        | SystemApiCallId::TraceIdSize
         | SystemApiCallId::Trap
         | SystemApiCallId::TryGrowWasmMemory => {
             ////////////////////////////////////////////////////////////////////
@@ -1534,6 +1695,8 @@ fn query_cache_future_proof_test() {
             //   call dependent on canister balance.
             // * Changes in `canister_version` always invalidate cache entries.
             //   This includes update calls, configuration changes, upgrades...
+            //   The only exception are canisters declaring a cache policy for
+            //   the query, see `query_cache/policy.rs`.
             //
             // If you introduce a new System API call that depends on
             // time or balance or a new Canister property that should
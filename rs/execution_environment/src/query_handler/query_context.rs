@@ -1,4 +1,4 @@
-use super::query_call_graph::evaluate_query_call_graph;
+use super::{query_call_graph::evaluate_query_call_graph, QueryScheduler};
 use crate::{
     execution::common::{self, validate_method},
     execution::nonreplicated_query::execute_non_replicated_query,
@@ -83,6 +83,24 @@ fn wasm_query_method(
     }
 }
 
+/// Returns the function to call between the execution slices of a query of the
+/// given canister. It lets queries of other canisters run on the current thread
+/// and stops the query once the time limit of the query context is reached. The
+/// time spent on queries of other canisters does not count towards that limit.
+fn continue_after_slice(
+    canister_id: CanisterId,
+    query_context_time_start: Instant,
+    query_context_time_limit: Duration,
+) -> impl FnMut() -> bool {
+    move || {
+        QueryScheduler::yield_to_other_canisters(canister_id);
+        query_context_time_start
+            .elapsed()
+            .saturating_sub(QueryScheduler::yielded_duration())
+            < query_context_time_limit
+    }
+}
+
 /// Executes a single user query along with its outgoing query calls.
 pub(super) struct QueryContext<'a> {
     log: &'a ReplicaLogger,
@@ -95,6 +113,10 @@ pub(super) struct QueryContext<'a> {
     data_certificate: (Vec<u8>, CanisterId),
     max_canister_memory_size: NumBytes,
     max_instructions_per_query: NumInstructions,
+    // If enabled, queries and response callbacks pause every
+    // `max_instructions_per_query_slice` instructions.
+    query_time_slicing: FlagStatus,
+    max_instructions_per_query_slice: NumInstructions,
     max_query_call_graph_depth: usize,
     instruction_overhead_per_query_call: RoundInstructions,
     round_limits: RoundLimits,
@@ -125,6 +147,8 @@ impl<'a> QueryContext<'a> {
         subnet_available_memory: SubnetAvailableMemory,
         max_canister_memory_size: NumBytes,
         max_instructions_per_query: NumInstructions,
+        query_time_slicing: FlagStatus,
+        max_instructions_per_query_slice: NumInstructions,
         max_query_call_graph_depth: usize,
         max_query_call_graph_instructions: NumInstructions,
         max_query_call_walltime: Duration,
@@ -151,6 +175,8 @@ impl<'a> QueryContext<'a> {
             data_certificate: (data_certificate, canister_id),
             max_canister_memory_size,
             max_instructions_per_query,
+            query_time_slicing,
+            max_instructions_per_query_slice,
             max_query_call_graph_depth,
             instruction_overhead_per_query_call: as_round_instructions(
                 instruction_overhead_per_query_call,
@@ -404,11 +430,19 @@ impl<'a> QueryContext<'a> {
         let instruction_limit = self.max_instructions_per_query.min(NumInstructions::new(
             self.round_limits.instructions.get().max(0) as u64,
         ));
-        let instruction_limits =
-            InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit);
+        let instruction_limits = InstructionLimits::new(
+            self.query_time_slicing,
+            instruction_limit,
+            self.max_instructions_per_query_slice,
+        );
         let execution_parameters = self.execution_parameters(&canister, instruction_limits);
 
         let data_certificate = self.get_data_certificate(&canister.canister_id());
+        let mut continue_after_slice = continue_after_slice(
+            canister.canister_id(),
+            self.query_context_time_start,
+            self.query_context_time_limit,
+        );
         let (mut canister, instructions_left, result, call_context_id, system_api_call_counters) =
             execute_non_replicated_query(
                 query_kind,
@@ -422,6 +456,7 @@ impl<'a> QueryContext<'a> {
                 self.hypervisor,
                 &mut self.round_limits,
                 self.query_critical_error,
+                &mut continue_after_slice,
             );
         self.add_system_api_call_counters(system_api_call_counters);
         let instructions_executed = instruction_limit - instructions_left;
@@ -606,8 +641,11 @@ impl<'a> QueryContext<'a> {
         let instruction_limit = self.max_instructions_per_query.min(NumInstructions::new(
             self.round_limits.instructions.get().max(0) as u64,
         ));
-        let instruction_limits =
-            InstructionLimits::new(FlagStatus::Disabled, instruction_limit, instruction_limit);
+        let instruction_limits = InstructionLimits::new(
+            self.query_time_slicing,
+            instruction_limit,
+            self.max_instructions_per_query_slice,
+        );
         let mut execution_parameters = self.execution_parameters(&canister, instruction_limits);
         let api_type = match response.response_payload {
             Payload::Data(payload) => ApiType::reply_callback(
@@ -632,7 +670,7 @@ impl<'a> QueryContext<'a> {
             ),
         };
 
-        let (output, output_execution_state, output_system_state) = self.hypervisor.execute(
+        let (output, output_execution_state, output_system_state) = self.hypervisor.execute_sliced(
             api_type,
             time,
             canister.system_state.clone(),
@@ -646,9 +684,20 @@ impl<'a> QueryContext<'a> {
             self.query_critical_error,
             &CallTreeMetricsNoOp,
             call_context.time(),
+            &mut continue_after_slice(
+                canister_id,
+                self.query_context_time_start,
+                self.query_context_time_limit,
+            ),
         );
 
         self.add_system_api_call_counters(output.system_api_call_counters);
+        if let Err(HypervisorError::Aborted) = output.wasm_result {
+            return Err(UserError::new(
+                ErrorCode::QueryTimeLimitExceeded,
+                "Composite query call exceeded the time limit.",
+            ));
+        }
         let canister_current_memory_usage = canister.memory_usage();
         let canister_current_message_memory_usage = canister.message_memory_usage();
         canister.execution_state = Some(output_execution_state);
@@ -728,8 +777,11 @@ impl<'a> QueryContext<'a> {
                 FuncRef::QueryClosure(cleanup_closure)
             }
         };
+        // The cleanup callback is never aborted, as it has to undo the effects
+        // of the failed callback.
+        let canister_id = canister.canister_id();
         let (cleanup_output, output_execution_state, output_system_state) =
-            self.hypervisor.execute(
+            self.hypervisor.execute_sliced(
                 ApiType::Cleanup {
                     caller: call_origin.get_principal(),
                     time,
@@ -748,6 +800,10 @@ impl<'a> QueryContext<'a> {
                 self.query_critical_error,
                 &CallTreeMetricsNoOp,
                 time,
+                &mut || {
+                    QueryScheduler::yield_to_other_canisters(canister_id);
+                    true
+                },
             );
 
         self.add_system_api_call_counters(cleanup_output.system_api_call_counters);
@@ -1019,7 +1075,10 @@ impl<'a> QueryContext<'a> {
 
     /// Return whether the time limit for this query context has been reached.
     pub fn time_limit_reached(&self) -> bool {
-        self.query_context_time_start.elapsed() >= self.query_context_time_limit
+        self.query_context_time_start
+            .elapsed()
+            .saturating_sub(QueryScheduler::yielded_duration())
+            >= self.query_context_time_limit
     }
 
     /// Returns a synthetic reject response for the case when a query call
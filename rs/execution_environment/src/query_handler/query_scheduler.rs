@@ -7,7 +7,7 @@ use ic_base_types::CanisterId;
 use ic_metrics::MetricsRegistry;
 
 use self::{
-    internal::{Query, QuerySchedulerInternal},
+    internal::{Query, QuerySchedulerInternal, ThreadLimits},
     thread_pool::QueryThreadPool,
 };
 
@@ -32,7 +32,13 @@ pub(crate) enum QuerySchedulerFlag {
 /// multiple queries until it reaches the `time_slice_per_canister` limit.
 /// The algorithm also ensures that each canister executes on at most
 /// `max_threads_per_canister` threads, which is necessary to avoid performance
-/// regression due to the memory bottleneck in the sandbox process.
+/// regression due to the memory bottleneck in the sandbox process. The only
+/// exception are hot canisters, which have more queries queued than they can
+/// execute in one time slice: they may execute on up to
+/// `max_threads_per_hot_canister` threads to raise their query throughput.
+/// The sandbox keeps a warm Wasm instance of the canister state per thread,
+/// so the concurrent queries of a hot canister share the state snapshot and
+/// skip the instance setup.
 #[derive(Clone)]
 pub(crate) enum QueryScheduler {
     NewScheduler {
@@ -50,12 +56,15 @@ impl QueryScheduler {
     /// Creates a query scheduler with `num_threads` threads.
     /// If the new scheduling algorithm is enabled, then it guarantees that
     /// there are no more than `max_threads_per_canister` threads processing
-    /// queries from the same canister concurrently at any point of time.
+    /// queries from the same canister concurrently at any point of time, or
+    /// `max_threads_per_hot_canister` threads if the canister is hot. A hot
+    /// canister always leaves at least one thread to other canisters.
     /// The `time_slice_per_canister` parameter defines how long a canister runs
     /// once it is scheduled for execution.
     pub fn new(
         num_threads: usize,
         max_threads_per_canister: usize,
+        max_threads_per_hot_canister: usize,
         time_slice_per_canister: Duration,
         metrics_registry: &MetricsRegistry,
         flag: QuerySchedulerFlag,
@@ -63,7 +72,12 @@ impl QueryScheduler {
         match flag {
             QuerySchedulerFlag::UseNewSchedulingAlgorithm => {
                 let scheduler = QuerySchedulerInternal::new(
-                    max_threads_per_canister,
+                    ThreadLimits {
+                        regular: max_threads_per_canister,
+                        hot: max_threads_per_hot_canister
+                            .min(num_threads.saturating_sub(1))
+                            .max(max_threads_per_canister),
+                    },
                     time_slice_per_canister,
                     metrics_registry,
                 );
@@ -105,4 +119,20 @@ impl QueryScheduler {
             }
         }
     }
+
+    /// Called by a query of the given canister that paused after an execution
+    /// slice. If queries of other canisters are waiting, then executes a batch
+    /// of them on the current thread before returning, so that long-running
+    /// queries do not starve other canisters. This is a no-op with the old
+    /// scheduling algorithm.
+    pub fn yield_to_other_canisters(canister_id: CanisterId) {
+        thread_pool::yield_to_other_canisters(canister_id);
+    }
+
+    /// Returns the time the query executing on the current thread spent in
+    /// `yield_to_other_canisters()` so far. This time does not count towards
+    /// the time limit of the query.
+    pub fn yielded_duration() -> Duration {
+        thread_pool::yielded_duration()
+    }
 }
@@ -6,7 +6,7 @@ use std::{
 
 use ic_base_types::CanisterId;
 use ic_metrics::{buckets::decimal_buckets_with_zero, MetricsRegistry};
-use prometheus::Histogram;
+use prometheus::{Histogram, IntCounter};
 
 /// An estimate of the average query execution duration. It is used at the
 /// start when there are no stats about the actual query execution duration.
@@ -16,6 +16,8 @@ pub(crate) const DEFAULT_QUERY_DURATION: Duration = Duration::from_millis(5);
 
 pub(crate) struct QuerySchedulerMetrics {
     pub queue_length: Histogram,
+    pub hot_canister_batches: IntCounter,
+    pub yielded_batches: IntCounter,
 }
 
 impl QuerySchedulerMetrics {
@@ -26,6 +28,14 @@ impl QuerySchedulerMetrics {
                 "The length of the query queue sampled for each arriving query",
                 decimal_buckets_with_zero(0, 4),
             ),
+            hot_canister_batches: metrics_registry.int_counter(
+                "execution_query_scheduler_hot_canister_batches_total",
+                "The number of query batches of hot canisters executed on threads beyond the regular per-canister limit",
+            ),
+            yielded_batches: metrics_registry.int_counter(
+                "execution_query_scheduler_yielded_batches_total",
+                "The number of query batches executed by threads of paused long-running queries",
+            ),
         }
     }
 }
@@ -39,6 +49,17 @@ impl Query {
     }
 }
 
+/// Limits on the number of threads concurrently executing queries of the same
+/// canister. A canister is considered hot if its queued queries would take its
+/// `regular` number of threads more than one time slice to execute. Hot
+/// canisters may use up to `hot` threads, which is less than the total number
+/// of threads, so that other canisters keep making progress.
+#[derive(Clone, Copy)]
+pub(crate) struct ThreadLimits {
+    pub regular: usize,
+    pub hot: usize,
+}
+
 /// Contains the query queues and execution stats of a canister.
 pub(crate) struct CanisterData {
     // All incoming queries are pushed into this FIFO queue.
@@ -78,16 +99,42 @@ impl CanisterData {
         !(self.incoming.is_empty() && self.leftover.is_empty())
     }
 
+    // Returns true if the queued queries of the canister cannot be executed
+    // within one time slice by the regular number of threads.
+    fn is_hot(&self, thread_limits: ThreadLimits, time_slice_per_canister: Duration) -> bool {
+        let queued = self.incoming.len() + self.leftover.len();
+        queued > self.queries_per_time_slice(time_slice_per_canister) * thread_limits.regular
+    }
+
+    // Returns the maximum number of threads that may execute queries of the
+    // canister concurrently.
+    fn max_threads(&self, thread_limits: ThreadLimits, time_slice_per_canister: Duration) -> usize {
+        if self.is_hot(thread_limits, time_slice_per_canister) {
+            thread_limits.hot.max(thread_limits.regular)
+        } else {
+            thread_limits.regular
+        }
+    }
+
     // Returns true if the canister is blocked due to the max thread capacity
     // and cannot execute new queries until the pending executions finish.
-    fn is_waiting_for_pending_executions(&self, max_threads_per_canister: usize) -> bool {
-        self.active_threads >= max_threads_per_canister
+    fn is_waiting_for_pending_executions(
+        &self,
+        thread_limits: ThreadLimits,
+        time_slice_per_canister: Duration,
+    ) -> bool {
+        self.active_threads >= self.max_threads(thread_limits, time_slice_per_canister)
     }
 
     // Returns true if the canister should be added to the `scheduled` canister
     // queue of the scheduler.
-    fn should_be_scheduled(&self, max_threads_per_canister: usize) -> bool {
-        self.has_queries() && !self.is_waiting_for_pending_executions(max_threads_per_canister)
+    fn should_be_scheduled(
+        &self,
+        thread_limits: ThreadLimits,
+        time_slice_per_canister: Duration,
+    ) -> bool {
+        self.has_queries()
+            && !self.is_waiting_for_pending_executions(thread_limits, time_slice_per_canister)
     }
 
     // Returns the number of queries that can be executed in one batch based on
@@ -116,11 +163,11 @@ struct QuerySchedulerCore {
     // - its `has_been_scheduled` flag is set.
     // - it has at least one query to execute.
     // - the number of currently running threads of this canister is below
-    //   the `max_threads_per_canister` limit.
+    //   its limit in `thread_limits`.
     scheduled: VecDeque<CanisterId>,
 
-    // The limit on the number of concurrently running threads per canister.
-    max_threads_per_canister: usize,
+    // The limits on the number of concurrently running threads per canister.
+    thread_limits: ThreadLimits,
 
     // The time limit for executing a batch of queries.
     time_slice_per_canister: Duration,
@@ -135,14 +182,14 @@ struct QuerySchedulerCore {
 
 impl QuerySchedulerCore {
     fn new(
-        max_threads_per_canister: usize,
+        thread_limits: ThreadLimits,
         time_slice_per_canister: Duration,
         metrics_registry: &MetricsRegistry,
     ) -> Self {
         Self {
             canisters: HashMap::default(),
             scheduled: VecDeque::default(),
-            max_threads_per_canister,
+            thread_limits,
             time_slice_per_canister,
             tearing_down: false,
             metrics: QuerySchedulerMetrics::new(metrics_registry),
@@ -162,8 +209,9 @@ impl QuerySchedulerCore {
             .queue_length
             .observe((canister.incoming.len() + canister.leftover.len()) as f64);
 
+        // Adding a query can only make the canister hot, never the opposite.
         if !canister.has_been_scheduled
-            && canister.should_be_scheduled(self.max_threads_per_canister)
+            && canister.should_be_scheduled(self.thread_limits, self.time_slice_per_canister)
         {
             canister.has_been_scheduled = true;
             self.scheduled.push_back(canister_id);
@@ -176,6 +224,21 @@ impl QuerySchedulerCore {
     /// Returns a batch of queries to execute if there are any.
     fn pop(&mut self) -> Option<(CanisterId, Vec<Query>)> {
         let canister_id = self.scheduled.pop_front()?;
+        Some(self.take_batch(canister_id))
+    }
+
+    /// Returns a batch of queries of the first scheduled canister other than
+    /// the given one, if there is any.
+    fn pop_other(&mut self, excluded: CanisterId) -> Option<(CanisterId, Vec<Query>)> {
+        let index = self.scheduled.iter().position(|id| *id != excluded)?;
+        let canister_id = self.scheduled.remove(index).unwrap();
+        self.metrics.yielded_batches.inc();
+        Some(self.take_batch(canister_id))
+    }
+
+    // Takes a batch of queries of the given canister that has just been
+    // removed from the `scheduled` queue.
+    fn take_batch(&mut self, canister_id: CanisterId) -> (CanisterId, Vec<Query>) {
         // It is safe to unwrap here because of the invariants in
         // `validate_invariants()`: each canister in the round-robin list must
         // be present in the canister table.
@@ -196,12 +259,15 @@ impl QuerySchedulerCore {
         // Follows from the main invariant.
         debug_assert!(!result.is_empty());
 
+        if canister.active_threads >= self.thread_limits.regular {
+            self.metrics.hot_canister_batches.inc();
+        }
         canister.active_threads += 1;
 
         // We removed the canister from the schedule at the beginning of this
         // method and cleared `has_been_scheduled`.
         debug_assert!(!canister.has_been_scheduled);
-        if canister.should_be_scheduled(self.max_threads_per_canister) {
+        if canister.should_be_scheduled(self.thread_limits, self.time_slice_per_canister) {
             canister.has_been_scheduled = true;
             self.scheduled.push_back(canister_id);
         }
@@ -209,7 +275,7 @@ impl QuerySchedulerCore {
         #[cfg(debug_assertions)]
         self.verify_invariants();
 
-        Some((canister_id, result))
+        (canister_id, result)
     }
 
     // This is called by the query execution thread after it finished executing
@@ -227,11 +293,16 @@ impl QuerySchedulerCore {
         canister.leftover.extend(leftover);
         canister.active_threads -= 1;
 
-        if !canister.has_been_scheduled
-            && canister.should_be_scheduled(self.max_threads_per_canister)
-        {
+        // The updated average query duration may make the canister cool down,
+        // in which case it has to wait for its other threads to finish.
+        let should_be_scheduled =
+            canister.should_be_scheduled(self.thread_limits, self.time_slice_per_canister);
+        if !canister.has_been_scheduled && should_be_scheduled {
             canister.has_been_scheduled = true;
             self.scheduled.push_back(canister_id);
+        } else if canister.has_been_scheduled && !should_be_scheduled {
+            canister.has_been_scheduled = false;
+            self.scheduled.retain(|id| *id != canister_id);
         }
 
         #[cfg(debug_assertions)]
@@ -251,10 +322,12 @@ impl QuerySchedulerCore {
         for canister_id in self.scheduled.iter() {
             let canister = self.canisters.get(canister_id).unwrap();
             debug_assert!(canister.has_been_scheduled);
-            debug_assert!(canister.should_be_scheduled(self.max_threads_per_canister));
+            debug_assert!(
+                canister.should_be_scheduled(self.thread_limits, self.time_slice_per_canister)
+            );
         }
         for (canister_id, canister) in self.canisters.iter() {
-            if canister.should_be_scheduled(self.max_threads_per_canister) {
+            if canister.should_be_scheduled(self.thread_limits, self.time_slice_per_canister) {
                 debug_assert!(canister.has_been_scheduled);
                 debug_assert!(self.scheduled.contains(canister_id))
             } else {
@@ -276,13 +349,13 @@ pub(crate) struct QuerySchedulerInternal {
 
 impl QuerySchedulerInternal {
     pub fn new(
-        max_threads_per_canister: usize,
+        thread_limits: ThreadLimits,
         time_slice_per_canister: Duration,
         metrics_registry: &MetricsRegistry,
     ) -> Self {
         Self {
             core: Arc::new(Mutex::new(QuerySchedulerCore::new(
-                max_threads_per_canister,
+                thread_limits,
                 time_slice_per_canister,
                 metrics_registry,
             ))),
@@ -324,6 +397,13 @@ impl QuerySchedulerInternal {
         core.pop()
     }
 
+    /// Returns a batch of queries of a canister other than the given one if
+    /// there is any ready for execution. This function never blocks.
+    pub fn try_pop_other(&self, canister_id: CanisterId) -> Option<(CanisterId, Vec<Query>)> {
+        let mut core = self.core.lock().unwrap();
+        core.pop_other(canister_id)
+    }
+
     // This is called by the query execution thread after it finished executing
     // a batch of queries.
     pub fn notify_finished_execution(
@@ -357,8 +437,12 @@ mod tests {
     #[test]
     fn query_scheduler_metrics_recorded() {
         let metrics_registry = MetricsRegistry::new();
-        let scheduler =
-            QuerySchedulerInternal::new(2, Duration::from_millis(100), &metrics_registry);
+        let thread_limits = ThreadLimits { regular: 2, hot: 2 };
+        let scheduler = QuerySchedulerInternal::new(
+            thread_limits,
+            Duration::from_millis(100),
+            &metrics_registry,
+        );
 
         scheduler.push(
             canister_test_id(0),
@@ -1,7 +1,7 @@
 use std::{
     sync::{
-        atomic::{AtomicU32, Ordering},
-        Arc, Mutex,
+        atomic::{AtomicBool, AtomicU32, Ordering},
+        mpsc, Arc, Mutex,
     },
     time::Duration,
 };
@@ -12,14 +12,17 @@ use ic_types_test_utils::ids::canister_test_id;
 use crate::query_handler::query_scheduler::internal::DEFAULT_QUERY_DURATION;
 
 use super::{
-    internal::{Query, QuerySchedulerInternal},
+    internal::{Query, QuerySchedulerInternal, ThreadLimits},
     QueryScheduler, QuerySchedulerFlag,
 };
 
+const NO_HOT_THREADS: ThreadLimits = ThreadLimits { regular: 2, hot: 2 };
+
 #[test]
 fn query_scheduler_does_not_starve_canisters() {
     let metrics_registry = MetricsRegistry::new();
     let scheduler = QueryScheduler::new(
+        1,
         1,
         1,
         Duration::from_millis(1),
@@ -60,6 +63,7 @@ fn query_scheduler_with_single_threaded_canister() {
     let scheduler = QueryScheduler::new(
         4,
         1,
+        1,
         Duration::from_millis(1),
         &metrics_registry,
         QuerySchedulerFlag::UseNewSchedulingAlgorithm,
@@ -89,7 +93,8 @@ fn query_scheduler_with_single_threaded_canister() {
 #[test]
 fn query_scheduler_respects_max_threads_per_canister() {
     let metrics_registry = MetricsRegistry::new();
-    let scheduler = QuerySchedulerInternal::new(2, Duration::from_millis(1), &metrics_registry);
+    let scheduler =
+        QuerySchedulerInternal::new(NO_HOT_THREADS, Duration::from_millis(1), &metrics_registry);
     for _ in 0..100 {
         scheduler.push(
             canister_test_id(0),
@@ -121,7 +126,8 @@ fn query_scheduler_respects_max_threads_per_canister() {
 #[test]
 fn query_scheduler_does_round_robin() {
     let metrics_registry = MetricsRegistry::new();
-    let scheduler = QuerySchedulerInternal::new(2, Duration::from_millis(1), &metrics_registry);
+    let scheduler =
+        QuerySchedulerInternal::new(NO_HOT_THREADS, Duration::from_millis(1), &metrics_registry);
 
     for c in 0..10 {
         for _ in 0..100 {
@@ -162,7 +168,11 @@ fn query_scheduler_does_round_robin() {
 #[test]
 fn query_scheduler_adjusts_batch_size() {
     let metrics_registry = MetricsRegistry::new();
-    let scheduler = QuerySchedulerInternal::new(2, Duration::from_millis(100), &metrics_registry);
+    let scheduler = QuerySchedulerInternal::new(
+        NO_HOT_THREADS,
+        Duration::from_millis(100),
+        &metrics_registry,
+    );
 
     for _ in 0..100 {
         scheduler.push(
@@ -195,7 +205,11 @@ fn query_scheduler_adjusts_batch_size() {
 #[test]
 fn query_scheduler_drains_leftover_queue_before_new_queries() {
     let metrics_registry = MetricsRegistry::new();
-    let scheduler = QuerySchedulerInternal::new(2, Duration::from_millis(100), &metrics_registry);
+    let scheduler = QuerySchedulerInternal::new(
+        NO_HOT_THREADS,
+        Duration::from_millis(100),
+        &metrics_registry,
+    );
 
     for i in 0..100 {
         scheduler.push(
@@ -222,7 +236,11 @@ fn query_scheduler_drains_leftover_queue_before_new_queries() {
 #[test]
 fn query_scheduler_properly_reads_leftover_queries() {
     let metrics_registry = MetricsRegistry::new();
-    let scheduler = QuerySchedulerInternal::new(2, Duration::from_millis(100), &metrics_registry);
+    let scheduler = QuerySchedulerInternal::new(
+        NO_HOT_THREADS,
+        Duration::from_millis(100),
+        &metrics_registry,
+    );
 
     scheduler.push(
         canister_test_id(0),
@@ -249,3 +267,178 @@ fn query_scheduler_properly_reads_leftover_queries() {
 
     assert_eq!(queries.len(), 1);
 }
+
+#[test]
+fn query_scheduler_gives_more_threads_to_hot_canisters() {
+    let metrics_registry = MetricsRegistry::new();
+    let thread_limits = ThreadLimits { regular: 2, hot: 4 };
+    let scheduler =
+        QuerySchedulerInternal::new(thread_limits, Duration::from_millis(10), &metrics_registry);
+
+    // The canister is not hot: two batches of 10ms / 5ms = 2 queries drain it.
+    for _ in 0..4 {
+        scheduler.push(
+            canister_test_id(0),
+            Query(Box::new(move || DEFAULT_QUERY_DURATION)),
+        );
+    }
+    assert_eq!(scheduler.try_pop().unwrap().1.len(), 2);
+    assert_eq!(scheduler.try_pop().unwrap().1.len(), 2);
+    assert!(scheduler.try_pop().is_none());
+
+    // The canister turns hot once it has more queries queued than its two
+    // threads execute in one time slice, so it gets two more threads.
+    for _ in 0..20 {
+        scheduler.push(
+            canister_test_id(0),
+            Query(Box::new(move || DEFAULT_QUERY_DURATION)),
+        );
+    }
+    assert_eq!(scheduler.try_pop().unwrap().1.len(), 2);
+    assert_eq!(scheduler.try_pop().unwrap().1.len(), 2);
+    assert!(scheduler.try_pop().is_none());
+}
+
+#[test]
+fn query_scheduler_cools_down_hot_canisters() {
+    let metrics_registry = MetricsRegistry::new();
+    let thread_limits = ThreadLimits { regular: 1, hot: 3 };
+    let scheduler =
+        QuerySchedulerInternal::new(thread_limits, Duration::from_millis(10), &metrics_registry);
+
+    for _ in 0..7 {
+        scheduler.push(
+            canister_test_id(0),
+            Query(Box::new(move || DEFAULT_QUERY_DURATION)),
+        );
+    }
+    assert_eq!(scheduler.try_pop().unwrap().1.len(), 2);
+    assert_eq!(scheduler.try_pop().unwrap().1.len(), 2);
+
+    // Faster queries make the remaining 3 queries fit into one time slice, so
+    // the canister is no longer hot and has to wait for its other thread.
+    scheduler.notify_finished_execution(canister_test_id(0), Duration::from_millis(1), vec![]);
+    assert!(scheduler.try_pop().is_none());
+
+    scheduler.notify_finished_execution(canister_test_id(0), Duration::from_millis(1), vec![]);
+    assert_eq!(scheduler.try_pop().unwrap().1.len(), 3);
+    assert!(scheduler.try_pop().is_none());
+}
+
+#[test]
+fn query_scheduler_pops_other_canisters_for_paused_queries() {
+    let metrics_registry = MetricsRegistry::new();
+    let scheduler =
+        QuerySchedulerInternal::new(NO_HOT_THREADS, Duration::from_millis(10), &metrics_registry);
+
+    scheduler.push(
+        canister_test_id(0),
+        Query(Box::new(move || DEFAULT_QUERY_DURATION)),
+    );
+    // A paused query of canister 0 never picks up queries of its own canister.
+    assert!(scheduler.try_pop_other(canister_test_id(0)).is_none());
+
+    scheduler.push(
+        canister_test_id(1),
+        Query(Box::new(move || DEFAULT_QUERY_DURATION)),
+    );
+    let (canister_id, batch) = scheduler.try_pop_other(canister_test_id(0)).unwrap();
+    assert_eq!(canister_id, canister_test_id(1));
+    assert_eq!(batch.len(), 1);
+
+    // The queries of canister 0 are still scheduled for the regular threads.
+    assert_eq!(scheduler.try_pop().unwrap().0, canister_test_id(0));
+    assert!(scheduler.try_pop().is_none());
+}
+
+#[test]
+fn query_scheduler_executes_other_canisters_while_query_is_paused() {
+    let metrics_registry = MetricsRegistry::new();
+    let scheduler = QueryScheduler::new(
+        1,
+        1,
+        1,
+        Duration::from_millis(1),
+        &metrics_registry,
+        QuerySchedulerFlag::UseNewSchedulingAlgorithm,
+    );
+    let other_query_duration = Duration::from_millis(50);
+    let other_executed = Arc::new(AtomicBool::new(false));
+    let (pushed_tx, pushed_rx) = mpsc::channel();
+    let (result_tx, result_rx) = mpsc::channel();
+
+    // The only thread executes a query of canister 0, which pauses once the
+    // queue of canister 1 is not empty.
+    {
+        let other_executed = Arc::clone(&other_executed);
+        scheduler.push(canister_test_id(0), move || {
+            pushed_rx.recv().unwrap();
+            QueryScheduler::yield_to_other_canisters(canister_test_id(0));
+            result_tx
+                .send((
+                    other_executed.load(Ordering::SeqCst),
+                    QueryScheduler::yielded_duration(),
+                ))
+                .unwrap();
+            DEFAULT_QUERY_DURATION
+        });
+    }
+    scheduler.push(canister_test_id(1), move || {
+        std::thread::sleep(other_query_duration);
+        other_executed.store(true, Ordering::SeqCst);
+        other_query_duration
+    });
+    pushed_tx.send(()).unwrap();
+
+    // The query of canister 1 ran on the thread of the paused query, and its
+    // duration is accounted as yielded time of the paused query.
+    let (other_executed, yielded_duration) = result_rx.recv().unwrap();
+    assert!(other_executed);
+    assert!(yielded_duration >= other_query_duration);
+}
+
+#[test]
+fn query_scheduler_runs_cold_canisters_while_hot_canister_is_saturated() {
+    let metrics_registry = MetricsRegistry::new();
+    let scheduler = QueryScheduler::new(
+        4,
+        2,
+        4,
+        Duration::from_millis(1),
+        &metrics_registry,
+        QuerySchedulerFlag::UseNewSchedulingAlgorithm,
+    );
+    let released = Arc::new(AtomicBool::new(false));
+    let running = Arc::new(AtomicU32::default());
+    let max_running = Arc::new(AtomicU32::default());
+    for _ in 0..100 {
+        let released = Arc::clone(&released);
+        let running = Arc::clone(&running);
+        let max_running = Arc::clone(&max_running);
+        scheduler.push(canister_test_id(0), move || {
+            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+            max_running.fetch_max(now_running, Ordering::SeqCst);
+            while !released.load(Ordering::SeqCst) {
+                std::thread::sleep(Duration::from_millis(1));
+            }
+            running.fetch_sub(1, Ordering::SeqCst);
+            Duration::from_millis(10)
+        });
+    }
+
+    // The hot canister gets all threads but one.
+    while running.load(Ordering::SeqCst) < 3 {
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    let (tx, rx) = mpsc::channel();
+    scheduler.push(canister_test_id(1), move || {
+        tx.send(()).unwrap();
+        Duration::from_millis(1)
+    });
+    let cold_result = rx.recv_timeout(Duration::from_secs(10));
+    released.store(true, Ordering::SeqCst);
+
+    assert!(cold_result.is_ok());
+    assert_eq!(max_running.load(Ordering::SeqCst), 3);
+}
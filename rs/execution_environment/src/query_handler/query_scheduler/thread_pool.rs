@@ -1,6 +1,11 @@
-use std::time::Duration;
+use std::{
+    cell::{Cell, RefCell},
+    time::{Duration, Instant},
+};
 
-use super::internal::QuerySchedulerInternal;
+use ic_base_types::CanisterId;
+
+use super::internal::{Query, QuerySchedulerInternal};
 
 /// Manages a thread-pool where each thread polls queries from `scheduler` and
 /// executes them. The threads stop when the thread-pool object is dropped.
@@ -44,27 +49,80 @@ impl Drop for QueryThreadPool {
     }
 }
 
+thread_local! {
+    // The scheduler of the current query execution thread. It is taken out
+    // while the thread executes a batch of queries on behalf of a paused
+    // query, so that the queries of that batch do not yield recursively.
+    static YIELD_TARGET: RefCell<Option<(QuerySchedulerInternal, Duration)>> =
+        const { RefCell::new(None) };
+
+    // The time the current query spent executing batches of other canisters.
+    static YIELDED_DURATION: Cell<Duration> = const { Cell::new(Duration::ZERO) };
+}
+
+/// Executes one batch of queries of a canister other than the given one on the
+/// current thread if there is any waiting. This lets a long-running query of
+/// the given canister, which paused after an execution slice, share its thread
+/// with other canisters. Does nothing outside of the query execution threads
+/// and when called from a query that itself runs on behalf of a paused query.
+pub(super) fn yield_to_other_canisters(canister_id: CanisterId) {
+    let Some((scheduler, time_slice_per_canister)) = YIELD_TARGET.with(|t| t.borrow_mut().take())
+    else {
+        return;
+    };
+    if let Some((other_canister_id, queries)) = scheduler.try_pop_other(canister_id) {
+        let start = Instant::now();
+        execute_batch(
+            &scheduler,
+            time_slice_per_canister,
+            other_canister_id,
+            queries,
+        );
+        YIELDED_DURATION.with(|d| d.set(d.get() + start.elapsed()));
+    }
+    YIELD_TARGET.with(|t| *t.borrow_mut() = Some((scheduler, time_slice_per_canister)));
+}
+
+/// Returns the time the current query spent executing queries of other
+/// canisters in `yield_to_other_canisters()` so far.
+pub(super) fn yielded_duration() -> Duration {
+    YIELDED_DURATION.with(|d| d.get())
+}
+
 // The body of each thread in the thread-pool.
 fn query_execution_thread(time_slice_per_canister: Duration, scheduler: QuerySchedulerInternal) {
-    loop {
-        match scheduler.pop() {
-            None => break,
-            Some((canister_id, queries)) => {
-                let mut iter = queries.into_iter();
-                let mut query_duration_sum = Duration::ZERO;
-                let mut query_duration_cnt = 0;
-                for query in iter.by_ref() {
-                    let query_duration = query.execute();
-                    query_duration_sum += query_duration;
-                    query_duration_cnt += 1;
-                    if query_duration_sum >= time_slice_per_canister {
-                        break;
-                    }
-                }
-                let average_query_duration = query_duration_sum / query_duration_cnt.max(1);
-                let leftover = iter.collect();
-                scheduler.notify_finished_execution(canister_id, average_query_duration, leftover)
-            }
+    YIELD_TARGET.with(|t| *t.borrow_mut() = Some((scheduler.clone(), time_slice_per_canister)));
+    while let Some((canister_id, queries)) = scheduler.pop() {
+        execute_batch(&scheduler, time_slice_per_canister, canister_id, queries);
+    }
+}
+
+// Executes the given queries one by one until the total execution duration
+// exceeds `time_slice_per_canister` and returns the rest to the scheduler.
+fn execute_batch(
+    scheduler: &QuerySchedulerInternal,
+    time_slice_per_canister: Duration,
+    canister_id: CanisterId,
+    queries: Vec<Query>,
+) {
+    let mut iter = queries.into_iter();
+    let mut query_duration_sum = Duration::ZERO;
+    let mut query_duration_cnt = 0;
+    for query in iter.by_ref() {
+        // The time spent on queries of other canisters while this query was
+        // paused does not count towards its duration.
+        let yielded_before = YIELDED_DURATION.with(|d| d.replace(Duration::ZERO));
+        let query_duration = query
+            .execute()
+            .saturating_sub(YIELDED_DURATION.with(|d| d.get()));
+        YIELDED_DURATION.with(|d| d.set(yielded_before));
+        query_duration_sum += query_duration;
+        query_duration_cnt += 1;
+        if query_duration_sum >= time_slice_per_canister {
+            break;
         }
     }
+    let average_query_duration = query_duration_sum / query_duration_cnt.max(1);
+    let leftover = iter.collect();
+    scheduler.notify_finished_execution(canister_id, average_query_duration, leftover)
 }
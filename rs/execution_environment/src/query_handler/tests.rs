@@ -13,7 +13,7 @@ use ic_types::{
     messages::{Query, QuerySource},
     Cycles, NumInstructions,
 };
-use std::sync::Arc;
+use std::{sync::Arc, time::Duration};
 
 const CYCLES_BALANCE: Cycles = Cycles::new(100_000_000_000_000);
 
@@ -1814,3 +1814,65 @@ fn query_call_exceeds_instructions_limit() {
         ))
     );
 }
+
+#[test]
+fn query_call_with_time_slicing_exceeds_slice_limit() {
+    let instructions_limit = 100;
+    let mut test = ExecutionTestBuilder::new()
+        .with_instruction_limit_without_dts(instructions_limit)
+        .with_query_time_slicing()
+        .with_max_instructions_per_query_slice(instructions_limit)
+        .build();
+
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    // The query exceeds the single slice limit, but completes in several slices.
+    let output = test.query(
+        Query {
+            source: QuerySource::User {
+                user_id: user_test_id(1),
+                ingress_expiry: 0,
+                nonce: None,
+            },
+            receiver: canister,
+            method_name: "query".to_string(),
+            method_payload: wasm().stable_grow(10).reply_data(b"done").build(),
+        },
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(output, Ok(WasmResult::Reply(b"done".to_vec())));
+}
+
+#[test]
+fn query_call_with_time_slicing_exceeds_time_limit() {
+    let mut test = ExecutionTestBuilder::new()
+        .with_query_time_slicing()
+        .with_max_instructions_per_query_slice(4)
+        .with_max_query_call_walltime(Duration::ZERO)
+        .build();
+
+    let canister = test.universal_canister_with_cycles(CYCLES_BALANCE).unwrap();
+
+    let output = test.query(
+        Query {
+            source: QuerySource::User {
+                user_id: user_test_id(1),
+                ingress_expiry: 0,
+                nonce: None,
+            },
+            receiver: canister,
+            method_name: "query".to_string(),
+            method_payload: wasm().stable_grow(10).reply_data(b"done").build(),
+        },
+        Arc::new(test.state().clone()),
+        vec![],
+    );
+    assert_eq!(
+        output,
+        Err(UserError::new(
+            ErrorCode::QueryTimeLimitExceeded,
+            format!("Query of canister {} exceeded the time limit.", canister),
+        ))
+    );
+}
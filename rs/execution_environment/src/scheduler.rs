@@ -1232,7 +1232,8 @@ impl SchedulerImpl {
                                 self.log,
                                 "Inducting {:?} on same subnet failed with error '{}'.", &msg, &err
                             );
-                        }),
+                        })
+                        .map(|()| self.metrics.call_graph.observe(msg)),
                     None => Err(()),
                 });
             let messages_after_induction = source_canister
@@ -1793,6 +1794,8 @@ impl Scheduler for SchedulerImpl {
                     .total_canister_reserved_balance
                     .set(total_canister_reserved_balance.get() as f64);
 
+                self.metrics.call_graph.observe_round();
+
                 // TODO(EXC-1124): Re-enable the check below once it's fixed.
                 //
                 // Check that amount of cycles at the beginning of the round (balances and cycles from input messages) is bigger or equal
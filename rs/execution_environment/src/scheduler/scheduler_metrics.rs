@@ -4,7 +4,10 @@ use ic_metrics::{
     buckets::{decimal_buckets, decimal_buckets_with_zero, linear_buckets},
     MetricsRegistry,
 };
-use ic_replicated_state::canister_state::system_state::CyclesUseCase;
+use ic_replicated_state::{
+    call_graph::{CallGraphMetrics, SOURCE_SCHEDULER},
+    canister_state::system_state::CyclesUseCase,
+};
 use ic_types::nominal_cycles::NominalCycles;
 use prometheus::{
     Gauge, GaugeVec, Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
@@ -106,6 +109,8 @@ pub(super) struct SchedulerMetrics {
     pub(super) canister_paused_install_code: Histogram,
     pub(super) canister_aborted_install_code: Histogram,
     pub(super) inducted_messages: IntCounterVec,
+    /// Calls between canisters on this subnet, inducted by the scheduler.
+    pub(super) call_graph: CallGraphMetrics,
     pub(super) ecdsa_signature_agreements: IntGauge,
     pub(super) delivered_pre_signatures: HistogramVec,
     pub(super) completed_signature_request_contexts: IntCounterVec,
@@ -674,6 +679,7 @@ impl SchedulerMetrics {
                 "Number of messages inducted, by destination.",
                 &["destination"],
             ),
+            call_graph: CallGraphMetrics::new(metrics_registry, SOURCE_SCHEDULER),
             stop_canister_calls_without_call_id:  metrics_registry.int_gauge(
                 "scheduler_stop_canister_calls_without_call_id",
                 "Number of stop canister calls with missing call ID.",
@@ -2756,6 +2756,8 @@ fn canister_is_stopped_if_timeout_occurs_and_ready_to_stop() {
             body: None,
             transform: None,
             max_response_bytes: None,
+            replication: None,
+            response_offset: None,
         })
         .unwrap();
 
@@ -3735,6 +3737,8 @@ fn consumed_cycles_http_outcalls_are_added_to_consumed_cycles_total() {
             }),
             context: transform_context,
         }),
+        replication: None,
+        response_offset: None,
     };
 
     // Create request to `HttpRequest` method.
@@ -5193,6 +5197,7 @@ fn test_is_next_method_added_to_task_queue() {
             method_payload: vec![1_u8],
             message_id: message_test_id(555),
             expiry_time: expiry_time_from_now(),
+            traced: false,
         });
 
     assert!(test
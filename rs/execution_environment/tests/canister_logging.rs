@@ -3,9 +3,9 @@ use ic_config::execution_environment::Config as ExecutionConfig;
 use ic_config::flag_status::FlagStatus;
 use ic_config::subnet_config::SubnetConfig;
 use ic_management_canister_types::{
-    self as ic00, CanisterIdRecord, CanisterInstallMode, CanisterLogRecord, CanisterSettingsArgs,
-    CanisterSettingsArgsBuilder, DataSize, EmptyBlob, FetchCanisterLogsRequest,
-    FetchCanisterLogsResponse, LogVisibility, Payload,
+    self as ic00, BacktraceVisibility, CanisterIdRecord, CanisterInstallMode, CanisterLogRecord,
+    CanisterSettingsArgs, CanisterSettingsArgsBuilder, DataSize, EmptyBlob,
+    FetchCanisterLogsRequest, FetchCanisterLogsResponse, LogVisibility, Payload,
 };
 use ic_registry_subnet_type::SubnetType;
 use ic_state_machine_tests::{
@@ -486,6 +486,134 @@ fn test_logging_explicit_canister_trap_with_message() {
     );
 }
 
+const BACKTRACE: &str = "Canister Backtrace:\n0: inner\n1: outer";
+
+// Creates a canister controlled by `controller` whose `test` method traps, and
+// returns the errors of calling it as the controller and as another user
+// together with the resulting canister log records.
+fn trap_as_controller_and_other_user(
+    log_visibility: LogVisibility,
+    backtrace_visibility: Option<BacktraceVisibility>,
+) -> (UserError, UserError, Vec<String>) {
+    let controller = PrincipalId::new_user_test_id(42);
+    let not_a_controller = PrincipalId::new_user_test_id(1);
+    let mut config = default_config_with_canister_logging(FlagStatus::Enabled);
+    config.embedders_config.feature_flags.canister_backtrace = FlagStatus::Enabled;
+    let env = StateMachineBuilder::new()
+        .with_config(Some(StateMachineConfig::new(
+            SubnetConfig::new(SubnetType::Application),
+            config,
+        )))
+        .with_checkpoints_enabled(false)
+        .build();
+    let mut settings = CanisterSettingsArgsBuilder::new()
+        .with_log_visibility(log_visibility)
+        .with_controller(controller);
+    if let Some(backtrace_visibility) = backtrace_visibility {
+        settings = settings.with_backtrace_visibility(backtrace_visibility);
+    }
+    let canister_id = env.create_canister_with_cycles(
+        None,
+        Cycles::from(100_000_000_000_u128),
+        Some(settings.build()),
+    );
+    let wasm = wat::parse_str(
+        r#"
+        (module
+            (func $inner unreachable)
+            (func $outer (export "canister_update test") (call $inner))
+            (memory 1)
+        )"#,
+    )
+    .unwrap();
+    env.install_wasm_in_mode(canister_id, CanisterInstallMode::Install, wasm, vec![])
+        .unwrap();
+
+    let controller_err = env
+        .execute_ingress_as(controller, canister_id, "test", vec![])
+        .unwrap_err();
+    let other_err = env
+        .execute_ingress_as(not_a_controller, canister_id, "test", vec![])
+        .unwrap_err();
+
+    let result = fetch_canister_logs(&env, controller, canister_id);
+    let response = FetchCanisterLogsResponse::decode(&get_reply(result)).unwrap();
+    let records = response
+        .canister_log_records
+        .into_iter()
+        .map(|record| String::from_utf8(record.content).unwrap())
+        .collect();
+    (controller_err, other_err, records)
+}
+
+#[test]
+fn test_trap_backtrace_is_disabled_by_default() {
+    let (controller_err, other_err, records) =
+        trap_as_controller_and_other_user(LogVisibility::Controllers, None);
+    for err in [controller_err, other_err] {
+        assert!(!err.description().contains("Canister Backtrace"), "{}", err);
+    }
+    assert_eq!(records.len(), 2);
+    for content in records {
+        assert!(content.starts_with("[TRAP]: "), "{}", content);
+        assert!(!content.contains("Canister Backtrace"), "{}", content);
+    }
+}
+
+#[test]
+fn test_trap_backtrace_is_only_returned_to_controllers() {
+    let (controller_err, other_err, records) = trap_as_controller_and_other_user(
+        LogVisibility::Controllers,
+        Some(BacktraceVisibility::Controllers),
+    );
+    assert!(
+        controller_err.description().ends_with(BACKTRACE),
+        "{}",
+        controller_err
+    );
+    assert!(
+        !other_err.description().contains("Canister Backtrace"),
+        "{}",
+        other_err
+    );
+
+    // The canister log contains the backtrace of both traps.
+    assert_eq!(records.len(), 2);
+    for content in records {
+        assert!(content.starts_with("[TRAP]: "), "{}", content);
+        assert!(content.ends_with(BACKTRACE), "{}", content);
+    }
+}
+
+#[test]
+fn test_trap_backtrace_is_not_logged_if_log_is_more_visible() {
+    let (controller_err, _, records) = trap_as_controller_and_other_user(
+        LogVisibility::Public,
+        Some(BacktraceVisibility::Controllers),
+    );
+    assert!(
+        controller_err.description().ends_with(BACKTRACE),
+        "{}",
+        controller_err
+    );
+    assert_eq!(records.len(), 2);
+    for content in records {
+        assert!(!content.contains("Canister Backtrace"), "{}", content);
+    }
+}
+
+#[test]
+fn test_public_trap_backtrace_is_returned_to_everyone() {
+    let (controller_err, other_err, records) =
+        trap_as_controller_and_other_user(LogVisibility::Public, Some(BacktraceVisibility::Public));
+    for err in [controller_err, other_err] {
+        assert!(err.description().ends_with(BACKTRACE), "{}", err);
+    }
+    for content in records {
+        assert!(content.ends_with(BACKTRACE), "{}", content);
+    }
+}
+
 #[test]
 fn test_canister_log_stays_within_limit() {
     // Test that the total size of canister log records stays within the limit
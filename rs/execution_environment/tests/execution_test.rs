@@ -1161,6 +1161,71 @@ fn canister_with_memory_allocation_cannot_grow_wasm_memory_above_allocation_wasm
     assert_eq!(err.code(), ErrorCode::CanisterOutOfMemory);
 }
 
+fn wasm64_state_machine_config() -> StateMachineConfig {
+    let mut embedders_config = ic_config::embedders::Config::default();
+    embedders_config.feature_flags.wasm64 = ic_config::flag_status::FlagStatus::Enabled;
+    StateMachineConfig::new(
+        SubnetConfig::new(SubnetType::Application),
+        HypervisorConfig {
+            embedders_config,
+            ..Default::default()
+        },
+    )
+}
+
+#[test]
+fn wasm64_heap_above_4_gib_survives_checkpoint_and_restart() {
+    let env = StateMachine::new_with_config(wasm64_state_machine_config());
+    env.set_checkpoints_enabled(true);
+
+    let wat = r#"
+        (module
+            (import "ic0" "msg_arg_data_size" (func $msg_arg_data_size (result i64)))
+            (import "ic0" "msg_arg_data_copy"
+                (func $msg_arg_data_copy (param i64 i64 i64)))
+            (import "ic0" "msg_reply_data_append"
+                (func $msg_reply_data_append (param i64 i64)))
+            (import "ic0" "msg_reply" (func $msg_reply))
+            (func (export "canister_init")
+                ;; Grow the heap to 4 GiB + 64 KiB.
+                (if (i64.ne (memory.grow (i64.const 65536)) (i64.const 1))
+                    (then (unreachable))
+                )
+            )
+            (func (export "canister_update write")
+                (i64.store (i64.const 0x1_0000_0000) (call $msg_arg_data_size))
+                (call $msg_arg_data_copy
+                    (i64.const 0x1_0000_0008) (i64.const 0) (call $msg_arg_data_size))
+                (call $msg_reply)
+            )
+            (func (export "canister_query read")
+                (call $msg_reply_data_append
+                    (i64.const 0x1_0000_0008) (i64.load (i64.const 0x1_0000_0000)))
+                (call $msg_reply)
+            )
+            (memory i64 1)
+        )"#;
+    let canister_id = create_canister_with_cycles(
+        &env,
+        wat::parse_str(wat).unwrap(),
+        None,
+        Cycles::new(1u128 << 100),
+    );
+
+    env.execute_ingress(canister_id, "write", b"wasm64".to_vec())
+        .unwrap();
+    assert_eq!(
+        env.query(canister_id, "read", vec![]).unwrap(),
+        WasmResult::Reply(b"wasm64".to_vec())
+    );
+
+    let env = env.restart_node_with_config(wasm64_state_machine_config());
+    assert_eq!(
+        env.query(canister_id, "read", vec![]).unwrap(),
+        WasmResult::Reply(b"wasm64".to_vec())
+    );
+}
+
 #[test]
 fn canister_with_memory_allocation_cannot_grow_stable_memory_above_allocation() {
     let subnet_config = SubnetConfig::new(SubnetType::Application);
@@ -338,6 +338,8 @@ mod test {
                 nonce: None,
                 sender: Blob(vec![0x04]),
                 ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                tip: None,
+                trace: None,
             },
         };
         let request1 = HttpRequestEnvelope::<HttpCallContent> {
@@ -355,6 +357,8 @@ mod test {
                 nonce: None,
                 sender: Blob(vec![0x04]),
                 ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                tip: None,
+                trace: None,
             },
         };
         let request2 = HttpRequestEnvelope::<HttpCallContent> {
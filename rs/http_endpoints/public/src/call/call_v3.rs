@@ -40,7 +40,7 @@ use std::{
     sync::{Arc, RwLock},
     time::Duration,
 };
-use tokio_util::time::FutureExt;
+use tokio::time::{timeout_at, Instant};
 use tower::{util::BoxCloneService, ServiceBuilder};
 
 enum CallV3Response {
@@ -191,12 +191,16 @@ async fn call_sync_v3(
 
     let message_id = ingress_submitter.message_id();
 
-    let timeout = Duration::from_secs(ingress_message_certificate_timeout_seconds);
+    // Subscribing and waiting for certification share the same deadline, so that
+    // the request is answered within the replica defined timeout.
+    let deadline =
+        Instant::now() + Duration::from_secs(ingress_message_certificate_timeout_seconds);
 
-    let certification_subscriber = match ingress_watcher_handle
-        .subscribe_for_certification(message_id.clone())
-        .timeout(timeout)
-        .await
+    let certification_subscriber = match timeout_at(
+        deadline,
+        ingress_watcher_handle.subscribe_for_certification(message_id.clone()),
+    )
+    .await
     {
         Ok(Ok(message_subscriber)) => Ok(message_subscriber),
         Ok(Err(SubscriptionError::DuplicateSubscriptionError)) => {
@@ -248,11 +252,7 @@ async fn call_sync_v3(
         }
     };
 
-    match certification_subscriber
-        .wait_for_certification()
-        .timeout(timeout)
-        .await
-    {
+    match timeout_at(deadline, certification_subscriber.wait_for_certification()).await {
         Ok(()) => (),
         Err(_) => {
             metrics
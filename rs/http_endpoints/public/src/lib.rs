@@ -46,7 +46,7 @@ use crate::{
         STATUS_SUCCESS,
     },
     pprof::{PprofFlamegraphService, PprofHomeService, PprofProfileService},
-    read_state::subnet::SubnetReadStateService,
+    read_state::{canister::CanisterAwaitRequestStatusService, subnet::SubnetReadStateService},
     status::StatusService,
     tracing_flamegraph::TracingFlamegraphService,
 };
@@ -153,6 +153,7 @@ struct HttpHandler {
     dashboard_router: Router,
     status_router: Router,
     canister_read_state_router: Router,
+    canister_await_request_status_router: Router,
     subnet_read_state_router: Router,
     pprof_home_router: Router,
     pprof_profile_router: Router,
@@ -343,7 +344,7 @@ pub fn start_server(
         rt_handle.clone(),
         log.clone(),
         metrics.clone(),
-        certified_height_watcher,
+        certified_height_watcher.clone(),
         completed_execution_messages_rx,
         CancellationToken::new(),
     );
@@ -370,7 +371,7 @@ pub fn start_server(
     .with_malicious_flags(malicious_flags.clone())
     .build_router();
 
-    let canister_read_state_router = CanisterReadStateServiceBuilder::builder(
+    let canister_read_state_builder = CanisterReadStateServiceBuilder::builder(
         state_reader.clone(),
         registry_client.clone(),
         ingress_verifier,
@@ -378,8 +379,13 @@ pub fn start_server(
     )
     .with_logger(log.clone())
     .with_health_status(health_status.clone())
-    .with_malicious_flags(malicious_flags)
-    .build_router();
+    .with_malicious_flags(malicious_flags);
+    let canister_read_state_router = canister_read_state_builder.clone().build_router();
+    let canister_await_request_status_router = canister_read_state_builder
+        .build_await_request_status_router(
+            certified_height_watcher,
+            Duration::from_secs(config.request_status_await_timeout_seconds),
+        );
 
     let subnet_read_state_router = SubnetReadStateService::new_router(
         Arc::clone(&health_status),
@@ -433,6 +439,7 @@ pub fn start_server(
         catchup_router,
         dashboard_router,
         canister_read_state_router,
+        canister_await_request_status_router,
         subnet_read_state_router,
         pprof_home_router,
         pprof_profile_router,
@@ -635,6 +642,16 @@ fn make_router(
                     )),
             ),
         )
+        .merge(
+            http_handler.canister_await_request_status_router.layer(
+                ServiceBuilder::new()
+                    .layer(HandleErrorLayer::new(map_box_error_to_response))
+                    .load_shed()
+                    .layer(GlobalConcurrencyLimitLayer::new(
+                        config.max_request_status_await_concurrent_requests,
+                    )),
+            ),
+        )
         .merge(
             http_handler.catchup_router.layer(
                 ServiceBuilder::new()
@@ -1129,6 +1146,10 @@ mod tests {
                 CanisterReadStateService::route(),
                 axum::routing::post(dummy),
             ),
+            canister_await_request_status_router: Router::new().route(
+                CanisterAwaitRequestStatusService::route(),
+                axum::routing::post(dummy),
+            ),
             subnet_read_state_router: Router::new()
                 .route(SubnetReadStateService::route(), axum::routing::post(dummy)),
             pprof_home_router: Router::new()
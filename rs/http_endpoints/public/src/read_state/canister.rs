@@ -16,22 +16,25 @@ use hyper::StatusCode;
 use ic_crypto_interfaces_sig_verification::IngressSigVerifier;
 use ic_crypto_tree_hash::{sparse_labeled_tree_from_paths, Label, Path, TooLongPathError};
 use ic_interfaces_registry::RegistryClient;
-use ic_interfaces_state_manager::StateReader;
+use ic_interfaces_state_manager::{CertifiedStateSnapshot, StateReader};
 use ic_logger::{replica_logger::no_op_logger, ReplicaLogger};
 use ic_registry_client_helpers::crypto::root_of_trust::RegistryRootOfTrustProvider;
 use ic_replicated_state::{canister_state::execution_state::CustomSectionType, ReplicatedState};
 use ic_types::{
+    ingress::IngressStatus,
     malicious_flags::MaliciousFlags,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpReadStateContent, HttpReadStateResponse,
         HttpRequest, HttpRequestEnvelope, MessageId, ReadState, EXPECTED_MESSAGE_ID_LENGTH,
     },
     time::current_time,
-    CanisterId, PrincipalId, UserId,
+    CanisterId, Height, PrincipalId, UserId,
 };
 use ic_validator::{CanisterIdSet, HttpRequestVerifier};
 use std::convert::{Infallible, TryFrom};
 use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio::{sync::watch, time::Instant};
 use tower::{util::BoxCloneService, ServiceBuilder};
 
 #[derive(Clone)]
@@ -44,6 +47,14 @@ pub struct CanisterReadStateService {
     registry_client: Arc<dyn RegistryClient>,
 }
 
+#[derive(Clone)]
+pub(crate) struct CanisterAwaitRequestStatusService {
+    read_state: CanisterReadStateService,
+    certified_height_watcher: watch::Receiver<Height>,
+    timeout: Duration,
+}
+
+#[derive(Clone)]
 pub struct CanisterReadStateServiceBuilder {
     log: Option<ReplicaLogger>,
     health_status: Option<Arc<AtomicCell<ReplicaHealthStatus>>>,
@@ -60,6 +71,12 @@ impl CanisterReadStateService {
     }
 }
 
+impl CanisterAwaitRequestStatusService {
+    pub(crate) fn route() -> &'static str {
+        "/api/v2/canister/:effective_canister_id/read_state/await"
+    }
+}
+
 impl CanisterReadStateServiceBuilder {
     pub fn builder(
         state_reader: Arc<dyn StateReader<State = ReplicatedState>>,
@@ -96,8 +113,8 @@ impl CanisterReadStateServiceBuilder {
         self
     }
 
-    pub(crate) fn build_router(self) -> Router {
-        let state = CanisterReadStateService {
+    fn build_state(self) -> CanisterReadStateService {
+        CanisterReadStateService {
             log: self.log.unwrap_or_else(no_op_logger),
             health_status: self
                 .health_status
@@ -106,7 +123,11 @@ impl CanisterReadStateServiceBuilder {
             state_reader: self.state_reader,
             validator: build_validator(self.ingress_verifier, self.malicious_flags),
             registry_client: self.registry_client,
-        };
+        }
+    }
+
+    pub(crate) fn build_router(self) -> Router {
+        let state = self.build_state();
         Router::new().route(
             CanisterReadStateService::route(),
             axum::routing::post(canister_read_state)
@@ -115,6 +136,27 @@ impl CanisterReadStateServiceBuilder {
         )
     }
 
+    /// Builds the router for the long-poll variant of `read_state`, which waits for
+    /// the certified height to change until the requested status is terminal or
+    /// `timeout` passes.
+    pub(crate) fn build_await_request_status_router(
+        self,
+        certified_height_watcher: watch::Receiver<Height>,
+        timeout: Duration,
+    ) -> Router {
+        let state = CanisterAwaitRequestStatusService {
+            read_state: self.build_state(),
+            certified_height_watcher,
+            timeout,
+        };
+        Router::new().route(
+            CanisterAwaitRequestStatusService::route(),
+            axum::routing::post(canister_await_request_status)
+                .with_state(state)
+                .layer(ServiceBuilder::new().layer(DefaultBodyLimit::disable())),
+        )
+    }
+
     pub fn build_service(self) -> BoxCloneService<Request<Body>, Response, Infallible> {
         let router = self.build_router();
         BoxCloneService::new(router.into_service())
@@ -123,90 +165,186 @@ impl CanisterReadStateServiceBuilder {
 
 pub(crate) async fn canister_read_state(
     axum::extract::Path(effective_canister_id): axum::extract::Path<CanisterId>,
-    State(CanisterReadStateService {
-        log,
-        health_status,
-        delegation_from_nns,
-        state_reader,
-        validator,
-        registry_client,
-    }): State<CanisterReadStateService>,
+    State(service): State<CanisterReadStateService>,
     WithTimeout(Cbor(request)): WithTimeout<Cbor<HttpRequestEnvelope<HttpReadStateContent>>>,
 ) -> impl IntoResponse {
-    if health_status.load() != ReplicaHealthStatus::Healthy {
+    let (read_state, targets) = match validate_read_state_request(&service, request).await {
+        Ok(validated) => validated,
+        Err(response) => return response,
+    };
+
+    let certified_state_reader = match get_certified_state_snapshot(&service.state_reader).await {
+        Ok(reader) => reader,
+        Err(response) => return response,
+    };
+
+    // Verify authorization for requested paths.
+    if let Err(HttpError { status, message }) = verify_paths(
+        certified_state_reader.get_state(),
+        &read_state.source,
+        &read_state.paths,
+        &targets,
+        effective_canister_id.into(),
+    ) {
+        return (status, message).into_response();
+    }
+
+    certified_read_state_response(
+        certified_state_reader.as_ref(),
+        read_state.paths,
+        &service.delegation_from_nns,
+    )
+}
+
+/// Same as [`canister_read_state`], but if the paths contain a `request_status`
+/// path, the response is held back until the status of the request is terminal
+/// in the certified state or until the timeout passes, whichever happens first.
+/// The response is always the certificate `read_state` would return at that point.
+pub(crate) async fn canister_await_request_status(
+    axum::extract::Path(effective_canister_id): axum::extract::Path<CanisterId>,
+    State(CanisterAwaitRequestStatusService {
+        read_state: service,
+        mut certified_height_watcher,
+        timeout,
+    }): State<CanisterAwaitRequestStatusService>,
+    WithTimeout(Cbor(request)): WithTimeout<Cbor<HttpRequestEnvelope<HttpReadStateContent>>>,
+) -> impl IntoResponse {
+    let (read_state, targets) = match validate_read_state_request(&service, request).await {
+        Ok(validated) => validated,
+        Err(response) => return response,
+    };
+    let request_status_id = request_status_id(&read_state.paths);
+    let deadline = Instant::now() + timeout;
+
+    loop {
+        // Mark the current certified height as seen before reading the certified state,
+        // so that no certification is missed while the state is inspected.
+        certified_height_watcher.borrow_and_update();
+
+        let certified_state_reader = match get_certified_state_snapshot(&service.state_reader).await
+        {
+            Ok(reader) => reader,
+            Err(response) => return response,
+        };
+
+        // Verify authorization for requested paths.
+        if let Err(HttpError { status, message }) = verify_paths(
+            certified_state_reader.get_state(),
+            &read_state.source,
+            &read_state.paths,
+            &targets,
+            effective_canister_id.into(),
+        ) {
+            return (status, message).into_response();
+        }
+
+        let is_terminal = request_status_id.as_ref().map_or(true, |message_id| {
+            match certified_state_reader
+                .get_state()
+                .get_ingress_status(message_id)
+            {
+                IngressStatus::Known { state, .. } => state.is_terminal(),
+                IngressStatus::Unknown => false,
+            }
+        });
+
+        if is_terminal || Instant::now() >= deadline {
+            return certified_read_state_response(
+                certified_state_reader.as_ref(),
+                read_state.paths,
+                &service.delegation_from_nns,
+            );
+        }
+
+        // Wait for the next certified height. If the deadline passes or the certified
+        // height is not tracked anymore, the latest certified state is returned.
+        if let Ok(Err(_)) =
+            tokio::time::timeout_at(deadline, certified_height_watcher.changed()).await
+        {
+            return certified_read_state_response(
+                certified_state_reader.as_ref(),
+                read_state.paths,
+                &service.delegation_from_nns,
+            );
+        }
+    }
+}
+
+/// Checks the health of the replica and validates the request, returning the
+/// content of the request and the canisters the sender is allowed to target.
+async fn validate_read_state_request(
+    service: &CanisterReadStateService,
+    request: HttpRequestEnvelope<HttpReadStateContent>,
+) -> Result<(ReadState, CanisterIdSet), Response> {
+    let health_status = service.health_status.load();
+    if health_status != ReplicaHealthStatus::Healthy {
         let status = StatusCode::SERVICE_UNAVAILABLE;
         let text = format!(
             "Replica is unhealthy: {:?}. Check the /api/v2/status for more information.",
-            health_status.load(),
+            health_status,
         );
-        return (status, text).into_response();
+        return Err((status, text).into_response());
     }
 
-    let delegation_from_nns = delegation_from_nns.read().unwrap().clone();
-
     // Convert the message to a strongly-typed struct.
     let request = match HttpRequest::<ReadState>::try_from(request) {
         Ok(request) => request,
         Err(e) => {
             let status = StatusCode::BAD_REQUEST;
             let text = format!("Malformed request: {:?}", e);
-            return (status, text).into_response();
+            return Err((status, text).into_response());
         }
     };
     let read_state = request.content().clone();
-    let registry_version = registry_client.get_latest_version();
+    let registry_version = service.registry_client.get_latest_version();
 
     let root_of_trust_provider =
-        RegistryRootOfTrustProvider::new(Arc::clone(&registry_client), registry_version);
+        RegistryRootOfTrustProvider::new(Arc::clone(&service.registry_client), registry_version);
     // Since spawn blocking requires 'static we can't use any references
     let request_c = request.clone();
-    let targets = match tokio::task::spawn_blocking(move || {
+    let validator = Arc::clone(&service.validator);
+    match tokio::task::spawn_blocking(move || {
         validator.validate_request(&request_c, current_time(), &root_of_trust_provider)
     })
     .await
     {
-        Ok(Ok(targets)) => targets,
+        Ok(Ok(targets)) => Ok((read_state, targets)),
         Ok(Err(err)) => {
-            let http_err = validation_error_to_http_error(request.id(), err, &log);
-            return (http_err.status, http_err.message).into_response();
-        }
-        Err(_) => {
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
-        }
-    };
-    let make_service_unavailable_response = || {
-        let status = StatusCode::SERVICE_UNAVAILABLE;
-        let text = "Certified state is not available yet. Please try again...".to_string();
-        (status, text).into_response()
-    };
-    let certified_state_reader = match tokio::task::spawn_blocking(move || {
-        state_reader.get_certified_state_snapshot()
-    })
-    .await
-    {
-        Ok(Some(reader)) => reader,
-        Ok(None) => return make_service_unavailable_response(),
-        Err(_) => {
-            return StatusCode::INTERNAL_SERVER_ERROR.into_response();
+            let http_err = validation_error_to_http_error(request.id(), err, &service.log);
+            Err((http_err.status, http_err.message).into_response())
         }
-    };
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
+    }
+}
 
-    // Verify authorization for requested paths.
-    if let Err(HttpError { status, message }) = verify_paths(
-        certified_state_reader.get_state(),
-        &read_state.source,
-        &read_state.paths,
-        &targets,
-        effective_canister_id.into(),
-    ) {
-        return (status, message).into_response();
+fn make_service_unavailable_response() -> Response {
+    let status = StatusCode::SERVICE_UNAVAILABLE;
+    let text = "Certified state is not available yet. Please try again...".to_string();
+    (status, text).into_response()
+}
+
+async fn get_certified_state_snapshot(
+    state_reader: &Arc<dyn StateReader<State = ReplicatedState>>,
+) -> Result<Box<dyn CertifiedStateSnapshot<State = ReplicatedState>>, Response> {
+    let state_reader = Arc::clone(state_reader);
+    match tokio::task::spawn_blocking(move || state_reader.get_certified_state_snapshot()).await {
+        Ok(Some(reader)) => Ok(reader),
+        Ok(None) => Err(make_service_unavailable_response()),
+        Err(_) => Err(StatusCode::INTERNAL_SERVER_ERROR.into_response()),
     }
+}
 
+/// Builds the certificate for `paths` from the certified state. The paths
+/// must have been verified with [`verify_paths`] beforehand.
+fn certified_read_state_response(
+    certified_state_reader: &dyn CertifiedStateSnapshot<State = ReplicatedState>,
+    mut paths: Vec<Path>,
+    delegation_from_nns: &RwLock<Option<CertificateDelegation>>,
+) -> Response {
     // Create labeled tree. This may be an expensive operation and by
     // creating the labeled tree after verifying the paths we know that
     // the depth is max 4.
     // Always add "time" to the paths even if not explicitly requested.
-    let mut paths: Vec<Path> = read_state.paths;
     paths.push(Path::from(Label::from("time")));
     let labeled_tree = match sparse_labeled_tree_from_paths(&paths) {
         Ok(tree) => tree,
@@ -222,6 +360,7 @@ pub(crate) async fn canister_read_state(
         None => return make_service_unavailable_response(),
     };
 
+    let delegation_from_nns = delegation_from_nns.read().unwrap().clone();
     let signature = certification.signed.signature.signature.get().0;
     let res = HttpReadStateResponse {
         certificate: Blob(into_cbor(&Certificate {
@@ -233,6 +372,16 @@ pub(crate) async fn canister_read_state(
     Cbor(res).into_response()
 }
 
+/// Returns the ID of the request whose status is requested in `paths`, if any.
+fn request_status_id(paths: &[Path]) -> Option<MessageId> {
+    paths.iter().find_map(|path| match path.as_slice() {
+        [request_status, request_id, ..] if request_status.as_bytes() == b"request_status" => {
+            MessageId::try_from(request_id.as_bytes()).ok()
+        }
+        _ => None,
+    })
+}
+
 // Verifies that the `user` is authorized to retrieve the `paths` requested.
 fn verify_paths(
     state: &ReplicatedState,
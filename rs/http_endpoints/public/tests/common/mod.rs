@@ -633,6 +633,8 @@ pub mod test_agent {
                     arg: Blob(ARG),
                     sender: Blob(SENDER.into_vec()),
                     nonce: None,
+                    tip: None,
+                    trace: None,
                 },
             };
 
@@ -754,6 +756,14 @@ pub mod test_agent {
         }
 
         pub async fn read_state(self, addr: SocketAddr) -> reqwest::Response {
+            self.send(addr, "read_state").await
+        }
+
+        pub async fn await_request_status(self, addr: SocketAddr) -> reqwest::Response {
+            self.send(addr, "read_state/await").await
+        }
+
+        async fn send(self, addr: SocketAddr, endpoint: &str) -> reqwest::Response {
             let ingress_expiry =
                 (current_time() + INGRESS_EXPIRY_DURATION).as_nanos_since_unix_epoch();
 
@@ -775,8 +785,8 @@ pub mod test_agent {
 
             let body = serde_cbor::to_vec(&envelope).unwrap();
             let url = format!(
-                "http://{}/api/v2/canister/{}/read_state",
-                addr, self.effective_canister_id
+                "http://{}/api/v2/canister/{}/{}",
+                addr, self.effective_canister_id, endpoint
             );
 
             reqwest::Client::new()
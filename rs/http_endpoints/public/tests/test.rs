@@ -51,11 +51,11 @@ use ic_types::{
         },
         CombinedThresholdSig, CombinedThresholdSigOf, CryptoHash, Signed,
     },
-    ingress::WasmResult,
-    messages::{Blob, Certificate, CertificateDelegation},
+    ingress::{IngressState, IngressStatus, WasmResult},
+    messages::{Blob, Certificate, CertificateDelegation, MessageId},
     signature::ThresholdSignature,
-    time::current_time,
-    CryptoHashOfPartialState, Height, PrincipalId, RegistryVersion,
+    time::{current_time, UNIX_EPOCH},
+    CryptoHashOfPartialState, Height, NumBytes, PrincipalId, RegistryVersion, UserId,
 };
 use prost::Message;
 use reqwest::header::CONTENT_TYPE;
@@ -660,6 +660,209 @@ fn test_too_long_paths_are_rejected() {
     });
 }
 
+/// Requests to the long-poll `read_state` endpoint that do not ask for a request status
+/// are answered right away.
+#[test]
+fn test_await_request_status_responds_immediately_without_request_status() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        request_status_await_timeout_seconds: 300,
+        ..Default::default()
+    };
+
+    HttpEndpointBuilder::new(rt.handle().clone(), config).run();
+
+    rt.block_on(async move {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        let response = tokio::time::timeout(
+            Duration::from_secs(10),
+            test_agent::CanisterReadState::default().await_request_status(addr),
+        )
+        .await
+        .expect("The endpoint should not wait for certification.");
+
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            APPLICATION_CBOR
+        );
+    });
+}
+
+/// If the requested status does not become terminal, the long-poll `read_state` endpoint
+/// responds with the latest certified state once the timeout passes.
+#[test]
+fn test_await_request_status_responds_with_certificate_after_timeout() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        request_status_await_timeout_seconds: 1,
+        ..Default::default()
+    };
+
+    let handlers = HttpEndpointBuilder::new(rt.handle().clone(), config)
+        .with_certified_height(Height::from(1))
+        .run();
+
+    let path = Path::new(vec![
+        CryptoTreeHashLabel::from("request_status"),
+        [0; 32].into(),
+        CryptoTreeHashLabel::from("status"),
+    ]);
+
+    rt.block_on(async move {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        let start = std::time::Instant::now();
+        // New certified heights that do not change the status do not end the request.
+        let (response, _) = tokio::join!(
+            test_agent::CanisterReadState::new(vec![path], PrincipalId::default())
+                .await_request_status(addr),
+            async {
+                sleep(Duration::from_millis(200)).await;
+                handlers
+                    .certified_height_watcher
+                    .send(Height::from(2))
+                    .unwrap();
+            }
+        );
+
+        assert!(start.elapsed() >= Duration::from_secs(1));
+        assert_eq!(StatusCode::OK, response.status());
+
+        let response_body = response.bytes().await.unwrap();
+        let response =
+            serde_cbor::from_slice::<CBOR>(&response_body).expect("Response is a valid CBOR.");
+        let CBOR::Map(response_map) = response else {
+            panic!("Expected a map, got {:?}", response);
+        };
+        let Some(CBOR::Bytes(certificate)) =
+            response_map.get(&CBOR::Text("certificate".to_string()))
+        else {
+            panic!("Certificate is missing.");
+        };
+        let _: Certificate = serde_cbor::from_slice(certificate).expect("Valid certificate");
+    });
+}
+
+/// The long-poll `read_state` endpoint responds as soon as the requested status becomes
+/// terminal in the certified state, well before the timeout passes.
+#[test]
+fn test_await_request_status_responds_once_status_is_terminal() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        request_status_await_timeout_seconds: 300,
+        ..Default::default()
+    };
+
+    let message_id = MessageId::from([0; 32]);
+    let completed = Arc::new(AtomicBool::new(false));
+    let completed_c = completed.clone();
+    let mut mock_state_manager = MockStateManager::new();
+    mock_state_manager
+        .expect_get_latest_state()
+        .returning(default_get_latest_state);
+    mock_state_manager
+        .expect_read_certified_state()
+        .returning(default_read_certified_state);
+    mock_state_manager
+        .expect_latest_certified_height()
+        .returning(default_latest_certified_height);
+    mock_state_manager
+        .expect_get_certified_state_snapshot()
+        .returning(move || {
+            struct FakeCertifiedStateSnapshot(ReplicatedState, MixedHashTree, Certification);
+
+            impl CertifiedStateSnapshot for FakeCertifiedStateSnapshot {
+                type State = ReplicatedState;
+
+                fn get_state(&self) -> &ReplicatedState {
+                    &self.0
+                }
+
+                fn get_height(&self) -> Height {
+                    self.2.height
+                }
+
+                fn read_certified_state(
+                    &self,
+                    _paths: &LabeledTree<()>,
+                ) -> Option<(MixedHashTree, Certification)> {
+                    Some((self.1.clone(), self.2.clone()))
+                }
+            }
+
+            let (_, hash_tree, certification) =
+                default_read_certified_state(&LabeledTree::Leaf(()))?;
+            let mut state = ReplicatedStateBuilder::new().build();
+            if completed_c.load(Ordering::SeqCst) {
+                state.set_ingress_status(
+                    message_id.clone(),
+                    IngressStatus::Known {
+                        receiver: canister_test_id(0).get(),
+                        user_id: UserId::from(PrincipalId::new_anonymous()),
+                        time: UNIX_EPOCH,
+                        state: IngressState::Completed(WasmResult::Reply(vec![])),
+                    },
+                    NumBytes::from(u64::MAX),
+                );
+            }
+
+            Some(Box::new(FakeCertifiedStateSnapshot(
+                state,
+                hash_tree,
+                certification,
+            )))
+        });
+
+    let handlers = HttpEndpointBuilder::new(rt.handle().clone(), config)
+        .with_state_manager(mock_state_manager)
+        .with_certified_height(Height::from(1))
+        .run();
+
+    let path = Path::new(vec![
+        CryptoTreeHashLabel::from("request_status"),
+        message_id.as_bytes().to_vec().into(),
+        CryptoTreeHashLabel::from("status"),
+    ]);
+
+    rt.block_on(async move {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        let start = std::time::Instant::now();
+        let (response, _) = tokio::time::timeout(Duration::from_secs(10), async {
+            tokio::join!(
+                test_agent::CanisterReadState::new(vec![path], PrincipalId::default())
+                    .await_request_status(addr),
+                async {
+                    sleep(Duration::from_millis(500)).await;
+                    completed.store(true, Ordering::SeqCst);
+                    handlers
+                        .certified_height_watcher
+                        .send(Height::from(2))
+                        .unwrap();
+                }
+            )
+        })
+        .await
+        .expect("The endpoint should respond once the status is terminal.");
+
+        // The request is held until the status becomes terminal.
+        assert!(start.elapsed() >= Duration::from_millis(500));
+        assert_eq!(StatusCode::OK, response.status());
+        assert_eq!(
+            response.headers().get(CONTENT_TYPE).unwrap(),
+            APPLICATION_CBOR
+        );
+    });
+}
+
 /// This test verifies that the http endpoint returns 503 (SERVICE_UNAVAILABLE) when the
 /// per canister certified state is unavailable. I.e. when the
 /// [`QueryExecutionService`](ic_interfaces::execution_environment::QueryExecutionService)
@@ -1282,6 +1485,61 @@ fn test_synchronous_call_endpoint_no_certification() {
     });
 }
 
+/// Tests that the /v3/.../call endpoint falls back to `202 ACCEPTED` once the
+/// replica defined timeout passes for ingress messages that never complete
+/// execution, and that the timeout bounds the whole request.
+#[test]
+fn test_synchronous_call_endpoint_falls_back_to_accepted_at_deadline() {
+    let rt = Runtime::new().unwrap();
+    let addr = get_free_localhost_socket_addr();
+    let config = Config {
+        listen_addr: addr,
+        ingress_message_certificate_timeout_seconds: 2,
+        ..Default::default()
+    };
+
+    let mut handlers = HttpEndpointBuilder::new(rt.handle().clone(), config)
+        .with_certified_height(Height::from(0))
+        .run();
+
+    let message = IngressMessage::default();
+
+    // Mock ingress filter to always accept the message.
+    rt.spawn(async move {
+        loop {
+            let (_, resp) = handlers.ingress_filter.next_request().await.unwrap();
+            resp.send_response(Ok(()))
+        }
+    });
+
+    // Receive the message but never complete its execution.
+    rt.spawn(async move { while handlers.ingress_rx.recv().await.is_some() {} });
+
+    rt.block_on(async {
+        wait_for_status_healthy(&addr).await.unwrap();
+
+        // The outer timeout is deliberately generous compared to the 2 second
+        // deadline so that the test does not depend on the load of the machine.
+        let start = std::time::Instant::now();
+        let response = tokio::time::timeout(
+            Duration::from_secs(30),
+            test_agent::Call::V3.call(addr, message),
+        )
+        .await
+        .expect("The endpoint should respond once the deadline passes.");
+        let elapsed = start.elapsed();
+
+        let status = response.status();
+        let text = response.text().await.unwrap();
+        assert_eq!(StatusCode::ACCEPTED, status, "{:?}", text);
+        assert_eq!(
+            "Message did not complete execution and certification within the replica defined timeout.",
+            text
+        );
+        assert!(elapsed >= Duration::from_secs(2), "{:?}", elapsed);
+    });
+}
+
 struct FakeCertifiedStateSnapshot;
 
 impl CertifiedStateSnapshot for FakeCertifiedStateSnapshot {
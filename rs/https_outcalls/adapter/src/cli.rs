@@ -299,7 +299,8 @@ pub mod test {
                 "format": "json",
                 "debug_overrides": []
             },
-            "socks_proxy": "socks5://notaproxy.com:1080"        
+            "socks_proxy": "socks5://notaproxy.com:1080",
+            "max_paged_response_size_bytes": 1048576
         }       
         "#;
 
@@ -325,6 +326,7 @@ pub mod test {
                 ..Default::default()
             },
             socks_proxy: "socks5://notaproxy.com:1080".to_string(),
+            max_paged_response_size_bytes: 1048576,
         };
         assert_eq!(config, expected_config);
     }
@@ -4,6 +4,7 @@ use std::path::PathBuf;
 
 const DEFAULT_HTTP_CONNECT_TIMEOUT_SECS: u64 = 2;
 const DEFAULT_HTTP_REQUEST_TIMEOUT_SECS: u64 = 30;
+const DEFAULT_MAX_PAGED_RESPONSE_SIZE_BYTES: u64 = 32 * 1024 * 1024;
 
 #[derive(Default, Clone, Debug, Deserialize, Eq, Serialize, PartialEq)]
 /// The source of the unix domain socket to be used for inter-process
@@ -30,6 +31,11 @@ pub struct Config {
     /// is not present at adapter startup. So to enable/disable the proxy there exists a `socks_proxy_allowed` field in
     /// the adapter request.
     pub socks_proxy: String,
+    /// Maximum number of body bytes that are downloaded for a paged request, i.e. a
+    /// request with a `response_offset`, if the server ignores the `Range` header and
+    /// returns the body from the start. Only the requested page of the body is returned
+    /// to the replica, so this limit may exceed the maximum response size.
+    pub max_paged_response_size_bytes: u64,
 }
 
 impl Default for Config {
@@ -40,6 +46,7 @@ impl Default for Config {
             incoming_source: IncomingSource::default(),
             logger: LoggerConfig::default(),
             socks_proxy: "socks5://notaproxy:1080".to_string(),
+            max_paged_response_size_bytes: DEFAULT_MAX_PAGED_RESPONSE_SIZE_BYTES,
         }
     }
 }
@@ -11,6 +11,9 @@ mod config;
 /// Adapter metrics
 mod metrics;
 
+/// Per-subnet destination policy enforced when connecting
+mod policy;
+
 pub use cli::Cli;
 pub use config::{Config, IncomingSource};
 pub use rpc_server::CanisterHttp;
@@ -48,7 +51,7 @@ impl AdapterServer {
                 .parse::<Uri>()
                 .expect("Failed to parse socks url."),
             auth: None,
-            connector: http_connector.clone(),
+            connector: http_connector,
         };
         let https_connector = HttpsConnectorBuilder::new()
             .with_native_roots()
@@ -57,14 +60,15 @@ impl AdapterServer {
             .wrap_connector(proxy_connector);
         let socks_client = Client::builder().build::<_, hyper::Body>(https_connector);
 
-        // Https client setup.
-        let https_connector = HttpsConnectorBuilder::new()
-            .with_native_roots()
-            .https_only()
-            .enable_http1()
-            .wrap_connector(http_connector);
-        let https_client = Client::builder().build::<_, hyper::Body>(https_connector);
-        let canister_http = CanisterHttp::new(https_client, socks_client, logger, metrics);
+        // The client for direct connections is built by `CanisterHttp`, since its connector
+        // depends on the destination policy of the requests.
+        let canister_http = CanisterHttp::new(
+            socks_client,
+            Duration::from_secs(config.http_connect_timeout_secs),
+            config.max_paged_response_size_bytes,
+            logger,
+            metrics,
+        );
 
         Self(
             Server::builder()
@@ -8,8 +8,10 @@ pub(crate) const LABEL_HEADER_RECEIVE_SIZE: &str = "header_receive_size";
 pub(crate) const LABEL_HTTP_SCHEME: &str = "http_scheme";
 pub(crate) const LABEL_HTTP_METHOD: &str = "http_method";
 pub(crate) const LABEL_RESPONSE_HEADERS: &str = "response_headers";
+pub(crate) const LABEL_RESPONSE_OFFSET: &str = "response_offset";
 pub(crate) const LABEL_REQUEST_HEADERS: &str = "request_headers";
 pub(crate) const LABEL_CONNECT: &str = "connect";
+pub(crate) const LABEL_DESTINATION_POLICY: &str = "destination_policy";
 pub(crate) const LABEL_URL_PARSE: &str = "url_parse";
 pub(crate) const LABEL_UPLOAD: &str = "up";
 pub(crate) const LABEL_DOWNLOAD: &str = "down";
@@ -0,0 +1,414 @@
+use futures::future::BoxFuture;
+use http::Uri;
+use hyper::client::connect::dns::{GaiResolver, Name};
+use ic_https_outcalls_service::HttpOutcallsPolicy;
+use ipnet::IpNet;
+use std::{
+    convert::TryFrom,
+    net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr},
+    sync::Arc,
+    task::{Context, Poll},
+};
+use thiserror::Error;
+use tower::Service;
+
+/// The destination policy of the subnet making a request, see `registry.subnet.v1.HttpOutcallsPolicy`.
+///
+/// Domains and IP literals are checked before the request is made. The addresses that host
+/// names resolve to are checked by the [`PolicyResolver`] of the connector, so that the
+/// checked addresses are the ones that are dialled.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub(crate) struct DestinationPolicy {
+    allowed_domains: Vec<String>,
+    denied_domains: Vec<String>,
+    allowed_ip_ranges: Vec<IpNet>,
+    denied_ip_ranges: Vec<IpNet>,
+    block_private_addresses: bool,
+    max_redirects: u32,
+}
+
+impl TryFrom<HttpOutcallsPolicy> for DestinationPolicy {
+    type Error = String;
+
+    fn try_from(policy: HttpOutcallsPolicy) -> Result<Self, Self::Error> {
+        let parse_ranges = |ranges: Vec<String>| {
+            ranges
+                .iter()
+                .map(|range| {
+                    range
+                        .parse::<IpNet>()
+                        .map_err(|err| format!("Invalid IP range {}: {}", range, err))
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            allowed_domains: normalize_domains(policy.allowed_domains),
+            denied_domains: normalize_domains(policy.denied_domains),
+            allowed_ip_ranges: parse_ranges(policy.allowed_ip_ranges)?,
+            denied_ip_ranges: parse_ranges(policy.denied_ip_ranges)?,
+            block_private_addresses: policy.block_private_addresses,
+            max_redirects: policy.max_redirects,
+        })
+    }
+}
+
+impl DestinationPolicy {
+    /// Maximum number of redirects that are followed.
+    pub(crate) fn max_redirects(&self) -> u32 {
+        self.max_redirects
+    }
+
+    /// Whether the policy restricts the addresses that may be contacted.
+    fn restricts_addresses(&self) -> bool {
+        !self.allowed_ip_ranges.is_empty()
+            || !self.denied_ip_ranges.is_empty()
+            || self.block_private_addresses
+    }
+
+    /// Checks whether the domain or IP literal of `uri` may be contacted. The addresses
+    /// a domain resolves to are checked when connecting, see [`PolicyResolver`].
+    pub(crate) fn check(&self, uri: &Uri) -> Result<(), String> {
+        let host = uri
+            .host()
+            .ok_or_else(|| "Url does not specify a host".to_string())?;
+        match parse_ip_literal(host) {
+            Some(ip) => self.check_address(ip),
+            None => self.check_domain(host),
+        }
+    }
+
+    /// Whether `uri` may be contacted through a SOCKS proxy. The proxy resolves host names
+    /// itself, so the resolved addresses cannot be checked if the policy restricts them.
+    pub(crate) fn allows_proxy(&self, uri: &Uri) -> bool {
+        !self.restricts_addresses() || uri.host().and_then(parse_ip_literal).is_some()
+    }
+
+    fn check_domain(&self, host: &str) -> Result<(), String> {
+        let host = host.trim_end_matches('.').to_ascii_lowercase();
+        if self
+            .denied_domains
+            .iter()
+            .any(|domain| matches_domain(&host, domain))
+        {
+            return Err(format!("Domain {} is denied", host));
+        }
+        if !self.allowed_domains.is_empty()
+            && !self
+                .allowed_domains
+                .iter()
+                .any(|domain| matches_domain(&host, domain))
+        {
+            return Err(format!("Domain {} is not allowed", host));
+        }
+        Ok(())
+    }
+
+    fn check_address(&self, ip: IpAddr) -> Result<(), String> {
+        if self.block_private_addresses && !is_public(ip) {
+            return Err(format!("Address {} is not publicly routable", ip));
+        }
+        if self
+            .denied_ip_ranges
+            .iter()
+            .any(|range| range.contains(&ip))
+        {
+            return Err(format!("Address {} is denied", ip));
+        }
+        if !self.allowed_ip_ranges.is_empty()
+            && !self
+                .allowed_ip_ranges
+                .iter()
+                .any(|range| range.contains(&ip))
+        {
+            return Err(format!("Address {} is not allowed", ip));
+        }
+        Ok(())
+    }
+}
+
+/// Error of the [`PolicyResolver`] if a host resolves to an address the policy denies.
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub(crate) struct AddressDenied(String);
+
+/// Returns the [`AddressDenied`] error that caused `err`, if any.
+pub(crate) fn denied_address<'a>(
+    err: &'a (dyn std::error::Error + 'static),
+) -> Option<&'a AddressDenied> {
+    let mut source = Some(err);
+    while let Some(err) = source {
+        if let Some(denied) = err.downcast_ref::<AddressDenied>() {
+            return Some(denied);
+        }
+        source = err.source();
+    }
+    None
+}
+
+/// Resolver for the connector of the direct client, which fails the lookup if the host
+/// resolves to any address the policy does not allow. IP literals are not resolved by the
+/// connector and are checked by [`DestinationPolicy::check`] instead.
+#[derive(Clone)]
+pub(crate) struct PolicyResolver {
+    policy: Arc<DestinationPolicy>,
+    resolver: GaiResolver,
+}
+
+impl PolicyResolver {
+    pub(crate) fn new(policy: DestinationPolicy) -> Self {
+        Self {
+            policy: Arc::new(policy),
+            resolver: GaiResolver::new(),
+        }
+    }
+}
+
+impl Service<Name> for PolicyResolver {
+    type Response = std::vec::IntoIter<SocketAddr>;
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+    type Future = BoxFuture<'static, Result<Self::Response, Self::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.resolver.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, name: Name) -> Self::Future {
+        let policy = self.policy.clone();
+        let lookup = self.resolver.call(name.clone());
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = lookup.await?.collect();
+            if policy.restricts_addresses() {
+                if addresses.is_empty() {
+                    return Err(
+                        AddressDenied(format!("{} does not resolve to any address", name)).into(),
+                    );
+                }
+                addresses
+                    .iter()
+                    .try_for_each(|address| policy.check_address(address.ip()))
+                    .map_err(AddressDenied)?;
+            }
+            Ok(addresses.into_iter())
+        })
+    }
+}
+
+/// Parses `host` as an IP address. IPv6 literals are enclosed in brackets.
+fn parse_ip_literal(host: &str) -> Option<IpAddr> {
+    host.trim_start_matches('[')
+        .trim_end_matches(']')
+        .parse()
+        .ok()
+}
+
+fn normalize_domains(domains: Vec<String>) -> Vec<String> {
+    domains
+        .into_iter()
+        .map(|domain| {
+            domain
+                .trim_start_matches('.')
+                .trim_end_matches('.')
+                .to_ascii_lowercase()
+        })
+        .collect()
+}
+
+/// Returns true if `host` is `domain` or one of its subdomains.
+fn matches_domain(host: &str, domain: &str) -> bool {
+    host == domain
+        || host
+            .strip_suffix(domain)
+            .is_some_and(|prefix| prefix.ends_with('.'))
+}
+
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => is_public_v4(ip),
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(ip) => is_public_v4(ip),
+            None => is_public_v6(ip),
+        },
+    }
+}
+
+fn is_public_v4(ip: Ipv4Addr) -> bool {
+    let [a, b, c, _] = ip.octets();
+    // Shared address space (100.64.0.0/10), see RFC 6598.
+    let is_shared = a == 100 && (b & 0b1100_0000) == 0b0100_0000;
+    // IETF protocol assignments (192.0.0.0/24), see RFC 6890.
+    let is_protocol_assignment = a == 192 && b == 0 && c == 0;
+    // Benchmarking (198.18.0.0/15), see RFC 2544.
+    let is_benchmarking = a == 198 && (b & 0b1111_1110) == 18;
+    // Reserved (240.0.0.0/4), see RFC 1112.
+    let is_reserved = (a & 0b1111_0000) == 240;
+    !(ip.is_private()
+        || ip.is_loopback()
+        || ip.is_link_local()
+        || ip.is_unspecified()
+        || ip.is_broadcast()
+        || ip.is_documentation()
+        || ip.is_multicast()
+        || is_shared
+        || is_protocol_assignment
+        || is_benchmarking
+        || is_reserved)
+}
+
+fn is_public_v6(ip: Ipv6Addr) -> bool {
+    let segments = ip.segments();
+    let embedded_v4 =
+        |high: u16, low: u16| Ipv4Addr::from((u32::from(high) << 16) | u32::from(low));
+    // NAT64 (64:ff9b::/96, see RFC 6052) and 6to4 (2002::/16, see RFC 3056) addresses
+    // reach the embedded IPv4 address, which is checked instead.
+    if segments[..6] == [0x64, 0xff9b, 0, 0, 0, 0] {
+        return is_public_v4(embedded_v4(segments[6], segments[7]));
+    }
+    if segments[0] == 0x2002 {
+        return is_public_v4(embedded_v4(segments[1], segments[2]));
+    }
+    // Unique local (fc00::/7) and unicast link-local (fe80::/10) addresses.
+    let is_unique_local = (segments[0] & 0xfe00) == 0xfc00;
+    let is_link_local = (segments[0] & 0xffc0) == 0xfe80;
+    !(ip.is_loopback()
+        || ip.is_unspecified()
+        || ip.is_multicast()
+        || is_unique_local
+        || is_link_local)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy(policy: HttpOutcallsPolicy) -> DestinationPolicy {
+        DestinationPolicy::try_from(policy).unwrap()
+    }
+
+    #[test]
+    fn test_default_policy_allows_everything() {
+        let policy = DestinationPolicy::default();
+        for url in [
+            "https://example.com",
+            "https://127.0.0.1",
+            "https://[::1]:8080/path",
+        ] {
+            assert_eq!(policy.check(&url.parse().unwrap()), Ok(()));
+        }
+    }
+
+    #[test]
+    fn test_domains() {
+        let policy = policy(HttpOutcallsPolicy {
+            allowed_domains: vec!["Example.com".to_string(), "other.org.".to_string()],
+            denied_domains: vec!["private.example.com".to_string()],
+            ..Default::default()
+        });
+        for url in [
+            "https://example.com",
+            "https://api.example.com/path",
+            "https://EXAMPLE.COM.",
+            "https://other.org",
+        ] {
+            assert_eq!(policy.check(&url.parse().unwrap()), Ok(()), "{url}");
+        }
+        for url in [
+            "https://badexample.com",
+            "https://example.com.evil.org",
+            "https://private.example.com",
+            "https://a.private.example.com",
+        ] {
+            assert!(policy.check(&url.parse().unwrap()).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn test_invalid_ip_range() {
+        DestinationPolicy::try_from(HttpOutcallsPolicy {
+            allowed_ip_ranges: vec!["10.0.0.0/33".to_string()],
+            ..Default::default()
+        })
+        .unwrap_err();
+    }
+
+    #[test]
+    fn test_ip_ranges() {
+        let policy = policy(HttpOutcallsPolicy {
+            allowed_ip_ranges: vec!["203.0.113.0/24".to_string(), "2001:db8::/32".to_string()],
+            denied_ip_ranges: vec!["203.0.113.128/25".to_string()],
+            ..Default::default()
+        });
+        for url in ["https://203.0.113.1", "https://[2001:db8::1]"] {
+            assert_eq!(policy.check(&url.parse().unwrap()), Ok(()), "{url}");
+        }
+        for url in [
+            "https://203.0.113.129",
+            "https://198.51.100.1",
+            "https://[2001:db9::1]",
+        ] {
+            assert!(policy.check(&url.parse().unwrap()).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn test_block_private_addresses() {
+        let policy = policy(HttpOutcallsPolicy {
+            block_private_addresses: true,
+            ..Default::default()
+        });
+        for url in [
+            "https://8.8.8.8",
+            "https://[2001:4860:4860::8888]",
+            "https://[64:ff9b::808:808]",
+            "https://[2002:808:808::1]",
+        ] {
+            assert_eq!(policy.check(&url.parse().unwrap()), Ok(()), "{url}");
+        }
+        for url in [
+            "https://10.1.2.3",
+            "https://172.16.0.1",
+            "https://192.168.1.1",
+            "https://127.0.0.1",
+            "https://169.254.169.254",
+            "https://100.64.0.1",
+            "https://0.0.0.0",
+            "https://[::1]",
+            "https://[fd00::1]",
+            "https://[fe80::1]",
+            "https://[::ffff:10.0.0.1]",
+            "https://192.0.0.8",
+            "https://198.18.0.1",
+            "https://198.19.255.255",
+            "https://240.0.0.1",
+            "https://[64:ff9b::a00:1]",
+            "https://[2002:a00:1::1]",
+        ] {
+            assert!(policy.check(&url.parse().unwrap()).is_err(), "{url}");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolver_checks_resolved_addresses() {
+        let mut resolver = PolicyResolver::new(policy(HttpOutcallsPolicy {
+            block_private_addresses: true,
+            ..Default::default()
+        }));
+        let localhost = "localhost".parse::<Name>().unwrap();
+        let err = resolver.call(localhost.clone()).await.unwrap_err();
+        assert!(denied_address(err.as_ref()).is_some());
+
+        let mut resolver = PolicyResolver::new(DestinationPolicy::default());
+        assert!(resolver.call(localhost).await.unwrap().next().is_some());
+    }
+
+    #[test]
+    fn test_proxy_only_for_unrestricted_addresses() {
+        let url = "https://example.com".parse().unwrap();
+        assert!(DestinationPolicy::default().allows_proxy(&url));
+        let policy = policy(HttpOutcallsPolicy {
+            block_private_addresses: true,
+            ..Default::default()
+        });
+        assert!(!policy.allows_proxy(&url));
+        assert!(policy.allows_proxy(&"https://8.8.8.8".parse().unwrap()));
+    }
+}
@@ -1,17 +1,27 @@
 use crate::metrics::{
     AdapterMetrics, LABEL_BODY_RECEIVE_SIZE, LABEL_BODY_RECEIVE_TIMEOUT, LABEL_CONNECT,
-    LABEL_DOWNLOAD, LABEL_HEADER_RECEIVE_SIZE, LABEL_HTTP_METHOD, LABEL_HTTP_SCHEME,
-    LABEL_REQUEST_HEADERS, LABEL_RESPONSE_HEADERS, LABEL_UPLOAD, LABEL_URL_PARSE,
+    LABEL_DESTINATION_POLICY, LABEL_DOWNLOAD, LABEL_HEADER_RECEIVE_SIZE, LABEL_HTTP_METHOD,
+    LABEL_HTTP_SCHEME, LABEL_REQUEST_HEADERS, LABEL_RESPONSE_HEADERS, LABEL_RESPONSE_OFFSET,
+    LABEL_UPLOAD, LABEL_URL_PARSE,
 };
+use crate::policy::{denied_address, DestinationPolicy, PolicyResolver};
 use byte_unit::Byte;
 use core::convert::TryFrom;
-use http::{header::USER_AGENT, uri::Scheme, HeaderName, HeaderValue, Uri};
+use http::{
+    header::{
+        AUTHORIZATION, CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, COOKIE, LOCATION, RANGE,
+        USER_AGENT,
+    },
+    uri::Scheme,
+    HeaderName, HeaderValue, StatusCode, Uri,
+};
 use hyper::{
+    body::HttpBody,
     client::HttpConnector,
     header::{HeaderMap, ToStrError},
     Body, Client, Method,
 };
-use hyper_rustls::HttpsConnector;
+use hyper_rustls::{HttpsConnector, HttpsConnectorBuilder};
 use hyper_socks2::SocksConnector;
 use ic_async_utils::{receive_body_without_timeout, BodyReceiveError};
 use ic_https_outcalls_service::{
@@ -20,7 +30,7 @@ use ic_https_outcalls_service::{
 };
 use ic_logger::{debug, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
-use std::str::FromStr;
+use std::{str::FromStr, sync::RwLock, time::Duration};
 use tonic::{Request, Response, Status};
 
 /// Hyper only supports a maximum of 32768 headers https://docs.rs/hyper/0.14.23/hyper/header/index.html#limitations-1
@@ -33,28 +43,268 @@ const HEADER_NAME_VALUE_LIMIT: usize = 8_192;
 /// By default most higher-level http libs like `curl` set some `User-Agent` so we do the same here to avoid getting rejected due to strict server requirements.
 const USER_AGENT_ADAPTER: &str = "ic/1.0";
 
+/// Size of the largest `content-range` header that is returned for a paged request,
+/// i.e. the header name and `bytes <first>-<last>/<total>` with 20 digit numbers.
+const CONTENT_RANGE_MAX_SIZE: u64 = 13 + 8 + 3 * 20;
+
+type DirectClient = Client<HttpsConnector<HttpConnector<PolicyResolver>>>;
+
 /// implements RPC
 pub struct CanisterHttp {
-    client: Client<HttpsConnector<HttpConnector>>,
+    /// Client for direct connections, together with the destination policy its connector
+    /// enforces. It is rebuilt whenever requests arrive with a different policy.
+    client: RwLock<(DestinationPolicy, DirectClient)>,
     socks_client: Client<HttpsConnector<SocksConnector<HttpConnector>>>,
+    http_connect_timeout: Duration,
+    max_paged_response_size_bytes: u64,
     logger: ReplicaLogger,
     metrics: AdapterMetrics,
 }
 
 impl CanisterHttp {
     pub fn new(
-        client: Client<HttpsConnector<HttpConnector>>,
         socks_client: Client<HttpsConnector<SocksConnector<HttpConnector>>>,
+        http_connect_timeout: Duration,
+        max_paged_response_size_bytes: u64,
         logger: ReplicaLogger,
         metrics: &MetricsRegistry,
     ) -> Self {
+        let policy = DestinationPolicy::default();
+        let client = direct_client(policy.clone(), http_connect_timeout);
         Self {
-            client,
+            client: RwLock::new((policy, client)),
             socks_client,
+            http_connect_timeout,
+            max_paged_response_size_bytes,
             logger,
             metrics: AdapterMetrics::new(metrics),
         }
     }
+
+    /// Returns a client for direct connections whose connector enforces `policy`. The
+    /// client is rebuilt if the policy changed, so that no connection that was checked
+    /// against a previous policy is reused.
+    fn direct_client_for(&self, policy: &DestinationPolicy) -> DirectClient {
+        {
+            let (client_policy, client) = &*self.client.read().unwrap();
+            if client_policy == policy {
+                return client.clone();
+            }
+        }
+        let client = direct_client(policy.clone(), self.http_connect_timeout);
+        *self.client.write().unwrap() = (policy.clone(), client.clone());
+        client
+    }
+
+    fn destination_denied(&self, err: impl std::fmt::Display) -> Status {
+        debug!(self.logger, "Destination denied by policy: {}", err);
+        self.metrics
+            .request_errors
+            .with_label_values(&[LABEL_DESTINATION_POLICY])
+            .inc();
+        Status::new(
+            tonic::Code::PermissionDenied,
+            format!("Destination denied by subnet policy: {}", err),
+        )
+    }
+
+    /// Sends a single request without following redirects.
+    async fn send_request(
+        &self,
+        client: &DirectClient,
+        uri: &Uri,
+        method: Method,
+        headers: HeaderMap,
+        body: Vec<u8>,
+        socks_proxy_allowed: bool,
+    ) -> Result<hyper::Response<Body>, Status> {
+        // If we are allowed to use socks and condition described in `should_use_socks_proxy` hold,
+        // we do the requests through the socks proxy. If not we use the default IPv6 route.
+        if socks_proxy_allowed {
+            // Http request does not implement clone. So we have to manually construct a clone.
+            let req_body_clone = body.clone();
+            let mut http_req = hyper::Request::new(Body::from(body));
+            *http_req.headers_mut() = headers;
+            *http_req.method_mut() = method;
+            *http_req.uri_mut() = uri.clone();
+            let mut http_req_clone = hyper::Request::new(Body::from(req_body_clone));
+            *http_req_clone.headers_mut() = http_req.headers().clone();
+            *http_req_clone.method_mut() = http_req.method().clone();
+            *http_req_clone.uri_mut() = http_req.uri().clone();
+
+            match client.request(http_req).await {
+                // If we fail we try with the socks proxy. For destinations that are ipv4 only this should
+                // fail fast because our interface does not have an ipv4 assigned.
+                Err(direct_err) => {
+                    self.metrics.requests_socks.inc();
+                    self.socks_client.request(http_req_clone).await.map_err(|e| {
+                        format!("Request failed direct connect {direct_err} and connect through socks {e}")
+                    })
+                }
+                Ok(resp)=> Ok(resp),
+            }
+        } else {
+            let mut http_req = hyper::Request::new(Body::from(body));
+            *http_req.headers_mut() = headers;
+            *http_req.method_mut() = method;
+            *http_req.uri_mut() = uri.clone();
+            match client.request(http_req).await {
+                Err(err) => match denied_address(&err) {
+                    Some(denied) => return Err(self.destination_denied(denied)),
+                    None => Err(format!("Failed to directly connect: {err}")),
+                },
+                Ok(resp) => Ok(resp),
+            }
+            }
+        .map_err(|err| {
+            debug!(self.logger, "Failed to connect: {}", err);
+            self.metrics
+                .request_errors
+                .with_label_values(&[LABEL_CONNECT])
+                .inc();
+            Status::new(
+                tonic::Code::Unavailable,
+                format!(
+                    "Connecting to {:.50} failed: {}",
+                    uri.host().unwrap_or(""),
+                    err,
+                ),
+            )
+        })
+    }
+
+    /// Receives the page of the response body that starts at `offset`, together with a
+    /// `content-range` header describing it. The page and the header together fit into
+    /// `size_limit` bytes. If the server ignored the `Range` header of the request, the
+    /// body is downloaded from the start up to the end of the page, but at most up to
+    /// `max_paged_response_size_bytes`.
+    ///
+    /// Pages of different requests are only consistent if the server returns the same
+    /// body for each of them, which canisters can enforce with an `If-Range` header.
+    async fn receive_page(
+        &self,
+        http_resp: hyper::Response<Body>,
+        offset: u64,
+        size_limit: u64,
+    ) -> Result<(HttpHeader, Vec<u8>), Status> {
+        let page_error = |code, message: String| {
+            self.metrics
+                .request_errors
+                .with_label_values(&[LABEL_RESPONSE_OFFSET])
+                .inc();
+            Status::new(code, message)
+        };
+        let page_size = size_limit
+            .checked_sub(CONTENT_RANGE_MAX_SIZE)
+            .filter(|page_size| *page_size > 0)
+            .ok_or_else(|| {
+                page_error(
+                    tonic::Code::OutOfRange,
+                    format!(
+                        "Response size limit {} is too small to return a page of the response body",
+                        size_limit
+                    ),
+                )
+            })?;
+
+        // Offset of the received body within the whole body and the size of the whole body.
+        let (body_offset, total) = if http_resp.status() == StatusCode::PARTIAL_CONTENT {
+            let (first, total) = http_resp
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|value| parse_content_range(value.to_str().ok()?))
+                .ok_or_else(|| {
+                    page_error(
+                        tonic::Code::Unavailable,
+                        "Partial response without a valid content-range header".to_string(),
+                    )
+                })?;
+            if first != offset {
+                return Err(page_error(
+                    tonic::Code::Unavailable,
+                    format!("Partial response starts at {} instead of {}", first, offset),
+                ));
+            }
+            (offset, total)
+        } else {
+            if offset >= self.max_paged_response_size_bytes {
+                return Err(page_error(
+                    tonic::Code::OutOfRange,
+                    format!(
+                        "Response offset {} exceeds the limit {} for servers without range support",
+                        offset, self.max_paged_response_size_bytes
+                    ),
+                ));
+            }
+            let content_length = http_resp
+                .headers()
+                .get(CONTENT_LENGTH)
+                .and_then(|value| value.to_str().ok()?.parse().ok());
+            (0, content_length)
+        };
+        let skip = offset - body_offset;
+        // A partial response starts at the offset, otherwise the body is only read up to the limit.
+        let read_limit = match body_offset {
+            0 => skip
+                .saturating_add(page_size)
+                .min(self.max_paged_response_size_bytes),
+            _ => page_size,
+        };
+
+        // We don't need a timeout here because there is a global timeout on the entire request.
+        let (bytes, complete) = receive_body_prefix(http_resp.into_body(), read_limit)
+            .await
+            .map_err(|err| {
+                debug!(self.logger, "Failed to fetch body: {}", err);
+                self.metrics
+                    .request_errors
+                    .with_label_values(&[LABEL_BODY_RECEIVE_TIMEOUT])
+                    .inc();
+                Status::new(
+                    tonic::Code::Unavailable,
+                    format!("Failed to fetch body: {}", err),
+                )
+            })?;
+        self.metrics
+            .network_traffic
+            .with_label_values(&[LABEL_DOWNLOAD])
+            .inc_by(bytes.len() as u64);
+
+        let total = if complete {
+            Some(body_offset + bytes.len() as u64)
+        } else {
+            total
+        };
+        if (bytes.len() as u64) < skip {
+            return Err(page_error(
+                tonic::Code::OutOfRange,
+                format!(
+                    "Response offset {} exceeds response body size {}",
+                    offset,
+                    bytes.len()
+                ),
+            ));
+        }
+        let page = bytes[skip as usize..].to_vec();
+        let total = total.map_or("*".to_string(), |total| total.to_string());
+        let value = if page.is_empty() {
+            format!("bytes */{}", total)
+        } else {
+            format!(
+                "bytes {}-{}/{}",
+                offset,
+                offset + page.len() as u64 - 1,
+                total
+            )
+        };
+        Ok((
+            HttpHeader {
+                name: CONTENT_RANGE.to_string(),
+                value,
+            },
+            page,
+        ))
+    }
 }
 
 #[tonic::async_trait]
@@ -65,7 +315,24 @@ impl CanisterHttpService for CanisterHttp {
     ) -> Result<Response<CanisterHttpSendResponse>, Status> {
         self.metrics.requests.inc();
 
-        let req = request.into_inner();
+        let mut req = request.into_inner();
+
+        let policy = req
+            .policy
+            .take()
+            .map(DestinationPolicy::try_from)
+            .transpose()
+            .map_err(|err| {
+                self.metrics
+                    .request_errors
+                    .with_label_values(&[LABEL_DESTINATION_POLICY])
+                    .inc();
+                Status::new(
+                    tonic::Code::InvalidArgument,
+                    format!("Invalid destination policy: {}", err),
+                )
+            })?
+            .unwrap_or_default();
 
         let uri = req.url.parse::<Uri>().map_err(|err| {
             debug!(self.logger, "Failed to parse URL: {}", err);
@@ -105,6 +372,10 @@ impl CanisterHttpService for CanisterHttp {
                 HttpMethod::Get => Ok(Method::GET),
                 HttpMethod::Post => Ok(Method::POST),
                 HttpMethod::Head => Ok(Method::HEAD),
+                HttpMethod::Put => Ok(Method::PUT),
+                HttpMethod::Patch => Ok(Method::PATCH),
+                HttpMethod::Delete => Ok(Method::DELETE),
+                HttpMethod::Options => Ok(Method::OPTIONS),
                 _ => {
                     self.metrics
                         .request_errors
@@ -129,63 +400,71 @@ impl CanisterHttpService for CanisterHttp {
         // Add user-agent header if not present.
         add_fallback_user_agent_header(&mut headers);
 
-        let mut request_size = req.body.len();
-        request_size += headers
-            .iter()
-            .map(|(name, value)| name.as_str().len() + value.len())
-            .sum::<usize>();
+        // For paged requests, ask the server for the body starting at the offset only.
+        if let Some(offset) = req.response_offset {
+            headers.insert(
+                RANGE,
+                HeaderValue::try_from(format!("bytes={}-", offset))
+                    .expect("The range header value is valid"),
+            );
+        }
 
-        // If we are allowed to use socks and condition described in `should_use_socks_proxy` hold,
-        // we do the requests through the socks proxy. If not we use the default IPv6 route.
-        let http_resp = if req.socks_proxy_allowed {
-            // Http request does not implement clone. So we have to manually construct a clone.
-            let req_body_clone = req.body.clone();
-            let mut http_req = hyper::Request::new(Body::from(req.body));
-            *http_req.headers_mut() = headers;
-            *http_req.method_mut() = method;
-            *http_req.uri_mut() = uri.clone();
-            let mut http_req_clone = hyper::Request::new(Body::from(req_body_clone));
-            *http_req_clone.headers_mut() = http_req.headers().clone();
-            *http_req_clone.method_mut() = http_req.method().clone();
-            *http_req_clone.uri_mut() = http_req.uri().clone();
+        let client = self.direct_client_for(&policy);
+        let mut uri = uri;
+        let mut method = method;
+        let mut body = req.body;
+        let mut redirects = 0;
+        let http_resp = loop {
+            policy
+                .check(&uri)
+                .map_err(|err| self.destination_denied(err))?;
 
-            match self.client.request(http_req).await {
-                // If we fail we try with the socks proxy. For destinations that are ipv4 only this should
-                // fail fast because our interface does not have an ipv4 assigned.
-                Err(direct_err) => {
-                    self.metrics.requests_socks.inc();
-                    self.socks_client.request(http_req_clone).await.map_err(|e| {
-                        format!("Request failed direct connect {direct_err} and connect through socks {e}")
-                    })
+            let mut request_size = body.len();
+            request_size += headers
+                .iter()
+                .map(|(name, value)| name.as_str().len() + value.len())
+                .sum::<usize>();
+
+            let http_resp = self
+                .send_request(
+                    &client,
+                    &uri,
+                    method.clone(),
+                    headers.clone(),
+                    body.clone(),
+                    req.socks_proxy_allowed && policy.allows_proxy(&uri),
+                )
+                .await?;
+            self.metrics
+                .network_traffic
+                .with_label_values(&[LABEL_UPLOAD])
+                .inc_by(request_size as u64);
+
+            match redirect_target(&uri, &http_resp) {
+                Some(target) if redirects < policy.max_redirects() => {
+                    debug!(self.logger, "Following redirect from {} to {}", uri, target);
+                    redirects += 1;
+                    let status = http_resp.status();
+                    if status == StatusCode::SEE_OTHER
+                        || (method == Method::POST
+                            && (status == StatusCode::MOVED_PERMANENTLY
+                                || status == StatusCode::FOUND))
+                    {
+                        method = Method::GET;
+                        body = Vec::new();
+                        headers.remove(CONTENT_LENGTH);
+                        headers.remove(CONTENT_TYPE);
+                    }
+                    // Do not leak credentials to other hosts.
+                    if target.authority() != uri.authority() {
+                        headers.remove(AUTHORIZATION);
+                        headers.remove(COOKIE);
+                    }
+                    uri = target;
                 }
-                Ok(resp)=> Ok(resp),
-            }
-        } else {
-            let mut http_req = hyper::Request::new(Body::from(req.body));
-            *http_req.headers_mut() = headers;
-            *http_req.method_mut() = method;
-            *http_req.uri_mut() = uri.clone();
-            self.client.request(http_req).await.map_err(|e| format!("Failed to directly connect: {e}"))
+                _ => break http_resp,
             }
-        .map_err(|err| {
-            debug!(self.logger, "Failed to connect: {}", err);
-            self.metrics
-                .request_errors
-                .with_label_values(&[LABEL_CONNECT])
-                .inc();
-            Status::new(
-                tonic::Code::Unavailable,
-                format!(
-                    "Connecting to {:.50} failed: {}",
-                    uri.host().unwrap_or(""),
-                    err,
-                ),
-            )
-        })?;
-        self.metrics
-            .network_traffic
-            .with_label_values(&[LABEL_UPLOAD])
-            .inc_by(request_size as u64);
+        };
 
         let status = http_resp.status().as_u16() as u32;
 
@@ -215,66 +494,101 @@ impl CanisterHttpService for CanisterHttp {
                 )
             })?;
 
-        // We don't need a timeout here because there is a global timeout on the entire request.
-        let body_bytes = receive_body_without_timeout(
-            http_resp.into_body(),
-            // Account for size of headers.
-            Byte::from(
-                req.max_response_size_bytes
-                    .checked_sub(headers_size_bytes as u64)
-                    .ok_or_else(|| {
-                        self.metrics
-                            .request_errors
-                            .with_label_values(&[LABEL_HEADER_RECEIVE_SIZE])
-                            .inc();
-                        Status::new(
-                            tonic::Code::OutOfRange,
-                            format!(
-                                "Header size exceeds specified response size limit {}",
-                                req.max_response_size_bytes
-                            ),
-                        )
-                    })?,
-            ),
-        )
-        .await
-        .map_err(|err| {
-            debug!(self.logger, "Failed to fetch body: {}", err);
-            match err {
-                // SysTransient error
-                BodyReceiveError::Timeout(e) | BodyReceiveError::Unavailable(e) => {
-                    self.metrics
-                        .request_errors
-                        .with_label_values(&[LABEL_BODY_RECEIVE_TIMEOUT])
-                        .inc();
-                    Status::new(
-                        tonic::Code::Unavailable,
-                        format!("Failed to fetch body: {}", e),
-                    )
-                }
-                // SysFatal error
-                BodyReceiveError::TooLarge(e) => {
-                    self.metrics
-                        .request_errors
-                        .with_label_values(&[LABEL_BODY_RECEIVE_SIZE])
-                        .inc();
-                    Status::new(tonic::Code::OutOfRange, e)
-                }
+        // Account for size of headers.
+        let body_size_limit = req
+            .max_response_size_bytes
+            .checked_sub(headers_size_bytes as u64)
+            .ok_or_else(|| {
+                self.metrics
+                    .request_errors
+                    .with_label_values(&[LABEL_HEADER_RECEIVE_SIZE])
+                    .inc();
+                Status::new(
+                    tonic::Code::OutOfRange,
+                    format!(
+                        "Header size exceeds specified response size limit {}",
+                        req.max_response_size_bytes
+                    ),
+                )
+            })?;
+
+        let (headers, content) = match req.response_offset {
+            None => {
+                // We don't need a timeout here because there is a global timeout on the entire request.
+                let body_bytes = receive_body_without_timeout(
+                    http_resp.into_body(),
+                    Byte::from(body_size_limit),
+                )
+                .await
+                .map_err(|err| {
+                    debug!(self.logger, "Failed to fetch body: {}", err);
+                    match err {
+                        // SysTransient error
+                        BodyReceiveError::Timeout(e) | BodyReceiveError::Unavailable(e) => {
+                            self.metrics
+                                .request_errors
+                                .with_label_values(&[LABEL_BODY_RECEIVE_TIMEOUT])
+                                .inc();
+                            Status::new(
+                                tonic::Code::Unavailable,
+                                format!("Failed to fetch body: {}", e),
+                            )
+                        }
+                        // SysFatal error
+                        BodyReceiveError::TooLarge(e) => {
+                            self.metrics
+                                .request_errors
+                                .with_label_values(&[LABEL_BODY_RECEIVE_SIZE])
+                                .inc();
+                            Status::new(tonic::Code::OutOfRange, e)
+                        }
+                    }
+                })?;
+                self.metrics
+                    .network_traffic
+                    .with_label_values(&[LABEL_DOWNLOAD])
+                    .inc_by(body_bytes.len() as u64 + headers_size_bytes as u64);
+                (headers, body_bytes.to_vec())
             }
-        })?;
+            Some(offset) => {
+                let (content_range, page) = self
+                    .receive_page(http_resp, offset, body_size_limit)
+                    .await?;
+                self.metrics
+                    .network_traffic
+                    .with_label_values(&[LABEL_DOWNLOAD])
+                    .inc_by(headers_size_bytes as u64);
+                let headers = headers
+                    .into_iter()
+                    .filter(|header| !header.name.eq_ignore_ascii_case(CONTENT_RANGE.as_str()))
+                    .chain(std::iter::once(content_range))
+                    .collect();
+                (headers, page)
+            }
+        };
 
-        self.metrics
-            .network_traffic
-            .with_label_values(&[LABEL_DOWNLOAD])
-            .inc_by(body_bytes.len() as u64 + headers_size_bytes as u64);
         Ok(Response::new(CanisterHttpSendResponse {
             status,
             headers,
-            content: body_bytes.to_vec(),
+            content,
         }))
     }
 }
 
+/// Builds a client for direct connections, whose connector only dials addresses allowed
+/// by `policy`.
+fn direct_client(policy: DestinationPolicy, connect_timeout: Duration) -> DirectClient {
+    let mut http_connector = HttpConnector::new_with_resolver(PolicyResolver::new(policy));
+    http_connector.enforce_http(false);
+    http_connector.set_connect_timeout(Some(connect_timeout));
+    let https_connector = HttpsConnectorBuilder::new()
+        .with_native_roots()
+        .https_only()
+        .enable_http1()
+        .wrap_connector(http_connector);
+    Client::builder().build::<_, hyper::Body>(https_connector)
+}
+
 fn validate_headers(raw_headers: Vec<HttpHeader>) -> Result<HeaderMap, Status> {
     // Check we are within limit for number of headers.
     if raw_headers.len() > HEADERS_LIMIT {
@@ -321,6 +635,64 @@ fn validate_headers(raw_headers: Vec<HttpHeader>) -> Result<HeaderMap, Status> {
     Ok(headers)
 }
 
+/// Receives at most `limit` bytes of `body`. Returns the received bytes and whether
+/// they are the whole body.
+async fn receive_body_prefix(mut body: Body, limit: u64) -> Result<(Vec<u8>, bool), hyper::Error> {
+    let limit = limit as usize;
+    let mut bytes = Vec::new();
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk?;
+        let remaining = limit - bytes.len();
+        if chunk.len() > remaining {
+            bytes.extend_from_slice(&chunk[..remaining]);
+            return Ok((bytes, false));
+        }
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok((bytes, true))
+}
+
+/// Parses a `content-range` header value of the form `bytes <first>-<last>/<total>`
+/// into the first byte position and the total size, if known.
+fn parse_content_range(value: &str) -> Option<(u64, Option<u64>)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (first, _) = range.split_once('-')?;
+    let total = match total {
+        "*" => None,
+        total => Some(total.parse().ok()?),
+    };
+    Some((first.parse().ok()?, total))
+}
+
+/// Returns the target of a redirect response, if it should be followed.
+///
+/// Only absolute https targets and targets relative to the root of the current
+/// origin are followed.
+fn redirect_target(uri: &Uri, response: &hyper::Response<Body>) -> Option<Uri> {
+    if !matches!(
+        response.status(),
+        StatusCode::MOVED_PERMANENTLY
+            | StatusCode::FOUND
+            | StatusCode::SEE_OTHER
+            | StatusCode::TEMPORARY_REDIRECT
+            | StatusCode::PERMANENT_REDIRECT
+    ) {
+        return None;
+    }
+    let location = response.headers().get(LOCATION)?.to_str().ok()?;
+    let target = if location.starts_with('/') && !location.starts_with("//") {
+        Uri::builder()
+            .scheme(Scheme::HTTPS)
+            .authority(uri.authority()?.clone())
+            .path_and_query(location)
+            .build()
+            .ok()?
+    } else {
+        location.parse::<Uri>().ok()?
+    };
+    (target.scheme() == Some(&Scheme::HTTPS)).then_some(target)
+}
+
 /// Adds a fallback user agent header if not already present in headermap
 fn add_fallback_user_agent_header(header_map: &mut HeaderMap) {
     if !header_map
@@ -426,4 +798,32 @@ mod tests {
         let headers = validate_headers(header_vec).unwrap();
         assert_eq!(headers.len(), 3);
     }
+
+    #[test]
+    // Verify that content-range headers of partial responses are parsed.
+    fn test_parse_content_range() {
+        assert_eq!(parse_content_range("bytes 0-39/100"), Some((0, Some(100))));
+        assert_eq!(parse_content_range("bytes 80-99/*"), Some((80, None)));
+        assert_eq!(parse_content_range("bytes */100"), None);
+        assert_eq!(parse_content_range("items 0-39/100"), None);
+        assert_eq!(parse_content_range("bytes 0-39/many"), None);
+    }
+
+    #[tokio::test]
+    // Verify that only a prefix of the body is received.
+    async fn test_receive_body_prefix() {
+        let body: Vec<u8> = (0..100).collect();
+
+        let (bytes, complete) = receive_body_prefix(Body::from(body.clone()), 40)
+            .await
+            .unwrap();
+        assert_eq!(bytes, body[0..40]);
+        assert!(!complete);
+
+        let (bytes, complete) = receive_body_prefix(Body::from(body.clone()), 100)
+            .await
+            .unwrap();
+        assert_eq!(bytes, body);
+        assert!(complete);
+    }
 }
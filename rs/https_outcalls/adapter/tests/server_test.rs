@@ -7,7 +7,7 @@ mod test {
     use ic_https_outcalls_adapter::{AdapterServer, Config};
     use ic_https_outcalls_service::{
         canister_http_service_client::CanisterHttpServiceClient, CanisterHttpSendRequest,
-        HttpMethod,
+        HttpMethod, HttpOutcallsPolicy,
     };
     use ic_logger::replica_logger::no_op_logger;
     use ic_metrics::MetricsRegistry;
@@ -96,6 +96,31 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             .and(warp::body::json())
             .map(|req: usize| Response::builder().body(vec![0u8; req]));
 
+        // Honours `Range: bytes=<first>-` headers with a partial response.
+        let get_range = warp::get()
+            .and(warp::path("range"))
+            .and(warp::header::optional::<String>("range"))
+            .and(warp::body::json())
+            .map(|range: Option<String>, size: usize| {
+                let first = range
+                    .and_then(|range| {
+                        range
+                            .strip_prefix("bytes=")?
+                            .strip_suffix('-')?
+                            .parse()
+                            .ok()
+                    })
+                    .unwrap_or(0)
+                    .min(size - 1);
+                Response::builder()
+                    .status(StatusCode::PARTIAL_CONTENT)
+                    .header(
+                        "content-range",
+                        format!("bytes {}-{}/{}", first, size - 1, size),
+                    )
+                    .body(vec![0u8; size - first])
+            });
+
         let get_delay = warp::get()
             .and(warp::path("delay"))
             .and(warp::body::json())
@@ -106,10 +131,21 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
 
         let basic_head = warp::head().and(warp::path("head")).map(warp::reply::reply);
 
+        let echo_method = warp::path("method")
+            .and(warp::method())
+            .map(|method: warp::http::Method| method.to_string());
+
+        let redirect = warp::get()
+            .and(warp::path("redirect"))
+            .map(|| warp::redirect::found(warp::http::Uri::from_static("/get")));
+
         let routes = basic_post
             .or(basic_get)
+            .or(redirect)
             .or(basic_head)
+            .or(echo_method)
             .or(get_response_size)
+            .or(get_range)
             .or(get_delay)
             .or(invalid_header);
 
@@ -138,6 +174,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
         let response = client.canister_http_send(request).await;
         let http_response = response.unwrap().into_inner();
@@ -161,6 +199,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
         let response = client.canister_http_send(request).await;
         assert_eq!(
@@ -189,6 +229,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "420".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
 
         let response = client.canister_http_send(request).await;
@@ -213,6 +255,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
 
         let response = client.canister_http_send(request).await;
@@ -220,6 +264,39 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
         assert_eq!(http_response.status, StatusCode::OK.as_u16() as u32);
     }
 
+    #[tokio::test]
+    async fn test_canister_http_server_other_methods() {
+        let server_config = Config {
+            ..Default::default()
+        };
+
+        let url = start_server(CERT_INIT.get_or_init(generate_certs));
+        let mut client = spawn_grpc_server(server_config);
+
+        for (method, name) in [
+            (HttpMethod::Put, "PUT"),
+            (HttpMethod::Patch, "PATCH"),
+            (HttpMethod::Delete, "DELETE"),
+            (HttpMethod::Options, "OPTIONS"),
+        ] {
+            let request = tonic::Request::new(CanisterHttpSendRequest {
+                url: format!("https://{}/method", &url),
+                headers: Vec::new(),
+                method: method as i32,
+                body: "".to_string().as_bytes().to_vec(),
+                max_response_size_bytes: 512,
+                socks_proxy_allowed: false,
+                response_offset: None,
+                policy: None,
+            });
+
+            let response = client.canister_http_send(request).await;
+            let http_response = response.unwrap().into_inner();
+            assert_eq!(http_response.status, StatusCode::OK.as_u16() as u32);
+            assert_eq!(String::from_utf8_lossy(&http_response.content), name);
+        }
+    }
+
     #[tokio::test]
     async fn test_response_limit_exceeded() {
         // Check if response with higher than allowed response limit is rejected.
@@ -238,6 +315,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: format!("{}", response_limit + 1).as_bytes().to_vec(),
             max_response_size_bytes: response_limit,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
 
         let response = client.canister_http_send(request).await;
@@ -251,6 +330,116 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             .contains(&"header exceeds http body size".to_string()));
     }
 
+    // Fetches a body of `body_size` bytes from `path` page by page and checks that each
+    // response has the expected status and a content-range header describing the page.
+    async fn fetch_pages(path: &str, body_size: u64, expected_status: StatusCode) {
+        let response_limit: u64 = 512;
+        let server_config = Config {
+            ..Default::default()
+        };
+
+        let url = start_server(CERT_INIT.get_or_init(generate_certs));
+        let mut client = spawn_grpc_server(server_config);
+
+        let mut offset = 0;
+        while offset < body_size {
+            let request = tonic::Request::new(CanisterHttpSendRequest {
+                url: format!("https://{}/{}", &url, path),
+                headers: Vec::new(),
+                method: HttpMethod::Get as i32,
+                body: format!("{}", body_size).as_bytes().to_vec(),
+                max_response_size_bytes: response_limit,
+                socks_proxy_allowed: false,
+                response_offset: Some(offset),
+                policy: None,
+            });
+
+            let response = client.canister_http_send(request).await;
+            let http_response = response.unwrap().into_inner();
+            assert_eq!(http_response.status, expected_status.as_u16() as u32);
+            let page_size = http_response.content.len() as u64;
+            assert!(page_size > 0);
+            let content_range = http_response
+                .headers
+                .iter()
+                .find(|header| header.name == "content-range")
+                .unwrap();
+            assert_eq!(
+                content_range.value,
+                format!("bytes {}-{}/{}", offset, offset + page_size - 1, body_size)
+            );
+            offset += page_size;
+        }
+        assert_eq!(offset, body_size);
+    }
+
+    #[tokio::test]
+    async fn test_paged_response() {
+        // Check that a body larger than the response limit can be fetched page by page
+        // from a server that ignores the range header.
+        fetch_pages("size", 2000, StatusCode::OK).await;
+    }
+
+    #[tokio::test]
+    async fn test_paged_response_with_range_support() {
+        // Check that only the requested part of the body is fetched from a server that
+        // supports range requests.
+        fetch_pages("range", 2000, StatusCode::PARTIAL_CONTENT).await;
+    }
+
+    #[tokio::test]
+    async fn test_destination_denied_by_policy() {
+        let server_config = Config {
+            ..Default::default()
+        };
+        let url = start_server(CERT_INIT.get_or_init(generate_certs));
+        let mut client = spawn_grpc_server(server_config);
+
+        let request = tonic::Request::new(CanisterHttpSendRequest {
+            url: format!("https://{}/get", &url),
+            headers: Vec::new(),
+            method: HttpMethod::Get as i32,
+            body: "hello".to_string().as_bytes().to_vec(),
+            max_response_size_bytes: 512,
+            socks_proxy_allowed: false,
+            response_offset: None,
+            policy: Some(HttpOutcallsPolicy {
+                block_private_addresses: true,
+                ..Default::default()
+            }),
+        });
+        let response = client.canister_http_send(request).await;
+        assert_eq!(response.unwrap_err().code(), tonic::Code::PermissionDenied);
+    }
+
+    #[tokio::test]
+    async fn test_redirect() {
+        let server_config = Config {
+            ..Default::default()
+        };
+        let url = start_server(CERT_INIT.get_or_init(generate_certs));
+        let mut client = spawn_grpc_server(server_config);
+
+        for (max_redirects, expected_status) in [(0, StatusCode::FOUND), (1, StatusCode::OK)] {
+            let request = tonic::Request::new(CanisterHttpSendRequest {
+                url: format!("https://{}/redirect", &url),
+                headers: Vec::new(),
+                method: HttpMethod::Get as i32,
+                body: Vec::new(),
+                max_response_size_bytes: 512,
+                socks_proxy_allowed: false,
+                response_offset: None,
+                policy: Some(HttpOutcallsPolicy {
+                    max_redirects,
+                    ..Default::default()
+                }),
+            });
+            let response = client.canister_http_send(request).await;
+            let http_response = response.unwrap().into_inner();
+            assert_eq!(http_response.status, expected_status.as_u16() as u32);
+        }
+    }
+
     #[tokio::test]
     async fn test_within_response_limit() {
         let response_size: u64 = 512;
@@ -268,6 +457,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: format!("{}", response_size).as_bytes().to_vec(),
             max_response_size_bytes: response_size * 2,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
 
         let response = client.canister_http_send(request).await;
@@ -293,6 +484,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: format!("{}", delay).as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
 
         let response = client.canister_http_send(request).await;
@@ -327,6 +520,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 64,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
         let response = client.canister_http_send(request).await;
         assert_eq!(
@@ -356,6 +551,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".as_bytes().to_vec(),
             max_response_size_bytes: response_limit,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
 
         let response = client.canister_http_send(request).await;
@@ -379,6 +576,8 @@ MIGHAgEAMBMGByqGSM49AgEGCCqGSM49AwEHBG0wawIBAQQgob29X4H4m2XOkSZE
             body: "hello".to_string().as_bytes().to_vec(),
             max_response_size_bytes: 512,
             socks_proxy_allowed: false,
+            response_offset: None,
+            policy: None,
         });
         let response = client.canister_http_send(request).await;
         let _ = response.unwrap_err();
@@ -4,7 +4,7 @@ use futures::future::TryFutureExt;
 use ic_error_types::{RejectCode, UserError};
 use ic_https_outcalls_service::{
     canister_http_service_client::CanisterHttpServiceClient, CanisterHttpSendRequest,
-    CanisterHttpSendResponse, HttpHeader, HttpMethod,
+    CanisterHttpSendResponse, HttpHeader, HttpMethod, HttpOutcallsPolicy,
 };
 use ic_interfaces::execution_environment::QueryExecutionService;
 use ic_interfaces_adapter_client::{NonBlockingChannel, SendError, TryReceiveError};
@@ -13,7 +13,7 @@ use ic_metrics::MetricsRegistry;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{
     canister_http::{
-        validate_http_headers_and_body, CanisterHttpMethod, CanisterHttpReject,
+        validate_http_headers_and_body, CanisterHttpMethod, CanisterHttpPolicy, CanisterHttpReject,
         CanisterHttpRequest, CanisterHttpRequestContext, CanisterHttpResponse,
         CanisterHttpResponseContent, Transform, MAX_CANISTER_HTTP_RESPONSE_BYTES,
     },
@@ -136,8 +136,10 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                         http_method: request_http_method,
                         max_response_bytes: request_max_response_bytes,
                         transform: request_transform,
+                        response_offset: request_response_offset,
                         ..
                     },
+                policy: request_policy,
             } = canister_http_request;
 
             let adapter_req_timer = Instant::now();
@@ -149,6 +151,10 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                         CanisterHttpMethod::GET => HttpMethod::Get.into(),
                         CanisterHttpMethod::POST => HttpMethod::Post.into(),
                         CanisterHttpMethod::HEAD => HttpMethod::Head.into(),
+                        CanisterHttpMethod::PUT => HttpMethod::Put.into(),
+                        CanisterHttpMethod::PATCH => HttpMethod::Patch.into(),
+                        CanisterHttpMethod::DELETE => HttpMethod::Delete.into(),
+                        CanisterHttpMethod::OPTIONS => HttpMethod::Options.into(),
                     },
                     max_response_size_bytes: request_max_response_bytes.unwrap_or(NumBytes::new(MAX_CANISTER_HTTP_RESPONSE_BYTES)).get(),
                     headers: request_headers
@@ -160,7 +166,9 @@ impl NonBlockingChannel<CanisterHttpRequest> for CanisterHttpAdapterClientImpl {
                         .collect(),
                     body: request_body.unwrap_or_default(),
                     // Socks proxy is only enabled on system subnets.
-                    socks_proxy_allowed: matches!(subnet_type, SubnetType::System)
+                    socks_proxy_allowed: matches!(subnet_type, SubnetType::System),
+                    response_offset: request_response_offset,
+                    policy: Some(policy_to_proto(request_policy)),
                 })
                 .map_err(|grpc_status| {
                     (
@@ -320,11 +328,32 @@ async fn transform_adapter_response(
     }
 }
 
+fn policy_to_proto(policy: CanisterHttpPolicy) -> HttpOutcallsPolicy {
+    let CanisterHttpPolicy {
+        allowed_domains,
+        denied_domains,
+        allowed_ip_ranges,
+        denied_ip_ranges,
+        block_private_addresses,
+        max_redirects,
+    } = policy;
+    HttpOutcallsPolicy {
+        allowed_domains,
+        denied_domains,
+        allowed_ip_ranges,
+        denied_ip_ranges,
+        block_private_addresses,
+        max_redirects,
+    }
+}
+
 fn grpc_status_code_to_reject(code: Code) -> RejectCode {
     match code {
         // TODO: Is unavailable really transient
         Code::Unavailable => RejectCode::SysTransient,
         Code::InvalidArgument => RejectCode::SysFatal,
+        // The adapter refused to contact the destination due to the subnet's policy.
+        Code::PermissionDenied => RejectCode::DestinationInvalid,
         _ => RejectCode::SysFatal,
     }
 }
@@ -340,7 +369,7 @@ mod tests {
     use ic_test_utilities_types::messages::RequestBuilder;
     use ic_types::canister_http::Transform;
     use ic_types::{
-        canister_http::CanisterHttpMethod,
+        canister_http::{CanisterHttpMethod, Replication},
         messages::{CallbackId, CertificateDelegation},
         time::current_time,
         time::UNIX_EPOCH,
@@ -435,7 +464,10 @@ mod tests {
                     context: vec![],
                 }),
                 time: UNIX_EPOCH,
+                replication: Replication::FullyReplicated,
+                response_offset: None,
             },
+            policy: CanisterHttpPolicy::default(),
         }
     }
 
@@ -749,6 +781,58 @@ mod tests {
         assert_eq!(client.try_receive(), Err(TryReceiveError::Empty));
     }
 
+    /// Test case where the adapter refuses the destination due to the subnet's policy.
+    /// This should be reported as an invalid destination.
+    #[tokio::test]
+    async fn test_client_permission_denied_adapter_response() {
+        // Adapter mock setup. Return a PERMISSION_DENIED error.
+        let mock_grpc_channel = setup_adapter_mock(Err((
+            Code::PermissionDenied,
+            "destination denied by policy".to_string(),
+        )))
+        .await;
+        // Asynchronous query handler mock setup. Does not serve any purpose in this test case.
+        let (svc, mut handle) = setup_anonymous_query_mock();
+
+        tokio::spawn(async move {
+            let (_, rsp) = handle.next_request().await.unwrap();
+            rsp.send_response(Err(QueryExecutionError::CertifiedStateUnavailable));
+        });
+
+        let mut client = CanisterHttpAdapterClientImpl::new(
+            tokio::runtime::Handle::current(),
+            mock_grpc_channel,
+            svc,
+            100,
+            MetricsRegistry::default(),
+            SubnetType::Application,
+        );
+
+        assert_eq!(
+            client.send(build_mock_canister_http_request(420, UNIX_EPOCH, None)),
+            Ok(())
+        );
+        // Yield to execute the request on the client.
+        loop {
+            match client.try_receive() {
+                Err(_) => tokio::time::sleep(Duration::from_millis(10)).await,
+                Ok(r) => {
+                    assert_eq!(
+                        r,
+                        build_mock_canister_http_response_reject(
+                            420,
+                            UNIX_EPOCH,
+                            RejectCode::DestinationInvalid,
+                            "destination denied by policy".to_string()
+                        )
+                    );
+                    break;
+                }
+            }
+        }
+        assert_eq!(client.try_receive(), Err(TryReceiveError::Empty));
+    }
+
     /// Test client with a specified transform function.
     #[tokio::test]
     async fn test_client_no_op_transform() {
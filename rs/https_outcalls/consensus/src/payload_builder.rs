@@ -34,7 +34,7 @@ use ic_types::{
     canister_http::{
         CanisterHttpResponse, CanisterHttpResponseContent, CanisterHttpResponseDivergence,
         CanisterHttpResponseMetadata, CanisterHttpResponseProof, CanisterHttpResponseWithConsensus,
-        CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
+        Replication, CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     consensus::Committee,
     crypto::Signed,
@@ -199,6 +199,8 @@ impl CanisterHttpPayloadBuilderImpl {
 
         let mut candidates = vec![];
         let mut timeouts = vec![];
+        // Requests that are made by a single node, with the designated node.
+        let mut non_replicated_requests = BTreeMap::new();
         let mut divergence_responses = vec![];
 
         // Metrics counters
@@ -218,14 +220,21 @@ impl CanisterHttpPayloadBuilderImpl {
             .state_reader
             .get_state_at(validation_context.certified_height)
         {
-            // Iterate over all outstanding canister http requests
-            for (callback_id, request) in state
+            let http_contexts = &state
                 .get_ref()
                 .metadata
                 .subnet_call_context_manager
-                .canister_http_request_contexts
+                .canister_http_request_contexts;
+            non_replicated_requests = http_contexts
                 .iter()
-            {
+                .filter_map(|(callback_id, request)| match request.replication {
+                    Replication::FullyReplicated => None,
+                    Replication::NonReplicated(node_id) => Some((*callback_id, node_id)),
+                })
+                .collect();
+
+            // Iterate over all outstanding canister http requests
+            for (callback_id, request) in http_contexts.iter() {
                 unique_includable_responses += 1;
                 let candidate_size = callback_id.count_bytes();
                 let size = NumBytes::new((accumulated_size + candidate_size) as u64);
@@ -279,7 +288,26 @@ impl CanisterHttpPayloadBuilderImpl {
 
             let candidates_and_divergences = response_candidates_by_callback_id
                 .into_iter()
-                .filter_map(|(_, grouped_shares)| {
+                .filter_map(|(callback_id, grouped_shares)| {
+                    // A non-replicated response only needs the share of the
+                    // designated node and can never diverge.
+                    if let Some(node_id) = non_replicated_requests.get(&callback_id) {
+                        unique_responses_count += 1;
+                        return grouped_shares.iter().find_map(|(metadata, shares)| {
+                            let share = shares
+                                .iter()
+                                .find(|share| share.signature.signer == *node_id)?;
+                            pool_access
+                                .get_response_content_by_hash(&metadata.content_hash)
+                                .map(|content| {
+                                    CandidateOrDivergence::Candidate((
+                                        metadata.clone(),
+                                        BTreeSet::from([share.signature.clone()]),
+                                        content,
+                                    ))
+                                })
+                        });
+                    }
                     if let Some((metadata, shares)) = grouped_shares.iter().find(|(_, shares)| {
                         unique_responses_count += 1;
                         let signers: BTreeSet<_> =
@@ -475,6 +503,35 @@ impl CanisterHttpPayloadBuilderImpl {
         // NOTE: We do this in a separate loop because this check is expensive and we want to
         // do all the cheap checks first
         for response in &payload.responses {
+            // Non-replicated responses must be signed by exactly the designated node
+            if let Some(Replication::NonReplicated(node_id)) = http_contexts
+                .get(&response.content.id)
+                .map(|context| &context.replication)
+            {
+                let signers: Vec<NodeId> = response
+                    .proof
+                    .signature
+                    .signatures_map
+                    .keys()
+                    .cloned()
+                    .collect();
+                if signers != [*node_id] {
+                    return invalid_artifact(
+                        InvalidCanisterHttpPayloadReason::NonReplicatedSignerMismatch {
+                            expected: *node_id,
+                            signers,
+                        },
+                    );
+                }
+                self.crypto
+                    .verify_aggregate(&response.proof, consensus_registry_version)
+                    .map_err(|err| {
+                        CanisterHttpPayloadValidationError::InvalidArtifact(
+                            InvalidCanisterHttpPayloadReason::SignatureError(Box::new(err)),
+                        )
+                    })?;
+                continue;
+            }
             let threshold = match self
                 .membership
                 .get_committee_threshold(height, Committee::CanisterHttp)
@@ -671,6 +728,8 @@ impl IntoMessages<(Vec<ConsensusResponse>, CanisterHttpBatchStats)>
 
         let timeouts = messages.timeouts.iter().map(|callback| {
             // Map timeouts to a rejected response
+            // NOTE: The server may still have executed the request, which matters for
+            // non-idempotent methods such as POST and PATCH.
             stats.timeouts += 1;
             ConsensusResponse::new(
                 *callback,
@@ -705,6 +764,9 @@ impl IntoMessages<(Vec<ConsensusResponse>, CanisterHttpBatchStats)>
 /// issue with the transform function (e.g. some non-deterministic component such as timestamp has not been removed).
 ///
 /// The function includes request id and timeout, which are also part of the hashed value.
+///
+/// NOTE: Divergence means that the replicas did make the request, so a request with a
+/// non-idempotent method (e.g. POST or PATCH) may have been executed multiple times.
 fn divergence_response_into_reject(
     response: &CanisterHttpResponseDivergence,
 ) -> Option<ConsensusResponse> {
@@ -36,7 +36,7 @@ use ic_types::{
     canister_http::{
         CanisterHttpMethod, CanisterHttpRequestContext, CanisterHttpResponse,
         CanisterHttpResponseContent, CanisterHttpResponseDivergence, CanisterHttpResponseMetadata,
-        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus,
+        CanisterHttpResponseShare, CanisterHttpResponseWithConsensus, Replication,
         CANISTER_HTTP_MAX_RESPONSES_PER_BLOCK, CANISTER_HTTP_TIMEOUT_INTERVAL,
     },
     consensus::get_faults_tolerated,
@@ -339,6 +339,8 @@ fn timeout_priority() {
                     transform: None,
                     // this is the important one
                     time: UNIX_EPOCH,
+                    replication: Replication::FullyReplicated,
+                    response_offset: None,
                 };
                 init_state
                     .metadata
@@ -774,6 +776,114 @@ fn divergence_response_validation_test() {
     }
 }
 
+/// Check that the response to a non-replicated request is included with only the share of the
+/// designated node, and that a response signed by another node is rejected
+#[test]
+fn non_replicated_request_test() {
+    let context = default_validation_context();
+    let mut init_state = ic_test_utilities_state::get_initial_state(0, 0);
+    init_state
+        .metadata
+        .subnet_call_context_manager
+        .canister_http_request_contexts
+        .insert(
+            CallbackId::new(0),
+            CanisterHttpRequestContext {
+                request: RequestBuilder::default().build(),
+                url: String::new(),
+                max_response_bytes: None,
+                headers: vec![],
+                body: None,
+                http_method: CanisterHttpMethod::GET,
+                transform: None,
+                time: UNIX_EPOCH,
+                replication: Replication::NonReplicated(node_test_id(1)),
+                response_offset: None,
+            },
+        );
+
+    test_config_with_http_feature(true, 4, |mut payload_builder, canister_http_pool| {
+        let state_manager = Arc::new(RefMockStateManager::default());
+        state_manager
+            .get_mut()
+            .expect_get_state_at()
+            .return_const(Ok(ic_interfaces_state_manager::Labeled::new(
+                Height::new(0),
+                Arc::new(init_state),
+            )));
+        payload_builder.state_reader = state_manager;
+
+        let (response, metadata) = test_response_and_metadata(0);
+        {
+            // Only the designated node makes the request
+            let mut pool_access = canister_http_pool.write().unwrap();
+            add_own_share_to_pool(
+                pool_access.deref_mut(),
+                &metadata_to_share(1, &metadata),
+                &response,
+            );
+        }
+
+        let payload = payload_builder.build_payload(
+            Height::new(1),
+            NumBytes::new(4 * 1024 * 1024),
+            &[],
+            &context,
+        );
+
+        let parsed_payload = bytes_to_payload(&payload).expect("Failed to parse the payload");
+        assert_eq!(parsed_payload.num_responses(), 1);
+        assert_eq!(parsed_payload.responses[0].content, response);
+        assert_eq!(
+            parsed_payload.responses[0]
+                .proof
+                .signature
+                .signatures_map
+                .keys()
+                .collect::<Vec<_>>(),
+            vec![&node_test_id(1)]
+        );
+        assert!(payload_builder
+            .validate_payload(
+                Height::new(1),
+                &test_proposal_context(&context),
+                &payload,
+                &[],
+            )
+            .is_ok());
+
+        // A response signed by a node other than the designated one is invalid
+        let mut proof = response_and_metadata_to_proof(&response, &metadata);
+        proof
+            .proof
+            .signature
+            .signatures_map
+            .insert(node_test_id(2), BasicSigOf::new(BasicSig(vec![])));
+        let payload = CanisterHttpPayload {
+            responses: vec![proof],
+            timeouts: vec![],
+            divergence_responses: vec![],
+        };
+        let payload = payload_to_bytes(&payload, NumBytes::new(4 * 1024 * 1024));
+
+        match payload_builder.validate_payload(
+            Height::new(1),
+            &test_proposal_context(&context),
+            &payload,
+            &[],
+        ) {
+            Err(ValidationError::InvalidArtifact(
+                InvalidPayloadReason::InvalidCanisterHttpPayload(
+                    InvalidCanisterHttpPayloadReason::NonReplicatedSignerMismatch {
+                        expected, ..
+                    },
+                ),
+            )) => assert_eq!(expected, node_test_id(1)),
+            x => panic!("Expected NonReplicatedSignerMismatch, got {:?}", x),
+        }
+    });
+}
+
 /// Check that the divergence error message is constructed correctly and readable
 #[test]
 fn divergence_error_message() {
@@ -151,7 +151,14 @@ impl CanisterHttpPoolManagerImpl {
     }
 
     /// Inform the HttpAdapterShim of any new requests that must be made.
-    fn make_new_requests(&self, canister_http_pool: &dyn CanisterHttpPool) {
+    ///
+    /// The destination policy is read at the registry version of the finalized
+    /// height, so that all nodes of the subnet enforce the same policy.
+    fn make_new_requests(
+        &self,
+        canister_http_pool: &dyn CanisterHttpPool,
+        finalized_height: Height,
+    ) {
         let _time = self
             .metrics
             .op_duration
@@ -187,7 +194,39 @@ impl CanisterHttpPoolManagerImpl {
             .cloned()
             .collect();
 
+        let registry_version = if let Some(registry_version) =
+            registry_version_at_height(self.consensus_pool_cache.as_ref(), finalized_height)
+        {
+            registry_version
+        } else {
+            error!(
+                self.log,
+                "Unable to obtain registry version for reading the http outcalls policy",
+            );
+            return;
+        };
+
+        let policy = match self
+            .registry_client
+            .get_http_outcalls_policy(self.replica_config.subnet_id, registry_version)
+        {
+            Ok(policy) => policy.map(CanisterHttpPolicy::from).unwrap_or_default(),
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "Failed to get the http outcalls policy from the registry {:?}", err
+                );
+                return;
+            }
+        };
+
         for (id, context) in http_requests {
+            // Non-replicated requests are only made by the designated node.
+            if let Replication::NonReplicated(node_id) = context.replication {
+                if node_id != self.replica_config.node_id {
+                    continue;
+                }
+            }
             if !request_ids_already_made.contains(&id) {
                 let timeout = context.time + Duration::from_secs(5 * 60);
                 if let Err(err) = self
@@ -198,6 +237,7 @@ impl CanisterHttpPoolManagerImpl {
                         id,
                         timeout,
                         context,
+                        policy: policy.clone(),
                     })
                 {
                     warn!(
@@ -291,6 +331,15 @@ impl CanisterHttpPoolManagerImpl {
             return Vec::new();
         };
 
+        let http_requests = self
+            .state_reader
+            .get_latest_state()
+            .get_ref()
+            .metadata
+            .subnet_call_context_manager
+            .canister_http_request_contexts
+            .clone();
+
         canister_http_pool
             .get_unvalidated_shares()
             .filter_map(|share| {
@@ -317,6 +366,19 @@ impl CanisterHttpPoolManagerImpl {
                             .to_string(),
                     ));
                 }
+                if let Some(Replication::NonReplicated(node_id)) = http_requests
+                    .get(&share.content.id)
+                    .map(|context| &context.replication)
+                {
+                    if *node_id != share.signature.signer {
+                        self.metrics.shares_marked_invalid.inc();
+                        return Some(CanisterHttpChangeAction::HandleInvalid(
+                            share.clone(),
+                            "Share of a non-replicated request signed by a node other than the designated one"
+                                .to_string(),
+                        ));
+                    }
+                }
                 // TODO: more precise error handling
                 if let Err(err) = self.crypto.verify(share, registry_version) {
                     error!(self.log, "Unable to verify signature of share, {}", err);
@@ -352,7 +414,7 @@ impl CanisterHttpPoolManagerImpl {
             .unwrap_or(false)
         {
             // Make any requests that need to be made
-            self.make_new_requests(canister_http_pool);
+            self.make_new_requests(canister_http_pool, finalized_height);
 
             // Create shares from any responses that are now available
             change_set.extend(self.create_shares_from_responses(finalized_height));
@@ -380,10 +442,15 @@ impl<T: CanisterHttpPool> ChangeSetProducer<T> for CanisterHttpPoolManagerImpl {
     type ChangeSet = CanisterHttpChangeSet;
 
     fn on_state_change(&self, canister_http_pool: &T) -> CanisterHttpChangeSet {
-        if let Ok(subnet_features) = self.registry_client.get_features(
-            self.replica_config.subnet_id,
-            self.registry_client.get_latest_version(),
-        ) {
+        let registry_version = self
+            .consensus_pool_cache
+            .finalized_block()
+            .context
+            .registry_version;
+        if let Ok(subnet_features) = self
+            .registry_client
+            .get_features(self.replica_config.subnet_id, registry_version)
+        {
             if subnet_features.unwrap_or_default().http_requests {
                 return self.generate_change_set(canister_http_pool);
             }
@@ -396,15 +463,17 @@ impl<T: CanisterHttpPool> ChangeSetProducer<T> for CanisterHttpPoolManagerImpl {
 pub mod test {
     use super::*;
     use ic_artifact_pool::canister_http_pool::CanisterHttpPoolImpl;
-    use ic_consensus_mocks::{dependencies, Dependencies};
+    use ic_consensus_mocks::{dependencies, dependencies_with_subnet_params, Dependencies};
     use ic_consensus_utils::crypto::SignVerify;
     use ic_interfaces::p2p::consensus::MutablePool;
     use ic_interfaces_state_manager::Labeled;
     use ic_logger::replica_logger::no_op_logger;
     use ic_metrics::MetricsRegistry;
+    use ic_protobuf::registry::subnet::v1::HttpOutcallsPolicy;
     use ic_registry_subnet_type::SubnetType;
     use ic_test_utilities_logger::with_test_replica_logger;
-    use ic_test_utilities_types::ids::subnet_test_id;
+    use ic_test_utilities_registry::{add_subnet_record, SubnetRecordBuilder};
+    use ic_test_utilities_types::ids::{node_test_id, subnet_test_id};
     use ic_types::{
         crypto::{CryptoHash, CryptoHashOf},
         messages::CallbackId,
@@ -476,6 +545,8 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::FullyReplicated,
+                    response_offset: None,
                 };
 
                 state_manager
@@ -620,6 +691,8 @@ pub mod test {
                     http_method: CanisterHttpMethod::GET,
                     transform: None,
                     time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::FullyReplicated,
+                    response_offset: None,
                 };
 
                 // Expect times to be called exactly once to check that already
@@ -631,6 +704,7 @@ pub mod test {
                         timeout: ic_types::Time::from_nanos_since_unix_epoch(10)
                             + Duration::from_secs(60 * 5),
                         context: request.clone(),
+                        policy: CanisterHttpPolicy::default(),
                     }))
                     .times(1)
                     .return_const(Ok(()));
@@ -695,4 +769,96 @@ pub mod test {
             });
         });
     }
+
+    #[test]
+    pub fn test_requests_use_policy_at_finalized_registry_version() {
+        ic_test_utilities::artifact_pool_config::with_test_pool_config(|pool_config| {
+            with_test_replica_logger(|log| {
+                let committee = (0..4).map(node_test_id).collect::<Vec<_>>();
+                let record_with_policy = |allowed_domain: &str| {
+                    let mut record = SubnetRecordBuilder::from(&committee).build();
+                    record.http_outcalls_policy = Some(HttpOutcallsPolicy {
+                        allowed_domains: vec![allowed_domain.to_string()],
+                        ..Default::default()
+                    });
+                    record
+                };
+                let Dependencies {
+                    pool,
+                    replica_config,
+                    crypto,
+                    state_manager,
+                    registry,
+                    registry_data_provider,
+                    ..
+                } = dependencies_with_subnet_params(
+                    pool_config,
+                    subnet_test_id(0),
+                    vec![(1, record_with_policy("old.example.com"))],
+                );
+                // The finalized block still refers to registry version 1, while
+                // the registry client already knows about version 2.
+                add_subnet_record(
+                    &registry_data_provider,
+                    2,
+                    subnet_test_id(0),
+                    record_with_policy("new.example.com"),
+                );
+                registry.update_to_latest_version();
+                assert_eq!(registry.get_latest_version(), RegistryVersion::from(2));
+
+                let request = CanisterHttpRequestContext {
+                    request: ic_test_utilities_types::messages::RequestBuilder::new().build(),
+                    url: "".to_string(),
+                    max_response_bytes: None,
+                    headers: vec![],
+                    body: None,
+                    http_method: CanisterHttpMethod::GET,
+                    transform: None,
+                    time: ic_types::Time::from_nanos_since_unix_epoch(10),
+                    replication: Replication::FullyReplicated,
+                    response_offset: None,
+                };
+
+                let mut shim_mock = MockNonBlockingChannel::<CanisterHttpRequest>::new();
+                shim_mock
+                    .expect_try_receive()
+                    .return_const(Err(TryReceiveError::Empty));
+                shim_mock
+                    .expect_send()
+                    .withf(|request: &CanisterHttpRequest| {
+                        request.policy.allowed_domains == vec!["old.example.com".to_string()]
+                    })
+                    .times(1)
+                    .return_const(Ok(()));
+                let shim: Arc<Mutex<CanisterHttpAdapterClient>> =
+                    Arc::new(Mutex::new(Box::new(shim_mock)));
+
+                state_manager
+                    .get_mut()
+                    .expect_get_latest_state()
+                    .return_const(Labeled::new(
+                        Height::from(1),
+                        Arc::new(state_with_pending_http_calls(BTreeMap::from([(
+                            CallbackId::from(7),
+                            request,
+                        )]))),
+                    ));
+
+                let pool_manager = CanisterHttpPoolManagerImpl::new(
+                    state_manager,
+                    shim,
+                    crypto,
+                    pool.get_cache(),
+                    replica_config,
+                    Arc::clone(&registry) as Arc<_>,
+                    MetricsRegistry::new(),
+                    log,
+                );
+                let canister_http_pool =
+                    CanisterHttpPoolImpl::new(MetricsRegistry::new(), no_op_logger());
+                pool_manager.generate_change_set(&canister_http_pool);
+            });
+        });
+    }
 }
@@ -125,13 +125,28 @@ impl IngressSelector for IngressManager {
         }
         let canister_count = canister_queues.len();
 
+        // The tip the receiving canister pays for a message. This is zero unless the
+        // canister opted into paying tips.
+        let tip = |artifact: &ValidatedIngressArtifact| {
+            state
+                .canister_state(&artifact.msg.signed_ingress.canister_id())
+                .map_or(Cycles::zero(), |canister| {
+                    self.cycles_account_manager
+                        .ingress_tip(artifact.msg.signed_ingress.content(), canister)
+                })
+        };
+
         // At this point messages are sorted by expiry time. In order to prevent malicious
         // users from putting their messages ahead of others by carefully crafting the expiry
         // times, we sort the ingress messages by the time they were delivered to the pool.
+        // Messages with a higher tip go first, as the canister pays for that.
         // NOTE: We sort in reverse order, because messages are pop()-ed from the back.
         for v in canister_queues.values_mut() {
-            v.msgs.sort_unstable_by_key(|artifact| {
-                std::cmp::Reverse(artifact.timestamp.as_nanos_since_unix_epoch())
+            v.msgs.sort_by_cached_key(|artifact| {
+                (
+                    tip(artifact),
+                    std::cmp::Reverse(artifact.timestamp.as_nanos_since_unix_epoch()),
+                )
             });
         }
         /* --------------------------------------------------------------------------- */
@@ -154,6 +169,19 @@ impl IngressSelector for IngressManager {
         let mut round_robin_iter: u32 = 0;
         'outer: while !canister_queues.is_empty() {
             round_robin_iter += 1;
+            // Visit canisters whose next message has a higher tip first, so that tipped
+            // messages make it into the payload under load. The per-canister quota still
+            // applies, so tips cannot be used to take more than a fair share of the
+            // payload. The sort is stable, i.e. canisters with equal tips keep their
+            // random order.
+            canisters.sort_by_cached_key(|canister_id| {
+                std::cmp::Reverse(
+                    canister_queues[canister_id]
+                        .msgs
+                        .last()
+                        .map(|artifact| tip(artifact)),
+                )
+            });
             // Execute a single round-robin iteration, by looping through the canisters
             // and selecting messages up bound by per-canister quota and payload size.
             let mut i = 0;
@@ -512,6 +540,8 @@ impl IngressManager {
                 cost: ingress_cost,
             } => match state.canister_state(&payer) {
                 Some(canister) => {
+                    let ingress_cost =
+                        ingress_cost + self.cycles_account_manager.ingress_tip(msg, canister);
                     let cumulative_ingress_cost =
                         cycles_needed.entry(payer).or_insert_with(Cycles::zero);
                     if let Err(err) = self.cycles_account_manager.can_withdraw_cycles(
@@ -654,6 +684,7 @@ mod tests {
     };
     use assert_matches::assert_matches;
     use ic_artifact_pool::ingress_pool::IngressPoolImpl;
+    use ic_cycles_account_manager::INGRESS_TIPS_SECTION;
     use ic_interfaces::{
         execution_environment::IngressHistoryError,
         ingress_pool::ChangeAction,
@@ -665,7 +696,10 @@ mod tests {
     use ic_interfaces_state_manager_mocks::MockStateManager;
     use ic_management_canister_types::{CanisterIdRecord, Payload, IC_00};
     use ic_metrics::MetricsRegistry;
-    use ic_replicated_state::CanisterState;
+    use ic_replicated_state::{
+        canister_state::execution_state::{CustomSection, CustomSectionType, WasmMetadata},
+        CanisterState, ExecutionState, ExportedFunctions,
+    };
     use ic_test_utilities::{
         artifact_pool_config::with_test_pool_config,
         crypto::temp_crypto_component_with_fake_registry,
@@ -673,7 +707,7 @@ mod tests {
     };
     use ic_test_utilities_logger::with_test_replica_logger;
     use ic_test_utilities_state::{
-        CanisterStateBuilder, MockIngressHistory, ReplicatedStateBuilder,
+        CanisterStateBuilder, ExecutionStateBuilder, MockIngressHistory, ReplicatedStateBuilder,
     };
     use ic_test_utilities_time::FastForwardTimeSource;
     use ic_test_utilities_types::{
@@ -686,12 +720,17 @@ mod tests {
         ingress::{IngressState, IngressStatus},
         malicious_flags::MaliciousFlags,
         messages::{MessageId, SignedIngress},
+        methods::{SystemMethod, WasmMethod},
         time::{expiry_time_from_now, UNIX_EPOCH},
         Height, RegistryVersion,
     };
     use rand::RngCore;
     use std::sync::RwLock;
-    use std::{collections::HashSet, convert::TryInto, time::Duration};
+    use std::{
+        collections::{BTreeSet, HashSet},
+        convert::TryInto,
+        time::Duration,
+    };
 
     const MAX_SIZE: usize = 1000;
     const MAX_SIZE_AS_NUM_BYTES: NumBytes = NumBytes::new(MAX_SIZE as u64);
@@ -2263,6 +2302,93 @@ mod tests {
             },
         )
     }
+    fn opted_into_tips_execution_state() -> ExecutionState {
+        let mut execution_state = ExecutionStateBuilder::new()
+            .with_wasm_metadata(WasmMetadata::new(BTreeMap::from([(
+                INGRESS_TIPS_SECTION.to_string(),
+                CustomSection::new(CustomSectionType::Private, vec![]),
+            )])))
+            .build();
+        execution_state.exports = ExportedFunctions::new(BTreeSet::from([WasmMethod::System(
+            SystemMethod::CanisterInspectMessage,
+        )]));
+        execution_state
+    }
+
+    #[tokio::test]
+    async fn test_tipped_messages_are_selected_first() {
+        const CANISTER_COUNT: u64 = 10;
+        let subnet_id = subnet_test_id(0);
+        let time = UNIX_EPOCH;
+        let expiry = time + Duration::from_secs(40);
+
+        let build_msg = |canister_id: CanisterId, tip: Option<u64>| {
+            let builder = SignedIngressBuilder::new()
+                .canister_id(canister_id)
+                .expiry_time(expiry)
+                .nonce(rand::thread_rng().next_u64())
+                .method_payload(vec![0xff; 200]);
+            match tip {
+                Some(tip) => builder.tip(tip).build(),
+                None => builder.build(),
+            }
+        };
+
+        // Every canister has an untipped message. Canister 7, which opted into paying
+        // tips, also has a tipped message that arrived later than its untipped one.
+        let untipped: Vec<_> = (0..CANISTER_COUNT)
+            .map(|i| build_msg(canister_test_id(i), None))
+            .collect();
+        let tipped = build_msg(canister_test_id(7), Some(1_000_000));
+
+        // Only a single message fits into the payload.
+        let max_size = tipped.count_bytes() + 100;
+        let registry = setup_registry(subnet_id, max_size);
+
+        let mut replicated_state = ReplicatedStateBuilder::new().with_subnet_id(subnet_id);
+        for i in 0..CANISTER_COUNT {
+            let mut canister = CanisterStateBuilder::default()
+                .with_canister_id(canister_test_id(i))
+                .with_cycles(Cycles::new(500_000_000_000))
+                .build();
+            if i == 7 {
+                canister.execution_state = Some(opted_into_tips_execution_state());
+            }
+            replicated_state = replicated_state.with_canister(canister);
+        }
+
+        setup_with_params(
+            None,
+            Some((registry, subnet_id)),
+            None,
+            Some(replicated_state.build()),
+            |ingress_manager, ingress_pool| {
+                let validation_context = ValidationContext {
+                    time,
+                    registry_version: RegistryVersion::from(1),
+                    certified_height: Height::from(0),
+                };
+                insert_unvalidated_ingress_with_timestamp(
+                    untipped,
+                    &ingress_pool,
+                    time + Duration::from_secs(1),
+                );
+                insert_unvalidated_ingress_with_timestamp(
+                    vec![tipped.clone()],
+                    &ingress_pool,
+                    time + Duration::from_secs(2),
+                );
+                let payload = ingress_manager.get_ingress_payload(
+                    &HashSet::new(),
+                    &validation_context,
+                    NumBytes::new(max_size as u64),
+                );
+                let msgs: Vec<SignedIngress> = payload.try_into().unwrap();
+                assert_eq!(msgs, vec![tipped]);
+            },
+        )
+    }
+
     #[tokio::test]
     async fn test_not_stuck() {
         const MSG_SIZE: usize = 154;
@@ -64,6 +64,12 @@ pub enum InvalidCanisterHttpPayloadReason {
         signers: Vec<NodeId>,
         expected_threshold: Threshold,
     },
+    /// The proof of a non-replicated response was not signed by exactly the
+    /// node designated to make the request
+    NonReplicatedSignerMismatch {
+        expected: NodeId,
+        signers: Vec<NodeId>,
+    },
     /// The payload contains a duplicate response
     DuplicateResponse(CallbackId),
     DivergenceProofContainsMultipleCallbackIds,
@@ -257,6 +257,17 @@ pub trait PoolSection<T> {
         )
     }
 
+    /// Return the CatchUpPackage at the given height in the protobuf form it
+    /// was stored in, if there is one.
+    fn catch_up_package_proto_at(&self, height: Height) -> Option<pb::CatchUpPackage> {
+        // NOTE: As above, this default implementation re-encodes the CUP and is
+        // overridden by the persistent pool sections.
+        self.catch_up_package()
+            .get_by_height(height)
+            .next()
+            .map(|cup| pb::CatchUpPackage::from(&cup))
+    }
+
     fn size(&self) -> u64;
 }
 
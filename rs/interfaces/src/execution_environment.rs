@@ -1,7 +1,9 @@
 //! The execution environment public interface.
 mod errors;
 
-pub use errors::{CanisterOutOfCyclesError, HypervisorError, TrapCode};
+pub use errors::{
+    BacktraceFrame, CanisterBacktrace, CanisterOutOfCyclesError, HypervisorError, TrapCode,
+};
 use ic_base_types::NumBytes;
 use ic_error_types::UserError;
 use ic_management_canister_types::MasterPublicKeyId;
@@ -211,6 +213,8 @@ pub enum SystemApiCallId {
     MsgReply,
     /// Tracker for `ic0.msg_reply_data_append()`
     MsgReplyDataAppend,
+    /// Tracker for `ic0.msg_tip()`
+    MsgTip,
     /// Tracker for `__.out_of_instructions()`
     OutOfInstructions,
     /// Tracker for `ic0.performance_counter()`
@@ -233,6 +237,10 @@ pub enum SystemApiCallId {
     StableWrite,
     /// Tracker for `ic0.time()`
     Time,
+    /// Tracker for `ic0.trace_id_copy()`
+    TraceIdCopy,
+    /// Tracker for `ic0.trace_id_size()`
+    TraceIdSize,
     /// Tracker for `ic0.trap()`
     Trap,
     /// Tracker for `__.try_grow_wasm_memory()`
@@ -653,6 +661,11 @@ pub trait SystemApi {
     // messages.
     fn ic0_accept_message(&mut self) -> HypervisorResult<()>;
 
+    /// Returns the tip in cycles that the canister pays if it accepts the
+    /// ingress message; or 0 if the canister did not opt into paying tips.
+    /// Can only be called in the context of accepting messages.
+    fn ic0_msg_tip(&self) -> HypervisorResult<u64>;
+
     /// Copies the data referred to by src/size out of the canister and appends
     /// it to the (initially empty) data reply.
     fn ic0_msg_reply_data_append(
@@ -735,9 +748,9 @@ pub trait SystemApi {
         name_src: usize,
         name_len: usize,
         reply_fun: u32,
-        reply_env: u32,
+        reply_env: u64,
         reject_fun: u32,
-        reject_env: u32,
+        reject_env: u64,
         heap: &[u8],
     ) -> HypervisorResult<()>;
 
@@ -774,7 +787,7 @@ pub trait SystemApi {
     /// `ic0.call_perform`.
     ///
     /// See <https://internetcomputer.org/docs/current/references/ic-interface-spec#system-api-call>
-    fn ic0_call_on_cleanup(&mut self, fun: u32, env: u32) -> HypervisorResult<()>;
+    fn ic0_call_on_cleanup(&mut self, fun: u32, env: u64) -> HypervisorResult<()>;
 
     /// (deprecated) Please use `ic0_call_cycles_add128` instead, as this API
     /// can only add a 64-bit value.
@@ -1151,6 +1164,20 @@ pub trait SystemApi {
         dst: usize,
         heap: &mut [u8],
     ) -> HypervisorResult<()>;
+
+    /// Returns the size of the trace ID of the call tree that the current
+    /// message is part of; or 0 if the call tree is not traced.
+    fn ic0_trace_id_size(&self) -> HypervisorResult<usize>;
+
+    /// Copies `size` bytes starting from `offset` in the trace ID of the call
+    /// tree that the current message is part of to heap[dst..dst+size].
+    fn ic0_trace_id_copy(
+        &self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()>;
 }
 
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
@@ -41,6 +41,46 @@ impl std::fmt::Display for TrapCode {
     }
 }
 
+/// A frame of a [`CanisterBacktrace`].
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct BacktraceFrame {
+    /// The index of the function in the canister's Wasm module.
+    pub func_index: u32,
+    /// The symbolized name of the function if the module provides one.
+    pub func_name: Option<String>,
+}
+
+impl std::fmt::Display for BacktraceFrame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.func_name {
+            Some(name) => write!(f, "{}", name),
+            None => write!(f, "<wasm function {}>", self.func_index),
+        }
+    }
+}
+
+/// The Wasm stack of a canister at the point where it trapped, innermost
+/// frame first. Only the innermost frames are kept to bound its size.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CanisterBacktrace {
+    pub frames: Vec<BacktraceFrame>,
+    /// The number of outer frames that were dropped.
+    pub omitted_frames: usize,
+}
+
+impl std::fmt::Display for CanisterBacktrace {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Canister Backtrace:")?;
+        for (i, frame) in self.frames.iter().enumerate() {
+            write!(f, "\n{}: {}", i, frame)?;
+        }
+        if self.omitted_frames > 0 {
+            write!(f, "\n... {} more frames", self.omitted_frames)?;
+        }
+        Ok(())
+    }
+}
+
 /// Error when a canister's balance is too low compared to its freezing
 /// threshold and cannot perform the requested action.
 ///
@@ -100,9 +140,15 @@ pub enum HypervisorError {
     InstrumentationFailed(WasmInstrumentationError),
     /// Canister Wasm trapped (e.g. by executing the `unreachable`
     /// instruction or dividing by zero).
-    Trapped(TrapCode),
+    Trapped {
+        trap_code: TrapCode,
+        backtrace: Option<CanisterBacktrace>,
+    },
     /// Canister explicitly called `ic.trap`.
-    CalledTrap(String),
+    CalledTrap {
+        message: String,
+        backtrace: Option<CanisterBacktrace>,
+    },
     /// An attempt was made to execute a message on a canister that does not
     /// contain a Wasm module.
     WasmModuleNotFound,
@@ -217,9 +263,22 @@ impl std::fmt::Display for HypervisorError {
             Self::InstrumentationFailed(err) => {
                 write!(f, "Could not instrument wasm module of canister: {}", err)
             }
-            Self::Trapped(code) => write!(f, "Canister trapped: {}", code),
-            Self::CalledTrap(msg) => {
-                write!(f, "Canister called `ic0.trap` with message: {}", msg)
+            Self::Trapped {
+                trap_code,
+                backtrace,
+            } => {
+                write!(f, "Canister trapped: {}", trap_code)?;
+                if let Some(backtrace) = backtrace {
+                    write!(f, "\n{}", backtrace)?;
+                }
+                Ok(())
+            }
+            Self::CalledTrap { message, backtrace } => {
+                write!(f, "Canister called `ic0.trap` with message: {}", message)?;
+                if let Some(backtrace) = backtrace {
+                    write!(f, "\n{}", backtrace)?;
+                }
+                Ok(())
             }
             Self::WasmModuleNotFound => write!(
                 f,
@@ -355,8 +414,8 @@ impl AsErrorHelp for HypervisorError {
                 doc_link: "http://internetcomputer.org/docs/current/references/execution-errors#method-not-found".to_string(),
             },
             Self::InstructionLimitExceeded(_)
-            | Self::Trapped(_)
-            | Self::CalledTrap(_)
+            | Self::Trapped { .. }
+            | Self::CalledTrap { .. }
             | Self::WasmModuleNotFound
             | Self::OutOfMemory
             | Self::InvalidPrincipalId(_)
@@ -405,8 +464,8 @@ impl HypervisorError {
             Self::InstructionLimitExceeded(_) => E::CanisterInstructionLimitExceeded,
             Self::InvalidWasm(_) => E::CanisterInvalidWasm,
             Self::InstrumentationFailed(_) => E::CanisterInvalidWasm,
-            Self::Trapped(_) => E::CanisterTrapped,
-            Self::CalledTrap(_) => E::CanisterCalledTrap,
+            Self::Trapped { .. } => E::CanisterTrapped,
+            Self::CalledTrap { .. } => E::CanisterCalledTrap,
             Self::WasmModuleNotFound => E::CanisterWasmModuleNotFound,
             Self::OutOfMemory => E::CanisterOutOfMemory,
             Self::InvalidPrincipalId(_) => E::CanisterContractViolation,
@@ -435,6 +494,28 @@ impl HypervisorError {
         UserError::new(code, description)
     }
 
+    /// Returns the backtrace of a trap, if any.
+    pub fn backtrace(&self) -> Option<&CanisterBacktrace> {
+        match self {
+            Self::Trapped { backtrace, .. } | Self::CalledTrap { backtrace, .. } => {
+                backtrace.as_ref()
+            }
+            _ => None,
+        }
+    }
+
+    /// Replaces the backtrace of a trap. Other errors are returned unchanged.
+    pub fn with_backtrace(self, backtrace: Option<CanisterBacktrace>) -> Self {
+        match self {
+            Self::Trapped { trap_code, .. } => Self::Trapped {
+                trap_code,
+                backtrace,
+            },
+            Self::CalledTrap { message, .. } => Self::CalledTrap { message, backtrace },
+            err => err,
+        }
+    }
+
     /// Returns a string slice representation of the enum variant name for use
     /// e.g. as a metric label.
     pub fn as_str(&self) -> &'static str {
@@ -446,8 +527,8 @@ impl HypervisorError {
             HypervisorError::InstructionLimitExceeded(_) => "InstructionLimitExceeded",
             HypervisorError::InvalidWasm(_) => "InvalidWasm",
             HypervisorError::InstrumentationFailed(_) => "InstrumentationFailed",
-            HypervisorError::Trapped(_) => "Trapped",
-            HypervisorError::CalledTrap(_) => "CalledTrap",
+            HypervisorError::Trapped { .. } => "Trapped",
+            HypervisorError::CalledTrap { .. } => "CalledTrap",
             HypervisorError::WasmModuleNotFound => "WasmModuleNotFound",
             HypervisorError::OutOfMemory => "OutOfMemory",
             HypervisorError::InvalidPrincipalId(_) => "InvalidPrincipalId",
@@ -310,6 +310,57 @@ impl SigsegvMemoryTracker {
         self.dirty_bitmap.borrow_mut().grow(delta);
     }
 
+    /// Discards all changes made to the memory since the tracker was created
+    /// and forgets all accesses, so that the memory again has the contents of
+    /// the page map and can be tracked for a new execution.
+    pub fn reset(&self) -> nix::Result<()> {
+        let size = self.memory_area.size();
+        if size > 0 {
+            // Replacing the mapping drops all pages touched so far.
+            unsafe {
+                mmap(
+                    self.memory_area.addr() as *mut libc::c_void,
+                    size,
+                    ProtFlags::PROT_NONE,
+                    MapFlags::MAP_PRIVATE | MapFlags::MAP_FIXED | MapFlags::MAP_ANON,
+                    -1,
+                    0,
+                )?
+            };
+        }
+
+        let num_pages = size / PAGE_SIZE;
+        *self.accessed_bitmap.borrow_mut() = PageBitmap::new(num_pages);
+        *self.dirty_bitmap.borrow_mut() = PageBitmap::new(num_pages);
+        self.dirty_pages.borrow_mut().clear();
+        self.speculatively_dirty_pages.borrow_mut().clear();
+        #[cfg(feature = "sigsegv_handler_checksum")]
+        self.checksum.replace(checksum::SigsegChecksum::default());
+        self.read_before_write_stats
+            .read_before_write_count
+            .store(0, Ordering::Relaxed);
+        self.read_before_write_stats
+            .direct_write_count
+            .store(0, Ordering::Relaxed);
+        self.sigsegv_count.store(0, Ordering::Relaxed);
+        self.memory_instructions_stats
+            .mmap_count
+            .store(0, Ordering::Relaxed);
+        self.memory_instructions_stats
+            .mprotect_count
+            .store(0, Ordering::Relaxed);
+        self.memory_instructions_stats
+            .copy_page_count
+            .store(0, Ordering::Relaxed);
+
+        if self.use_new_signal_handler {
+            let mut instructions = self.page_map.get_base_memory_instructions();
+            instructions.restrict_to_range(&self.page_range());
+            apply_memory_instructions(self, ProtFlags::PROT_NONE, instructions);
+        }
+        Ok(())
+    }
+
     pub fn take_dirty_pages(&self) -> Vec<PageIndex> {
         self.dirty_pages.take()
     }
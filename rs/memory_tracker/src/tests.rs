@@ -627,6 +627,38 @@ fn prefetch_for_write_after_read_unordered() {
     );
 }
 
+#[test]
+fn reset_discards_writes_and_accesses() {
+    let (tracker, _page_map, memory, vec) = setup(
+        50,
+        100,
+        (25..75).map(PageIndex::new).collect(),
+        DirtyPageTracking::Track,
+    );
+    let pages = [10, 50, 80];
+    for page in pages {
+        let page_index = PageIndex::new(page);
+        sigsegv(&tracker, page_index, AccessKind::Read);
+        sigsegv(&tracker, page_index, AccessKind::Write);
+        unsafe { (memory as *mut u8).add(page as usize * PAGE_SIZE).write(255) };
+    }
+    assert_eq!(tracker.take_dirty_pages().len(), pages.len());
+
+    tracker.reset().unwrap();
+    assert_eq!(tracker.num_accessed_pages(), 0);
+    assert_eq!(tracker.sigsegv_count(), 0);
+    assert!(tracker.take_dirty_pages().is_empty());
+    assert!(tracker.take_speculatively_dirty_pages().is_empty());
+
+    // The memory has the contents of the page map again.
+    for page in pages {
+        sigsegv(&tracker, PageIndex::new(page), AccessKind::Read);
+        let offset = page as usize * PAGE_SIZE;
+        let byte = unsafe { (memory as *const u8).add(offset).read() };
+        assert_eq!(byte, vec[offset]);
+    }
+}
+
 #[test]
 fn page_bitmap_restrict_to_unaccessed() {
     let mut bitmap = PageBitmap::new(10);
@@ -5,6 +5,7 @@ use ic_logger::{error, warn, ReplicaLogger};
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_registry_subnet_type::SubnetType;
 use ic_replicated_state::{
+    call_graph::{CallGraphMetrics, SOURCE_STREAM_BUILDER},
     replicated_state::{
         PeekableOutputIterator, ReplicatedStateMessageRouting, MR_SYNTHETIC_REJECT_MESSAGE_MAX_LEN,
     },
@@ -12,10 +13,10 @@ use ic_replicated_state::{
 };
 use ic_types::{
     messages::{
-        Payload, RejectContext, Request, RequestOrResponse, Response,
+        Payload, RejectContext, Request, RequestOrResponse, Response, TraceId,
         MAX_INTER_CANISTER_PAYLOAD_IN_BYTES, MAX_REJECT_MESSAGE_LEN_BYTES,
     },
-    CountBytes, SubnetId,
+    CanisterId, CountBytes, SubnetId,
 };
 #[cfg(test)]
 use mockall::automock;
@@ -39,6 +40,15 @@ struct StreamBuilderMetrics {
     pub routed_messages: IntCounterVec,
     /// Successfully routed XNet messages' total payload size.
     pub routed_payload_sizes: Histogram,
+    /// Largest byte size of the messages enqueued by a single canister, by remote subnet.
+    pub max_canister_backlog_bytes: IntGaugeVec,
+    /// Byte size of the messages enqueued in remote streams, per sending canister.
+    pub canister_backlog_bytes: Histogram,
+    /// Number of times a canister was moved to the back of the round robin after
+    /// exhausting its byte budget.
+    pub canister_deferrals: IntCounter,
+    /// Calls between canisters, as routed into streams.
+    pub call_graph: CallGraphMetrics,
     /// Critical error counter for detected infinite loops while routing.
     pub critical_error_infinite_loops: IntCounter,
     /// Critical error for payloads above the maximum supported size.
@@ -59,12 +69,24 @@ const TARGET_STREAM_SIZE_BYTES: usize = 10 * 1024 * 1024;
 /// `count_bytes()` is greater than or equal to `TARGET_STREAM_SIZE_BYTES`.
 const MAX_STREAM_MESSAGES: usize = 50_000;
 
+/// Byte budget granted to a canister every time it gets its turn in the round
+/// robin across canisters with outputs.
+///
+/// A canister keeps routing messages into remote streams while its budget is
+/// positive; once exhausted, the canister is moved to the back of the round robin
+/// and granted another `FAIR_QUEUING_QUANTUM_BYTES`. This way a canister sending
+/// large messages cannot crowd out canisters sending small ones.
+const FAIR_QUEUING_QUANTUM_BYTES: usize = 64 * 1024;
+
 const METRIC_STREAM_MESSAGES: &str = "mr_stream_messages";
 const METRIC_STREAM_BYTES: &str = "mr_stream_bytes";
 const METRIC_STREAM_BEGIN: &str = "mr_stream_begin";
 const METRIC_SIGNALS_END: &str = "mr_signals_end";
 const METRIC_ROUTED_MESSAGES: &str = "mr_routed_message_count";
 const METRIC_ROUTED_PAYLOAD_SIZES: &str = "mr_routed_payload_size_bytes";
+const METRIC_MAX_CANISTER_BACKLOG_BYTES: &str = "mr_stream_max_canister_backlog_bytes";
+const METRIC_CANISTER_BACKLOG_BYTES: &str = "mr_stream_canister_backlog_bytes";
+const METRIC_CANISTER_DEFERRALS: &str = "mr_stream_builder_canister_deferrals_total";
 
 const LABEL_TYPE: &str = "type";
 const LABEL_STATUS: &str = "status";
@@ -114,6 +136,21 @@ impl StreamBuilderMetrics {
             // 10 B - 5 MB
             decimal_buckets(1, 6),
         );
+        let max_canister_backlog_bytes = metrics_registry.int_gauge_vec(
+            METRIC_MAX_CANISTER_BACKLOG_BYTES,
+            "Largest byte size of the messages enqueued by a single canister, by remote subnet.",
+            &[LABEL_REMOTE],
+        );
+        let canister_backlog_bytes = metrics_registry.histogram(
+            METRIC_CANISTER_BACKLOG_BYTES,
+            "Byte size of the messages enqueued in remote streams, per sending canister.",
+            // 10 B - 50 MB
+            decimal_buckets(1, 7),
+        );
+        let canister_deferrals = metrics_registry.int_counter(
+            METRIC_CANISTER_DEFERRALS,
+            "Number of times a canister was moved to the back of the round robin after exhausting its byte budget.",
+        );
         let critical_error_infinite_loops =
             metrics_registry.error_counter(CRITICAL_ERROR_INFINITE_LOOP);
         let critical_error_payload_too_large =
@@ -144,6 +181,10 @@ impl StreamBuilderMetrics {
             signals_end,
             routed_messages,
             routed_payload_sizes,
+            max_canister_backlog_bytes,
+            canister_backlog_bytes,
+            canister_deferrals,
+            call_graph: CallGraphMetrics::new(metrics_registry, SOURCE_STREAM_BUILDER),
             critical_error_infinite_loops,
             critical_error_payload_too_large,
             critical_error_response_destination_not_found,
@@ -191,6 +232,13 @@ impl StreamBuilderImpl {
         reject_code: RejectCode,
         reject_message: String,
     ) {
+        let _reject_span = ic_tracing::messages::reject_span(
+            request_trace_id(req),
+            req.sender,
+            req.receiver,
+            reject_code,
+        )
+        .entered();
         state
             .push_input(
                 Response {
@@ -242,12 +290,14 @@ impl StreamBuilderImpl {
 
     /// Implementation of `StreamBuilder::build_streams()` that takes a
     /// `target_stream_size_bytes` argument to limit how many messages will be
-    /// routed into each stream.
+    /// routed into each stream and a `fair_queuing_quantum_bytes` argument to
+    /// limit how many bytes a canister may route per round robin turn.
     fn build_streams_impl(
         &self,
         mut state: ReplicatedState,
         max_stream_messages: usize,
         target_stream_size_bytes: usize,
+        fair_queuing_quantum_bytes: usize,
     ) -> ReplicatedState {
         /// Pops the previously peeked message.
         ///
@@ -295,6 +345,17 @@ impl StreamBuilderImpl {
                 && stream_messages_len >= 2 * SYSTEM_SUBNET_STREAM_MSG_LIMIT
         }
 
+        /// Charges the byte size of a message routed into a remote stream to the
+        /// byte budget of its sender.
+        fn charge_byte_budget(
+            byte_budgets: &mut BTreeMap<CanisterId, i64>,
+            msg: &RequestOrResponse,
+        ) {
+            if let Some(byte_budget) = byte_budgets.get_mut(&msg.sender()) {
+                *byte_budget = byte_budget.saturating_sub(msg.count_bytes() as i64);
+            }
+        }
+
         let mut streams = state.take_streams();
         let routing_table = state.routing_table();
         let subnet_types: BTreeMap<_, _> = state
@@ -311,12 +372,33 @@ impl StreamBuilderImpl {
         let mut output_iter = state.output_into_iter();
         let mut last_output_size = usize::MAX;
 
+        // Remaining byte budget of every canister encountered so far. Zero quanta
+        // are rounded up, so that every deferred canister eventually gets its turn.
+        let fair_queuing_quantum_bytes = i64::try_from(fair_queuing_quantum_bytes)
+            .unwrap_or(i64::MAX)
+            .max(1);
+        let mut byte_budgets: BTreeMap<CanisterId, i64> = BTreeMap::new();
+
         // Route all messages into the appropriate stream or generate reject Responses
         // when unable to (no route to canister). When a stream's byte size reaches or
         // exceeds `target_stream_size_bytes`, any matching queues are skipped.
         while let Some(msg) = output_iter.peek() {
             // Cheap to clone, `RequestOrResponse` wraps `Arcs`.
             let msg = msg.clone();
+
+            // Canister exhausted its byte budget, grant it a new quantum and move on to
+            // the next canister. This consumes no message, but always terminates, since
+            // the budget grows with every turn.
+            let byte_budget = byte_budgets
+                .entry(msg.sender())
+                .or_insert(fair_queuing_quantum_bytes);
+            if *byte_budget <= 0 {
+                *byte_budget = byte_budget.saturating_add(fair_queuing_quantum_bytes);
+                self.metrics.canister_deferrals.inc();
+                output_iter.defer_canister();
+                continue;
+            }
+
             // Safeguard to guarantee that iteration always terminates. Will always loop at
             // least once, if messages are available.
             let output_size = output_iter.size();
@@ -410,6 +492,17 @@ impl StreamBuilderImpl {
                                 }
                             }
 
+                            if dst_subnet_id != self.subnet_id {
+                                charge_byte_budget(&mut byte_budgets, &msg);
+                            }
+                            let _enqueue_span = ic_tracing::messages::enqueue_span(
+                                message_trace_id(&msg),
+                                msg.sender(),
+                                msg.receiver(),
+                                dst_subnet_id,
+                            )
+                            .entered();
+                            self.metrics.call_graph.observe(&msg);
                             streams.push(dst_subnet_id, msg);
                         }
 
@@ -417,6 +510,17 @@ impl StreamBuilderImpl {
                             // Route the message into the stream.
                             self.observe_message_status(&msg, LABEL_VALUE_STATUS_SUCCESS);
                             self.observe_payload_size(&msg);
+                            if dst_subnet_id != self.subnet_id {
+                                charge_byte_budget(&mut byte_budgets, &msg);
+                            }
+                            let _enqueue_span = ic_tracing::messages::enqueue_span(
+                                message_trace_id(&msg),
+                                msg.sender(),
+                                msg.receiver(),
+                                dst_subnet_id,
+                            )
+                            .entered();
+                            self.metrics.call_graph.observe(&msg);
                             streams.push(dst_subnet_id, msg);
                         }
                     };
@@ -505,6 +609,29 @@ impl StreamBuilderImpl {
                     .set(signals_end.get() as i64);
             });
 
+        // Export the byte size of the messages enqueued by each canister, per remote stream.
+        // The streams keep running per-canister tallies, so this is linear in the number
+        // of sending canisters rather than in the number of messages.
+        for (subnet_id, stream) in streams.iter() {
+            if *subnet_id == self.subnet_id {
+                continue;
+            }
+            let canister_backlogs = stream.senders_size_bytes();
+            for backlog in canister_backlogs.values() {
+                self.metrics.canister_backlog_bytes.observe(*backlog as f64);
+            }
+            self.metrics
+                .max_canister_backlog_bytes
+                .with_label_values(&[&subnet_id.to_string()])
+                .set(
+                    canister_backlogs
+                        .values()
+                        .max()
+                        .copied()
+                        .unwrap_or_default() as i64,
+                );
+        }
+
         {
             // Record the enqueuing time of any messages newly enqueued into `streams`.
             let mut time_in_stream_metrics = self.time_in_stream_metrics.lock().unwrap();
@@ -516,6 +643,8 @@ impl StreamBuilderImpl {
             }
         }
 
+        self.metrics.call_graph.observe_round();
+
         // Put the updated CanisterStates (outgoing messages removed) and Streams
         // (messages added) into the ReplicatedState to be returned.
         state.put_streams(streams);
@@ -525,6 +654,27 @@ impl StreamBuilderImpl {
 
 impl StreamBuilder for StreamBuilderImpl {
     fn build_streams(&self, state: ReplicatedState) -> ReplicatedState {
-        self.build_streams_impl(state, MAX_STREAM_MESSAGES, TARGET_STREAM_SIZE_BYTES)
+        self.build_streams_impl(
+            state,
+            MAX_STREAM_MESSAGES,
+            TARGET_STREAM_SIZE_BYTES,
+            FAIR_QUEUING_QUANTUM_BYTES,
+        )
+    }
+}
+
+/// Returns the trace ID of the call tree that `req` belongs to, if any.
+fn request_trace_id(req: &Request) -> Option<TraceId> {
+    req.metadata
+        .as_ref()
+        .and_then(|metadata| metadata.trace_id().copied())
+}
+
+/// Returns the trace ID of `msg`, if it is a request belonging to a traced call
+/// tree.
+fn message_trace_id(msg: &RequestOrResponse) -> Option<TraceId> {
+    match msg {
+        RequestOrResponse::Request(req) => request_trace_id(req),
+        RequestOrResponse::Response(_) => None,
     }
 }
@@ -422,10 +422,20 @@ fn build_streams_impl_at_limit_leaves_state_untouched() {
         let expected_state = provided_state.clone();
 
         // Act.
-        let result_state = stream_builder.build_streams_impl(provided_state.clone(), usize::MAX, 0);
+        let result_state = stream_builder.build_streams_impl(
+            provided_state.clone(),
+            usize::MAX,
+            0,
+            FAIR_QUEUING_QUANTUM_BYTES,
+        );
         assert_eq!(result_state, expected_state);
 
-        let result_state = stream_builder.build_streams_impl(provided_state, 0, usize::MAX);
+        let result_state = stream_builder.build_streams_impl(
+            provided_state,
+            0,
+            usize::MAX,
+            FAIR_QUEUING_QUANTUM_BYTES,
+        );
         assert_eq!(result_state, expected_state);
 
         assert_eq!(
@@ -533,6 +543,7 @@ fn build_streams_impl_respects_limits(
             provided_state,
             max_stream_messages,
             target_stream_size_bytes,
+            FAIR_QUEUING_QUANTUM_BYTES,
         );
 
         assert_eq!(expected_state.canister_states, result_state.canister_states);
@@ -585,6 +596,86 @@ fn build_streams_impl_respects_message_limit() {
     build_streams_impl_respects_limits(4, 1_000_000, 4);
 }
 
+// Tests that a canister sending large messages does not crowd out a canister
+// sending small messages when stream space is limited.
+#[test]
+fn build_streams_impl_fair_queuing() {
+    const LARGE_SENDER: CanisterId = CanisterId::from_u64(3);
+    const SMALL_SENDER: CanisterId = CanisterId::from_u64(4);
+    const MESSAGES_PER_SENDER: u64 = 10;
+    const LARGE_PAYLOAD_BYTES: usize = 10_000;
+
+    /// Routes the messages of both senders into a stream with room for about 3
+    /// large messages and returns the number of messages routed per sender.
+    fn route(fair_queuing_quantum_bytes: usize) -> BTreeMap<CanisterId, u64> {
+        with_test_replica_logger(|log| {
+            let (stream_builder, mut provided_state, metrics_registry) = new_fixture(&log);
+            provided_state.metadata.network_topology.routing_table = Arc::new(RoutingTable::try_from(
+                btreemap! {
+                    CanisterIdRange{ start: CanisterId::from(0), end: CanisterId::from(0xfff) } => REMOTE_SUBNET,
+                },
+            ).unwrap());
+
+            let mut msgs = Vec::new();
+            for (sender, payload_bytes) in [(LARGE_SENDER, LARGE_PAYLOAD_BYTES), (SMALL_SENDER, 0)]
+            {
+                for i in 1..=MESSAGES_PER_SENDER {
+                    msgs.push(
+                        RequestBuilder::default()
+                            .sender(sender)
+                            .receiver(canister_test_id(700))
+                            .sender_reply_callback(CallbackId::from(i))
+                            .method_payload(vec![0; payload_bytes])
+                            .build(),
+                    );
+                }
+            }
+            provided_state.put_canister_states(canister_states_with_outputs(msgs));
+
+            let result_state = stream_builder.build_streams_impl(
+                provided_state,
+                usize::MAX,
+                size_of::<Stream>() + 3 * LARGE_PAYLOAD_BYTES,
+                fair_queuing_quantum_bytes,
+            );
+
+            let stream = result_state.get_stream(&REMOTE_SUBNET).unwrap();
+            let mut routed_messages = BTreeMap::new();
+            let mut backlog_bytes = BTreeMap::<CanisterId, usize>::new();
+            for (_, msg) in stream.messages().iter() {
+                *routed_messages.entry(msg.sender()).or_default() += 1;
+                *backlog_bytes.entry(msg.sender()).or_default() += msg.count_bytes();
+            }
+            assert_eq!(
+                metric_vec(&[(
+                    &[(LABEL_REMOTE, &REMOTE_SUBNET.to_string())],
+                    *backlog_bytes.values().max().unwrap() as u64
+                )]),
+                fetch_int_gauge_vec(&metrics_registry, METRIC_MAX_CANISTER_BACKLOG_BYTES)
+            );
+            assert_eq!(
+                backlog_bytes.len() as u64,
+                fetch_histogram_stats(&metrics_registry, METRIC_CANISTER_BACKLOG_BYTES)
+                    .unwrap()
+                    .count
+            );
+
+            routed_messages
+        })
+    }
+
+    // Without fair queuing, both canisters take turns and are limited to about
+    // the same number of messages.
+    let routed_messages = route(usize::MAX);
+    assert!(routed_messages[&SMALL_SENDER] < MESSAGES_PER_SENDER);
+
+    // With fair queuing, the canister sending large messages has to wait for its
+    // byte budget to be replenished, while all small messages are routed.
+    let routed_messages = route(1_000);
+    assert_eq!(MESSAGES_PER_SENDER, routed_messages[&SMALL_SENDER]);
+    assert!(routed_messages[&LARGE_SENDER] >= 1);
+}
+
 // Tests that messages addressed to canisters not mapped to a known subnet
 // result in reject Responses.
 #[test]
@@ -23,7 +23,7 @@ use ic_types::{
         SignedIngressContent,
     },
     time::expiry_time_from_now,
-    SubnetId, Time,
+    Cycles, SubnetId, Time,
 };
 use prometheus::{Histogram, HistogramVec, IntCounterVec, IntGauge};
 use std::sync::Arc;
@@ -269,6 +269,15 @@ impl ValidSetRuleImpl {
             effective_canister_id,
             subnet_size,
         );
+        // The paying canister also pays the tip, if it opted into paying tips.
+        let tip = match &induction_cost {
+            IngressInductionCost::Fee { payer, .. } => state
+                .canister_state(payer)
+                .map_or(Cycles::zero(), |canister| {
+                    self.cycles_account_manager.ingress_tip(&msg, canister)
+                }),
+            IngressInductionCost::Free => Cycles::zero(),
+        };
 
         let ingress = Ingress::from((msg, effective_canister_id));
         match induction_cost {
@@ -295,7 +304,7 @@ impl ValidSetRuleImpl {
                     memory_usage,
                     message_memory_usage,
                     compute_allocation,
-                    cost,
+                    cost + tip,
                     subnet_size,
                     reveal_top_up,
                 ) {
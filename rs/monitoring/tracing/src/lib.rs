@@ -1,5 +1,6 @@
 use tracing_subscriber::{layer::Layer, reload::Handle, Registry};
 
+pub mod messages;
 pub mod utils;
 
 // We use dynamic dispatch here to make the ReloadHandles struct work with different
@@ -0,0 +1,82 @@
+//! Spans recording the lifecycle of canister messages that carry a trace ID.
+//!
+//! All spans share the `trace_id` field, so that the enqueueing, execution and
+//! response of every call in a call tree can be correlated, across canisters
+//! and subnets. Messages without a trace ID are not recorded.
+
+use std::fmt::Display;
+use tracing::{info_span, Span};
+
+/// Span covering the enqueueing of a request into the stream to `dst_subnet`.
+pub fn enqueue_span(
+    trace_id: Option<impl Display>,
+    sender: impl Display,
+    receiver: impl Display,
+    dst_subnet: impl Display,
+) -> Span {
+    match trace_id {
+        Some(trace_id) => info_span!(
+            "enqueue",
+            trace_id = %trace_id,
+            sender = %sender,
+            receiver = %receiver,
+            dst_subnet = %dst_subnet,
+        ),
+        None => Span::none(),
+    }
+}
+
+/// Span covering the rejection of a request from `sender` to `receiver` that
+/// could not be enqueued into any stream, e.g. because it had no route or an
+/// oversized payload.
+pub fn reject_span(
+    trace_id: Option<impl Display>,
+    sender: impl Display,
+    receiver: impl Display,
+    reject_code: impl Display,
+) -> Span {
+    match trace_id {
+        Some(trace_id) => info_span!(
+            "reject",
+            trace_id = %trace_id,
+            sender = %sender,
+            receiver = %receiver,
+            reject_code = %reject_code,
+        ),
+        None => Span::none(),
+    }
+}
+
+/// Span covering the execution of a call to `method` on `canister`.
+pub fn execution_span(
+    trace_id: Option<impl Display>,
+    canister: impl Display,
+    method: impl Display,
+) -> Span {
+    match trace_id {
+        Some(trace_id) => info_span!(
+            "execution",
+            trace_id = %trace_id,
+            canister = %canister,
+            method = %method,
+        ),
+        None => Span::none(),
+    }
+}
+
+/// Span covering the execution of a response from `respondent` by `originator`.
+pub fn response_span(
+    trace_id: Option<impl Display>,
+    originator: impl Display,
+    respondent: impl Display,
+) -> Span {
+    match trace_id {
+        Some(trace_id) => info_span!(
+            "response",
+            trace_id = %trace_id,
+            originator = %originator,
+            respondent = %respondent,
+        ),
+        None => Span::none(),
+    }
+}
@@ -59,6 +59,7 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
                 chain_key_config: None,
+                http_outcalls_policy: None,
             };
 
             let key = make_subnet_record_key(subnet_id);
@@ -103,6 +104,7 @@ fn test_submit_and_accept_update_subnet_proposal() {
                 max_number_of_canisters: Some(200),
                 ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
                 ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
+                http_outcalls_policy: None,
                 chain_key_config: None,
                 chain_key_signing_enable: None,
                 chain_key_signing_disable: None,
@@ -186,6 +188,7 @@ fn test_submit_and_accept_update_subnet_proposal() {
                     ssh_backup_access: vec!["pub_key_1".to_string()],
                     ecdsa_config: None,
                     chain_key_config: None,
+                    http_outcalls_policy: None,
                 }
             );
             Ok(())
@@ -25,7 +25,7 @@ use ic_base_types::NodeId;
 use ic_interfaces::p2p::consensus::{PriorityFnAndFilterProducer, ValidatedPoolReader};
 use ic_logger::{error, warn, ReplicaLogger};
 use ic_protobuf::{p2p::v1 as pb, proxy::ProtoProxy};
-use ic_quic_transport::{ConnId, SubnetTopology, Transport};
+use ic_quic_transport::{ConnId, PeerMisbehavior, SubnetTopology, Transport};
 use ic_types::artifact::{ArtifactKind, Priority, PriorityFn, UnvalidatedArtifactMutation};
 use rand::{rngs::SmallRng, seq::IteratorRandom, SeedableRng};
 use tokio::{
@@ -563,7 +563,10 @@ where
                                         log,
                                         "Peer {} responded with wrong artifact for advert", peer
                                     );
+                                    transport.report_peer(&peer, PeerMisbehavior::InvalidArtifact);
                                 }
+                            } else {
+                                transport.report_peer(&peer, PeerMisbehavior::ProtocolViolation);
                             }
                         }
                         _ => {
@@ -1748,6 +1751,14 @@ mod tests {
                     .unwrap())
             })
             .in_sequence(&mut seq);
+        // Peer that responded with the wrong artifact gets reported.
+        mock_transport
+            .expect_report_peer()
+            .once()
+            .withf(|peer, misbehavior| {
+                peer == &NODE_1 && misbehavior == &PeerMisbehavior::InvalidArtifact
+            })
+            .return_const(());
 
         let mut pc = PeerCounter::new();
         pc.insert(NODE_1);
@@ -41,7 +41,7 @@ use axum::{
     Router,
 };
 use bytes::Bytes;
-use ic_quic_transport::{ConnId, Transport};
+use ic_quic_transport::{ConnId, PeerMisbehavior, Transport};
 use ic_types::NodeId;
 use std::{
     collections::HashMap,
@@ -315,4 +315,6 @@ impl Transport for PeerTransport {
             .map(|(k, _)| (*k, ConnId::from(u64::MAX)))
             .collect()
     }
+
+    fn report_peer(&self, _peer_id: &NodeId, _misbehavior: PeerMisbehavior) {}
 }
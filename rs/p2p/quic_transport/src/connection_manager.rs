@@ -26,6 +26,10 @@
 //!       it needs to repair broken connections.
 //!     - Currently there is a periodic check that checks the status of the connection
 //!       and reconnects if necessary.
+//!
+//! Peer bans:
+//!     - Peers banned by the peer reputation are not dialed and their inbound connections
+//!       are closed. Once the ban expires the peer is dialed again.
 use std::{
     collections::{BTreeSet, HashMap},
     net::SocketAddr,
@@ -44,7 +48,6 @@ use ic_crypto_tls_interfaces::{
 use ic_crypto_utils_tls::node_id_from_certificate_der;
 use ic_interfaces_registry::RegistryClient;
 use ic_logger::{error, info, ReplicaLogger};
-use ic_metrics::MetricsRegistry;
 use quinn::{
     AsyncUdpSocket, ConnectError, Connecting, Connection, ConnectionError, Endpoint,
     EndpointConfig, VarInt,
@@ -56,6 +59,7 @@ use tokio_util::{sync::CancellationToken, time::DelayQueue};
 use crate::{
     connection_handle::ConnectionHandle,
     metrics::{CONNECTION_RESULT_FAILED_LABEL, CONNECTION_RESULT_SUCCESS_LABEL},
+    peer_reputation::PeerReputation,
     utils::collect_metrics,
     ConnId, Shutdown, SubnetTopology,
 };
@@ -99,6 +103,7 @@ struct ConnectionManager {
     // Shared state
     watcher: tokio::sync::watch::Receiver<SubnetTopology>,
     peer_map: Arc<RwLock<HashMap<NodeId, ConnectionHandle>>>,
+    reputation: Arc<PeerReputation>,
     conn_id_counter: ConnId,
 
     // Local state.
@@ -176,20 +181,19 @@ impl std::fmt::Display for ConnectionEstablishError {
 
 pub(crate) fn start_connection_manager(
     log: &ReplicaLogger,
-    metrics_registry: &MetricsRegistry,
+    metrics: QuicTransportMetrics,
     rt: &Handle,
     tls_config: Arc<dyn TlsConfig + Send + Sync>,
     registry_client: Arc<dyn RegistryClient>,
     node_id: NodeId,
     peer_map: Arc<RwLock<HashMap<NodeId, ConnectionHandle>>>,
+    reputation: Arc<PeerReputation>,
     watcher: tokio::sync::watch::Receiver<SubnetTopology>,
     socket: Either<SocketAddr, impl AsyncUdpSocket>,
     router: Router,
 ) -> Shutdown {
     let topology = watcher.borrow().clone();
 
-    let metrics = QuicTransportMetrics::new(metrics_registry);
-
     let router = router.route_layer(from_fn_with_state(metrics.clone(), collect_metrics));
 
     // We use a random reset key here. The downside of this is that
@@ -287,6 +291,7 @@ pub(crate) fn start_connection_manager(
         topology,
         connect_queue: DelayQueue::new(),
         peer_map,
+        reputation,
         conn_id_counter: ConnId::default(),
         watcher,
         endpoint,
@@ -458,6 +463,11 @@ impl ConnectionManager {
             }
         });
         self.metrics.peer_map_size.set(peer_map.len() as i64);
+        drop(peer_map);
+
+        // Forget reputation of peers that left the topology.
+        self.reputation
+            .retain(|peer_id| self.topology.is_member(peer_id));
     }
 
     fn handle_dial(&mut self, peer_id: NodeId) {
@@ -482,6 +492,12 @@ impl ConnectionManager {
             return;
         }
 
+        // Banned peers are dialed again once the ban expires.
+        if let Some(ban_remaining) = self.reputation.ban_remaining(&peer_id) {
+            self.connect_queue.insert(peer_id, ban_remaining);
+            return;
+        }
+
         info!(self.log, "Connecting to node {}", peer_id);
         self.metrics.outbound_connection_total.inc();
         let addr = self
@@ -536,6 +552,23 @@ impl ConnectionManager {
         peer_id: Option<NodeId>,
     ) {
         match conn_res {
+            Ok(ConnectionWithPeerId {
+                peer_id,
+                connection,
+            }) if self.reputation.is_banned(&peer_id) => {
+                self.metrics
+                    .connection_results_total
+                    .with_label_values(&[CONNECTION_RESULT_FAILED_LABEL])
+                    .inc();
+                connection.close(VarInt::from_u32(0), b"peer is banned");
+                info!(self.log, "Rejected connection from banned peer {}", peer_id);
+                // Retry once the ban expires if this node is the dialer.
+                if let Some(ban_remaining) = self.reputation.ban_remaining(&peer_id) {
+                    if self.am_i_dialer(&peer_id) {
+                        self.connect_queue.insert(peer_id, ban_remaining);
+                    }
+                }
+            }
             Ok(ConnectionWithPeerId {
                 peer_id,
                 connection,
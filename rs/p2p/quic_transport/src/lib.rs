@@ -10,6 +10,8 @@
 //!  - Request Handler (request_handler.rs): Accepts streams on an active connection.
 //!    Spawned by the connection manager for each connection.
 //!  - Connection Handle (connection_handle.rs): Provides rpc and push interfaces to a peer.
+//!  - Peer Reputation (peer_reputation.rs): Scores peers based on reported misbehavior and
+//!    temporarily bans peers with a low score.
 //!
 //! API:
 //!  - Constructor takes a topology watcher. The topology defines the
@@ -20,12 +22,14 @@
 //!     The connection handle is small wrapper around the actual quic connection
 //!     with an rpc/push interface. Passed in requests need to specify an URI to get
 //!     routed to the correct handler.
+//!  - `report_peer`: Can be used by transport users to report misbehavior of a peer.
 //!
 //! GUARANTEES:
 //!  - If a peer is reachable, part of the topology and well-behaving transport will eventually
 //!    open a connection.
 //!  - The connection handle returned by `get_conn_handle` can be broken.
 //!    It is responsibility of the transport user to have an adequate retry logic.
+//!  - Banned peers are disconnected and not returned by `peers` until the ban expires.
 //!
 //!
 use std::{
@@ -50,20 +54,25 @@ use ic_interfaces_registry::RegistryClient;
 use ic_logger::{info, ReplicaLogger};
 use ic_metrics::MetricsRegistry;
 use phantom_newtype::AmountOf;
-use quinn::AsyncUdpSocket;
+use quinn::{AsyncUdpSocket, VarInt};
 use tokio::sync::watch;
 use tokio_util::{sync::CancellationToken, task::task_tracker::TaskTracker};
 use tracing::instrument;
 
 use crate::connection_handle::ConnectionHandle;
 use crate::connection_manager::start_connection_manager;
+use crate::metrics::QuicTransportMetrics;
+use crate::peer_reputation::PeerReputation;
 
 mod connection_handle;
 mod connection_manager;
 mod metrics;
+mod peer_reputation;
 mod request_handler;
 mod utils;
 
+pub use crate::peer_reputation::PeerMisbehavior;
+
 #[derive(Clone)]
 pub struct Shutdown {
     cancellation: CancellationToken,
@@ -107,6 +116,7 @@ impl Shutdown {
 #[derive(Clone)]
 pub struct QuicTransport {
     conn_handles: Arc<RwLock<HashMap<NodeId, ConnectionHandle>>>,
+    reputation: Arc<PeerReputation>,
     shutdown: Shutdown,
 }
 
@@ -142,15 +152,18 @@ impl QuicTransport {
         info!(log, "Starting Quic transport.");
 
         let conn_handles = Arc::new(RwLock::new(HashMap::new()));
+        let metrics = QuicTransportMetrics::new(metrics_registry);
+        let reputation = Arc::new(PeerReputation::new(metrics.clone()));
 
         let shutdown = start_connection_manager(
             log,
-            metrics_registry,
+            metrics,
             rt,
             tls_config.clone(),
             registry_client,
             node_id,
             conn_handles.clone(),
+            reputation.clone(),
             topology_watcher,
             udp_socket,
             router,
@@ -158,6 +171,7 @@ impl QuicTransport {
 
         QuicTransport {
             conn_handles,
+            reputation,
             shutdown,
         }
     }
@@ -205,9 +219,21 @@ impl Transport for QuicTransport {
             .read()
             .unwrap()
             .iter()
+            .filter(|(n, _)| !self.reputation.is_banned(n))
             .map(|(n, c)| (*n, c.conn_id()))
             .collect()
     }
+
+    fn report_peer(&self, peer_id: &NodeId, misbehavior: PeerMisbehavior) {
+        if self.reputation.report(peer_id, misbehavior) {
+            // The connection manager removes the closed connection and
+            // makes sure no new connection is established while the peer is banned.
+            if let Ok(peer) = self.get_conn_handle(peer_id) {
+                peer.connection
+                    .close(VarInt::from_u32(0), b"peer banned because of misbehavior");
+            }
+        }
+    }
 }
 
 /// Low-level transport interface for exchanging messages between nodes.
@@ -225,6 +251,10 @@ pub trait Transport: Send + Sync {
     async fn push(&self, peer_id: &NodeId, request: Request<Bytes>) -> Result<(), anyhow::Error>;
 
     fn peers(&self) -> Vec<(NodeId, ConnId)>;
+
+    /// Reports misbehavior of a peer. Peers that repeatedly misbehave are
+    /// temporarily disconnected.
+    fn report_peer(&self, peer_id: &NodeId, misbehavior: PeerMisbehavior);
 }
 
 pub struct ConnIdTag {}
@@ -13,6 +13,7 @@ const STREAM_TYPE_LABEL: &str = "stream";
 const HANDLER_LABEL: &str = "handler";
 const ERROR_TYPE_LABEL: &str = "error";
 const REQUEST_TYPE_LABEL: &str = "request";
+const MISBEHAVIOR_LABEL: &str = "misbehavior";
 pub(crate) const CONNECTION_RESULT_SUCCESS_LABEL: &str = "success";
 pub(crate) const CONNECTION_RESULT_FAILED_LABEL: &str = "failed";
 pub(crate) const ERROR_TYPE_ACCEPT: &str = "accept";
@@ -40,6 +41,11 @@ pub struct QuicTransportMetrics {
     pub connecting_connections: IntGauge,
     pub delay_queue_size: IntGauge,
     pub closed_request_handlers_total: IntCounter,
+    // Peer reputation
+    pub peer_misbehavior_total: IntCounterVec,
+    pub peer_bans_total: IntCounter,
+    pub banned_peers: IntGauge,
+    peer_score: GaugeVec,
     // Request handler
     pub request_task_monitor: TaskMonitor,
     pub request_handle_errors_total: IntCounterVec,
@@ -110,6 +116,25 @@ impl QuicTransportMetrics {
                 "quic_transport_closed_request_handler_total",
                 "Number of closed request handlers.",
             ),
+            // Peer reputation
+            peer_misbehavior_total: metrics_registry.int_counter_vec(
+                "quic_transport_peer_misbehavior_total",
+                "Misbehavior reported for peers by misbehavior type.",
+                &[MISBEHAVIOR_LABEL],
+            ),
+            peer_bans_total: metrics_registry.int_counter(
+                "quic_transport_peer_bans_total",
+                "Number of times a peer got banned because of low reputation.",
+            ),
+            banned_peers: metrics_registry.int_gauge(
+                "quic_transport_banned_peers",
+                "Number of currently banned peers.",
+            ),
+            peer_score: metrics_registry.gauge_vec(
+                "quic_transport_peer_score",
+                "Current reputation score of the peer. Lower is worse.",
+                &[PEER_ID_LABEL],
+            ),
             // Request handler
             request_task_monitor,
             request_handle_errors_total: metrics_registry.int_counter_vec(
@@ -200,4 +225,14 @@ impl QuicTransportMetrics {
             .with_label_values(&peer_id_label)
             .set(path_stats.lost_packets as i64);
     }
+
+    pub(crate) fn set_peer_score(&self, peer_id: &NodeId, score: f64) {
+        self.peer_score
+            .with_label_values(&[&peer_id.to_string()])
+            .set(score);
+    }
+
+    pub(crate) fn remove_peer_score(&self, peer_id: &NodeId) {
+        let _ = self.peer_score.remove_label_values(&[&peer_id.to_string()]);
+    }
 }
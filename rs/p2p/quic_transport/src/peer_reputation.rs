@@ -0,0 +1,247 @@
+//! Quic Transport peer reputation.
+//!
+//! Keeps a score for each peer that is lowered when a component built on top of
+//! transport reports misbehavior (invalid artifacts, protocol violations).
+//! Penalties decay over time such that occasional faults of a well-behaving peer
+//! are forgotten.
+//!
+//! Only provable faults are reported. Slowness is not, because a ban closes the
+//! whole connection and would also cut off loaded but honest peers from
+//! consensus. Users that care about slow peers, like state sync, deprioritize
+//! them locally instead.
+//!
+//! If the score of a peer drops below `BAN_THRESHOLD` the peer gets banned for
+//! `BAN_DURATION`. While banned:
+//!     - The connection to the peer is closed and the peer is excluded from `peers()`.
+//!     - The connection manager neither dials nor accepts connections from the peer.
+//! After the ban expires the peer starts again with a neutral score.
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+use ic_base_types::NodeId;
+
+use crate::metrics::QuicTransportMetrics;
+
+/// Score a peer has before any misbehavior was reported.
+const NEUTRAL_SCORE: f64 = 0.0;
+/// Peers with a score lower or equal to this threshold get banned.
+const BAN_THRESHOLD: f64 = -100.0;
+/// Duration for which a peer is disconnected once it is banned.
+const BAN_DURATION: Duration = Duration::from_secs(60);
+/// Time after which half of the accumulated penalty is forgiven.
+const PENALTY_HALF_LIFE: Duration = Duration::from_secs(30);
+
+/// Misbehavior a transport user can attribute to a peer.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum PeerMisbehavior {
+    /// Peer delivered an artifact or chunk that failed validation.
+    InvalidArtifact,
+    /// Peer sent a message that does not adhere to the protocol. I.e. undecodable.
+    ProtocolViolation,
+}
+
+impl PeerMisbehavior {
+    fn penalty(&self) -> f64 {
+        match self {
+            Self::InvalidArtifact => 25.0,
+            Self::ProtocolViolation => 50.0,
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &'static str {
+        match self {
+            Self::InvalidArtifact => "invalid_artifact",
+            Self::ProtocolViolation => "protocol_violation",
+        }
+    }
+}
+
+#[derive(Debug)]
+struct PeerScore {
+    score: f64,
+    last_update: Instant,
+    banned_until: Option<Instant>,
+}
+
+impl PeerScore {
+    fn new(now: Instant) -> Self {
+        Self {
+            score: NEUTRAL_SCORE,
+            last_update: now,
+            banned_until: None,
+        }
+    }
+
+    /// Decays the accumulated penalty towards the neutral score.
+    fn decay(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.last_update);
+        let factor = 0.5_f64.powf(elapsed.as_secs_f64() / PENALTY_HALF_LIFE.as_secs_f64());
+        self.score = NEUTRAL_SCORE + (self.score - NEUTRAL_SCORE) * factor;
+        self.last_update = now;
+    }
+}
+
+/// Peer scores shared between the transport handle and the connection manager.
+pub(crate) struct PeerReputation {
+    scores: Mutex<HashMap<NodeId, PeerScore>>,
+    metrics: QuicTransportMetrics,
+}
+
+impl PeerReputation {
+    pub(crate) fn new(metrics: QuicTransportMetrics) -> Self {
+        Self {
+            scores: Mutex::new(HashMap::new()),
+            metrics,
+        }
+    }
+
+    /// Lowers the score of the peer. Returns true if the peer got banned by this report.
+    pub(crate) fn report(&self, peer_id: &NodeId, misbehavior: PeerMisbehavior) -> bool {
+        self.report_at(peer_id, misbehavior, Instant::now())
+    }
+
+    fn report_at(&self, peer_id: &NodeId, misbehavior: PeerMisbehavior, now: Instant) -> bool {
+        self.metrics
+            .peer_misbehavior_total
+            .with_label_values(&[misbehavior.as_str()])
+            .inc();
+
+        let mut scores = self.scores.lock().unwrap();
+        let peer_score = scores
+            .entry(*peer_id)
+            .or_insert_with(|| PeerScore::new(now));
+
+        // Reports for an already banned peer are ignored. Their connection is already closed.
+        if !self.maybe_lift_ban(peer_id, peer_score, now) {
+            return false;
+        }
+
+        peer_score.decay(now);
+        peer_score.score -= misbehavior.penalty();
+        self.metrics.set_peer_score(peer_id, peer_score.score);
+
+        if peer_score.score <= BAN_THRESHOLD {
+            peer_score.banned_until = Some(now + BAN_DURATION);
+            self.metrics.peer_bans_total.inc();
+            self.metrics.banned_peers.inc();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns the remaining ban duration if the peer is currently banned.
+    pub(crate) fn ban_remaining(&self, peer_id: &NodeId) -> Option<Duration> {
+        self.ban_remaining_at(peer_id, Instant::now())
+    }
+
+    fn ban_remaining_at(&self, peer_id: &NodeId, now: Instant) -> Option<Duration> {
+        let mut scores = self.scores.lock().unwrap();
+        let peer_score = scores.get_mut(peer_id)?;
+        if self.maybe_lift_ban(peer_id, peer_score, now) {
+            None
+        } else {
+            peer_score.banned_until.map(|until| until - now)
+        }
+    }
+
+    /// Lifts an expired ban and gives the peer a fresh start.
+    /// Returns false if the peer is still banned.
+    fn maybe_lift_ban(&self, peer_id: &NodeId, peer_score: &mut PeerScore, now: Instant) -> bool {
+        match peer_score.banned_until {
+            Some(until) if until > now => false,
+            Some(_) => {
+                *peer_score = PeerScore::new(now);
+                self.metrics.banned_peers.dec();
+                self.metrics.set_peer_score(peer_id, NEUTRAL_SCORE);
+                true
+            }
+            None => true,
+        }
+    }
+
+    pub(crate) fn is_banned(&self, peer_id: &NodeId) -> bool {
+        self.ban_remaining(peer_id).is_some()
+    }
+
+    /// Drops the state of peers that are not part of the topology anymore.
+    pub(crate) fn retain(&self, mut f: impl FnMut(&NodeId) -> bool) {
+        self.scores.lock().unwrap().retain(|peer_id, peer_score| {
+            let keep = f(peer_id);
+            if !keep {
+                if peer_score.banned_until.is_some() {
+                    self.metrics.banned_peers.dec();
+                }
+                self.metrics.remove_peer_score(peer_id);
+            }
+            keep
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ic_metrics::MetricsRegistry;
+    use ic_types_test_utils::ids::{NODE_1, NODE_2};
+
+    use super::*;
+
+    fn reputation() -> PeerReputation {
+        PeerReputation::new(QuicTransportMetrics::new(&MetricsRegistry::default()))
+    }
+
+    #[test]
+    fn repeated_invalid_artifacts_ban_peer() {
+        let reputation = reputation();
+        let now = Instant::now();
+
+        for _ in 0..3 {
+            assert!(!reputation.report_at(&NODE_1, PeerMisbehavior::InvalidArtifact, now));
+        }
+        assert!(reputation.report_at(&NODE_1, PeerMisbehavior::InvalidArtifact, now));
+        assert_eq!(
+            reputation.ban_remaining_at(&NODE_1, now),
+            Some(BAN_DURATION)
+        );
+        assert_eq!(reputation.ban_remaining_at(&NODE_2, now), None);
+        // Reports during the ban do not extend it.
+        assert!(!reputation.report_at(&NODE_1, PeerMisbehavior::ProtocolViolation, now));
+    }
+
+    #[test]
+    fn penalties_decay_over_time() {
+        let reputation = reputation();
+        let now = Instant::now();
+
+        assert!(!reputation.report_at(&NODE_1, PeerMisbehavior::ProtocolViolation, now));
+        // After a long time the first penalty is mostly forgiven.
+        let later = now + 10 * PENALTY_HALF_LIFE;
+        assert!(!reputation.report_at(&NODE_1, PeerMisbehavior::ProtocolViolation, later));
+        assert_eq!(reputation.ban_remaining_at(&NODE_1, later), None);
+    }
+
+    #[test]
+    fn ban_expires() {
+        let reputation = reputation();
+        let now = Instant::now();
+
+        assert!(!reputation.report_at(&NODE_1, PeerMisbehavior::ProtocolViolation, now));
+        assert!(reputation.report_at(&NODE_1, PeerMisbehavior::ProtocolViolation, now));
+        assert!(reputation
+            .ban_remaining_at(&NODE_1, now + BAN_DURATION / 2)
+            .is_some());
+        assert_eq!(
+            reputation.ban_remaining_at(&NODE_1, now + BAN_DURATION),
+            None
+        );
+        // Peer starts with a neutral score after the ban.
+        assert!(!reputation.report_at(
+            &NODE_1,
+            PeerMisbehavior::InvalidArtifact,
+            now + BAN_DURATION
+        ));
+    }
+}
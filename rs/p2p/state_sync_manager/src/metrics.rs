@@ -1,13 +1,15 @@
+use ic_base_types::NodeId;
 use ic_metrics::{
     buckets::decimal_buckets, tokio_metrics_collector::TokioTaskMetricsCollector, MetricsRegistry,
 };
-use prometheus::{Histogram, IntCounter, IntCounterVec, IntGauge};
+use prometheus::{GaugeVec, Histogram, IntCounter, IntCounterVec, IntGauge};
 use tokio_metrics::TaskMonitor;
 
 use crate::ongoing::DownloadChunkError;
 
 const CHUNK_DOWNLOAD_STATUS_LABEL: &str = "status";
 const CHUNK_DOWNLOAD_STATUS_SUCCESS: &str = "success";
+const PEER_ID_LABEL: &str = "peer";
 
 #[derive(Debug, Clone)]
 pub(crate) struct StateSyncManagerMetrics {
@@ -68,6 +70,8 @@ pub(crate) struct OngoingStateSyncMetrics {
     pub peers_serving_state: IntGauge,
     pub chunk_download_duration: Histogram,
     pub chunk_download_results_total: IntCounterVec,
+    pub bandwidth_throttled_total: IntCounter,
+    peer_throughput_bytes_per_second: GaugeVec,
 }
 
 impl OngoingStateSyncMetrics {
@@ -113,9 +117,30 @@ impl OngoingStateSyncMetrics {
                 "Chunk download request results.",
                 &[CHUNK_DOWNLOAD_STATUS_LABEL],
             ),
+            bandwidth_throttled_total: metrics_registry.int_counter(
+                "state_sync_manager_bandwidth_throttled_total",
+                "Number of times chunk downloads were delayed because the bandwidth limit was reached.",
+            ),
+            peer_throughput_bytes_per_second: metrics_registry.gauge_vec(
+                "state_sync_manager_peer_throughput_bytes_per_second",
+                "Average throughput of a single chunk download from the peer.",
+                &[PEER_ID_LABEL],
+            ),
         }
     }
 
+    pub fn set_peer_throughput(&self, peer_id: &NodeId, throughput: f64) {
+        self.peer_throughput_bytes_per_second
+            .with_label_values(&[&peer_id.to_string()])
+            .set(throughput);
+    }
+
+    pub fn remove_peer(&self, peer_id: &NodeId) {
+        let _ = self
+            .peer_throughput_bytes_per_second
+            .remove_label_values(&[&peer_id.to_string()]);
+    }
+
     /// Utility to record metrics for download result.
     pub fn record_chunk_download_result(&self, res: &Result<(), DownloadChunkError>) {
         match res {
@@ -5,8 +5,17 @@
 //!  - Ask State sync for which chunks to download
 //!  - Download this batch of chunk in parallel with a concurrency limiter per peer.
 //!    Note: - We randomly chose a peer from the set of peers advertised this state.
+//!            Peers are weighted by their observed throughput such that fast peers
+//!            serve more chunks.
+//!          - The number of parallel downloads per peer adapts to the observed behaviour.
+//!            It is increased on successful downloads and halved on timeouts.
+//!          - Requests that take much longer than the average latency of the peer time out
+//!            early. These stragglers are retried in the next batch download, likely from
+//!            another peer.
 //!          - We don't retry failed downloads immediately. Failed downloads are retried
 //!            in the next batch download.
+//!  - The total download bandwidth is capped such that state sync does not starve
+//!    other traffic, i.e. consensus.
 //!  - Add downloaded chunk to state.
 //!  - Repeat until state sync reports completed or we hit the state sync timeout or
 //!    this object is dropped.
@@ -23,7 +32,7 @@ use ic_async_utils::JoinMap;
 use ic_base_types::NodeId;
 use ic_interfaces::p2p::state_sync::{ChunkId, Chunkable, StateSyncArtifactId};
 use ic_logger::{error, info, ReplicaLogger};
-use ic_quic_transport::{Shutdown, Transport};
+use ic_quic_transport::{PeerMisbehavior, Shutdown, Transport};
 use rand::{
     distributions::{Distribution, WeightedIndex},
     rngs::SmallRng,
@@ -34,13 +43,26 @@ use tokio::{
     runtime::Handle,
     select,
     sync::mpsc::{Receiver, Sender},
+    time::{sleep_until, Instant},
 };
 use tokio_util::sync::CancellationToken;
 
-// TODO: NET-1461 find appropriate value for the parallelism
-const PARALLEL_CHUNK_DOWNLOADS: usize = 10;
+/// Number of parallel downloads a newly added peer starts with.
+const INITIAL_PARALLEL_CHUNK_DOWNLOADS: usize = 10;
+const MIN_PARALLEL_CHUNK_DOWNLOADS: usize = 1;
+const MAX_PARALLEL_CHUNK_DOWNLOADS: usize = 40;
 const ONGOING_STATE_SYNC_CHANNEL_SIZE: usize = 200;
+/// Upper bound for a single chunk download.
 const CHUNK_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(10);
+const MIN_CHUNK_DOWNLOAD_TIMEOUT: Duration = Duration::from_secs(1);
+/// Downloads that take longer than this multiple of the average latency of a peer are
+/// considered stragglers and are retried.
+const STRAGGLER_LATENCY_FACTOR: u32 = 4;
+/// Weight of the latest observation in the per peer moving averages.
+const EWMA_ALPHA: f64 = 0.2;
+/// Upper bound on the download bandwidth used by a state sync.
+/// 125MB/s ~ 1Gb/s leaves enough headroom for consensus traffic.
+const MAX_DOWNLOAD_BANDWIDTH_BYTES_PER_SEC: f64 = 125_000_000.0;
 
 struct OngoingStateSync {
     log: ReplicaLogger,
@@ -50,15 +72,105 @@ struct OngoingStateSync {
     transport: Arc<dyn Transport>,
     // Peer management
     new_peers_rx: Receiver<NodeId>,
-    // Peers that advertised state and the number of outstanding chunk downloads to that peer.
-    active_downloads: HashMap<NodeId, u64>,
+    // Peers that advertised state and their observed download performance.
+    active_downloads: HashMap<NodeId, PeerState>,
     // Download management
-    allowed_downloads: usize,
+    bandwidth_limiter: BandwidthLimiter,
     chunks_to_download: Box<dyn Iterator<Item = ChunkId> + Send>,
     // Event tasks
     downloading_chunks: JoinMap<ChunkId, DownloadResult>,
 }
 
+/// Download performance observed for a single peer.
+#[derive(Debug)]
+struct PeerState {
+    /// Number of outstanding chunk downloads to this peer.
+    active_downloads: usize,
+    /// Number of outstanding chunk downloads this peer is allowed to have.
+    parallel_downloads: usize,
+    /// Moving average of the chunk download latency.
+    latency: Option<Duration>,
+    /// Moving average of the throughput of a single chunk download.
+    throughput: Option<f64>,
+}
+
+impl PeerState {
+    fn new() -> Self {
+        Self {
+            active_downloads: 0,
+            parallel_downloads: INITIAL_PARALLEL_CHUNK_DOWNLOADS,
+            latency: None,
+            throughput: None,
+        }
+    }
+
+    fn spare_capacity(&self) -> usize {
+        self.parallel_downloads
+            .saturating_sub(self.active_downloads)
+    }
+
+    /// Timeout after which a download from this peer is considered a straggler.
+    fn download_timeout(&self) -> Duration {
+        self.latency
+            .map(|latency| {
+                (latency * STRAGGLER_LATENCY_FACTOR)
+                    .clamp(MIN_CHUNK_DOWNLOAD_TIMEOUT, CHUNK_DOWNLOAD_TIMEOUT)
+            })
+            .unwrap_or(CHUNK_DOWNLOAD_TIMEOUT)
+    }
+
+    fn record_success(&mut self, bytes: u64, elapsed: Duration) {
+        let throughput = bytes as f64 / elapsed.as_secs_f64().max(f64::EPSILON);
+        self.throughput = Some(match self.throughput {
+            Some(avg) => avg + EWMA_ALPHA * (throughput - avg),
+            None => throughput,
+        });
+        self.latency = Some(match self.latency {
+            Some(avg) => avg.mul_f64(1.0 - EWMA_ALPHA) + elapsed.mul_f64(EWMA_ALPHA),
+            None => elapsed,
+        });
+        // Additive increase.
+        self.parallel_downloads = (self.parallel_downloads + 1).min(MAX_PARALLEL_CHUNK_DOWNLOADS);
+    }
+
+    fn record_timeout(&mut self) {
+        // Multiplicative decrease.
+        self.parallel_downloads = (self.parallel_downloads / 2).max(MIN_PARALLEL_CHUNK_DOWNLOADS);
+    }
+}
+
+/// Token bucket that limits the number of bytes downloaded per second.
+/// Downloads are allowed to overdraw the bucket since the chunk size is only known
+/// after the download completed.
+struct BandwidthLimiter {
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl BandwidthLimiter {
+    fn new() -> Self {
+        Self {
+            tokens: MAX_DOWNLOAD_BANDWIDTH_BYTES_PER_SEC,
+            last_refill: Instant::now(),
+        }
+    }
+
+    fn consume(&mut self, bytes: u64) {
+        self.tokens -= bytes as f64;
+    }
+
+    /// Returns the instant at which downloads can resume if the bandwidth is exhausted.
+    fn throttled_until(&mut self) -> Option<Instant> {
+        let now = Instant::now();
+        let refill = (now - self.last_refill).as_secs_f64() * MAX_DOWNLOAD_BANDWIDTH_BYTES_PER_SEC;
+        self.tokens = (self.tokens + refill).min(MAX_DOWNLOAD_BANDWIDTH_BYTES_PER_SEC);
+        self.last_refill = now;
+        (self.tokens < 0.0).then(|| {
+            now + Duration::from_secs_f64(-self.tokens / MAX_DOWNLOAD_BANDWIDTH_BYTES_PER_SEC)
+        })
+    }
+}
+
 pub(crate) struct OngoingStateSyncHandle {
     pub sender: Sender<NodeId>,
     pub artifact_id: StateSyncArtifactId,
@@ -67,6 +179,9 @@ pub(crate) struct OngoingStateSyncHandle {
 
 pub(crate) struct DownloadResult {
     peer_id: NodeId,
+    /// Size of the downloaded chunk on the wire.
+    bytes: u64,
+    elapsed: Duration,
     result: Result<(), DownloadChunkError>,
 }
 
@@ -87,7 +202,7 @@ pub(crate) fn start_ongoing_state_sync<T: Send + 'static>(
         transport,
         new_peers_rx,
         active_downloads: HashMap::new(),
-        allowed_downloads: 0,
+        bandwidth_limiter: BandwidthLimiter::new(),
         chunks_to_download: Box::new(std::iter::empty()),
         downloading_chunks: JoinMap::new(),
     };
@@ -110,17 +225,21 @@ impl OngoingStateSync {
         cancellation: CancellationToken,
         tracker: Arc<Mutex<Box<dyn Chunkable<T> + Send>>>,
     ) {
+        // Set if no new downloads are started because the bandwidth budget is exhausted.
+        let mut throttled_until: Option<Instant> = None;
         loop {
             select! {
                 () = cancellation.cancelled() => {
                     break
                 },
+                () = sleep_until(throttled_until.unwrap_or_else(Instant::now)), if throttled_until.is_some() => {
+                    throttled_until = self.spawn_chunk_downloads(cancellation.clone(), tracker.clone());
+                }
                 Some(new_peer) = self.new_peers_rx.recv() => {
                     if let Entry::Vacant(e) = self.active_downloads.entry(new_peer) {
                         info!(self.log, "Adding peer {} to ongoing state sync of height {}.", new_peer, self.artifact_id.height);
-                        e.insert(0);
-                        self.allowed_downloads += PARALLEL_CHUNK_DOWNLOADS;
-                        self.spawn_chunk_downloads(cancellation.clone(), tracker.clone());
+                        e.insert(PeerState::new());
+                        throttled_until = self.spawn_chunk_downloads(cancellation.clone(), tracker.clone());
                     }
                 }
                 Some(download_result) = self.downloading_chunks.join_next() => {
@@ -131,9 +250,9 @@ impl OngoingStateSync {
                             // of an underflow. In the case where we close old download task while having active downloads we might start to
                             // undercount active downloads for this peer but this is acceptable since everything will be reset anyway every
                             // 5-10min when state sync restarts.
-                            self.active_downloads.entry(result.peer_id).and_modify(|v| { *v = v.saturating_sub(1) });
+                            self.active_downloads.entry(result.peer_id).and_modify(|v| { v.active_downloads = v.active_downloads.saturating_sub(1) });
                             self.handle_downloaded_chunk_result(result);
-                            self.spawn_chunk_downloads(cancellation.clone(), tracker.clone());
+                            throttled_until = self.spawn_chunk_downloads(cancellation.clone(), tracker.clone());
                         }
                         Err(err) => {
                             // If task panic we propagate but we allow tasks to be cancelled.
@@ -148,14 +267,13 @@ impl OngoingStateSync {
                 }
             }
 
-            debug_assert!(
-                self.active_downloads.len() * PARALLEL_CHUNK_DOWNLOADS == self.allowed_downloads
-            );
-
             // Collect metrics
-            self.metrics
-                .allowed_parallel_downloads
-                .set(self.allowed_downloads as i64);
+            self.metrics.allowed_parallel_downloads.set(
+                self.active_downloads
+                    .values()
+                    .map(|p| p.parallel_downloads as i64)
+                    .sum(),
+            );
             self.metrics
                 .peers_serving_state
                 .set(self.active_downloads.len() as i64);
@@ -168,72 +286,124 @@ impl OngoingStateSync {
         while let Some(Ok((finished, _))) = self.downloading_chunks.join_next().await {
             self.handle_downloaded_chunk_result(finished);
         }
+        for peer_id in self.active_downloads.keys() {
+            self.metrics.remove_peer(peer_id);
+        }
         self.new_peers_rx.close();
     }
 
+    fn remove_peer(&mut self, peer_id: &NodeId) {
+        if self.active_downloads.remove(peer_id).is_some() {
+            self.metrics.remove_peer(peer_id);
+        }
+    }
+
     fn handle_downloaded_chunk_result(
         &mut self,
-        DownloadResult { peer_id, result }: DownloadResult,
+        DownloadResult {
+            peer_id,
+            bytes,
+            elapsed,
+            result,
+        }: DownloadResult,
     ) {
         self.metrics.record_chunk_download_result(&result);
+        self.bandwidth_limiter.consume(bytes);
         match result {
             // Received chunk
-            Ok(()) => {}
-            Err(DownloadChunkError::NoContent) => {
-                if self.active_downloads.remove(&peer_id).is_some() {
-                    self.allowed_downloads -= PARALLEL_CHUNK_DOWNLOADS;
+            Ok(()) => {
+                if let Some(peer) = self.active_downloads.get_mut(&peer_id) {
+                    peer.record_success(bytes, elapsed);
+                    self.metrics
+                        .set_peer_throughput(&peer_id, peer.throughput.unwrap_or_default());
                 }
             }
+            Err(DownloadChunkError::NoContent) => {
+                self.remove_peer(&peer_id);
+            }
             Err(DownloadChunkError::RequestError { chunk_id, err }) => {
                 info!(
                     self.log,
                     "Failed to download chunk {} from {}: {} ", chunk_id, peer_id, err
                 );
-                if self.active_downloads.remove(&peer_id).is_some() {
-                    self.allowed_downloads -= PARALLEL_CHUNK_DOWNLOADS;
-                }
+                self.remove_peer(&peer_id);
+            }
+            Err(DownloadChunkError::InvalidChunk { chunk_id, err }) => {
+                info!(
+                    self.log,
+                    "Received invalid chunk {} from {}: {} ", chunk_id, peer_id, err
+                );
+                self.transport
+                    .report_peer(&peer_id, PeerMisbehavior::InvalidArtifact);
+                self.remove_peer(&peer_id);
             }
             Err(DownloadChunkError::Overloaded) => {}
-            Err(DownloadChunkError::Timeout) => {}
+            Err(DownloadChunkError::Timeout) => {
+                // Slow peers are only deprioritized for state sync and not reported to
+                // transport, whose bans would also disconnect them from consensus.
+                if let Some(peer) = self.active_downloads.get_mut(&peer_id) {
+                    peer.record_timeout();
+                }
+            }
             Err(DownloadChunkError::Cancelled) => {}
         }
     }
 
+    /// Spawns downloads for peers with spare capacity. Returns the instant at which
+    /// downloads should be spawned again if the bandwidth budget is exhausted.
     fn spawn_chunk_downloads<T: 'static + Send>(
         &mut self,
         cancellation: CancellationToken,
         tracker: Arc<Mutex<Box<dyn Chunkable<T> + Send>>>,
-    ) {
-        let available_download_capacity = self
-            .allowed_downloads
-            .saturating_sub(self.downloading_chunks.len());
-
-        if self.active_downloads.is_empty() {
-            return;
+    ) -> Option<Instant> {
+        if let Some(throttled_until) = self.bandwidth_limiter.throttled_until() {
+            self.metrics.bandwidth_throttled_total.inc();
+            return Some(throttled_until);
         }
 
-        let mut small_rng = SmallRng::from_entropy();
-        let max_active_downloads = self
+        let available_download_capacity: usize = self
             .active_downloads
             .values()
-            .max()
-            .expect("Peers not empty");
-        let mut peers = Vec::with_capacity(self.active_downloads.len());
-        let mut weights = Vec::with_capacity(self.active_downloads.len());
-        for (peer, active_downloads) in &self.active_downloads {
-            peers.push(*peer);
-            // Add one such that all peers can get selected.
-            weights.push(max_active_downloads - active_downloads + 1);
-        }
-        let dist = WeightedIndex::new(weights).expect("weights>=0, sum(weights)>0, len(weigths)>0");
+            .map(PeerState::spare_capacity)
+            .sum();
+
+        // Peers without a throughput estimate are treated like an average peer.
+        let known_throughputs: Vec<f64> = self
+            .active_downloads
+            .values()
+            .filter_map(|p| p.throughput)
+            .collect();
+        let default_throughput = if known_throughputs.is_empty() {
+            1.0
+        } else {
+            known_throughputs.iter().sum::<f64>() / known_throughputs.len() as f64
+        };
+
+        let mut small_rng = SmallRng::from_entropy();
         for _ in 0..available_download_capacity {
             match self.chunks_to_download.next() {
                 Some(chunk) if !self.downloading_chunks.contains(&chunk) => {
-                    // Select random peer weighted proportional to active downloads.
-                    // Peers with less active downloads are more likely to be selected.
+                    // Select random peer with spare capacity weighted proportional to its throughput.
+                    // Faster peers are more likely to be selected.
+                    let mut peers = Vec::with_capacity(self.active_downloads.len());
+                    let mut weights = Vec::with_capacity(self.active_downloads.len());
+                    for (peer, state) in &self.active_downloads {
+                        if state.spare_capacity() > 0 {
+                            peers.push(*peer);
+                            weights.push(state.throughput.unwrap_or(default_throughput).max(1.0));
+                        }
+                    }
+                    let Ok(dist) = WeightedIndex::new(weights) else {
+                        break;
+                    };
                     let peer_id = *peers.get(dist.sample(&mut small_rng)).expect("Is present");
 
-                    self.active_downloads.entry(peer_id).and_modify(|v| *v += 1);
+                    let peer = self
+                        .active_downloads
+                        .get_mut(&peer_id)
+                        .expect("Peer was just selected");
+                    peer.active_downloads += 1;
+                    let timeout = peer.download_timeout();
                     self.downloading_chunks.spawn_on(
                         chunk,
                         self.metrics
@@ -244,6 +414,7 @@ impl OngoingStateSync {
                                 tracker.clone(),
                                 self.artifact_id.clone(),
                                 chunk,
+                                timeout,
                                 cancellation.child_token(),
                                 self.metrics.clone(),
                             )),
@@ -268,6 +439,7 @@ impl OngoingStateSync {
                 }
             }
         }
+        None
     }
 
     async fn download_chunk_task<T: 'static + Send>(
@@ -276,28 +448,35 @@ impl OngoingStateSync {
         tracker: Arc<Mutex<Box<dyn Chunkable<T> + Send>>>,
         artifact_id: StateSyncArtifactId,
         chunk_id: ChunkId,
+        download_timeout: Duration,
         download_cancel_token: CancellationToken,
         metrics: OngoingStateSyncMetrics,
     ) -> DownloadResult {
         let _timer = metrics.chunk_download_duration.start_timer();
+        let start = Instant::now();
 
         let response_result = select! {
             () = download_cancel_token.cancelled() => {
                 return DownloadResult {
                     peer_id,
+                    bytes: 0,
+                    elapsed: start.elapsed(),
                     result: Err(DownloadChunkError::Cancelled)
                 }
             }
-            res = tokio::time::timeout(CHUNK_DOWNLOAD_TIMEOUT,client.rpc(&peer_id, build_chunk_handler_request(artifact_id, chunk_id))) => {
+            res = tokio::time::timeout(download_timeout, client.rpc(&peer_id, build_chunk_handler_request(artifact_id, chunk_id))) => {
                 res
             }
         };
+        let elapsed = start.elapsed();
 
         let response = match response_result {
             Ok(Ok(response)) => response,
             Ok(Err(e)) => {
                 return DownloadResult {
                     peer_id,
+                    bytes: 0,
+                    elapsed,
                     result: Err(DownloadChunkError::RequestError {
                         chunk_id,
                         err: e.to_string(),
@@ -307,16 +486,19 @@ impl OngoingStateSync {
             Err(_) => {
                 return DownloadResult {
                     peer_id,
+                    bytes: 0,
+                    elapsed,
                     result: Err(DownloadChunkError::Timeout),
                 }
             }
         };
+        let bytes = response.body().len() as u64;
 
         let result = tokio::task::spawn_blocking(move || {
             let chunk = parse_chunk_handler_response(response, chunk_id, metrics)?;
             let mut tracker_guard = tracker.lock().unwrap();
             tracker_guard.add_chunk(chunk_id, chunk).map_err(|err| {
-                DownloadChunkError::RequestError {
+                DownloadChunkError::InvalidChunk {
                     chunk_id,
                     err: err.to_string(),
                 }
@@ -329,7 +511,12 @@ impl OngoingStateSync {
         })
         .and_then(std::convert::identity);
 
-        DownloadResult { peer_id, result }
+        DownloadResult {
+            peer_id,
+            bytes,
+            elapsed,
+            result,
+        }
     }
 }
 
@@ -353,6 +540,9 @@ pub(crate) enum DownloadChunkError {
     /// do not return a RequestError.
     #[error("request_error")]
     RequestError { chunk_id: ChunkId, err: String },
+    /// The peer delivered a chunk that could not be decoded or failed verification.
+    #[error("invalid_chunk")]
+    InvalidChunk { chunk_id: ChunkId, err: String },
 }
 
 #[cfg(test)]
@@ -432,6 +622,11 @@ mod tests {
                 .returning(|| Box::new(std::iter::once(ChunkId::from(1))));
             c.expect_add_chunk()
                 .return_const(Err(AddChunkError::Invalid));
+            t.expect_report_peer()
+                .withf(|peer, misbehavior| {
+                    peer == &NODE_1 && misbehavior == &PeerMisbehavior::InvalidArtifact
+                })
+                .return_const(());
 
             let rt = Runtime::new().unwrap();
             let ongoing = start_ongoing_state_sync(
@@ -454,8 +649,7 @@ mod tests {
         });
     }
 
-    /// Add peer multiple times to ongoing sync. Debug assertion in event loop verifies
-    /// that download budget is correct.
+    /// Add peer multiple times to ongoing sync. The peer must only be tracked once.
     #[test]
     fn test_add_peer_multiple_times_to_ongoing_state_sync() {
         with_test_replica_logger(|log| {
@@ -495,4 +689,42 @@ mod tests {
             });
         });
     }
+
+    /// Verify that the per peer download budget adapts to the observed performance.
+    #[test]
+    fn test_peer_state_adapts_to_performance() {
+        let mut peer = PeerState::new();
+        assert_eq!(peer.download_timeout(), CHUNK_DOWNLOAD_TIMEOUT);
+
+        peer.record_success(1_000_000, Duration::from_millis(100));
+        assert_eq!(
+            peer.parallel_downloads,
+            INITIAL_PARALLEL_CHUNK_DOWNLOADS + 1
+        );
+        assert_eq!(peer.latency, Some(Duration::from_millis(100)));
+        // Stragglers time out early but never before the minimum timeout.
+        assert_eq!(peer.download_timeout(), MIN_CHUNK_DOWNLOAD_TIMEOUT);
+
+        peer.record_success(1_000_000, Duration::from_secs(20));
+        assert_eq!(peer.download_timeout(), CHUNK_DOWNLOAD_TIMEOUT);
+
+        for _ in 0..10 {
+            peer.record_timeout();
+        }
+        assert_eq!(peer.parallel_downloads, MIN_PARALLEL_CHUNK_DOWNLOADS);
+        for _ in 0..100 {
+            peer.record_success(1_000_000, Duration::from_millis(100));
+        }
+        assert_eq!(peer.parallel_downloads, MAX_PARALLEL_CHUNK_DOWNLOADS);
+    }
+
+    /// Verify that downloads are throttled once the bandwidth budget is exhausted.
+    #[test]
+    fn test_bandwidth_limiter() {
+        let mut limiter = BandwidthLimiter::new();
+        assert!(limiter.throttled_until().is_none());
+        limiter.consume(2 * MAX_DOWNLOAD_BANDWIDTH_BYTES_PER_SEC as u64);
+        let throttled_until = limiter.throttled_until().expect("Budget is exhausted");
+        assert!(throttled_until > Instant::now());
+    }
 }
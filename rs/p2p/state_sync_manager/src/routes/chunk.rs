@@ -105,7 +105,7 @@ pub(crate) fn parse_chunk_handler_response(
                 .chunk_size_compressed_total
                 .inc_by(body.len() as u64);
             let decompressed = zstd::bulk::decompress(&body, MAX_CHUNK_SIZE).map_err(|e| {
-                DownloadChunkError::RequestError {
+                DownloadChunkError::InvalidChunk {
                     chunk_id,
                     err: e.to_string(),
                 }
@@ -117,7 +117,7 @@ pub(crate) fn parse_chunk_handler_response(
 
             let pb =
                 pb::StateSyncChunkResponse::decode(Bytes::from(decompressed)).map_err(|e| {
-                    DownloadChunkError::RequestError {
+                    DownloadChunkError::InvalidChunk {
                         chunk_id,
                         err: e.to_string(),
                     }
@@ -43,6 +43,7 @@ use tokio::{
 
 pub mod consensus;
 pub mod mocks;
+pub mod simulation;
 pub mod turmoil;
 
 /// Creates a temp crypto component with TLS key and specified node id.
@@ -5,7 +5,7 @@ use ic_interfaces::p2p::{
     consensus::{PriorityFnAndFilterProducer, ValidatedPoolReader},
     state_sync::{AddChunkError, Chunk, ChunkId, Chunkable, StateSyncArtifactId, StateSyncClient},
 };
-use ic_quic_transport::{ConnId, Transport};
+use ic_quic_transport::{ConnId, PeerMisbehavior, Transport};
 use ic_types::artifact::{ArtifactKind, PriorityFn};
 use ic_types::NodeId;
 use mockall::mock;
@@ -47,6 +47,8 @@ mock! {
         ) -> Result<(), anyhow::Error>;
 
         fn peers(&self) -> Vec<(NodeId, ConnId)>;
+
+        fn report_peer(&self, peer_id: &NodeId, misbehavior: PeerMisbehavior);
     }
 }
 
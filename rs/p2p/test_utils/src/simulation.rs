@@ -0,0 +1,631 @@
+//! Deterministic discrete-event simulation of a subnet running consensus.
+//!
+//! Every simulated node implements [`SimulatedNode`], e.g. by wrapping the real
+//! consensus components and their artifact pools. The simulation owns a virtual clock
+//! and a single event queue. Nodes are polled periodically (ticks) and every message
+//! a node emits is broadcast to all other nodes, each copy with its own latency.
+//! Tick jitter and message latencies (and hence message reordering) are drawn from a
+//! `ChaChaRng` seeded with the configured seed, and events with the same timestamp are
+//! processed in the order they were scheduled. The simulation does not read the wall
+//! clock, spawn threads or use OS randomness, so two runs with the same seed and the
+//! same nodes process exactly the same events in exactly the same order. Every
+//! processed event is appended to [`Simulation::trace`], which allows to compare runs.
+//!
+//! Partitioned links and crashed nodes do not lose messages. Messages that cannot be
+//! delivered are held back and scheduled again once the partition is healed or the
+//! receiver is restarted, which models retransmission by P2P.
+//!
+//! After every tick the harness checks safety: No two nodes may ever finalize different
+//! blocks at the same height. Liveness is asserted with [`Step::AwaitFinalization`].
+//!
+//! Example:
+//! ```ignore
+//! let mut simulation = SimulationBuilder::new(log).seed(42).add_nodes(nodes).build();
+//! simulation.run_scenario([
+//!     Step::Partition(vec![node_test_id(0), node_test_id(1)], vec![node_test_id(2), node_test_id(3)]),
+//!     Step::RunFor(Duration::from_secs(5)),
+//!     Step::Heal,
+//!     Step::AwaitFinalization { height: Height::from(10), timeout: Duration::from_secs(60) },
+//! ]).unwrap();
+//! ```
+use std::{
+    cmp::Ordering,
+    collections::{BTreeMap, BTreeSet, BinaryHeap},
+    fmt,
+    io::Write,
+    time::Duration,
+};
+
+use ic_logger::{info, ReplicaLogger};
+use ic_types::{Height, NodeId, Time};
+use rand::Rng;
+use rand_chacha::{rand_core::SeedableRng, ChaChaRng};
+
+const DEFAULT_SEED: u64 = 0;
+const DEFAULT_TICK_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A node that takes part in the simulation.
+pub trait SimulatedNode {
+    /// Messages broadcast between nodes.
+    type Message: Clone + fmt::Debug;
+    /// Identifies a finalized block, e.g. its hash.
+    type BlockId: Clone + Eq + fmt::Debug;
+
+    /// Delivers a message sent by `from` at the (simulated) time `now`.
+    fn on_message(&mut self, from: NodeId, message: Self::Message, now: Time);
+
+    /// Lets the node make progress at the (simulated) time `now`. Returns the
+    /// messages to broadcast to all other nodes.
+    fn on_tick(&mut self, now: Time) -> Vec<Self::Message>;
+
+    /// Height of the highest block finalized by this node.
+    fn finalized_height(&self) -> Height;
+
+    /// The block this node finalized at the given height, if it still has it.
+    fn finalized_block(&self, height: Height) -> Option<Self::BlockId>;
+}
+
+/// A single action of a simulation scenario.
+#[derive(Clone, Debug)]
+pub enum Step {
+    /// Holds back all messages between the two groups of nodes.
+    Partition(Vec<NodeId>, Vec<NodeId>),
+    /// Repairs all partitions and delivers the held back messages.
+    Heal,
+    /// Crashes the node. The state of the node is retained.
+    Crash(NodeId),
+    /// Restarts a crashed node and delivers the messages it missed.
+    Restart(NodeId),
+    /// Runs the simulation for the given (simulated) duration.
+    RunFor(Duration),
+    /// Runs the simulation until all running nodes finalized the given height or the
+    /// timeout is reached.
+    AwaitFinalization { height: Height, timeout: Duration },
+}
+
+#[derive(Debug)]
+pub enum SimulationError {
+    /// Safety invariant was violated.
+    Safety(String),
+    /// Nodes did not make progress in time.
+    Liveness(String),
+}
+
+impl fmt::Display for SimulationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Safety(e) => write!(f, "Safety violation: {e}"),
+            Self::Liveness(e) => write!(f, "Liveness violation: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for SimulationError {}
+
+pub struct SimulationBuilder<N: SimulatedNode> {
+    log: ReplicaLogger,
+    seed: u64,
+    start_time: Time,
+    tick_interval: Duration,
+    min_message_latency: Duration,
+    max_message_latency: Duration,
+    nodes: BTreeMap<NodeId, N>,
+}
+
+impl<N: SimulatedNode> SimulationBuilder<N> {
+    pub fn new(log: ReplicaLogger) -> Self {
+        Self {
+            log,
+            seed: DEFAULT_SEED,
+            start_time: Time::from_nanos_since_unix_epoch(0),
+            tick_interval: DEFAULT_TICK_INTERVAL,
+            min_message_latency: Duration::from_millis(0),
+            max_message_latency: Duration::from_millis(100),
+            nodes: BTreeMap::new(),
+        }
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
+    /// Simulated time at which the simulation starts.
+    pub fn start_time(mut self, start_time: Time) -> Self {
+        self.start_time = start_time;
+        self
+    }
+
+    /// Interval in which every node is polled.
+    pub fn tick_interval(mut self, tick_interval: Duration) -> Self {
+        self.tick_interval = tick_interval;
+        self
+    }
+
+    /// Messages are delayed by a random duration in the given range. Varying latencies
+    /// also cause messages to be reordered.
+    pub fn message_latency(mut self, min: Duration, max: Duration) -> Self {
+        self.min_message_latency = min;
+        self.max_message_latency = max;
+        self
+    }
+
+    pub fn add_node(mut self, node_id: NodeId, node: N) -> Self {
+        self.nodes.insert(node_id, node);
+        self
+    }
+
+    pub fn add_nodes(self, nodes: impl IntoIterator<Item = (NodeId, N)>) -> Self {
+        nodes.into_iter().fold(self, |builder, (node_id, node)| {
+            builder.add_node(node_id, node)
+        })
+    }
+
+    pub fn build(self) -> Simulation<N> {
+        info!(
+            self.log,
+            "Starting simulation with {} nodes and seed {}",
+            self.nodes.len(),
+            self.seed
+        );
+        let mut simulation = Simulation {
+            log: self.log,
+            rng: ChaChaRng::seed_from_u64(self.seed),
+            now: self.start_time,
+            tick_interval: self.tick_interval,
+            min_message_latency: self.min_message_latency,
+            max_message_latency: self.max_message_latency,
+            next_seq: 0,
+            events: BinaryHeap::new(),
+            held: Vec::new(),
+            nodes: self.nodes,
+            crashed: BTreeSet::new(),
+            partitions: BTreeSet::new(),
+            checked_height: BTreeMap::new(),
+            finalized: BTreeMap::new(),
+            trace: Vec::new(),
+        };
+        let node_ids: Vec<_> = simulation.nodes.keys().copied().collect();
+        for node_id in node_ids {
+            simulation.schedule_first_tick(node_id);
+        }
+        simulation
+    }
+}
+
+enum EventKind<M> {
+    Tick(NodeId),
+    Deliver {
+        from: NodeId,
+        to: NodeId,
+        message: M,
+    },
+}
+
+/// An event of the queue. Events are ordered by time and then by the order in which
+/// they were scheduled, which makes the processing order independent of the message
+/// contents.
+struct Event<M> {
+    time: Time,
+    seq: u64,
+    kind: EventKind<M>,
+}
+
+impl<M> Ord for Event<M> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        // `BinaryHeap` is a max-heap, the earliest event must compare as the greatest.
+        (other.time, other.seq).cmp(&(self.time, self.seq))
+    }
+}
+
+impl<M> PartialOrd for Event<M> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<M> PartialEq for Event<M> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl<M> Eq for Event<M> {}
+
+/// Handle to a running simulation.
+pub struct Simulation<N: SimulatedNode> {
+    log: ReplicaLogger,
+    rng: ChaChaRng,
+    now: Time,
+    tick_interval: Duration,
+    min_message_latency: Duration,
+    max_message_latency: Duration,
+    next_seq: u64,
+    events: BinaryHeap<Event<N::Message>>,
+    /// Messages that could not be delivered because of a partition or a crash.
+    held: Vec<(NodeId, NodeId, N::Message)>,
+    nodes: BTreeMap<NodeId, N>,
+    crashed: BTreeSet<NodeId>,
+    /// Partitioned links, stored in both directions.
+    partitions: BTreeSet<(NodeId, NodeId)>,
+    /// Height up to which the finalized blocks of a node were checked for safety.
+    checked_height: BTreeMap<NodeId, Height>,
+    /// The first finalized block observed at each height, and the node that finalized it.
+    finalized: BTreeMap<Height, (NodeId, N::BlockId)>,
+    trace: Vec<u8>,
+}
+
+impl<N: SimulatedNode> Simulation<N> {
+    pub fn nodes(&self) -> impl Iterator<Item = &NodeId> {
+        self.nodes.keys()
+    }
+
+    pub fn node(&self, node_id: &NodeId) -> &N {
+        self.nodes
+            .get(node_id)
+            .expect("Node is part of the simulation")
+    }
+
+    /// Current simulated time.
+    pub fn now(&self) -> Time {
+        self.now
+    }
+
+    /// Log of all processed events. Two runs with the same seed produce identical traces.
+    pub fn trace(&self) -> &[u8] {
+        &self.trace
+    }
+
+    /// Runs the steps in order. Returns the first safety or liveness violation.
+    pub fn run_scenario(
+        &mut self,
+        steps: impl IntoIterator<Item = Step>,
+    ) -> Result<(), SimulationError> {
+        for step in steps {
+            info!(self.log, "Simulation step: {:?}", step);
+            self.record(format_args!("step {:?}", step));
+            self.run_step(step)?;
+        }
+        Ok(())
+    }
+
+    fn run_step(&mut self, step: Step) -> Result<(), SimulationError> {
+        match step {
+            Step::Partition(a, b) => {
+                for x in &a {
+                    for y in &b {
+                        self.partitions.insert((*x, *y));
+                        self.partitions.insert((*y, *x));
+                    }
+                }
+            }
+            Step::Heal => {
+                self.partitions.clear();
+                self.release_held();
+            }
+            Step::Crash(node) => {
+                self.crashed.insert(node);
+            }
+            Step::Restart(node) => {
+                if self.crashed.remove(&node) {
+                    self.schedule_first_tick(node);
+                    self.release_held();
+                }
+            }
+            Step::RunFor(duration) => {
+                let until = self.now + duration;
+                self.run_until(until)?;
+            }
+            Step::AwaitFinalization { height, timeout } => {
+                let until = self.now + timeout;
+                while let Err(e) = self.check_finalized(height) {
+                    if self.now >= until {
+                        return Err(e);
+                    }
+                    let next = std::cmp::min(self.now + self.tick_interval, until);
+                    self.run_until(next)?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Processes all events up to and including the given time.
+    fn run_until(&mut self, until: Time) -> Result<(), SimulationError> {
+        while self.events.peek().is_some_and(|event| event.time <= until) {
+            let event = self.events.pop().expect("Peeked event exists");
+            self.now = event.time;
+            self.process(event.kind)?;
+        }
+        self.now = until;
+        Ok(())
+    }
+
+    fn process(&mut self, kind: EventKind<N::Message>) -> Result<(), SimulationError> {
+        match kind {
+            EventKind::Tick(node_id) => {
+                // A crashed node stops ticking until it is restarted.
+                if self.crashed.contains(&node_id) {
+                    return Ok(());
+                }
+                let now = self.now;
+                let messages = self
+                    .nodes
+                    .get_mut(&node_id)
+                    .expect("Node is part of the simulation")
+                    .on_tick(now);
+                self.record(format_args!("tick {node_id} sent {}", messages.len()));
+                let peers: Vec<_> = self
+                    .nodes
+                    .keys()
+                    .filter(|peer_id| **peer_id != node_id)
+                    .copied()
+                    .collect();
+                for message in messages {
+                    for peer_id in &peers {
+                        self.schedule_delivery(node_id, *peer_id, message.clone());
+                    }
+                }
+                self.schedule(now + self.tick_interval, EventKind::Tick(node_id));
+                self.check_safety(node_id)
+            }
+            EventKind::Deliver { from, to, message } => {
+                if self.crashed.contains(&to) || self.partitions.contains(&(from, to)) {
+                    self.record(format_args!("hold {from} -> {to}"));
+                    self.held.push((from, to, message));
+                    return Ok(());
+                }
+                self.record(format_args!("deliver {from} -> {to}: {message:?}"));
+                let now = self.now;
+                self.nodes
+                    .get_mut(&to)
+                    .expect("Node is part of the simulation")
+                    .on_message(from, message, now);
+                Ok(())
+            }
+        }
+    }
+
+    /// Checks the blocks the node finalized since the last check against the blocks
+    /// finalized by all other nodes. Fails if two nodes finalized different blocks at
+    /// the same height.
+    fn check_safety(&mut self, node_id: NodeId) -> Result<(), SimulationError> {
+        let node = self.node(&node_id);
+        let finalized_height = node.finalized_height();
+        let checked_height = self
+            .checked_height
+            .get(&node_id)
+            .copied()
+            .unwrap_or_default();
+        let mut newly_finalized = Vec::new();
+        let mut height = checked_height.increment();
+        while height <= finalized_height {
+            if let Some(block) = node.finalized_block(height) {
+                newly_finalized.push((height, block));
+            }
+            height = height.increment();
+        }
+        self.checked_height.insert(node_id, finalized_height);
+
+        for (height, block) in newly_finalized {
+            self.record(format_args!("finalized {node_id} {height} {block:?}"));
+            match self.finalized.get(&height) {
+                Some((other_id, other_block)) if *other_block != block => {
+                    return Err(SimulationError::Safety(format!(
+                        "{node_id} finalized {block:?} at height {height} but {other_id} finalized {other_block:?}"
+                    )));
+                }
+                Some(_) => (),
+                None => {
+                    self.finalized.insert(height, (node_id, block));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks that every running node finalized at least the given height.
+    fn check_finalized(&self, height: Height) -> Result<(), SimulationError> {
+        for (node_id, node) in &self.nodes {
+            if self.crashed.contains(node_id) {
+                continue;
+            }
+            let finalized_height = node.finalized_height();
+            if finalized_height < height {
+                return Err(SimulationError::Liveness(format!(
+                    "{node_id} finalized height {finalized_height} instead of {height} at {}",
+                    self.now
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Schedules all held back messages that can be delivered again.
+    fn release_held(&mut self) {
+        let held = std::mem::take(&mut self.held);
+        for (from, to, message) in held {
+            if self.crashed.contains(&to) || self.partitions.contains(&(from, to)) {
+                self.held.push((from, to, message));
+            } else {
+                self.schedule_delivery(from, to, message);
+            }
+        }
+    }
+
+    fn schedule_first_tick(&mut self, node_id: NodeId) {
+        let jitter = self.rng.gen_range(Duration::ZERO..self.tick_interval);
+        self.schedule(self.now + jitter, EventKind::Tick(node_id));
+    }
+
+    fn schedule_delivery(&mut self, from: NodeId, to: NodeId, message: N::Message) {
+        let latency = self
+            .rng
+            .gen_range(self.min_message_latency..=self.max_message_latency);
+        self.schedule(self.now + latency, EventKind::Deliver { from, to, message });
+    }
+
+    fn schedule(&mut self, time: Time, kind: EventKind<N::Message>) {
+        let seq = self.next_seq;
+        self.next_seq += 1;
+        self.events.push(Event { time, seq, kind });
+    }
+
+    fn record(&mut self, event: fmt::Arguments<'_>) {
+        writeln!(
+            self.trace,
+            "{} {}",
+            self.now.as_nanos_since_unix_epoch(),
+            event
+        )
+        .expect("Writing to a Vec does not fail");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_logger::no_op_logger;
+    use ic_types_test_utils::ids::node_test_id;
+
+    /// Finalizes a height once it received the previous height from all peers. A node
+    /// configured with `fork` finalizes a block of its own at that height.
+    struct LockStepNode {
+        num_peers: usize,
+        fork: Option<Height>,
+        received: BTreeMap<Height, BTreeSet<NodeId>>,
+        finalized: BTreeMap<Height, u64>,
+    }
+
+    impl LockStepNode {
+        fn new(num_peers: usize) -> Self {
+            Self {
+                num_peers,
+                fork: None,
+                received: BTreeMap::new(),
+                finalized: BTreeMap::from([(Height::from(0), 0)]),
+            }
+        }
+    }
+
+    impl SimulatedNode for LockStepNode {
+        type Message = Height;
+        type BlockId = u64;
+
+        fn on_message(&mut self, from: NodeId, message: Height, _now: Time) {
+            self.received.entry(message).or_default().insert(from);
+        }
+
+        fn on_tick(&mut self, _now: Time) -> Vec<Height> {
+            let height = self.finalized_height();
+            let peers = self.received.get(&height).map_or(0, BTreeSet::len);
+            if peers == self.num_peers {
+                let block = if self.fork == Some(height.increment()) {
+                    1
+                } else {
+                    0
+                };
+                self.finalized.insert(height.increment(), block);
+            }
+            vec![self.finalized_height()]
+        }
+
+        fn finalized_height(&self) -> Height {
+            *self.finalized.keys().last().unwrap()
+        }
+
+        fn finalized_block(&self, height: Height) -> Option<u64> {
+            self.finalized.get(&height).copied()
+        }
+    }
+
+    fn simulation(seed: u64, fork: Option<Height>) -> Simulation<LockStepNode> {
+        let mut nodes: Vec<_> = (0..4)
+            .map(|i| (node_test_id(i), LockStepNode::new(3)))
+            .collect();
+        nodes[0].1.fork = fork;
+        SimulationBuilder::new(no_op_logger())
+            .seed(seed)
+            .add_nodes(nodes)
+            .build()
+    }
+
+    #[test]
+    fn same_seed_produces_identical_trace() {
+        let run = |seed| {
+            let mut simulation = simulation(seed, None);
+            simulation
+                .run_scenario([Step::AwaitFinalization {
+                    height: Height::from(20),
+                    timeout: Duration::from_secs(60),
+                }])
+                .unwrap();
+            simulation.trace().to_vec()
+        };
+        assert_eq!(run(7), run(7));
+        assert_ne!(run(7), run(8));
+    }
+
+    #[test]
+    fn conflicting_finalization_is_detected() {
+        let mut simulation = simulation(0, Some(Height::from(3)));
+        let result = simulation.run_scenario([Step::AwaitFinalization {
+            height: Height::from(10),
+            timeout: Duration::from_secs(60),
+        }]);
+        assert!(matches!(result, Err(SimulationError::Safety(_))));
+    }
+
+    #[test]
+    fn partition_stalls_until_healed() {
+        let mut simulation = simulation(0, None);
+        let result = simulation.run_scenario([
+            Step::Partition(
+                vec![node_test_id(0), node_test_id(1)],
+                vec![node_test_id(2), node_test_id(3)],
+            ),
+            Step::AwaitFinalization {
+                height: Height::from(2),
+                timeout: Duration::from_secs(10),
+            },
+        ]);
+        assert!(matches!(result, Err(SimulationError::Liveness(_))));
+
+        simulation
+            .run_scenario([
+                Step::Heal,
+                Step::AwaitFinalization {
+                    height: Height::from(10),
+                    timeout: Duration::from_secs(60),
+                },
+            ])
+            .unwrap();
+    }
+
+    #[test]
+    fn restarted_node_catches_up() {
+        let mut simulation = simulation(0, None);
+        let crashed = node_test_id(3);
+        simulation
+            .run_scenario([
+                Step::AwaitFinalization {
+                    height: Height::from(2),
+                    timeout: Duration::from_secs(60),
+                },
+                Step::Crash(crashed),
+                Step::RunFor(Duration::from_secs(5)),
+            ])
+            .unwrap();
+        let stalled_height = simulation.node(&crashed).finalized_height();
+
+        simulation
+            .run_scenario([
+                Step::Restart(crashed),
+                Step::AwaitFinalization {
+                    height: stalled_height + Height::from(5),
+                    timeout: Duration::from_secs(60),
+                },
+            ])
+            .unwrap();
+    }
+}
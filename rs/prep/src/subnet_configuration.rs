@@ -320,6 +320,7 @@ impl SubnetConfig {
             ssh_backup_access: self.ssh_backup_access,
             ecdsa_config: None,
             chain_key_config: self.chain_key_config,
+            http_outcalls_policy: None,
         };
 
         let dkg_dealing_encryption_pubkeys: BTreeMap<_, _> = initialized_nodes
@@ -88,6 +88,10 @@ pub struct SubnetRecord {
     /// key. If the removed key is not held by another subnet, it will be lost.
     #[prost(message, optional, tag = "29")]
     pub chain_key_config: ::core::option::Option<ChainKeyConfig>,
+    /// Destination policy enforced by the HTTPS outcalls adapter of every node in
+    /// this subnet. If unset, any public destination may be contacted.
+    #[prost(message, optional, tag = "30")]
+    pub http_outcalls_policy: ::core::option::Option<HttpOutcallsPolicy>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -325,6 +329,34 @@ pub struct SubnetFeatures {
     #[prost(bool, optional, tag = "9")]
     pub sev_enabled: ::core::option::Option<bool>,
 }
+/// Restricts the destinations canisters of a subnet may contact via HTTPS outcalls.
+#[allow(clippy::derive_partial_eq_without_eq)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct HttpOutcallsPolicy {
+    /// If non-empty, only these domains (and their subdomains) may be contacted.
+    #[prost(string, repeated, tag = "1")]
+    pub allowed_domains: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// Domains (and their subdomains) that must never be contacted. Takes precedence
+    /// over `allowed_domains`.
+    #[prost(string, repeated, tag = "2")]
+    pub denied_domains: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// If non-empty, the destination must resolve only to addresses within these
+    /// CIDR ranges (e.g. "203.0.113.0/24", "2001:db8::/32").
+    #[prost(string, repeated, tag = "3")]
+    pub allowed_ip_ranges: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// CIDR ranges that must never be contacted. Takes precedence over
+    /// `allowed_ip_ranges`.
+    #[prost(string, repeated, tag = "4")]
+    pub denied_ip_ranges: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// If `true`, destinations resolving to private, loopback, link-local or
+    /// otherwise non-globally-routable addresses are rejected.
+    #[prost(bool, tag = "5")]
+    pub block_private_addresses: bool,
+    /// Maximum number of HTTP redirects the adapter follows. Zero means that
+    /// redirects are returned to the canister as-is.
+    #[prost(uint32, tag = "6")]
+    pub max_redirects: u32,
+}
 /// Per subnet ECDSA configuration
 ///
 /// Deprecated; please use ChainKeyConfig instead.
@@ -660,6 +660,9 @@ pub struct CanisterStateBits {
     pub priority_credit: i64,
     #[prost(enumeration = "LongExecutionMode", tag = "49")]
     pub long_execution_mode: i32,
+    /// Backtrace visibility for the canister.
+    #[prost(enumeration = "BacktraceVisibility", tag = "50")]
+    pub backtrace_visibility: i32,
     #[prost(oneof = "canister_state_bits::CanisterStatus", tags = "11, 12, 13")]
     pub canister_status: ::core::option::Option<canister_state_bits::CanisterStatus>,
 }
@@ -834,6 +837,38 @@ impl LogVisibility {
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
+pub enum BacktraceVisibility {
+    Unspecified = 0,
+    Disabled = 1,
+    Controllers = 2,
+    Public = 3,
+}
+impl BacktraceVisibility {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            BacktraceVisibility::Unspecified => "BACKTRACE_VISIBILITY_UNSPECIFIED",
+            BacktraceVisibility::Disabled => "BACKTRACE_VISIBILITY_DISABLED",
+            BacktraceVisibility::Controllers => "BACKTRACE_VISIBILITY_CONTROLLERS",
+            BacktraceVisibility::Public => "BACKTRACE_VISIBILITY_PUBLIC",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "BACKTRACE_VISIBILITY_UNSPECIFIED" => Some(Self::Unspecified),
+            "BACKTRACE_VISIBILITY_DISABLED" => Some(Self::Disabled),
+            "BACKTRACE_VISIBILITY_CONTROLLERS" => Some(Self::Controllers),
+            "BACKTRACE_VISIBILITY_PUBLIC" => Some(Self::Public),
+            _ => None,
+        }
+    }
+}
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
 pub enum LongExecutionMode {
     Unspecified = 0,
     Opportunistic = 1,
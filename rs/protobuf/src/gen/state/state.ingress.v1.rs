@@ -140,6 +140,9 @@ pub struct Ingress {
     /// Represents the id of the canister that the message is targeting.
     #[prost(message, optional, tag = "7")]
     pub effective_canister_id: ::core::option::Option<super::super::super::types::v1::CanisterId>,
+    /// Whether the call tree rooted in this message is traced.
+    #[prost(bool, tag = "8")]
+    pub traced: bool,
 }
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
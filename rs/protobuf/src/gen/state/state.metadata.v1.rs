@@ -213,6 +213,11 @@ pub struct CanisterHttpRequestContext {
     pub max_response_bytes: ::core::option::Option<u64>,
     #[prost(message, optional, tag = "10")]
     pub transform_context: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
+    /// Set if only this node makes the request.
+    #[prost(message, optional, tag = "11")]
+    pub non_replicated_node: ::core::option::Option<super::super::super::types::v1::NodeId>,
+    #[prost(uint64, optional, tag = "12")]
+    pub response_offset: ::core::option::Option<u64>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -585,6 +590,10 @@ pub enum HttpMethod {
     Get = 1,
     Post = 2,
     Head = 3,
+    Put = 4,
+    Patch = 5,
+    Delete = 6,
+    Options = 7,
 }
 impl HttpMethod {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -597,6 +606,10 @@ impl HttpMethod {
             HttpMethod::Get => "HTTP_METHOD_GET",
             HttpMethod::Post => "HTTP_METHOD_POST",
             HttpMethod::Head => "HTTP_METHOD_HEAD",
+            HttpMethod::Put => "HTTP_METHOD_PUT",
+            HttpMethod::Patch => "HTTP_METHOD_PATCH",
+            HttpMethod::Delete => "HTTP_METHOD_DELETE",
+            HttpMethod::Options => "HTTP_METHOD_OPTIONS",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -606,6 +619,10 @@ impl HttpMethod {
             "HTTP_METHOD_GET" => Some(Self::Get),
             "HTTP_METHOD_POST" => Some(Self::Post),
             "HTTP_METHOD_HEAD" => Some(Self::Head),
+            "HTTP_METHOD_PUT" => Some(Self::Put),
+            "HTTP_METHOD_PATCH" => Some(Self::Patch),
+            "HTTP_METHOD_DELETE" => Some(Self::Delete),
+            "HTTP_METHOD_OPTIONS" => Some(Self::Options),
             _ => None,
         }
     }
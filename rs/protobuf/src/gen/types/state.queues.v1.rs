@@ -67,6 +67,9 @@ pub struct RequestMetadata {
     /// Reserved for future use (guaranteed replies won't be affected).
     #[prost(uint64, optional, tag = "3")]
     pub call_subtree_deadline_nanos: ::core::option::Option<u64>,
+    /// Identifies the call tree that the request is part of, for tracing purposes.
+    #[prost(bytes = "vec", optional, tag = "4")]
+    pub trace_id: ::core::option::Option<::prost::alloc::vec::Vec<u8>>,
 }
 #[allow(clippy::derive_partial_eq_without_eq)]
 #[derive(Clone, PartialEq, ::prost::Message)]
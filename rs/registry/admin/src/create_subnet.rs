@@ -1,5 +1,6 @@
 use crate::helpers::{
-    get_proposer_and_sender, parse_proposal_url, shortened_pids_string, summary_from_string_or_file,
+    get_proposer_and_sender, parse_http_outcalls_policy, parse_proposal_url, shortened_pids_string,
+    summary_from_string_or_file,
 };
 use crate::types::{ProposalMetadata, ProposalPayload};
 use crate::ProposalTitle;
@@ -11,7 +12,9 @@ use ic_config::subnet_config::SchedulerConfig;
 use ic_management_canister_types::MasterPublicKeyId;
 use ic_nns_common::types::NeuronId;
 use ic_prep_lib::subnet_configuration::get_default_config_params;
-use ic_protobuf::registry::subnet::v1::SubnetFeatures as SubnetFeaturesPb;
+use ic_protobuf::registry::subnet::v1::{
+    HttpOutcallsPolicy as HttpOutcallsPolicyPb, SubnetFeatures as SubnetFeaturesPb,
+};
 use ic_registry_subnet_features::SubnetFeatures;
 use ic_registry_subnet_type::SubnetType;
 use ic_types::{NodeId, PrincipalId, ReplicaVersion};
@@ -151,6 +154,22 @@ pub(crate) struct ProposeToCreateSubnetCmd {
     #[clap(long, multiple_values(true))]
     ssh_backup_access: Vec<String>,
 
+    /// The policy restricting the destinations of HTTPS outcalls made by
+    /// canisters on this subnet, given as JSON. For example:
+    ///
+    /// ```
+    /// --http-outcalls-policy '{
+    ///     "allowed_domains": ["example.com"],
+    ///     "denied_domains": [],
+    ///     "allowed_ip_ranges": [],
+    ///     "denied_ip_ranges": ["10.0.0.0/8"],
+    ///     "block_private_addresses": true,
+    ///     "max_redirects": 0
+    /// }'
+    /// ```
+    #[clap(long, value_parser = parse_http_outcalls_policy)]
+    pub http_outcalls_policy: Option<HttpOutcallsPolicyPb>,
+
     /// The maximum number of canisters that are allowed to be created in this
     /// subnet.
     #[clap(long)]
@@ -311,6 +330,7 @@ impl ProposeToCreateSubnetCmd {
             features: SubnetFeaturesPb::from(self.features.expect("features must be specified.")),
             ssh_readonly_access: self.ssh_readonly_access.clone(),
             ssh_backup_access: self.ssh_backup_access.clone(),
+            http_outcalls_policy: self.http_outcalls_policy.clone(),
             max_number_of_canisters: self.max_number_of_canisters.unwrap_or_default(),
             chain_key_config,
 
@@ -362,6 +382,7 @@ mod tests {
             node_ids: vec![],
             ssh_readonly_access: vec![],
             ssh_backup_access: vec![],
+            http_outcalls_policy: None,
             proposer: None,
             proposal_url: None,
             proposal_title: None,
@@ -5,7 +5,8 @@ use ic_nervous_system_common_test_keys::TEST_NEURON_1_OWNER_KEYPAIR;
 use ic_nns_common::types::NeuronId;
 use ic_nns_governance::init::TEST_NEURON_1_ID;
 use ic_protobuf::registry::subnet::v1::{
-    SubnetListRecord as SubnetListRecordPb, SubnetRecord as SubnetRecordPb,
+    HttpOutcallsPolicy as HttpOutcallsPolicyPb, SubnetListRecord as SubnetListRecordPb,
+    SubnetRecord as SubnetRecordPb,
 };
 use ic_registry_keys::{make_subnet_list_record_key, make_subnet_record_key};
 use ic_registry_nns_data_provider::registry::RegistryCanister;
@@ -74,6 +75,19 @@ pub(crate) fn parse_proposal_url(url: Option<Url>) -> String {
     }
 }
 
+/// Parses an HTTPS outcalls policy given as JSON, e.g.
+/// `{"allowed_domains": ["example.com"], "denied_domains": [],
+/// "allowed_ip_ranges": [], "denied_ip_ranges": ["10.0.0.0/8"],
+/// "block_private_addresses": true, "max_redirects": 0}`.
+pub(crate) fn parse_http_outcalls_policy(value: &str) -> Result<HttpOutcallsPolicyPb, String> {
+    serde_json::from_str(value).map_err(|err| {
+        format!(
+            "Cannot parse `{}` as an HTTPS outcalls policy: {}",
+            value, err
+        )
+    })
+}
+
 /// Selects a `(NeuronId, Sender)` pair to submit the proposal. If
 /// `use_test_neuron` is true, it returns `TEST_NEURON_1_ID` and a `Sender`
 /// based on that test neuron's private key, otherwise it validates and returns
@@ -9,7 +9,7 @@ use ic_nns_common::types::NeuronId;
 use ic_protobuf::registry::{
     node::v1::IPv4InterfaceConfig,
     provisional_whitelist::v1::ProvisionalWhitelist as ProvisionalWhitelistProto,
-    subnet::v1::SubnetRecord as SubnetRecordProto,
+    subnet::v1::{HttpOutcallsPolicy, SubnetRecord as SubnetRecordProto},
 };
 use ic_registry_nns_data_provider::registry::RegistryCanister;
 use ic_registry_provisional_whitelist::ProvisionalWhitelist;
@@ -77,6 +77,7 @@ pub(crate) struct SubnetRecord {
     pub ssh_backup_access: Vec<String>,
     pub ecdsa_config: Option<EcdsaConfig>,
     pub chain_key_config: Option<ChainKeyConfig>,
+    pub http_outcalls_policy: Option<HttpOutcallsPolicy>,
 }
 
 impl SubnetRecord {
@@ -135,6 +136,7 @@ impl From<&SubnetRecordProto> for SubnetRecord {
                 .chain_key_config
                 .as_ref()
                 .map(|c| c.clone().try_into().unwrap()),
+            http_outcalls_policy: value.http_outcalls_policy.clone(),
         }
     }
 }
@@ -1,6 +1,6 @@
 use crate::helpers::{
-    get_proposer_and_sender, get_subnet_record, parse_proposal_url, shortened_subnet_string,
-    summary_from_string_or_file,
+    get_proposer_and_sender, get_subnet_record, parse_http_outcalls_policy, parse_proposal_url,
+    shortened_subnet_string, summary_from_string_or_file,
 };
 use crate::types::{ProposalMetadata, ProposalPayload, SubnetRecord};
 use crate::{ProposalTitle, SubnetDescriptor};
@@ -10,6 +10,7 @@ use ic_admin_derive::derive_common_proposal_fields;
 use ic_canister_client::{Agent, Sender};
 use ic_management_canister_types::MasterPublicKeyId;
 use ic_nns_common::types::NeuronId;
+use ic_protobuf::registry::subnet::v1::HttpOutcallsPolicy as HttpOutcallsPolicyPb;
 use ic_registry_nns_data_provider::registry::RegistryCanister;
 use ic_registry_subnet_features::SubnetFeatures;
 use ic_types::SubnetId;
@@ -171,6 +172,12 @@ pub(crate) struct ProposeToUpdateSubnetCmd {
     #[clap(long, multiple_values(true))]
     ssh_backup_access: Option<Vec<String>>,
 
+    /// If set, replaces the policy restricting the destinations of HTTPS
+    /// outcalls made by canisters on this subnet. The policy is given as JSON,
+    /// see `ProposeToCreateSubnetCmd` for the format.
+    #[clap(long, value_parser = parse_http_outcalls_policy)]
+    pub http_outcalls_policy: Option<HttpOutcallsPolicyPb>,
+
     /// If set, the created proposal will contain a desired override of that
     /// field to the value set. See `ProposeToCreateSubnetCmd` for the semantic
     /// of this field.
@@ -337,6 +344,7 @@ impl ProposeToUpdateSubnetCmd {
 
             ssh_readonly_access: self.ssh_readonly_access.clone(),
             ssh_backup_access: self.ssh_backup_access.clone(),
+            http_outcalls_policy: self.http_outcalls_policy.clone(),
             max_number_of_canisters: self.max_number_of_canisters,
 
             chain_key_config,
@@ -411,6 +419,7 @@ mod tests {
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            http_outcalls_policy: None,
             chain_key_config: None,
             chain_key_signing_enable: None,
             chain_key_signing_disable: None,
@@ -449,6 +458,7 @@ mod tests {
             features: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            http_outcalls_policy: None,
             max_number_of_canisters: None,
         }
     }
@@ -714,4 +724,44 @@ mod tests {
             },
         );
     }
+
+    #[test]
+    fn cli_to_payload_conversion_works_for_http_outcalls_policy() {
+        // Boilerplate stuff
+        let subnet_id = SubnetId::from(PrincipalId::new_user_test_id(1));
+        let subnet_record = SubnetRecord::default();
+
+        // Field under test
+        let http_outcalls_policy = parse_http_outcalls_policy(
+            r#"{
+                "allowed_domains": ["example.com"],
+                "denied_domains": [],
+                "allowed_ip_ranges": [],
+                "denied_ip_ranges": ["10.0.0.0/8"],
+                "block_private_addresses": true,
+                "max_redirects": 2
+            }"#,
+        )
+        .unwrap();
+
+        // Run code under test
+        let cmd = ProposeToUpdateSubnetCmd {
+            http_outcalls_policy: Some(http_outcalls_policy),
+            ..empty_propose_to_update_subnet_cmd(subnet_id)
+        };
+
+        assert_eq!(
+            cmd.new_payload_for_subnet(subnet_id, subnet_record),
+            do_update_subnet::UpdateSubnetPayload {
+                http_outcalls_policy: Some(HttpOutcallsPolicyPb {
+                    allowed_domains: vec!["example.com".to_string()],
+                    denied_ip_ranges: vec!["10.0.0.0/8".to_string()],
+                    block_private_addresses: true,
+                    max_redirects: 2,
+                    ..Default::default()
+                }),
+                ..make_empty_update_payload(subnet_id)
+            },
+        );
+    }
 }
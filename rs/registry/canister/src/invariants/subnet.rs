@@ -9,11 +9,13 @@ use crate::invariants::common::{
 
 use ic_base_types::{NodeId, PrincipalId};
 use ic_nns_common::registry::{decode_or_panic, MAX_NUM_SSH_KEYS};
-use ic_protobuf::registry::subnet::v1::{SubnetRecord, SubnetType};
+use ic_protobuf::registry::subnet::v1::{HttpOutcallsPolicy, SubnetRecord, SubnetType};
 use ic_registry_keys::{make_node_record_key, make_subnet_record_key, SUBNET_RECORD_KEY_PREFIX};
+use ipnet::IpNet;
 
 /// Subnet invariants hold iff:
 ///    * Each SSH key access list does not contain more than 50 keys
+///    * Each IP range of the HTTPS outcalls policy is a valid CIDR range
 ///    * Subnet membership contains no repetition
 ///    * Each node belongs to at most one subnet
 ///    * Each subnet contains at least one node
@@ -53,6 +55,16 @@ pub(crate) fn check_subnet_invariants(
             });
         }
 
+        if let Some(policy) = &subnet_record.http_outcalls_policy {
+            check_http_outcalls_policy(policy).map_err(|msg| InvariantCheckError {
+                msg: format!(
+                    "Subnet {:} has an invalid HTTPS outcalls policy: {}",
+                    subnet_id, msg
+                ),
+                source: None,
+            })?;
+        }
+
         let num_nodes = subnet_record.membership.len();
         let mut subnet_members: HashSet<NodeId> = subnet_record
             .membership
@@ -118,6 +130,21 @@ pub(crate) fn check_subnet_invariants(
     Ok(())
 }
 
+// Checks that all IP ranges of the policy are valid CIDR ranges, as the
+// adapter cannot enforce a policy it fails to parse.
+fn check_http_outcalls_policy(policy: &HttpOutcallsPolicy) -> Result<(), String> {
+    for range in policy
+        .allowed_ip_ranges
+        .iter()
+        .chain(policy.denied_ip_ranges.iter())
+    {
+        range
+            .parse::<IpNet>()
+            .map_err(|err| format!("`{}` is not a valid CIDR range: {}", range, err))?;
+    }
+    Ok(())
+}
+
 // Return all subnet records in the snapshot
 pub(crate) fn get_subnet_records_map(
     snapshot: &RegistrySnapshot,
@@ -131,3 +158,31 @@ pub(crate) fn get_subnet_records_map(
     }
     subnets
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn http_outcalls_policy_ip_ranges_must_be_cidr_ranges() {
+        let valid = HttpOutcallsPolicy {
+            allowed_ip_ranges: vec!["203.0.113.0/24".to_string()],
+            denied_ip_ranges: vec!["2001:db8::/32".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(check_http_outcalls_policy(&valid), Ok(()));
+
+        for range in ["203.0.113.1", "10.0.0.0/33", "example.com"] {
+            let allowed = HttpOutcallsPolicy {
+                allowed_ip_ranges: vec![range.to_string()],
+                ..Default::default()
+            };
+            assert!(check_http_outcalls_policy(&allowed).is_err());
+            let denied = HttpOutcallsPolicy {
+                denied_ip_ranges: vec![range.to_string()],
+                ..Default::default()
+            };
+            assert!(check_http_outcalls_policy(&denied).is_err());
+        }
+    }
+}
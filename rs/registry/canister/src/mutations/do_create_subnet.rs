@@ -16,7 +16,8 @@ use ic_protobuf::registry::{
     node::v1::NodeRecord,
     subnet::v1::{
         CatchUpPackageContents, ChainKeyConfig as ChainKeyConfigPb, EcdsaConfig as EcdsaConfigPb,
-        SubnetFeatures as SubnetFeaturesPb, SubnetRecord,
+        HttpOutcallsPolicy as HttpOutcallsPolicyPb, SubnetFeatures as SubnetFeaturesPb,
+        SubnetRecord,
     },
 };
 use ic_registry_keys::{
@@ -325,6 +326,9 @@ pub struct CreateSubnetPayload {
     pub ssh_readonly_access: Vec<String>,
     pub ssh_backup_access: Vec<String>,
 
+    /// Restricts the destinations of HTTPS outcalls made by the subnet's canisters.
+    pub http_outcalls_policy: Option<HttpOutcallsPolicyPb>,
+
     // Deprecated. Please use `chain_key_config` instead.
     //
     // TODO[NNS1-3022]: Make this field obsolete.
@@ -652,6 +656,7 @@ impl From<CreateSubnetPayload> for SubnetRecord {
                 })
                 .map(ChainKeyConfigPb::from),
             ecdsa_config: None, // obsolete (chain_key_config is used instead now)
+            http_outcalls_policy: val.http_outcalls_policy,
         }
     }
 }
@@ -8,7 +8,8 @@ use dfn_core::println;
 use ic_base_types::{subnet_id_into_protobuf, SubnetId};
 use ic_management_canister_types::{EcdsaKeyId, MasterPublicKeyId};
 use ic_protobuf::registry::subnet::v1::{
-    SubnetFeatures as SubnetFeaturesPb, SubnetRecord as SubnetRecordPb,
+    HttpOutcallsPolicy as HttpOutcallsPolicyPb, SubnetFeatures as SubnetFeaturesPb,
+    SubnetRecord as SubnetRecordPb,
 };
 use ic_registry_keys::{make_chain_key_signing_subnet_list_key, make_subnet_record_key};
 use ic_registry_subnet_features::{
@@ -363,6 +364,9 @@ pub struct UpdateSubnetPayload {
     pub ssh_readonly_access: Option<Vec<String>>,
     pub ssh_backup_access: Option<Vec<String>>,
 
+    /// Restricts the destinations of HTTPS outcalls made by the subnet's canisters.
+    pub http_outcalls_policy: Option<HttpOutcallsPolicyPb>,
+
     // TODO(NNS1-2444): The fields below are deprecated and they are not read anywhere.
     pub max_artifact_streams_per_peer: Option<u32>,
     pub max_chunk_wait_ms: Option<u32>,
@@ -559,6 +563,7 @@ fn merge_subnet_record(
         max_number_of_canisters,
         ssh_readonly_access,
         ssh_backup_access,
+        http_outcalls_policy,
         // Deprecated/unused values follow
         max_artifact_streams_per_peer: _,
         max_chunk_wait_ms: _,
@@ -614,6 +619,8 @@ fn merge_subnet_record(
     maybe_set!(subnet_record, ssh_readonly_access);
     maybe_set!(subnet_record, ssh_backup_access);
 
+    maybe_set_option!(subnet_record, http_outcalls_policy);
+
     subnet_record
 }
 
@@ -671,6 +678,7 @@ mod tests {
             max_number_of_canisters: None,
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            http_outcalls_policy: None,
             chain_key_config: None,
             chain_key_signing_enable: None,
             chain_key_signing_disable: None,
@@ -712,6 +720,7 @@ mod tests {
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            http_outcalls_policy: None,
         };
 
         let ecdsa_config = Some(EcdsaConfig {
@@ -725,6 +734,13 @@ mod tests {
         let ecdsa_config_pb = ecdsa_config.clone().map(EcdsaConfigPb::from);
         let chain_key_config_pb = ecdsa_config_pb.clone().map(ChainKeyConfigPb::from);
 
+        let http_outcalls_policy = Some(HttpOutcallsPolicyPb {
+            allowed_domains: vec!["example.com".to_string()],
+            denied_ip_ranges: vec!["10.0.0.0/8".to_string()],
+            block_private_addresses: true,
+            ..Default::default()
+        });
+
         let payload = UpdateSubnetPayload {
             subnet_id: SubnetId::from(
                 PrincipalId::from_str(
@@ -760,6 +776,7 @@ mod tests {
             max_number_of_canisters: Some(10),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
+            http_outcalls_policy: http_outcalls_policy.clone(),
             chain_key_config: None,
             chain_key_signing_enable: None,
             chain_key_signing_disable: None,
@@ -807,6 +824,7 @@ mod tests {
                 max_number_of_canisters: 10,
                 ssh_readonly_access: vec!["pub_key_0".to_string()],
                 ssh_backup_access: vec!["pub_key_1".to_string()],
+                http_outcalls_policy,
             }
         );
     }
@@ -836,6 +854,7 @@ mod tests {
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            http_outcalls_policy: None,
         };
 
         let payload = UpdateSubnetPayload {
@@ -866,6 +885,7 @@ mod tests {
             max_number_of_canisters: Some(50),
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            http_outcalls_policy: None,
             chain_key_config: None,
             chain_key_signing_enable: None,
             chain_key_signing_disable: None,
@@ -905,6 +925,7 @@ mod tests {
                 ssh_backup_access: vec![],
                 ecdsa_config: None,
                 chain_key_config: None,
+                http_outcalls_policy: None,
             }
         );
     }
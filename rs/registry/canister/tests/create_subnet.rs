@@ -547,6 +547,7 @@ fn make_create_subnet_payload(node_ids: Vec<NodeId>) -> CreateSubnetPayload {
         max_number_of_canisters: 0,
         ssh_readonly_access: vec![],
         ssh_backup_access: vec![],
+        http_outcalls_policy: None,
         ecdsa_config: None,
         chain_key_config: None,
         // Unused section follows
@@ -86,6 +86,7 @@ fn test_the_anonymous_user_cannot_update_a_subnets_configuration() {
             max_number_of_canisters: Some(10),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
+            http_outcalls_policy: None,
             chain_key_config: None,
             chain_key_signing_enable: None,
             chain_key_signing_disable: None,
@@ -169,6 +170,7 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            http_outcalls_policy: None,
         };
 
         // An attacker got a canister that is trying to pass for the governance
@@ -219,6 +221,7 @@ fn test_a_canister_other_than_the_governance_canister_cannot_update_a_subnets_co
             max_number_of_canisters: Some(100),
             ssh_readonly_access: None,
             ssh_backup_access: None,
+            http_outcalls_policy: None,
             chain_key_config: None,
             chain_key_signing_enable: None,
             chain_key_signing_disable: None,
@@ -301,6 +304,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                             ssh_backup_access: vec![],
                             ecdsa_config: None,
                             chain_key_config: None,
+                            http_outcalls_policy: None,
                         }),
                     )],
                     preconditions: vec![],
@@ -342,6 +346,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
             max_number_of_canisters: Some(42),
             ssh_readonly_access: Some(vec!["pub_key_0".to_string()]),
             ssh_backup_access: Some(vec!["pub_key_1".to_string()]),
+            http_outcalls_policy: None,
             chain_key_config: None,
             chain_key_signing_enable: None,
             chain_key_signing_disable: None,
@@ -400,6 +405,7 @@ fn test_the_governance_canister_can_update_a_subnets_configuration() {
                 ssh_backup_access: vec!["pub_key_1".to_string()],
                 ecdsa_config: None,
                 chain_key_config: None,
+                http_outcalls_policy: None,
             }
         );
 
@@ -457,6 +463,7 @@ fn test_subnets_configuration_ecdsa_fields_are_updated_correctly_legacy() {
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            http_outcalls_policy: None,
         };
 
         // Just create the registry canister and wait until the subnet_handler ID is
@@ -709,6 +716,7 @@ fn test_subnets_configuration_chain_key_fields_are_updated_correctly(key_id: Mas
             ssh_backup_access: vec![],
             ecdsa_config: None,
             chain_key_config: None,
+            http_outcalls_policy: None,
         };
 
         // Just create the registry canister and wait until the subnet_handler ID is
@@ -924,6 +932,7 @@ fn empty_update_subnet_payload(subnet_id: SubnetId) -> UpdateSubnetPayload {
         max_number_of_canisters: None,
         ssh_readonly_access: None,
         ssh_backup_access: None,
+        http_outcalls_policy: None,
         ecdsa_config: None,
         ecdsa_key_signing_enable: None,
         ecdsa_key_signing_disable: None,
@@ -6,7 +6,7 @@ use ic_protobuf::{
     registry::{
         node::v1::NodeRecord,
         replica_version::v1::ReplicaVersionRecord,
-        subnet::v1::{CatchUpPackageContents, SubnetListRecord, SubnetRecord},
+        subnet::v1::{CatchUpPackageContents, HttpOutcallsPolicy, SubnetListRecord, SubnetRecord},
     },
     types::v1::SubnetId as SubnetIdProto,
 };
@@ -80,6 +80,13 @@ pub trait SubnetRegistry {
         version: RegistryVersion,
     ) -> RegistryClientResult<ChainKeyConfig>;
 
+    /// Returns the destination policy for HTTPS outcalls
+    fn get_http_outcalls_policy(
+        &self,
+        subnet_id: SubnetId,
+        version: RegistryVersion,
+    ) -> RegistryClientResult<HttpOutcallsPolicy>;
+
     /// Returns notarization delay settings:
     /// - the unit delay for blockmaker;
     /// - the initial delay for notary, to give time to rank-0 block
@@ -256,6 +263,16 @@ impl<T: RegistryClient + ?Sized> SubnetRegistry for T {
             .map(SubnetFeatures::from))
     }
 
+    fn get_http_outcalls_policy(
+        &self,
+        subnet_id: SubnetId,
+        version: RegistryVersion,
+    ) -> RegistryClientResult<HttpOutcallsPolicy> {
+        let bytes = self.get_value(&make_subnet_record_key(subnet_id), version);
+        let subnet = deserialize_registry_value::<SubnetRecord>(bytes)?;
+        Ok(subnet.and_then(|subnet| subnet.http_outcalls_policy))
+    }
+
     fn get_chain_key_config(
         &self,
         subnet_id: SubnetId,
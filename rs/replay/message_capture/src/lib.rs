@@ -0,0 +1,253 @@
+//! A message capture records a single canister message together with
+//! everything needed to re-execute it outside of the subnet, so that a
+//! message that trapped in production can be reproduced locally.
+//!
+//! A capture is a directory containing:
+//!  - `canister_state/`: the files of the receiver's canister layout at the
+//!    checkpoint the capture was taken from. Besides the Wasm module and the
+//!    memories, this includes the cycles balance, the certified data and the
+//!    canister version, i.e. the inputs of the corresponding system API calls.
+//!  - `message.json`: the message itself and the execution environment
+//!    observed by the canister: the subnet, its size and the batch time.
+//!
+//! Captures are written by `ic-replay capture-message` and executed by
+//! `drun --replay-message`. They are shared by both tools through this crate,
+//! so that `drun` does not depend on `ic-replay`.
+//!
+//! A capture does not record the batch randomness nor a data certificate.
+//! Neither is observable by the captured message itself: update calls cannot
+//! read a data certificate, and randomness is only handed out through
+//! `raw_rand`, whose response is executed in a later message. Replaying a
+//! message that calls `raw_rand` hence yields different random bytes in its
+//! callback.
+
+use ic_registry_subnet_type::SubnetType;
+use ic_types::{
+    messages::{CallbackId, Ingress, MessageId, Request},
+    CanisterId, Height, PrincipalId, SubnetId, Time,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    convert::TryFrom,
+    io::{Error, ErrorKind, Result},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+/// Version of the capture format. Captures of other versions are rejected.
+const CAPTURE_FORMAT_VERSION: u32 = 1;
+
+const MANIFEST_FILE_NAME: &str = "message.json";
+const CANISTER_STATE_DIR_NAME: &str = "canister_state";
+
+/// Identifies the message to capture.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MessageSelector {
+    /// An ingress message, given by its message ID in hex.
+    Ingress(MessageId),
+    /// An inter-canister request, given as `<sender>:<callback id>`.
+    Request {
+        sender: CanisterId,
+        callback_id: CallbackId,
+    },
+}
+
+impl FromStr for MessageSelector {
+    type Err = String;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        if let Some((sender, callback_id)) = s.split_once(':') {
+            let sender = PrincipalId::from_str(sender)
+                .map_err(|err| format!("Unable to parse sender {:?}: {}", sender, err))?;
+            let callback_id = callback_id
+                .parse::<u64>()
+                .map_err(|err| format!("Unable to parse callback id {:?}: {}", callback_id, err))?;
+            return Ok(MessageSelector::Request {
+                sender: CanisterId::unchecked_from_principal(sender),
+                callback_id: CallbackId::from(callback_id),
+            });
+        }
+        let bytes =
+            hex::decode(s).map_err(|err| format!("Unable to parse message id {:?}: {}", s, err))?;
+        MessageId::try_from(&bytes[..])
+            .map(MessageSelector::Ingress)
+            .map_err(|err| format!("Unable to parse message id {:?}: {:?}", s, err))
+    }
+}
+
+/// The captured message.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub enum CapturedMessage {
+    Ingress(Ingress),
+    Request(Request),
+}
+
+impl CapturedMessage {
+    /// Returns the canister that executes the message.
+    pub fn receiver(&self) -> CanisterId {
+        match self {
+            CapturedMessage::Ingress(ingress) => ingress.receiver,
+            CapturedMessage::Request(request) => request.receiver,
+        }
+    }
+
+    /// Returns true if the message is the one identified by `selector`.
+    pub fn matches(&self, selector: &MessageSelector) -> bool {
+        match (self, selector) {
+            (CapturedMessage::Ingress(ingress), MessageSelector::Ingress(message_id)) => {
+                ingress.message_id == *message_id
+            }
+            (
+                CapturedMessage::Request(request),
+                MessageSelector::Request {
+                    sender,
+                    callback_id,
+                },
+            ) => request.sender == *sender && request.sender_reply_callback == *callback_id,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct MessageCapture {
+    format_version: u32,
+    /// The subnet that executed the message.
+    pub subnet_id: SubnetId,
+    pub subnet_type: SubnetType,
+    /// The number of nodes of the subnet, which determines the cycles costs
+    /// reported to the canister.
+    pub subnet_size: usize,
+    /// The height of the checkpoint the canister state was taken from.
+    pub checkpoint_height: Height,
+    /// The batch time the message is executed at: the time of the block that
+    /// included an ingress message, or the time of the first block above the
+    /// checkpoint if the message was already enqueued at the checkpoint. The
+    /// latter is only exact if the message was executed in the first round
+    /// after the checkpoint.
+    pub batch_time: Time,
+    pub message: CapturedMessage,
+}
+
+impl MessageCapture {
+    pub fn new(
+        subnet_id: SubnetId,
+        subnet_type: SubnetType,
+        subnet_size: usize,
+        checkpoint_height: Height,
+        batch_time: Time,
+        message: CapturedMessage,
+    ) -> Self {
+        Self {
+            format_version: CAPTURE_FORMAT_VERSION,
+            subnet_id,
+            subnet_type,
+            subnet_size,
+            checkpoint_height,
+            batch_time,
+            message,
+        }
+    }
+
+    /// Returns the directory of the captured canister state.
+    pub fn canister_state_dir(capture_dir: &Path) -> PathBuf {
+        capture_dir.join(CANISTER_STATE_DIR_NAME)
+    }
+
+    /// Writes the capture to `capture_dir`, copying the files of the given
+    /// canister layout into the capture.
+    pub fn write_to_dir(&self, canister_layout_dir: &Path, capture_dir: &Path) -> Result<()> {
+        let canister_state_dir = Self::canister_state_dir(capture_dir);
+        std::fs::create_dir_all(&canister_state_dir)?;
+        for entry in std::fs::read_dir(canister_layout_dir)? {
+            let entry = entry?;
+            if entry.file_type()?.is_file() {
+                std::fs::copy(entry.path(), canister_state_dir.join(entry.file_name()))?;
+            }
+        }
+        let manifest = serde_json::to_vec_pretty(self)?;
+        std::fs::write(capture_dir.join(MANIFEST_FILE_NAME), manifest)
+    }
+
+    /// Reads the manifest of the capture in `capture_dir`.
+    pub fn read_from_dir(capture_dir: &Path) -> Result<Self> {
+        let manifest = std::fs::read(capture_dir.join(MANIFEST_FILE_NAME))?;
+        let capture: Self = serde_json::from_slice(&manifest)?;
+        if capture.format_version != CAPTURE_FORMAT_VERSION {
+            return Err(Error::new(
+                ErrorKind::InvalidData,
+                format!(
+                    "Unsupported capture format version {}, expected {}",
+                    capture.format_version, CAPTURE_FORMAT_VERSION
+                ),
+            ));
+        }
+        Ok(capture)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ic_test_utilities_types::{
+        ids::{canister_test_id, message_test_id, subnet_test_id},
+        messages::IngressBuilder,
+    };
+
+    #[test]
+    fn parses_message_selectors() {
+        let message_id = message_test_id(7);
+        assert_eq!(
+            MessageSelector::from_str(&hex::encode(message_id.as_bytes())),
+            Ok(MessageSelector::Ingress(message_id))
+        );
+        let sender = canister_test_id(3);
+        assert_eq!(
+            MessageSelector::from_str(&format!("{}:42", sender)),
+            Ok(MessageSelector::Request {
+                sender,
+                callback_id: CallbackId::from(42),
+            })
+        );
+        assert!(MessageSelector::from_str("abc").is_err());
+        assert!(MessageSelector::from_str(&format!("{}:x", sender)).is_err());
+    }
+
+    #[test]
+    fn capture_round_trips_through_dir() {
+        let canister_layout = tempfile::tempdir().unwrap();
+        std::fs::write(canister_layout.path().join("software.wasm"), b"wasm").unwrap();
+        let ingress = IngressBuilder::new()
+            .receiver(canister_test_id(1))
+            .method_name("write")
+            .method_payload(vec![1, 2, 3])
+            .build();
+        let capture = MessageCapture::new(
+            subnet_test_id(1),
+            SubnetType::Application,
+            13,
+            Height::new(500),
+            Time::from_nanos_since_unix_epoch(1_000),
+            CapturedMessage::Ingress(ingress.clone()),
+        );
+        assert!(capture
+            .message
+            .matches(&MessageSelector::Ingress(ingress.message_id)));
+
+        let capture_dir = tempfile::tempdir().unwrap();
+        capture
+            .write_to_dir(canister_layout.path(), capture_dir.path())
+            .unwrap();
+        assert_eq!(
+            MessageCapture::read_from_dir(capture_dir.path()).unwrap(),
+            capture
+        );
+        assert_eq!(
+            std::fs::read(
+                MessageCapture::canister_state_dir(capture_dir.path()).join("software.wasm")
+            )
+            .unwrap(),
+            b"wasm"
+        );
+    }
+}
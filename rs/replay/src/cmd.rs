@@ -1,4 +1,5 @@
 use clap::Parser;
+use ic_replay_message_capture::MessageSelector;
 use ic_types::{CanisterId, PrincipalId, SubnetId};
 use icp_ledger::AccountIdentifier;
 use std::path::PathBuf;
@@ -63,6 +64,16 @@ pub enum SubCommand {
     /// Restore from the backup.
     RestoreFromBackup(RestoreFromBackupCmd),
 
+    /// Replay a bundle exported with `ic-consensus-pool-util export-bundle`.
+    /// The finalized blocks are executed on top of the checkpoint at the
+    /// height of the bundle's CUP, which has to be present in the state
+    /// directory of the given replica config.
+    ReplayBundle(ReplayBundleCmd),
+
+    /// Capture a single canister message together with the state of its
+    /// receiver, to re-execute it locally with `drun --replay-message`.
+    CaptureMessage(CaptureMessageCmd),
+
     /// The replay will add a test Neuron to the Governance canister
     /// and the corresponding account in the ledger.
     WithNeuronForTests(WithNeuronCmd),
@@ -116,6 +127,27 @@ pub struct RestoreFromBackupCmd {
     pub start_height: u64,
 }
 
+#[derive(Clone, Parser)]
+pub struct ReplayBundleCmd {
+    /// Path to the bundle file
+    pub bundle_path: PathBuf,
+    /// The replica version that produced the bundle
+    pub replica_version: String,
+}
+
+#[derive(Clone, Parser)]
+pub struct CaptureMessageCmd {
+    /// Height of the checkpoint to take the canister state from. An ingress
+    /// message has to be enqueued at the checkpoint or included in the block
+    /// right above it
+    pub checkpoint_height: u64,
+    /// The ingress message id (in hex), or the inter-canister request as
+    /// `<sender canister id>:<callback id>`
+    pub message: MessageSelector,
+    /// Directory to write the capture to
+    pub output_dir: PathBuf,
+}
+
 #[derive(Clone, Parser)]
 pub struct RestoreFromBackup2Cmd {
     /// Registry local store path
@@ -19,6 +19,7 @@ use crate::{
     ingress::*,
     player::{Player, ReplayResult},
 };
+use ic_artifact_pool::replay_bundle::ReplayBundle;
 use ic_canister_client::{Agent, Sender};
 use ic_config::{Config, ConfigSource};
 use ic_nns_constants::GOVERNANCE_CANISTER_ID;
@@ -125,6 +126,23 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
             return;
         }
 
+        if let Some(SubCommand::ReplayBundle(cmd)) = subcmd {
+            let _enter_guard = rt.enter();
+
+            let bundle = ReplayBundle::read_from_file(&cmd.bundle_path)
+                .unwrap_or_else(|err| panic!("Couldn't read the bundle: {:?}", err));
+            let mut player = Player::new_for_bundle(
+                cfg,
+                ReplicaVersion::try_from(cmd.replica_version.as_str())
+                    .expect("Couldn't parse the replica version"),
+                &bundle,
+                subnet_id,
+            )
+            .with_replay_target_height(target_height);
+            *res_clone.borrow_mut() = player.replay_bundle(&bundle);
+            return;
+        }
+
         {
             let _enter_guard = rt.enter();
             let player = match (subcmd.as_ref(), target_height) {
@@ -143,6 +161,11 @@ pub fn replay(args: ReplayToolArgs) -> ReplayResult {
                 return;
             }
 
+            if let Some(SubCommand::CaptureMessage(cmd)) = subcmd {
+                cmd_capture_message(&player, cmd).unwrap();
+                return;
+            }
+
             let extra = move |player: &Player, time| -> Vec<IngressWithPrinter> {
                 // Use a dummy URL here because we don't send any outgoing ingress.
                 // The agent is only used to construct ingress messages.
@@ -222,6 +245,25 @@ pub fn consent_given(question: &str) -> bool {
     matches!(s.as_str(), "\n" | "y\n" | "Y\n")
 }
 
+// Captures a message and the state of its receiver at the given checkpoint.
+fn cmd_capture_message(
+    player: &crate::player::Player,
+    cmd: &crate::cmd::CaptureMessageCmd,
+) -> Result<(), String> {
+    let capture = player.capture_message(
+        ic_types::Height::from(cmd.checkpoint_height),
+        &cmd.message,
+        &cmd.output_dir,
+    )?;
+    println!(
+        "Captured message to canister {} at batch time {} into {}",
+        capture.message.receiver(),
+        capture.batch_time,
+        cmd.output_dir.display()
+    );
+    Ok(())
+}
+
 // Creates a recovery CUP by using the latest CUP and overriding the height and
 // the state hash.
 fn cmd_get_recovery_cup(
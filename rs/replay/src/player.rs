@@ -7,6 +7,7 @@ use crate::{
 use ic_artifact_pool::{
     certification_pool::CertificationPoolImpl,
     consensus_pool::{ConsensusPoolImpl, UncachedConsensusPoolImpl},
+    replay_bundle::ReplayBundle,
 };
 use ic_config::{artifact_pool::ArtifactPoolConfig, subnet_config::SubnetConfig, Config};
 use ic_consensus::{certification::VerifierImpl, consensus::batch_delivery::deliver_batches};
@@ -24,7 +25,8 @@ use ic_interfaces::{
     certification::CertificationPool,
     execution_environment::{IngressHistoryReader, QueryExecutionError, QueryExecutionService},
     messaging::{MessageRouting, MessageRoutingError},
-    time_source::SysTimeSource,
+    p2p::consensus::{MutablePool, UnvalidatedArtifact},
+    time_source::{SysTimeSource, TimeSource},
 };
 use ic_interfaces_registry::{RegistryClient, RegistryTransportRecord};
 use ic_interfaces_state_manager::{
@@ -42,7 +44,8 @@ use ic_registry_client::client::RegistryClientImpl;
 use ic_registry_client_helpers::{deserialize_registry_value, subnet::SubnetRegistry};
 use ic_registry_keys::{make_blessed_replica_versions_key, make_subnet_record_key};
 use ic_registry_local_store::{
-    Changelog, ChangelogEntry, KeyMutation, LocalStoreImpl, LocalStoreWriter,
+    compact_delta_to_changelog, Changelog, ChangelogEntry, KeyMutation, LocalStoreImpl,
+    LocalStoreWriter,
 };
 use ic_registry_nns_data_provider::registry::registry_deltas_to_registry_transport_records;
 use ic_registry_subnet_type::SubnetType;
@@ -51,12 +54,15 @@ use ic_registry_transport::{
     deserialize_get_value_response, serialize_get_changes_since_request,
     serialize_get_value_request,
 };
+use ic_replay_message_capture::{CapturedMessage, MessageCapture, MessageSelector};
+use ic_replicated_state::ReplicatedState;
 use ic_state_manager::StateManagerImpl;
+use ic_test_utilities_time::FastForwardTimeSource;
 use ic_types::{
     batch::{Batch, BatchMessages, BlockmakerMetrics},
     consensus::{
         certification::{Certification, CertificationContent, CertificationShare},
-        CatchUpContentProtobufBytes, CatchUpPackage, HasHeight, HasVersion,
+        CatchUpContentProtobufBytes, CatchUpPackage, ConsensusMessage, HasHeight, HasVersion,
     },
     crypto::{
         threshold_sig::ni_dkg::{NiDkgId, NiDkgTag, NiDkgTargetSubnet},
@@ -64,7 +70,7 @@ use ic_types::{
     },
     ingress::{IngressState, IngressStatus, WasmResult},
     malicious_flags::MaliciousFlags,
-    messages::{CertificateDelegation, Query, QuerySource},
+    messages::{CanisterMessage, CertificateDelegation, Ingress, MessageId, Query, QuerySource},
     signature::ThresholdSignature,
     time::current_time,
     CryptoHashOfPartialState, CryptoHashOfState, Height, NodeId, PrincipalId, Randomness,
@@ -107,6 +113,8 @@ pub enum ReplayError {
     CUPVerificationFailed(Height),
     /// Replay was successful, but manual inspection is required to choose correct state.
     ManualInspectionRequired(StateParams),
+    /// Can't proceed because the state at the given height is not available.
+    MissingState(Height),
 }
 
 pub type ReplayResult = Result<StateParams, ReplayError>;
@@ -136,6 +144,9 @@ pub struct Player {
     // The target height until which the state will be replayed.
     // None means finalized height.
     replay_target_height: Option<u64>,
+    // The clock of consensus when replaying a bundle. It follows the times of
+    // the replayed blocks instead of the system time.
+    bundle_time_source: Option<Arc<FastForwardTimeSource>>,
     runtime: Runtime,
 }
 
@@ -195,7 +206,7 @@ impl Player {
             artifact_pool_config,
             MetricsRegistry::new(),
             log.clone(),
-            time_source,
+            time_source.clone(),
         );
 
         let mut player = Player::new_with_params(
@@ -205,6 +216,7 @@ impl Player {
             Some(pool),
             Some(backup_dir),
             replica_version,
+            time_source,
             log,
             _async_log_guard,
         );
@@ -212,6 +224,87 @@ impl Player {
         player
     }
 
+    /// Create and return a `Player` from a replica configuration object for
+    /// validating a bundle exported with `ic-consensus-pool-util export-bundle`.
+    /// The registry content of the bundle is restored into a temporary local
+    /// store and a temporary consensus pool is created from the bundle's CUP.
+    pub fn new_for_bundle(
+        mut cfg: Config,
+        replica_version: ReplicaVersion,
+        bundle: &ReplayBundle,
+        subnet_id: SubnetId,
+    ) -> Self {
+        let (log, _async_log_guard) = new_replica_logger_from_config(&cfg.logger);
+
+        let cup_proto = bundle
+            .cup_proto()
+            .expect("Couldn't decode the CUP of the bundle");
+        let cup =
+            CatchUpPackage::try_from(&cup_proto).expect("Couldn't decode the CUP of the bundle");
+        // Consensus runs on the times of the bundle's blocks, starting with the
+        // time of the CUP block, so that the replay does not depend on when it runs.
+        let time_source = FastForwardTimeSource::new();
+        time_source
+            .set_time(cup.content.block.get_value().context.time)
+            .expect("Couldn't set the time of the bundle's CUP");
+        let tmp_dir = tempfile::Builder::new()
+            .prefix("replay_bundle_")
+            .tempdir()
+            .expect("Couldn't create a temporary directory");
+
+        let local_store_path = tmp_dir.path().join("ic_registry_local_store");
+        let local_store = LocalStoreImpl::new(&local_store_path);
+        let (start_version, changelog) = compact_delta_to_changelog(&bundle.registry_delta)
+            .expect("Couldn't decode the registry content of the bundle");
+        for (i, entry) in changelog.into_iter().enumerate() {
+            local_store
+                .store(
+                    RegistryVersion::from(start_version.get() + i as u64 + 1),
+                    entry,
+                )
+                .expect("Couldn't write to the temporary registry local store");
+        }
+        let registry = Arc::new(RegistryClientImpl::new(Arc::new(local_store), None));
+        registry
+            .poll_once()
+            .expect("Couldn't poll the registry data provider");
+
+        cfg.artifact_pool.consensus_pool_path = tmp_dir.path().join("ic_consensus_pool");
+        // If the backup was configured, make sure we switch it off during the replay.
+        cfg.artifact_pool.backup = None;
+        println!(
+            "Using {:?} for the temporary consensus pool...",
+            cfg.artifact_pool.consensus_pool_path
+        );
+        let artifact_pool_config = ArtifactPoolConfig::from(cfg.artifact_pool.clone());
+        // The bundle contains the original CUP proto, so its signature can be validated.
+        let pool = ConsensusPoolImpl::new(
+            NodeId::from(PrincipalId::new_anonymous()),
+            subnet_id,
+            cup_proto,
+            artifact_pool_config,
+            MetricsRegistry::new(),
+            log.clone(),
+            time_source.clone(),
+        );
+
+        let mut player = Player::new_with_params(
+            cfg,
+            registry,
+            subnet_id,
+            Some(pool),
+            None,
+            replica_version,
+            time_source.clone(),
+            log,
+            _async_log_guard,
+        );
+        player.local_store_path = local_store_path;
+        player.tmp_dir = Some(tmp_dir);
+        player.bundle_time_source = Some(time_source);
+        player
+    }
+
     /// Create and return a `Player` from a replica configuration object for
     /// subnet recovery.
     pub fn new(cfg: Config, subnet_id: SubnetId) -> Self {
@@ -230,7 +323,7 @@ impl Player {
                 UncachedConsensusPoolImpl::new(artifact_pool_config, log.clone()),
                 MetricsRegistry::new(),
                 log.clone(),
-                time_source,
+                time_source.clone(),
             );
             Some(consensus_pool)
         } else {
@@ -251,6 +344,7 @@ impl Player {
             consensus_pool,
             None,
             replica_version,
+            time_source,
             log,
             _async_log_guard,
         )
@@ -264,6 +358,7 @@ impl Player {
         consensus_pool: Option<ConsensusPoolImpl>,
         backup_dir: Option<PathBuf>,
         replica_version: ReplicaVersion,
+        time_source: Arc<dyn TimeSource>,
         log: ReplicaLogger,
         _async_log_guard: AsyncGuard,
     ) -> Self {
@@ -352,6 +447,7 @@ impl Player {
                 registry.clone(),
                 state_manager.clone(),
                 message_routing.clone(),
+                time_source,
                 log.clone(),
             )
         });
@@ -385,6 +481,7 @@ impl Player {
             _async_log_guard,
             tmp_dir: None,
             replay_target_height: None,
+            bundle_time_source: None,
             runtime,
         }
     }
@@ -1124,6 +1221,180 @@ impl Player {
         }
     }
 
+    /// Replays the artifacts of the given bundle on top of the checkpoint at the height
+    /// of the bundle's CUP. The artifacts are inserted into the unvalidated section of the
+    /// pool height by height, in the same way as the node would have received them, with
+    /// the clock set to the time of the blocks at that height. After each height, the
+    /// validator moves valid artifacts to the validated section and all newly finalized
+    /// blocks are delivered for execution. Since the order of insertion, the clock, the
+    /// registry and the state are fixed, the replay deterministically reproduces the
+    /// validation, finalization and execution of the bundle's blocks.
+    pub fn replay_bundle(&mut self, bundle: &ReplayBundle) -> ReplayResult {
+        let cup_height = self.get_latest_cup().height();
+        let state_height = self.state_manager.latest_state_height();
+        if state_height != cup_height {
+            println!(
+                "Replaying the bundle requires the checkpoint at the CUP height {}, but the latest state is at height {}",
+                cup_height, state_height
+            );
+            return Err(ReplayError::MissingState(cup_height));
+        }
+        match self.verify_latest_cup() {
+            Err(ReplayError::UpgradeDetected(_)) | Ok(_) => {}
+            other => other?,
+        };
+
+        let target_height = bundle.max_height.min(
+            self.replay_target_height
+                .map(Height::from)
+                .unwrap_or(bundle.max_height),
+        );
+        let mut height_to_artifacts: BTreeMap<Height, Vec<ConsensusMessage>> = BTreeMap::new();
+        let validated = bundle
+            .validated()
+            .expect("Couldn't decode the validated artifacts of the bundle");
+        let unvalidated = bundle
+            .unvalidated()
+            .expect("Couldn't decode the unvalidated artifacts of the bundle");
+        for message in validated.into_iter().chain(unvalidated) {
+            // The pool cache assumes there is at most one CUP in the pool, which is the
+            // one from the bundle we started with.
+            if let ConsensusMessage::CatchUpPackage(_) = message {
+                continue;
+            }
+            height_to_artifacts
+                .entry(message.height())
+                .or_default()
+                .push(message);
+        }
+        println!(
+            "Replaying the bundle of subnet {} from height {} to {}",
+            self.subnet_id, bundle.min_height, target_height
+        );
+
+        let validator = self.validator.as_ref().expect("No validator found");
+        let time_source = self
+            .bundle_time_source
+            .clone()
+            .expect("No bundle time source found");
+        let mut dkg_manager =
+            validator.new_key_manager(&PoolReader::new(self.consensus_pool.as_ref().unwrap()));
+        let mut invalid_artifacts = Vec::new();
+        let mut last_batch_height = cup_height;
+        for (height, artifacts) in height_to_artifacts.range(..=target_height) {
+            // Advance the clock to the latest block time at this height, as the node
+            // received the blocks only after they were proposed.
+            if let Some(block_time) = artifacts
+                .iter()
+                .filter_map(|message| match message {
+                    ConsensusMessage::BlockProposal(proposal) => {
+                        Some(proposal.content.as_ref().context.time)
+                    }
+                    _ => None,
+                })
+                .max()
+            {
+                time_source.set_time(block_time).ok();
+            }
+
+            let pool = self.consensus_pool.as_mut().unwrap();
+            let last_finalized_height = PoolReader::new(pool).get_finalized_height();
+            for message in artifacts {
+                pool.insert(UnvalidatedArtifact {
+                    message: message.clone(),
+                    peer_id: validator.replica_cfg.node_id,
+                    timestamp: time_source.get_relative_time(),
+                });
+            }
+            let mut invalid = match validator.validate(
+                pool,
+                &mut HashMap::new(),
+                &mut dkg_manager,
+                last_finalized_height,
+            ) {
+                Ok(invalid) => invalid,
+                Err(ReplayError::ValidationIncomplete(_, invalid)) => invalid,
+                Err(other) => return Err(other),
+            };
+            for artifact in &invalid {
+                println!("Invalid artifact at height {}: {:?}", height, artifact);
+            }
+            invalid_artifacts.append(&mut invalid);
+
+            let pool_reader = PoolReader::new(self.consensus_pool.as_ref().unwrap());
+            let finalized_height = pool_reader.get_finalized_height();
+            for h in (last_finalized_height.get() + 1)..=finalized_height.get() {
+                if let Some(finalization) = pool_reader
+                    .pool()
+                    .validated()
+                    .finalization()
+                    .get_by_height(Height::from(h))
+                    .next()
+                {
+                    println!(
+                        "Finalized block {:?} at height {}",
+                        finalization.content.block, h
+                    );
+                }
+            }
+            if finalized_height > last_finalized_height {
+                last_batch_height = self.deliver_batches(
+                    self.message_routing.as_ref(),
+                    &pool_reader,
+                    self.membership.as_ref().unwrap(),
+                    Some(target_height),
+                );
+            }
+        }
+        self.wait_for_state(last_batch_height);
+
+        let finalized_height =
+            PoolReader::new(self.consensus_pool.as_ref().unwrap()).get_finalized_height();
+        if finalized_height < target_height {
+            println!(
+                "Finalization stopped at height {} before reaching the target height {}",
+                finalized_height, target_height
+            );
+            return Err(ReplayError::ValidationIncomplete(
+                finalized_height,
+                invalid_artifacts,
+            ));
+        }
+        println!("All blocks of the bundle successfully replayed.");
+        // We only want to persist the checkpoint after the latest batch.
+        self.state_manager.remove_states_below(last_batch_height);
+        Ok(self.get_latest_state_params(None, invalid_artifacts))
+    }
+
+    /// Captures the given message together with the state of its receiver at
+    /// the checkpoint at `checkpoint_height` into `output_dir`.
+    ///
+    /// The message is looked up in the input queues of the canisters at the
+    /// checkpoint and, for ingress messages, in the finalized blocks above the
+    /// checkpoint. The capture has to reflect the state the message was
+    /// executed on, so an ingress message is only captured if it was included
+    /// in the first block above the checkpoint. Otherwise, the state has to be
+    /// replayed until the height right before that block first, e.g. using
+    /// `--replay-until-height`. The batch time is taken from the finalized
+    /// blocks, so the consensus pool has to contain the block above the
+    /// checkpoint.
+    pub fn capture_message(
+        &self,
+        checkpoint_height: Height,
+        selector: &MessageSelector,
+        output_dir: &Path,
+    ) -> Result<MessageCapture, String> {
+        let pool_reader = self.consensus_pool.as_ref().map(PoolReader::new);
+        capture_message_at_checkpoint(
+            &self.state_manager,
+            pool_reader.as_ref(),
+            self.subnet_id,
+            checkpoint_height,
+            selector,
+            output_dir,
+        )
+    }
+
     // Checks that the restored catch-up package contains the same state hash as
     // the one computed by the state manager from the restored artifacts and drops
     // all states below the last CUP.
@@ -1135,7 +1406,7 @@ impl Player {
         let purge_height = cache.catch_up_package().height();
         println!("Removing all states below height {:?}", purge_height);
         self.state_manager.remove_states_below(purge_height);
-        use ic_interfaces::{consensus_pool::ChangeAction, p2p::consensus::MutablePool};
+        use ic_interfaces::consensus_pool::ChangeAction;
         pool.apply_changes(ChangeAction::PurgeValidatedBelow(purge_height).into());
         Ok(params)
     }
@@ -1221,6 +1492,146 @@ impl Player {
     }
 }
 
+// Implements `Player::capture_message()` on the given state manager and
+// consensus pool.
+fn capture_message_at_checkpoint(
+    state_manager: &StateManagerImpl,
+    pool_reader: Option<&PoolReader>,
+    subnet_id: SubnetId,
+    checkpoint_height: Height,
+    selector: &MessageSelector,
+    output_dir: &Path,
+) -> Result<MessageCapture, String> {
+    let state = state_manager
+        .get_state_at(checkpoint_height)
+        .map_err(|err| format!("Couldn't load the checkpoint: {:?}", err))?
+        .take();
+
+    // A message enqueued at the checkpoint, or included in the first block
+    // above it, is executed in the round of that block at the earliest.
+    let next_height = checkpoint_height.increment();
+    let (message, batch_time) = match find_message_in_state(&state, selector) {
+        Some(message) => {
+            let batch_time = pool_reader
+                .and_then(|pool_reader| pool_reader.get_finalized_block(next_height))
+                .map(|block| block.context.time)
+                .ok_or_else(|| {
+                    format!(
+                        "Couldn't determine the batch time: no finalized block at height {}",
+                        next_height
+                    )
+                })?;
+            (message, batch_time)
+        }
+        None => match (selector, pool_reader) {
+            (MessageSelector::Ingress(message_id), Some(pool_reader)) => {
+                let (height, message, batch_time) =
+                    find_ingress_in_blocks(pool_reader, checkpoint_height, message_id)
+                        .ok_or_else(|| {
+                            format!(
+                                "Ingress message {} neither found at the checkpoint nor in finalized blocks",
+                                message_id
+                            )
+                        })?;
+                if height != next_height {
+                    return Err(format!(
+                        "Ingress message {} was included in the block at height {}, but the \
+                         checkpoint is at height {}. Replay until height {} first and capture \
+                         the message from the checkpoint at that height.",
+                        message_id,
+                        height,
+                        checkpoint_height,
+                        height.decrement()
+                    ));
+                }
+                (message, batch_time)
+            }
+            _ => {
+                return Err(format!(
+                    "Message {:?} not found at the checkpoint at height {}",
+                    selector, checkpoint_height
+                ))
+            }
+        },
+    };
+
+    let receiver = message.receiver();
+    let canister_layout = state_manager
+        .state_layout()
+        .checkpoint(checkpoint_height)
+        .and_then(|checkpoint| checkpoint.canister(&receiver))
+        .map_err(|err| format!("Couldn't open the layout of canister {}: {}", receiver, err))?;
+    let subnet_size = state
+        .metadata
+        .network_topology
+        .get_subnet_size(&subnet_id)
+        .ok_or_else(|| format!("Subnet {} not found in the network topology", subnet_id))?;
+
+    let capture = MessageCapture::new(
+        subnet_id,
+        state.metadata.own_subnet_type,
+        subnet_size,
+        checkpoint_height,
+        batch_time,
+        message,
+    );
+    capture
+        .write_to_dir(canister_layout.raw_path(), output_dir)
+        .map_err(|err| format!("Couldn't write the capture: {}", err))?;
+    Ok(capture)
+}
+
+// Returns the selected message if it is enqueued in the input queues of one
+// of the canisters in the given state.
+fn find_message_in_state(
+    state: &ReplicatedState,
+    selector: &MessageSelector,
+) -> Option<CapturedMessage> {
+    state.canisters_iter().find_map(|canister| {
+        // Popping from a copy of the canister state leaves the state intact.
+        let mut canister = canister.clone();
+        std::iter::from_fn(|| canister.pop_input()).find_map(|message| {
+            let message = match message {
+                CanisterMessage::Ingress(ingress) => {
+                    CapturedMessage::Ingress(ingress.as_ref().clone())
+                }
+                CanisterMessage::Request(request) => {
+                    CapturedMessage::Request(request.as_ref().clone())
+                }
+                CanisterMessage::Response(_) => return None,
+            };
+            message.matches(selector).then_some(message)
+        })
+    })
+}
+
+// Returns the ingress message with the given id together with the height and
+// the time of the first finalized block above `height` that included it.
+fn find_ingress_in_blocks(
+    pool_reader: &PoolReader,
+    height: Height,
+    message_id: &MessageId,
+) -> Option<(Height, CapturedMessage, Time)> {
+    let finalized_height = pool_reader.get_finalized_height();
+    (height.get() + 1..=finalized_height.get()).find_map(|h| {
+        let block = pool_reader.get_finalized_block(Height::from(h))?;
+        if block.payload.is_summary() {
+            return None;
+        }
+        let ingress_payload = &block.payload.as_ref().as_data().batch.ingress;
+        (0..ingress_payload.message_count()).find_map(|index| {
+            let (id, signed_ingress) = ingress_payload.get(index).ok()?;
+            (MessageId::from(&id) == *message_id).then(|| {
+                (
+                    block.height,
+                    CapturedMessage::Ingress(Ingress::from((signed_ingress, None))),
+                    block.context.time,
+                )
+            })
+        })
+    })
+}
+
 /// Return the set of signers that created multiple valid certification shares for the same height
 fn find_malicious_nodes(
     certification_pool: &CertificationPoolImpl,
@@ -1416,19 +1827,290 @@ fn get_state_hash<T>(
 
 #[cfg(test)]
 mod tests {
+    use ic_consensus_mocks::{dependencies, Dependencies};
+    use ic_interfaces_state_manager::CertificationScope;
     use ic_logger::replica_logger::no_op_logger;
-    use ic_test_utilities_consensus::fake::FakeSigner;
-    use ic_test_utilities_types::ids::node_test_id;
+    use ic_replicated_state::metadata_state::SubnetTopology;
+    use ic_test_artifact_pool::consensus_pool::TestConsensusPool;
+    use ic_test_utilities_consensus::fake::{FakeContentUpdate, FakeSigner, FakeVerifier};
+    use ic_test_utilities_state::insert_dummy_canister;
+    use ic_test_utilities_types::{
+        ids::{canister_test_id, node_test_id, subnet_test_id, user_test_id},
+        messages::SignedIngressBuilder,
+    };
     use ic_types::{
-        consensus::certification::{
-            CertificationContent, CertificationMessage, CertificationShare,
+        batch::{BatchPayload, IngressPayload},
+        consensus::{
+            certification::{CertificationContent, CertificationMessage, CertificationShare},
+            dkg, Block, BlockPayload, DataPayload, Payload,
         },
-        crypto::{CryptoHash, Signed},
+        crypto::{crypto_hash, CryptoHash, Signed},
+        malicious_flags::MaliciousFlags,
+        messages::SignedIngress,
         signature::ThresholdSignatureShare,
     };
 
     use super::*;
 
+    const CHECKPOINT_HEIGHT: Height = Height::new(1);
+
+    fn signed_ingress(nonce: u64) -> SignedIngress {
+        SignedIngressBuilder::new()
+            .canister_id(canister_test_id(1))
+            .method_name("run")
+            .nonce(nonce)
+            .build()
+    }
+
+    fn ingress_selector(ingress: &SignedIngress) -> MessageSelector {
+        MessageSelector::Ingress(ingress.id())
+    }
+
+    // Returns a state manager with a checkpoint at `CHECKPOINT_HEIGHT` whose
+    // only canister has the given ingress messages enqueued.
+    fn state_manager_with_checkpoint(
+        state_dir: &Path,
+        subnet_id: SubnetId,
+        enqueued: &[SignedIngress],
+    ) -> StateManagerImpl {
+        let state_manager = StateManagerImpl::new(
+            Arc::new(FakeVerifier::new()),
+            subnet_id,
+            SubnetType::Application,
+            no_op_logger(),
+            &MetricsRegistry::new(),
+            &ic_config::state_manager::Config::new(state_dir.to_path_buf()),
+            None,
+            MaliciousFlags::default(),
+        );
+        let (_height, mut state) = state_manager.take_tip();
+        insert_dummy_canister(&mut state, canister_test_id(1), user_test_id(0).get());
+        state.metadata.network_topology.subnets.insert(
+            subnet_id,
+            SubnetTopology {
+                nodes: (0..4).map(node_test_id).collect(),
+                ..SubnetTopology::default()
+            },
+        );
+        for ingress in enqueued {
+            state
+                .push_ingress(Ingress::from((ingress.clone(), None)))
+                .unwrap();
+        }
+        state_manager.commit_and_certify(state, CHECKPOINT_HEIGHT, CertificationScope::Full);
+        state_manager.flush_tip_channel();
+        state_manager
+    }
+
+    // Finalizes the next block of the pool with the given ingress messages in
+    // its payload and returns the block.
+    fn finalize_block_with_ingress(
+        pool: &mut TestConsensusPool,
+        ingress: Vec<SignedIngress>,
+    ) -> Block {
+        let mut block = pool.make_next_block();
+        block.content.as_mut().payload = Payload::new(
+            crypto_hash,
+            BlockPayload::Data(DataPayload {
+                batch: BatchPayload {
+                    ingress: IngressPayload::from(ingress),
+                    ..BatchPayload::default()
+                },
+                dealings: dkg::Dealings::new_empty(Height::new(0)),
+                ecdsa: None,
+            }),
+        );
+        block.update_content();
+        pool.advance_round_with_block(&block);
+        block.content.as_ref().clone()
+    }
+
+    #[test]
+    fn test_find_message_in_state() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let enqueued = signed_ingress(0);
+        let state_manager =
+            state_manager_with_checkpoint(tmp.path(), subnet_test_id(0), &[enqueued.clone()]);
+        let state = state_manager
+            .get_state_at(CHECKPOINT_HEIGHT)
+            .unwrap()
+            .take();
+
+        assert_eq!(
+            find_message_in_state(&state, &ingress_selector(&enqueued)),
+            Some(CapturedMessage::Ingress(Ingress::from((enqueued, None))))
+        );
+        assert_eq!(
+            find_message_in_state(&state, &ingress_selector(&signed_ingress(1))),
+            None
+        );
+        // Looking up the message leaves it enqueued.
+        assert_eq!(
+            state
+                .canister_state(&canister_test_id(1))
+                .unwrap()
+                .system_state
+                .queues()
+                .ingress_queue_message_count(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_find_ingress_in_blocks() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let Dependencies { mut pool, .. } =
+            dependencies(ArtifactPoolConfig::new(tmp.path().to_path_buf()), 1);
+        let included = signed_ingress(0);
+        pool.advance_round_normal_operation_n(2);
+        let block = finalize_block_with_ingress(&mut pool, vec![included.clone()]);
+        pool.advance_round_normal_operation();
+        let pool_reader = PoolReader::new(&pool);
+
+        assert_eq!(
+            find_ingress_in_blocks(&pool_reader, Height::new(1), &included.id()),
+            Some((
+                block.height,
+                CapturedMessage::Ingress(Ingress::from((included.clone(), None))),
+                block.context.time
+            ))
+        );
+        // Only blocks above the given height are searched.
+        assert_eq!(
+            find_ingress_in_blocks(&pool_reader, block.height, &included.id()),
+            None
+        );
+        assert_eq!(
+            find_ingress_in_blocks(&pool_reader, Height::new(0), &signed_ingress(1).id()),
+            None
+        );
+    }
+
+    #[test]
+    fn test_capture_message_enqueued_at_checkpoint() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let subnet_id = subnet_test_id(0);
+        let enqueued = signed_ingress(0);
+        let state_manager = state_manager_with_checkpoint(
+            &tmp.path().join("state"),
+            subnet_id,
+            &[enqueued.clone()],
+        );
+        let Dependencies { mut pool, .. } =
+            dependencies(ArtifactPoolConfig::new(tmp.path().join("pool")), 1);
+        pool.advance_round_normal_operation();
+        let block = finalize_block_with_ingress(&mut pool, vec![]);
+        assert_eq!(block.height, CHECKPOINT_HEIGHT.increment());
+
+        let output_dir = tmp.path().join("capture");
+        let capture = capture_message_at_checkpoint(
+            &state_manager,
+            Some(&PoolReader::new(&pool)),
+            subnet_id,
+            CHECKPOINT_HEIGHT,
+            &ingress_selector(&enqueued),
+            &output_dir,
+        )
+        .unwrap();
+
+        assert_eq!(capture.subnet_id, subnet_id);
+        assert_eq!(capture.subnet_size, 4);
+        assert_eq!(capture.checkpoint_height, CHECKPOINT_HEIGHT);
+        assert_eq!(capture.batch_time, block.context.time);
+        assert_eq!(
+            capture.message,
+            CapturedMessage::Ingress(Ingress::from((enqueued, None)))
+        );
+        assert_eq!(MessageCapture::read_from_dir(&output_dir).unwrap(), capture);
+        assert!(MessageCapture::canister_state_dir(&output_dir)
+            .read_dir()
+            .unwrap()
+            .next()
+            .is_some());
+    }
+
+    #[test]
+    fn test_capture_message_included_in_next_block() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let subnet_id = subnet_test_id(0);
+        let state_manager =
+            state_manager_with_checkpoint(&tmp.path().join("state"), subnet_id, &[]);
+        let Dependencies { mut pool, .. } =
+            dependencies(ArtifactPoolConfig::new(tmp.path().join("pool")), 1);
+        let included = signed_ingress(0);
+        pool.advance_round_normal_operation();
+        let block = finalize_block_with_ingress(&mut pool, vec![included.clone()]);
+
+        let output_dir = tmp.path().join("capture");
+        let capture = capture_message_at_checkpoint(
+            &state_manager,
+            Some(&PoolReader::new(&pool)),
+            subnet_id,
+            CHECKPOINT_HEIGHT,
+            &ingress_selector(&included),
+            &output_dir,
+        )
+        .unwrap();
+
+        assert_eq!(capture.batch_time, block.context.time);
+        assert_eq!(
+            capture.message,
+            CapturedMessage::Ingress(Ingress::from((included, None)))
+        );
+        assert_eq!(MessageCapture::read_from_dir(&output_dir).unwrap(), capture);
+    }
+
+    #[test]
+    fn test_capture_message_included_in_later_block_fails() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let subnet_id = subnet_test_id(0);
+        let state_manager =
+            state_manager_with_checkpoint(&tmp.path().join("state"), subnet_id, &[]);
+        let Dependencies { mut pool, .. } =
+            dependencies(ArtifactPoolConfig::new(tmp.path().join("pool")), 1);
+        let included = signed_ingress(0);
+        pool.advance_round_normal_operation_n(2);
+        let block = finalize_block_with_ingress(&mut pool, vec![included.clone()]);
+        assert_eq!(block.height, Height::new(3));
+
+        let output_dir = tmp.path().join("capture");
+        let err = capture_message_at_checkpoint(
+            &state_manager,
+            Some(&PoolReader::new(&pool)),
+            subnet_id,
+            CHECKPOINT_HEIGHT,
+            &ingress_selector(&included),
+            &output_dir,
+        )
+        .unwrap_err();
+
+        assert!(err.contains("Replay until height 2"), "{}", err);
+        assert!(!output_dir.exists());
+    }
+
+    #[test]
+    fn test_capture_message_not_found() {
+        let tmp = tempfile::tempdir().expect("Could not create a temp dir");
+        let subnet_id = subnet_test_id(0);
+        let state_manager =
+            state_manager_with_checkpoint(&tmp.path().join("state"), subnet_id, &[]);
+        let Dependencies { mut pool, .. } =
+            dependencies(ArtifactPoolConfig::new(tmp.path().join("pool")), 1);
+        pool.advance_round_normal_operation_n(2);
+
+        let err = capture_message_at_checkpoint(
+            &state_manager,
+            Some(&PoolReader::new(&pool)),
+            subnet_id,
+            CHECKPOINT_HEIGHT,
+            &ingress_selector(&signed_ingress(0)),
+            &tmp.path().join("capture"),
+        )
+        .unwrap_err();
+
+        assert!(err.contains("neither found"), "{}", err);
+    }
+
     fn make_share(height: u64, hash: Vec<u8>, node_id: u64) -> CertificationMessage {
         CertificationMessage::CertificationShare(CertificationShare {
             height: Height::from(height),
@@ -19,7 +19,7 @@ use ic_interfaces::{
     consensus_pool::{ChangeAction, ConsensusPool, ConsensusPoolCache, HeightIndexedPool},
     messaging::MessageRouting,
     p2p::consensus::{MutablePool, UnvalidatedArtifact},
-    time_source::{SysTimeSource, TimeSource},
+    time_source::TimeSource,
 };
 use ic_interfaces_registry::RegistryClient;
 use ic_interfaces_state_manager::StateManager;
@@ -108,6 +108,7 @@ impl ReplayValidator {
         registry: Arc<dyn RegistryClient>,
         state_manager: Arc<dyn StateManager<State = ReplicatedState>>,
         message_routing: Arc<dyn MessageRouting>,
+        time_source: Arc<dyn TimeSource>,
         log: ReplicaLogger,
     ) -> Self {
         let metrics_registry = MetricsRegistry::new();
@@ -116,7 +117,6 @@ impl ReplayValidator {
             registry.clone(),
             subnet_id,
         ));
-        let time_source = Arc::new(SysTimeSource::new());
         let dkg_pool = RwLock::new(DkgPoolImpl::new(metrics_registry.clone(), log.clone()));
         let node_id = NodeId::from(PrincipalId::new_node_test_id(1));
         let replica_cfg = ReplicaConfig::new(node_id, subnet_id);
@@ -4,7 +4,7 @@ use ic_config::subnet_config::CyclesAccountManagerConfig;
 use ic_config::Config;
 use ic_error_types::{ErrorCode, RejectCode};
 use ic_management_canister_types::{
-    self as ic00, CanisterChange, CanisterIdRecord, CanisterInstallMode,
+    self as ic00, BacktraceVisibility, CanisterChange, CanisterIdRecord, CanisterInstallMode,
     CanisterSettingsArgsBuilder, CanisterStatusResultV2, CanisterStatusType, EmptyBlob,
     InstallCodeArgs, LogVisibility, Method, Payload, UpdateSettingsArgs, IC_00,
 };
@@ -713,6 +713,7 @@ fn can_get_canister_information() {
                 2592000,
                 Some(5_000_000_000_000u128),
                 LogVisibility::default(),
+                BacktraceVisibility::default(),
                 0u128,
                 0u128,
                 0u128,
@@ -771,6 +772,7 @@ fn can_get_canister_information() {
                     259200,
                     None,
                     LogVisibility::default(),
+                    BacktraceVisibility::default(),
                     0u128,
                     0u128,
                     0u128,
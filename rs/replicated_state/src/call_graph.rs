@@ -0,0 +1,273 @@
+//! Bounded aggregation of canister-to-canister calls, exported as metrics.
+//!
+//! [`CallGraphMetrics`] accumulates the calls routed by a component (e.g. the
+//! scheduler for subnet-local messages; or the stream builder for XNet
+//! messages) over a window of rounds. At the end of each window the per-edge
+//! aggregates are published as gauges and the window is reset, so the exported
+//! values always describe the last completed window.
+//!
+//! In order to keep the cardinality of the exported metrics bounded, at most
+//! `max_edges` distinct `(caller, callee, method)` edges and `(caller, callee)`
+//! response edges are tracked per window. Observations beyond that limit are
+//! only reflected in a counter of dropped observations. Method names are
+//! truncated to `MAX_METHOD_LABEL_BYTES`, so a canister cannot inflate the
+//! size of the exported labels with arbitrarily long method names.
+//!
+//! The metrics are only exported on the replica's metrics endpoint. They are
+//! visualized by the Grafana dashboard under `rs/tests/dashboards`, not by the
+//! replica's `/_/dashboard` page.
+
+use ic_metrics::MetricsRegistry;
+use ic_types::{
+    messages::{Payload, RequestOrResponse},
+    CanisterId, Cycles,
+};
+use ic_utils::str::StrTruncate;
+use prometheus::{GaugeVec, IntCounter, IntGaugeVec, Opts};
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+
+#[cfg(test)]
+mod tests;
+
+/// Default maximum number of distinct edges tracked per window.
+pub const DEFAULT_MAX_EDGES: usize = 1_000;
+
+/// Default number of rounds after which the window is flushed.
+pub const DEFAULT_FLUSH_INTERVAL_ROUNDS: u64 = 100;
+
+/// Maximum byte length of the method name label. Longer method names are
+/// truncated (at a character boundary) and aggregated under the same edge.
+pub const MAX_METHOD_LABEL_BYTES: usize = 64;
+
+const METRIC_CALLS: &str = "canister_call_graph_calls";
+const METRIC_CYCLES: &str = "canister_call_graph_cycles";
+const METRIC_RESPONSES: &str = "canister_call_graph_responses";
+const METRIC_REJECT_RATIO: &str = "canister_call_graph_reject_ratio";
+const METRIC_DROPPED_OBSERVATIONS: &str = "canister_call_graph_dropped_observations_total";
+
+const LABEL_SOURCE: &str = "source";
+const LABEL_CALLER: &str = "caller";
+const LABEL_CALLEE: &str = "callee";
+const LABEL_METHOD: &str = "method";
+
+/// Call graph source label value for subnet-local messages inducted by the
+/// scheduler.
+pub const SOURCE_SCHEDULER: &str = "scheduler";
+/// Call graph source label value for messages routed into XNet streams.
+pub const SOURCE_STREAM_BUILDER: &str = "stream_builder";
+
+/// Aggregate of the requests along one `(caller, callee, method)` edge.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct CallStats {
+    calls: u64,
+    cycles: Cycles,
+}
+
+/// Aggregate of the responses along one `(caller, callee)` edge.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+struct ResponseStats {
+    responses: u64,
+    rejects: u64,
+}
+
+/// Observations accumulated since the last flush.
+#[derive(Debug, Default)]
+struct Window {
+    /// Request aggregates, by `(caller, callee)` and method name.
+    calls: BTreeMap<(CanisterId, CanisterId), BTreeMap<String, CallStats>>,
+    /// Total number of `(caller, callee, method)` edges in `calls`.
+    call_edges: usize,
+    /// Response aggregates, by `(caller, callee)`.
+    responses: BTreeMap<(CanisterId, CanisterId), ResponseStats>,
+    /// Number of rounds observed since the last flush.
+    rounds: u64,
+}
+
+/// Periodically flushed, bounded call graph metrics.
+pub struct CallGraphMetrics {
+    window: Mutex<Window>,
+    max_edges: usize,
+    flush_interval_rounds: u64,
+
+    /// Number of requests per `(caller, callee, method)` in the last window.
+    calls: IntGaugeVec,
+    /// Cycles attached to requests per `(caller, callee, method)` in the last
+    /// window.
+    ///
+    /// `f64` gauge because cycles values are `u128`.
+    cycles: GaugeVec,
+    /// Number of responses per `(caller, callee)` in the last window.
+    responses: IntGaugeVec,
+    /// Fraction of responses per `(caller, callee)` that were rejects in the
+    /// last window.
+    reject_ratio: GaugeVec,
+    /// Observations not recorded because the window was at `max_edges`.
+    dropped_observations: IntCounter,
+}
+
+impl CallGraphMetrics {
+    /// Creates call graph metrics with the default limits, registering them
+    /// with the given `source` label value.
+    pub fn new(metrics_registry: &MetricsRegistry, source: &str) -> Self {
+        Self::with_limits(
+            metrics_registry,
+            source,
+            DEFAULT_MAX_EDGES,
+            DEFAULT_FLUSH_INTERVAL_ROUNDS,
+        )
+    }
+
+    /// Creates call graph metrics tracking at most `max_edges` edges and
+    /// flushing every `flush_interval_rounds` rounds.
+    ///
+    /// Multiple instances may be registered with the same registry, as long as
+    /// they use distinct `source` label values.
+    pub fn with_limits(
+        metrics_registry: &MetricsRegistry,
+        source: &str,
+        max_edges: usize,
+        flush_interval_rounds: u64,
+    ) -> Self {
+        let opts = |name: &str, help: &str| Opts::new(name, help).const_label(LABEL_SOURCE, source);
+        let edge_labels = [LABEL_CALLER, LABEL_CALLEE, LABEL_METHOD];
+        let response_labels = [LABEL_CALLER, LABEL_CALLEE];
+
+        let calls = IntGaugeVec::new(
+            opts(
+                METRIC_CALLS,
+                "Requests by caller, callee and method, over the last window.",
+            ),
+            &edge_labels,
+        );
+        let cycles = GaugeVec::new(
+            opts(
+                METRIC_CYCLES,
+                "Cycles attached to requests by caller, callee and method, over the last window.",
+            ),
+            &edge_labels,
+        );
+        let responses = IntGaugeVec::new(
+            opts(
+                METRIC_RESPONSES,
+                "Responses by caller and callee, over the last window.",
+            ),
+            &response_labels,
+        );
+        let reject_ratio = GaugeVec::new(
+            opts(
+                METRIC_REJECT_RATIO,
+                "Fraction of rejected responses by caller and callee, over the last window.",
+            ),
+            &response_labels,
+        );
+        let dropped_observations = IntCounter::with_opts(opts(
+            METRIC_DROPPED_OBSERVATIONS,
+            "Messages not recorded in the call graph because the edge limit was reached.",
+        ));
+
+        Self {
+            window: Mutex::new(Window::default()),
+            max_edges,
+            flush_interval_rounds: flush_interval_rounds.max(1),
+            calls: metrics_registry.register(calls.unwrap()),
+            cycles: metrics_registry.register(cycles.unwrap()),
+            responses: metrics_registry.register(responses.unwrap()),
+            reject_ratio: metrics_registry.register(reject_ratio.unwrap()),
+            dropped_observations: metrics_registry.register(dropped_observations.unwrap()),
+        }
+    }
+
+    /// Records a message routed from a canister to another canister.
+    ///
+    /// Requests are recorded along the `(sender, receiver, method)` edge;
+    /// responses along the `(originator, respondent)` edge of the request they
+    /// respond to.
+    pub fn observe(&self, msg: &RequestOrResponse) {
+        let mut window = self.window.lock().unwrap();
+        let window = &mut *window;
+
+        let recorded = match msg {
+            RequestOrResponse::Request(req) => {
+                let method = req.method_name.safe_truncate(MAX_METHOD_LABEL_BYTES);
+                let methods = window.calls.entry((req.sender, req.receiver)).or_default();
+                if !methods.contains_key(method) && window.call_edges < self.max_edges {
+                    window.call_edges += 1;
+                    methods.insert(method.to_string(), CallStats::default());
+                }
+                methods.get_mut(method).map(|stats| {
+                    stats.calls += 1;
+                    stats.cycles += req.payment;
+                })
+            }
+
+            RequestOrResponse::Response(rep) => {
+                let edge = (rep.originator, rep.respondent);
+                let stats = if window.responses.contains_key(&edge)
+                    || window.responses.len() < self.max_edges
+                {
+                    Some(window.responses.entry(edge).or_default())
+                } else {
+                    None
+                };
+                stats.map(|stats| {
+                    stats.responses += 1;
+                    if let Payload::Reject(_) = rep.response_payload {
+                        stats.rejects += 1;
+                    }
+                })
+            }
+        };
+
+        if recorded.is_none() {
+            self.dropped_observations.inc();
+        }
+    }
+
+    /// Marks the end of a round, flushing the window every
+    /// `flush_interval_rounds` rounds.
+    pub fn observe_round(&self) {
+        let mut window = self.window.lock().unwrap();
+        window.rounds += 1;
+        if window.rounds >= self.flush_interval_rounds {
+            self.flush_window(&mut window);
+        }
+    }
+
+    /// Publishes the current window and starts a new one.
+    pub fn flush(&self) {
+        let mut window = self.window.lock().unwrap();
+        self.flush_window(&mut window);
+    }
+
+    fn flush_window(&self, window: &mut Window) {
+        self.calls.reset();
+        self.cycles.reset();
+        self.responses.reset();
+        self.reject_ratio.reset();
+
+        let window = std::mem::take(window);
+        for ((caller, callee), methods) in window.calls {
+            let (caller, callee) = (caller.to_string(), callee.to_string());
+            for (method, stats) in methods {
+                let labels = [caller.as_str(), callee.as_str(), method.as_str()];
+                self.calls
+                    .with_label_values(&labels)
+                    .set(stats.calls as i64);
+                self.cycles
+                    .with_label_values(&labels)
+                    .set(stats.cycles.get() as f64);
+            }
+        }
+        for ((caller, callee), stats) in window.responses {
+            let (caller, callee) = (caller.to_string(), callee.to_string());
+            let labels = [caller.as_str(), callee.as_str()];
+            self.responses
+                .with_label_values(&labels)
+                .set(stats.responses as i64);
+            self.reject_ratio
+                .with_label_values(&labels)
+                .set(stats.rejects as f64 / stats.responses as f64);
+        }
+    }
+}
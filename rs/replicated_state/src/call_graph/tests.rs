@@ -0,0 +1,181 @@
+use super::*;
+use ic_error_types::RejectCode;
+use ic_test_utilities_metrics::{
+    fetch_gauge_vec, fetch_int_counter_vec, fetch_int_gauge_vec, metric_vec,
+};
+use ic_test_utilities_types::{
+    ids::canister_test_id,
+    messages::{RequestBuilder, ResponseBuilder},
+};
+use ic_types::messages::RejectContext;
+
+fn request(sender: u64, receiver: u64, method: &str, payment: u128) -> RequestOrResponse {
+    RequestBuilder::default()
+        .sender(canister_test_id(sender))
+        .receiver(canister_test_id(receiver))
+        .method_name(method)
+        .payment(Cycles::new(payment))
+        .build()
+        .into()
+}
+
+fn response(originator: u64, respondent: u64, reject: bool) -> RequestOrResponse {
+    let builder = ResponseBuilder::default()
+        .originator(canister_test_id(originator))
+        .respondent(canister_test_id(respondent));
+    if reject {
+        builder
+            .response_payload(Payload::Reject(RejectContext::new(
+                RejectCode::CanisterError,
+                "oops",
+            )))
+            .build()
+            .into()
+    } else {
+        builder.build().into()
+    }
+}
+
+fn edge_labels(caller: u64, callee: u64, method: &str) -> Vec<(&'static str, String)> {
+    vec![
+        (LABEL_SOURCE, SOURCE_SCHEDULER.to_string()),
+        (LABEL_CALLER, canister_test_id(caller).to_string()),
+        (LABEL_CALLEE, canister_test_id(callee).to_string()),
+        (LABEL_METHOD, method.to_string()),
+    ]
+}
+
+fn response_labels(caller: u64, callee: u64) -> Vec<(&'static str, String)> {
+    vec![
+        (LABEL_SOURCE, SOURCE_SCHEDULER.to_string()),
+        (LABEL_CALLER, canister_test_id(caller).to_string()),
+        (LABEL_CALLEE, canister_test_id(callee).to_string()),
+    ]
+}
+
+#[test]
+fn call_graph_is_only_published_on_flush() {
+    let metrics_registry = MetricsRegistry::new();
+    let call_graph = CallGraphMetrics::with_limits(&metrics_registry, SOURCE_SCHEDULER, 10, 2);
+
+    call_graph.observe(&request(1, 2, "foo", 100));
+    call_graph.observe(&request(1, 2, "foo", 200));
+    call_graph.observe(&request(1, 2, "bar", 0));
+    call_graph.observe(&response(1, 2, false));
+    call_graph.observe(&response(1, 2, true));
+    call_graph.observe(&response(1, 2, true));
+    call_graph.observe(&response(2, 3, false));
+
+    // Nothing is published before the end of the window.
+    call_graph.observe_round();
+    assert!(fetch_int_gauge_vec(&metrics_registry, METRIC_CALLS).is_empty());
+
+    call_graph.observe_round();
+    assert_eq!(
+        metric_vec(&[
+            (edge_labels(1, 2, "foo").as_slice(), 2),
+            (edge_labels(1, 2, "bar").as_slice(), 1),
+        ]),
+        fetch_int_gauge_vec(&metrics_registry, METRIC_CALLS)
+    );
+    assert_eq!(
+        metric_vec(&[
+            (edge_labels(1, 2, "foo").as_slice(), 300.0),
+            (edge_labels(1, 2, "bar").as_slice(), 0.0),
+        ]),
+        fetch_gauge_vec(&metrics_registry, METRIC_CYCLES)
+    );
+    assert_eq!(
+        metric_vec(&[
+            (response_labels(1, 2).as_slice(), 3),
+            (response_labels(2, 3).as_slice(), 1),
+        ]),
+        fetch_int_gauge_vec(&metrics_registry, METRIC_RESPONSES)
+    );
+    assert_eq!(
+        metric_vec(&[
+            (response_labels(1, 2).as_slice(), 2.0 / 3.0),
+            (response_labels(2, 3).as_slice(), 0.0),
+        ]),
+        fetch_gauge_vec(&metrics_registry, METRIC_REJECT_RATIO)
+    );
+
+    // An empty window resets all edges.
+    call_graph.flush();
+    assert!(fetch_int_gauge_vec(&metrics_registry, METRIC_CALLS).is_empty());
+    assert!(fetch_int_gauge_vec(&metrics_registry, METRIC_RESPONSES).is_empty());
+}
+
+#[test]
+fn call_graph_edges_are_bounded() {
+    let metrics_registry = MetricsRegistry::new();
+    let call_graph = CallGraphMetrics::with_limits(&metrics_registry, SOURCE_SCHEDULER, 2, 1);
+
+    call_graph.observe(&request(1, 2, "foo", 0));
+    call_graph.observe(&request(1, 3, "foo", 0));
+    // Third edge is dropped, but existing edges are still updated.
+    call_graph.observe(&request(1, 2, "bar", 0));
+    call_graph.observe(&request(1, 3, "foo", 0));
+    call_graph.flush();
+
+    assert_eq!(
+        metric_vec(&[
+            (edge_labels(1, 2, "foo").as_slice(), 1),
+            (edge_labels(1, 3, "foo").as_slice(), 2),
+        ]),
+        fetch_int_gauge_vec(&metrics_registry, METRIC_CALLS)
+    );
+    assert_eq!(
+        metric_vec(&[(&[(LABEL_SOURCE, SOURCE_SCHEDULER)], 1)]),
+        fetch_int_counter_vec(&metrics_registry, METRIC_DROPPED_OBSERVATIONS)
+    );
+}
+
+#[test]
+fn call_graph_truncates_long_method_names() {
+    let metrics_registry = MetricsRegistry::new();
+    let call_graph = CallGraphMetrics::with_limits(&metrics_registry, SOURCE_SCHEDULER, 10, 1);
+
+    let prefix = "m".repeat(MAX_METHOD_LABEL_BYTES);
+    call_graph.observe(&request(1, 2, &format!("{}_foo", prefix), 0));
+    call_graph.observe(&request(1, 2, &format!("{}_bar", prefix), 0));
+    // Multi-byte characters are not split.
+    let multi_byte = format!("{}€", "m".repeat(MAX_METHOD_LABEL_BYTES - 1));
+    call_graph.observe(&request(1, 3, &multi_byte, 0));
+    call_graph.flush();
+
+    // Method names sharing the truncated prefix are aggregated under one edge.
+    assert_eq!(
+        metric_vec(&[
+            (edge_labels(1, 2, &prefix).as_slice(), 2),
+            (
+                edge_labels(1, 3, &"m".repeat(MAX_METHOD_LABEL_BYTES - 1)).as_slice(),
+                1
+            ),
+        ]),
+        fetch_int_gauge_vec(&metrics_registry, METRIC_CALLS)
+    );
+}
+
+#[test]
+fn call_graph_sources_share_metric_families() {
+    let metrics_registry = MetricsRegistry::new();
+    let scheduler = CallGraphMetrics::new(&metrics_registry, SOURCE_SCHEDULER);
+    let stream_builder = CallGraphMetrics::new(&metrics_registry, SOURCE_STREAM_BUILDER);
+
+    scheduler.observe(&request(1, 2, "foo", 0));
+    stream_builder.observe(&request(1, 2, "foo", 0));
+    stream_builder.observe(&request(1, 2, "foo", 0));
+    scheduler.flush();
+    stream_builder.flush();
+
+    let mut stream_builder_labels = edge_labels(1, 2, "foo");
+    stream_builder_labels[0].1 = SOURCE_STREAM_BUILDER.to_string();
+    assert_eq!(
+        metric_vec(&[
+            (edge_labels(1, 2, "foo").as_slice(), 1),
+            (stream_builder_labels.as_slice(), 2),
+        ]),
+        fetch_int_gauge_vec(&metrics_registry, METRIC_CALLS)
+    );
+}
@@ -306,6 +306,7 @@ fn test_message_picking_ingress_only() {
             method_payload: vec![i as u8],
             message_id: message_test_id(555),
             expiry_time: expiry_time_from_now(),
+            traced: false,
         });
     }
 
@@ -445,6 +446,7 @@ fn test_message_picking_round_robin() {
         method_payload: Vec::new(),
         message_id: message_test_id(555),
         expiry_time: expiry_time_from_now(),
+        traced: false,
     });
 
     // POPPING
@@ -624,6 +626,7 @@ fn test_peek_round_robin() {
         effective_canister_id: None,
         message_id: message_test_id(555),
         expiry_time: expiry_time_from_now(),
+        traced: false,
     };
     queues.push_ingress(ingress.clone());
 
@@ -694,6 +697,7 @@ fn test_skip_round_robin() {
         effective_canister_id: None,
         message_id: message_test_id(555),
         expiry_time: expiry_time_from_now(),
+        traced: false,
     };
     queues.push_ingress(ingress.clone());
     let ingress_input = CanisterMessage::Ingress(Arc::new(ingress));
@@ -12,7 +12,7 @@ pub use call_context_manager::{CallContext, CallContextAction, CallContextManage
 use ic_base_types::NumSeconds;
 use ic_logger::{error, ReplicaLogger};
 use ic_management_canister_types::{
-    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibility,
+    BacktraceVisibility, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, LogVisibility,
 };
 use ic_protobuf::proxy::{try_from_option_field, ProxyDecodeError};
 use ic_protobuf::state::canister_state_bits::v1 as pb;
@@ -354,6 +354,9 @@ pub struct SystemState {
 
     /// Next local snapshot id.
     pub next_snapshot_id: u64,
+
+    /// Backtrace visibility of the canister.
+    pub backtrace_visibility: BacktraceVisibility,
 }
 
 /// A wrapper around the different canister statuses.
@@ -718,6 +721,7 @@ impl SystemState {
             canister_log: Default::default(),
             wasm_memory_limit: None,
             next_snapshot_id: 0,
+            backtrace_visibility: BacktraceVisibility::default(),
         }
     }
 
@@ -745,6 +749,7 @@ impl SystemState {
         canister_log: CanisterLog,
         wasm_memory_limit: Option<NumBytes>,
         next_snapshot_id: u64,
+        backtrace_visibility: BacktraceVisibility,
     ) -> Self {
         Self {
             controllers,
@@ -771,6 +776,7 @@ impl SystemState {
             canister_log,
             wasm_memory_limit,
             next_snapshot_id,
+            backtrace_visibility,
         }
     }
 
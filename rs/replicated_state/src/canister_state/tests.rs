@@ -15,7 +15,8 @@ use crate::Memory;
 use ic_base_types::NumSeconds;
 use ic_logger::replica_logger::no_op_logger;
 use ic_management_canister_types::{
-    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterLogRecord, LogVisibility,
+    BacktraceVisibility, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin,
+    CanisterLogRecord, LogVisibility,
 };
 use ic_metrics::MetricsRegistry;
 use ic_test_utilities_types::{
@@ -613,6 +614,18 @@ fn canister_state_log_visibility_round_trip() {
     }
 }
 
+#[test]
+fn canister_state_backtrace_visibility_round_trip() {
+    use ic_protobuf::state::canister_state_bits::v1 as pb;
+
+    for initial in BacktraceVisibility::iter() {
+        let encoded = pb::BacktraceVisibility::from(&initial);
+        let round_trip = BacktraceVisibility::from(encoded);
+
+        assert_eq!(initial, round_trip);
+    }
+}
+
 #[test]
 fn long_execution_mode_round_trip() {
     use ic_protobuf::state::canister_state_bits::v1 as pb;
@@ -657,6 +670,18 @@ fn compatibility_for_log_visibility() {
     );
 }
 
+#[test]
+fn compatibility_for_backtrace_visibility() {
+    // If this fails, you are making a potentially incompatible change to `BacktraceVisibility`.
+    // See note [Handling changes to Enums in Replicated State] for how to proceed.
+    assert_eq!(
+        BacktraceVisibility::iter()
+            .map(|x| x as i32)
+            .collect::<Vec<i32>>(),
+        [1, 2, 3]
+    );
+}
+
 #[test]
 fn canister_state_canister_log_record_round_trip() {
     use ic_protobuf::state::canister_state_bits::v1 as pb;
@@ -55,6 +55,7 @@
 //!    the name.
 //!
 mod bitcoin;
+pub mod call_graph;
 pub mod canister_snapshots;
 pub mod canister_state;
 pub(crate) mod hash;
@@ -1211,6 +1211,9 @@ pub struct Stream {
     /// Estimated byte size of `self.messages`.
     messages_size_bytes: usize,
 
+    /// Estimated byte size of `self.messages`, by sending canister.
+    senders_size_bytes: BTreeMap<CanisterId, usize>,
+
     /// Stream flags observed in the header of the reverse stream.
     reverse_stream_flags: StreamFlags,
 }
@@ -1221,6 +1224,7 @@ impl Default for Stream {
         let signals_end = Default::default();
         let reject_signals = VecDeque::default();
         let messages_size_bytes = Self::size_bytes(&messages);
+        let senders_size_bytes = Self::senders_size_bytes_from(&messages);
         let reverse_stream_flags = StreamFlags {
             deprecated_responses_only: false,
         };
@@ -1229,6 +1233,7 @@ impl Default for Stream {
             signals_end,
             reject_signals,
             messages_size_bytes,
+            senders_size_bytes,
             reverse_stream_flags,
         }
     }
@@ -1268,6 +1273,7 @@ impl TryFrom<pb_queues::Stream> for Stream {
             messages.push(req_or_resp.try_into()?);
         }
         let messages_size_bytes = Self::size_bytes(&messages);
+        let senders_size_bytes = Self::senders_size_bytes_from(&messages);
 
         let signals_end = item.signals_end.into();
         // TODO: MR-577 Remove `deprecated_reject_signals` cases once all replicas are updated.
@@ -1326,6 +1332,7 @@ impl TryFrom<pb_queues::Stream> for Stream {
             signals_end,
             reject_signals,
             messages_size_bytes,
+            senders_size_bytes,
             reverse_stream_flags: item
                 .reverse_stream_flags
                 .map(|flags| StreamFlags {
@@ -1340,11 +1347,13 @@ impl Stream {
     /// Creates a new `Stream` with the given `messages` and `signals_end`.
     pub fn new(messages: StreamIndexedQueue<RequestOrResponse>, signals_end: StreamIndex) -> Self {
         let messages_size_bytes = Self::size_bytes(&messages);
+        let senders_size_bytes = Self::senders_size_bytes_from(&messages);
         Self {
             messages,
             signals_end,
             reject_signals: VecDeque::new(),
             messages_size_bytes,
+            senders_size_bytes,
             reverse_stream_flags: Default::default(),
         }
     }
@@ -1356,11 +1365,13 @@ impl Stream {
         reject_signals: VecDeque<RejectSignal>,
     ) -> Self {
         let messages_size_bytes = Self::size_bytes(&messages);
+        let senders_size_bytes = Self::senders_size_bytes_from(&messages);
         Self {
             messages,
             signals_end,
             reject_signals,
             messages_size_bytes,
+            senders_size_bytes,
             reverse_stream_flags: Default::default(),
         }
     }
@@ -1401,6 +1412,7 @@ impl Stream {
     /// Appends the given message to the tail of the stream.
     pub fn push(&mut self, message: RequestOrResponse) {
         self.messages_size_bytes += message.count_bytes();
+        *self.senders_size_bytes.entry(message.sender()).or_default() += message.count_bytes();
         self.messages.push(message);
         debug_assert_eq!(Self::size_bytes(&self.messages), self.messages_size_bytes);
     }
@@ -1443,6 +1455,14 @@ impl Stream {
             // Deduct every discarded message from the stream's byte size.
             self.messages_size_bytes -= msg.count_bytes();
             debug_assert_eq!(Self::size_bytes(&self.messages), self.messages_size_bytes);
+            match self.senders_size_bytes.get_mut(&msg.sender()) {
+                Some(sender_size_bytes) if *sender_size_bytes > msg.count_bytes() => {
+                    *sender_size_bytes -= msg.count_bytes();
+                }
+                _ => {
+                    self.senders_size_bytes.remove(&msg.sender());
+                }
+            }
 
             // If we received a reject signal for this message, collect it in
             // `rejected_messages`.
@@ -1496,6 +1516,25 @@ impl Stream {
         messages.iter().map(|(_, m)| m.count_bytes()).sum()
     }
 
+    /// Calculates the estimated byte size of the given messages, by sending canister.
+    ///
+    /// Time complexity: O(num_messages).
+    fn senders_size_bytes_from(
+        messages: &StreamIndexedQueue<RequestOrResponse>,
+    ) -> BTreeMap<CanisterId, usize> {
+        let mut senders_size_bytes = BTreeMap::new();
+        for (_, msg) in messages.iter() {
+            *senders_size_bytes.entry(msg.sender()).or_default() += msg.count_bytes();
+        }
+        senders_size_bytes
+    }
+
+    /// Returns the estimated byte size of the messages in the stream, by sending
+    /// canister. Canisters without messages in the stream have no entry.
+    pub fn senders_size_bytes(&self) -> &BTreeMap<CanisterId, usize> {
+        &self.senders_size_bytes
+    }
+
     /// Returns a reference to the reverse stream flags.
     pub fn reverse_stream_flags(&self) -> &StreamFlags {
         &self.reverse_stream_flags
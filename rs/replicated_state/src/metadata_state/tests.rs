@@ -17,7 +17,7 @@ use ic_test_utilities_types::{
 };
 use ic_types::{
     batch::BlockmakerMetrics,
-    canister_http::{CanisterHttpMethod, CanisterHttpRequestContext},
+    canister_http::{CanisterHttpMethod, CanisterHttpRequestContext, Replication},
     ingress::WasmResult,
     messages::{CallbackId, CanisterCall, Payload, Request, RequestMetadata},
     time::CoarseTime,
@@ -218,6 +218,53 @@ fn streams_stats() {
     assert_eq!(streams.responses_size_bytes(), &expected_responses_size);
 }
 
+#[test]
+fn stream_senders_size_bytes() {
+    let local_a = canister_test_id(1);
+    let local_b = canister_test_id(2);
+    let remote = canister_test_id(3);
+
+    fn request(sender: CanisterId, receiver: CanisterId) -> (RequestOrResponse, usize) {
+        let req: RequestOrResponse = RequestBuilder::default()
+            .sender(sender)
+            .receiver(receiver)
+            .build()
+            .into();
+        let req_bytes = req.count_bytes();
+        (req, req_bytes)
+    }
+
+    let (req_a1, req_a1_size) = request(local_a, remote);
+    let (req_b, req_b_size) = request(local_b, remote);
+    let (req_a2, req_a2_size) = request(local_a, remote);
+
+    let mut stream = Stream::default();
+    assert!(stream.senders_size_bytes().is_empty());
+
+    stream.push(req_a1);
+    stream.push(req_b);
+    stream.push(req_a2);
+    assert_eq!(
+        stream.senders_size_bytes(),
+        &btreemap! {
+            local_a => req_a1_size + req_a2_size,
+            local_b => req_b_size,
+        }
+    );
+
+    // Discard `req_a1` and `req_b`.
+    stream.discard_messages_before(2.into(), &Default::default());
+    // `local_b` has no more messages in the stream, so its entry is gone.
+    assert_eq!(
+        stream.senders_size_bytes(),
+        &btreemap! { local_a => req_a2_size }
+    );
+
+    // Stats are recomputed from scratch on deserialization.
+    let deserialized = Stream::try_from(pb_queues::Stream::from(&stream)).unwrap();
+    assert_eq!(stream, deserialized);
+}
+
 #[test]
 fn streams_stats_after_deserialization() {
     let mut system_metadata = SystemMetadata::new(SUBNET_0, SubnetType::Application);
@@ -705,6 +752,8 @@ fn subnet_call_contexts_deserialization() {
         http_method: CanisterHttpMethod::GET,
         transform: Some(transform.clone()),
         time: UNIX_EPOCH,
+        replication: Replication::FullyReplicated,
+        response_offset: None,
     };
     subnet_call_context_manager.push_context(SubnetCallContext::CanisterHttpRequest(
         canister_http_request,
@@ -24,7 +24,7 @@ pub use storage::{
 use storage::{OverlayFile, OverlayVersion, Storage};
 
 use ic_types::{Height, NumOsPages, MAX_STABLE_MEMORY_IN_BYTES};
-use int_map::{Bounds, IntMap};
+use int_map::{Bounds, IntMap, IntMapVersion};
 use libc::off_t;
 use page_allocator::Page;
 use prometheus::{Histogram, HistogramVec, IntCounter, IntCounterVec};
@@ -211,6 +211,16 @@ impl PageDelta {
     fn max_page_index(&self) -> Option<PageIndex> {
         self.0.max_key().map(PageIndex::from)
     }
+
+    /// Returns a version of this delta that does not keep its pages alive.
+    fn version(&self) -> IntMapVersion<Page> {
+        self.0.version()
+    }
+
+    /// Returns true if this delta is still the one given version refers to.
+    fn has_version(&self, version: &IntMapVersion<Page>) -> bool {
+        self.0.has_version(version)
+    }
 }
 
 impl<I> From<I> for PageDelta
@@ -412,6 +422,13 @@ impl<'a> MemoryInstructions<'a> {
     }
 }
 
+/// A cheap handle that identifies the contents of a `PageMap` at some point in
+/// time without keeping its pages alive. See `PageMap::version()`.
+pub struct PageMapVersion {
+    base_height: Option<Height>,
+    page_delta: IntMapVersion<Page>,
+}
+
 /// `PageMap` is a data structure that represents an image of a canister virtual
 /// memory.  The memory is viewed as a collection of _pages_. `PageMap` uses
 /// 4KiB host OS pages to track the heap contents, not 64KiB Wasm pages.
@@ -835,6 +852,24 @@ impl PageMap {
         self.page_delta.iter().map(|(index, _)| index).collect()
     }
 
+    /// Returns the current version of this page map.
+    ///
+    /// The version is conservative: `has_version()` returning true guarantees
+    /// that the contents did not change, but a page map may stop having its
+    /// version without any change of its contents, e.g. after a checkpoint.
+    pub fn version(&self) -> PageMapVersion {
+        PageMapVersion {
+            base_height: self.base_height,
+            page_delta: self.page_delta.version(),
+        }
+    }
+
+    /// Returns true if the contents of this page map are the same as when the
+    /// given version was taken. Takes constant time.
+    pub fn has_version(&self, version: &PageMapVersion) -> bool {
+        self.base_height == version.base_height && self.page_delta.has_version(&version.page_delta)
+    }
+
     /// Whether there are any page deltas
     pub fn page_delta_is_empty(&self) -> bool {
         self.page_delta.is_empty()
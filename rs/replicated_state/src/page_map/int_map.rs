@@ -3,7 +3,10 @@
 #[cfg(test)]
 mod test;
 
-use std::{cmp::Ordering, sync::Arc};
+use std::{
+    cmp::Ordering,
+    sync::{Arc, Weak},
+};
 
 /// Big-endian patricia trees.
 #[derive(Clone, Debug, Default)]
@@ -461,6 +464,104 @@ impl<T: Clone> IntMap<T> {
     }
 }
 
+/// Values whose identity can be tracked without keeping them alive.
+pub trait WeakIdentity {
+    type Weak;
+
+    /// Returns a weak reference to this value.
+    fn downgrade(&self) -> Self::Weak;
+
+    /// Returns true if `weak` refers to this very value.
+    fn is_same(&self, weak: &Self::Weak) -> bool;
+}
+
+impl<U> WeakIdentity for Arc<U> {
+    type Weak = Weak<U>;
+
+    fn downgrade(&self) -> Weak<U> {
+        Arc::downgrade(self)
+    }
+
+    fn is_same(&self, weak: &Weak<U>) -> bool {
+        std::ptr::eq(Arc::as_ptr(self), weak.as_ptr())
+    }
+}
+
+/// Identifies a version of an `IntMap` without keeping its entries alive.
+///
+/// Since the map is persistent, a clone of the map has the same root as the
+/// original until either of them is modified. Holding weak references to the
+/// root prevents the memory of the root from being reused, so the identity
+/// check cannot be fooled by a different map allocated at the same address.
+pub struct IntMapVersion<T: WeakIdentity>(Version<T>);
+
+enum Version<T: WeakIdentity> {
+    Empty,
+    Leaf(u64, T::Weak),
+    Branch {
+        prefix: u64,
+        branching_bit: u8,
+        left: Weak<Tree<T>>,
+        right: Weak<Tree<T>>,
+    },
+}
+
+impl<T: Clone + WeakIdentity> IntMap<T> {
+    /// Returns the version of this map.
+    ///
+    /// Complexity: O(1)
+    pub fn version(&self) -> IntMapVersion<T> {
+        IntMapVersion(match &self.0 {
+            Tree::Empty => Version::Empty,
+            Tree::Leaf(k, v) => Version::Leaf(*k, v.downgrade()),
+            Tree::Branch {
+                prefix,
+                branching_bit,
+                left,
+                right,
+            } => Version::Branch {
+                prefix: *prefix,
+                branching_bit: *branching_bit,
+                left: Arc::downgrade(left),
+                right: Arc::downgrade(right),
+            },
+        })
+    }
+
+    /// Returns true if this map is known to be the given version, i.e. it is a
+    /// clone of the map the version was taken from and neither of them was
+    /// modified. Maps with equal entries that were built independently are
+    /// not recognized as the same version.
+    ///
+    /// Complexity: O(1)
+    pub fn has_version(&self, version: &IntMapVersion<T>) -> bool {
+        match (&self.0, &version.0) {
+            (Tree::Empty, Version::Empty) => true,
+            (Tree::Leaf(k0, v0), Version::Leaf(k1, v1)) => k0 == k1 && v0.is_same(v1),
+            (
+                Tree::Branch {
+                    prefix: p0,
+                    branching_bit: b0,
+                    left: l0,
+                    right: r0,
+                },
+                Version::Branch {
+                    prefix: p1,
+                    branching_bit: b1,
+                    left: l1,
+                    right: r1,
+                },
+            ) => {
+                p0 == p1
+                    && b0 == b1
+                    && std::ptr::eq(Arc::as_ptr(l0), l1.as_ptr())
+                    && std::ptr::eq(Arc::as_ptr(r0), r1.as_ptr())
+            }
+            _ => false,
+        }
+    }
+}
+
 impl<T: Clone> std::iter::FromIterator<(u64, T)> for IntMap<T> {
     fn from_iter<I>(iter: I) -> Self
     where
@@ -1,3 +1,5 @@
+use std::sync::Arc;
+
 use super::IntMap;
 
 #[test]
@@ -96,3 +98,32 @@ fn test_max_key_range() {
         assert_eq!(m.max_key(), Some(i));
     }
 }
+
+#[test]
+fn test_int_map_version() {
+    let empty: IntMap<Arc<u64>> = IntMap::new();
+    let leaf = empty.clone().insert(1, Arc::new(1));
+    let branch = leaf.clone().insert(2, Arc::new(2));
+
+    for m in [&empty, &leaf, &branch] {
+        let version = m.version();
+        assert!(m.has_version(&version));
+        assert!(m.clone().has_version(&version));
+    }
+
+    // Modifications create a new version, even if the entries are equal.
+    let version = leaf.version();
+    assert!(!leaf.clone().insert(1, Arc::new(1)).has_version(&version));
+    assert!(!branch.has_version(&version));
+    let version = branch.version();
+    assert!(!branch.clone().insert(3, Arc::new(3)).has_version(&version));
+    let rebuilt: IntMap<Arc<u64>> = (1..3).map(|i| (i, Arc::new(i))).collect();
+    assert!(!rebuilt.has_version(&version));
+
+    // The version does not keep the entries alive.
+    let value = Arc::new(4);
+    let m = IntMap::new().insert(4, Arc::clone(&value));
+    let _version = m.version();
+    drop(m);
+    assert_eq!(Arc::strong_count(&value), 1);
+}
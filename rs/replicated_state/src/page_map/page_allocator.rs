@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 use std::{
     fmt::Debug,
     sync::atomic::{AtomicUsize, Ordering},
-    sync::Arc,
+    sync::{Arc, Weak},
 };
 mod page_bytes;
 
@@ -14,6 +14,7 @@ pub mod mmap;
 use mmap::{PageAllocatorId, PageAllocatorInner, PageInner};
 
 pub use self::page_allocator_registry::PageAllocatorRegistry;
+use super::int_map::WeakIdentity;
 use super::{FileDescriptor, FileOffset, PageAllocatorFileDescriptor};
 
 static ALLOCATED_PAGES: PageCounter = PageCounter::new();
@@ -42,6 +43,19 @@ impl Page {
     }
 }
 
+/// Lets a `PageDelta` version refer to its pages without keeping them alive.
+impl WeakIdentity for Page {
+    type Weak = Weak<PageInner>;
+
+    fn downgrade(&self) -> Weak<PageInner> {
+        self.0.downgrade()
+    }
+
+    fn is_same(&self, weak: &Weak<PageInner>) -> bool {
+        self.0.is_same(weak)
+    }
+}
+
 /// We have to implement `Clone` manually because `#[derive(Clone)]` is confused
 /// by the generic parameter even though it is wrapped in `Arc`.
 impl Clone for Page {
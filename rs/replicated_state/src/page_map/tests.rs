@@ -138,6 +138,24 @@ fn new_delta_wins_on_update() {
     assert_eq!(page_map.get_page(PageIndex::new(1)), &page_2);
 }
 
+#[test]
+fn page_map_version_changes_on_update() {
+    let mut page_map = PageMap::new_for_testing();
+    let empty = page_map.version();
+    assert!(page_map.has_version(&empty));
+
+    page_map.update(&[(PageIndex::new(1), &[1u8; PAGE_SIZE])]);
+    assert!(!page_map.has_version(&empty));
+
+    let version = page_map.version();
+    let copy = page_map.clone();
+    assert!(copy.has_version(&version));
+
+    page_map.update(&[(PageIndex::new(1), &[2u8; PAGE_SIZE])]);
+    assert!(!page_map.has_version(&version));
+    assert!(copy.has_version(&version));
+}
+
 #[test]
 fn persisted_map_is_equivalent_to_the_original() {
     fn persist_check_eq_and_load(
@@ -231,6 +231,10 @@ pub trait PeekableOutputIterator: std::iter::Iterator<Item = RequestOrResponse>
     /// in the output queue.
     fn exclude_queue(&mut self);
 
+    /// Moves the next canister (i.e. all its output queues) to the back of the
+    /// iteration order, without consuming any messages.
+    fn defer_canister(&mut self);
+
     /// Returns the exact number of messages left in the iterator.
     fn size(&self) -> usize;
 }
@@ -250,6 +254,12 @@ impl PeekableOutputIterator for OutputIterator<'_> {
         }
     }
 
+    fn defer_canister(&mut self) {
+        if let Some(canister_iterator) = self.canister_iterators.pop_front() {
+            self.canister_iterators.push_back(canister_iterator);
+        }
+    }
+
     fn size(&self) -> usize {
         self.size
     }
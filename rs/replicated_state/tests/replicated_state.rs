@@ -921,6 +921,48 @@ proptest! {
         assert_eq!(replicated_state.output_message_count(), excluded);
     }
 
+    /// Replicated state with multiple canisters, each with multiple output queues.
+    /// Canisters are regularly deferred instead of consuming their next message.
+    ///
+    /// Expect all messages to be consumed and the messages of every canister to be
+    /// consumed in order.
+    #[test]
+    fn peek_and_next_consistent_with_defer(
+        (mut replicated_state, raw_requests, total_requests) in arb_replicated_state_with_queues(SUBNET_ID, 20, 20, None),
+        start in 0..=1,
+        defer_step in 2..=5,
+    ) {
+        let mut output_iter = replicated_state.output_into_iter();
+
+        let mut i = start;
+        let mut consumed = Vec::new();
+        while let Some(msg) = output_iter.peek() {
+            i += 1;
+            if i % defer_step == 0 {
+                let size = output_iter.size();
+                output_iter.defer_canister();
+                assert_eq!(size, output_iter.size());
+            } else {
+                assert_eq!(Some(msg.clone()), output_iter.next());
+                consumed.push(msg.clone());
+            }
+        }
+
+        drop(output_iter);
+        assert_eq!(total_requests, consumed.len());
+        assert_eq!(replicated_state.output_message_count(), 0);
+
+        // Deferring a canister never reorders the messages of that canister.
+        for requests in raw_requests {
+            let consumed_from_canister: Vec<_> = consumed
+                .iter()
+                .filter(|msg| requests.iter().any(|request| request == *msg))
+                .cloned()
+                .collect();
+            assert_eq!(Vec::from(requests), consumed_from_canister);
+        }
+    }
+
     #[test]
     fn iter_yields_correct_elements(
        (mut replicated_state, mut raw_requests, _total_requests) in arb_replicated_state_with_queues(SUBNET_ID, 20, 20, None),
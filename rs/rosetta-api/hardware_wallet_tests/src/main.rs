@@ -86,6 +86,8 @@ pub fn generate_zondax_test(
         sender: Blob(pid.into_vec()),
         // sender: Blob(from.into_vec()),
         ingress_expiry: 0,
+        tip: None,
+        trace: None,
     };
 
     let from = AccountIdentifier::new(pid, from_subaccount);
@@ -334,6 +334,8 @@ fn handle_transfer_operation(
                 .into_vec(),
         ),
         ingress_expiry: 0,
+        tip: None,
+        trace: None,
     };
 
     add_payloads(
@@ -380,6 +382,8 @@ fn handle_neuron_info(
         nonce: None,
         sender: Blob(sender.into_vec()), // Sender is controller or hotkey.
         ingress_expiry: 0,
+        tip: None,
+        trace: None,
     };
     add_payloads(
         payloads,
@@ -432,6 +436,8 @@ fn handle_list_neurons(
         nonce: None,
         sender: Blob(sender.into_vec()), // Sender is controller or hotkey.
         ingress_expiry: 0,
+        tip: None,
+        trace: None,
     };
     add_payloads(
         payloads,
@@ -523,6 +529,8 @@ fn handle_stake(
                 .into_vec(),
         ),
         ingress_expiry: 0,
+        tip: None,
+        trace: None,
     };
 
     add_payloads(
@@ -928,6 +936,8 @@ fn add_neuron_management_payload(
                 .into_vec(),
         ),
         ingress_expiry: 0,
+        tip: None,
+        trace: None,
     };
 
     add_payloads(
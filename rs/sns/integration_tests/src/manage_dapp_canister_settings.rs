@@ -106,6 +106,7 @@ fn test_manage_dapp_canister_settings_successful() {
             100_000,
             Some(1_000_000_000_000),
             ic_management_canister_types::LogVisibility::Public,
+            ic_management_canister_types::BacktraceVisibility::default(),
             Some(1_000_000_000),
         ),
     );
@@ -159,6 +160,7 @@ fn test_manage_dapp_canister_settings_successful() {
             0,
             Some(0),
             ic_management_canister_types::LogVisibility::Controllers,
+            ic_management_canister_types::BacktraceVisibility::default(),
             Some(2_000_000_000),
         ),
     );
@@ -236,6 +238,7 @@ fn test_manage_dapp_canister_settings_failure() {
             100_000,
             Some(1_000_000_000_000),
             ic_management_canister_types::LogVisibility::Public,
+            ic_management_canister_types::BacktraceVisibility::default(),
             Some(1_000_000_000),
         ),
     );
@@ -309,6 +312,7 @@ fn test_manage_dapp_canister_settings_failure() {
             100_000,
             Some(1_000_000_000_000),
             ic_management_canister_types::LogVisibility::Public,
+            ic_management_canister_types::BacktraceVisibility::default(),
             Some(1_000_000_000),
         ),
     );
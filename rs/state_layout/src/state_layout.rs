@@ -4,7 +4,7 @@ use crate::utils::do_copy;
 use ic_base_types::{NumBytes, NumSeconds};
 use ic_config::flag_status::FlagStatus;
 use ic_logger::{error, info, warn, ReplicaLogger};
-use ic_management_canister_types::LogVisibility;
+use ic_management_canister_types::{BacktraceVisibility, LogVisibility};
 use ic_metrics::{buckets::decimal_buckets, MetricsRegistry};
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
@@ -170,6 +170,7 @@ pub struct CanisterStateBits {
     pub canister_log: CanisterLog,
     pub wasm_memory_limit: Option<NumBytes>,
     pub next_snapshot_id: u64,
+    pub backtrace_visibility: BacktraceVisibility,
 }
 
 /// This struct contains bits of the `CanisterSnapshot` that are not already
@@ -273,6 +274,10 @@ struct CheckpointRefData {
 /// ├── diverged_state_markers
 /// │   └──<hex(round)>
 /// │
+/// ├── compilation_cache (configured by `hypervisor.compilation_cache_dir`)
+/// │   └──modules_<fingerprint>
+/// │      └── <hex(wasm_hash)>.module
+/// │
 /// ├── tmp
 /// └── fs_tmp
 /// ```
@@ -1924,6 +1929,10 @@ impl From<CanisterStateBits> for pb_canister_state_bits::CanisterStateBits {
             next_canister_log_record_idx: item.canister_log.next_idx(),
             wasm_memory_limit: item.wasm_memory_limit.map(|v| v.get()),
             next_snapshot_id: item.next_snapshot_id,
+            backtrace_visibility: pb_canister_state_bits::BacktraceVisibility::from(
+                &item.backtrace_visibility,
+            )
+            .into(),
         }
     }
 }
@@ -2080,6 +2089,16 @@ impl TryFrom<pb_canister_state_bits::CanisterStateBits> for CanisterStateBits {
             ),
             wasm_memory_limit: value.wasm_memory_limit.map(NumBytes::from),
             next_snapshot_id: value.next_snapshot_id,
+            backtrace_visibility: BacktraceVisibility::from(
+                pb_canister_state_bits::BacktraceVisibility::try_from(value.backtrace_visibility)
+                    .map_err(|_| ProxyDecodeError::ValueOutOfRange {
+                    typ: "BacktraceVisibility",
+                    err: format!(
+                        "Unexpected value of backtrace visibility: {}",
+                        value.backtrace_visibility
+                    ),
+                })?,
+            ),
         })
     }
 }
@@ -1,8 +1,8 @@
 use super::*;
 
 use ic_management_canister_types::{
-    CanisterChange, CanisterChangeDetails, CanisterChangeOrigin, CanisterInstallMode,
-    LogVisibility, IC_00,
+    BacktraceVisibility, CanisterChange, CanisterChangeDetails, CanisterChangeOrigin,
+    CanisterInstallMode, LogVisibility, IC_00,
 };
 use ic_replicated_state::{
     canister_state::system_state::CanisterHistory,
@@ -57,6 +57,7 @@ fn default_canister_state_bits() -> CanisterStateBits {
         canister_log: Default::default(),
         wasm_memory_limit: None,
         next_snapshot_id: 0,
+        backtrace_visibility: BacktraceVisibility::default(),
     }
 }
 
@@ -76,7 +76,7 @@ use ic_replicated_state::{
     canister_state::{system_state::CyclesUseCase, NumWasmPages, WASM_PAGE_SIZE_IN_BYTES},
     metadata_state::subnet_call_context_manager::SignWithThresholdContext,
     page_map::Buffer,
-    CheckpointLoadingMetrics, Memory, PageMap, ReplicatedState,
+    CheckpointLoadingMetrics, Memory, PageMap, ReplicatedState, StateError,
 };
 use ic_state_layout::{CheckpointLayout, RwPolicy};
 use ic_state_manager::StateManagerImpl;
@@ -107,8 +107,8 @@ use ic_types::{
     malicious_flags::MaliciousFlags,
     messages::{
         Blob, Certificate, CertificateDelegation, HttpCallContent, HttpCanisterUpdate,
-        HttpRequestEnvelope, Payload as MsgPayload, Query, QuerySource, RejectContext,
-        SignedIngress, SignedIngressContent, EXPECTED_MESSAGE_ID_LENGTH,
+        HttpRequestEnvelope, Payload as MsgPayload, Query, QuerySource, RejectContext, Request,
+        RequestOrResponse, SignedIngress, SignedIngressContent, EXPECTED_MESSAGE_ID_LENGTH,
     },
     signature::ThresholdSignature,
     time::GENESIS,
@@ -1476,6 +1476,8 @@ impl StateMachine {
                     sender: sender.into(),
                     ingress_expiry,
                     nonce: nonce.clone(),
+                    tip: None,
+                    trace: None,
                 },
             },
             sender_pubkey: None,
@@ -1950,6 +1952,26 @@ impl StateMachine {
             .commit_and_certify(state, h.increment(), CertificationScope::Metadata);
     }
 
+    /// Pushes the given request into the input queue of its receiver as if it
+    /// had been inducted from a stream, so that it is executed in the next
+    /// round. The sender does not have to exist in this state machine.
+    ///
+    /// This is useful for reproducing the execution of a request captured on
+    /// another subnet.
+    pub fn push_input_request(&self, request: Request) -> Result<(), StateError> {
+        let (h, mut state) = self.state_manager.take_tip();
+        let mut subnet_available_memory = i64::MAX;
+        let result = state
+            .push_input(
+                RequestOrResponse::Request(Arc::new(request)),
+                &mut subnet_available_memory,
+            )
+            .map_err(|(err, _)| err);
+        self.state_manager
+            .commit_and_certify(state, h.increment(), CertificationScope::Metadata);
+        result
+    }
+
     // Enable checkpoints and make a tick to write a checkpoint.
     fn checkpointed_tick(&self) {
         let checkpoint_interval_length = self.checkpoint_interval_length.load(Ordering::Relaxed);
@@ -2938,6 +2960,8 @@ impl PayloadBuilder {
                     sender: Blob(sender.into_vec()),
                     ingress_expiry: self.expiry_time.as_nanos_since_unix_epoch(),
                     nonce: self.nonce.map(|n| Blob(n.to_be_bytes().to_vec())),
+                    tip: None,
+                    trace: None,
                 },
             },
             sender_pubkey: None,
@@ -900,6 +900,7 @@ fn serialize_canister_to_tip(
             canister_log: canister_state.system_state.canister_log.clone(),
             wasm_memory_limit: canister_state.system_state.wasm_memory_limit,
             next_snapshot_id: canister_state.system_state.next_snapshot_id,
+            backtrace_visibility: canister_state.system_state.backtrace_visibility,
         }
         .into(),
     )?;
@@ -1,6 +1,6 @@
 use assert_matches::assert_matches;
 use ic_certification_version::{
-    CertificationVersion::{V11, V15},
+    CertificationVersion::{V11, V15, V19},
     CURRENT_CERTIFICATION_VERSION,
 };
 use ic_config::{
@@ -57,7 +57,7 @@ use ic_types::batch::{
 use ic_types::{
     crypto::CryptoHash,
     ingress::{IngressState, IngressStatus, WasmResult},
-    messages::CallbackId,
+    messages::{CallbackId, RequestMetadata, TraceId},
     time::{Time, UNIX_EPOCH},
     xnet::{StreamIndex, StreamIndexedQueue},
     CanisterId, CryptoHashOfPartialState, CryptoHashOfState, Height, NodeId, NumBytes, PrincipalId,
@@ -5745,6 +5745,31 @@ fn lsmt_shard_size_is_stable() {
     assert_eq!(lsmt_config_default().shard_num_pages, 10 * 1024 * 1024);
 }
 
+#[test]
+fn stream_store_preserves_trace_ids() {
+    // Trace IDs are only encoded from certification version 19 onwards.
+    assert!(CURRENT_CERTIFICATION_VERSION >= V19);
+
+    let mut stream = Stream::default();
+    for (i, trace_id) in [Some(TraceId::new([7; 16])), None].into_iter().enumerate() {
+        let metadata =
+            RequestMetadata::new(1, Time::from_nanos_since_unix_epoch(100)).with_trace_id(trace_id);
+        stream.push(
+            RequestBuilder::default()
+                .sender_reply_callback(CallbackId::from(i as u64))
+                .metadata(Some(metadata))
+                .build()
+                .into(),
+        );
+    }
+
+    // The decoded slice must be equal to the stream, including the trace ID of
+    // the first request.
+    encode_decode_stream_test(stream, None, None, true, |state_manager, slice| {
+        (state_manager, slice)
+    });
+}
+
 proptest! {
     #[test]
     fn stream_store_encode_decode(stream in arb_stream(0, 10, 0, 10), size_limit in 0..20usize) {
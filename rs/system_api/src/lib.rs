@@ -338,6 +338,7 @@ pub enum ApiType {
         method_name: String,
         #[serde(with = "serde_bytes")]
         incoming_payload: Vec<u8>,
+        tip: Cycles,
         time: Time,
         message_accepted: bool,
     },
@@ -520,12 +521,14 @@ impl ApiType {
         caller: PrincipalId,
         method_name: String,
         incoming_payload: Vec<u8>,
+        tip: Cycles,
         time: Time,
     ) -> Self {
         Self::InspectMessage {
             caller,
             method_name,
             incoming_payload,
+            tip,
             time,
             message_accepted: false,
         }
@@ -1492,6 +1495,17 @@ impl SystemApiImpl {
         self.sandbox_safe_system_state.canister_log()
     }
 
+    /// Returns true if backtraces may be written to the canister log.
+    pub fn backtrace_can_be_logged(&self) -> bool {
+        self.sandbox_safe_system_state.backtrace_can_be_logged()
+    }
+
+    /// Returns true if the caller may see the backtraces of the canister's
+    /// traps.
+    pub fn caller_can_view_backtrace(&self) -> bool {
+        self.sandbox_safe_system_state.caller_can_view_backtrace()
+    }
+
     /// Checks if the current API type is an install or upgrade message.
     /// This is relevant when enforcing the stable memory dirty page limit.
     pub fn is_install_or_upgrade_message(&self) -> bool {
@@ -1798,6 +1812,25 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
+    fn ic0_msg_tip(&self) -> HypervisorResult<u64> {
+        let result = match &self.api_type {
+            ApiType::Start { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::Init { .. } => Err(self.error_for("ic0_msg_tip")),
+            // The tip originates from a `u64` field of the ingress message.
+            ApiType::InspectMessage { tip, .. } => Ok(u64::try_from(tip.get()).unwrap_or(u64::MAX)),
+        };
+        trace_syscall!(self, MsgTip, result);
+        result
+    }
+
     fn ic0_msg_reply(&mut self) -> HypervisorResult<()> {
         let result = match self.get_response_info() {
             None => Err(self.error_for("ic0_msg_reply")),
@@ -2025,9 +2058,9 @@ impl SystemApi for SystemApiImpl {
         name_src: usize,
         name_len: usize,
         reply_fun: u32,
-        reply_env: u32,
+        reply_env: u64,
         reject_fun: u32,
-        reject_env: u32,
+        reject_env: u64,
         heap: &[u8],
     ) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
@@ -2072,8 +2105,8 @@ impl SystemApi for SystemApiImpl {
                     name_src,
                     name_len,
                     heap,
-                    WasmClosure::new(reply_fun, reply_env.into()),
-                    WasmClosure::new(reject_fun, reject_env.into()),
+                    WasmClosure::new(reply_fun, reply_env),
+                    WasmClosure::new(reject_fun, reject_env),
                     MAX_INTER_CANISTER_PAYLOAD_IN_BYTES,
                     MULTIPLIER_MAX_SIZE_LOCAL_SUBNET,
                     self.max_sum_exported_function_name_lengths,
@@ -2146,7 +2179,7 @@ impl SystemApi for SystemApiImpl {
         result
     }
 
-    fn ic0_call_on_cleanup(&mut self, fun: u32, env: u32) -> HypervisorResult<()> {
+    fn ic0_call_on_cleanup(&mut self, fun: u32, env: u64) -> HypervisorResult<()> {
         let result = match &mut self.api_type {
             ApiType::Start { .. }
             | ApiType::Init { .. }
@@ -2181,7 +2214,7 @@ impl SystemApi for SystemApiImpl {
                     error: "ic0.call_on_cleanup called when no call is under construction."
                         .to_string(),
                 }),
-                Some(request) => request.set_on_cleanup(WasmClosure::new(fun, env.into())),
+                Some(request) => request.set_on_cleanup(WasmClosure::new(fun, env)),
             },
         };
         trace_syscall!(self, CallOnCleanup, fun, env);
@@ -2660,9 +2693,10 @@ impl SystemApi for SystemApiImpl {
         let resulting_size = current_size.saturating_add(additional_pages);
         if let StableMemoryApi::Stable32 = stable_memory_api {
             if current_size > MAX_32_BIT_STABLE_MEMORY_IN_PAGES {
-                return Err(HypervisorError::Trapped(
-                    TrapCode::StableMemoryTooBigFor32Bit,
-                ));
+                return Err(HypervisorError::Trapped {
+                    trap_code: TrapCode::StableMemoryTooBigFor32Bit,
+                    backtrace: None,
+                });
             }
             if resulting_size > MAX_32_BIT_STABLE_MEMORY_IN_PAGES {
                 return Ok(StableGrowOutcome::Failure);
@@ -2704,7 +2738,10 @@ impl SystemApi for SystemApiImpl {
                 .ic0_canister_cycle_balance_helper("ic0_canister_cycle_balance")?
                 .into_parts();
             if high_amount != 0 {
-                return Err(HypervisorError::Trapped(CyclesAmountTooBigFor64Bit));
+                return Err(HypervisorError::Trapped {
+                    trap_code: CyclesAmountTooBigFor64Bit,
+                    backtrace: None,
+                });
             }
             Ok(low_amount)
         };
@@ -2734,7 +2771,10 @@ impl SystemApi for SystemApiImpl {
                 .ic0_msg_cycles_available_helper("ic0_msg_cycles_available")?
                 .into_parts();
             if high_amount != 0 {
-                return Err(HypervisorError::Trapped(CyclesAmountTooBigFor64Bit));
+                return Err(HypervisorError::Trapped {
+                    trap_code: CyclesAmountTooBigFor64Bit,
+                    backtrace: None,
+                });
             }
             Ok(low_amount)
         };
@@ -2759,7 +2799,10 @@ impl SystemApi for SystemApiImpl {
                 .ic0_msg_cycles_refunded_helper("ic0_msg_cycles_refunded")?
                 .into_parts();
             if high_amount != 0 {
-                return Err(HypervisorError::Trapped(CyclesAmountTooBigFor64Bit));
+                return Err(HypervisorError::Trapped {
+                    trap_code: CyclesAmountTooBigFor64Bit,
+                    backtrace: None,
+                });
             }
             Ok(low_amount)
         };
@@ -3088,7 +3131,10 @@ impl SystemApi for SystemApiImpl {
             let msg = valid_subslice("trap", src, size, heap)
                 .map(|bytes| String::from_utf8_lossy(bytes).to_string())
                 .unwrap_or_else(|_| "(trap message out of memory bounds)".to_string());
-            CalledTrap(msg)
+            CalledTrap {
+                message: msg,
+                backtrace: None,
+            }
         };
         trace_syscall!(self, Trap, src, size, summarize(heap, src, size));
         Err(result)
@@ -3267,6 +3313,69 @@ impl SystemApi for SystemApiImpl {
         trace_syscall!(self, CyclesBurn128, result, amount);
         result
     }
+
+    fn ic0_trace_id_size(&self) -> HypervisorResult<usize> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_trace_id_size")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => Ok(self
+                .sandbox_safe_system_state
+                .trace_id()
+                .map_or(0, |trace_id| trace_id.as_bytes().len())),
+        };
+        trace_syscall!(self, TraceIdSize, result);
+        result
+    }
+
+    fn ic0_trace_id_copy(
+        &self,
+        dst: usize,
+        offset: usize,
+        size: usize,
+        heap: &mut [u8],
+    ) -> HypervisorResult<()> {
+        let result = match &self.api_type {
+            ApiType::Start { .. } => Err(self.error_for("ic0_trace_id_copy")),
+            ApiType::Init { .. }
+            | ApiType::SystemTask { .. }
+            | ApiType::Cleanup { .. }
+            | ApiType::Update { .. }
+            | ApiType::ReplicatedQuery { .. }
+            | ApiType::NonReplicatedQuery { .. }
+            | ApiType::ReplyCallback { .. }
+            | ApiType::RejectCallback { .. }
+            | ApiType::PreUpgrade { .. }
+            | ApiType::InspectMessage { .. } => {
+                valid_subslice("ic0.trace_id_copy heap", dst, size, heap)?;
+                let trace_id_bytes = self
+                    .sandbox_safe_system_state
+                    .trace_id()
+                    .map_or(&[][..], |trace_id| trace_id.as_bytes());
+                let slice =
+                    valid_subslice("ic0.trace_id_copy trace_id", offset, size, trace_id_bytes)?;
+                deterministic_copy_from_slice(&mut heap[dst..dst + size], slice);
+                Ok(())
+            }
+        };
+        trace_syscall!(
+            self,
+            TraceIdCopy,
+            result,
+            dst,
+            offset,
+            size,
+            summarize(heap, dst, size)
+        );
+        result
+    }
 }
 
 /// The default implementation of the `OutOfInstructionHandler` trait.
@@ -10,9 +10,9 @@ use ic_error_types::{ErrorCode, RejectCode, UserError};
 use ic_interfaces::execution_environment::{HypervisorError, HypervisorResult};
 use ic_logger::{info, ReplicaLogger};
 use ic_management_canister_types::{
-    CreateCanisterArgs, InstallChunkedCodeArgs, InstallCodeArgsV2, LoadCanisterSnapshotArgs,
-    Method as Ic00Method, Payload, ProvisionalCreateCanisterWithCyclesArgs, UninstallCodeArgs,
-    UpdateSettingsArgs, IC_00,
+    BacktraceVisibility, CreateCanisterArgs, InstallChunkedCodeArgs, InstallCodeArgsV2,
+    LoadCanisterSnapshotArgs, LogVisibility, Method as Ic00Method, Payload,
+    ProvisionalCreateCanisterWithCyclesArgs, UninstallCodeArgs, UpdateSettingsArgs, IC_00,
 };
 use ic_nns_constants::CYCLES_MINTING_CANISTER_ID;
 use ic_registry_subnet_type::SubnetType;
@@ -21,7 +21,9 @@ use ic_replicated_state::{
     CallOrigin, CanisterStatus, NetworkTopology, SystemState,
 };
 use ic_types::{
-    messages::{CallContextId, CallbackId, RejectContext, Request, RequestMetadata, NO_DEADLINE},
+    messages::{
+        CallContextId, CallbackId, RejectContext, Request, RequestMetadata, TraceId, NO_DEADLINE,
+    },
     methods::Callback,
     time::CoarseTime,
     CanisterLog, CanisterTimer, ComputeAllocation, Cycles, MemoryAllocation, NumInstructions, Time,
@@ -575,6 +577,8 @@ pub struct SandboxSafeSystemState {
     global_timer: CanisterTimer,
     canister_version: u64,
     controllers: BTreeSet<PrincipalId>,
+    log_visibility: LogVisibility,
+    backtrace_visibility: BacktraceVisibility,
     pub(super) request_metadata: RequestMetadata,
     caller: Option<PrincipalId>,
 }
@@ -605,6 +609,8 @@ impl SandboxSafeSystemState {
         global_timer: CanisterTimer,
         canister_version: u64,
         controllers: BTreeSet<PrincipalId>,
+        log_visibility: LogVisibility,
+        backtrace_visibility: BacktraceVisibility,
         request_metadata: RequestMetadata,
         caller: Option<PrincipalId>,
         next_canister_log_record_idx: u64,
@@ -638,6 +644,8 @@ impl SandboxSafeSystemState {
             global_timer,
             canister_version,
             controllers,
+            log_visibility,
+            backtrace_visibility,
             request_metadata,
             caller,
         }
@@ -720,6 +728,8 @@ impl SandboxSafeSystemState {
             system_state.global_timer,
             system_state.canister_version,
             system_state.controllers.clone(),
+            system_state.log_visibility,
+            system_state.backtrace_visibility,
             request_metadata,
             caller,
             system_state.canister_log.next_idx(),
@@ -808,6 +818,11 @@ impl SandboxSafeSystemState {
         self.call_context_deadline.unwrap_or(NO_DEADLINE)
     }
 
+    /// Returns the trace ID of the call tree being executed, if it is traced.
+    pub fn trace_id(&self) -> Option<&TraceId> {
+        self.request_metadata.trace_id()
+    }
+
     fn update_balance_change(&mut self, new_balance: Cycles) {
         self.system_state_changes.cycles_balance_change =
             CyclesBalanceChange::new(self.initial_cycles_balance, new_balance);
@@ -1255,6 +1270,26 @@ impl SandboxSafeSystemState {
         &self.system_state_changes.canister_log
     }
 
+    /// Returns true if backtraces may be written to the canister log, i.e.
+    /// if everyone who may read the log may also see the backtraces.
+    pub fn backtrace_can_be_logged(&self) -> bool {
+        match self.backtrace_visibility {
+            BacktraceVisibility::Disabled => false,
+            BacktraceVisibility::Controllers => self.log_visibility == LogVisibility::Controllers,
+            BacktraceVisibility::Public => true,
+        }
+    }
+
+    /// Returns true if the caller may see the backtraces of the canister's
+    /// traps.
+    pub fn caller_can_view_backtrace(&self) -> bool {
+        match self.backtrace_visibility {
+            BacktraceVisibility::Disabled => false,
+            BacktraceVisibility::Controllers => self.caller_is_controller(),
+            BacktraceVisibility::Public => true,
+        }
+    }
+
     fn caller_is_controller(&self) -> bool {
         if let Some(caller) = self.caller {
             self.controllers.contains(&caller)
@@ -1282,6 +1317,7 @@ mod tests {
     use ic_config::subnet_config::{CyclesAccountManagerConfig, SchedulerConfig};
     use ic_constants::SMALL_APP_SUBNET_MAX_SIZE;
     use ic_cycles_account_manager::CyclesAccountManager;
+    use ic_management_canister_types::{BacktraceVisibility, LogVisibility};
     use ic_registry_subnet_type::SubnetType;
     use ic_replicated_state::{canister_state::system_state::CyclesUseCase, SystemState};
     use ic_test_utilities_types::ids::{canister_test_id, subnet_test_id, user_test_id};
@@ -1388,6 +1424,8 @@ mod tests {
             CanisterTimer::Inactive,
             0,
             BTreeSet::new(),
+            LogVisibility::default(),
+            BacktraceVisibility::default(),
             RequestMetadata::new(0, Time::from_nanos_since_unix_epoch(0)),
             None,
             0,
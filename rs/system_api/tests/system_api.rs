@@ -225,7 +225,13 @@ fn composite_cleanup_api() -> ApiType {
 }
 
 fn inspect_message_api() -> ApiType {
-    ApiType::inspect_message(user_test_id(1).get(), "".to_string(), vec![], UNIX_EPOCH)
+    ApiType::inspect_message(
+        user_test_id(1).get(),
+        "".to_string(),
+        vec![],
+        Cycles::zero(),
+        UNIX_EPOCH,
+    )
 }
 
 fn system_task_api() -> ApiType {
@@ -264,6 +270,7 @@ fn is_supported(api_type: SystemApiCallId, context: &str) -> bool {
         SystemApiCallId::MsgMethodNameSize => vec!["F"],
         SystemApiCallId::MsgMethodNameCopy => vec!["F"],
         SystemApiCallId::AcceptMessage => vec!["F"],
+        SystemApiCallId::MsgTip => vec!["F"],
         SystemApiCallId::CallNew => vec!["U", "CQ", "Ry", "Rt", "CRy", "CRt", "T"],
         SystemApiCallId::CallOnCleanup => vec!["U", "CQ", "Ry", "Rt", "CRy", "CRt", "T"],
         SystemApiCallId::CallDataAppend => vec!["U", "CQ", "Ry", "Rt", "CRy", "CRt", "T"],
@@ -284,6 +291,8 @@ fn is_supported(api_type: SystemApiCallId, context: &str) -> bool {
         SystemApiCallId::DataCertificateSize => vec!["NRQ", "CQ"],
         SystemApiCallId::DataCertificateCopy => vec!["NRQ", "CQ"],
         SystemApiCallId::Time => vec!["*"],
+        SystemApiCallId::TraceIdSize => vec!["*"],
+        SystemApiCallId::TraceIdCopy => vec!["*"],
         SystemApiCallId::GlobalTimerSet => vec!["I", "G", "U", "Ry", "Rt", "C", "T"],
         SystemApiCallId::PerformanceCounter => vec!["*", "s"],
         SystemApiCallId::IsController => vec!["*", "s"],
@@ -395,6 +404,16 @@ fn api_availability_test(
                 context,
             );
         }
+        SystemApiCallId::MsgTip => {
+            assert_api_availability(
+                |api| api.ic0_msg_tip(),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
         SystemApiCallId::MsgDeadline => {
             assert_api_availability(
                 |api| api.ic0_msg_deadline(),
@@ -465,6 +484,26 @@ fn api_availability_test(
                 context,
             );
         }
+        SystemApiCallId::TraceIdSize => {
+            assert_api_availability(
+                |api| api.ic0_trace_id_size(),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
+        SystemApiCallId::TraceIdCopy => {
+            assert_api_availability(
+                |api| api.ic0_trace_id_copy(0, 0, 0, &mut [42; 128]),
+                api_type,
+                &system_state,
+                cycles_account_manager,
+                api_type_enum,
+                context,
+            );
+        }
         SystemApiCallId::DebugPrint => {
             assert_api_availability(
                 |api| api.ic0_debug_print(0, 0, &[42; 128]),
@@ -1032,9 +1071,10 @@ fn test_canister_cycle_balance() {
     // Check ic0_canister_cycle_balance.
     assert_eq!(
         api.ic0_canister_cycle_balance(),
-        Err(HypervisorError::Trapped(
-            TrapCode::CyclesAmountTooBigFor64Bit
-        ))
+        Err(HypervisorError::Trapped {
+            trap_code: TrapCode::CyclesAmountTooBigFor64Bit,
+            backtrace: None
+        })
     );
 
     let mut heap = vec![0; 16];
@@ -1042,6 +1082,24 @@ fn test_canister_cycle_balance() {
     assert_eq!(heap, cycles_amount.get().to_le_bytes());
 }
 
+#[test]
+fn test_msg_tip_returns_tip_of_inspected_message() {
+    let system_state = get_system_state();
+    let api = get_system_api(
+        ApiType::inspect_message(
+            user_test_id(1).get(),
+            "method".to_string(),
+            vec![],
+            Cycles::new(1_000),
+            UNIX_EPOCH,
+        ),
+        &system_state,
+        CyclesAccountManagerBuilder::new().build(),
+    );
+
+    assert_eq!(api.ic0_msg_tip(), Ok(1_000));
+}
+
 #[test]
 fn test_msg_cycles_available_traps() {
     let cycles_amount = Cycles::from(123456789012345678901234567890u128);
@@ -1066,9 +1124,10 @@ fn test_msg_cycles_available_traps() {
 
     assert_eq!(
         api.ic0_msg_cycles_available(),
-        Err(HypervisorError::Trapped(
-            TrapCode::CyclesAmountTooBigFor64Bit
-        ))
+        Err(HypervisorError::Trapped {
+            trap_code: TrapCode::CyclesAmountTooBigFor64Bit,
+            backtrace: None
+        })
     );
 
     let mut heap = vec![0; 16];
@@ -1090,9 +1149,10 @@ fn test_msg_cycles_refunded_traps() {
 
     assert_eq!(
         api.ic0_msg_cycles_refunded(),
-        Err(HypervisorError::Trapped(
-            TrapCode::CyclesAmountTooBigFor64Bit
-        ))
+        Err(HypervisorError::Trapped {
+            trap_code: TrapCode::CyclesAmountTooBigFor64Bit,
+            backtrace: None
+        })
     );
 
     let mut heap = vec![0; 16];
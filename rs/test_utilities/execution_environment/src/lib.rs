@@ -1850,6 +1850,21 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn with_query_time_slicing(mut self) -> Self {
+        self.execution_config.query_time_slicing = FlagStatus::Enabled;
+        self
+    }
+
+    pub fn with_max_instructions_per_query_slice(mut self, max_instructions: u64) -> Self {
+        self.execution_config.max_instructions_per_query_slice = max_instructions.into();
+        self
+    }
+
+    pub fn with_max_query_call_walltime(mut self, max_query_call_walltime: Duration) -> Self {
+        self.execution_config.max_query_call_walltime = max_query_call_walltime;
+        self
+    }
+
     pub fn with_query_cache_capacity(mut self, capacity_bytes: u64) -> Self {
         self.execution_config.query_cache_capacity = capacity_bytes.into();
         self
@@ -1946,6 +1961,14 @@ impl ExecutionTestBuilder {
         self
     }
 
+    pub fn without_wasm_tail_call(mut self) -> Self {
+        self.execution_config
+            .embedders_config
+            .feature_flags
+            .wasm_tail_call = FlagStatus::Disabled;
+        self
+    }
+
     pub fn with_metering_type(mut self, metering_type: MeteringType) -> Self {
         self.execution_config.embedders_config.metering_type = metering_type;
         self
@@ -217,6 +217,7 @@ pub fn test_subnet_record() -> SubnetRecord {
         ssh_backup_access: vec![],
         ecdsa_config: None,
         chain_key_config: None,
+        http_outcalls_policy: None,
     }
 }
 
@@ -835,7 +835,8 @@ prop_compose! {
     pub fn arb_stream_with_config(min_size: usize, max_size: usize, min_signal_count: usize, max_signal_count: usize)(
         msg_start in 0..10000u64,
         msgs in prop::collection::vec(
-            arbitrary::request_or_response_with_config(true, true),
+            // Trace IDs are only encoded from certification version 19 on.
+            arbitrary::request_or_response_with_config(true, false, true),
             min_size..=max_size
         ),
         (signals_end, reject_signals) in arb_reject_signals(min_signal_count, max_signal_count),
@@ -29,6 +29,7 @@ impl Default for IngressBuilder {
                 method_payload: Vec::new(),
                 message_id: MessageId::from([0; 32]),
                 expiry_time: expiry_time_from_now(),
+                traced: false,
             },
         }
     }
@@ -82,6 +83,12 @@ impl IngressBuilder {
         self
     }
 
+    /// Sets the `traced` field.
+    pub fn traced(mut self, traced: bool) -> Self {
+        self.ingress.traced = traced;
+        self
+    }
+
     /// Returns the built `Ingress`.
     pub fn build(&self) -> Ingress {
         self.ingress.clone()
@@ -103,6 +110,8 @@ impl Default for SignedIngressBuilder {
             sender: Blob(PrincipalId::new_anonymous().into()),
             ingress_expiry: expiry_time_from_now().as_nanos_since_unix_epoch(),
             nonce: None,
+            tip: None,
+            trace: None,
         };
         Self {
             update,
@@ -147,6 +156,18 @@ impl SignedIngressBuilder {
         self
     }
 
+    /// Sets the `tip` field.
+    pub fn tip(mut self, tip: u64) -> Self {
+        self.update.tip = Some(tip);
+        self
+    }
+
+    /// Sets the `trace` field.
+    pub fn trace(mut self, trace: bool) -> Self {
+        self.update.trace = Some(trace);
+        self
+    }
+
     /// Sets the `ingress_expiry` field.
     pub fn expiry_time(mut self, expiry_time: Time) -> Self {
         self.update.ingress_expiry = expiry_time.as_nanos_since_unix_epoch();
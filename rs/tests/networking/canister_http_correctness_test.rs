@@ -85,6 +85,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -112,6 +114,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -142,6 +146,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 0,
                 },
@@ -164,6 +170,8 @@ pub fn test(env: TestEnv) {
                 context: vec![0, 1, 2],
             }),
             max_response_bytes: None,
+            replication: None,
+            response_offset: None,
         };
         test_results.push(
             test_canister_http_property(
@@ -214,6 +222,8 @@ pub fn test(env: TestEnv) {
                 context: vec![0, 1, 2],
             }),
             max_response_bytes: Some(16384),
+            replication: None,
+            response_offset: None,
         };
         test_results.push(
             test_canister_http_property(
@@ -269,6 +279,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: Some(4 * 1024 * 1024),
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 0,
                 },
@@ -296,6 +308,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -323,6 +337,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -353,6 +369,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -380,6 +398,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: Some(8 * 1024),
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -408,6 +428,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -435,6 +457,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
@@ -462,6 +486,8 @@ pub fn test(env: TestEnv) {
                             context: vec![0, 1, 2],
                         }),
                         max_response_bytes: None,
+                        replication: None,
+                        response_offset: None,
                     },
                     cycles: 500_000_000_000,
                 },
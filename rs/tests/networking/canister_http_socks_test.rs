@@ -170,6 +170,8 @@ pub fn test(env: TestEnv) {
                             }),
                             method: HttpMethod::GET,
                             max_response_bytes: None,
+                            replication: None,
+                            response_offset: None,
                         },
                         cycles: 500_000_000_000,
                     },
@@ -223,6 +225,8 @@ pub fn test(env: TestEnv) {
                             }),
                             method: HttpMethod::GET,
                             max_response_bytes: None,
+                            replication: None,
+                            response_offset: None,
                         },
                         cycles: 500_000_000_000,
                     },
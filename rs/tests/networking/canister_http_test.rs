@@ -95,6 +95,8 @@ async fn test_proxy_canister(proxy_canister: &Canister<'_>, url: String, logger:
                             }),
                             method: HttpMethod::GET,
                             max_response_bytes: None,
+                            replication: None,
+                            response_offset: None,
                         },
                         cycles: 500_000_000_000,
                     },
@@ -66,6 +66,8 @@ pub fn test(env: TestEnv) {
                     context: vec![0, 1, 2],
                 }),
                 max_response_bytes: None,
+                replication: None,
+                response_offset: None,
             },
             cycles: 500_000_000_000,
         };
@@ -244,6 +244,8 @@ async fn test_request_with_no_signature_and_no_pubkey<T: Identity + 'static>(
             sender: Blob(identity.sender().unwrap().as_slice().to_vec()),
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            tip: None,
+            trace: None,
         },
     };
 
@@ -289,6 +291,8 @@ async fn test_request_with_correct_signature_and_incorrect_pubkey<T: Identity +
             sender: Blob(identity.sender().unwrap().as_slice().to_vec()),
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            tip: None,
+            trace: None,
         },
     };
 
@@ -336,6 +340,8 @@ async fn test_request_with_incorrect_signature_and_correct_pubkey<T: Identity +
             sender: Blob(identity.sender().unwrap().as_slice().to_vec()),
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            tip: None,
+            trace: None,
         },
     };
 
@@ -383,6 +389,8 @@ async fn test_request_with_incorrect_sender<T: Identity + 'static>(
             sender: Blob(wrong_identity.sender().unwrap().as_slice().to_vec()), // wrong sender
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            tip: None,
+            trace: None,
         },
     };
 
@@ -427,6 +435,8 @@ async fn test_request_with_expired_ingress<T: Identity + 'static>(
             sender: Blob(identity.sender().unwrap().as_slice().to_vec()),
             ingress_expiry: 0,
             nonce: None,
+            tip: None,
+            trace: None,
         },
     };
 
@@ -475,6 +485,8 @@ async fn test_request_with_delegation<T: Identity + 'static>(
             sender: Blob(identity.sender().unwrap().as_slice().to_vec()),
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            tip: None,
+            trace: None,
         },
     };
 
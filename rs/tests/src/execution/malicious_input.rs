@@ -152,6 +152,8 @@ fn test_valid_update_followed_by_garbage(env: TestEnv) {
                 sender: Blob(vec![4]), // the anonymous user.
                 ingress_expiry: expiry_time().as_nanos() as u64,
                 nonce: None,
+                tip: None,
+                trace: None,
             },
         },
         sender_delegation: None,
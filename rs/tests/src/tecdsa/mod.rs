@@ -115,6 +115,7 @@ pub(crate) fn empty_subnet_update() -> UpdateSubnetPayload {
         max_number_of_canisters: None,
         ssh_readonly_access: None,
         ssh_backup_access: None,
+        http_outcalls_policy: None,
         // Deprecated/unused values follow
         max_artifact_streams_per_peer: None,
         max_chunk_wait_ms: None,
@@ -641,6 +642,7 @@ pub(crate) async fn create_new_subnet_with_keys(
         max_number_of_canisters: 4,
         ssh_readonly_access: vec![],
         ssh_backup_access: vec![],
+        http_outcalls_policy: None,
         chain_key_config: Some(chain_key_config),
         // Unused section follows
         ecdsa_config: None,
@@ -164,6 +164,8 @@ impl<'a> AgentWithDelegation<'a> {
             sender: self.sender(),
             ingress_expiry: expiry_time().as_nanos() as u64,
             nonce: None,
+            tip: None,
+            trace: None,
         };
         let request_id = update.id();
         let content = HttpCallContent::Call { update };
@@ -64,12 +64,14 @@ pub type BoundedHttpHeaders = BoundedVec<
 //     url : text;
 //     max_response_bytes: opt nat64;
 //     headers : vec http_header;
-//     method : variant { get; head; post };
+//     method : variant { get; head; post; put; patch; delete; options };
 //     body : opt blob;
 //     transform : opt record {
 //       function : func (record {response : http_response; context : blob}) -> (http_response) query;
 //       context : blob;
 //     };
+//     replication : opt variant { replicated; non_replicated : opt vec principal };
+//     response_offset : opt nat64;
 //   })`
 #[derive(CandidType, Deserialize, Debug, Clone, PartialEq)]
 pub struct CanisterHttpRequestArgs {
@@ -79,6 +81,15 @@ pub struct CanisterHttpRequestArgs {
     pub body: Option<Vec<u8>>,
     pub method: HttpMethod,
     pub transform: Option<TransformContext>,
+    /// Defaults to `Replicated` if not specified.
+    pub replication: Option<HttpOutcallReplication>,
+    /// If set, the response body may be larger than `max_response_bytes`. Only the part of
+    /// the body starting at this offset is returned (and transformed), together with a
+    /// `content-range` header holding the total size of the body, so that large bodies can
+    /// be fetched page by page. The body is requested with a `Range` header, but since
+    /// servers may ignore it, the request is charged for downloading the body up to the
+    /// end of the page.
+    pub response_offset: Option<u64>,
 }
 
 impl Payload<'_> for CanisterHttpRequestArgs {}
@@ -113,6 +124,8 @@ fn test_http_headers_max_number() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            replication: None,
+            response_offset: None,
         };
 
         // Act.
@@ -166,6 +179,8 @@ fn test_http_headers_max_total_size() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            replication: None,
+            response_offset: None,
         };
 
         // Act.
@@ -213,6 +228,8 @@ fn test_http_headers_max_element_size() {
             body: None,
             method: HttpMethod::GET,
             transform: None,
+            replication: None,
+            response_offset: None,
         };
 
         // Act.
@@ -281,6 +298,27 @@ fn test_http_header_data_size() {
     }
 }
 
+/// Enum used for encoding/decoding:
+/// `variant { replicated; non_replicated : opt vec principal }`
+#[derive(Clone, Debug, PartialEq, CandidType, Eq, Hash, Serialize, Deserialize)]
+pub enum HttpOutcallReplication {
+    /// Every node of the subnet makes the request and the nodes agree on the response.
+    #[serde(rename = "replicated")]
+    Replicated,
+    /// A single node makes the request and signs the response on its own. The node is chosen
+    /// pseudo-randomly among the given nodes, or among all nodes of the subnet if none are given.
+    #[serde(rename = "non_replicated")]
+    NonReplicated(Option<Vec<PrincipalId>>),
+}
+
+/// The HTTP method of a canister http request.
+///
+/// Unless the request is non-replicated, every node of the subnet makes the
+/// request, so the server receives it multiple times. Requests with
+/// non-idempotent methods (`POST`, `PATCH`) should therefore carry an
+/// idempotency key or be otherwise safe to repeat. Note that a request that is
+/// rejected because it timed out or the responses diverged may still have been
+/// executed by the server.
 #[derive(Clone, Debug, PartialEq, CandidType, Eq, Hash, Serialize, Deserialize)]
 pub enum HttpMethod {
     #[serde(rename = "get")]
@@ -289,6 +327,14 @@ pub enum HttpMethod {
     POST,
     #[serde(rename = "head")]
     HEAD,
+    #[serde(rename = "put")]
+    PUT,
+    #[serde(rename = "patch")]
+    PATCH,
+    #[serde(rename = "delete")]
+    DELETE,
+    #[serde(rename = "options")]
+    OPTIONS,
 }
 
 /// Represents the response for a canister http request.
@@ -11,7 +11,7 @@ use candid::{CandidType, Decode, DecoderConfig, Deserialize, Encode};
 pub use data_size::*;
 pub use http::{
     BoundedHttpHeaders, CanisterHttpRequestArgs, CanisterHttpResponsePayload, HttpHeader,
-    HttpMethod, TransformArgs, TransformContext, TransformFunc,
+    HttpMethod, HttpOutcallReplication, TransformArgs, TransformContext, TransformFunc,
 };
 use ic_base_types::{
     CanisterId, NodeId, NumBytes, PrincipalId, RegistryVersion, SnapshotId, SubnetId,
@@ -810,6 +810,49 @@ impl From<pb_canister_state_bits::LogVisibility> for LogVisibility {
     }
 }
 
+/// Backtrace visibility for a canister, i.e. who receives the backtrace of
+/// the canister's Wasm stack when one of its messages traps.
+/// ```text
+/// variant {
+///    disabled;
+///    controllers;
+///    public;
+/// }
+/// ```
+#[derive(Default, Clone, Copy, CandidType, Deserialize, Debug, PartialEq, Eq, EnumIter)]
+pub enum BacktraceVisibility {
+    #[default]
+    #[serde(rename = "disabled")]
+    Disabled = 1,
+    #[serde(rename = "controllers")]
+    Controllers = 2,
+    #[serde(rename = "public")]
+    Public = 3,
+}
+
+impl From<&BacktraceVisibility> for pb_canister_state_bits::BacktraceVisibility {
+    fn from(item: &BacktraceVisibility) -> Self {
+        match item {
+            BacktraceVisibility::Disabled => pb_canister_state_bits::BacktraceVisibility::Disabled,
+            BacktraceVisibility::Controllers => {
+                pb_canister_state_bits::BacktraceVisibility::Controllers
+            }
+            BacktraceVisibility::Public => pb_canister_state_bits::BacktraceVisibility::Public,
+        }
+    }
+}
+
+impl From<pb_canister_state_bits::BacktraceVisibility> for BacktraceVisibility {
+    fn from(item: pb_canister_state_bits::BacktraceVisibility) -> Self {
+        match item {
+            pb_canister_state_bits::BacktraceVisibility::Unspecified => Self::default(),
+            pb_canister_state_bits::BacktraceVisibility::Disabled => Self::Disabled,
+            pb_canister_state_bits::BacktraceVisibility::Controllers => Self::Controllers,
+            pb_canister_state_bits::BacktraceVisibility::Public => Self::Public,
+        }
+    }
+}
+
 /// Struct used for encoding/decoding
 /// `(record {
 ///     controller : principal;
@@ -818,6 +861,7 @@ impl From<pb_canister_state_bits::LogVisibility> for LogVisibility {
 ///     freezing_threshold: nat;
 ///     reserved_cycles_limit: nat;
 ///     log_visibility: log_visibility;
+///     backtrace_visibility: backtrace_visibility;
 ///     wasm_memory_limit: nat;
 /// })`
 #[derive(CandidType, Clone, Deserialize, Debug, Eq, PartialEq)]
@@ -829,6 +873,7 @@ pub struct DefiniteCanisterSettingsArgs {
     freezing_threshold: candid::Nat,
     reserved_cycles_limit: candid::Nat,
     log_visibility: LogVisibility,
+    backtrace_visibility: BacktraceVisibility,
     wasm_memory_limit: candid::Nat,
 }
 
@@ -841,6 +886,7 @@ impl DefiniteCanisterSettingsArgs {
         freezing_threshold: u64,
         reserved_cycles_limit: Option<u128>,
         log_visibility: LogVisibility,
+        backtrace_visibility: BacktraceVisibility,
         wasm_memory_limit: Option<u64>,
     ) -> Self {
         let memory_allocation = candid::Nat::from(memory_allocation.unwrap_or(0));
@@ -854,6 +900,7 @@ impl DefiniteCanisterSettingsArgs {
             freezing_threshold: candid::Nat::from(freezing_threshold),
             reserved_cycles_limit,
             log_visibility,
+            backtrace_visibility,
             wasm_memory_limit,
         }
     }
@@ -869,6 +916,10 @@ impl DefiniteCanisterSettingsArgs {
     pub fn log_visibility(&self) -> LogVisibility {
         self.log_visibility
     }
+
+    pub fn backtrace_visibility(&self) -> BacktraceVisibility {
+        self.backtrace_visibility
+    }
 }
 
 impl Payload<'_> for DefiniteCanisterSettingsArgs {}
@@ -985,6 +1036,7 @@ impl CanisterStatusResultV2 {
         freezing_threshold: u64,
         reserved_cycles_limit: Option<u128>,
         log_visibility: LogVisibility,
+        backtrace_visibility: BacktraceVisibility,
         idle_cycles_burned_per_day: u128,
         reserved_cycles: u128,
         query_num_calls: u128,
@@ -1010,6 +1062,7 @@ impl CanisterStatusResultV2 {
                 freezing_threshold,
                 reserved_cycles_limit,
                 log_visibility,
+                backtrace_visibility,
                 wasm_memory_limit,
             ),
             freezing_threshold: candid::Nat::from(freezing_threshold),
@@ -1660,6 +1713,7 @@ impl DataSize for PrincipalId {
 ///     reserved_cycles_limit: opt nat;
 ///     log_visibility : opt log_visibility;
 ///     wasm_memory_limit: opt nat;
+///     backtrace_visibility: opt backtrace_visibility;
 /// })`
 #[derive(Default, Clone, CandidType, Deserialize, Debug, PartialEq, Eq)]
 pub struct CanisterSettingsArgs {
@@ -1672,6 +1726,7 @@ pub struct CanisterSettingsArgs {
     pub reserved_cycles_limit: Option<candid::Nat>,
     pub log_visibility: Option<LogVisibility>,
     pub wasm_memory_limit: Option<candid::Nat>,
+    pub backtrace_visibility: Option<BacktraceVisibility>,
 }
 
 impl Payload<'_> for CanisterSettingsArgs {}
@@ -1689,6 +1744,7 @@ impl CanisterSettingsArgs {
             reserved_cycles_limit: None,
             log_visibility: None,
             wasm_memory_limit: None,
+            backtrace_visibility: None,
         }
     }
 
@@ -1707,6 +1763,7 @@ pub struct CanisterSettingsArgsBuilder {
     reserved_cycles_limit: Option<candid::Nat>,
     log_visibility: Option<LogVisibility>,
     wasm_memory_limit: Option<candid::Nat>,
+    backtrace_visibility: Option<BacktraceVisibility>,
 }
 
 #[allow(dead_code)]
@@ -1725,6 +1782,7 @@ impl CanisterSettingsArgsBuilder {
             reserved_cycles_limit: self.reserved_cycles_limit,
             log_visibility: self.log_visibility,
             wasm_memory_limit: self.wasm_memory_limit,
+            backtrace_visibility: self.backtrace_visibility,
         }
     }
 
@@ -1808,6 +1866,14 @@ impl CanisterSettingsArgsBuilder {
             ..self
         }
     }
+
+    /// Sets the backtrace visibility.
+    pub fn with_backtrace_visibility(self, backtrace_visibility: BacktraceVisibility) -> Self {
+        Self {
+            backtrace_visibility: Some(backtrace_visibility),
+            ..self
+        }
+    }
 }
 
 /// Struct used for encoding/decoding
@@ -198,6 +198,8 @@ mod tests {
                 sender: Blob(vec![0x05]),
                 nonce: Some(Blob(vec![1, 2, 3, 4])),
                 ingress_expiry: ingress_expiry.as_nanos_since_unix_epoch(),
+                tip: None,
+                trace: None,
             },
         };
         let update_messages = vec![
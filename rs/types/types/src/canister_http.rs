@@ -44,23 +44,26 @@
 use crate::{
     crypto::{CryptoHashOf, Signed},
     messages::{CallbackId, RejectContext, Request},
+    node_id_into_protobuf, node_id_try_from_option,
     signature::*,
-    CanisterId, CountBytes, RegistryVersion, Time,
+    CanisterId, CountBytes, NodeId, RegistryVersion, Time,
 };
 use ic_base_types::{NumBytes, PrincipalId};
 use ic_error_types::{ErrorCode, RejectCode, UserError};
 #[cfg(test)]
 use ic_exhaustive_derive::ExhaustiveSet;
 use ic_management_canister_types::{
-    CanisterHttpRequestArgs, HttpHeader, HttpMethod, TransformContext,
+    CanisterHttpRequestArgs, HttpHeader, HttpMethod, HttpOutcallReplication, TransformContext,
 };
 use ic_protobuf::{
     proxy::{try_from_option_field, ProxyDecodeError},
+    registry::subnet::v1 as pb_subnet,
     state::system_metadata::v1 as pb_metadata,
 };
 use serde::{Deserialize, Serialize};
 use std::time::Duration;
 use std::{
+    collections::BTreeSet,
     convert::{TryFrom, TryInto},
     mem::size_of,
 };
@@ -113,6 +116,56 @@ impl From<TransformContext> for Transform {
     }
 }
 
+/// The nodes that make a canister http request.
+#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Replication {
+    /// All nodes of the subnet make the request and agree on the response.
+    FullyReplicated,
+    /// Only the given node makes the request. Its response is signed by that node
+    /// alone and is not agreed on by the other nodes.
+    NonReplicated(NodeId),
+}
+
+impl Replication {
+    /// Chooses the nodes that make the request, as requested by the canister.
+    ///
+    /// For non-replicated requests a single node is chosen from the candidates
+    /// specified by the canister, or from all `subnet_nodes` if none are
+    /// specified. The choice is based on the value returned by `seed`, which must
+    /// be derived from the round's randomness so that all nodes agree on it. It is
+    /// only called for non-replicated requests, so that replicated requests do not
+    /// consume any randomness.
+    pub fn choose(
+        requested: Option<&HttpOutcallReplication>,
+        subnet_nodes: &BTreeSet<NodeId>,
+        seed: impl FnOnce() -> u64,
+    ) -> Result<Self, CanisterHttpRequestContextError> {
+        let candidates: Vec<NodeId> = match requested {
+            None | Some(HttpOutcallReplication::Replicated) => {
+                return Ok(Replication::FullyReplicated)
+            }
+            Some(HttpOutcallReplication::NonReplicated(None)) => {
+                subnet_nodes.iter().copied().collect()
+            }
+            Some(HttpOutcallReplication::NonReplicated(Some(nodes))) => {
+                let candidates: BTreeSet<NodeId> =
+                    nodes.iter().map(|id| NodeId::from(*id)).collect();
+                if let Some(node_id) = candidates.difference(subnet_nodes).next() {
+                    return Err(CanisterHttpRequestContextError::UnknownReplicationNode(
+                        node_id.get(),
+                    ));
+                }
+                candidates.into_iter().collect()
+            }
+        };
+        if candidates.is_empty() {
+            return Err(CanisterHttpRequestContextError::NoReplicationNodes);
+        }
+        let index = (seed() % candidates.len() as u64) as usize;
+        Ok(Replication::NonReplicated(candidates[index]))
+    }
+}
+
 #[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct CanisterHttpRequestContext {
     pub request: Request,
@@ -124,6 +177,10 @@ pub struct CanisterHttpRequestContext {
     pub http_method: CanisterHttpMethod,
     pub transform: Option<Transform>,
     pub time: Time,
+    pub replication: Replication,
+    /// Offset of the page of the response body to return, see
+    /// `CanisterHttpRequestArgs::response_offset`.
+    pub response_offset: Option<u64>,
 }
 
 impl From<&CanisterHttpRequestContext> for pb_metadata::CanisterHttpRequestContext {
@@ -154,6 +211,11 @@ impl From<&CanisterHttpRequestContext> for pb_metadata::CanisterHttpRequestConte
                 .map(|transform| transform.context.clone()),
             http_method: pb_metadata::HttpMethod::from(&context.http_method).into(),
             time: context.time.as_nanos_since_unix_epoch(),
+            non_replicated_node: match context.replication {
+                Replication::FullyReplicated => None,
+                Replication::NonReplicated(node_id) => Some(node_id_into_protobuf(node_id)),
+            },
+            response_offset: context.response_offset,
         }
     }
 }
@@ -212,6 +274,13 @@ impl TryFrom<pb_metadata::CanisterHttpRequestContext> for CanisterHttpRequestCon
                 .try_into()?,
             transform,
             time: Time::from_nanos_since_unix_epoch(context.time),
+            replication: match context.non_replicated_node {
+                None => Replication::FullyReplicated,
+                Some(node_id) => {
+                    Replication::NonReplicated(node_id_try_from_option(Some(node_id))?)
+                }
+            },
+            response_offset: context.response_offset,
         })
     }
 }
@@ -331,9 +400,16 @@ impl TryFrom<(Time, &Request, CanisterHttpRequestArgs)> for CanisterHttpRequestC
                 HttpMethod::GET => CanisterHttpMethod::GET,
                 HttpMethod::POST => CanisterHttpMethod::POST,
                 HttpMethod::HEAD => CanisterHttpMethod::HEAD,
+                HttpMethod::PUT => CanisterHttpMethod::PUT,
+                HttpMethod::PATCH => CanisterHttpMethod::PATCH,
+                HttpMethod::DELETE => CanisterHttpMethod::DELETE,
+                HttpMethod::OPTIONS => CanisterHttpMethod::OPTIONS,
             },
             transform: args.transform.map(From::from),
             time,
+            // The nodes are chosen by execution, see `Replication::choose`.
+            replication: Replication::FullyReplicated,
+            response_offset: args.response_offset,
         })
     }
 }
@@ -353,6 +429,22 @@ impl CanisterHttpRequestContext {
             });
         NumBytes::from(request_size as u64)
     }
+
+    /// Upper bound on the number of response bytes that are downloaded for this
+    /// request, which is what the request is charged for. For a paged request to a
+    /// server without support for range requests, the body is downloaded from the
+    /// start up to the end of the requested page.
+    pub fn downloaded_bytes_limit(&self) -> Option<NumBytes> {
+        match self.response_offset {
+            None => self.max_response_bytes,
+            Some(offset) => {
+                let page_size = self
+                    .max_response_bytes
+                    .map_or(MAX_CANISTER_HTTP_RESPONSE_BYTES, |bytes| bytes.get());
+                Some(NumBytes::from(offset.saturating_add(page_size)))
+            }
+        }
+    }
 }
 
 /// The error that occurs when an end-user specifies an invalid
@@ -384,6 +476,8 @@ pub enum CanisterHttpRequestContextError {
     TooLongHeaderValue(usize),
     TooLargeHeaders(usize),
     TooLargeRequest(usize),
+    UnknownReplicationNode(PrincipalId),
+    NoReplicationNodes,
 }
 
 impl From<CanisterHttpRequestContextError> for UserError {
@@ -442,6 +536,17 @@ impl From<CanisterHttpRequestContextError> for UserError {
                     total_request_size, MAX_CANISTER_HTTP_REQUEST_BYTES
                 ),
             ),
+            CanisterHttpRequestContextError::UnknownReplicationNode(node_id) => UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                format!(
+                    "node {} requested for a non-replicated http request is not a member of the subnet",
+                    node_id
+                ),
+            ),
+            CanisterHttpRequestContextError::NoReplicationNodes => UserError::new(
+                ErrorCode::CanisterRejectedMessage,
+                "no nodes given for a non-replicated http request".to_string(),
+            ),
         }
     }
 }
@@ -456,6 +561,41 @@ pub struct CanisterHttpRequest {
     pub id: CanisterHttpRequestId,
     /// The context of the request which captures all the metadata about this request
     pub context: CanisterHttpRequestContext,
+    /// The destination policy of the subnet that the adapter has to enforce
+    pub policy: CanisterHttpPolicy,
+}
+
+/// The destination policy for canister http requests, as configured in the
+/// `SubnetRecord` of the subnet making the request.
+///
+/// The default policy allows all destinations and does not follow redirects.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct CanisterHttpPolicy {
+    /// If non-empty, only these domains (and their subdomains) may be contacted.
+    pub allowed_domains: Vec<String>,
+    /// Domains (and their subdomains) that must never be contacted.
+    pub denied_domains: Vec<String>,
+    /// If non-empty, destinations must resolve to addresses within these CIDR ranges.
+    pub allowed_ip_ranges: Vec<String>,
+    /// CIDR ranges that must never be contacted.
+    pub denied_ip_ranges: Vec<String>,
+    /// Whether private and other non-globally-routable addresses are rejected.
+    pub block_private_addresses: bool,
+    /// Maximum number of redirects that are followed.
+    pub max_redirects: u32,
+}
+
+impl From<pb_subnet::HttpOutcallsPolicy> for CanisterHttpPolicy {
+    fn from(policy: pb_subnet::HttpOutcallsPolicy) -> Self {
+        Self {
+            allowed_domains: policy.allowed_domains,
+            denied_domains: policy.denied_domains,
+            allowed_ip_ranges: policy.allowed_ip_ranges,
+            denied_ip_ranges: policy.denied_ip_ranges,
+            block_private_addresses: policy.block_private_addresses,
+            max_redirects: policy.max_redirects,
+        }
+    }
 }
 
 /// The content of a response after the transformation
@@ -530,6 +670,10 @@ pub enum CanisterHttpMethod {
     GET = 1,
     POST = 2,
     HEAD = 3,
+    PUT = 4,
+    PATCH = 5,
+    DELETE = 6,
+    OPTIONS = 7,
 }
 
 impl From<&CanisterHttpMethod> for pb_metadata::HttpMethod {
@@ -538,6 +682,10 @@ impl From<&CanisterHttpMethod> for pb_metadata::HttpMethod {
             CanisterHttpMethod::GET => pb_metadata::HttpMethod::Get,
             CanisterHttpMethod::POST => pb_metadata::HttpMethod::Post,
             CanisterHttpMethod::HEAD => pb_metadata::HttpMethod::Head,
+            CanisterHttpMethod::PUT => pb_metadata::HttpMethod::Put,
+            CanisterHttpMethod::PATCH => pb_metadata::HttpMethod::Patch,
+            CanisterHttpMethod::DELETE => pb_metadata::HttpMethod::Delete,
+            CanisterHttpMethod::OPTIONS => pb_metadata::HttpMethod::Options,
         }
     }
 }
@@ -550,6 +698,10 @@ impl TryFrom<pb_metadata::HttpMethod> for CanisterHttpMethod {
             pb_metadata::HttpMethod::Get => Ok(CanisterHttpMethod::GET),
             pb_metadata::HttpMethod::Post => Ok(CanisterHttpMethod::POST),
             pb_metadata::HttpMethod::Head => Ok(CanisterHttpMethod::HEAD),
+            pb_metadata::HttpMethod::Put => Ok(CanisterHttpMethod::PUT),
+            pb_metadata::HttpMethod::Patch => Ok(CanisterHttpMethod::PATCH),
+            pb_metadata::HttpMethod::Delete => Ok(CanisterHttpMethod::DELETE),
+            pb_metadata::HttpMethod::Options => Ok(CanisterHttpMethod::OPTIONS),
             pb_metadata::HttpMethod::Unspecified => Err(ProxyDecodeError::ValueOutOfRange {
                 typ: "ic_protobuf::state::system_metadata::v1::HttpMethod",
                 err: "Unspecified HttpMethod".to_string(),
@@ -632,6 +784,7 @@ mod tests {
 
     use super::*;
 
+    use std::collections::HashSet;
     use strum::IntoEnumIterator;
 
     #[test]
@@ -660,6 +813,8 @@ mod tests {
                 deadline: NO_DEADLINE,
             },
             time: UNIX_EPOCH,
+            replication: Replication::FullyReplicated,
+            response_offset: None,
         };
 
         let expected_size = context.url.len()
@@ -702,6 +857,8 @@ mod tests {
                 deadline: NO_DEADLINE,
             },
             time: UNIX_EPOCH,
+            replication: Replication::FullyReplicated,
+            response_offset: None,
         };
 
         let expected_size = context.url.len()
@@ -732,7 +889,54 @@ mod tests {
             CanisterHttpMethod::iter()
                 .map(|x| x as i32)
                 .collect::<Vec<i32>>(),
-            [1, 2, 3]
+            [1, 2, 3, 4, 5, 6, 7]
         );
     }
+
+    #[test]
+    fn choose_replication() {
+        let node = |id| NodeId::from(PrincipalId::new_node_test_id(id));
+        let subnet_nodes: BTreeSet<_> = (0..4).map(node).collect();
+
+        assert_eq!(
+            Replication::choose(None, &subnet_nodes, || panic!("No randomness needed")).unwrap(),
+            Replication::FullyReplicated
+        );
+
+        // Every node of the subnet is chosen for some seed.
+        let requested = HttpOutcallReplication::NonReplicated(None);
+        let chosen: HashSet<_> = (0..4)
+            .map(|seed| Replication::choose(Some(&requested), &subnet_nodes, || seed).unwrap())
+            .collect();
+        assert_eq!(
+            chosen,
+            subnet_nodes
+                .iter()
+                .map(|node_id| Replication::NonReplicated(*node_id))
+                .collect()
+        );
+
+        // Only the requested nodes are chosen.
+        let requested =
+            HttpOutcallReplication::NonReplicated(Some(vec![node(1).get(), node(2).get()]));
+        for seed in 0..4 {
+            let replication =
+                Replication::choose(Some(&requested), &subnet_nodes, || seed).unwrap();
+            assert!(
+                replication == Replication::NonReplicated(node(1))
+                    || replication == Replication::NonReplicated(node(2))
+            );
+        }
+
+        let requested = HttpOutcallReplication::NonReplicated(Some(vec![node(5).get()]));
+        assert!(matches!(
+            Replication::choose(Some(&requested), &subnet_nodes, || 7),
+            Err(CanisterHttpRequestContextError::UnknownReplicationNode(node_id)) if node_id == node(5).get()
+        ));
+        let requested = HttpOutcallReplication::NonReplicated(Some(vec![]));
+        assert!(matches!(
+            Replication::choose(Some(&requested), &subnet_nodes, || 7),
+            Err(CanisterHttpRequestContextError::NoReplicationNodes)
+        ));
+    }
 }
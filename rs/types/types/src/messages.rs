@@ -33,7 +33,7 @@ pub use ingress_messages::{
 };
 pub use inter_canister::{
     CallContextId, CallbackId, Payload, RejectContext, Request, RequestMetadata, RequestOrResponse,
-    Response, MAX_REJECT_MESSAGE_LEN_BYTES, NO_DEADLINE,
+    Response, TraceId, MAX_REJECT_MESSAGE_LEN_BYTES, NO_DEADLINE, TRACE_ID_LENGTH,
 };
 pub use message_id::{MessageId, MessageIdError, EXPECTED_MESSAGE_ID_LENGTH};
 use phantom_newtype::Id;
@@ -642,6 +642,8 @@ mod tests {
                         sender: Blob(vec![0x04]),
                         nonce: None,
                         ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                        tip: None,
+                        trace: None,
                     },
                 },
                 sender_pubkey: Some(Blob(vec![])),
@@ -676,6 +678,8 @@ mod tests {
                         sender: Blob(vec![0x04]),
                         nonce: None,
                         ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                        tip: None,
+                        trace: None,
                     },
                 },
                 sender_pubkey: Some(Blob(vec![])),
@@ -710,6 +714,8 @@ mod tests {
                         sender: Blob(vec![0x04]),
                         nonce: Some(Blob(vec![1, 2, 3, 4, 5])),
                         ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                        tip: None,
+                        trace: None,
                     },
                 },
                 sender_pubkey: Some(Blob(vec![])),
@@ -744,6 +750,8 @@ mod tests {
                     sender: Blob(vec![0x04]),
                     nonce: None,
                     ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                    tip: None,
+                    trace: None,
                 },
             },
             sender_pubkey: Some(Blob(vec![2; 32])),
@@ -812,6 +820,8 @@ mod tests {
                     sender: Blob(vec![0x04]),
                     nonce: None,
                     ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
+                    tip: None,
+                    trace: None,
                 },
             },
             sender_pubkey: None,
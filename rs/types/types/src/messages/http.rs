@@ -41,6 +41,8 @@ pub(crate) fn representation_independent_hash_call_or_query(
     ingress_expiry: u64,
     sender: Vec<u8>,
     nonce: Option<&[u8]>,
+    tip: Option<u64>,
+    trace: Option<bool>,
 ) -> [u8; 32] {
     use RawHttpRequestVal::*;
     let mut map = btreemap! {
@@ -57,6 +59,12 @@ pub(crate) fn representation_independent_hash_call_or_query(
     if let Some(some_nonce) = nonce {
         map.insert("nonce".to_string(), Bytes(some_nonce.to_vec()));
     }
+    if let Some(some_tip) = tip {
+        map.insert("tip".to_string(), U64(some_tip));
+    }
+    if let Some(some_trace) = trace {
+        map.insert("trace".to_string(), U64(some_trace as u64));
+    }
     hash_of_map(&map)
 }
 
@@ -103,6 +111,14 @@ pub struct HttpCanisterUpdate {
     // Do not include omitted fields in MessageId calculation
     #[serde(skip_serializing_if = "Option::is_none")]
     pub nonce: Option<Blob>,
+    /// Cycles offered to have the message prioritised by the ingress selector
+    /// under load. Only paid by target canisters that opted into paying tips.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tip: Option<u64>,
+    /// Whether the call tree rooted in this message should be traced across
+    /// canisters and subnets.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trace: Option<bool>,
 }
 
 impl HttpCanisterUpdate {
@@ -116,6 +132,8 @@ impl HttpCanisterUpdate {
             self.ingress_expiry,
             self.sender.0.clone(),
             self.nonce.as_ref().map(|x| x.0.as_slice()),
+            self.tip,
+            self.trace,
         )
     }
 
@@ -234,6 +252,8 @@ impl HttpUserQuery {
             self.ingress_expiry,
             self.sender.0.clone(),
             self.nonce.as_ref().map(|x| x.0.as_slice()),
+            None,
+            None,
         )
     }
 }
@@ -74,6 +74,8 @@ mod try_from {
                 sender: Blob(fixed::principal_id().to_vec()),
                 ingress_expiry: fixed::ingress_expiry(),
                 nonce: Some(Blob(fixed::nonce())),
+                tip: None,
+                trace: None,
             }
         }
 
@@ -7,7 +7,7 @@ use crate::{
         Authentication, HasCanisterId, HttpCallContent, HttpCanisterUpdate, HttpRequest,
         HttpRequestContent, HttpRequestEnvelope, HttpRequestError, SignedRequestBytes,
     },
-    CanisterId, CountBytes, PrincipalId, SubnetId, Time, UserId,
+    CanisterId, CountBytes, Cycles, PrincipalId, SubnetId, Time, UserId,
 };
 use ic_error_types::{ErrorCode, UserError};
 use ic_management_canister_types::{
@@ -38,6 +38,8 @@ pub struct SignedIngressContent {
     arg: Vec<u8>,
     ingress_expiry: u64,
     nonce: Option<Vec<u8>>,
+    tip: Option<u64>,
+    trace: Option<bool>,
 }
 
 impl SignedIngressContent {
@@ -61,6 +63,19 @@ impl SignedIngressContent {
         self.nonce.as_ref()
     }
 
+    /// Returns the tip in cycles that the sender offers to have the message
+    /// prioritised, or zero if no tip is attached. The tip is paid by the
+    /// target canister only if it opted into paying tips.
+    pub fn tip(&self) -> Cycles {
+        Cycles::from(self.tip.unwrap_or_default())
+    }
+
+    /// Returns whether the sender asked for the call tree rooted in this
+    /// message to be traced.
+    pub fn is_traced(&self) -> bool {
+        self.trace.unwrap_or_default()
+    }
+
     /// Checks whether the given ingress message is addressed to the subnet (rather than to a canister).
     pub fn is_addressed_to_subnet(&self, own_subnet_id: SubnetId) -> bool {
         let canister_id = self.canister_id();
@@ -87,6 +102,8 @@ impl SignedIngressContent {
             arg,
             ingress_expiry,
             nonce,
+            tip: None,
+            trace: None,
         }
     }
 }
@@ -107,6 +124,8 @@ impl HttpRequestContent for SignedIngressContent {
             self.ingress_expiry,
             self.sender.get().into_vec(),
             self.nonce.as_deref(),
+            self.tip,
+            self.trace,
         ))
     }
 
@@ -144,6 +163,8 @@ impl TryFrom<HttpCanisterUpdate> for SignedIngressContent {
             arg: update.arg.0,
             ingress_expiry: update.ingress_expiry,
             nonce: update.nonce.map(|n| n.0),
+            tip: update.tip,
+            trace: update.trace,
         })
     }
 }
@@ -298,6 +319,10 @@ impl SignedIngress {
     pub fn nonce(&self) -> Option<Vec<u8>> {
         self.signed.nonce()
     }
+
+    pub fn tip(&self) -> Cycles {
+        self.content().tip()
+    }
 }
 
 impl TryFrom<SignedRequestBytes> for SignedIngress {
@@ -344,6 +369,10 @@ pub struct Ingress {
     pub method_payload: Vec<u8>,
     pub message_id: MessageId,
     pub expiry_time: Time,
+    /// Whether the sender asked for the call tree rooted in this message to be
+    /// traced.
+    #[serde(default)]
+    pub traced: bool,
 }
 
 impl Ingress {
@@ -365,6 +394,7 @@ impl From<(SignedIngress, Option<CanisterId>)> for Ingress {
             method_payload: signed_ingress.method_arg().to_vec(),
             message_id: signed_ingress.id(),
             expiry_time: signed_ingress.expiry_time(),
+            traced: signed_ingress.content().is_traced(),
         }
     }
 }
@@ -381,6 +411,7 @@ impl From<(SignedIngressContent, Option<CanisterId>)> for Ingress {
             method_payload: ingress.arg,
             message_id,
             expiry_time: Time::from_nanos_since_unix_epoch(ingress.ingress_expiry),
+            traced: ingress.is_traced(),
         }
     }
 }
@@ -396,6 +427,7 @@ impl From<&Ingress> for pb_ingress::Ingress {
             message_id: item.message_id.as_bytes().to_vec(),
             expiry_time_nanos: item.expiry_time.as_nanos_since_unix_epoch(),
             effective_canister_id,
+            traced: item.traced,
         }
     }
 }
@@ -417,6 +449,7 @@ impl TryFrom<pb_ingress::Ingress> for Ingress {
             method_payload: item.method_payload,
             message_id: item.message_id.as_slice().try_into()?,
             expiry_time: Time::from_nanos_since_unix_epoch(item.expiry_time_nanos),
+            traced: item.traced,
         })
     }
 }
@@ -572,6 +605,8 @@ mod test {
                 arg: vec![],
                 ingress_expiry: 0,
                 nonce: None,
+                tip: None,
+                trace: None,
             };
             let result = extract_effective_canister_id(&msg, subnet_id);
             assert!(
@@ -593,6 +628,8 @@ mod test {
                 arg: vec![],
                 ingress_expiry: 0,
                 nonce: None,
+                tip: None,
+                trace: None,
             };
             assert_eq!(
                 extract_effective_canister_id(&msg, subnet_id),
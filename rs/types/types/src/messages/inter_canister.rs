@@ -1,5 +1,6 @@
 use crate::{
-    ingress::WasmResult, time::CoarseTime, CanisterId, CountBytes, Cycles, Funds, NumBytes, Time,
+    ingress::WasmResult, messages::MessageId, time::CoarseTime, CanisterId, CountBytes, Cycles,
+    Funds, NumBytes, Time,
 };
 use ic_error_types::{RejectCode, UserError};
 #[cfg(test)]
@@ -48,7 +49,64 @@ pub enum CallContextIdTag {}
 /// Identifies an incoming call.
 pub type CallContextId = Id<CallContextIdTag, u64>;
 
-#[derive(Clone, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// Length of a [`TraceId`], in bytes.
+pub const TRACE_ID_LENGTH: usize = 16;
+
+/// Identifies all calls belonging to the same call tree, for the purpose of
+/// tracing them across canisters and subnets.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(test, derive(ExhaustiveSet))]
+pub struct TraceId([u8; TRACE_ID_LENGTH]);
+
+impl TraceId {
+    pub const fn new(bytes: [u8; TRACE_ID_LENGTH]) -> Self {
+        Self(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; TRACE_ID_LENGTH] {
+        &self.0
+    }
+}
+
+/// Derives the trace ID of a call tree rooted in an ingress message from the
+/// first bytes of its message ID.
+impl From<&MessageId> for TraceId {
+    fn from(message_id: &MessageId) -> Self {
+        let mut bytes = [0; TRACE_ID_LENGTH];
+        bytes.copy_from_slice(&message_id.as_bytes()[..TRACE_ID_LENGTH]);
+        Self(bytes)
+    }
+}
+
+impl TryFrom<&[u8]> for TraceId {
+    type Error = String;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        <[u8; TRACE_ID_LENGTH]>::try_from(bytes)
+            .map(Self)
+            .map_err(|_| {
+                format!(
+                    "Invalid trace ID length: expected {} bytes, got {}",
+                    TRACE_ID_LENGTH,
+                    bytes.len()
+                )
+            })
+    }
+}
+
+impl std::fmt::Display for TraceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", hex::encode(self.0))
+    }
+}
+
+impl std::fmt::Debug for TraceId {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "TraceId({})", self)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 #[cfg_attr(test, derive(ExhaustiveSet))]
 pub struct RequestMetadata {
     /// Indicates how many steps down the call tree a request is, starting at 0.
@@ -56,6 +114,10 @@ pub struct RequestMetadata {
     /// The block time (on the respective subnet) at the start of the call at the
     /// root of the call tree that this request is part of.
     call_tree_start_time: Time,
+    /// Identifies the call tree that this request is part of, if tracing is
+    /// enabled for it.
+    #[serde(default)]
+    trace_id: Option<TraceId>,
 }
 
 impl RequestMetadata {
@@ -63,6 +125,7 @@ impl RequestMetadata {
         Self {
             call_tree_depth,
             call_tree_start_time,
+            trace_id: None,
         }
     }
 
@@ -72,9 +135,15 @@ impl RequestMetadata {
     }
 
     /// Creates `RequestMetadata` for a downstream call from another metadata, i.e. with depth
-    /// increased by 1 and the same `call_tree_start_time`.
+    /// increased by 1 and the same `call_tree_start_time` and `trace_id`.
     pub fn for_downstream_call(&self) -> Self {
-        Self::new(self.call_tree_depth + 1, self.call_tree_start_time)
+        Self::new(self.call_tree_depth + 1, self.call_tree_start_time).with_trace_id(self.trace_id)
+    }
+
+    /// Sets the trace ID of the call tree.
+    pub fn with_trace_id(mut self, trace_id: Option<TraceId>) -> Self {
+        self.trace_id = trace_id;
+        self
     }
 
     pub fn call_tree_depth(&self) -> &u64 {
@@ -84,6 +153,29 @@ impl RequestMetadata {
     pub fn call_tree_start_time(&self) -> &Time {
         &self.call_tree_start_time
     }
+
+    pub fn trace_id(&self) -> Option<&TraceId> {
+        self.trace_id.as_ref()
+    }
+}
+
+/// Custom hash implementation, ensuring consistency with the previous version
+/// without a `trace_id`. See the `Hash` implementation of `Response`.
+impl Hash for RequestMetadata {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        let RequestMetadata {
+            call_tree_depth,
+            call_tree_start_time,
+            trace_id,
+        } = self;
+
+        call_tree_depth.hash(state);
+        call_tree_start_time.hash(state);
+
+        if let Some(trace_id) = trace_id {
+            trace_id.hash(state);
+        }
+    }
 }
 
 /// Canister-to-canister request message.
@@ -545,6 +637,9 @@ impl From<&RequestMetadata> for pb_queues::RequestMetadata {
                 metadata.call_tree_start_time.as_nanos_since_unix_epoch(),
             ),
             call_subtree_deadline_nanos: None,
+            trace_id: metadata
+                .trace_id
+                .map(|trace_id| trace_id.as_bytes().to_vec()),
         }
     }
 }
@@ -572,6 +667,11 @@ impl From<pb_queues::RequestMetadata> for RequestMetadata {
             call_tree_start_time: Time::from_nanos_since_unix_epoch(
                 metadata.call_tree_start_time_nanos.unwrap_or(0),
             ),
+            // A malformed trace ID only affects observability, so it is dropped
+            // rather than failing the decoding of the whole request.
+            trace_id: metadata
+                .trace_id
+                .and_then(|trace_id| TraceId::try_from(trace_id.as_slice()).ok()),
         }
     }
 }
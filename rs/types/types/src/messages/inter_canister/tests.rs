@@ -140,3 +140,30 @@ fn request_or_response_proto_round_trip() {
         assert_eq!(r, round_trip);
     }
 }
+
+#[test]
+fn same_metadata_hash_without_trace_id() {
+    let metadata = RequestMetadata::new(1, Time::from_nanos_since_unix_epoch(2));
+
+    let mut hasher = DefaultHasher::new();
+    1_u64.hash(&mut hasher);
+    Time::from_nanos_since_unix_epoch(2).hash(&mut hasher);
+    assert_eq!(hasher.finish(), hash(&metadata));
+
+    assert_ne!(
+        hash(&metadata),
+        hash(&metadata.clone().with_trace_id(Some(TraceId::new([3; 16]))))
+    );
+}
+
+#[test]
+fn trace_id_is_propagated_to_downstream_calls() {
+    let trace_id = TraceId::from(&MessageId::from([7; 32]));
+    let metadata = RequestMetadata::for_new_call_tree(Time::from_nanos_since_unix_epoch(1))
+        .with_trace_id(Some(trace_id));
+
+    let downstream = metadata.for_downstream_call().for_downstream_call();
+    assert_eq!(2, *downstream.call_tree_depth());
+    assert_eq!(Some(&TraceId::new([7; 16])), downstream.trace_id());
+    assert_eq!("07".repeat(16), trace_id.to_string());
+}
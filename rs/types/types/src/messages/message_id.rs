@@ -244,7 +244,7 @@ impl From<MessageIdError> for ProxyDecodeError {
 #[cfg(test)]
 mod tests {
     use super::super::{
-        Blob, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, RawHttpRequestVal,
+        Blob, HttpCallContent, HttpCanisterUpdate, HttpRequestEnvelope, Ingress, RawHttpRequestVal,
         SignedIngress,
     };
     use super::*;
@@ -467,6 +467,8 @@ mod tests {
             sender: Blob(vec![0; 29]),
             ingress_expiry: expiry_time.as_nanos_since_unix_epoch(),
             nonce: None,
+            tip: None,
+            trace: None,
         };
         let content = HttpCallContent::Call { update };
         let envelope = HttpRequestEnvelope::<HttpCallContent> {
@@ -510,6 +512,71 @@ mod tests {
         assert_eq!(message_id1, message_id2);
     }
 
+    #[test]
+    /// The tip is signed as part of the MessageId, but only if it is present,
+    /// so that the MessageId of messages without a tip is unchanged.
+    fn message_id_tip() {
+        let update = HttpCanisterUpdate {
+            canister_id: Blob(vec![0, 0, 0, 0, 0, 0, 4, 210]),
+            method_name: "hello".to_string(),
+            arg: Blob(b"DIDL\x00\xFD*".to_vec()),
+            sender: Blob(vec![0; 29]),
+            ingress_expiry: 1_685_570_400_000_000_000,
+            nonce: None,
+            tip: None,
+            trace: None,
+        };
+        let with_tip = HttpCanisterUpdate {
+            tip: Some(0),
+            ..update.clone()
+        };
+        assert_ne!(update.id(), with_tip.id());
+
+        let signed_ingress = SignedIngress::try_from(HttpRequestEnvelope::<HttpCallContent> {
+            content: HttpCallContent::Call {
+                update: with_tip.clone(),
+            },
+            sender_pubkey: None,
+            sender_sig: None,
+            sender_delegation: None,
+        })
+        .unwrap();
+        assert_eq!(signed_ingress.id(), with_tip.id());
+    }
+
+    #[test]
+    /// The trace flag is signed as part of the MessageId, but only if it is
+    /// present, and is carried over into the inducted `Ingress`.
+    fn message_id_trace() {
+        let update = HttpCanisterUpdate {
+            canister_id: Blob(vec![0, 0, 0, 0, 0, 0, 4, 210]),
+            method_name: "hello".to_string(),
+            arg: Blob(b"DIDL\x00\xFD*".to_vec()),
+            sender: Blob(vec![0; 29]),
+            ingress_expiry: 1_685_570_400_000_000_000,
+            nonce: None,
+            tip: None,
+            trace: None,
+        };
+        let traced = HttpCanisterUpdate {
+            trace: Some(true),
+            ..update.clone()
+        };
+        assert_ne!(update.id(), traced.id());
+
+        let signed_ingress = SignedIngress::try_from(HttpRequestEnvelope::<HttpCallContent> {
+            content: HttpCallContent::Call {
+                update: traced.clone(),
+            },
+            sender_pubkey: None,
+            sender_sig: None,
+            sender_delegation: None,
+        })
+        .unwrap();
+        assert_eq!(signed_ingress.id(), traced.id());
+        assert!(Ingress::from((signed_ingress, None)).traced);
+    }
+
     #[test]
     fn message_id_deserialize() {
         let id = MessageId::from(hex!(
@@ -52,6 +52,8 @@ impl Query {
                 *ingress_expiry,
                 user_id.get().into_vec(),
                 nonce.as_deref(),
+                None,
+                None,
             )),
             QuerySource::Anonymous => {
                 MessageId::from(representation_independent_hash_call_or_query(
@@ -62,6 +64,8 @@ impl Query {
                     0,
                     IC_00.get().into_vec(),
                     None,
+                    None,
+                    None,
                 ))
             }
         }
@@ -4,7 +4,7 @@ use ic_types::{
     crypto::{AlgorithmId, KeyPurpose, UserPublicKey},
     messages::{
         CallbackId, Payload, RejectContext, Request, RequestMetadata, RequestOrResponse, Response,
-        NO_DEADLINE,
+        TraceId, NO_DEADLINE,
     },
     time::{CoarseTime, UNIX_EPOCH},
     xnet::StreamIndex,
@@ -97,15 +97,26 @@ prop_compose! {
 }
 
 prop_compose! {
-    /// Returns an arbitrary ['RequestMetadata'].
-    pub fn request_metadata()(
+    /// Returns an arbitrary ['RequestMetadata'], with or without a populated `trace_id`.
+    pub fn request_metadata_with_config(populate_trace_id: bool)(
         call_tree_depth in any::<u64>(),
         call_tree_start_time_nanos in any::<u64>(),
+        trace_id in proptest::option::of(any::<[u8; 16]>()),
     ) -> RequestMetadata {
         RequestMetadata::new(
             call_tree_depth,
             Time::from_nanos_since_unix_epoch(call_tree_start_time_nanos),
         )
+        .with_trace_id(if populate_trace_id { trace_id.map(TraceId::new) } else { None })
+    }
+}
+
+prop_compose! {
+    /// Returns an arbitrary ['RequestMetadata'].
+    pub fn request_metadata()(
+        metadata in request_metadata_with_config(true),
+    ) -> RequestMetadata {
+        metadata
     }
 }
 
@@ -123,16 +134,20 @@ prop_compose! {
 }
 
 prop_compose! {
-    /// Generates an arbitrary [`Request`], with or without populated `metadata` and
-    /// `deadline` fields.
-    pub fn request_with_config(populate_metadata: bool, populate_deadline: bool)(
+    /// Generates an arbitrary [`Request`], with or without populated `metadata`,
+    /// `metadata.trace_id` and `deadline` fields.
+    pub fn request_with_config(
+        populate_metadata: bool,
+        populate_trace_id: bool,
+        populate_deadline: bool,
+    )(
         receiver in canister_id(),
         sender in canister_id(),
         cycles_payment in any::<u64>(),
         method_name in "[a-zA-Z]{1,6}",
         callback in any::<u64>(),
         method_payload in prop::collection::vec(any::<u8>(), 0..16),
-        metadata in proptest::option::of(request_metadata()),
+        metadata in proptest::option::of(request_metadata_with_config(populate_trace_id)),
         deadline in deadline(),
     ) -> Request {
         Request {
@@ -158,7 +173,7 @@ prop_compose! {
         // Always populate all fields, regardless of e.g. current certification version.
         // `ic_canonical_state` should not be using this generator; and all other crates /
         // proptests should be able to deal with all fields being populated.
-        request in request_with_config(true, true),
+        request in request_with_config(true, true, true),
     ) -> Request {
         request
     }
@@ -217,11 +232,16 @@ prop_compose! {
 /// populated or not.
 pub fn request_or_response_with_config(
     populate_request_metadata: bool,
+    populate_trace_id: bool,
     populate_deadline: bool,
 ) -> impl Strategy<Value = RequestOrResponse> {
     prop_oneof![
-        request_with_config(populate_request_metadata, populate_deadline)
-            .prop_flat_map(|req| Just(req.into())),
+        request_with_config(
+            populate_request_metadata,
+            populate_trace_id,
+            populate_deadline
+        )
+        .prop_flat_map(|req| Just(req.into())),
         response_with_config(populate_deadline).prop_flat_map(|rep| Just(rep.into())),
     ]
 }
@@ -63,6 +63,8 @@ pub enum WasmValidationError {
     InvalidCustomSection(String),
     /// Module contains an invalid global section
     InvalidGlobalSection(String),
+    /// Module contains an invalid memory section
+    InvalidMemorySection(String),
     /// Module contains too many globals.
     TooManyGlobals { defined: usize, allowed: usize },
     /// Module contains too many functions.
@@ -119,6 +121,9 @@ impl std::fmt::Display for WasmValidationError {
             Self::InvalidGlobalSection(err) => {
                 write!(f, "Wasm module has an invalid global section. {err}")
             }
+            Self::InvalidMemorySection(err) => {
+                write!(f, "Wasm module has an invalid memory section. {err}")
+            }
             Self::TooManyGlobals { defined, allowed } => write!(
                 f,
                 "Wasm module defined {defined} \
@@ -186,6 +191,7 @@ impl AsErrorHelp for WasmValidationError {
             | WasmValidationError::UnsupportedWasmInstruction { .. }
             | WasmValidationError::TooManyCustomSections { .. } => ErrorHelp::ToolchainError,
             WasmValidationError::UserInvalidExportSection(_)
+            | WasmValidationError::InvalidMemorySection(_)
             | WasmValidationError::TooManyFunctions { .. }
             | WasmValidationError::TooManyGlobals { .. }
             | WasmValidationError::FunctionComplexityTooHigh { .. }
@@ -48,6 +48,8 @@ impl From<AnonymousContent> for HttpCallContent {
                 sender,
                 ingress_expiry: content.ingress_expiry,
                 nonce: content.nonce,
+                tip: None,
+                trace: None,
             },
         }
     }
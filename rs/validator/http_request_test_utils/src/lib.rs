@@ -96,6 +96,8 @@ fn dummy_call_request_content() -> HttpCanisterUpdate {
         sender: Default::default(),
         ingress_expiry: 0,
         nonce: None,
+        tip: None,
+        trace: None,
     }
 }
 
@@ -52,6 +52,8 @@ impl Default for IngressMessageVerifier<ConstantRootOfTrustProvider> {
     /// #                 sender: Blob(vec![0x04]),
     /// #                 nonce: None,
     /// #                 ingress_expiry,
+    /// #                 tip: None,
+    /// #                 trace: None,
     /// #             },
     /// #         },
     /// #         sender_pubkey: None,
@@ -117,6 +119,8 @@ impl IngressMessageVerifier<ConstantRootOfTrustProvider> {
     /// #                 sender: Blob(vec![0x04]),
     /// #                 nonce: None,
     /// #                 ingress_expiry,
+    /// #                 tip: None,
+    /// #                 trace: None,
     /// #             },
     /// #         },
     /// #         sender_pubkey: None,
@@ -543,6 +543,8 @@ mod validate_ingress_expiry {
                     sender: Blob(vec![0x04]),
                     nonce: None,
                     ingress_expiry,
+                    tip: None,
+                    trace: None,
                 },
             },
             sender_pubkey: None,
@@ -144,6 +144,15 @@ pub(super) mod internal_to_encoder {
         Ok(wasm_encoder::ConstExpr::raw(bytes))
     }
 
+    pub(crate) fn tag_type(tag: wasmparser::TagType) -> wasm_encoder::TagType {
+        wasm_encoder::TagType {
+            kind: match tag.kind {
+                wasmparser::TagKind::Exception => wasm_encoder::TagKind::Exception,
+            },
+            func_type_idx: tag.func_type_idx,
+        }
+    }
+
     pub(crate) fn catch(catch: &wasmparser::Catch) -> wasm_encoder::Catch {
         match catch {
             wasmparser::Catch::One { tag, label } => wasm_encoder::Catch::One {
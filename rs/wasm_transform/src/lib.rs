@@ -2,7 +2,7 @@ use std::ops::Range;
 
 use wasmparser::{
     BinaryReaderError, Export, GlobalType, Import, MemoryType, Operator, Parser, Payload, RefType,
-    SubType, TableType, ValType,
+    SubType, TableType, TagType, ValType,
 };
 
 mod convert;
@@ -164,6 +164,8 @@ pub struct Module<'a> {
     /// Each table has a type and optional initialization expression.
     pub tables: Vec<(TableType, Option<Operator<'a>>)>,
     pub memories: Vec<MemoryType>,
+    /// Exception tags, introduced by the exception-handling proposal.
+    pub tags: Vec<TagType>,
     pub globals: Vec<Global<'a>>,
     pub data: Vec<DataSegment<'a>>,
     pub data_count_section_exists: bool,
@@ -183,6 +185,7 @@ impl<'a> Module<'a> {
         let mut data = vec![];
         let mut tables = vec![];
         let mut memories = vec![];
+        let mut tags = vec![];
         let mut functions = vec![];
         let mut elements = vec![];
         let mut code_section_count = 0;
@@ -233,6 +236,9 @@ impl<'a> Module<'a> {
                         .into_iter()
                         .collect::<Result<_, _>>()?;
                 }
+                Payload::TagSection(tag_section_reader) => {
+                    tags = tag_section_reader.into_iter().collect::<Result<_, _>>()?;
+                }
                 Payload::FunctionSection(function_section_reader) => {
                     functions = function_section_reader
                         .into_iter()
@@ -321,8 +327,7 @@ impl<'a> Module<'a> {
                     contents: _,
                     range: _,
                 } => return Err(Error::UnknownSection { section_id: id }),
-                Payload::TagSection(_)
-                | Payload::ModuleSection {
+                Payload::ModuleSection {
                     parser: _,
                     range: _,
                 }
@@ -363,6 +368,7 @@ impl<'a> Module<'a> {
             functions,
             tables,
             memories,
+            tags,
             globals,
             exports,
             start,
@@ -434,6 +440,14 @@ impl<'a> Module<'a> {
             module.section(&memories);
         }
 
+        if !self.tags.is_empty() {
+            let mut tags = wasm_encoder::TagSection::new();
+            for tag in self.tags {
+                tags.tag(internal_to_encoder::tag_type(tag));
+            }
+            module.section(&tags);
+        }
+
         if !self.globals.is_empty() {
             let mut globals = wasm_encoder::GlobalSection::new();
             for global in self.globals {
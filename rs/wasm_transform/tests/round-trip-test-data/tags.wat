@@ -0,0 +1,14 @@
+(module
+  (type $t (func (param i32)))
+  (tag $e (type $t))
+  (func $f (param i32) (result i32)
+    block $h (result i32)
+      try_table (catch $e $h)
+        local.get 0
+        throw $e
+      end
+      i32.const 0
+    end
+  )
+  (export "e" (tag $e))
+)
@@ -0,0 +1,14 @@
+(module
+  (type $t (func (param i32) (result i32)))
+  (table 1 funcref)
+  (elem (i32.const 0) $g)
+  (func $f (param i32) (result i32)
+    local.get 0
+    return_call $g
+  )
+  (func $g (param i32) (result i32)
+    local.get 0
+    i32.const 0
+    return_call_indirect (type $t)
+  )
+)
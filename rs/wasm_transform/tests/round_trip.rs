@@ -39,6 +39,8 @@ mod round_trip {
         globals,
         exports,
         start,
-        const_expr
+        const_expr,
+        tags,
+        tail_call
     );
 }